@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+// stdin, stdout and stderr take the fds before it
+const FIRST_FREE_FD: u8 = 3;
+
+fn main() -> isize {
+    for path in ["/", "/APP", "/KERNEL.ELF", "/app/access"] {
+        assert!(sys_exists(path), "{} should exist", path);
+    }
+
+    for path in ["/MISSING", "/MISSING/KERNEL.ELF", "/KERNEL.ELF/MISSING"] {
+        assert!(!sys_exists(path), "{} should not exist", path);
+    }
+
+    // probing does not take any fd
+    let fd = sys_open_file("/KERNEL.ELF");
+    assert_eq!(fd, FIRST_FREE_FD);
+    sys_close_file(fd);
+
+    println!("Access test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const ALL_CPUS: u64 = u64::MAX;
+/// A CPU beyond the most the kernel keeps the state of, never online
+const OFFLINE_CPU: u64 = 1 << 63;
+const ROUNDS: usize = 16;
+
+/// Run a while and check every time the process is on an allowed CPU
+fn check_pinned(mask: u64) {
+    for _ in 0..ROUNDS {
+        let cpu = sys_getcpu();
+        assert!(mask & (1 << cpu) != 0, "Running on CPU #{} out of {:#x}", cpu, mask);
+        sys_yield();
+    }
+}
+
+fn main() -> isize {
+    assert_eq!(sys_get_affinity(0), Some(ALL_CPUS));
+
+    // pin to the CPU running now, which is online
+    let pinned = 1 << sys_getcpu();
+    assert!(sys_set_affinity(0, pinned));
+    assert_eq!(sys_get_affinity(0), Some(pinned));
+    check_pinned(pinned);
+
+    // a mask without any online CPU is rejected and changes nothing
+    assert!(!sys_set_affinity(0, 0));
+    assert!(!sys_set_affinity(0, OFFLINE_CPU));
+    assert_eq!(sys_get_affinity(0), Some(pinned));
+
+    // the child inherits the mask, which the parent may change
+    let child = sys_fork();
+    if child == 0 {
+        assert_eq!(sys_get_affinity(0), Some(pinned));
+        check_pinned(pinned);
+        sys_exit(0);
+    }
+    assert!(sys_set_affinity(child, pinned | OFFLINE_CPU));
+    assert_eq!(sys_get_affinity(child), Some(pinned | OFFLINE_CPU));
+    assert_eq!(sys_wait_pid(child), 0);
+
+    // but not of the processes outside its descendants
+    assert!(!sys_set_affinity(1, pinned));
+
+    assert!(sys_set_affinity(0, ALL_CPUS));
+
+    println!("Affinity test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const ALARM_MS: u64 = 50;
+
+fn main() -> isize {
+    assert_eq!(sys_alarm(0), 0, "An alarm is armed without asking");
+
+    // re-arming & disarming before the deadline, nothing fires
+    assert_eq!(sys_alarm(ALARM_MS), 0);
+    let left = sys_alarm(ALARM_MS * 2);
+    assert!(left > 0 && left <= ALARM_MS, "{} ms left", left);
+    assert!(sys_alarm(0) > ALARM_MS);
+    assert!(sys_nanosleep(ALARM_MS * 3 * 1_000_000));
+
+    let child = sys_fork();
+    if child == 0 {
+        // the alarm of the parent is not inherited
+        assert_eq!(sys_alarm(ALARM_MS), 0);
+        loop {
+            sys_yield();
+        }
+    }
+
+    // killed by the alarm, which is gone with the child
+    let code = sys_wait_pid(child);
+    println!("Child #{} exited with {}", child, code);
+    assert_eq!(code, -(SIGALRM as isize));
+
+    // the alarm of a sleeping process fires as well
+    let child = sys_fork();
+    if child == 0 {
+        sys_alarm(ALARM_MS);
+        sys_nanosleep(ALARM_MS * 10 * 1_000_000);
+        sys_exit(0);
+    }
+    assert_eq!(sys_wait_pid(child), -(SIGALRM as isize));
+    assert_eq!(sys_alarm(0), 0);
+
+    println!("Alarm test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+use core::alloc::Layout;
+use lib::*;
+
+extern crate lib;
+
+const EXIT_CODE: isize = 5;
+
+fn main() -> isize {
+    // the heap of lib is on brk, only the allocations made here are counted
+    assert_eq!(sys_alloc_stats(), (0, 0), "Allocations are counted before any");
+
+    let small = Layout::from_size_align(24, 8).unwrap();
+    let large = Layout::from_size_align(1000, 16).unwrap();
+    let first = sys_allocate(&small);
+    let second = sys_allocate(&large);
+    assert!(!first.is_null() && !second.is_null(), "Failed to allocate");
+    let stats = sys_alloc_stats();
+    println!("Outstanding: {} allocations, {} bytes", stats.0, stats.1);
+    assert_eq!(stats, (2, 1024));
+
+    // a rejected free is still outstanding
+    assert!(!sys_deallocate(first, &large));
+    assert_eq!(sys_alloc_stats(), (2, 1024));
+    assert!(sys_deallocate(first, &small));
+    assert_eq!(sys_alloc_stats(), (1, 1000));
+
+    // the leaks of a child are not counted for the parent
+    let child = sys_fork();
+    if child == 0 {
+        assert_eq!(sys_alloc_stats(), (0, 0), "The allocations are inherited");
+        assert!(!sys_allocate(&small).is_null());
+        sys_exit(EXIT_CODE);
+    }
+    assert_ne!(child, FORK_FAILED);
+    assert_eq!(sys_wait_pid(child), EXIT_CODE);
+    assert_eq!(sys_alloc_stats(), (1, 1000));
+
+    assert!(sys_deallocate(second, &large));
+    assert_eq!(sys_alloc_stats(), (0, 0), "The stats do not return to zero");
+
+    println!("Alloc stats test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The worker, which prints a line & exits
+const WORKER_PATH: &str = "/APP/HELLO";
+const WORKER_EXIT_CODE: isize = 233;
+const SPAWNS: u64 = 8;
+
+/// Spawn a worker & wait for it, returns the tsc cycles of the spawn alone
+fn spawn_worker() -> u64 {
+    let start = rdtsc();
+    let child = sys_spawn(WORKER_PATH).expect("Failed to spawn the worker");
+    let cycles = rdtsc() - start;
+    assert_eq!(sys_wait_pid(child), WORKER_EXIT_CODE);
+    cycles
+}
+
+fn main() -> isize {
+    // a writable handle drops the cached image, so the first spawn reads the disk
+    let fd = sys_open(WORKER_PATH, O_RDWR).expect("Failed to open the worker");
+    sys_close_file(fd);
+
+    let cold = spawn_worker();
+    let warm = (1..SPAWNS).map(|_| spawn_worker()).sum::<u64>() / (SPAWNS - 1);
+    println!("Spawn: {} cycles cold, {} cycles warm on average", cold, warm);
+    assert!(warm < cold, "The cached spawns are not cheaper");
+
+    println!("App cache test passed!");
+
+    0
+}
+
+entry!(main);
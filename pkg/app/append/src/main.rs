@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, trailing bytes are ignored by the ELF loader
+const FILE_PATH: &str = "/APP/APPEND";
+const MARKER: &[u8] = b"appended by the append test";
+
+fn main() -> isize {
+    let fd = sys_open(FILE_PATH, O_APPEND).expect("Failed to open the file");
+
+    let length = sys_seek(fd, 0, SEEK_END);
+    assert!(length > 0, "Failed to seek to the end of {}", FILE_PATH);
+    println!("{} has {} bytes", FILE_PATH, length);
+
+    // the explicit seek is ignored by the write
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+    assert_eq!(sys_write(fd, MARKER), Some(MARKER.len()));
+
+    let new_length = sys_seek(fd, 0, SEEK_CUR);
+    assert_eq!(new_length, length + MARKER.len() as isize);
+    assert_eq!(sys_seek(fd, 0, SEEK_END), new_length);
+
+    // the marker landed at the end, the head is untouched
+    let mut buf = [0u8; MARKER.len()];
+    assert_eq!(sys_seek(fd, length, SEEK_SET), length);
+    assert_eq!(sys_read(fd, &mut buf), Some(MARKER.len()));
+    assert_eq!(&buf, MARKER);
+
+    let mut head = [0u8; 4];
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+    assert_eq!(sys_read(fd, &mut head), Some(4));
+    assert_eq!(&head, b"\x7fELF");
+
+    sys_close_file(fd);
+
+    println!("Append test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// Read the keys arrived so far, waiting for the first one
+fn read_keys(buf: &mut [u8]) -> usize {
+    loop {
+        match sys_read(0, buf) {
+            Some(len) if len > 0 => return len,
+            _ => sys_yield(),
+        }
+    }
+}
+
+fn main() -> isize {
+    // e.g. `printf '\033[A'` into the serial console
+    println!("Press the up arrow key...");
+
+    let mut buf = [0u8; 16];
+    let len = read_keys(&mut buf);
+
+    // let the rest of a sequence arrive, if it was not decoded
+    sleep(1);
+    let rest = sys_read(0, &mut buf[len..]).unwrap_or(0);
+
+    println!("Got {} key(s): {:x?}", len + rest, &buf[..len + rest]);
+    assert_eq!(len + rest, 1, "The sequence should be a single key");
+    assert_eq!(buf[0], KEY_UP);
+
+    println!("Arrow key test passed!");
+
+    0
+}
+
+entry!(main);
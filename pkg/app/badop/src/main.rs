@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+#[inline(never)]
+fn crash() {
+    unsafe { core::arch::asm!("ud2") };
+}
+
+fn main() -> isize {
+    let child = sys_fork();
+    if child == 0 {
+        crash();
+        unreachable!("The process survived an invalid opcode");
+    }
+
+    // the dump on serial should show `badop` and a RIP in `crash`
+    println!(
+        "Process #{} runs ud2 in crash() at {:#x}",
+        child, crash as usize
+    );
+    assert_eq!(sys_wait_pid(child), KILLED_EXIT_CODE);
+
+    println!("Invalid opcode test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+use lib::{string::String, *};
+
+extern crate lib;
+
+/// The binary of this app, which opens fine with a good path
+const SELF_PATH: &str = "/APP/BADPATH";
+/// Longer than the limit of the kernel
+const LONG_LEN: usize = 300;
+/// The start of the mappings, nothing is mapped there by this app
+const UNMAPPED: usize = 0x1000_0000_0000;
+/// The physical memory mapped for the kernel only
+const KERNEL_ADDR: usize = 0xffff_8000_0000_0000;
+
+/// A path of `len` bytes at `addr`, the kernel must not read it
+fn bad_path(addr: usize, len: usize) -> &'static str {
+    unsafe { core::str::from_utf8_unchecked(core::slice::from_raw_parts(addr as *const u8, len)) }
+}
+
+fn main() -> isize {
+    let fd = sys_open(SELF_PATH, 0).expect("Failed to open the app");
+    sys_close_file(fd);
+
+    // an over-long path is rejected even if the memory is fine
+    let mut long = String::from(SELF_PATH);
+    while long.len() < LONG_LEN {
+        long.push('/');
+    }
+    assert_eq!(sys_open(&long, 0), None);
+    assert!(!sys_exists(&long));
+
+    // and so are the paths outside the memory of the user
+    for addr in [UNMAPPED, KERNEL_ADDR] {
+        let path = bad_path(addr, SELF_PATH.len());
+        assert_eq!(sys_open(path, 0), None, "Opened a path at {:#x}", addr);
+        assert!(!sys_exists(path));
+        assert_eq!(sys_spawn(path), None);
+        assert_eq!(sys_exec(path), -1);
+        assert_eq!(sys_unlink(path), -1);
+        assert_eq!(sys_rename(SELF_PATH, path), -1);
+    }
+    // a path running off the end of the mapped memory
+    let heap_end = sys_brk(None).unwrap();
+    let page_end = (heap_end + 0xfff) & !0xfff;
+    assert_eq!(sys_open(bad_path(page_end - 4, 16), 0), None);
+
+    assert!(sys_exists(SELF_PATH), "The app is lost by the rename");
+
+    println!("Bad path test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const SEM_KEY: u32 = 0xba66;
+const WORKERS: usize = 4;
+
+fn main() -> isize {
+    let (rfd, wfd) = sys_pipe().expect("Failed to create the pipe");
+    assert!(sys_new_sem(SEM_KEY, 0));
+
+    let mut workers = [0u16; WORKERS];
+    for (i, worker) in workers.iter_mut().enumerate() {
+        *worker = sys_fork();
+        if *worker == 0 {
+            assert!(sys_sem_wait(SEM_KEY));
+            assert_eq!(sys_write(wfd, &[i as u8]), Some(1));
+            sys_exit(0);
+        }
+    }
+
+    // release them all once every worker reaches the barrier
+    for worker in workers {
+        while sys_block_reason(worker) != Some(BlockReason::Semaphore) {
+            sys_yield();
+        }
+    }
+    assert!(sys_sem_signal_all(SEM_KEY));
+
+    for worker in workers {
+        assert_eq!(sys_wait_pid(worker), 0);
+    }
+    let mut passed = [0u8; WORKERS];
+    assert_eq!(sys_read(rfd, &mut passed), Some(WORKERS));
+    passed.sort_unstable();
+    for (i, worker) in passed.iter().enumerate() {
+        assert_eq!(*worker as usize, i, "Worker #{} is not released", i);
+    }
+
+    // the count is reset, nothing is left for the next wait
+    assert!(!sys_sem_timedwait(SEM_KEY, 10));
+    // no waiters, still succeeds
+    assert!(sys_sem_signal_all(SEM_KEY));
+
+    sys_close_file(rfd);
+    sys_close_file(wfd);
+    assert!(sys_remove_sem(SEM_KEY));
+
+    println!("Barrier test passed!");
+
+    0
+}
+
+entry!(main);
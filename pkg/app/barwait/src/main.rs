@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const BARRIER_KEY: u32 = 0xba77;
+const VICTIM_KEY: u32 = 0xba78;
+const WORKERS: usize = 4;
+const ROUNDS: u8 = 2;
+
+/// Read a byte from each worker, all of them for the same round
+fn read_round(rfd: u8, round: u8) {
+    let mut buf = [0u8; WORKERS];
+    let mut read = 0;
+    while read < WORKERS {
+        match sys_read(rfd, &mut buf[read..]) {
+            Some(len) if len > 0 => read += len,
+            _ => sys_yield(),
+        }
+    }
+    assert!(buf.iter().all(|r| *r == round), "Round {}: {:?}", round, buf);
+}
+
+fn main() -> isize {
+    let (rfd, wfd) = sys_pipe().expect("Failed to create the pipe");
+    // the workers & self
+    assert!(sys_new_barrier(BARRIER_KEY, WORKERS + 1));
+    assert!(!sys_new_barrier(BARRIER_KEY, WORKERS + 1));
+    assert!(!sys_new_barrier(VICTIM_KEY, 0));
+
+    let mut workers = [0u16; WORKERS];
+    for worker in workers.iter_mut() {
+        *worker = sys_fork();
+        if *worker == 0 {
+            for round in 0..ROUNDS {
+                assert!(sys_barrier_wait(BARRIER_KEY));
+                assert_eq!(sys_write(wfd, &[round]), Some(1));
+            }
+            sys_exit(0);
+        }
+    }
+
+    // none of the workers pass a round before the last one arrives,
+    // the barrier is reset for the next round
+    for round in 0..ROUNDS {
+        for worker in workers {
+            while sys_block_reason(worker) != Some(BlockReason::Semaphore) {
+                sys_yield();
+            }
+        }
+        assert!(sys_barrier_wait(BARRIER_KEY));
+        read_round(rfd, round);
+    }
+    for worker in workers {
+        assert_eq!(sys_wait_pid(worker), 0);
+    }
+
+    // a waiter killed before the barrier fills is not expected anymore
+    assert!(sys_new_barrier(VICTIM_KEY, 2));
+    let victim = sys_fork();
+    if victim == 0 {
+        sys_barrier_wait(VICTIM_KEY);
+        sys_exit(0);
+    }
+    while sys_block_reason(victim) != Some(BlockReason::Semaphore) {
+        sys_yield();
+    }
+    assert!(sys_kill(victim));
+    sys_wait_pid(victim);
+    assert!(sys_barrier_wait(VICTIM_KEY), "The barrier waits for a killed process");
+
+    sys_close_file(rfd);
+    sys_close_file(wfd);
+    assert!(sys_remove_barrier(BARRIER_KEY));
+    assert!(sys_remove_barrier(VICTIM_KEY));
+    assert!(!sys_barrier_wait(VICTIM_KEY));
+
+    println!("Barrier wait test passed!");
+
+    0
+}
+
+entry!(main);
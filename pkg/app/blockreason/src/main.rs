@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const SEM_KEY: u32 = 0x5EED;
+const MSG_KEY: u32 = 0xB10C;
+const SLEEP_NS: u64 = 1_000_000_000;
+/// Long enough for the children to get blocked
+const SETTLE_NS: u64 = 100_000_000;
+
+fn main() -> isize {
+    assert!(sys_new_sem(SEM_KEY, 0));
+    assert!(sys_msg_get(MSG_KEY));
+
+    let sleeper = sys_fork();
+    if sleeper == 0 {
+        sys_nanosleep(SLEEP_NS);
+        sys_exit(0);
+    }
+
+    let waiter = sys_fork();
+    if waiter == 0 {
+        sys_exit(sys_wait_pid(sleeper));
+    }
+
+    let sem_waiter = sys_fork();
+    if sem_waiter == 0 {
+        sys_sem_wait(SEM_KEY);
+        sys_exit(0);
+    }
+
+    let receiver = sys_fork();
+    if receiver == 0 {
+        let mut buf = [0u8; 8];
+        sys_exit(sys_msg_recv(MSG_KEY, &mut buf));
+    }
+
+    assert!(sys_nanosleep(SETTLE_NS));
+
+    let expected = [
+        (sleeper, BlockReason::Sleeping),
+        (waiter, BlockReason::WaitingChild),
+        (sem_waiter, BlockReason::Semaphore),
+        (receiver, BlockReason::Message),
+    ];
+    for (pid, reason) in expected {
+        println!("Process #{} is blocked: {:?}", pid, sys_block_reason(pid));
+        assert_eq!(sys_block_reason(pid), Some(reason));
+    }
+    assert_eq!(sys_block_reason(sys_get_pid()), None);
+
+    // release all of them
+    sys_sem_signal(SEM_KEY);
+    sys_msg_send(MSG_KEY, b"wake", 0);
+    for (pid, _) in expected {
+        sys_wait_pid(pid);
+    }
+    sys_remove_sem(SEM_KEY);
+
+    println!("Block reason test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// Launched as init by the kernel built with `bootarg_test`
+fn main() -> isize {
+    // the pairs of the command line, the later overriding the earlier
+    assert_eq!(sys_get_env("init").as_deref(), Some("/APP/BOOTARG"));
+    assert_eq!(sys_get_env("bootarg").as_deref(), Some("ok"));
+    // a flag without a value
+    assert_eq!(sys_get_env("quiet").as_deref(), Some(""));
+    assert_eq!(sys_get_env("missing"), None);
+
+    // passed on to the forked children like any env
+    let child = sys_fork();
+    if child == 0 {
+        let ok = sys_get_env("bootarg").as_deref() == Some("ok");
+        sys_exit(ok as isize);
+    }
+    assert_eq!(sys_wait_pid(child), 1, "The child does not inherit the env");
+
+    println!("Boot argument test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 0x1000;
+const PATTERN: u8 = 0xAA;
+
+fn assert_zeroed(start: usize, end: usize) {
+    for i in start..end {
+        let value = unsafe { *(i as *const u8) };
+        assert_eq!(value, 0, "Heap byte at {:#x} is not zeroed", i);
+    }
+}
+
+fn main() -> isize {
+    let heap_end = sys_brk(None).unwrap();
+
+    println!("Grow the heap and write a pattern");
+    let grown_end = sys_brk(Some(heap_end + PAGE_SIZE * 4)).expect("Failed to grow heap");
+    assert_zeroed(heap_end, grown_end);
+    for i in heap_end..grown_end {
+        unsafe { *(i as *mut u8) = PATTERN };
+    }
+
+    println!("Shrink the heap and grow it again");
+    // shrink to the middle of a page, so part of the pattern stays mapped
+    let shrunk_end = heap_end + PAGE_SIZE + PAGE_SIZE / 2;
+    sys_brk(Some(shrunk_end)).expect("Failed to shrink heap");
+    let regrown_end = sys_brk(Some(grown_end)).expect("Failed to regrow heap");
+
+    assert_zeroed(shrunk_end, regrown_end);
+    for i in heap_end..shrunk_end {
+        assert_eq!(unsafe { *(i as *const u8) }, PATTERN);
+    }
+
+    sys_brk(Some(heap_end)).expect("Failed to clean up the heap");
+
+    println!("Regrown heap reads back as zeros.");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    let mut buf = [0u8; 128];
+    assert_eq!(sys_capture_end(&mut buf), None);
+
+    assert!(sys_capture_start());
+    println!("captured line {}", 1);
+    print!("captured line {}\n", 2);
+
+    // the inner capture takes the writes until it ends
+    assert!(sys_capture_start());
+    println!("inner");
+    let len = sys_capture_end(&mut buf).expect("Failed to end the inner capture");
+    assert_eq!(&buf[..len], b"inner\n");
+    println!("after inner");
+
+    let len = sys_capture_end(&mut buf).expect("Failed to end the capture");
+    assert_eq!(&buf[..len], b"captured line 1\ncaptured line 2\nafter inner\n");
+
+    // back to the serial console
+    assert_eq!(sys_write(1, b"on the console\n"), Some(15));
+    assert!(sys_isatty(1));
+    assert_eq!(sys_capture_end(&mut buf), None);
+
+    println!("Capture test passed!");
+
+    0
+}
+
+entry!(main);
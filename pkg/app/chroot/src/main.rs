@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const APP_PATH: &str = "/APP/CHROOT";
+/// The root the app is run in, by `chroot /EFI /APP/CHROOT` in the shell
+const ROOT: &str = "/EFI";
+/// `/EFI/BOOT/BOOTX64.EFI` outside of the root
+const INSIDE: &str = "/BOOT/BOOTX64.EFI";
+
+fn reachable(path: &str) -> bool {
+    match sys_open(path, 0) {
+        Some(fd) => sys_close_file(fd),
+        None => false,
+    }
+}
+
+fn main() -> isize {
+    assert!(
+        reachable(INSIDE),
+        "Not confined to {}, run it by `chroot {} {}`",
+        ROOT,
+        ROOT,
+        APP_PATH
+    );
+    if sys_get_arg(0).is_some() {
+        // a spawned child starts in the same root
+        return 0;
+    }
+
+    // the working dir is the root, which is its own parent
+    assert!(reachable("BOOT/BOOTX64.EFI"));
+    assert!(reachable("../BOOT/BOOTX64.EFI"));
+    assert!(reachable("/BOOT/../../BOOT/./BOOTX64.EFI"));
+
+    // the files outside cannot be reached
+    for path in ["/KERNEL.ELF", "../KERNEL.ELF", "/../KERNEL.ELF", "../../APP/CHROOT"] {
+        assert!(!reachable(path), "{} escapes the root", path);
+    }
+
+    // the shell drops the privilege before running the app
+    assert!(!sys_chroot("/BOOT"));
+    assert!(reachable(INSIDE));
+
+    let child = sys_posix_spawn(APP_PATH, &["child"], None, None).expect("Failed to spawn");
+    assert_eq!(sys_wait_pid(child), 0, "The child escapes the root");
+
+    println!("Chroot test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,113 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate lib;
+
+use alloc::boxed::Box;
+use lib::*;
+
+const APP_PATH: &str = "/APP/CKPT";
+/// The child counts up to this and exits
+const CHILD_LIMIT: u64 = 200;
+
+/// Count on the heap, writing every value to stdout as 8 bytes
+fn child() -> isize {
+    let mut counter = Box::new(0u64);
+    while *counter < CHILD_LIMIT {
+        *counter += 1;
+        let bytes = counter.to_le_bytes();
+        while sys_write(1, &bytes) == Some(0) {
+            sys_yield();
+        }
+        sys_yield();
+    }
+    0
+}
+
+/// The next value written by the child, waiting for it
+fn next_value(fd: u8) -> u64 {
+    let mut buf = [0u8; 8];
+    loop {
+        match sys_read(fd, &mut buf) {
+            Some(8) => return u64::from_le_bytes(buf),
+            Some(0) => sys_yield(),
+            ret => panic!("Failed to read: {:?}", ret),
+        }
+    }
+}
+
+/// Read the values up to `target`, checking they count up by one
+fn advance(fd: u8, last: &mut u64, target: u64) {
+    while *last < target {
+        let value = next_value(fd);
+        assert_eq!(value, *last + 1, "The counter skips");
+        *last = value;
+    }
+}
+
+fn main() -> isize {
+    if sys_get_arg(0).is_some() {
+        return child();
+    }
+
+    // the caller resumes from its own checkpoint, with the heap reverted
+    // and the fds opened since closed
+    let mut boxed = Box::new(5u64);
+    let counter = &mut *boxed as *mut u64;
+    let (next_fd, _) = sys_pipe().expect("Failed to create a pipe");
+    assert!(sys_close_file(next_fd) && sys_close_file(next_fd + 1));
+    match sys_checkpoint(0) {
+        Some(false) => {
+            unsafe { counter.write_volatile(counter.read_volatile() + 10) };
+            assert_eq!(sys_pipe(), Some((next_fd, next_fd + 1)));
+            sys_restore(0);
+            panic!("Failed to restore the checkpoint");
+        }
+        Some(true) => {
+            let counter = unsafe { counter.read_volatile() };
+            assert_eq!(counter, 5, "The heap is not restored");
+            assert_eq!(sys_fd_stats(next_fd), None, "The fd opened since is kept");
+        }
+        None => panic!("Failed to checkpoint"),
+    }
+
+    // a descendant goes back to its checkpoint while it is not running
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create a pipe");
+    let child =
+        sys_posix_spawn(APP_PATH, &["child"], None, Some(write_fd)).expect("Failed to spawn");
+    assert!(sys_close_file(write_fd));
+
+    let mut last = 0;
+    advance(read_fd, &mut last, 10);
+    assert_eq!(sys_checkpoint(child), Some(false));
+    // the child may have counted further before the checkpoint
+    let saved = last;
+    advance(read_fd, &mut last, saved + 20);
+    assert!(sys_restore(child));
+
+    // the counter drops back once, then counts up again from there
+    let reverted = loop {
+        let value = next_value(read_fd);
+        if value != last + 1 {
+            break value;
+        }
+        last = value;
+    };
+    println!("Checkpoint after {}, reverted from {} to {}", saved, last, reverted);
+    assert!(reverted > saved && reverted <= last, "The counter is not reverted");
+    last = reverted;
+    advance(read_fd, &mut last, CHILD_LIMIT);
+    assert_eq!(sys_wait_pid(child), 0);
+
+    // bad targets
+    assert!(!sys_restore(child));
+    assert_eq!(sys_checkpoint(1), None);
+    assert!(!sys_restore(1));
+
+    println!("Checkpoint test passed!");
+
+    0
+}
+
+entry!(main);
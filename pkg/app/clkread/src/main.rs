@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The tick boundaries to cross in the tight loop
+const BOUNDARIES: u64 = 5;
+const MAX_READS: usize = 100_000_000;
+
+fn main() -> isize {
+    let first = sys_uptime();
+    let mut last = first;
+    let mut reads = 0;
+    while last < first + BOUNDARIES {
+        let now = sys_uptime();
+        assert!(now >= last, "Uptime went backwards: {} -> {}", last, now);
+        last = now;
+        reads += 1;
+        assert!(reads < MAX_READS, "Uptime does not advance");
+    }
+    println!("{} reads across {} ticks", reads, last - first);
+
+    // the wall clock is read from the cache within a tick
+    let time = sys_time();
+    for _ in 0..1000 {
+        let now = sys_time();
+        // it may only wrap at midnight
+        assert!(now >= time || now < 60, "Time went backwards: {} -> {}", time, now);
+    }
+
+    println!("Clock read test passed!");
+
+    0
+}
+
+entry!(main);
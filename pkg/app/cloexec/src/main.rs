@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// Lists the fds of itself, exits with the bitmask of the opened ones
+const LSFD_PATH: &str = "/APP/LSFD";
+
+fn main() -> isize {
+    let kept = sys_open_file("/KERNEL.ELF");
+    let closed = sys_open_file(LSFD_PATH);
+    assert!(kept != u8::MAX && closed != u8::MAX, "Failed to open files");
+
+    assert!(sys_set_cloexec(closed, true));
+    assert!(!sys_set_cloexec(200, true));
+
+    // marking twice and clearing are both fine
+    assert!(sys_set_cloexec(kept, true));
+    assert!(sys_set_cloexec(kept, false));
+
+    let child = sys_fork();
+    if child == 0 {
+        // a failed exec keeps running the caller
+        assert_eq!(sys_exec("/APP/NOTEXIST"), -1);
+
+        sys_exec(LSFD_PATH);
+        sys_exit(-1);
+    }
+
+    let opened = sys_wait_pid(child);
+    println!("Opened fds after exec: {:#b}", opened);
+    assert_eq!(opened, 0b111 | 1 << kept, "Only the fd not marked should survive");
+
+    // the marks are per process, the parent keeps both
+    assert!(sys_fstat(closed).is_some());
+
+    sys_close_file(kept);
+    sys_close_file(closed);
+
+    println!("Close-on-exec test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,70 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const SEM_KEY: u32 = 0x6c6e;
+const ENV_KEY: &str = "CLONE";
+
+static VALUE: AtomicUsize = AtomicUsize::new(0);
+
+fn store(value: usize) {
+    VALUE.store(value, Ordering::SeqCst);
+}
+
+fn close(fd: usize) {
+    assert!(sys_close_file(fd as u8));
+}
+
+fn set_env(shared: usize) {
+    sys_set_env(ENV_KEY, if shared != 0 { "shared" } else { "private" });
+}
+
+fn signal(key: usize) {
+    assert!(sys_sem_signal(key as u32));
+}
+
+/// Clone a child running `entry(arg)` and wait for it to return
+fn run(flags: usize, entry: ThreadEntry, arg: usize) {
+    let pid = sys_clone(flags, entry, arg).expect("Failed to clone");
+    assert_eq!(sys_wait_pid(pid), 0, "The child failed with flags {:#x}", flags);
+}
+
+fn main() -> isize {
+    // the memory is shared like a thread, or copied like a process
+    run(CLONE_VM, store, 1);
+    assert_eq!(VALUE.load(Ordering::SeqCst), 1);
+    run(0, store, 2);
+    assert_eq!(VALUE.load(Ordering::SeqCst), 1, "A copied memory is written");
+
+    // a shared fd table loses the fd closed by the child
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create a pipe");
+    run(CLONE_VM | CLONE_FILES, close, read_fd as usize);
+    assert!(!sys_close_file(read_fd), "The fd closed in a shared table is kept");
+    run(CLONE_VM, close, write_fd as usize);
+    assert!(sys_close_file(write_fd), "The fd closed in a copied table is lost");
+
+    run(CLONE_VM | CLONE_ENV, set_env, 1);
+    assert_eq!(sys_get_env(ENV_KEY).as_deref(), Some("shared"));
+    run(CLONE_VM, set_env, 0);
+    assert_eq!(sys_get_env(ENV_KEY).as_deref(), Some("shared"));
+
+    // a copied semaphore is signaled apart from the one of the parent
+    assert!(sys_new_sem(SEM_KEY, 0));
+    run(CLONE_VM, signal, SEM_KEY as usize);
+    assert!(!sys_sem_timedwait(SEM_KEY, 10), "A copied semaphore is signaled");
+    run(CLONE_VM | CLONE_SYSVSEM, signal, SEM_KEY as usize);
+    assert!(sys_sem_timedwait(SEM_KEY, 10), "A shared semaphore is not signaled");
+    assert!(sys_remove_sem(SEM_KEY));
+
+    assert_eq!(sys_clone(0x1, store, 3), None);
+
+    println!("Clone test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, spawned again for the children
+const SELF_PATH: &str = "/APP/COLLECT";
+const CHILDREN: usize = 4;
+
+fn exit_code(pid: u16) -> isize {
+    pid as isize * 3 + 1
+}
+
+fn main() -> isize {
+    // the children are one level below the copy started by the shell
+    if sys_spawn_depth().0 > 2 {
+        for _ in 0..sys_get_pid() % 5 {
+            sys_yield();
+        }
+        return exit_code(sys_get_pid());
+    }
+
+    let mut pids = [0u16; CHILDREN];
+    for pid in pids.iter_mut() {
+        *pid = sys_spawn_collectable(SELF_PATH).expect("Failed to spawn");
+    }
+    // a plain child is never collectable
+    let plain = sys_spawn(SELF_PATH).expect("Failed to spawn");
+
+    // collect from the last one, whatever order they exit in
+    for pid in pids.iter().rev() {
+        let code = loop {
+            match sys_collect(*pid) {
+                Some(code) => break code,
+                None => sys_yield(),
+            }
+        };
+        println!("Child #{} exited with {}", pid, code);
+        assert_eq!(code, exit_code(*pid));
+    }
+
+    // each code is delivered exactly once
+    for pid in pids.iter() {
+        assert_eq!(sys_collect(*pid), None, "The code of #{} is delivered twice", pid);
+    }
+
+    assert_eq!(sys_wait_pid(plain), exit_code(plain));
+    assert_eq!(sys_collect(plain), None);
+
+    println!("Collect test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// Spawned by `pipeline` with the stdin redirected to a pipe,
+/// echoes the input until EOF and exits with its length
+fn main() -> isize {
+    let mut buf = [0u8; 64];
+    let mut total = 0;
+    loop {
+        if !sys_poll(&[0])[0].readable {
+            sys_yield();
+            continue;
+        }
+        match sys_read(0, &mut buf).expect("Failed to read stdin") {
+            // readable but empty, all the write ends are closed
+            0 => break,
+            count => {
+                let text = core::str::from_utf8(&buf[..count]).expect("Not UTF-8");
+                print!("{}", text);
+                total += count;
+            }
+        }
+    }
+
+    total as isize
+}
+
+entry!(main);
@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGES: usize = 64;
+const TOUCHED: usize = 8;
+const PAGE: usize = 4096;
+
+static mut DATA: [u8; PAGES * PAGE] = [0; PAGES * PAGE];
+
+fn data() -> &'static mut [u8; PAGES * PAGE] {
+    unsafe { &mut *core::ptr::addr_of_mut!(DATA) }
+}
+
+fn main() -> isize {
+    // fault in every page so the fork has them all mapped
+    for page in 0..PAGES {
+        data()[page * PAGE] = 1;
+    }
+
+    let child = sys_fork_cow();
+    if child == FORK_FAILED {
+        println!("Failed to fork.");
+        return -1;
+    }
+
+    let value = if child == 0 { 2 } else { 3 };
+    let (before, _) = sys_page_faults();
+    for page in 0..TOUCHED {
+        data()[page * PAGE] = value;
+    }
+    let (after, _) = sys_page_faults();
+
+    // only the touched pages are copied, and each of them once
+    let faults = after - before;
+    println!("Process #{} wrote {} pages with {} faults", sys_get_pid(), TOUCHED, faults);
+    assert!(faults <= TOUCHED + 1, "Too many faults for {} pages", TOUCHED);
+
+    // yield to let the other process write its value
+    sys_yield();
+
+    for page in 0..TOUCHED {
+        assert_eq!(data()[page * PAGE], value);
+    }
+    for page in TOUCHED..PAGES {
+        assert_eq!(data()[page * PAGE], 1);
+    }
+
+    if child == 0 {
+        sys_exit(0);
+    }
+
+    assert_eq!(sys_wait_pid(child), 0);
+    println!("Copy-on-write fork test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const TICKS: u64 = 100;
+
+fn spin_until(deadline: u64) {
+    while sys_uptime() < deadline {
+        core::hint::spin_loop();
+    }
+}
+
+fn main() -> isize {
+    let start_switches = sys_context_switches();
+    let deadline = sys_uptime() + TICKS;
+
+    let mut pids = [0u16; 2];
+    for pid in pids.iter_mut() {
+        *pid = sys_fork();
+        if *pid == 0 {
+            spin_until(deadline);
+            sys_exit(0);
+        }
+    }
+
+    for pid in pids {
+        sys_wait_pid(pid);
+    }
+
+    let switches = sys_context_switches() - start_switches;
+    println!("{} context switches in {} ticks", switches, TICKS);
+
+    // the quantum is one tick, two busy processes should
+    // be switched about once per tick
+    assert!(
+        (TICKS / 2..=TICKS * 2).contains(&switches),
+        "Unexpected context switch count: {}",
+        switches
+    );
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const DEBUG_CODE: usize = 0xdead;
+const CHILD_EXIT_CODE: isize = 7;
+
+fn main() -> isize {
+    let child = sys_fork();
+
+    if child == 0 {
+        // a passing assertion does not trap
+        debug_assert_kernel!(sys_get_pid() != 0);
+
+        println!("Process #{} trapping into the kernel...", sys_get_pid());
+        debug_assert_kernel!(sys_get_pid() == 0, DEBUG_CODE);
+        debug_assert_kernel!(CHILD_EXIT_CODE < 0);
+
+        // the trapping process keeps running after the diagnostic
+        println!("Process #{} is still running", sys_get_pid());
+        sys_exit(CHILD_EXIT_CODE);
+    }
+
+    // the other processes are not affected meanwhile
+    for _ in 0..10 {
+        sys_yield();
+    }
+
+    assert_eq!(sys_wait_pid(child), CHILD_EXIT_CODE);
+
+    println!("Debug trap test passed, see the kernel log for the registers.");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,34 @@
+#![no_std]
+#![no_main]
+
+use core::alloc::Layout;
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    let layout = Layout::from_size_align(64, 8).unwrap();
+
+    let ptr = sys_allocate(&layout);
+    assert!(!ptr.is_null(), "Failed to allocate memory");
+
+    assert!(sys_deallocate(ptr, &layout), "Failed to free memory");
+    assert!(!sys_deallocate(ptr, &layout), "Double free is not rejected");
+
+    // pointers out of the user heap are rejected
+    let mut local = 0u64;
+    assert!(!sys_deallocate(&mut local as *mut u64 as *mut u8, &layout));
+
+    // the allocator still works after the rejected frees
+    let ptr = sys_allocate(&layout);
+    assert!(!ptr.is_null());
+    let wrong = Layout::from_size_align(128, 8).unwrap();
+    assert!(!sys_deallocate(ptr, &wrong), "Mismatched layout is not rejected");
+    assert!(sys_deallocate(ptr, &layout));
+
+    println!("Deallocate validation test passed!");
+
+    0
+}
+
+entry!(main);
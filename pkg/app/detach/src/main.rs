@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const APP_PATH: &str = "/APP/DETACH";
+/// The parent of every detached process
+const KERNEL_PID: u16 = 1;
+/// The ticks the daemon keeps running for, after the spawner is gone
+const DAEMON_TICKS: u64 = 50;
+
+fn frames_in_use() -> usize {
+    let stats = sys_frame_stats();
+    stats.used - stats.recycled
+}
+
+fn under_kernel(pid: u16) -> bool {
+    sys_list_children(KERNEL_PID).contains(&pid)
+}
+
+/// Run for a while with nobody to wait for it, then exit by itself
+fn daemon() -> isize {
+    sys_sleep_until(sys_uptime() + DAEMON_TICKS);
+    0
+}
+
+/// Spawn the daemon and exit with its pid, its tree killed first
+fn spawner() -> ! {
+    let daemon = sys_spawn_detached(APP_PATH).expect("Failed to spawn");
+    assert!(sys_list_children(0).is_empty(), "The daemon is a child");
+    assert_eq!(sys_kill_tree(KILLED_EXIT_CODE), 0, "The daemon is killed");
+    sys_exit(daemon as isize)
+}
+
+fn main() -> isize {
+    // spawned by the kernel only as the daemon, the test is run by the shell
+    if under_kernel(sys_get_pid()) {
+        return daemon();
+    }
+
+    let before = frames_in_use();
+    let child = sys_fork();
+    if child == 0 {
+        spawner();
+    }
+    assert_ne!(child, FORK_FAILED);
+    let daemon = sys_wait_pid(child) as u16;
+    println!("Daemon #{} spawned by #{}", daemon, child);
+
+    // the spawner is gone, the daemon goes on
+    assert!(under_kernel(daemon), "The daemon is not under the kernel");
+    assert_eq!(sys_getsid(daemon), Some(daemon), "The daemon shares the session");
+
+    // then it exits by itself, and is dropped with its memory
+    let end = sys_uptime() + 2 * DAEMON_TICKS;
+    while under_kernel(daemon) && sys_uptime() < end {
+        sys_yield();
+    }
+    assert!(!under_kernel(daemon), "The daemon does not exit");
+    let after = frames_in_use();
+    println!("Frames in use: {} before, {} after", before, after);
+    assert_eq!(after, before, "The frames of the daemon are leaked");
+
+    println!("Detach test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const MISSING_APP: &str = "/APP/NODMESG";
+const DMESG_SIZE: usize = 4096;
+
+fn main() -> isize {
+    // the kernel warns that the app cannot be opened
+    assert_eq!(sys_spawn(MISSING_APP), None);
+
+    let mut buf = [0u8; DMESG_SIZE];
+    let len = sys_dmesg(&mut buf);
+    let log = core::str::from_utf8(&buf[..len]).unwrap_or("");
+    let line = log.lines().rev().find(|line| line.contains(MISSING_APP));
+    println!("Found in dmesg: {:?}", line);
+    assert!(line.is_some_and(|line| line.starts_with("[WARN]")));
+
+    // a short buffer gets the latest bytes
+    let mut tail = [0u8; 16];
+    assert_eq!(sys_dmesg(&mut tail), tail.len());
+    assert!(buf[..len].ends_with(&tail));
+    assert_eq!(sys_dmesg(&mut []), 0);
+
+    println!("Dmesg test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const MS: u64 = 1_000_000;
+static SEM: Semaphore = Semaphore::new(0x4453);
+
+/// Whether the line of the structure lists the process
+fn listed(dump: &str, prefix: &str, pid: u16) -> bool {
+    let entry = format!("#{} (", pid);
+    dump.lines()
+        .filter(|line| line.starts_with(prefix))
+        .any(|line| line.contains(entry.as_str()))
+}
+
+fn main() -> isize {
+    assert!(SEM.init(0));
+
+    let sleeper = sys_fork();
+    if sleeper == 0 {
+        assert!(sys_nanosleep(1000 * MS), "TSC is not calibrated");
+        sys_exit(0);
+    }
+    let waiter = sys_fork();
+    if waiter == 0 {
+        sys_exit(sys_wait_pid(sleeper));
+    }
+    let holder = sys_fork();
+    if holder == 0 {
+        SEM.wait();
+        sys_exit(0);
+    }
+
+    // let all of them block
+    assert!(sys_nanosleep(300 * MS));
+    sys_dump_sched();
+
+    let mut buf = [0u8; 1024];
+    let len = sys_dump_sched_into(&mut buf);
+    assert!(len <= buf.len(), "The dump does not fit");
+    let dump = core::str::from_utf8(&buf[..len]).expect("Not UTF-8");
+
+    assert!(listed(dump, "Sleeping", sleeper));
+    assert!(listed(dump, &format!("Waiting : #{} (", sleeper), waiter));
+    assert!(listed(dump, "Sem     : <0x4453>", holder));
+    for pid in [sleeper, waiter, holder] {
+        assert!(!listed(dump, "Ready", pid), "#{} is not blocked", pid);
+    }
+
+    SEM.signal();
+    for pid in [holder, waiter, sleeper] {
+        assert_eq!(sys_wait_pid(pid), 0);
+    }
+    assert!(SEM.remove());
+
+    println!("Scheduler dump test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    assert_eq!(sys_elf_info("missing"), None);
+    assert_eq!(sys_elf_info(""), None);
+
+    // apps are embedded only with `load_apps=1` in boot.conf
+    let info = match sys_elf_info("elfinfo") {
+        Some(info) => info,
+        None => {
+            println!("App elfinfo is not embedded, skipped.");
+            return 0;
+        }
+    };
+    println!(
+        "Entry: {:#x}, {} loadable segments",
+        info.entry, info.load_segments
+    );
+
+    assert_eq!(info.entry, __impl_start as usize as u64);
+    assert!(info.load_segments > 0);
+
+    println!("ELF info test passed!");
+
+    0
+}
+
+entry!(main);
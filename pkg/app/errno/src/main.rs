@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate lib;
+
+use alloc::string::String;
+use lib::*;
+
+const FILE_PATH: &str = "/KERNEL.ELF";
+/// An fd that is never opened during the test
+const BAD_FD: u8 = 200;
+/// A pid that is never allocated during the test
+const UNUSED_PID: u16 = 60000;
+
+/// Every error with its documented value
+const ERRORS: [(SysError, isize); 9] = [
+    (SysError::NotFound, -2),
+    (SysError::BadFd, -9),
+    (SysError::Again, -11),
+    (SysError::NoMem, -12),
+    (SysError::Perm, -13),
+    (SysError::Busy, -16),
+    (SysError::Exists, -17),
+    (SysError::Inval, -22),
+    (SysError::NoFds, -24),
+];
+
+fn check_codes() {
+    for (err, code) in ERRORS {
+        assert_eq!(err.code(), code, "{:?} has a wrong code", err);
+        assert_eq!(syscall_result(code), Err(err));
+    }
+    assert_eq!(syscall_result(0), Ok(0));
+    assert_eq!(syscall_result(42), Ok(42));
+    // an unknown code is still an error
+    assert_eq!(syscall_result(-1), Err(SysError::Inval));
+    assert_eq!((ENOENT, EACCES, EBUSY, EEXIST), (-2, -13, -16, -17));
+}
+
+fn check_open() {
+    assert_eq!(sys_open_raw("/MISSING", 0), ENOENT);
+    assert_eq!(sys_open_result("/MISSING", 0), Err(SysError::NotFound));
+    assert_eq!(sys_open_result(FILE_PATH, O_CREAT | O_EXCL), Err(SysError::Exists));
+    // the path is longer than the kernel takes
+    let long: String = "/A".repeat(200);
+    assert_eq!(sys_open_result(&long, 0), Err(SysError::Inval));
+
+    // the fd limit is reached with the fds open now
+    let fd = sys_open_result(FILE_PATH, 0).expect("Failed to open the file");
+    let old = sys_prlimit(0, RLIMIT_NOFILE, None).expect("Failed to get the limit");
+    let limit = Limit {
+        soft: fd as u64 + 1,
+        hard: old.hard,
+    };
+    assert!(sys_prlimit(0, RLIMIT_NOFILE, Some(limit)).is_some());
+    assert_eq!(sys_open_result(FILE_PATH, 0), Err(SysError::NoFds));
+    assert!(sys_prlimit(0, RLIMIT_NOFILE, Some(old)).is_some());
+    assert_eq!(sys_close_result(fd), Ok(()));
+}
+
+fn check_fds() {
+    let mut buf = [0u8; 8];
+    assert_eq!(sys_read_result(BAD_FD, &mut buf), Err(SysError::BadFd));
+    assert_eq!(sys_write_result(BAD_FD, &buf), Err(SysError::BadFd));
+    assert_eq!(sys_close_result(BAD_FD), Err(SysError::BadFd));
+
+    // the ends of a pipe go one way only
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create a pipe");
+    assert_eq!(sys_write_result(read_fd, &buf), Err(SysError::BadFd));
+    assert_eq!(sys_read_result(write_fd, &mut buf), Err(SysError::BadFd));
+    assert_eq!(sys_write_result(write_fd, b"ok"), Ok(2));
+    assert_eq!(sys_read_result(read_fd, &mut buf), Ok(2));
+
+    // a closed fd is gone
+    assert_eq!(sys_close_result(read_fd), Ok(()));
+    assert_eq!(sys_close_result(read_fd), Err(SysError::BadFd));
+    assert_eq!(sys_read_result(read_fd, &mut buf), Err(SysError::BadFd));
+    assert!(sys_close_file(write_fd));
+    assert!(!sys_close_file(write_fd));
+}
+
+fn check_wait() {
+    assert_eq!(sys_wait_pid_result(sys_get_pid()), Err(SysError::NotFound));
+    assert_eq!(sys_wait_pid_result(UNUSED_PID), Err(SysError::NotFound));
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(7);
+    }
+    assert_eq!(sys_wait_pid_result(child), Ok(7));
+}
+
+fn main() -> isize {
+    check_codes();
+    check_open();
+    check_fds();
+    check_wait();
+
+    println!("Errno test passed!");
+
+    0
+}
+
+entry!(main);
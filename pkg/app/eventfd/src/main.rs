@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const SIGNAL: u64 = 3;
+
+fn main() -> isize {
+    let efd = sys_eventfd(0, 0);
+    assert_ne!(efd, u8::MAX, "Failed to create an eventfd");
+    let flags = sys_poll(&[efd])[0];
+    assert!(!flags.readable && flags.writable);
+
+    // the child waits on the counter shared by the fork
+    let child = sys_fork();
+    if child == 0 {
+        let value = sys_eventfd_read(efd);
+        sys_exit(if value == Some(SIGNAL) { 0 } else { 1 });
+    }
+
+    for _ in 0..4 {
+        sys_yield();
+    }
+    assert!(sys_eventfd_write(efd, SIGNAL));
+    assert_eq!(sys_wait_pid(child), 0, "The child misses the signal");
+
+    // the counter is reset by the read, and readable once written again
+    let mut buf = [0u8; 8];
+    assert_eq!(sys_read(efd, &mut buf), Some(0));
+    assert!(sys_eventfd_write(efd, 2));
+    assert!(sys_eventfd_write(efd, 5));
+    assert!(sys_poll(&[efd])[0].readable);
+    assert_eq!(sys_eventfd_read(efd), Some(7));
+    assert!(!sys_poll(&[efd])[0].readable);
+
+    // a short buffer & the overflow are refused
+    assert_eq!(sys_read(efd, &mut buf[..4]), None);
+    assert!(!sys_eventfd_write(efd, u64::MAX));
+    assert!(sys_eventfd_write(efd, u64::MAX - 1));
+    assert!(!sys_eventfd_write(efd, 1));
+    assert!(!sys_poll(&[efd])[0].writable);
+    sys_close_file(efd);
+
+    // the semaphore mode takes one at a time
+    let sem = sys_eventfd(2, EFD_SEMAPHORE);
+    assert_eq!(sys_eventfd_read(sem), Some(1));
+    assert_eq!(sys_eventfd_read(sem), Some(1));
+    assert_eq!(sys_read(sem, &mut buf), Some(0));
+    sys_close_file(sem);
+
+    assert_eq!(sys_eventfd(0, 2), u8::MAX);
+
+    println!("EventFd test passed!");
+
+    0
+}
+
+entry!(main);
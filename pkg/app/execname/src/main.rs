@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const NAME: &str = "execname";
+const TARGET: &str = "/APP/HELLO";
+/// The exit code of `hello`
+const TARGET_EXIT_CODE: isize = 233;
+
+fn main() -> isize {
+    assert_eq!(sys_get_proc_name(0).as_deref(), Some(NAME));
+
+    let child = sys_fork();
+    if child == 0 {
+        // a failed exec keeps the old image and its name
+        assert!(sys_exec("/APP/NOTEXIST") < 0);
+        assert_eq!(sys_get_proc_name(0).as_deref(), Some(NAME));
+        sys_exec(TARGET);
+        unreachable!("Failed to exec {}", TARGET);
+    }
+    assert_eq!(sys_wait_pid(child), TARGET_EXIT_CODE);
+
+    // the entry of the child is kept after the exit, named after the new image
+    let name = sys_get_proc_name(child).expect("The child is not listed");
+    println!("Child #{} is named {}", child, name);
+    assert_eq!(name, "hello");
+
+    println!("Exec name test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const THREADS: usize = 3;
+const EXIT_CODE: isize = 7;
+
+// the forked child shares the memory, so the parent sees these
+static STARTED: AtomicUsize = AtomicUsize::new(0);
+static SPINS: AtomicUsize = AtomicUsize::new(0);
+static PEAK_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
+fn frames_in_use() -> usize {
+    let info = sys_meminfo();
+    info.used - info.recycled
+}
+
+fn spin(_: usize) {
+    STARTED.fetch_add(1, Ordering::SeqCst);
+    loop {
+        SPINS.fetch_add(1, Ordering::SeqCst);
+        sys_yield();
+    }
+}
+
+fn main() -> isize {
+    let child = sys_fork();
+    if child == 0 {
+        for _ in 0..THREADS {
+            sys_thread_create(spin, 0).expect("Failed to create the thread");
+        }
+        while STARTED.load(Ordering::SeqCst) < THREADS {
+            sys_yield();
+        }
+        PEAK_FRAMES.store(frames_in_use(), Ordering::SeqCst);
+        // the spinning threads never exit on their own
+        sys_exit_group(EXIT_CODE);
+    }
+
+    assert_eq!(sys_wait_pid(child), EXIT_CODE);
+
+    let spins = SPINS.load(Ordering::SeqCst);
+    for _ in 0..16 {
+        sys_yield();
+    }
+    assert_eq!(SPINS.load(Ordering::SeqCst), spins, "A thread is still running");
+
+    // every thread gives back at least its stack
+    let peak = PEAK_FRAMES.load(Ordering::SeqCst);
+    let now = frames_in_use();
+    println!("Frames in use: {} at the peak, {} now", peak, now);
+    assert!(now + THREADS <= peak, "The memory of the threads is not freed");
+
+    println!("Exit group test passed!");
+
+    0
+}
+
+entry!(main);
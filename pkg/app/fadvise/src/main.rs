@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate lib;
+
+use alloc::vec::Vec;
+use lib::*;
+
+const APP_PATH: &str = "/APP/FADVISE";
+/// Smaller than a sector, so that a sector is asked for several times
+const CHUNK_SIZE: usize = 128;
+
+/// Read the whole file from the start in small chunks,
+/// returns the contents & the disk reads issued meanwhile
+fn read_through(fd: u8) -> (Vec<u8>, DiskStats) {
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+    let mut contents = Vec::new();
+    let mut buf = [0u8; CHUNK_SIZE];
+    let before = sys_disk_stats();
+    loop {
+        match sys_read(fd, &mut buf) {
+            Some(0) => break,
+            Some(len) => contents.extend_from_slice(&buf[..len]),
+            None => panic!("Failed to read"),
+        }
+    }
+    let after = sys_disk_stats();
+    let stats = DiskStats {
+        reads: after.reads - before.reads,
+        sectors: after.sectors - before.sectors,
+    };
+    (contents, stats)
+}
+
+fn main() -> isize {
+    let fd = sys_open(APP_PATH, 0).expect("Failed to open the file");
+
+    assert!(sys_fadvise(fd, POSIX_FADV_RANDOM));
+    let (random, random_stats) = read_through(fd);
+    assert!(sys_fadvise(fd, POSIX_FADV_SEQUENTIAL));
+    let (sequential, sequential_stats) = read_through(fd);
+    println!(
+        "Read {} bytes, random: {:?}, sequential: {:?}",
+        random.len(),
+        random_stats,
+        sequential_stats
+    );
+
+    // the same data, in fewer & larger reads
+    assert!(!random.is_empty());
+    assert_eq!(random, sequential, "The contents differ");
+    assert!(sequential_stats.reads < random_stats.reads, "No fewer reads");
+    assert!(
+        sequential_stats.sectors * random_stats.reads
+            > random_stats.sectors * sequential_stats.reads,
+        "No larger reads"
+    );
+
+    // the hint is kept by the fd until changed
+    assert!(sys_fadvise(fd, POSIX_FADV_NORMAL));
+    let (normal, normal_stats) = read_through(fd);
+    assert_eq!(normal, random);
+    assert_eq!(normal_stats.reads, normal_stats.sectors);
+    assert!(sys_close_file(fd));
+
+    // a no-op for the other fds, bad fds & hints are rejected
+    assert!(sys_fadvise(1, POSIX_FADV_SEQUENTIAL));
+    assert!(!sys_fadvise(fd, POSIX_FADV_SEQUENTIAL));
+    let fd = sys_open(APP_PATH, 0).expect("Failed to open the file");
+    assert!(!sys_fadvise(fd, 3));
+    assert!(sys_close_file(fd));
+
+    println!("Fadvise test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 4096;
+const PIXEL: u32 = 0x00C0_FFEE;
+
+fn main() -> isize {
+    let fb = match sys_map_framebuffer() {
+        Some(fb) => fb,
+        None => {
+            println!("No framebuffer, skipped.");
+            return 0;
+        }
+    };
+    println!("Framebuffer: {:#x?}", fb);
+    assert!(fb.width > 0 && fb.height > 0 && fb.stride >= fb.width);
+    assert!(fb.size >= fb.stride * fb.height * 4);
+
+    let pixels = unsafe { core::slice::from_raw_parts_mut(fb.base as *mut u32, fb.size / 4) };
+    let origin = pixels[0];
+    unsafe { core::ptr::write_volatile(&mut pixels[0], PIXEL) };
+    assert_eq!(unsafe { core::ptr::read_volatile(&pixels[0]) }, PIXEL);
+
+    // a second mapping is backed by the same memory, so the write is live
+    let other = sys_map_framebuffer().expect("Failed to map again");
+    assert_ne!(other.base, fb.base);
+    assert_eq!(unsafe { core::ptr::read_volatile(other.base as *const u32) }, PIXEL);
+    let phys = |base: usize| sys_virt_to_phys(base).expect("Not mapped").phys;
+    assert_eq!(phys(other.base), phys(fb.base));
+
+    unsafe { core::ptr::write_volatile(&mut pixels[0], origin) };
+    assert!(sys_munmap(other.base & !(PAGE_SIZE - 1)));
+    assert!(sys_munmap(fb.base & !(PAGE_SIZE - 1)));
+
+    println!("Framebuffer test passed!");
+
+    0
+}
+
+entry!(main);
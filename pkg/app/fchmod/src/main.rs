@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, the appended bytes are ignored by the ELF loader
+const FILE_PATH: &str = "/APP/FCHMOD";
+const MARKER: &[u8] = b"written by the fchmod test";
+
+fn main() -> isize {
+    let fd = sys_open_file(FILE_PATH);
+    let length = sys_seek(fd, 0, SEEK_END);
+    assert!(length > 0, "Failed to open {}", FILE_PATH);
+
+    // a read-only file can be read, but neither written nor opened to write
+    assert!(sys_fchmod(fd, MODE_READONLY));
+    assert_eq!(sys_write(fd, MARKER), None);
+    assert_eq!(sys_open(FILE_PATH, O_RDWR), None);
+
+    let mut head = [0u8; 4];
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+    assert_eq!(sys_read(fd, &mut head), Some(4));
+    assert_eq!(&head, b"\x7fELF");
+
+    // writable again, the write succeeds
+    assert!(sys_fchmod(fd, MODE_DEFAULT));
+    assert_eq!(sys_seek(fd, 0, SEEK_END), length);
+    assert_eq!(sys_write(fd, MARKER), Some(MARKER.len()));
+
+    let rw = sys_open(FILE_PATH, O_RDWR).expect("Failed to open for writing");
+    assert!(sys_ftruncate(rw, length as usize));
+    sys_close_file(rw);
+    sys_close_file(fd);
+
+    // only regular files have a mode
+    assert!(!sys_fchmod(1, MODE_READONLY));
+
+    println!("Fchmod test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The bytes a pipe holds, the write over it is short
+const PIPE_CAPACITY: usize = 4096;
+const CHUNK: usize = 3000;
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create a pipe");
+    assert_eq!(sys_fd_stats(write_fd), Some((0, 0)));
+
+    let buf = [0x5Au8; CHUNK];
+    assert_eq!(sys_write(write_fd, &buf), Some(CHUNK));
+    assert_eq!(sys_fd_stats(write_fd), Some((0, CHUNK as u64)));
+
+    // only what fits is taken, and counted
+    let short = sys_write(write_fd, &buf).expect("Failed to write");
+    assert_eq!(short, PIPE_CAPACITY - CHUNK);
+    assert_eq!(sys_fd_stats(write_fd), Some((0, PIPE_CAPACITY as u64)));
+    assert_eq!(sys_write(write_fd, &buf), Some(0));
+    assert_eq!(sys_fd_stats(write_fd), Some((0, PIPE_CAPACITY as u64)));
+
+    let mut out = [0u8; 1000];
+    assert_eq!(sys_read(read_fd, &mut out), Some(out.len()));
+    assert_eq!(sys_fd_stats(read_fd), Some((out.len() as u64, 0)));
+
+    // the counts go with the fd, a new one starts from zero
+    sys_close_file(write_fd);
+    assert_eq!(sys_fd_stats(write_fd), None);
+    let (read_again, write_again) = sys_pipe().expect("Failed to create a pipe");
+    assert_eq!(read_again, write_fd, "The lowest free fd is not reused");
+    assert_eq!(sys_fd_stats(read_again), Some((0, 0)));
+
+    sys_close_file(read_fd);
+    sys_close_file(read_again);
+    sys_close_file(write_again);
+
+    println!("Fd stats test passed!");
+
+    0
+}
+
+entry!(main);
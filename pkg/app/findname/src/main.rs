@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate lib;
+
+use alloc::vec::Vec;
+use lib::*;
+
+const APP_PATH: &str = "/APP/FINDNAME";
+const NAME: &str = "twin";
+const TWINS: usize = 2;
+const TRIES: usize = 100;
+
+/// Take the shared name, then keep running until killed
+fn child() -> isize {
+    assert_eq!(sys_set_proc_name(NAME), Some(NAME.len()));
+    loop {
+        sys_yield();
+    }
+}
+
+fn main() -> isize {
+    if sys_get_arg(0).is_some() {
+        return child();
+    }
+
+    let mut twins: Vec<u16> = (0..TWINS)
+        .map(|_| sys_posix_spawn(APP_PATH, &["child"], None, None).expect("Failed to spawn"))
+        .collect();
+    twins.sort();
+
+    // both are found once they have renamed themselves
+    let mut found = Vec::new();
+    for _ in 0..TRIES {
+        found = sys_find_by_name(NAME);
+        if found.len() == TWINS {
+            break;
+        }
+        sys_yield();
+    }
+    println!("Found {:?} named {}", found, NAME);
+    assert_eq!(found, twins, "The twins are not found");
+
+    // the match is exact
+    assert!(sys_find_by_name("twi").is_empty());
+    assert!(sys_find_by_name("twins").is_empty());
+
+    // like killall, the dead ones are no longer found
+    for pid in found {
+        assert!(sys_kill(pid));
+        sys_wait_pid(pid);
+    }
+    assert!(sys_find_by_name(NAME).is_empty());
+
+    println!("Find by name test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,34 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const KEY: &str = "FORKENV";
+
+fn main() -> isize {
+    sys_set_env(KEY, "parent");
+
+    let pid = sys_fork();
+    if pid == 0 {
+        // the child starts with a copy of the parent's variables
+        assert_eq!(sys_get_env(KEY).as_deref(), Some("parent"));
+        sys_set_env(KEY, "child");
+        sys_set_env("CHILD_ONLY", "1");
+        assert_eq!(sys_get_env(KEY).as_deref(), Some("child"));
+        sys_exit(0);
+    }
+
+    assert_eq!(sys_wait_pid(pid), 0);
+
+    println!("Parent sees {} = {:?}", KEY, sys_get_env(KEY));
+    assert_eq!(sys_get_env(KEY).as_deref(), Some("parent"));
+    assert_eq!(sys_get_env("CHILD_ONLY"), None);
+
+    println!("Fork env test passed!");
+
+    0
+}
+
+entry!(main);
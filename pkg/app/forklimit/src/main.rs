@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+// more than the kernel will ever allow for a single process
+const MAX_TRY: usize = 128;
+
+fn main() -> isize {
+    let mut children = vec::Vec::new();
+
+    for _ in 0..MAX_TRY {
+        let pid = sys_fork();
+        if pid == 0 {
+            sys_exit(0);
+        } else if pid == FORK_FAILED {
+            break;
+        }
+        children.push(pid);
+    }
+
+    assert!(
+        children.len() < MAX_TRY,
+        "Fork was never rejected after {} children",
+        MAX_TRY
+    );
+    println!("Fork rejected after {} children", children.len());
+
+    // reap all the children, then fork should work again
+    for pid in children {
+        assert_eq!(sys_wait_pid(pid), 0);
+    }
+
+    let pid = sys_fork();
+    if pid == 0 {
+        sys_exit(0);
+    }
+    assert_ne!(pid, FORK_FAILED, "Fork still rejected after reaping");
+    assert_eq!(sys_wait_pid(pid), 0);
+
+    println!("Fork limit test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const MAX_TRY: usize = FORK_RATE_LIMIT * 4;
+
+/// Fork a child exiting at once and reap it, false if the fork is rejected
+fn fork_and_reap() -> bool {
+    let pid = sys_fork();
+    if pid == 0 {
+        sys_exit(0);
+    } else if pid == FORK_FAILED {
+        return false;
+    }
+    assert_eq!(sys_wait_pid(pid), 0);
+    true
+}
+
+fn wait_until(tick: u64) {
+    while sys_uptime() < tick {
+        sys_yield();
+    }
+}
+
+fn main() -> isize {
+    // the children are reaped at once, so only the rate can stop the loop
+    let mut forked = 0;
+    while forked < MAX_TRY && fork_and_reap() {
+        forked += 1;
+    }
+    let rejected_at = sys_uptime();
+    println!("Fork rejected by rate after {} children", forked);
+    assert!(forked < MAX_TRY, "Fork was never rejected");
+    assert!(forked >= FORK_RATE_LIMIT, "Fork rejected under the rate");
+
+    // the window passes, then a fork is allowed again
+    wait_until(rejected_at + FORK_RATE_WINDOW);
+    assert!(fork_and_reap(), "Fork still rejected after the window");
+
+    // a slow forker never reaches the rate
+    let interval = FORK_RATE_WINDOW / FORK_RATE_LIMIT as u64 + 1;
+    for i in 0..FORK_RATE_LIMIT * 2 {
+        wait_until(sys_uptime() + interval);
+        assert!(fork_and_reap(), "Slow fork #{} is rejected", i);
+    }
+
+    println!("Fork rate test passed!");
+
+    0
+}
+
+entry!(main);
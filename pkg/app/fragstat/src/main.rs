@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE: usize = 4096;
+const PAGES: usize = 64;
+
+fn brk(end: usize) {
+    assert_eq!(sys_brk(Some(end)), Some(end), "Failed to move brk to {:#x}", end);
+}
+
+fn main() -> isize {
+    let base = sys_brk(None).expect("Failed to get brk");
+
+    // take all the recycled frames, the next ones come from the memory map
+    let before = sys_frame_stats();
+    println!("Before: {:?}", before);
+    let mark = base + before.recycled * PAGE;
+    brk(mark);
+
+    // contiguous: free a run of pages allocated at once
+    brk(mark + PAGES * PAGE);
+    brk(mark);
+    let contiguous = sys_frame_stats();
+    println!("Contiguous: {:?}", contiguous);
+    assert!(contiguous.recycled >= PAGES);
+
+    // fragmenting: interleave the heap pages with the stack pages,
+    // then free the heap pages only
+    let local = 0u8;
+    let stack = core::ptr::addr_of!(local) as usize & !(PAGE - 1);
+    for page in 0..PAGES {
+        brk(mark + (page + 1) * PAGE);
+        let addr = stack - (page + 2) * PAGE;
+        unsafe { core::ptr::write_volatile(addr as *mut u8, 1) };
+    }
+    brk(mark);
+    let fragmented = sys_frame_stats();
+    println!("Fragmented: {:?}", fragmented);
+
+    assert!(
+        fragmented.largest_run < contiguous.largest_run,
+        "The largest run should shrink"
+    );
+
+    brk(base);
+
+    println!("Frame stats test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const FILE_PATH: &str = "/KERNEL.ELF";
+
+fn main() -> isize {
+    let fd = sys_open_file(FILE_PATH);
+
+    // count the bytes by reading the whole file
+    let mut buf = [0u8; 512];
+    let mut length = 0;
+    loop {
+        let len = sys_read(fd, &mut buf).expect("Failed to read file");
+        if len == 0 {
+            break;
+        }
+        length += len;
+    }
+
+    let stat = sys_fstat(fd).expect("Failed to stat file");
+    println!("{}: {:?}, {} bytes read", FILE_PATH, stat, length);
+    assert_eq!(stat.kind, FileKind::Regular);
+    assert!(!stat.is_dir);
+    assert_eq!(stat.size, length as u64);
+    // the offset is not moved by fstat
+    assert_eq!(sys_seek(fd, 0, SEEK_CUR), length as isize);
+
+    sys_close_file(fd);
+
+    for fd in 0..3 {
+        let stat = sys_fstat(fd).expect("Failed to stat stdio");
+        assert_eq!(stat.kind, FileKind::CharDevice);
+        assert_eq!(stat.size, 0);
+    }
+
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+    for fd in [read_fd, write_fd] {
+        assert_eq!(sys_fstat(fd).map(|s| s.kind), Some(FileKind::CharDevice));
+    }
+
+    assert_eq!(sys_fstat(200), None);
+
+    println!("Fstat test passed!");
+
+    0
+}
+
+entry!(main);
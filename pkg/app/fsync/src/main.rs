@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, restored to its length at the end
+const FILE_PATH: &str = "/APP/FSYNC";
+const PATTERN: &[u8] = b"synced by the fsync test";
+
+fn main() -> isize {
+    let fd = sys_open(FILE_PATH, O_RDWR).expect("Failed to open the file");
+    let length = sys_seek(fd, 0, SEEK_END);
+    assert!(length > 0, "Failed to seek to the end of {}", FILE_PATH);
+
+    assert_eq!(sys_write(fd, PATTERN), Some(PATTERN.len()));
+    assert!(sys_fsync(fd));
+
+    // the data is visible through a freshly opened fd
+    let other = sys_open(FILE_PATH, 0).expect("Failed to open the file");
+    let mut buf = [0u8; PATTERN.len()];
+    assert_eq!(sys_seek(other, length, SEEK_SET), length);
+    assert_eq!(sys_read(other, &mut buf), Some(PATTERN.len()));
+    assert_eq!(&buf, PATTERN);
+    sys_close_file(other);
+
+    assert!(sys_ftruncate(fd, length as usize));
+    sys_close_file(fd);
+
+    // the console output is drained, stdin has nothing to flush
+    print!("Flushing the serial output... ");
+    assert!(sys_fsync(1));
+    println!("done");
+    assert!(sys_fsync(0));
+    assert!(!sys_fsync(fd));
+
+    println!("Fsync test passed!");
+
+    0
+}
+
+entry!(main);
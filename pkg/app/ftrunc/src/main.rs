@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, restored to its length at the end
+const FILE_PATH: &str = "/APP/FTRUNC";
+const EXTRA: usize = 1000;
+
+fn main() -> isize {
+    let fd = sys_open_file(FILE_PATH);
+    let length = sys_fstat(fd).expect("Failed to stat the file").size as usize;
+    println!("{} has {} bytes", FILE_PATH, length);
+
+    // truncate up, the new tail reads as zeros
+    assert!(sys_ftruncate(fd, length + EXTRA));
+    assert_eq!(sys_fstat(fd).unwrap().size as usize, length + EXTRA);
+
+    let mut buf = [0xAAu8; EXTRA];
+    assert_eq!(sys_seek(fd, length as isize, SEEK_SET), length as isize);
+    let mut read = 0;
+    while read < EXTRA {
+        match sys_read(fd, &mut buf[read..]) {
+            Some(len) if len > 0 => read += len,
+            _ => break,
+        }
+    }
+    assert_eq!(read, EXTRA);
+    assert!(buf.iter().all(|b| *b == 0), "The extended part is not zeroed");
+
+    // truncate down, the prefix is preserved
+    assert!(sys_ftruncate(fd, length));
+    assert_eq!(sys_fstat(fd).unwrap().size as usize, length);
+    assert_eq!(sys_seek(fd, 0, SEEK_END), length as isize);
+
+    let mut head = [0u8; 4];
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+    assert_eq!(sys_read(fd, &mut head), Some(4));
+    assert_eq!(&head, b"\x7fELF");
+
+    sys_close_file(fd);
+
+    // only regular files can be resized
+    assert!(!sys_ftruncate(1, 0));
+    assert!(!sys_ftruncate(fd, 0));
+
+    println!("Ftruncate test passed!");
+
+    0
+}
+
+entry!(main);
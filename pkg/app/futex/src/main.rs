@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const ROUNDS: usize = 20;
+
+/// 0 for unlocked, 1 for locked, 2 for locked with waiters
+static STATE: AtomicU32 = AtomicU32::new(0);
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+static WAITS: AtomicUsize = AtomicUsize::new(0);
+
+fn lock() {
+    if STATE
+        .compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst)
+        .is_ok()
+    {
+        return;
+    }
+    // mark it contended, so the holder wakes up a waiter on unlock
+    while STATE.swap(2, Ordering::SeqCst) != 0 {
+        if sys_futex_wait(&STATE, 2) == 0 {
+            WAITS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+fn unlock() {
+    if STATE.swap(0, Ordering::SeqCst) == 2 {
+        sys_futex_wake(&STATE, 1);
+    }
+}
+
+fn work() {
+    for _ in 0..ROUNDS {
+        lock();
+        // a racy increment, only correct under the lock
+        let value = COUNTER.load(Ordering::SeqCst);
+        sys_yield();
+        COUNTER.store(value + 1, Ordering::SeqCst);
+        unlock();
+    }
+}
+
+fn main() -> isize {
+    // the value differs, or nobody waits
+    assert_eq!(sys_futex_wait(&STATE, 1), -2);
+    assert_eq!(sys_futex_wake(&STATE, 1), 0);
+
+    // the forked child shares the memory, so the word as well
+    let child = sys_fork();
+    if child == 0 {
+        work();
+        sys_exit(0);
+    }
+
+    work();
+    assert_eq!(sys_wait_pid(child), 0);
+
+    let count = COUNTER.load(Ordering::SeqCst);
+    println!("Counter: {}, futex waits: {}", count, WAITS.load(Ordering::SeqCst));
+    assert_eq!(count, ROUNDS * 2, "The mutex let both in");
+    assert!(WAITS.load(Ordering::SeqCst) > 0, "Nobody blocked on the futex");
+    assert_eq!(STATE.load(Ordering::SeqCst), 0);
+
+    println!("Futex test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The most processors the kernel keeps the state of
+const MAX_CPUS: u32 = 4;
+const ROUNDS: usize = 64;
+
+fn main() -> isize {
+    let cpu = sys_getcpu();
+    println!("Running on CPU #{}", cpu);
+    assert!(cpu < MAX_CPUS, "CPU #{} is out of range", cpu);
+
+    // nothing moves the process between back to back calls,
+    // and there is only one processor to run on anyway
+    for _ in 0..ROUNDS {
+        assert_eq!(sys_getcpu(), cpu);
+    }
+    // nor across a switch to the other processes
+    sys_yield();
+    assert_eq!(sys_getcpu(), cpu);
+
+    println!("Getcpu test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+use lib::{string::String, vec::Vec, *};
+
+extern crate lib;
+
+const DIR_PATH: &str = "/APP";
+const BUFFER_LEN: usize = 4;
+
+fn main() -> isize {
+    let fd = sys_open_file(DIR_PATH);
+    let stat = sys_fstat(fd).expect("Failed to stat dir");
+    assert!(stat.is_dir);
+    assert_eq!(stat.kind, FileKind::Directory);
+
+    // walk the dir with a buffer smaller than the number of entries
+    let mut names: Vec<String> = Vec::new();
+    let mut cursor = 0;
+    let mut calls = 0;
+    loop {
+        let mut dirents = [Dirent::default(); BUFFER_LEN];
+        cursor = sys_getdents(fd, cursor, &mut dirents).expect("Failed to read dir");
+        calls += 1;
+        for dirent in dirents.iter().take_while(|d| d.name_len > 0) {
+            names.push(dirent.name().into());
+        }
+        if cursor == 0 {
+            break;
+        }
+    }
+    println!("{} entries in {} calls", names.len(), calls);
+    assert!(calls > 1, "{} fits in one buffer", DIR_PATH);
+
+    // every entry is visited exactly once
+    let mut sorted = names.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(sorted.len(), names.len());
+
+    // the iterator walks the same entries in the same order
+    let iterated: Vec<String> = sys_read_dir(fd).map(|d| d.name().into()).collect();
+    assert_eq!(iterated, names);
+
+    // only dirs can be iterated
+    let mut dirents = [Dirent::default(); BUFFER_LEN];
+    assert_eq!(sys_getdents(1, 0, &mut dirents), None);
+
+    sys_close_file(fd);
+
+    println!("GetDents test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 0x1000;
+/// Far more than the memory of this app, far less than the heap area
+const LOOSE_LIMIT: usize = 0x4000_0000;
+const HEADROOM: usize = PAGE_SIZE * 10 + 123;
+
+fn main() -> isize {
+    let start = sys_brk(None).unwrap();
+    let end = sys_brk(Some(start + PAGE_SIZE * 4)).expect("Failed to grow");
+
+    // bounded by the heap area only, which is larger than the loose limit
+    let unlimited = sys_heap_avail() as usize;
+    assert!(unlimited > LOOSE_LIMIT);
+
+    // the usage is what the loose limit leaves out
+    sys_set_mem_limit(LOOSE_LIMIT);
+    let usage = LOOSE_LIMIT - sys_heap_avail() as usize;
+    println!("Heap avail: {:#x} unlimited, usage {:#x}", unlimited, usage);
+
+    sys_set_mem_limit(usage + HEADROOM);
+    assert_eq!(sys_heap_avail() as usize, HEADROOM);
+
+    // growing exactly the headroom succeeds, a byte more fails
+    assert_eq!(sys_brk(Some(end + HEADROOM + 1)), None);
+    assert_eq!(sys_brk(Some(end + HEADROOM)), Some(end + HEADROOM));
+    assert_eq!(sys_heap_avail(), 0);
+    assert_eq!(sys_brk(Some(end + HEADROOM + 1)), None);
+
+    // shrinking gives it back
+    assert_eq!(sys_brk(Some(end)), Some(end));
+    assert_eq!(sys_heap_avail() as usize, HEADROOM);
+
+    sys_set_mem_limit(0);
+    assert_eq!(sys_heap_avail() as usize, unlimited);
+    assert_eq!(sys_brk(Some(start)), Some(start));
+
+    println!("Heap avail test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const SLEEP_NS: u64 = 500_000_000;
+/// Besides the clock, the serial port may wake the idle kernel a few times
+const EXTRA_WAKEUPS: u64 = 16;
+
+fn main() -> isize {
+    // run alone, the shell waits for this process, so nothing is ready
+    // while it sleeps and the kernel process idles
+    let (idle, wakeups) = sys_idle_stats();
+    let ticks = sys_uptime();
+
+    assert!(sys_nanosleep(SLEEP_NS));
+
+    let ticks = sys_uptime() - ticks;
+    let (idle, wakeups) = {
+        let (now_idle, now_wakeups) = sys_idle_stats();
+        (now_idle - idle, now_wakeups - wakeups)
+    };
+    println!(
+        "Slept {} ticks: {} idle switches, {} idle wakeups",
+        ticks, idle, wakeups
+    );
+
+    // the clock still fires and wakes the sleeper
+    assert!(ticks > 0, "The clock did not tick while idle");
+    assert!(idle > 0, "The scheduler never idled");
+
+    // the kernel halts between ticks instead of spinning
+    assert!(
+        wakeups <= ticks + EXTRA_WAKEUPS,
+        "The idle kernel woke up {} times in {} ticks",
+        wakeups,
+        ticks
+    );
+
+    println!("Idle test passed!");
+
+    0
+}
+
+entry!(main);
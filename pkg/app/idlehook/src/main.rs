@@ -0,0 +1,34 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const SLEEP_NS: u64 = 500_000_000;
+const BUSY_TICKS: u64 = 2;
+
+fn main() -> isize {
+    // run alone, the shell waits for this process, so nothing is ready
+    // while it sleeps and the kernel process runs its hooks
+    let runs = sys_idle_hook_runs();
+    assert!(sys_nanosleep(SLEEP_NS));
+    let idle_runs = sys_idle_hook_runs() - runs;
+    println!("The idle hooks ran {} times in the sleep", idle_runs);
+    assert!(idle_runs > 0, "The idle hooks never ran");
+
+    // no hook runs while this process is ready
+    let runs = sys_idle_hook_runs();
+    let start = sys_uptime();
+    while sys_uptime() < start + BUSY_TICKS {
+        core::hint::spin_loop();
+    }
+    let busy_runs = sys_idle_hook_runs() - runs;
+    assert_eq!(busy_runs, 0, "The idle hooks ran while busy");
+
+    println!("Idle hook test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const HEADER: &[u8] = b"HEAD ";
+const BODY: &[u8] = b"this is the body";
+const TRAILER: &[u8] = b" TAIL";
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+    let total = HEADER.len() + BODY.len() + TRAILER.len();
+
+    // three segments come out as one stream, empty ones are skipped
+    let written = sys_writev(write_fd, &[HEADER, b"", BODY, TRAILER]);
+    assert_eq!(written, Some(total));
+
+    let mut buf = [0u8; 64];
+    let len = sys_read(read_fd, &mut buf).expect("Failed to read pipe");
+    let output = core::str::from_utf8(&buf[..len]).expect("Invalid utf8");
+    println!("Read {:?}", output);
+    assert_eq!(&buf[..HEADER.len()], HEADER);
+    assert_eq!(&buf[HEADER.len()..HEADER.len() + BODY.len()], BODY);
+    assert_eq!(&buf[HEADER.len() + BODY.len()..len], TRAILER);
+
+    // a stream is split into the segments in order, short once drained
+    assert_eq!(sys_write(write_fd, BODY), Some(BODY.len()));
+    let (mut first, mut second, mut third) = ([0u8; 4], [0u8; 10], [0u8; 8]);
+    let read = sys_readv(read_fd, &mut [&mut first, &mut second, &mut third]);
+    assert_eq!(read, Some(BODY.len()));
+    assert_eq!(&first, &BODY[..4]);
+    assert_eq!(&second, &BODY[4..14]);
+    assert_eq!(&third[..2], &BODY[14..]);
+    assert_eq!(sys_readv(read_fd, &mut [&mut first]), Some(0));
+
+    // every segment must be mapped
+    let bad = unsafe { core::slice::from_raw_parts(0x1000 as *const u8, 16) };
+    assert_eq!(sys_writev(write_fd, &[HEADER, bad]), None);
+    assert_eq!(sys_read(read_fd, &mut buf), Some(0), "A bad writev wrote something");
+    let segments = [HEADER; IOV_MAX + 1];
+    assert_eq!(sys_writev(write_fd, &segments), None);
+    assert_eq!(sys_writev(u8::MAX, &[HEADER]), None);
+
+    sys_close_file(read_fd);
+    sys_close_file(write_fd);
+
+    println!("Readv & writev test passed!");
+
+    0
+}
+
+entry!(main);
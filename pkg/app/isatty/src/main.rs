@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const FILE_PATH: &str = "/KERNEL.ELF";
+
+fn main() -> isize {
+    // the standard fds are the console
+    assert!(sys_isatty(0));
+    assert!(sys_isatty(1));
+    assert!(sys_isatty(2));
+
+    let fd = sys_open_file(FILE_PATH);
+    assert!(!sys_isatty(fd), "{} is not a tty", FILE_PATH);
+    sys_close_file(fd);
+
+    let (reader, writer) = sys_pipe().expect("Failed to create a pipe");
+    assert!(!sys_isatty(reader));
+    assert!(!sys_isatty(writer));
+    sys_close_file(reader);
+    sys_close_file(writer);
+
+    // closed or never opened fds
+    assert!(!sys_isatty(fd));
+    assert!(!sys_isatty(200));
+
+    println!("Isatty test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The children killed while they fork, under the fork rate of each process
+const ROUNDS: usize = 24;
+const GRANDCHILDREN: usize = 4;
+const KILLED_EXIT_CODE: isize = -9;
+
+fn main() -> isize {
+    let mut killed = 0;
+    for round in 0..ROUNDS {
+        let child = sys_fork();
+        if child == 0 {
+            // fork & reap until killed, so a kill may land in the middle
+            for _ in 0..GRANDCHILDREN {
+                let grandchild = sys_fork();
+                if grandchild == 0 {
+                    sys_yield();
+                    sys_exit(0);
+                }
+                if grandchild != FORK_FAILED {
+                    sys_wait_pid(grandchild);
+                }
+            }
+            sys_exit(0);
+        }
+        assert_ne!(child, FORK_FAILED, "Fork failed in round {}", round);
+
+        // kill after a varying number of switches, sometimes before it runs
+        for _ in 0..round % 4 {
+            sys_yield();
+        }
+        if sys_kill(child) {
+            killed += 1;
+            assert_eq!(sys_wait_pid(child), KILLED_EXIT_CODE);
+        } else {
+            assert_eq!(sys_wait_pid(child), 0);
+        }
+        // a second kill of the dead child fails
+        assert!(!sys_kill(child));
+    }
+
+    println!("Killed {} of {} children while forking", killed, ROUNDS);
+    println!("Kill fork test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const EXIT_CODE: isize = -42;
+// child -> two grandchildren -> one great-grandchild
+const TREE_SIZE: usize = 4;
+
+/// Report the pid to the root, then run until killed
+fn report_and_spin(write_fd: u8) -> ! {
+    sys_write(write_fd, &sys_get_pid().to_le_bytes());
+    loop {
+        sys_yield();
+    }
+}
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+
+    if sys_fork() == 0 {
+        for level in 0..2 {
+            if sys_fork() == 0 {
+                if level == 0 && sys_fork() == 0 {
+                    report_and_spin(write_fd);
+                }
+                report_and_spin(write_fd);
+            }
+        }
+        report_and_spin(write_fd);
+    }
+
+    // wait until the whole tree is running
+    let mut buf = [0u8; TREE_SIZE * 2];
+    let mut len = 0;
+    while len < buf.len() {
+        len += sys_read(read_fd, &mut buf[len..]).unwrap_or(0);
+        sys_yield();
+    }
+
+    assert_eq!(sys_kill_tree(EXIT_CODE), TREE_SIZE);
+
+    for pid in buf.chunks(2) {
+        let pid = u16::from_le_bytes([pid[0], pid[1]]);
+        assert_eq!(sys_wait_pid(pid), EXIT_CODE);
+        println!("Process #{} was killed.", pid);
+    }
+
+    // nothing is left
+    assert_eq!(sys_kill_tree(EXIT_CODE), 0);
+
+    println!("Kill tree test passed!");
+
+    0
+}
+
+entry!(main);
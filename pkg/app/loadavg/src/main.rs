@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+extern crate lib;
+
+use alloc::vec::Vec;
+use lib::*;
+
+const APP_PATH: &str = "/APP/LOADAVG";
+const CHILDREN: usize = 3;
+/// The ticks a child keeps busy for, longer than the parent samples it
+const BUSY_TICKS: u64 = 150;
+/// The ticks for the average to settle
+const SETTLE_TICKS: u64 = 100;
+const ONE: u32 = 1 << LOAD_SHIFT;
+
+fn child() -> isize {
+    let end = sys_uptime() + BUSY_TICKS;
+    while sys_uptime() < end {
+        core::hint::spin_loop();
+    }
+    0
+}
+
+/// Yield for `ticks` clock ticks, then sample the load
+fn settle(ticks: u64) -> u32 {
+    let end = sys_uptime() + ticks;
+    while sys_uptime() < end {
+        sys_yield();
+    }
+    sys_loadavg()
+}
+
+fn show(name: &str, load: u32) {
+    println!(
+        "Load {}: {}.{:02}",
+        name,
+        load >> LOAD_SHIFT,
+        (load & (ONE - 1)) * 100 / ONE
+    );
+}
+
+fn main() -> isize {
+    if sys_get_arg(0).is_some() {
+        return child();
+    }
+
+    // only self is ready from time to time
+    let idle = settle(SETTLE_TICKS);
+    show("idle", idle);
+
+    // the busy children are ready or running all the time
+    let children: Vec<u16> = (0..CHILDREN)
+        .map(|_| sys_posix_spawn(APP_PATH, &["child"], None, None).expect("Failed to spawn"))
+        .collect();
+    let busy = settle(SETTLE_TICKS);
+    show("busy", busy);
+    assert!(busy >= idle + 2 * ONE, "The load does not rise");
+
+    for child in children {
+        assert_eq!(sys_wait_pid(child), 0);
+    }
+    let after = settle(SETTLE_TICKS);
+    show("after", after);
+    assert!(after + ONE <= busy, "The load does not fall");
+    assert!(after <= idle + ONE / 2, "The load does not settle");
+
+    println!("Load average test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,26 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    // the app is spawned by the shell, which is the init process
+    assert!(!sys_set_log_level(2), "Only init may set the log level");
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(sys_set_log_level(5) as isize);
+    }
+    assert_eq!(sys_wait_pid(child), 0);
+
+    // out of range levels are rejected as well
+    assert!(!sys_set_log_level(6));
+
+    println!("Log level test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const WRITE_COUNT: u64 = 1000;
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create a pipe");
+    let mut buf = [0u8; 8];
+
+    // every write & read finds the caller on the processor, only the switches
+    // & the other processes woken meanwhile look up the process table
+    let switches = sys_context_switches();
+    let before = sys_table_lookups();
+    for i in 0..WRITE_COUNT {
+        let byte = [i as u8];
+        assert_eq!(sys_write(write_fd, &byte), Some(1));
+        assert_eq!(sys_read(read_fd, &mut buf), Some(1));
+        assert_eq!(buf[0], i as u8);
+    }
+    let lookups = sys_table_lookups() - before;
+    let switches = sys_context_switches() - switches;
+
+    println!(
+        "{} writes & reads: {} lookups, {} switches",
+        WRITE_COUNT, lookups, switches
+    );
+    assert!(
+        lookups < WRITE_COUNT,
+        "The syscalls still look up the current process"
+    );
+
+    sys_close_file(read_fd);
+    sys_close_file(write_fd);
+
+    println!("Lookups test passed!");
+
+    0
+}
+
+entry!(main);
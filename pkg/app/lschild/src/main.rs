@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const CHILDREN: usize = 3;
+
+static DONE: AtomicBool = AtomicBool::new(false);
+
+fn main() -> isize {
+    assert!(sys_list_children(0).is_empty());
+
+    let mut pids = [0u16; CHILDREN];
+    for pid in pids.iter_mut() {
+        *pid = sys_fork();
+        if *pid == 0 {
+            while !DONE.load(Ordering::SeqCst) {
+                sys_yield();
+            }
+            sys_exit(0);
+        }
+    }
+
+    let children = sys_list_children(0);
+    println!("Children of #{}: {:?}", sys_get_pid(), children);
+    assert_eq!(children.len(), CHILDREN);
+    for pid in pids.iter() {
+        assert!(children.contains(pid), "#{} is missing", pid);
+        assert!(sys_list_children(*pid).is_empty());
+    }
+    assert_eq!(sys_list_children(sys_get_pid()), children);
+
+    DONE.store(true, Ordering::SeqCst);
+    for pid in pids.iter() {
+        assert_eq!(sys_wait_pid(*pid), 0);
+    }
+    // the exited ones are not listed
+    assert!(sys_list_children(0).is_empty());
+    assert!(sys_list_children(u16::MAX - 1).is_empty());
+
+    println!("List children test passed!");
+
+    0
+}
+
+entry!(main);
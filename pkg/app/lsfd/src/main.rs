@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The fds checked, the exit code is the bitmask of the opened ones
+const MAX_FD: u8 = 16;
+
+fn main() -> isize {
+    let mut opened = 0;
+    for fd in 0..MAX_FD {
+        if let Some(stat) = sys_fstat(fd) {
+            println!("fd {}: {:?}, {} bytes", fd, stat.kind, stat.size);
+            opened |= 1 << fd;
+        }
+    }
+
+    opened
+}
+
+entry!(main);
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 0x1000;
+const PAGES: usize = 8;
+
+fn main() -> isize {
+    // a page aligned region in the middle of the heap
+    let heap_end = sys_brk(None).unwrap();
+    let start = (heap_end + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    let new_end = start + PAGE_SIZE * (PAGES + 1);
+    assert_eq!(sys_brk(Some(new_end)), Some(new_end), "Failed to grow");
+
+    let len = PAGE_SIZE * PAGES;
+    let region = unsafe { core::slice::from_raw_parts_mut(start as *mut u8, len) };
+    region.fill(0x5A);
+    // the page after the region keeps its data
+    let tail = unsafe { &mut *((start + len) as *mut u8) };
+    *tail = 0xA5;
+
+    let before = sys_frame_stats().recycled;
+    assert!(sys_madvise_dontneed(start as *mut u8, len));
+    let after = sys_frame_stats().recycled;
+    println!("Recycled frames: {} -> {}", before, after);
+    assert!(after >= before + PAGES, "The frames are not released");
+    assert_eq!(sys_brk(None), Some(new_end), "The heap end is changed");
+
+    // the pages come back zeroed, and are usable again
+    assert!(region.iter().all(|b| *b == 0), "The released pages keep the old data");
+    assert_eq!(*tail, 0xA5);
+    region.fill(0x3C);
+    assert!(region.iter().all(|b| *b == 0x3C));
+
+    // a released page can be released again, and shrunk away
+    assert!(sys_madvise_dontneed(start as *mut u8, PAGE_SIZE));
+
+    // bad ranges
+    assert!(!sys_madvise_dontneed((start + 1) as *mut u8, PAGE_SIZE));
+    assert!(!sys_madvise_dontneed(start as *mut u8, 0));
+    assert!(!sys_madvise_dontneed(new_end as *mut u8, PAGE_SIZE));
+    assert!(!sys_madvise_dontneed(start as *mut u8, new_end - start + 1));
+
+    assert_eq!(sys_brk(Some(heap_end)), Some(heap_end));
+
+    println!("Madvise test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{compiler_fence, AtomicBool, AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const ROUNDS: usize = 32;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const UNSEEN: AtomicBool = AtomicBool::new(false);
+
+/// The last round each side has stored its flag for
+static FAST: AtomicUsize = AtomicUsize::new(0);
+static SLOW: AtomicUsize = AtomicUsize::new(0);
+/// The last round each side has finished
+static FAST_DONE: AtomicUsize = AtomicUsize::new(0);
+static SLOW_DONE: AtomicUsize = AtomicUsize::new(0);
+/// Whether each side saw the flag of the other in the round
+static FAST_SAW: [AtomicBool; ROUNDS] = [UNSEEN; ROUNDS];
+static SLOW_SAW: [AtomicBool; ROUNDS] = [UNSEEN; ROUNDS];
+
+fn wait_for(done: &AtomicUsize, round: usize) {
+    while done.load(Ordering::Acquire) < round {
+        sys_yield();
+    }
+}
+
+/// Store, then load the other flag, with no fence but the compiler's,
+/// the barrier of the slow side orders it against the other CPUs
+fn fast_side() {
+    for round in 1..=ROUNDS {
+        wait_for(&SLOW_DONE, round - 1);
+        FAST.store(round, Ordering::Relaxed);
+        compiler_fence(Ordering::SeqCst);
+        if round % 3 == 0 {
+            sys_yield();
+        }
+        let saw = SLOW.load(Ordering::Relaxed) >= round;
+        FAST_SAW[round - 1].store(saw, Ordering::Relaxed);
+        FAST_DONE.store(round, Ordering::Release);
+    }
+}
+
+fn slow_side() {
+    for round in 1..=ROUNDS {
+        wait_for(&FAST_DONE, round - 1);
+        SLOW.store(round, Ordering::Relaxed);
+        sys_membarrier();
+        if round % 2 == 0 {
+            sys_yield();
+        }
+        let saw = FAST.load(Ordering::Relaxed) >= round;
+        SLOW_SAW[round - 1].store(saw, Ordering::Relaxed);
+        SLOW_DONE.store(round, Ordering::Release);
+    }
+}
+
+fn main() -> isize {
+    // the forked child shares the memory, so the flags as well
+    let child = sys_fork();
+    if child == 0 {
+        fast_side();
+        sys_exit(0);
+    }
+
+    slow_side();
+    assert_eq!(sys_wait_pid(child), 0);
+
+    // with the barrier, at least one side sees the other in every round,
+    // only meaningful once the two may run on different CPUs
+    for round in 0..ROUNDS {
+        let fast = FAST_SAW[round].load(Ordering::Relaxed);
+        let slow = SLOW_SAW[round].load(Ordering::Relaxed);
+        assert!(fast || slow, "Both sides missed the other in round {}", round + 1);
+    }
+
+    println!("MemBarrier test passed!");
+
+    0
+}
+
+entry!(main);
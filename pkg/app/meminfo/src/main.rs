@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGES: usize = 256;
+/// The page tables created for the new heap pages
+const TABLE_SLACK: usize = 8;
+
+fn main() -> isize {
+    let before = sys_meminfo();
+    let page_size = before.page_size;
+    println!(
+        "Memory: {} KiB used, {} KiB free, {} frames of {} bytes",
+        before.used_bytes() / 1024,
+        before.free_bytes() / 1024,
+        before.total,
+        page_size
+    );
+    assert_eq!(page_size, 4096);
+    assert_eq!(
+        before.used_bytes() + before.free_bytes(),
+        before.total * page_size
+    );
+
+    // brk maps the heap pages at once
+    let heap_end = sys_brk(None).unwrap();
+    let new_end = heap_end + PAGES * page_size;
+    assert_eq!(sys_brk(Some(new_end)), Some(new_end));
+
+    let after = sys_meminfo();
+    let grown = (after.used_bytes() - before.used_bytes()) / page_size;
+    println!("Heap grew by {} pages, {} frames taken", PAGES, grown);
+    assert!(
+        (PAGES..=PAGES + TABLE_SLACK).contains(&grown),
+        "Expected about {} more frames in use",
+        PAGES
+    );
+    assert_eq!(after.total, before.total);
+
+    // shrinking gives the frames back
+    assert_eq!(sys_brk(Some(heap_end)), Some(heap_end));
+    let shrunk = sys_meminfo();
+    assert!(shrunk.used_bytes() <= before.used_bytes() + TABLE_SLACK * page_size);
+
+    println!("Meminfo test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 0x1000;
+const HEAP_START: usize = 0x2000_0000_0000;
+/// Heap, stack & mappings together, the stack takes a few pages
+const LIMIT: usize = 16 * PAGE_SIZE;
+
+fn main() -> isize {
+    let heap_start = sys_brk(None).unwrap();
+    println!("Limit the memory to {} bytes", LIMIT);
+    sys_set_mem_limit(LIMIT);
+
+    // grow the heap page by page until the limit is reached
+    let mut heap_end = heap_start;
+    while let Some(end) = sys_brk(Some(heap_end + PAGE_SIZE)) {
+        heap_end = end;
+    }
+
+    // the failed growth leaves the heap as it was
+    assert_eq!(sys_brk(None), Some(heap_end));
+    assert_eq!(sys_mmap(0, 0, PAGE_SIZE, MAP_PRIVATE | MAP_ANONYMOUS), None);
+
+    // shrinking is always fine, and leaves room for the stack to grow
+    assert_eq!(sys_brk(Some(heap_end - 2 * PAGE_SIZE)), Some(heap_end - 2 * PAGE_SIZE));
+
+    let pages = (heap_end - heap_start) / PAGE_SIZE;
+    println!("Heap stopped after {} pages", pages);
+    assert!(pages > 0, "The heap should grow below the limit");
+    assert!(heap_end - HEAP_START <= LIMIT, "The heap exceeds the limit");
+
+    // no limit
+    sys_set_mem_limit(0);
+    let end = heap_end + LIMIT;
+    assert_eq!(sys_brk(Some(end)), Some(end));
+
+    sys_brk(Some(heap_start)).expect("Failed to clean up the heap");
+
+    println!("Memory limit test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 0x1000;
+const PAGES: usize = 8;
+const LOCKED_PAGES: usize = 4;
+
+fn main() -> isize {
+    // a page aligned region in the middle of the heap
+    let heap_end = sys_brk(None).unwrap();
+    let start = (heap_end + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    let new_end = start + PAGE_SIZE * (PAGES + 1);
+    assert_eq!(sys_brk(Some(new_end)), Some(new_end), "Failed to grow");
+
+    let len = PAGE_SIZE * PAGES;
+    let region = unsafe { core::slice::from_raw_parts_mut(start as *mut u8, len) };
+    region.fill(0x5A);
+
+    // lock the first half, then drop the whole region
+    let locked_len = PAGE_SIZE * LOCKED_PAGES;
+    assert!(sys_mlock(start as *mut u8, locked_len));
+    let frames: [u64; LOCKED_PAGES] = core::array::from_fn(|i| {
+        sys_virt_to_phys(start + i * PAGE_SIZE).expect("The page is not mapped").phys
+    });
+
+    let before = sys_frame_stats().recycled;
+    assert!(sys_madvise_dontneed(start as *mut u8, len));
+    let after = sys_frame_stats().recycled;
+    println!("Recycled frames: {} -> {}", before, after);
+    assert!(after >= before + PAGES - LOCKED_PAGES, "The unlocked frames are not released");
+
+    // the locked pages keep their frames and data, the others are gone
+    for (i, frame) in frames.iter().enumerate() {
+        let mapping = sys_virt_to_phys(start + i * PAGE_SIZE);
+        assert_eq!(mapping.map(|m| m.phys), Some(*frame), "Locked page {} is freed", i);
+    }
+    for i in LOCKED_PAGES..PAGES {
+        assert_eq!(sys_virt_to_phys(start + i * PAGE_SIZE), None);
+    }
+    assert!(region[..locked_len].iter().all(|b| *b == 0x5A));
+    assert!(region[locked_len..].iter().all(|b| *b == 0));
+
+    // unlocked pages are released again
+    assert!(sys_munlock(start as *mut u8, locked_len));
+    assert!(sys_madvise_dontneed(start as *mut u8, locked_len));
+    assert_eq!(sys_virt_to_phys(start), None);
+    assert!(region[..locked_len].iter().all(|b| *b == 0));
+
+    // a mapping can be locked as well, within its range
+    let mapped_len = PAGE_SIZE * 2;
+    let addr = sys_mmap(0, 0, mapped_len, MAP_PRIVATE | MAP_ANONYMOUS | MAP_WRITE)
+        .expect("Failed to map");
+    assert!(sys_mlock(addr as *mut u8, mapped_len));
+    assert!(!sys_mlock(addr as *mut u8, mapped_len + 1));
+    assert!(sys_munlock(addr as *mut u8, mapped_len));
+    assert!(sys_munmap(addr));
+    assert!(!sys_mlock(addr as *mut u8, PAGE_SIZE));
+
+    // bad ranges
+    assert!(!sys_mlock((start + 1) as *mut u8, PAGE_SIZE));
+    assert!(!sys_mlock(start as *mut u8, 0));
+    assert!(!sys_mlock(new_end as *mut u8, PAGE_SIZE));
+    assert!(!sys_mlock(start as *mut u8, new_end - start + 1));
+
+    assert_eq!(sys_brk(Some(heap_end)), Some(heap_end));
+
+    println!("Mlock test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,87 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, extended by a page for the shared mapping
+/// and restored to its length at the end
+const FILE_PATH: &str = "/APP/MMAP";
+const PAGE_SIZE: usize = 4096;
+const PATTERN: &[u8] = b"written through the mapping";
+
+fn read_exact(fd: u8, offset: usize, buf: &mut [u8]) {
+    assert_eq!(sys_seek(fd, offset as isize, SEEK_SET), offset as isize);
+    let mut read = 0;
+    while read < buf.len() {
+        match sys_read(fd, &mut buf[read..]) {
+            Some(len) if len > 0 => read += len,
+            _ => break,
+        }
+    }
+    assert_eq!(read, buf.len());
+}
+
+fn main() -> isize {
+    let fd = sys_open(FILE_PATH, O_RDWR).expect("Failed to open the file");
+    let length = sys_fstat(fd).expect("Failed to stat the file").size as usize;
+    println!("{} has {} bytes", FILE_PATH, length);
+
+    // a private mapping reads the same bytes as the file, page by page
+    let addr = sys_mmap(fd, 0, length, MAP_PRIVATE | MAP_WRITE).expect("Failed to map");
+    let mapped = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, length) };
+
+    let (faults, _) = sys_page_faults();
+    assert_eq!(&mapped[..4], b"\x7fELF");
+    assert_eq!(sys_page_faults().0, faults + 1);
+
+    let middle = length / 2;
+    let mut expected = [0u8; 64];
+    read_exact(fd, middle, &mut expected);
+    assert_eq!(&mapped[middle..middle + 64], &expected);
+
+    // private writes never reach the file
+    mapped[0] = 0;
+    let mut head = [0u8; 4];
+    read_exact(fd, 0, &mut head);
+    assert_eq!(&head, b"\x7fELF");
+    assert!(sys_munmap(addr));
+
+    // a shared writable mapping is written back on munmap
+    let offset = (length + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    assert!(sys_ftruncate(fd, offset + PAGE_SIZE));
+    let addr = sys_mmap(fd, offset, PAGE_SIZE, MAP_SHARED | MAP_WRITE).expect("Failed to map");
+    let mapped = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, PAGE_SIZE) };
+    assert!(mapped.iter().all(|b| *b == 0), "The extended part is not zeroed");
+    mapped[..PATTERN.len()].copy_from_slice(PATTERN);
+    assert!(sys_munmap(addr));
+    assert!(!sys_munmap(addr));
+
+    let mut buf = [0u8; PATTERN.len()];
+    read_exact(fd, offset, &mut buf);
+    assert_eq!(&buf, PATTERN);
+    assert!(sys_ftruncate(fd, length));
+
+    // anonymous memory is zero-filled
+    let addr = sys_mmap(0, 0, PAGE_SIZE * 2, MAP_PRIVATE | MAP_ANONYMOUS | MAP_WRITE)
+        .expect("Failed to map");
+    let mapped = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, PAGE_SIZE * 2) };
+    assert!(mapped.iter().all(|b| *b == 0));
+    mapped.fill(0x5A);
+    assert!(sys_munmap(addr));
+
+    // bad arguments
+    assert_eq!(sys_mmap(fd, 1, PAGE_SIZE, MAP_PRIVATE), None);
+    assert_eq!(sys_mmap(fd, 0, 0, MAP_PRIVATE), None);
+    assert_eq!(sys_mmap(fd, 0, PAGE_SIZE, MAP_SHARED | MAP_PRIVATE), None);
+    assert_eq!(sys_mmap(1, 0, PAGE_SIZE, MAP_PRIVATE), None);
+
+    sys_close_file(fd);
+
+    println!("Mmap test passed!");
+
+    0
+}
+
+entry!(main);
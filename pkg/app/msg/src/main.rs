@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const KEY: u32 = 0x2333;
+const MESSAGE_COUNT: usize = 32;
+const MSG_MAX_SIZE: usize = 256;
+
+fn message(i: usize) -> string::String {
+    // messages of different lengths to check the boundaries
+    format!("message #{} {}", i, "*".repeat(i))
+}
+
+fn main() -> isize {
+    assert!(sys_msg_get(KEY));
+
+    // oversized messages are rejected
+    let huge = [0u8; MSG_MAX_SIZE + 1];
+    assert!(sys_msg_send(KEY, &huge, 0) < 0);
+
+    let pid = sys_fork();
+    if pid == 0 {
+        // producer, blocks when the queue is full
+        for i in 0..MESSAGE_COUNT {
+            let msg = message(i);
+            assert_eq!(sys_msg_send(KEY, msg.as_bytes(), 0), msg.len() as isize);
+        }
+        sys_exit(0);
+    }
+
+    // consumer, blocks when the queue is empty
+    let mut buf = [0u8; MSG_MAX_SIZE];
+    for i in 0..MESSAGE_COUNT {
+        let len = sys_msg_recv(KEY, &mut buf);
+        assert!(len > 0, "Failed to receive message #{}", i);
+        let msg = core::str::from_utf8(&buf[..len as usize]).unwrap();
+        assert_eq!(msg, message(i));
+    }
+    assert_eq!(sys_wait_pid(pid), 0);
+
+    // a full queue fails at once without waiting
+    let mut sent = 0;
+    while sys_msg_send(KEY, b"fill", MSG_NOWAIT) > 0 {
+        sent += 1;
+    }
+    println!("Queue is full after {} messages", sent);
+    for _ in 0..sent {
+        assert_eq!(sys_msg_recv(KEY, &mut buf), 4);
+    }
+
+    println!("Message queue test passed!");
+
+    0
+}
+
+entry!(main);
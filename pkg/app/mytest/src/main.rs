@@ -9,9 +9,9 @@ fn main() -> isize {
     let pid = sys_fork();
 
     if pid == 0 {
-        sys_wait_pid(sys_spawn("app/sem"));
+        sys_wait_pid(sys_spawn("app/sem").expect("Failed to spawn sem"));
     } else {
-        sys_wait_pid(sys_spawn("app/spin"));
+        sys_wait_pid(sys_spawn("app/spin").expect("Failed to spawn spin"));
         sys_wait_pid(pid);
     }
 
@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// (duration in ns, description)
+const CASES: [(u64, &str); 4] = [
+    (1_000, "1 us"),
+    (100_000, "100 us"),
+    (5_000_000, "5 ms"),
+    (200_000_000, "200 ms"),
+];
+
+fn main() -> isize {
+    let freq = sys_tsc_frequency();
+    assert!(freq > 0, "TSC is not calibrated");
+    println!("TSC frequency: {} MHz", freq / 1_000_000);
+
+    for (ns, desc) in CASES {
+        let start = rdtsc();
+        let ticks = sys_uptime();
+        assert!(sys_nanosleep(ns));
+        let elapsed = rdtsc() - start;
+        let elapsed_ns = (elapsed as u128 * NANOS_PER_SEC as u128 / freq as u128) as u64;
+
+        println!(
+            "Sleep {}: {} ns elapsed, {} ticks",
+            desc,
+            elapsed_ns,
+            sys_uptime() - ticks
+        );
+        assert!(
+            elapsed_ns >= ns,
+            "Slept {} ns, less than {} ns",
+            elapsed_ns,
+            ns
+        );
+    }
+
+    println!("NanoSleep test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create a pipe");
+    let mut buf = [0u8; 8];
+
+    // an empty pipe reads 0 by default, or fails once non-blocking
+    assert_eq!(sys_fcntl(read_fd, F_GETFL, 0), Ok(0));
+    assert_eq!(sys_read_result(read_fd, &mut buf), Ok(0));
+    assert!(sys_set_nonblocking(read_fd, true));
+    assert_eq!(sys_fcntl(read_fd, F_GETFL, 0), Ok(O_NONBLOCK));
+    assert_eq!(sys_read_result(read_fd, &mut buf), Err(SysError::Again));
+    assert_eq!(sys_try_read(read_fd, &mut buf), SysError::Again.code());
+
+    // the buffered data is read as usual
+    assert_eq!(sys_write(write_fd, b"ysos"), Some(4));
+    assert_eq!(sys_read_result(read_fd, &mut buf), Ok(4));
+    assert_eq!(&buf[..4], b"ysos");
+    assert_eq!(sys_read_result(read_fd, &mut buf), Err(SysError::Again));
+
+    // a dup starts blocking, and the flag is cleared again on request
+    assert_eq!(sys_dup2(read_fd, 10), 10);
+    assert_eq!(sys_fcntl(10, F_GETFL, 0), Ok(0));
+    assert!(sys_close_file(10));
+    assert!(sys_set_nonblocking(read_fd, false));
+    assert_eq!(sys_read_result(read_fd, &mut buf), Ok(0));
+
+    // the end of the pipe still reads 0
+    assert!(sys_set_nonblocking(read_fd, true));
+    assert!(sys_close_file(write_fd));
+    assert_eq!(sys_read_result(read_fd, &mut buf), Ok(0));
+    assert!(sys_close_file(read_fd));
+
+    // bad fds & commands
+    assert_eq!(sys_fcntl(read_fd, F_GETFL, 0), Err(SysError::BadFd));
+    assert!(!sys_set_nonblocking(read_fd, true));
+    assert_eq!(sys_fcntl(1, 0, 0), Err(SysError::Inval));
+
+    println!("Nonblock test passed!");
+
+    0
+}
+
+entry!(main);
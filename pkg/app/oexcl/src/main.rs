@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const FILE_PATH: &str = "/APP/EXCL.LCK";
+const CONTENT: &[u8] = b"locked";
+
+fn main() -> isize {
+    // left by an earlier run
+    sys_unlink(FILE_PATH);
+
+    let fd = sys_open_raw(FILE_PATH, O_CREAT | O_EXCL | O_RDWR);
+    assert!(fd >= 0, "Failed to create the file: {}", fd);
+    let fd = fd as u8;
+    assert_eq!(sys_write(fd, CONTENT), Some(CONTENT.len()));
+    sys_close_file(fd);
+
+    // the second exclusive create fails, the file is kept as it is
+    assert_eq!(sys_open_raw(FILE_PATH, O_CREAT | O_EXCL | O_RDWR), EEXIST);
+    assert_eq!(sys_open_raw(FILE_PATH, O_CREAT | O_EXCL), EEXIST);
+
+    // without `O_EXCL` the existing file is opened
+    let fd = sys_open(FILE_PATH, O_CREAT | O_RDWR).expect("Failed to open the file");
+    assert_eq!(sys_fstat(fd).unwrap().size as usize, CONTENT.len());
+    let mut buf = [0u8; CONTENT.len()];
+    assert_eq!(sys_read(fd, &mut buf), Some(CONTENT.len()));
+    assert_eq!(&buf, CONTENT);
+    sys_close_file(fd);
+
+    assert_eq!(sys_unlink(FILE_PATH), 0);
+    assert_eq!(sys_open_raw(FILE_PATH, O_RDWR), ENOENT);
+
+    println!("Exclusive create test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, trailing bytes are ignored by the ELF loader
+const FILE_PATH: &str = "/APP/OPENDEF";
+const MARKER: &[u8] = b"appended by default";
+
+fn main() -> isize {
+    sys_set_open_defaults(O_APPEND);
+
+    // no flags given, but the default append applies
+    let fd = sys_open_file(FILE_PATH);
+    let length = sys_seek(fd, 0, SEEK_END);
+    assert!(length > 0, "Failed to seek to the end of {}", FILE_PATH);
+
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+    assert_eq!(sys_write(fd, MARKER), Some(MARKER.len()));
+    assert_eq!(sys_seek(fd, 0, SEEK_END), length + MARKER.len() as isize);
+
+    // the head of the binary is untouched
+    let mut head = [0u8; 4];
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+    assert_eq!(sys_read(fd, &mut head), Some(4));
+    assert_eq!(&head, b"\x7fELF");
+
+    sys_close_file(fd);
+    sys_set_open_defaults(0);
+
+    println!("Open defaults test passed!");
+
+    0
+}
+
+entry!(main);
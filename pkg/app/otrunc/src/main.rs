@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, written back from a private mapping at the end
+const FILE_PATH: &str = "/APP/OTRUNC";
+const PAGE_SIZE: usize = 4096;
+
+fn file_size(fd: u8) -> usize {
+    sys_fstat(fd).expect("Failed to stat the file").size as usize
+}
+
+fn main() -> isize {
+    let fd = sys_open(FILE_PATH, O_RDWR).expect("Failed to open the file");
+    let length = file_size(fd);
+    println!("{} has {} bytes", FILE_PATH, length);
+
+    // keep a copy in the pages of a private mapping, filled on the first touch
+    let addr = sys_mmap(fd, 0, length, MAP_PRIVATE).expect("Failed to map");
+    let copy = unsafe { core::slice::from_raw_parts(addr as *const u8, length) };
+    let sum = copy
+        .iter()
+        .step_by(PAGE_SIZE)
+        .fold(0usize, |sum, b| sum + *b as usize);
+    assert_ne!(sum, 0);
+
+    // without the write access the flag is ignored
+    let other = sys_open(FILE_PATH, O_TRUNC).expect("Failed to open the file");
+    assert_eq!(file_size(other), length);
+    sys_close_file(other);
+
+    let other = sys_open(FILE_PATH, O_WRONLY | O_TRUNC).expect("Failed to open the file");
+    assert_eq!(file_size(other), 0);
+    // the other fds see the same file
+    assert_eq!(file_size(fd), 0);
+
+    // write the content back
+    let mut written = 0;
+    while written < length {
+        match sys_write(other, &copy[written..]) {
+            Some(len) if len > 0 => written += len,
+            _ => panic!("Failed to restore the file"),
+        }
+    }
+    sys_close_file(other);
+    assert_eq!(file_size(fd), length);
+
+    assert!(sys_munmap(addr));
+    sys_close_file(fd);
+
+    println!("Open truncate test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+/// The child exits with it once it has seen the parent paused
+const SEEN_PAUSED: isize = 1;
+const TRIES: usize = 20;
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+fn on_child_exit(_pid: u16) {
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Wait for the parent to pause, then signal it by exiting
+fn child(parent: u16) -> ! {
+    for _ in 0..TRIES {
+        if sys_block_reason(parent) == Some(BlockReason::Paused) {
+            sys_exit(SEEN_PAUSED);
+        }
+        sys_yield();
+    }
+    sys_exit(0)
+}
+
+fn main() -> isize {
+    assert!(sys_sigchld(Some(on_child_exit)));
+    let parent = sys_get_pid();
+
+    let child_pid = sys_fork();
+    if child_pid == 0 {
+        child(parent);
+    }
+    assert_ne!(child_pid, FORK_FAILED);
+
+    // returns once the handler has run for the exit
+    sys_pause();
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1, "The handler did not run");
+    assert_eq!(sys_wait_pid(child_pid), SEEN_PAUSED, "The parent is not paused");
+    for _ in 0..TRIES {
+        sys_yield();
+    }
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1, "The handler ran twice");
+    assert!(sys_sigchld(None));
+
+    println!("Pause test passed!");
+
+    0
+}
+
+entry!(main);
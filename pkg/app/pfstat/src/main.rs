@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const DEPTH: usize = 64;
+const FRAME_SIZE: usize = 1024;
+
+// every level takes about one KiB of the stack
+fn recurse(depth: usize) -> usize {
+    let frame = core::hint::black_box([depth as u8; FRAME_SIZE]);
+    if depth == 0 {
+        return frame[0] as usize;
+    }
+    recurse(depth - 1) + frame[FRAME_SIZE - 1] as usize
+}
+
+fn main() -> isize {
+    let (total, stack) = sys_page_faults();
+    println!("Before: {} page faults, {} stack growth", total, stack);
+    assert!(stack <= total);
+
+    let sum = recurse(DEPTH);
+    assert_eq!(sum, (0..=DEPTH).sum());
+
+    let (new_total, new_stack) = sys_page_faults();
+    println!("After: {} page faults, {} stack growth", new_total, new_stack);
+
+    assert!(new_stack > stack, "The stack did not grow on demand");
+    // every stack growth is counted as a page fault as well
+    assert!(new_total - total >= new_stack - stack);
+    assert!(new_stack <= new_total);
+
+    sys_print_info(sys_get_pid());
+
+    println!("Page fault statistics test passed!");
+
+    0
+}
+
+entry!(main);
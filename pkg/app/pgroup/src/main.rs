@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const GROUP_SIZE: usize = 3;
+// the exit code of a killed process
+const KILLED: isize = -9;
+
+fn main() -> isize {
+    let mut pids = [0u16; GROUP_SIZE];
+
+    for pid in pids.iter_mut() {
+        *pid = sys_fork();
+        if *pid == 0 {
+            // wait here until killed
+            loop {
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    // the first child leads the group
+    let pgid = pids[0];
+    for pid in pids {
+        assert!(sys_setpgid(pid, pgid), "Failed to set pgid of #{}", pid);
+    }
+
+    let killed = sys_killpg(pgid);
+    println!("Killed {} processes in group #{}", killed, pgid);
+    assert_eq!(killed, GROUP_SIZE as isize);
+
+    for pid in pids {
+        assert_eq!(sys_wait_pid(pid), KILLED);
+    }
+
+    // the whole group is dead now
+    assert_eq!(sys_killpg(pgid), 0);
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const CHILD_COUNT: usize = 4;
+
+fn main() -> isize {
+    let parent = sys_get_pid();
+    let mut children = [0u16; CHILD_COUNT];
+
+    for child in children.iter_mut() {
+        *child = sys_fork();
+        if *child == 0 {
+            // spin until preempted, the current pid must survive the switches
+            for i in 0..0x100000 {
+                core::hint::black_box(i);
+            }
+            sys_exit(sys_get_pid() as isize);
+        }
+    }
+
+    for child in children {
+        // every child must see its own pid from the per-CPU slot
+        assert_eq!(sys_wait_pid(child), child as isize);
+    }
+
+    assert_eq!(sys_get_pid(), parent);
+    println!("Current pid resolved correctly for {} children.", CHILD_COUNT);
+
+    0
+}
+
+entry!(main);
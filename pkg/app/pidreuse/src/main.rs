@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const APP_PATH: &str = "/APP/PIDREUSE";
+/// The children kept suspended all along, their pids must never be handed out
+const HELD: usize = 4;
+/// The spawns past the pid space, so every pid is reused at least once
+const EXTRA_SPAWNS: usize = 64;
+
+fn main() -> isize {
+    // the children are spawned with an argument and exit at once
+    if sys_get_arg(0).is_some() {
+        return 0;
+    }
+
+    let max_pid = sys_max_pid();
+    println!("Max pid: {}", max_pid);
+    assert!(max_pid < FORK_FAILED, "A pid would read as a failed fork");
+
+    let mut held = [0u16; HELD];
+    for pid in held.iter_mut() {
+        *pid = sys_spawn_suspended(APP_PATH).expect("Failed to spawn");
+    }
+
+    let me = sys_get_pid();
+    let total = max_pid as usize + EXTRA_SPAWNS;
+    let mut last = 0u16;
+    let mut wraps = 0;
+    for round in 0..total {
+        let pid = sys_posix_spawn(APP_PATH, &["child"], None, None).expect("Failed to spawn");
+        assert!(pid > 1 && pid <= max_pid, "Pid {} out of range", pid);
+        assert_ne!(pid, me);
+        assert!(!held.contains(&pid), "Pid {} of a live child is reused", pid);
+        // the pid just reaped is not taken again at once
+        assert_ne!(pid, last, "Pid {} is reused at once", pid);
+        if pid < last {
+            wraps += 1;
+        }
+        assert_eq!(sys_wait_pid(pid), 0);
+        last = pid;
+
+        if round % 8192 == 0 {
+            println!("Spawned {} of {}, last pid {}", round, total, pid);
+        }
+    }
+    assert!(wraps > 0, "No pid is ever reused");
+
+    // they never run, as they would start the test over without an argument
+    for pid in held {
+        assert!(sys_kill(pid));
+        assert_eq!(sys_wait_pid(pid), KILLED_EXIT_CODE);
+    }
+
+    println!("Spawned {} processes, wrapped {} times", total, wraps);
+    println!("PID reuse test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const CHILD_COUNT: usize = 8;
+const ROUNDS: usize = 2000;
+
+fn main() -> isize {
+    let mut children = [0u16; CHILD_COUNT];
+    for child in children.iter_mut() {
+        *child = sys_fork();
+        if *child == 0 {
+            let pid = sys_get_pid();
+            for round in 0..ROUNDS {
+                assert_eq!(sys_get_pid(), pid, "Pid changed in round {}", round);
+                // switch as often as possible
+                if round % 2 == 0 {
+                    sys_yield();
+                }
+            }
+            // report the pid seen by the child to the parent
+            sys_exit(pid as isize);
+        }
+    }
+
+    let pid = sys_get_pid();
+    for _ in 0..ROUNDS {
+        assert_eq!(sys_get_pid(), pid);
+        sys_yield();
+    }
+
+    for child in children {
+        assert_eq!(sys_wait_pid(child), child as isize);
+    }
+
+    println!("Pid stress test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const MESSAGE: &[u8] = b"bye";
+const CHILD_EXIT_CODE: isize = 3;
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+
+    let child = sys_fork();
+    if child == 0 {
+        // the child has its own copy of the fds, closing one here
+        // does not close it for the parent
+        sys_close_file(read_fd);
+        sys_write(write_fd, MESSAGE).expect("Failed to write pipe");
+        sleep(1);
+        // exit with the write end still open
+        sys_exit(CHILD_EXIT_CODE);
+    }
+
+    // only the child holds the write end now
+    sys_close_file(write_fd);
+
+    let mut buf = [0u8; 16];
+    let mut len = 0;
+    loop {
+        if !sys_poll(&[read_fd])[0].readable {
+            sys_yield();
+            continue;
+        }
+        match sys_read(read_fd, &mut buf[len..]).expect("Failed to read pipe") {
+            // readable but empty, all the write ends are closed
+            0 => break,
+            count => len += count,
+        }
+    }
+    println!("Read {:?} before EOF", core::str::from_utf8(&buf[..len]));
+    assert_eq!(&buf[..len], MESSAGE);
+
+    assert_eq!(sys_wait_pid(child), CHILD_EXIT_CODE);
+    sys_close_file(read_fd);
+
+    println!("Pipe EOF test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The output of `produce`
+const EXPECTED: &str = "line 0\nline 1\nline 2\n";
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+
+    let producer =
+        sys_spawn_redirect("/APP/produce", None, Some(write_fd)).expect("Failed to spawn");
+    let consumer =
+        sys_spawn_redirect("/APP/consume", Some(read_fd), None).expect("Failed to spawn");
+
+    // the children hold their own references to the pipe,
+    // the consumer sees EOF once the producer exits
+    sys_close_file(read_fd);
+    sys_close_file(write_fd);
+
+    assert_eq!(sys_wait_pid(producer), 0);
+    assert_eq!(sys_wait_pid(consumer), EXPECTED.len() as isize);
+
+    // fds that are not opened cannot be redirected
+    assert_eq!(sys_spawn_redirect("/APP/produce", None, Some(write_fd)), None);
+
+    println!("Pipeline test passed!");
+
+    0
+}
+
+entry!(main);
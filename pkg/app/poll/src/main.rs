@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    let (read_a, write_a) = sys_pipe().expect("Failed to create pipe");
+    let (read_b, write_b) = sys_pipe().expect("Failed to create pipe");
+
+    sys_write(write_b, b"ping").expect("Failed to write pipe");
+
+    let flags = sys_poll(&[read_a, read_b]);
+    println!("Poll result: {:?}", flags);
+    assert!(!flags[0].readable, "Empty pipe reported readable");
+    assert!(flags[1].readable, "Pipe with data not reported readable");
+
+    // both write ends have free space
+    let flags = sys_poll(&[write_a, write_b]);
+    assert!(flags.iter().all(|f| f.writable));
+
+    // a closed write end makes the read end readable (EOF)
+    sys_close_file(write_a);
+    assert!(sys_poll(&[read_a])[0].readable);
+
+    sys_close_file(read_a);
+    sys_close_file(read_b);
+    sys_close_file(write_b);
+
+    println!("Poll test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    // the name set through prctl is the one seen by everyone else
+    assert_eq!(sys_prctl_set_name("prctl-test"), Some(10));
+    assert_eq!(sys_prctl_get_name(), "prctl-test");
+    assert_eq!(sys_get_proc_name(0).as_deref(), Some("prctl-test"));
+    assert_eq!(sys_prctl_set_name(""), None);
+
+    // no-new-privs is sticky & inherited by a forked child
+    assert!(!sys_prctl_get_no_new_privs());
+    sys_prctl_set_no_new_privs();
+    assert!(sys_prctl_get_no_new_privs());
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(if sys_prctl_get_no_new_privs() { 0 } else { 1 });
+    }
+    assert_eq!(sys_wait_pid(child), 0, "No-new-privs is not inherited");
+
+    // the open defaults read back what was set
+    sys_prctl_set_open_defaults(O_APPEND);
+    assert_eq!(sys_prctl_get_open_defaults(), O_APPEND);
+    sys_prctl_set_open_defaults(0);
+    assert_eq!(sys_prctl_get_open_defaults(), 0);
+
+    println!("Prctl test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const CHILD_PRIORITY: usize = 5;
+const MAX_TRIES: usize = 100;
+
+fn main() -> isize {
+    let child = sys_fork();
+    if child == 0 {
+        // stay ready without blocking until killed
+        loop {
+            core::hint::spin_loop();
+        }
+    }
+
+    // a parent can set any priority for its child
+    assert!(sys_set_priority(child, CHILD_PRIORITY));
+
+    // the caller is running, it has not aged
+    let base = sys_get_priority(0).expect("Failed to get priority");
+    assert!(sys_set_priority(0, base + 2));
+    assert_eq!(sys_get_priority(0), Some(base + 2));
+    // but it cannot raise its own priority
+    assert!(!sys_set_priority(0, base + 1));
+    assert_eq!(sys_get_priority(0), Some(base + 2));
+
+    // the child is passed over each time the parent is scheduled
+    let mut aged = None;
+    for _ in 0..MAX_TRIES {
+        sys_yield();
+        let priority = sys_get_priority(child).expect("Failed to get priority");
+        assert!(priority <= CHILD_PRIORITY);
+        if priority < CHILD_PRIORITY {
+            aged = Some(priority);
+            break;
+        }
+    }
+    let aged = aged.expect("The effective priority of the child never changed");
+    println!("Child priority: base {}, effective {}", CHILD_PRIORITY, aged);
+
+    // processes outside the family cannot be changed
+    assert!(!sys_set_priority(1, 0));
+    assert_eq!(sys_get_priority(u16::MAX), None);
+
+    assert!(sys_kill(child));
+    sys_wait_pid(child);
+
+    println!("Priority test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// Try a privileged call that changes nothing, strace is off by default
+fn try_privileged() -> bool {
+    sys_set_strace(false)
+}
+
+fn main() -> isize {
+    // the app is spawned by the shell, only the shell itself (init) & its forks
+    // are privileged, which the `loglevel` & `strace` commands go through
+    assert!(!try_privileged(), "A spawned app is privileged");
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(try_privileged() as isize);
+    }
+    assert_eq!(sys_wait_pid(child), 0, "The fork of an unprivileged app is privileged");
+
+    // dropping is permanent, for self & the children forked later
+    sys_drop_privilege();
+    sys_drop_privilege();
+    assert!(!try_privileged());
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(try_privileged() as isize);
+    }
+    assert_eq!(sys_wait_pid(child), 0);
+
+    println!("Privilege test passed!");
+
+    0
+}
+
+entry!(main);
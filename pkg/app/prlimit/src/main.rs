@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, opened again and again
+const FILE_PATH: &str = "/APP/PRLIMIT";
+/// The fds kept open past the ones inherited
+const EXTRA_FDS: u64 = 2;
+
+fn main() -> isize {
+    let default = sys_prlimit(0, RLIMIT_NOFILE, None).expect("Failed to get the fd limit");
+    println!("The fd limit: {:?}", default);
+    assert_eq!(default.soft, default.hard);
+
+    // the inherited fds are counted too, probe the open count first
+    let fd = sys_open(FILE_PATH, 0).expect("Failed to open the file");
+    let opened = (0..=u8::MAX).filter(|fd| sys_fstat(*fd).is_some()).count() as u64;
+    assert!(sys_close_file(fd));
+    let low = Limit {
+        soft: opened - 1 + EXTRA_FDS,
+        hard: default.hard,
+    };
+    assert_eq!(sys_prlimit(0, RLIMIT_NOFILE, Some(low)), Some(default));
+    assert_eq!(sys_prlimit(0, RLIMIT_NOFILE, None), Some(low));
+
+    let mut fds = [0u8; EXTRA_FDS as usize];
+    for fd in fds.iter_mut() {
+        *fd = sys_open(FILE_PATH, 0).expect("Failed to open below the limit");
+    }
+    assert_eq!(sys_open(FILE_PATH, 0), None, "Opened past the fd limit");
+
+    // a forked child inherits the limit
+    let child = sys_fork();
+    if child == 0 {
+        let ok = sys_prlimit(0, RLIMIT_NOFILE, None) == Some(low);
+        sys_exit(if ok { 0 } else { 1 });
+    }
+    assert_eq!(sys_wait_pid(child), 0, "The child does not inherit the limit");
+
+    // the soft limit is never over the hard one, which is never raised
+    let over = Limit {
+        soft: default.hard,
+        hard: low.soft,
+    };
+    assert_eq!(sys_prlimit(0, RLIMIT_NOFILE, Some(over)), None);
+    let raised = Limit {
+        soft: low.soft,
+        hard: default.hard + 1,
+    };
+    assert_eq!(sys_prlimit(0, RLIMIT_NOFILE, Some(raised)), None);
+    assert_eq!(sys_prlimit(0, 1, None), None, "The resource is unknown");
+
+    // opens succeed again once the soft limit is restored
+    assert_eq!(sys_prlimit(0, RLIMIT_NOFILE, Some(default)), Some(low));
+    let fd = sys_open(FILE_PATH, 0).expect("Failed to open after raising the limit");
+    assert!(sys_close_file(fd));
+    for fd in fds {
+        assert!(sys_close_file(fd));
+    }
+
+    println!("Prlimit test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const WAIT_TICKS: u64 = 5;
+
+static DONE: AtomicBool = AtomicBool::new(false);
+
+fn wait_ticks(ticks: u64) {
+    let start = sys_uptime();
+    while sys_uptime() < start + ticks {
+        sys_yield();
+    }
+}
+
+fn main() -> isize {
+    // the parent gets older than the child by the ticks before the fork
+    wait_ticks(WAIT_TICKS);
+
+    let child = sys_fork();
+    if child == 0 {
+        while !DONE.load(Ordering::SeqCst) {
+            sys_yield();
+        }
+        sys_exit(0);
+    }
+
+    wait_ticks(WAIT_TICKS);
+
+    let age = sys_proc_age(child).expect("Failed to get the age of the child");
+    let own = sys_proc_age(0).expect("Failed to get the own age");
+    println!("Child #{} age: {} ticks, own age: {} ticks", child, age, own);
+    assert!(age >= WAIT_TICKS, "The age is less than the ticks waited");
+    // the child counts from the fork, not from the parent's creation
+    assert!(own >= age + WAIT_TICKS, "The child inherits the creation time");
+
+    DONE.store(true, Ordering::SeqCst);
+    assert_eq!(sys_wait_pid(child), 0);
+
+    assert_eq!(sys_proc_age(u16::MAX - 1), None);
+
+    println!("Process age test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 0x1000;
+const OLD_VALUE: u64 = 0x1122_3344_5566_7788;
+const CHILD_VALUE: u64 = 0xdead_beef_cafe_f00d;
+const NEW_VALUE: u64 = 0x0bad_c0de_0bad_c0de;
+
+fn main() -> isize {
+    let heap_end = sys_brk(None).unwrap();
+    sys_brk(Some(heap_end + PAGE_SIZE)).expect("Failed to grow the heap");
+    let value = heap_end as *mut u64;
+    unsafe { value.write_volatile(OLD_VALUE) };
+
+    let (rfd, wfd) = sys_pipe().expect("Failed to create the pipe");
+    // the private copy of the heap page is written by the child only
+    let child = sys_fork_cow();
+    if child == 0 {
+        unsafe { value.write_volatile(CHILD_VALUE) };
+        assert_eq!(sys_write(wfd, b"r"), Some(1));
+        while unsafe { value.read_volatile() } != NEW_VALUE {
+            sys_yield();
+        }
+        sys_exit(0);
+    }
+
+    let mut ready = [0u8; 1];
+    while sys_read(rfd, &mut ready) != Some(1) {
+        sys_yield();
+    }
+
+    let path = format!("/proc/{}/mem", child);
+    let fd = sys_open(&path, O_RDWR).expect("Failed to open the memory of the child");
+    let mut buf = [0u8; 8];
+    assert_eq!(sys_seek(fd, heap_end as isize, SEEK_SET), heap_end as isize);
+    assert_eq!(sys_read(fd, &mut buf), Some(8));
+    assert_eq!(u64::from_ne_bytes(buf), CHILD_VALUE);
+    assert_eq!(unsafe { value.read_volatile() }, OLD_VALUE);
+
+    // the write lands in the memory of the child, which exits once it sees it
+    assert_eq!(sys_seek(fd, heap_end as isize, SEEK_SET), heap_end as isize);
+    assert_eq!(sys_write(fd, &NEW_VALUE.to_ne_bytes()), Some(8));
+    assert_eq!(sys_wait_pid(child), 0);
+
+    // nothing to read once the child has exited
+    assert_eq!(sys_seek(fd, heap_end as isize, SEEK_SET), heap_end as isize);
+    assert_eq!(sys_read(fd, &mut buf), Some(0));
+    sys_close_file(fd);
+
+    // only descendants
+    assert_eq!(sys_open(&format!("/proc/{}/mem", sys_get_pid()), O_RDWR), None);
+    assert_eq!(sys_open("/proc/1/mem", O_RDWR), None);
+
+    sys_close_file(rfd);
+    sys_close_file(wfd);
+
+    println!("Proc mem test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const NAME: &str = "worker";
+const LONG_NAME: &str = "a-very-long-process-name";
+
+fn main() -> isize {
+    let child = sys_fork();
+    if child == 0 {
+        assert_eq!(sys_set_proc_name(NAME), Some(NAME.len()));
+        // the list shows the child as `worker` next to the parent
+        sys_stat();
+        sys_exit(0);
+    }
+    assert_eq!(sys_wait_pid(child), 0);
+
+    // names are truncated to the width of the list
+    assert_eq!(sys_set_proc_name(LONG_NAME), Some(12));
+    assert_eq!(sys_set_proc_name(""), None);
+    sys_stat();
+
+    println!("Process name test passed!");
+
+    0
+}
+
+entry!(main);
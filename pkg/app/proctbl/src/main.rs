@@ -0,0 +1,62 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const ROUNDS: usize = 4;
+const CHILDREN: usize = 6;
+const GRANDCHILDREN: usize = 3;
+
+/// Fork a few grandchildren and reap them while the siblings do the same,
+/// returns the sum of their exit codes
+fn fork_and_reap(seed: usize) -> isize {
+    let mut pids = [0u16; GRANDCHILDREN];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        *pid = sys_fork();
+        if *pid == 0 {
+            sys_yield();
+            sys_exit((seed * GRANDCHILDREN + i) as isize);
+        }
+        sys_yield();
+    }
+
+    // reap in the reverse order to interleave with the exits
+    let mut sum = 0;
+    for (i, pid) in pids.iter().enumerate().rev() {
+        let ret = sys_wait_pid(*pid);
+        assert_eq!(ret, (seed * GRANDCHILDREN + i) as isize, "Lost #{}", pid);
+        sum += ret;
+    }
+    sum
+}
+
+fn expected(seed: usize) -> isize {
+    (0..GRANDCHILDREN)
+        .map(|i| (seed * GRANDCHILDREN + i) as isize)
+        .sum()
+}
+
+fn main() -> isize {
+    for round in 0..ROUNDS {
+        let mut children = [0u16; CHILDREN];
+        for (i, child) in children.iter_mut().enumerate() {
+            *child = sys_fork();
+            if *child == 0 {
+                sys_exit(fork_and_reap(round * CHILDREN + i));
+            }
+        }
+
+        for (i, child) in children.iter().enumerate() {
+            assert_eq!(sys_wait_pid(*child), expected(round * CHILDREN + i));
+        }
+        println!("Round {} done, last child #{}", round, children[CHILDREN - 1]);
+    }
+
+    println!("Process table stress test passed!");
+
+    0
+}
+
+entry!(main);
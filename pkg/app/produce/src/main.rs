@@ -0,0 +1,17 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// Spawned by `pipeline` with the stdout redirected to a pipe
+fn main() -> isize {
+    for i in 0..3 {
+        println!("line {}", i);
+    }
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+use lib::{vec::Vec, *};
+
+extern crate lib;
+
+const APP_PATH: &str = "/APP/PSPAWN";
+const ARGS: [&str; 3] = ["pspawn", "hello", "world"];
+const CHILD_EXIT_CODE: isize = 7;
+
+/// Print the arguments to stdout, which is the pipe of the parent
+fn child() -> isize {
+    let mut args = Vec::new();
+    while let Some(arg) = sys_get_arg(args.len()) {
+        args.push(arg);
+    }
+    println!("{}", args.join(" "));
+    CHILD_EXIT_CODE
+}
+
+fn main() -> isize {
+    // spawned by the shell without any argument
+    if sys_get_arg(0).is_some() {
+        return child();
+    }
+
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+    let pid = sys_posix_spawn(APP_PATH, &ARGS, None, Some(write_fd)).expect("Failed to spawn");
+    // only the child holds the write end now
+    sys_close_file(write_fd);
+
+    let mut buf = [0u8; 64];
+    let mut len = 0;
+    while let Some(count) = sys_read(read_fd, &mut buf[len..]) {
+        if count == 0 {
+            if !sys_poll(&[read_fd])[0].readable {
+                sys_yield();
+                continue;
+            }
+            // readable but empty, the child has exited
+            break;
+        }
+        len += count;
+    }
+    sys_close_file(read_fd);
+
+    let output = core::str::from_utf8(&buf[..len]).expect("Invalid utf8");
+    println!("Child #{} printed {:?}", pid, output);
+    assert_eq!(output.trim_end(), ARGS.join(" "));
+    assert_eq!(sys_wait_pid(pid), CHILD_EXIT_CODE);
+
+    // bad arguments
+    assert_eq!(sys_posix_spawn(APP_PATH, &[""; ARG_MAX + 1], None, None), None);
+    assert_eq!(sys_posix_spawn("/APP/NOTHING", &ARGS, None, None), None);
+    assert_eq!(sys_posix_spawn(APP_PATH, &ARGS, Some(u8::MAX - 1), None), None);
+
+    println!("Posix spawn test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const SEC: u64 = 1_000_000_000;
+/// The share of the cpu allowed for the throttled child
+const QUOTA_DIVISOR: u64 = 4;
+
+// shared by the forked children
+static THROTTLED: AtomicU64 = AtomicU64::new(0);
+static FREE: AtomicU64 = AtomicU64::new(0);
+static START: AtomicBool = AtomicBool::new(false);
+
+fn spin(counter: &AtomicU64) -> ! {
+    while !START.load(Ordering::SeqCst) {
+        sys_yield();
+    }
+    loop {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn main() -> isize {
+    // measure the clock ticks per second
+    let start = sys_uptime();
+    assert!(sys_nanosleep(SEC), "TSC is not calibrated");
+    let hz = sys_uptime() - start;
+    println!("Clock: {} ticks per second", hz);
+
+    let throttled = sys_fork();
+    if throttled == 0 {
+        assert!(sys_set_quota(hz / QUOTA_DIVISOR));
+        spin(&THROTTLED);
+    }
+    let free = sys_fork();
+    if free == 0 {
+        spin(&FREE);
+    }
+
+    // let them compete for two seconds
+    START.store(true, Ordering::SeqCst);
+    assert!(sys_nanosleep(2 * SEC));
+    let throttled_count = THROTTLED.load(Ordering::Relaxed);
+    let free_count = FREE.load(Ordering::Relaxed);
+    sys_kill(throttled);
+    sys_kill(free);
+    sys_wait_pid(throttled);
+    sys_wait_pid(free);
+
+    println!("Progress: throttled {}, free {}", throttled_count, free_count);
+    // a fair share would be a half, the quota caps it to about a quarter
+    assert!(throttled_count > 0, "The throttled child never ran");
+    assert!(
+        throttled_count * 2 < free_count,
+        "The throttled child was not capped"
+    );
+    assert!(sys_set_quota(0));
+
+    println!("Quota test passed!");
+
+    0
+}
+
+entry!(main);
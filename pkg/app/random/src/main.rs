@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const BUF_SIZE: usize = 61;
+
+fn main() -> isize {
+    // the bytes after the requested length must be left untouched
+    let mut first = [0u8; BUF_SIZE + 3];
+    let mut second = [0u8; BUF_SIZE + 3];
+
+    assert_eq!(sys_getrandom(&mut first[..BUF_SIZE]), Some(BUF_SIZE));
+    assert_eq!(sys_getrandom(&mut second[..BUF_SIZE]), Some(BUF_SIZE));
+    assert_eq!(first[BUF_SIZE..], [0; 3]);
+    assert_eq!(second[BUF_SIZE..], [0; 3]);
+
+    println!("First:  {:02x?}", &first[..BUF_SIZE]);
+    println!("Second: {:02x?}", &second[..BUF_SIZE]);
+    assert_ne!(first[..BUF_SIZE], second[..BUF_SIZE]);
+
+    assert_eq!(sys_getrandom(&mut []), Some(0));
+    assert_ne!(random_u64(), random_u64());
+
+    println!("GetRandom test passed!");
+
+    0
+}
+
+entry!(main);
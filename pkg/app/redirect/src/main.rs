@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+// where the original stdout is kept during the redirection
+const SAVED_STDOUT: u8 = 10;
+const MESSAGE: &str = "Hello, pipe!";
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+
+    assert_eq!(sys_dup2(1, SAVED_STDOUT), SAVED_STDOUT as isize);
+    assert_eq!(sys_dup2(write_fd, 1), 1);
+
+    println!("{}", MESSAGE);
+    errln!("stderr still reaches the console while stdout is redirected");
+
+    // restore stdout
+    assert_eq!(sys_dup2(SAVED_STDOUT, 1), 1);
+    sys_close_file(SAVED_STDOUT);
+
+    let mut buf = [0u8; 64];
+    let len = sys_read(read_fd, &mut buf).expect("Failed to read pipe");
+    let output = core::str::from_utf8(&buf[..len]).expect("Invalid utf8");
+    assert_eq!(output.trim_end(), MESSAGE);
+
+    sys_close_file(read_fd);
+    sys_close_file(write_fd);
+
+    println!("Redirected output: {:?}", output);
+
+    0
+}
+
+entry!(main);
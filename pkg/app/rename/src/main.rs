@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+// the binary of this app, which is sure to exist
+const FILE_PATH: &str = "/APP/rename";
+const TEMP_PATH: &str = "/APP/renamed";
+
+fn main() -> isize {
+    assert_eq!(sys_unlink("/APP/missing"), ENOENT);
+    assert_eq!(sys_rename("/APP/missing", TEMP_PATH), ENOENT);
+    assert_eq!(sys_unlink("/MISSING/file"), ENOENT);
+
+    // an opened file can be neither removed nor renamed
+    let fd = sys_open_file(FILE_PATH);
+    assert_eq!(sys_unlink(FILE_PATH), EBUSY);
+    // the path is case-insensitive
+    assert_eq!(sys_unlink("/app/RENAME"), EBUSY);
+    assert_eq!(sys_rename(FILE_PATH, TEMP_PATH), EBUSY);
+    assert_eq!(sys_rename(TEMP_PATH, FILE_PATH), ENOENT);
+    sys_close_file(fd);
+    println!("Opened file is kept.");
+
+    // rename back and forth, the file is still readable
+    assert_eq!(sys_rename(FILE_PATH, TEMP_PATH), 0);
+    assert_eq!(sys_rename(FILE_PATH, TEMP_PATH), ENOENT);
+    let fd = sys_open_file(TEMP_PATH);
+    let mut magic = [0u8; 4];
+    assert_eq!(sys_read(fd, &mut magic), Some(4));
+    assert_eq!(&magic, b"\x7fELF");
+    sys_close_file(fd);
+    assert_eq!(sys_rename(TEMP_PATH, FILE_PATH), 0);
+    println!("Renamed file is readable.");
+
+    println!("Rename test passed!");
+
+    0
+}
+
+entry!(main);
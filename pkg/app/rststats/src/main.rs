@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 4096;
+const FIRST_PAGES: usize = 8;
+const FIRST_TICKS: u64 = 60;
+const SECOND_TICKS: u64 = 10;
+
+fn spin_for(ticks: u64) {
+    let deadline = sys_uptime() + ticks;
+    while sys_uptime() < deadline {
+        core::hint::spin_loop();
+    }
+}
+
+fn main() -> isize {
+    sys_reset_stats();
+
+    // the first phase spins longer and touches new pages
+    let len = PAGE_SIZE * FIRST_PAGES;
+    let addr = sys_mmap(0, 0, len, MAP_PRIVATE | MAP_ANONYMOUS | MAP_WRITE).expect("Failed to map");
+    let mapped = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, len) };
+    mapped.fill(0x5A);
+    spin_for(FIRST_TICKS);
+    let first = sys_reset_stats();
+
+    // the second phase spins only, on the pages already there
+    mapped.fill(0xA5);
+    spin_for(SECOND_TICKS);
+    let second = sys_reset_stats();
+
+    println!("First: {:?}", first);
+    println!("Second: {:?}", second);
+    assert!(first.page_faults >= FIRST_PAGES);
+    assert!(second.page_faults < FIRST_PAGES);
+    // a process never runs for more ticks than the ones passed,
+    // give one for the tick of the syscall itself
+    assert!(second.ticks as u64 <= SECOND_TICKS + 1, "The first phase is counted");
+    assert!(first.ticks > second.ticks);
+
+    assert!(sys_munmap(addr));
+
+    println!("ResetStats test passed!");
+
+    0
+}
+
+entry!(main);
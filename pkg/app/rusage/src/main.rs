@@ -0,0 +1,81 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The ticks both children keep ready for
+const BUSY_TICKS: u64 = 100;
+
+/// Yield until the end tick, giving up the processor by itself
+fn yielder(end: u64) -> ! {
+    while sys_uptime() < end {
+        sys_yield();
+    }
+    sys_exit(0)
+}
+
+/// Spin until the end tick, only leaving as the clock preempts it
+fn spinner(end: u64) -> ! {
+    while sys_uptime() < end {
+        core::hint::spin_loop();
+    }
+    sys_exit(0)
+}
+
+fn spawn(run: fn(u64) -> !, end: u64) -> u16 {
+    let child = sys_fork();
+    if child == 0 {
+        run(end);
+    }
+    assert_ne!(child, FORK_FAILED);
+    child
+}
+
+fn main() -> isize {
+    let before = sys_getrusage(RUSAGE_SELF).expect("Failed to get the usage");
+
+    let end = sys_uptime() + BUSY_TICKS;
+    let yielding = spawn(yielder, end);
+    let spinning = spawn(spinner, end);
+    let (code, yielded) = sys_wait4(yielding);
+    assert_eq!(code, 0);
+    let (code, spun) = sys_wait4(spinning);
+    assert_eq!(code, 0);
+    let policy = sys_get_scheduler();
+    println!(
+        "Switches under {:?}: yielder {} voluntary {} involuntary, \
+         spinner {} voluntary {} involuntary",
+        policy,
+        yielded.voluntary_switches,
+        yielded.involuntary_switches,
+        spun.voluntary_switches,
+        spun.involuntary_switches
+    );
+
+    // FIFO runs a process again on yield & preemption, so it never switches
+    if policy != SchedPolicy::Fifo {
+        assert!(
+            yielded.voluntary_switches >= BUSY_TICKS as usize / 2
+                && yielded.voluntary_switches > yielded.involuntary_switches,
+            "The yielder is not switched away by itself"
+        );
+        assert!(
+            spun.involuntary_switches >= BUSY_TICKS as usize / 2
+                && spun.involuntary_switches > spun.voluntary_switches,
+            "The spinner is not preempted"
+        );
+    }
+
+    // the parent has blocked to wait for each child
+    let after = sys_getrusage(RUSAGE_SELF).expect("Failed to get the usage");
+    assert!(after.voluntary_switches > before.voluntary_switches);
+    assert_eq!(sys_getrusage(1), Err(SysError::Inval));
+
+    println!("Rusage test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The ticks a child keeps busy for
+const BUSY_TICKS: u64 = 100;
+const HIGH_PRIORITY: usize = 0;
+/// Passed over this many times before it ages up to the high one
+const LOW_PRIORITY: usize = 20;
+
+/// Keep ready until the end tick, then exit with the longest wait
+fn busy(end: u64) -> ! {
+    while sys_uptime() < end {
+        core::hint::spin_loop();
+    }
+    let latency = sys_sched_latency(0).expect("Failed to get the latency");
+    sys_exit(latency.max as isize)
+}
+
+fn spawn_busy(end: u64, priority: usize) -> u16 {
+    let child = sys_fork();
+    if child == 0 {
+        busy(end);
+    }
+    assert_ne!(child, FORK_FAILED);
+    assert!(sys_set_priority(child, priority));
+    child
+}
+
+fn main() -> isize {
+    let end = sys_uptime() + BUSY_TICKS;
+    let high = spawn_busy(end, HIGH_PRIORITY);
+    let low = spawn_busy(end, LOW_PRIORITY);
+
+    let high_max = sys_wait_pid(high) as usize;
+    let low_max = sys_wait_pid(low) as usize;
+    let policy = sys_get_scheduler();
+    println!(
+        "Max latency under {:?}: high {} ticks, low {} ticks",
+        policy, high_max, low_max
+    );
+
+    // the high one is picked again on every tick
+    assert!(high_max <= 2, "The high priority process waits");
+    if policy == SchedPolicy::Priority {
+        // the low one waits until it has aged enough
+        assert!(low_max >= LOW_PRIORITY / 2, "The low priority process is not starved");
+    } else {
+        // the others take turns
+        assert!(low_max <= 2, "The low priority process waits");
+    }
+
+    // the parent itself has been picked, after each wake-up
+    let latency = sys_sched_latency(0).expect("Failed to get the latency");
+    assert!(latency.picks > 0 && latency.max >= latency.average());
+    assert!(sys_sched_latency(u16::MAX).is_none());
+
+    println!("Sched latency test passed!");
+
+    0
+}
+
+entry!(main);
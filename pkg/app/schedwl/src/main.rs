@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const WORKERS: usize = 3;
+/// How long each worker spins, in milliseconds
+const WORK_MS: u64 = 50;
+
+fn spin_for(ms: u64) {
+    let cycles = sys_tsc_frequency() * ms / 1000;
+    let start = rdtsc();
+    while rdtsc() - start < cycles {
+        core::hint::spin_loop();
+    }
+}
+
+/// Run the same workload under the current policy,
+/// compare the output by switching the policy with `sched` in the shell
+fn main() -> isize {
+    assert!(sys_tsc_frequency() > 0, "TSC is not calibrated");
+
+    // the app is spawned by the shell, which is the init process
+    let policy = sys_get_scheduler();
+    assert!(!sys_set_scheduler(policy), "Only init may set the scheduler");
+
+    let start = sys_context_switches();
+    let mut workers = [0u16; WORKERS];
+    for worker in workers.iter_mut() {
+        *worker = sys_fork();
+        if *worker == 0 {
+            spin_for(WORK_MS);
+            sys_exit(0);
+        }
+    }
+    for worker in workers {
+        assert_eq!(sys_wait_pid(worker), 0);
+    }
+    let switches = sys_context_switches() - start;
+
+    println!("{:?}: {} context switches", policy, switches);
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const FILE_PATH: &str = "/KERNEL.ELF";
+const CHUNK_SIZE: usize = 64;
+
+fn main() -> isize {
+    let fd = sys_open_file(FILE_PATH);
+
+    let length = sys_seek(fd, 0, SEEK_END);
+    assert!(length > 0, "Failed to seek to the end of {}", FILE_PATH);
+    println!("{} has {} bytes", FILE_PATH, length);
+
+    let middle = length as usize / 2;
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+
+    // read the whole file sequentially, remembering the bytes in the middle
+    let mut expected = [0u8; CHUNK_SIZE];
+    let mut buf = [0u8; 512];
+    let mut offset = 0;
+    loop {
+        let len = sys_read(fd, &mut buf).expect("Failed to read file");
+        if len == 0 {
+            break;
+        }
+        for (i, byte) in buf[..len].iter().enumerate() {
+            let pos = offset + i;
+            if (middle..middle + CHUNK_SIZE).contains(&pos) {
+                expected[pos - middle] = *byte;
+            }
+        }
+        offset += len;
+    }
+    assert_eq!(offset, length as usize);
+
+    println!("Seek to the middle of the file: {}", middle);
+    assert_eq!(sys_seek(fd, middle as isize, SEEK_SET), middle as isize);
+
+    let mut actual = [0u8; CHUNK_SIZE];
+    let len = sys_read(fd, &mut actual).expect("Failed to read file");
+    assert_eq!(len, CHUNK_SIZE);
+    assert_eq!(actual, expected);
+
+    // seek relative to the current offset and the end
+    assert_eq!(
+        sys_seek(fd, -(CHUNK_SIZE as isize), SEEK_CUR),
+        middle as isize
+    );
+    assert_eq!(sys_seek(fd, -1, SEEK_END), length - 1);
+    assert_eq!(sys_seek(fd, 1, SEEK_END), -1);
+
+    // stdin / stdout are not seekable
+    assert_eq!(sys_seek(0, 0, SEEK_SET), -1);
+    assert_eq!(sys_seek(1, 0, SEEK_SET), -1);
+
+    sys_close_file(fd);
+
+    println!("Seek test passed!");
+
+    0
+}
+
+entry!(main);
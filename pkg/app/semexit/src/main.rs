@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const LOCK_KEY: u32 = 0x5e4e;
+
+fn main() -> isize {
+    assert!(sys_new_robust_sem(LOCK_KEY, 1));
+
+    // the child takes the lock and exits without releasing it
+    let parent = sys_get_pid();
+    let child = sys_fork();
+    if child == 0 {
+        assert!(sys_sem_wait(LOCK_KEY));
+        while sys_block_reason(parent) != Some(BlockReason::Semaphore) {
+            sys_yield();
+        }
+        sys_exit(0);
+    }
+
+    // blocked until the holder exits, then woken up with an error
+    assert!(!sys_sem_wait(LOCK_KEY), "The abandoned lock is acquired");
+    assert_eq!(sys_wait_pid(child), 0);
+
+    // the unit held by the child is given back
+    assert!(sys_sem_wait(LOCK_KEY));
+    assert!(sys_sem_signal(LOCK_KEY));
+    assert!(sys_remove_sem(LOCK_KEY));
+
+    println!("Semaphore exit test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const SEM_KEY: u32 = 0x7e30;
+const TIMEOUT_MS: u64 = 100;
+
+/// Wait the semaphore for `TIMEOUT_MS`, returns the result and the elapsed ms
+fn timed_wait(freq: u64) -> (bool, u64) {
+    let start = rdtsc();
+    let ret = sys_sem_timedwait(SEM_KEY, TIMEOUT_MS);
+    (ret, (rdtsc() - start) * 1000 / freq)
+}
+
+fn main() -> isize {
+    let freq = sys_tsc_frequency();
+    assert!(freq > 0, "TSC is not calibrated");
+    assert!(sys_new_sem(SEM_KEY, 0));
+
+    // never signaled, woken up by the timer
+    let (ret, elapsed) = timed_wait(freq);
+    println!("Timed out after {} ms", elapsed);
+    assert!(!ret, "A never signaled wait succeeds");
+    assert!(elapsed >= TIMEOUT_MS, "Woken up after {} ms", elapsed);
+    assert!(elapsed < TIMEOUT_MS * 10, "Woken up after {} ms", elapsed);
+
+    // signaled before the timeout, the timer is cancelled
+    let parent = sys_get_pid();
+    let child = sys_fork();
+    if child == 0 {
+        while sys_block_reason(parent) != Some(BlockReason::Semaphore) {
+            sys_yield();
+        }
+        assert!(sys_sem_signal(SEM_KEY));
+        sys_exit(0);
+    }
+    let (ret, elapsed) = timed_wait(freq);
+    assert!(ret, "The signal is missed");
+    assert!(elapsed < TIMEOUT_MS, "Signaled after {} ms", elapsed);
+    assert_eq!(sys_wait_pid(child), 0);
+
+    // the next wait is not cut short by the timer of the last one
+    let (ret, elapsed) = timed_wait(freq);
+    assert!(!ret);
+    assert!(elapsed >= TIMEOUT_MS, "Woken up after {} ms", elapsed);
+
+    assert!(sys_remove_sem(SEM_KEY));
+
+    println!("Semaphore timed wait test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const MESSAGE: &[u8] = b"through the passed fd";
+
+fn main() -> isize {
+    let (ctl_read, ctl_write) = sys_pipe().expect("Failed to create pipe");
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_close_file(ctl_write);
+        // the data pipe is created after the fork, only passed to us
+        let fd = loop {
+            match sys_recv_fd(ctl_read) {
+                Some(fd) => break fd,
+                None => sys_yield(),
+            }
+        };
+        assert_eq!(sys_write_all(fd, MESSAGE), Some(MESSAGE.len()));
+        sys_close_file(fd);
+        sys_exit(0);
+    }
+
+    // nothing is queued yet, and only the read end receives
+    assert_eq!(sys_recv_fd(ctl_read), None);
+    assert_eq!(sys_recv_fd(ctl_write), None);
+
+    let (data_read, data_write) = sys_pipe().expect("Failed to create pipe");
+    // a pipe never carries its own ends, nor does a read end send
+    assert!(!sys_send_fd(ctl_write, ctl_read));
+    assert!(!sys_send_fd(ctl_write, ctl_write));
+    assert!(!sys_send_fd(ctl_read, data_write));
+
+    assert!(sys_send_fd(ctl_write, data_write));
+    // the child holds the only write end once it is received
+    sys_close_file(data_write);
+
+    let mut buf = [0u8; 64];
+    let mut len = 0;
+    loop {
+        if !sys_poll(&[data_read])[0].readable {
+            sys_yield();
+            continue;
+        }
+        match sys_read(data_read, &mut buf[len..]).expect("Failed to read pipe") {
+            // all the write ends are closed
+            0 => break,
+            count => len += count,
+        }
+    }
+    println!("Read {:?}", core::str::from_utf8(&buf[..len]));
+    assert_eq!(&buf[..len], MESSAGE);
+    assert_eq!(sys_wait_pid(child), 0);
+
+    sys_close_file(data_read);
+    sys_close_file(ctl_read);
+    sys_close_file(ctl_write);
+
+    println!("SendFd test passed!");
+
+    0
+}
+
+entry!(main);
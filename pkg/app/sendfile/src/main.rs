@@ -0,0 +1,87 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const FILE_PATH: &str = "/KERNEL.ELF";
+/// The bytes a pipe holds, a larger transfer is short
+const PIPE_CAPACITY: usize = 4096;
+const COUNT: usize = 1000;
+
+/// Read the file at `offset` into `buf` in the usual way, keeping the offset
+fn read_file(fd: u8, offset: usize, buf: &mut [u8]) {
+    let saved = sys_seek(fd, 0, SEEK_CUR);
+    assert_eq!(sys_seek(fd, offset as isize, SEEK_SET), offset as isize);
+    let mut read = 0;
+    while read < buf.len() {
+        match sys_read(fd, &mut buf[read..]) {
+            Some(len) if len > 0 => read += len,
+            _ => break,
+        }
+    }
+    assert_eq!(read, buf.len());
+    sys_seek(fd, saved, SEEK_SET);
+}
+
+/// Drain exactly `buf.len()` bytes from the pipe
+fn read_pipe(fd: u8, buf: &mut [u8]) {
+    let mut read = 0;
+    while read < buf.len() {
+        read += sys_read(fd, &mut buf[read..]).expect("Failed to read pipe");
+    }
+}
+
+fn main() -> isize {
+    let fd = sys_open_file(FILE_PATH);
+    let length = sys_seek(fd, 0, SEEK_END) as usize;
+    assert!(length > COUNT + PIPE_CAPACITY, "{} is too small", FILE_PATH);
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+
+    // a full transfer advances the offset
+    assert_eq!(sys_sendfile(write_fd, fd, COUNT), Some(COUNT));
+    assert_eq!(sys_seek(fd, 0, SEEK_CUR), COUNT as isize);
+
+    let mut expected = [0u8; PIPE_CAPACITY];
+    let mut actual = [0u8; PIPE_CAPACITY];
+    read_file(fd, 0, &mut expected[..COUNT]);
+    read_pipe(read_fd, &mut actual[..COUNT]);
+    assert_eq!(&actual[..COUNT], &expected[..COUNT]);
+
+    // short once the pipe is full, the rest stays in the file
+    assert_eq!(sys_sendfile(write_fd, fd, 2 * PIPE_CAPACITY), Some(PIPE_CAPACITY));
+    assert_eq!(sys_seek(fd, 0, SEEK_CUR), (COUNT + PIPE_CAPACITY) as isize);
+    assert_eq!(sys_sendfile(write_fd, fd, 1), Some(0));
+
+    read_file(fd, COUNT, &mut expected);
+    read_pipe(read_fd, &mut actual);
+    assert_eq!(actual, expected);
+
+    // short at EOF
+    assert_eq!(sys_seek(fd, -10, SEEK_END), length as isize - 10);
+    assert_eq!(sys_sendfile(write_fd, fd, 100), Some(10));
+    assert_eq!(sys_sendfile(write_fd, fd, 100), Some(0));
+    read_pipe(read_fd, &mut actual[..10]);
+    read_file(fd, length - 10, &mut expected[..10]);
+    assert_eq!(&actual[..10], &expected[..10]);
+
+    // only from a file, and to an fd that can be written
+    assert_eq!(sys_sendfile(write_fd, read_fd, 1), None);
+    assert_eq!(sys_sendfile(read_fd, fd, 1), None);
+    sys_close_file(read_fd);
+    assert_eq!(sys_seek(fd, 0, SEEK_SET), 0);
+    assert_eq!(sys_sendfile(write_fd, fd, 1), None);
+    assert_eq!(sys_seek(fd, 0, SEEK_CUR), 0);
+
+    sys_close_file(write_fd);
+    sys_close_file(fd);
+
+    println!("Sendfile test passed!");
+
+    0
+}
+
+entry!(main);
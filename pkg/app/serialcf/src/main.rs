@@ -0,0 +1,22 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    // the app is spawned by the shell, so it is not privileged,
+    // the registers are checked by the `serial_test` feature of the kernel
+    assert!(
+        !sys_serial_config(38400, 8, PARITY_NONE, 1),
+        "Only privileged processes may configure the serial line"
+    );
+    assert!(!sys_serial_config(9600, 7, PARITY_EVEN, 2));
+
+    println!("Serial config test passed!");
+
+    0
+}
+
+entry!(main);
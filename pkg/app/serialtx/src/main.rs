@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+// larger than the output ring of the kernel
+const LINES: usize = 128;
+const LINE_LEN: usize = 64;
+
+fn main() -> isize {
+    let mut buf = vec::Vec::with_capacity(LINES * LINE_LEN);
+    let mut checksum = 0u32;
+    for line in 0..LINES {
+        let start = buf.len();
+        buf.extend_from_slice(format!("{:04} ", line).as_bytes());
+        while buf.len() < start + LINE_LEN - 2 {
+            buf.push(b'a' + ((buf.len() - start + line) % 26) as u8);
+        }
+        buf.extend_from_slice(b"\r\n");
+    }
+    for byte in buf.iter() {
+        checksum = checksum.wrapping_mul(31).wrapping_add(*byte as u32);
+    }
+
+    // the write returns once all the bytes are queued
+    let start = sys_uptime();
+    assert_eq!(sys_write(1, &buf), Some(buf.len()));
+    let elapsed = sys_uptime() - start;
+
+    // the serial log should have the lines numbered from 0000 in order,
+    // each followed by the rotated alphabet, matching the checksum
+    println!(
+        "Wrote {} bytes in {} ticks, checksum: {:#010x}",
+        buf.len(),
+        elapsed,
+        checksum
+    );
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    let pid = sys_get_pid();
+    let sid = sys_getsid(0).expect("Failed to get the session");
+    // a spawned app leads its own process group in the session of the shell
+    assert_eq!(sys_getpgid(0), Some(pid));
+    assert_eq!(sys_setsid(), None, "A group leader creates a session");
+    assert_eq!(sys_getsid(0), Some(sid));
+
+    let child = sys_fork();
+    if child == 0 {
+        let child = sys_get_pid();
+        // a forked child is in the group of the parent, so it may lead a session
+        assert_eq!(sys_getpgid(0), Some(pid));
+        assert_eq!(sys_setsid(), Some(child));
+        assert_eq!(sys_getsid(0), Some(child));
+        assert_eq!(sys_getpgid(0), Some(child));
+        assert_eq!(sys_setsid(), None, "The session leader creates another one");
+
+        let grandchild = sys_fork();
+        if grandchild == 0 {
+            assert_eq!(sys_getsid(0), Some(child));
+            assert_eq!(sys_getpgid(0), Some(child));
+            sys_exit(0);
+        }
+        assert_eq!(sys_getsid(grandchild), Some(child));
+        assert_eq!(sys_wait_pid(grandchild), 0);
+        sys_exit(0);
+    }
+    assert_eq!(sys_wait_pid(child), 0);
+    // the session of the parent is left as it is
+    assert_eq!(sys_getsid(0), Some(sid));
+    assert_eq!(sys_getsid(u16::MAX), None);
+
+    println!("Session test passed!");
+
+    0
+}
+
+entry!(main);
@@ -20,8 +20,13 @@ fn main() -> isize {
                 println!("\"ls /path/to/your/dir \" to list all the files in directory");
                 println!("\"cat /path/to/your/dir \" to check the content of the file");
                 println!("\"run /path/to/your/app \" to run the app");
+                println!("\"chroot /dir /path/to/your/app \" to run the app confined to dir");
                 println!("\"ps\" to list all the processes");
                 println!("\"info\" to print current process info");
+                println!("\"df\" to show the free space of the filesystem");
+                println!("\"loglevel warn\" to set the kernel log level, off ~ trace");
+                println!("\"sched fifo\" to set the scheduler policy, rr / fifo / prio");
+                println!("\"strace on\" to log every syscall in the kernel, on / off");
                 println!("\"exit\" to exit the shell");
             }
             "la" => {
@@ -43,14 +48,38 @@ fn main() -> isize {
             "run" => {
                 let path = command.next().unwrap();
                 let name: vec::Vec<&str> = path.rsplit('/').collect();
-                let pid = sys_spawn(path);
-                if pid == 0 {
-                    println!("Failed to run app: {}", name[0]);
-                    continue;
-                } else {
+                if let Some(pid) = sys_spawn(path) {
                     sys_stat();
                     println!("{} exited with {}", name[0], sys_wait_pid(pid));
+                } else {
+                    println!("Failed to run app: {}", name[0]);
+                }
+            }
+            "chroot" => {
+                let (dir, path) = match (command.next(), command.next()) {
+                    (Some(dir), Some(path)) => (dir, path),
+                    _ => {
+                        println!("Usage: chroot /dir /path/to/your/app");
+                        continue;
+                    }
+                };
+                // the fork of the shell is privileged, the app is not
+                let pid = sys_fork();
+                if pid == FORK_FAILED {
+                    println!("Failed to fork");
+                    continue;
+                }
+                if pid == 0 {
+                    if !sys_chroot(dir) {
+                        println!("Failed to chroot to {}", dir);
+                        sys_exit(-1);
+                    }
+                    sys_drop_privilege();
+                    sys_exec(path);
+                    println!("Failed to run app: {}", path);
+                    sys_exit(-1);
                 }
+                println!("{} exited with {}", path, sys_wait_pid(pid));
             }
             "ps" => {
                 sys_stat();
@@ -62,6 +91,60 @@ fn main() -> isize {
             "info" => {
                 sys_print_info(sys_get_pid());
             }
+            "df" => match sys_statfs() {
+                Some(info) => {
+                    let kib = |blocks: u64| blocks * info.block_size / 1024;
+                    println!("Total: {} KiB", kib(info.total_blocks));
+                    println!("Used:  {} KiB", kib(info.total_blocks - info.free_blocks));
+                    println!("Free:  {} KiB", kib(info.free_blocks));
+                }
+                None => println!("No filesystem is mounted"),
+            },
+            "loglevel" => {
+                let levels = ["off", "error", "warn", "info", "debug", "trace"];
+                let name = command.next().unwrap_or("");
+                match levels.iter().position(|&level| level.eq_ignore_ascii_case(name)) {
+                    Some(level) if sys_set_log_level(level) => {
+                        println!("Kernel log level set to {}", levels[level]);
+                    }
+                    _ => println!("Failed to set log level: {}", name),
+                }
+            }
+            "strace" => {
+                let on = match command.next() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => {
+                        println!("Usage: strace on / off");
+                        continue;
+                    }
+                };
+                if sys_set_strace(on) {
+                    println!("Syscall tracing turned {}", if on { "on" } else { "off" });
+                } else {
+                    println!("Failed to set syscall tracing");
+                }
+            }
+            "sched" => {
+                let policy = match command.next() {
+                    Some("rr") => SchedPolicy::RoundRobin,
+                    Some("fifo") => SchedPolicy::Fifo,
+                    Some("prio") => SchedPolicy::Priority,
+                    Some(name) => {
+                        println!("Unknown scheduler policy: {}", name);
+                        continue;
+                    }
+                    None => {
+                        println!("Scheduler policy: {:?}", sys_get_scheduler());
+                        continue;
+                    }
+                };
+                if sys_set_scheduler(policy) {
+                    println!("Scheduler policy set to {:?}", policy);
+                } else {
+                    println!("Failed to set scheduler policy");
+                }
+            }
             _ => {
                 println!("Unknown command: {}", op);
             }
@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PIPE_CAPACITY: usize = 4096;
+const FREE_SPACE: usize = 10;
+const CHUNK_SIZE: usize = 100;
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+
+    // zero-length reads and writes are valid no-ops
+    assert_eq!(sys_write(write_fd, &[]), Some(0));
+    assert_eq!(sys_read(read_fd, &mut []), Some(0));
+
+    // leave only a few bytes of free space in the pipe
+    let filler = [b'a'; PIPE_CAPACITY - FREE_SPACE];
+    assert_eq!(sys_write(write_fd, &filler), Some(filler.len()));
+
+    // the write is cut short at the capacity
+    let chunk = [b'b'; CHUNK_SIZE];
+    let first = sys_write(write_fd, &chunk).expect("Failed to write pipe");
+    println!("Short write: {} of {} bytes", first, CHUNK_SIZE);
+    assert_eq!(first, FREE_SPACE);
+
+    // a full pipe accepts nothing until drained
+    assert_eq!(sys_write(write_fd, &chunk[first..]), Some(0));
+
+    let mut buf = [0u8; CHUNK_SIZE];
+    assert_eq!(sys_read(read_fd, &mut buf), Some(CHUNK_SIZE));
+
+    let second = sys_write(write_fd, &chunk[first..]).expect("Failed to write pipe");
+    assert_eq!(first + second, CHUNK_SIZE);
+
+    // drain the pipe, the tail is exactly the chunk
+    let mut total = CHUNK_SIZE;
+    let mut tail = 0;
+    let mut buf = [0u8; 512];
+    while let Some(len) = sys_read(read_fd, &mut buf).filter(|len| *len > 0) {
+        tail += buf[..len].iter().filter(|byte| **byte == b'b').count();
+        total += len;
+    }
+    assert_eq!(total, filler.len() + CHUNK_SIZE);
+    assert_eq!(tail, CHUNK_SIZE);
+
+    // sys_write_all keeps writing while a child drains the pipe
+    let child = sys_fork();
+    if child == 0 {
+        let mut received = 0;
+        while received < PIPE_CAPACITY * 2 {
+            received += sys_read(read_fd, &mut buf).expect("Failed to read pipe");
+        }
+        sys_exit(received as isize);
+    }
+
+    let data = vec![b'c'; PIPE_CAPACITY * 2];
+    assert_eq!(sys_write_all(write_fd, &data), Some(data.len()));
+    assert_eq!(sys_wait_pid(child), data.len() as isize);
+
+    sys_close_file(read_fd);
+    sys_close_file(write_fd);
+
+    println!("Short count test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const CHILD_COUNT: usize = 4;
+const PAGE_SIZE: usize = 0x1000;
+const CHILD_PAGES: usize = 16;
+const SEM_KEY: u32 = 0x3638;
+
+/// Hold some heap & mapped memory, then block until killed by the shutdown
+fn hold_memory() -> ! {
+    let heap_end = sys_brk(None).unwrap();
+    let new_end = sys_brk(Some(heap_end + PAGE_SIZE * CHILD_PAGES)).expect("Failed to grow");
+    let heap = unsafe { core::slice::from_raw_parts_mut(heap_end as *mut u8, new_end - heap_end) };
+    heap.fill(0x5A);
+
+    let len = PAGE_SIZE * CHILD_PAGES;
+    let addr = sys_mmap(0, 0, len, MAP_PRIVATE | MAP_ANONYMOUS | MAP_WRITE).expect("Failed to map");
+    let mapped = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, len) };
+    mapped.fill(0xA5);
+
+    sys_sem_wait(SEM_KEY);
+    unreachable!("The semaphore is never signaled");
+}
+
+fn main() -> isize {
+    assert!(sys_new_sem(SEM_KEY, 0));
+
+    let mut pids = [0u16; CHILD_COUNT];
+    for pid in pids.iter_mut() {
+        *pid = sys_fork_cow();
+        if *pid == 0 {
+            hold_memory();
+        }
+    }
+
+    for pid in pids {
+        while sys_block_reason(pid) != Some(BlockReason::Semaphore) {
+            sys_yield();
+        }
+    }
+
+    // the kernel checks the frames of all the processes are released,
+    // QEMU exits with 1 if no frame is leaked, or 35 otherwise
+    let stats = sys_frame_stats();
+    println!("Shutting down with {} frames in use", stats.used - stats.recycled);
+    sys_shutdown();
+}
+
+entry!(main);
@@ -0,0 +1,124 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const TRIES: usize = 100;
+/// The ticks a child sleeps for, longer than the parent takes to signal it
+const SLEEP_TICKS: u64 = 100;
+
+// the signals seen by each handler, bit `sig - 1` for each
+static FIRST: AtomicUsize = AtomicUsize::new(0);
+static SECOND: AtomicUsize = AtomicUsize::new(0);
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+fn first(sig: usize) {
+    FIRST.fetch_or(sig_bit(sig) as usize, Ordering::SeqCst);
+    RUNS.fetch_add(1, Ordering::SeqCst);
+}
+
+fn second(sig: usize) {
+    SECOND.fetch_or(sig_bit(sig) as usize, Ordering::SeqCst);
+    RUNS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Sleep with the handler of `SIGUSR1` set by `flags`, exits with 1 if the
+/// sleep is cut short by the signal of the parent
+fn sleeper(flags: usize) -> ! {
+    RUNS.store(0, Ordering::SeqCst);
+    sys_sigaction(SIGUSR1, &sig_action(first, flags)).unwrap();
+    let deadline = sys_uptime() + SLEEP_TICKS;
+    sys_sleep_until(deadline);
+    let cut = sys_uptime() < deadline;
+    assert_eq!(RUNS.load(Ordering::SeqCst), 1, "The handler did not run");
+    sys_exit(cut as isize)
+}
+
+fn spawn_sleeper(flags: usize) -> u16 {
+    let child = sys_fork();
+    if child == 0 {
+        sleeper(flags);
+    }
+    assert_ne!(child, FORK_FAILED);
+    for _ in 0..TRIES {
+        if sys_block_reason(child) == Some(BlockReason::Sleeping) {
+            break;
+        }
+        sys_yield();
+    }
+    assert_eq!(sys_block_reason(child), Some(BlockReason::Sleeping));
+    child
+}
+
+fn main() -> isize {
+    let pid = sys_get_pid();
+
+    // each signal runs its own handler, the one replaced is returned
+    let first_action = sig_action(first, 0);
+    let second_action = sig_action(second, SA_RESTART);
+    assert_eq!(sys_sigaction(SIGUSR1, &second_action), Ok(SigAction::default()));
+    assert_eq!(sys_sigaction(SIGUSR1, &first_action), Ok(second_action));
+    assert_eq!(sys_sigaction(SIGUSR2, &second_action), Ok(SigAction::default()));
+    assert_eq!(sys_tkill(pid, SIGUSR1), Ok(()));
+    assert_eq!(sys_tkill(pid, SIGUSR2), Ok(()));
+    assert_eq!(FIRST.load(Ordering::SeqCst), sig_bit(SIGUSR1) as usize);
+    assert_eq!(SECOND.load(Ordering::SeqCst), sig_bit(SIGUSR2) as usize);
+    assert_eq!(RUNS.load(Ordering::SeqCst), 2);
+
+    // a masked one waits, and is merged with the one sent again
+    sys_sigprocmask(SIG_BLOCK, sig_bit(SIGUSR1)).unwrap();
+    assert_eq!(sys_tkill(pid, SIGUSR1), Ok(()));
+    assert_eq!(sys_tkill(pid, SIGUSR1), Ok(()));
+    assert_eq!(RUNS.load(Ordering::SeqCst), 2, "The masked signal is handled");
+    sys_sigprocmask(SIG_UNBLOCK, sig_bit(SIGUSR1)).unwrap();
+    assert_eq!(RUNS.load(Ordering::SeqCst), 3, "The unmasked signal is not handled");
+
+    // the alarm is handled too, instead of killing
+    assert_eq!(sys_sigaction(SIGALRM, &second_action), Ok(SigAction::default()));
+    sys_alarm(10);
+    let alarmed = || SECOND.load(Ordering::SeqCst) & sig_bit(SIGALRM) as usize != 0;
+    let end = sys_uptime() + SLEEP_TICKS;
+    while !alarmed() && sys_uptime() < end {
+        sys_yield();
+    }
+    assert!(alarmed(), "The alarm is not handled");
+    let action = SigAction::default();
+    assert_eq!(sys_sigaction(SIGALRM, &action), Ok(second_action));
+
+    // a one-shot handler runs once, then the signal kills
+    let child = sys_fork();
+    if child == 0 {
+        sys_sigaction(SIGUSR1, &sig_action(first, SA_RESETHAND)).unwrap();
+        sys_tkill(sys_get_pid(), SIGUSR1).unwrap();
+        // the other handlers are inherited
+        assert_eq!(sys_sigaction(SIGUSR2, &action), Ok(second_action));
+        sys_tkill(sys_get_pid(), SIGUSR1).unwrap();
+        sys_exit(0);
+    }
+    assert_ne!(child, FORK_FAILED);
+    assert_eq!(sys_wait_pid(child), -(SIGUSR1 as isize));
+
+    // a sleep is cut short, unless the handler restarts it
+    let child = spawn_sleeper(0);
+    assert_eq!(sys_tkill(child, SIGUSR1), Ok(()));
+    assert_eq!(sys_wait_pid(child), 1, "The sleep goes on");
+    let child = spawn_sleeper(SA_RESTART);
+    assert_eq!(sys_tkill(child, SIGUSR1), Ok(()));
+    assert_eq!(sys_wait_pid(child), 0, "The sleep is cut short");
+
+    // bad signals & targets
+    assert_eq!(sys_sigaction(SIGKILL, &first_action), Err(SysError::Inval));
+    assert_eq!(sys_sigaction(SIGCHLD, &first_action), Err(SysError::Inval));
+    assert_eq!(sys_sigaction(NSIG + 1, &first_action), Err(SysError::Inval));
+    assert_eq!(sys_tkill(pid, 0), Err(SysError::Inval));
+    assert_eq!(sys_tkill(child, SIGUSR1), Err(SysError::NotFound));
+
+    println!("Sigaction test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicU16, AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const CHILD_EXIT_CODE: isize = 7;
+const MAX_TRIES: usize = 100;
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+static LAST_PID: AtomicU16 = AtomicU16::new(0);
+
+fn on_child_exit(pid: u16) {
+    LAST_PID.store(pid, Ordering::SeqCst);
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+fn main() -> isize {
+    assert!(!sys_child_exited());
+    assert!(sys_sigchld(Some(on_child_exit)));
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(CHILD_EXIT_CODE);
+    }
+
+    // the handler interrupts the parent once it is scheduled again
+    for _ in 0..MAX_TRIES {
+        if HANDLED.load(Ordering::SeqCst) != 0 {
+            break;
+        }
+        sys_yield();
+    }
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1, "The handler did not run");
+    assert_eq!(LAST_PID.load(Ordering::SeqCst), child);
+    println!("Handled the exit of #{}", child);
+
+    // the flag is set as well, and taken once
+    assert!(sys_child_exited());
+    assert!(!sys_child_exited());
+    assert_eq!(sys_wait_pid(child), CHILD_EXIT_CODE);
+
+    // without a handler only the flag is set
+    assert!(sys_sigchld(None));
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(CHILD_EXIT_CODE);
+    }
+    assert_eq!(sys_wait_pid(child), CHILD_EXIT_CODE);
+    assert!(sys_child_exited());
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1);
+
+    println!("SIGCHLD test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const CHILD_EXIT_CODE: isize = 7;
+const TRIES: usize = 20;
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+fn on_child_exit(_pid: u16) {
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+fn main() -> isize {
+    assert!(sys_sigchld(Some(on_child_exit)));
+    assert_eq!(sys_sigprocmask(SIG_BLOCK, sig_bit(SIGCHLD)), Some(0));
+    assert_eq!(sys_sigmask(), sig_bit(SIGCHLD));
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(CHILD_EXIT_CODE);
+    }
+    assert_eq!(sys_wait_pid(child), CHILD_EXIT_CODE);
+
+    // the exit stays pending while masked, over many schedules
+    for _ in 0..TRIES {
+        sys_yield();
+    }
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 0, "The masked handler ran");
+    assert!(sys_child_exited());
+
+    // the handler runs once unmasked, before the syscall returns
+    let old = sys_sigprocmask(SIG_UNBLOCK, sig_bit(SIGCHLD));
+    assert_eq!(old, Some(sig_bit(SIGCHLD)));
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1, "The pending exit is lost");
+    for _ in 0..TRIES {
+        sys_yield();
+    }
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1, "The handler ran twice");
+
+    // SIGKILL & SIGSTOP are never masked, and `how` must be known
+    let all = sig_bit(SIGKILL) | sig_bit(SIGSTOP) | sig_bit(SIGCHLD);
+    assert_eq!(sys_sigprocmask(SIG_SETMASK, all), Some(0));
+    assert_eq!(sys_sigmask(), sig_bit(SIGCHLD));
+    assert_eq!(sys_sigprocmask(3, 0), None);
+    assert_eq!(sys_sigprocmask(SIG_SETMASK, 0), Some(sig_bit(SIGCHLD)));
+    assert!(sys_sigchld(None));
+
+    println!("Signal mask test passed!");
+
+    0
+}
+
+entry!(main);
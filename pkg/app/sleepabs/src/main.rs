@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PERIOD: u64 = 10;
+const ROUNDS: u64 = 10;
+/// The ticks of work in each round, which a relative sleep would add up
+const WORK_TICKS: u64 = 3;
+/// The ticks a wake-up may take to be scheduled
+const SLACK: u64 = 2;
+
+fn busy(ticks: u64) {
+    let end = sys_uptime() + ticks;
+    while sys_uptime() < end {
+        core::hint::spin_loop();
+    }
+}
+
+fn main() -> isize {
+    // a passed deadline returns at once
+    let now = sys_uptime();
+    sys_sleep_until(now.saturating_sub(PERIOD));
+    sys_sleep_until(now);
+    assert!(sys_uptime() <= now + SLACK, "A passed deadline blocks");
+
+    // each wake-up is at or after its deadline, and the work in the rounds
+    // does not push the later deadlines back
+    let start = sys_uptime();
+    let mut deadline = start;
+    for round in 1..=ROUNDS {
+        busy(WORK_TICKS);
+        assert!(sleep_periodic(&mut deadline, PERIOD), "Round {} overran", round);
+        let woken = sys_uptime();
+        assert_eq!(deadline, start + round * PERIOD);
+        assert!(woken >= deadline, "Woken at {} before {}", woken, deadline);
+        assert!(woken <= deadline + SLACK, "Woken at {} long after {}", woken, deadline);
+    }
+    let drift = sys_uptime() - (start + ROUNDS * PERIOD);
+    println!("Drift after {} rounds: {} ticks", ROUNDS, drift);
+    assert!(drift <= SLACK, "The deadlines drift");
+
+    // an overrun round is reported, and the next one catches up
+    busy(PERIOD + WORK_TICKS);
+    assert!(!sleep_periodic(&mut deadline, PERIOD));
+    assert!(sleep_periodic(&mut deadline, PERIOD));
+    assert!(sys_uptime() <= deadline + SLACK);
+
+    println!("Sleep until test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const QUEUE_KEY: u32 = 0x534c;
+const MS: u64 = 1_000_000;
+/// The sleep of each child, the last two share the same deadline
+const DEADLINES: [u64; 5] = [100 * MS, 500 * MS, 300 * MS, 700 * MS, 700 * MS];
+/// The child killed in the middle of its sleep
+const VICTIM: usize = 1;
+
+fn main() -> isize {
+    assert!(sys_msg_get(QUEUE_KEY));
+
+    let mut pids = [0u16; DEADLINES.len()];
+    for (i, pid) in pids.iter_mut().enumerate() {
+        *pid = sys_fork();
+        if *pid == 0 {
+            assert!(sys_nanosleep(DEADLINES[i]), "TSC is not calibrated");
+            // report the wake up order
+            sys_msg_send(QUEUE_KEY, &[i as u8], 0);
+            sys_exit(i as isize);
+        }
+    }
+
+    // kill the victim between the wake ups of the others
+    assert!(sys_nanosleep(200 * MS));
+    assert_eq!(sys_block_reason(pids[VICTIM]), Some(BlockReason::Sleeping));
+    assert!(sys_kill(pids[VICTIM]));
+
+    let mut order = [0u8; DEADLINES.len() - 1];
+    for slot in order.iter_mut() {
+        let mut buf = [0u8; 1];
+        assert_eq!(sys_msg_recv(QUEUE_KEY, &mut buf), 1);
+        *slot = buf[0];
+    }
+    println!("Wake up order: {:?}", order);
+
+    // the survivors woke up by deadline, the tie in either order
+    assert_eq!(&order[..2], &[0, 2]);
+    assert!(order[2..] == [3, 4] || order[2..] == [4, 3]);
+
+    for (i, pid) in pids.iter().enumerate() {
+        let ret = sys_wait_pid(*pid);
+        if i != VICTIM {
+            assert_eq!(ret, i as isize);
+        } else {
+            assert_ne!(ret, i as isize, "The killed child was woken up");
+        }
+    }
+
+    println!("Sleep order test passed!");
+
+    0
+}
+
+entry!(main);
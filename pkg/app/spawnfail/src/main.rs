@@ -0,0 +1,24 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    // neither a missing app nor a non-ELF file can be spawned
+    for path in ["/APP/missing", "/MISSING/app", "/", "/EFI/BOOT/boot.conf"] {
+        assert_eq!(sys_spawn(path), None, "{} should not be spawned", path);
+        println!("Spawning {} failed as expected.", path);
+    }
+
+    // the caller keeps running and can still spawn
+    let pid = sys_spawn("/APP/hello").expect("Failed to spawn hello");
+    println!("hello exited with {}", sys_wait_pid(pid));
+
+    println!("Spawn failure test passed!");
+
+    0
+}
+
+entry!(main);
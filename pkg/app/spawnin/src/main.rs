@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const APP_PATH: &str = "/APP/SPAWNIN";
+const APP_DIR: &str = "/APP";
+/// Only found from the dir of the apps
+const RELATIVE_PATH: &str = "SPAWNIN";
+
+fn main() -> isize {
+    // the child spawned in the dir of the apps
+    if sys_spawn_depth().0 > 2 {
+        return match sys_open(RELATIVE_PATH, 0) {
+            Some(fd) => {
+                sys_close_file(fd);
+                0
+            }
+            None => 1,
+        };
+    }
+
+    assert_eq!(sys_open(RELATIVE_PATH, 0), None, "Found from the root dir");
+
+    let child = sys_spawn_in(APP_PATH, APP_DIR).expect("Failed to spawn");
+    assert_eq!(sys_wait_pid(child), 0, "The child cannot open the relative path");
+
+    // the caller keeps its own working dir
+    assert_eq!(sys_open(RELATIVE_PATH, 0), None);
+
+    // the working dir must be an existing dir
+    assert_eq!(sys_spawn_in(APP_PATH, "/NODIR"), None);
+    assert_eq!(sys_spawn_in(APP_PATH, APP_PATH), None);
+
+    println!("Spawn in test passed!");
+
+    0
+}
+
+entry!(main);
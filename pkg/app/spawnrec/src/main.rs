@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The binary of this app, every copy spawns the next one
+const SELF_PATH: &str = "/APP/SPAWNREC";
+
+fn main() -> isize {
+    let (depth, max_depth) = sys_spawn_depth();
+
+    match sys_spawn(SELF_PATH) {
+        // the deepest copy reports its depth up the chain
+        None => {
+            println!("Spawn stopped at depth {} (max {})", depth, max_depth);
+            assert_eq!(depth, max_depth);
+            depth as isize
+        }
+        Some(child) => {
+            let deepest = sys_wait_pid(child);
+            assert_eq!(deepest, max_depth as isize);
+            // the copy started by the shell, which is spawned by the kernel
+            if depth == 2 {
+                println!("Spawn depth test passed!");
+            }
+            deepest
+        }
+    }
+}
+
+entry!(main);
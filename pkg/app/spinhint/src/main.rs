@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use lib::*;
+
+extern crate lib;
+
+/// Set by the child, the memory is shared by `sys_fork`
+static FLAG: AtomicBool = AtomicBool::new(false);
+
+fn main() -> isize {
+    // the first hints after being scheduled in only pause
+    sys_yield();
+    assert!(!sys_spin_hint(), "The first hint yields");
+
+    // the hints yield after a bounded number, with a timer switch
+    // resetting the count at most once in between
+    let mut hints = 0;
+    while !sys_spin_hint() {
+        hints += 1;
+        assert!(hints <= SPIN_HINT_LIMIT * 2, "No yield after {} hints", hints);
+    }
+
+    // spin on a flag set by another process, which runs once we yield
+    let child = sys_fork();
+    if child == 0 {
+        FLAG.store(true, Ordering::Release);
+        sys_exit(0);
+    }
+
+    let mut hints = 0;
+    while !FLAG.load(Ordering::Acquire) {
+        sys_spin_hint();
+        hints += 1;
+    }
+    println!("The flag is set after {} hints", hints);
+    assert!(hints <= (SPIN_HINT_LIMIT + 1) * 2);
+    assert_eq!(sys_wait_pid(child), 0);
+
+    println!("SpinHint test passed!");
+
+    0
+}
+
+entry!(main);
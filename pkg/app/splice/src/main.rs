@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The bytes a pipe holds, a larger transfer is short
+const PIPE_CAPACITY: usize = 4096;
+const COUNT: usize = 1000;
+
+/// Drain exactly `buf.len()` bytes from the pipe
+fn read_pipe(fd: u8, buf: &mut [u8]) {
+    let mut read = 0;
+    while read < buf.len() {
+        read += sys_read(fd, &mut buf[read..]).expect("Failed to read pipe");
+    }
+}
+
+/// Fill the pipe with `buf` entirely
+fn write_pipe(fd: u8, buf: &[u8]) {
+    let mut written = 0;
+    while written < buf.len() {
+        written += sys_write(fd, &buf[written..]).expect("Failed to write pipe");
+    }
+}
+
+fn main() -> isize {
+    let (first_read, first_write) = sys_pipe().expect("Failed to create pipe");
+    let (second_read, second_write) = sys_pipe().expect("Failed to create pipe");
+
+    let mut expected = [0u8; PIPE_CAPACITY];
+    for (i, byte) in expected.iter_mut().enumerate() {
+        *byte = (i % 251) as u8;
+    }
+    let mut actual = [0u8; PIPE_CAPACITY];
+
+    // the bytes flow through both pipes in order
+    write_pipe(first_write, &expected[..COUNT]);
+    assert_eq!(sys_splice(first_read, second_write, COUNT / 2), Some(COUNT / 2));
+    assert_eq!(sys_splice(first_read, second_write, COUNT), Some(COUNT - COUNT / 2));
+    // nothing left in the source
+    assert_eq!(sys_splice(first_read, second_write, COUNT), Some(0));
+    read_pipe(second_read, &mut actual[..COUNT]);
+    assert_eq!(&actual[..COUNT], &expected[..COUNT]);
+
+    // short once the target is full, the rest stays in the source
+    write_pipe(second_write, &expected[..COUNT]);
+    write_pipe(first_write, &expected);
+    let free = PIPE_CAPACITY - COUNT;
+    assert_eq!(sys_splice(first_read, second_write, PIPE_CAPACITY), Some(free));
+    read_pipe(second_read, &mut actual);
+    assert_eq!(&actual[..COUNT], &expected[..COUNT]);
+    assert_eq!(&actual[COUNT..], &expected[..free]);
+    assert_eq!(sys_splice(first_read, second_write, PIPE_CAPACITY), Some(COUNT));
+    read_pipe(second_read, &mut actual[..COUNT]);
+    assert_eq!(&actual[..COUNT], &expected[free..]);
+
+    // only from a read end to the write end of another pipe
+    assert_eq!(sys_splice(second_write, first_write, 1), None);
+    assert_eq!(sys_splice(first_read, first_read, 1), None);
+    assert_eq!(sys_splice(second_read, second_write, 1), None);
+    assert_eq!(sys_splice(0, second_write, 1), None);
+
+    // EOF once the write ends of the source are closed
+    write_pipe(first_write, &expected[..10]);
+    sys_close_file(first_write);
+    assert_eq!(sys_splice(first_read, second_write, 100), Some(10));
+    assert_eq!(sys_splice(first_read, second_write, 100), Some(0));
+    read_pipe(second_read, &mut actual[..10]);
+    assert_eq!(&actual[..10], &expected[..10]);
+
+    // the target is broken once its read ends are closed
+    sys_close_file(second_read);
+    assert_eq!(sys_splice(first_read, second_write, 1), None);
+
+    sys_close_file(first_read);
+    sys_close_file(second_write);
+
+    println!("Splice test passed!");
+
+    0
+}
+
+entry!(main);
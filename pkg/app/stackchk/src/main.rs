@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// A frame laid out like the one of a function built with
+/// `-Z stack-protector`, the canary right after the buffer
+#[repr(C)]
+struct Frame {
+    buf: [u8; 16],
+    canary: usize,
+}
+
+#[inline(never)]
+fn copy_unchecked(input: &[u8]) {
+    let mut frame = Frame {
+        buf: [0; 16],
+        canary: unsafe { __stack_chk_guard },
+    };
+    // the same unchecked copy the protector is there for, it runs past
+    // the buffer into the canary, which still lies inside the frame
+    let dst = &mut frame as *mut Frame as *mut u8;
+    for (i, byte) in input.iter().enumerate() {
+        unsafe { dst.add(i).write_volatile(*byte) };
+    }
+    // the check the compiler puts before the return
+    if unsafe { core::ptr::read_volatile(&frame.canary) } != unsafe { __stack_chk_guard } {
+        __stack_chk_fail();
+    }
+}
+
+fn main() -> isize {
+    let guard = unsafe { __stack_chk_guard };
+    assert_eq!(guard as u64, sys_stack_canary(), "The guard is not installed");
+    assert_ne!(guard, 0);
+
+    // a copy that fits leaves the canary alone
+    copy_unchecked(&[0x41; 16]);
+
+    let child = sys_fork();
+    if child == 0 {
+        // the guard is kept with the memory on fork
+        assert_eq!(sys_stack_canary(), guard as u64);
+        copy_unchecked(&[0x41; 24]);
+        sys_exit(0);
+    }
+    assert_eq!(sys_wait_pid(child), STACK_CHK_EXIT_CODE, "The overrun is missed");
+
+    println!("Stack check test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const APP_PATH: &str = "/APP/STACKSZ";
+/// Each level takes a bit more than a KiB, about 64 KiB in all
+const DEPTH: usize = 64;
+const FRAME_SIZE: usize = 1024;
+const TINY_STACK: usize = 16 * 1024;
+const LARGE_STACK: usize = 1024 * 1024;
+
+fn recurse(depth: usize) -> usize {
+    let mut frame = [depth as u8; FRAME_SIZE];
+    let frame = core::hint::black_box(&mut frame);
+    match depth {
+        0 => frame[0] as usize,
+        _ => recurse(depth - 1) + frame[FRAME_SIZE - 1] as usize,
+    }
+}
+
+fn main() -> isize {
+    // the child recurses on the stack given by the parent
+    if sys_spawn_depth().0 > 2 {
+        let sum = recurse(DEPTH);
+        assert_eq!(sum, DEPTH * (DEPTH + 1) / 2);
+        return 0;
+    }
+
+    let child = sys_spawn_stack(APP_PATH, TINY_STACK).expect("Failed to spawn");
+    assert_eq!(sys_wait_pid(child), KILLED_EXIT_CODE, "The tiny stack is not limited");
+
+    let child = sys_spawn_stack(APP_PATH, LARGE_STACK).expect("Failed to spawn");
+    assert_eq!(sys_wait_pid(child), 0, "The large stack is not enough");
+
+    // the size cannot be over the stack slot of a process
+    assert_eq!(sys_spawn_stack(APP_PATH, usize::MAX / 2), None);
+
+    println!("Stack size test passed!");
+
+    0
+}
+
+entry!(main);
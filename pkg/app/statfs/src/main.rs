@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const FILE_PATH: &str = "/APP/STATFS.TMP";
+const FILE_SIZE: usize = 64 * 1024;
+
+fn main() -> isize {
+    // left by an earlier run
+    sys_unlink(FILE_PATH);
+
+    let before = sys_statfs().expect("No filesystem is mounted");
+    println!("Before: {:?}", before);
+    assert!(before.block_size > 0);
+    assert!(before.free_blocks <= before.total_blocks);
+
+    let fd = sys_open(FILE_PATH, O_CREAT | O_RDWR).expect("Failed to create the file");
+    let chunk = [0x5Au8; 4096];
+    for _ in 0..FILE_SIZE / chunk.len() {
+        assert_eq!(sys_write_all(fd, &chunk), Some(chunk.len()));
+    }
+    sys_close_file(fd);
+
+    let after = sys_statfs().unwrap();
+    println!("After: {:?}", after);
+    assert_eq!(after.total_blocks, before.total_blocks);
+    assert_eq!(after.block_size, before.block_size);
+
+    let expected = (FILE_SIZE as u64).div_ceil(before.block_size);
+    let used = before.free_blocks - after.free_blocks;
+    // a sub dir may take one more cluster for its entries
+    assert!(
+        (expected..=expected + 1).contains(&used),
+        "{} blocks are used, {} are expected",
+        used,
+        expected
+    );
+
+    assert_eq!(sys_unlink(FILE_PATH), 0);
+    assert_eq!(sys_statfs().unwrap().free_blocks, before.free_blocks);
+
+    println!("StatFs test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,25 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    // the app is spawned by the shell, which is the init process,
+    // the lines are checked by the kernel at boot, run `strace on` to see them
+    assert!(!sys_set_strace(true), "Only init may trace the syscalls");
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(sys_set_strace(true) as isize);
+    }
+    assert_eq!(sys_wait_pid(child), 0);
+    assert!(!sys_set_strace(false));
+
+    println!("Strace test passed!");
+
+    0
+}
+
+entry!(main);
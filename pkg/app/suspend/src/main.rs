@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const TARGET_PATH: &str = "/APP/SUSPTGT";
+/// The queue shared with `susptgt`
+const QUEUE_KEY: u32 = 0x5350;
+
+fn recv() -> [u8; 8] {
+    let mut buf = [0u8; 8];
+    assert!(sys_msg_recv(QUEUE_KEY, &mut buf) > 0, "Failed to receive");
+    buf
+}
+
+fn main() -> isize {
+    assert!(sys_msg_get(QUEUE_KEY));
+
+    let pid = sys_spawn_suspended(TARGET_PATH).expect("Failed to spawn");
+    println!("Spawned #{} suspended", pid);
+
+    // a running child would have sent its message long before
+    sleep(1);
+    assert!(sys_msg_send(QUEUE_KEY, b"parent", 0) > 0);
+
+    assert!(sys_cont(pid));
+    // only a suspended process can be continued
+    assert!(!sys_cont(pid));
+
+    assert_eq!(sys_wait_pid(pid), 0);
+
+    // the child ran only after it was continued
+    assert_eq!(&recv()[..6], b"parent");
+    assert_eq!(&recv()[..5], b"child");
+
+    println!("Suspended spawn test passed!");
+
+    0
+}
+
+entry!(main);
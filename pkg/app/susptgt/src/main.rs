@@ -0,0 +1,19 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The queue shared with the `suspend` test
+const QUEUE_KEY: u32 = 0x5350;
+
+fn main() -> isize {
+    // tell the spawner that this process has started running
+    sys_msg_get(QUEUE_KEY);
+    sys_msg_send(QUEUE_KEY, b"child", 0);
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+// the binary of this app, which is sure to exist
+const FILE_PATH: &str = "/APP/SYMLINK";
+const LINK_PATH: &str = "/APP/abs.lnk";
+const REL_PATH: &str = "/APP/rel.lnk";
+const DIR_LINK: &str = "/BIN.LNK";
+const LOOP_PATH: &str = "/APP/loop.lnk";
+const PING_PATH: &str = "/APP/ping.lnk";
+const PONG_PATH: &str = "/APP/pong.lnk";
+
+/// The first bytes of the file at `path`
+fn read_magic(path: &str) -> SyscallResult<[u8; 4]> {
+    let fd = sys_open_result(path, 0)?;
+    let mut magic = [0u8; 4];
+    assert_eq!(sys_read(fd, &mut magic), Some(4));
+    assert!(sys_close_file(fd));
+    Ok(magic)
+}
+
+fn main() -> isize {
+    // to a file, absolute or relative to the dir of the link
+    assert_eq!(sys_symlink(FILE_PATH, LINK_PATH), 0);
+    assert_eq!(sys_symlink("symlink", REL_PATH), 0);
+    assert_eq!(read_magic(LINK_PATH), Ok(*b"\x7fELF"));
+    assert_eq!(read_magic(REL_PATH), Ok(*b"\x7fELF"));
+    assert_eq!(sys_readlink(LINK_PATH).as_deref(), Some(FILE_PATH));
+    assert_eq!(sys_readlink(REL_PATH).as_deref(), Some("symlink"));
+    println!("Opened through the links.");
+
+    // to a dir, followed in the middle of a path, even by another link
+    assert_eq!(sys_symlink("/APP", DIR_LINK), 0);
+    assert_eq!(read_magic("/BIN.LNK/SYMLINK"), Ok(*b"\x7fELF"));
+    assert_eq!(read_magic("/bin.lnk/abs.lnk"), Ok(*b"\x7fELF"));
+    assert_eq!(sys_readlink("/BIN.LNK/rel.lnk").as_deref(), Some("symlink"));
+
+    // loops fail instead of hanging
+    assert_eq!(sys_symlink(LOOP_PATH, LOOP_PATH), 0);
+    assert_eq!(sys_symlink(PONG_PATH, PING_PATH), 0);
+    assert_eq!(sys_symlink(PING_PATH, PONG_PATH), 0);
+    assert_eq!(sys_open_result(LOOP_PATH, 0), Err(SysError::Loop));
+    assert_eq!(sys_open_result(PING_PATH, 0), Err(SysError::Loop));
+    assert_eq!(sys_readlink(LOOP_PATH).as_deref(), Some(LOOP_PATH));
+    println!("Looping links are caught.");
+
+    // nothing is replaced, and only links are read
+    assert_eq!(sys_symlink(FILE_PATH, LINK_PATH), EEXIST);
+    assert_eq!(sys_symlink("/APP", FILE_PATH), EEXIST);
+    assert_eq!(sys_symlink("/APP", "/MISSING/x.lnk"), ENOENT);
+    assert_eq!(sys_readlink(FILE_PATH), None);
+    assert_eq!(sys_readlink("/APP/missing"), None);
+
+    // unlinking a link keeps its target
+    for link in [LINK_PATH, REL_PATH, DIR_LINK, LOOP_PATH, PING_PATH, PONG_PATH] {
+        assert_eq!(sys_unlink(link), 0);
+        assert_eq!(sys_readlink(link), None);
+    }
+    assert_eq!(sys_open_result(LINK_PATH, 0), Err(SysError::NotFound));
+    assert_eq!(read_magic(FILE_PATH), Ok(*b"\x7fELF"));
+
+    println!("Symlink test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,82 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The bytes the slowest reader can fall behind, the same as the kernel
+const TEE_CAPACITY: usize = 4096;
+const STREAM_LEN: usize = 12000;
+
+fn byte_at(offset: usize) -> u8 {
+    (offset % 251) as u8
+}
+
+/// Read up to `max` bytes from `fd`, checking they continue the stream at `offset`
+fn read_and_check(fd: u8, offset: &mut usize, max: usize) {
+    let mut buf = [0u8; 512];
+    let len = sys_read(fd, &mut buf[..max]).expect("Failed to read");
+    for (i, byte) in buf[..len].iter().enumerate() {
+        assert_eq!(*byte, byte_at(*offset + i), "Wrong byte at {}", *offset + i);
+    }
+    *offset += len;
+}
+
+fn main() -> isize {
+    // both readers get the whole stream, each at its own pace
+    let (write_fd, read_fds) = sys_tee_pipe(2).expect("Failed to create the tee pipe");
+    let (fast, slow) = (read_fds[0], read_fds[1]);
+
+    let mut written = 0;
+    let (mut fast_offset, mut slow_offset) = (0, 0);
+    while written < STREAM_LEN || slow_offset < STREAM_LEN {
+        if written < STREAM_LEN {
+            let chunk: [u8; 256] = core::array::from_fn(|i| byte_at(written + i));
+            let len = core::cmp::min(chunk.len(), STREAM_LEN - written);
+            written += sys_write(write_fd, &chunk[..len]).expect("Failed to write");
+        }
+        read_and_check(fast, &mut fast_offset, 512);
+        read_and_check(slow, &mut slow_offset, 96);
+        assert!(written - slow_offset <= TEE_CAPACITY);
+    }
+    assert_eq!(fast_offset, STREAM_LEN);
+    assert!(sys_close_file(write_fd));
+    assert_eq!(sys_read(fast, &mut [0u8; 16]), Some(0));
+    assert!(sys_close_file(fast) && sys_close_file(slow));
+
+    // the slowest reader holds the writer back until it is closed
+    let (write_fd, read_fds) = sys_tee_pipe(2).expect("Failed to create the tee pipe");
+    let (fast, slow) = (read_fds[0], read_fds[1]);
+    let chunk = [0x5Au8; 512];
+    let mut written = 0;
+    while let Some(len) = sys_write(write_fd, &chunk) {
+        if len == 0 {
+            break;
+        }
+        written += len;
+    }
+    assert_eq!(written, TEE_CAPACITY);
+
+    let mut buf = [0u8; TEE_CAPACITY];
+    assert_eq!(sys_read(fast, &mut buf), Some(TEE_CAPACITY));
+    assert_eq!(sys_write(write_fd, &chunk), Some(0), "The slow reader is skipped");
+
+    assert!(sys_close_file(slow));
+    assert_eq!(sys_write(write_fd, &chunk), Some(chunk.len()));
+    assert_eq!(sys_read(fast, &mut buf), Some(chunk.len()));
+
+    assert!(sys_close_file(fast));
+    assert_eq!(sys_write(write_fd, &chunk), None);
+    assert!(sys_close_file(write_fd));
+
+    // bad reader counts
+    assert!(sys_tee_pipe(0).is_none());
+    assert!(sys_tee_pipe(9).is_none());
+
+    println!("Tee pipe test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const THREADS: usize = 2;
+const ROUNDS: usize = 20;
+
+static LOCK: Semaphore = Semaphore::new(0x7468);
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+static SUM: AtomicUsize = AtomicUsize::new(0);
+
+fn work(arg: usize) {
+    // the locals live on the fresh stack of the thread
+    let mut local = [0usize; 64];
+    for _ in 0..ROUNDS {
+        assert!(LOCK.wait());
+        // a racy increment, only correct under the lock
+        let value = COUNTER.load(Ordering::SeqCst);
+        sys_yield();
+        COUNTER.store(value + 1, Ordering::SeqCst);
+        assert!(LOCK.signal());
+        local[value % local.len()] += arg;
+    }
+    SUM.fetch_add(local.iter().sum(), Ordering::SeqCst);
+}
+
+fn main() -> isize {
+    assert!(LOCK.init(1));
+
+    let mut tids = [0u16; THREADS];
+    for (i, tid) in tids.iter_mut().enumerate() {
+        *tid = sys_thread_create(work, i + 1).expect("Failed to create the thread");
+    }
+
+    for tid in tids.iter() {
+        assert_eq!(sys_thread_join(*tid), 0);
+    }
+
+    let count = COUNTER.load(Ordering::SeqCst);
+    println!("Counter: {}, sum: {}", count, SUM.load(Ordering::SeqCst));
+    assert_eq!(count, THREADS * ROUNDS, "The semaphore let both in");
+    // each thread adds its argument once per round
+    assert_eq!(SUM.load(Ordering::SeqCst), (1..=THREADS).sum::<usize>() * ROUNDS);
+    assert!(LOCK.remove());
+
+    println!("Thread test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,46 @@
+#![no_std]
+#![no_main]
+
+use lib::{vec::Vec, *};
+
+extern crate lib;
+
+const TAG: u32 = 0x7ace;
+const SEM_KEY: u32 = 0x7ace;
+
+fn main() -> isize {
+    assert!(sys_new_sem(SEM_KEY, 0));
+
+    // the child marks in between the two marks of the parent
+    let parent = sys_get_pid();
+    let child = sys_fork();
+    if child == 0 {
+        assert!(sys_sem_wait(SEM_KEY));
+        sys_trace_mark(TAG, 2);
+        sys_exit(0);
+    }
+
+    sys_trace_mark(TAG, 1);
+    assert!(sys_sem_signal(SEM_KEY));
+    assert_eq!(sys_wait_pid(child), 0);
+    sys_trace_mark(TAG, 3);
+
+    sys_dump_trace();
+    let mut buf = [TraceEntry::default(); 64];
+    let count = sys_dump_trace_into(&mut buf);
+    // other processes may have marked before, only the latest are ours
+    let marks: Vec<(u16, u64)> = buf[..count]
+        .iter()
+        .filter(|entry| entry.tag == TAG)
+        .map(|entry| (entry.pid, entry.value))
+        .collect();
+    assert!(marks.len() >= 3, "Markers are lost: {:?}", marks);
+    assert_eq!(&marks[marks.len() - 3..], &[(parent, 1), (child, 2), (parent, 3)]);
+    assert!(sys_remove_sem(SEM_KEY));
+
+    println!("Trace mark test passed!");
+
+    0
+}
+
+entry!(main);
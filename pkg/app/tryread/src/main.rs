@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const ROUNDS: usize = 100;
+
+fn main() -> isize {
+    let mut buf = [0u8; 16];
+
+    // nothing is typed, so every try returns at once with nothing
+    let start = sys_uptime();
+    for _ in 0..ROUNDS {
+        assert_eq!(sys_try_read(0, &mut buf), 0);
+    }
+    let ticks = sys_uptime() - start;
+    println!("{} tries on stdin took {} ticks", ROUNDS, ticks);
+    assert!(ticks < 10, "A try read has waited for input");
+
+    // a pipe gives all the buffered bytes
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create a pipe");
+    assert_eq!(sys_try_read(read_fd, &mut buf), 0);
+    assert_eq!(sys_write(write_fd, b"hello"), Some(5));
+    assert_eq!(sys_try_read(read_fd, &mut buf), 5);
+    assert_eq!(&buf[..5], b"hello");
+    assert_eq!(sys_try_read(read_fd, &mut buf), 0);
+    sys_close_file(read_fd);
+    sys_close_file(write_fd);
+
+    assert_eq!(sys_try_read(read_fd, &mut buf), SysError::BadFd.code());
+    assert_eq!(sys_try_read(0, &mut []), 0);
+
+    println!("Try read test passed!");
+
+    0
+}
+
+entry!(main);
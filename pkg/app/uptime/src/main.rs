@@ -0,0 +1,28 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+fn main() -> isize {
+    let mut last = sys_uptime();
+    println!("Uptime: {} ticks", last);
+
+    for _ in 0..5 {
+        // spin for a while, the timer keeps ticking meanwhile
+        for i in 0..0x100000 {
+            core::hint::black_box(i);
+        }
+
+        let now = sys_uptime();
+        assert!(now >= last, "Uptime went backwards: {} -> {}", last, now);
+        last = now;
+    }
+
+    println!("Uptime: {} ticks", last);
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 0x1000;
+const PAGES: usize = 4;
+const CHILD_EXIT_CODE: isize = 6;
+
+/// Check `addr` is mapped for the user with the flags, returns its physical address
+fn check_mapped(addr: usize, flags: u64) -> u64 {
+    let mapping = sys_virt_to_phys(addr).expect("The address is not mapped");
+    let expected = PAGE_PRESENT | PAGE_USER | flags;
+    assert_eq!(mapping.flags & expected, expected, "Bad flags: {:#x}", mapping.flags);
+    assert_eq!(mapping.phys as usize % PAGE_SIZE, addr % PAGE_SIZE);
+    mapping.phys
+}
+
+fn main() -> isize {
+    let heap_end = sys_brk(None).unwrap();
+    let base = (heap_end + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    let new_end = sys_brk(Some(base + PAGE_SIZE * PAGES)).expect("Failed to grow");
+    let heap = unsafe { core::slice::from_raw_parts_mut(base as *mut u8, new_end - base) };
+    heap.fill(0x5A);
+
+    // the heap is mapped writable for the user, byte by byte within a frame
+    let phys = check_mapped(base, PAGE_WRITABLE);
+    println!("Heap {:#x} -> {:#x}", base, phys);
+    assert_eq!(check_mapped(base + 100, PAGE_WRITABLE), phys + 100);
+    let last = base + PAGE_SIZE * (PAGES - 1);
+    check_mapped(last, PAGE_WRITABLE | PAGE_DIRTY);
+    assert_eq!(sys_virt_to_phys(new_end), None);
+
+    // the frame is given back, and a new one is mapped on the next access
+    assert!(sys_madvise_dontneed(last as *mut u8, PAGE_SIZE));
+    assert_eq!(sys_virt_to_phys(last), None);
+    heap[PAGE_SIZE * (PAGES - 1)] = 1;
+    check_mapped(last, PAGE_WRITABLE);
+
+    // a copy-on-write child shares the frame until it writes the page
+    let child = sys_fork_cow();
+    assert_ne!(child, FORK_FAILED, "Failed to fork");
+    if child == 0 {
+        let mapping = sys_virt_to_phys(base).expect("The heap is not mapped");
+        assert_eq!(mapping.phys, phys);
+        assert_eq!(mapping.flags & (PAGE_COW | PAGE_WRITABLE), PAGE_COW);
+        heap[0] = 2;
+        let mapping = sys_virt_to_phys(base).expect("The heap is not mapped");
+        assert_eq!(mapping.flags & (PAGE_COW | PAGE_WRITABLE), PAGE_WRITABLE);
+        sys_exit(CHILD_EXIT_CODE);
+    }
+    // only self and the descendants can be looked up
+    assert_eq!(sys_virt_to_phys_of(1, base), None);
+    assert_eq!(sys_wait_pid(child), CHILD_EXIT_CODE);
+    assert_eq!(heap[0], 0x5A);
+
+    assert_eq!(sys_virt_to_phys(0), None);
+    assert_eq!(sys_brk(Some(heap_end)), Some(heap_end));
+
+    println!("Virt to phys test passed!");
+
+    0
+}
+
+entry!(main);
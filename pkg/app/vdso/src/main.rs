@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const SAMPLES: usize = 1000;
+/// The ticks for the page to be seen advancing
+const ADVANCE_TICKS: u64 = 5;
+
+/// Check the page against the syscall, a tick may pass in between
+fn agree_with_uptime() {
+    for _ in 0..SAMPLES {
+        let before = sys_uptime();
+        let fast = read_time_fast();
+        let after = sys_uptime();
+        assert!(before <= fast && fast <= after, "{} not in {}..={}", fast, before, after);
+        assert!(after - before <= 1, "More than one tick in between");
+    }
+}
+
+/// The fast ticks over a second of the wall clock
+fn ticks_in_second() -> u64 {
+    let next_second = |time: u64| {
+        while sys_time() == time {
+            core::hint::spin_loop();
+        }
+        (sys_time(), read_time_fast())
+    };
+    // start on the edge of a second
+    let (time, start) = next_second(sys_time());
+    let (_, end) = next_second(time);
+    end - start
+}
+
+fn main() -> isize {
+    agree_with_uptime();
+
+    // the page is live, without a syscall in the loop
+    let start = read_time_fast();
+    while read_time_fast() < start + ADVANCE_TICKS {
+        core::hint::spin_loop();
+    }
+
+    // the frequency matches the wall clock
+    let freq = read_clock_freq();
+    let ticks = ticks_in_second();
+    println!("Clock page: {} ticks per second, {} counted", freq, ticks);
+    assert!(freq > 0, "The frequency is not calibrated");
+    assert!(ticks.abs_diff(freq) <= freq / 4, "The frequency is off");
+
+    // a copy-on-write fork sees the same page
+    let child = sys_fork();
+    if child == 0 {
+        agree_with_uptime();
+        sys_exit(0);
+    }
+    assert_ne!(child, FORK_FAILED);
+    assert_eq!(sys_wait_pid(child), 0);
+
+    println!("Clock page test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,86 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lib::*;
+
+extern crate lib;
+
+const TARGET: &str = "/APP/HELLO";
+/// The exit code of `hello`
+const TARGET_EXIT_CODE: isize = 233;
+const EXIT_CODE: isize = 7;
+
+// the child runs on the stack of the parent, which may reuse the slots of
+// its locals, so the values are kept in the shared memory instead
+static PARENT: AtomicUsize = AtomicUsize::new(0);
+static IN_USE_BEFORE: AtomicUsize = AtomicUsize::new(0);
+static IN_USE_CHILD: AtomicUsize = AtomicUsize::new(0);
+// the steps of the child, seen by the parent once resumed
+static STEPS: AtomicUsize = AtomicUsize::new(0);
+
+fn frames_in_use() -> usize {
+    let stats = sys_frame_stats();
+    stats.used - stats.recycled
+}
+
+/// Record what the child sees before it leaves the memory of the parent
+#[inline(never)]
+fn child_check() {
+    IN_USE_CHILD.store(frames_in_use(), Ordering::SeqCst);
+    let parent = PARENT.load(Ordering::SeqCst) as u16;
+    if sys_block_reason(parent) == Some(BlockReason::Vfork) {
+        STEPS.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[inline(never)]
+fn child_exec() -> ! {
+    child_check();
+    sys_exec(TARGET);
+    sys_exit(-1)
+}
+
+#[inline(never)]
+fn child_exit() -> ! {
+    child_check();
+    sys_exit(EXIT_CODE)
+}
+
+fn expect_child(steps: usize) {
+    assert_eq!(STEPS.load(Ordering::SeqCst), steps, "The parent is not blocked");
+    let before = IN_USE_BEFORE.load(Ordering::SeqCst);
+    let child = IN_USE_CHILD.load(Ordering::SeqCst);
+    println!("Frames in use: {} before, {} in the child", before, child);
+    assert_eq!(child, before, "The memory is copied");
+}
+
+fn main() -> isize {
+    PARENT.store(sys_get_pid() as usize, Ordering::SeqCst);
+
+    // the parent resumes once the child execs
+    IN_USE_BEFORE.store(frames_in_use(), Ordering::SeqCst);
+    let child = sys_vfork();
+    if child == 0 {
+        child_exec();
+    }
+    assert_ne!(child, FORK_FAILED);
+    expect_child(1);
+    assert_eq!(sys_wait_pid(child), TARGET_EXIT_CODE);
+
+    // or once the child exits
+    IN_USE_BEFORE.store(frames_in_use(), Ordering::SeqCst);
+    let child = sys_vfork();
+    if child == 0 {
+        child_exit();
+    }
+    assert_ne!(child, FORK_FAILED);
+    expect_child(2);
+    assert_eq!(sys_wait_pid(child), EXIT_CODE);
+
+    println!("Vfork test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const PAGE_SIZE: usize = 0x1000;
+const CHILD_PAGES: usize = 16;
+const CHILD_EXIT_CODE: isize = 5;
+
+fn main() -> isize {
+    let child = sys_fork();
+    if child == 0 {
+        // grow the heap, touch it and give it back before exiting,
+        // only the peak is left to be seen
+        let heap_end = sys_brk(None).unwrap();
+        let new_end = sys_brk(Some(heap_end + PAGE_SIZE * CHILD_PAGES)).expect("Failed to grow");
+        let len = new_end - heap_end;
+        let heap = unsafe { core::slice::from_raw_parts_mut(heap_end as *mut u8, len) };
+        heap.fill(0x5A);
+        assert_eq!(sys_brk(Some(heap_end)), Some(heap_end));
+
+        for _ in 0..4 {
+            sys_yield();
+        }
+        sys_exit(CHILD_EXIT_CODE);
+    }
+
+    let (code, usage) = sys_wait4(child);
+    println!("Child #{} exited with {}: {:?}", child, code, usage);
+    assert_eq!(code, CHILD_EXIT_CODE);
+    assert!(usage.ticks > 0, "The ticks of the child are not counted");
+    assert!(
+        usage.peak_memory >= PAGE_SIZE * CHILD_PAGES,
+        "The peak memory misses the heap growth"
+    );
+
+    println!("Wait4 test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+/// The seconds each child sleeps before exiting
+const DELAYS: [u64; 3] = [3, 1, 2];
+
+fn main() -> isize {
+    let mut children = [0u16; DELAYS.len()];
+    for (i, child) in children.iter_mut().enumerate() {
+        *child = sys_fork();
+        if *child == 0 {
+            sleep(DELAYS[i]);
+            sys_exit(i as isize);
+        }
+    }
+
+    // the children are collected in the order they exit
+    let mut order: [usize; 3] = [0, 1, 2];
+    order.sort_by_key(|i| DELAYS[*i]);
+    for i in order {
+        let (pid, code) = sys_waitany().expect("A child should be left");
+        println!("Child #{} exited with {}", pid, code);
+        assert_eq!(pid, children[i]);
+        assert_eq!(code, i as isize);
+    }
+
+    assert_eq!(sys_waitany(), None);
+
+    println!("Waitany test passed!");
+
+    0
+}
+
+entry!(main);
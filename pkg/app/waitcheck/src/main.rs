@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const EXIT_CODE: isize = 7;
+/// A pid that is never allocated during the test
+const UNUSED_PID: u16 = 60000;
+
+fn main() -> isize {
+    // waiting on itself returns at once
+    assert_eq!(sys_wait_pid(sys_get_pid()), SysError::NotFound.code());
+    println!("Self wait returned {}.", SysError::NotFound.code());
+
+    assert_eq!(sys_wait_pid(UNUSED_PID), SysError::NotFound.code());
+    println!("Wait on a pid that never existed returned {}.", SysError::NotFound.code());
+
+    let child = sys_fork();
+    if child == 0 {
+        sys_exit(EXIT_CODE);
+    }
+
+    // the sibling returns only after the child is dead
+    let sibling = sys_fork();
+    if sibling == 0 {
+        sys_exit(sys_wait_pid(child));
+    }
+    assert_eq!(sys_wait_pid(sibling), EXIT_CODE);
+
+    // the child is dead now, its exit code is returned without blocking
+    assert_eq!(sys_wait_pid(child), EXIT_CODE);
+    println!("Wait on a dead process returned {}.", EXIT_CODE);
+
+    println!("Wait check test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const TARGET_KEY: u32 = 0x3a17;
+const WAITER_KEY: u32 = 0x3a20;
+const WAITERS: usize = 3;
+const EXIT_CODE: isize = 7;
+
+fn main() -> isize {
+    let (rfd, wfd) = sys_pipe().expect("Failed to create the pipe");
+    assert!(sys_new_sem(TARGET_KEY, 0));
+    for i in 0..WAITERS {
+        assert!(sys_new_sem(WAITER_KEY + i as u32, 0));
+    }
+
+    // the process waited for, it exits once all the waiters are blocked
+    let target = sys_fork();
+    if target == 0 {
+        assert!(sys_sem_wait(TARGET_KEY));
+        sys_exit(EXIT_CODE);
+    }
+
+    let mut waiters = [0u16; WAITERS];
+    for (i, waiter) in waiters.iter_mut().enumerate() {
+        *waiter = sys_fork();
+        if *waiter == 0 {
+            assert!(sys_sem_wait(WAITER_KEY + i as u32));
+            assert_eq!(sys_wait_pid(target), EXIT_CODE);
+            assert_eq!(sys_write(wfd, &[i as u8]), Some(1));
+            sys_exit(0);
+        }
+    }
+
+    // start to wait in the reversed order of the pids
+    let order: [usize; WAITERS] = core::array::from_fn(|i| WAITERS - 1 - i);
+    for i in order {
+        assert!(sys_sem_signal(WAITER_KEY + i as u32));
+        while sys_block_reason(waiters[i]) != Some(BlockReason::WaitingChild) {
+            sys_yield();
+        }
+    }
+    assert!(sys_sem_signal(TARGET_KEY));
+
+    for waiter in waiters {
+        assert_eq!(sys_wait_pid(waiter), 0);
+    }
+    let mut woken = [0u8; WAITERS];
+    assert_eq!(sys_read(rfd, &mut woken), Some(WAITERS));
+    println!("Woken up in the order {:?}", woken);
+    for (woken, expected) in woken.iter().zip(order) {
+        assert_eq!(*woken as usize, expected, "Not woken up in the waiting order");
+    }
+    assert_eq!(sys_wait_pid(target), EXIT_CODE);
+
+    sys_close_file(rfd);
+    sys_close_file(wfd);
+    assert!(sys_remove_sem(TARGET_KEY));
+    for i in 0..WAITERS {
+        assert!(sys_remove_sem(WAITER_KEY + i as u32));
+    }
+
+    println!("Wait order test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const WAITER_COUNT: usize = 2;
+const EXIT_CODE: isize = 42;
+
+fn main() -> isize {
+    let child = sys_fork();
+
+    if child == 0 {
+        // give the waiters time to block on this process
+        sleep(2);
+        sys_exit(EXIT_CODE);
+    }
+
+    let mut waiters = [0u16; WAITER_COUNT];
+    for waiter in waiters.iter_mut() {
+        *waiter = sys_fork();
+        if *waiter == 0 {
+            let ret = sys_wait_pid(child);
+            println!("Process #{} got exit code {}", sys_get_pid(), ret);
+            sys_exit(ret);
+        }
+    }
+
+    // the parent is the third process waiting for the same child
+    let ret = sys_wait_pid(child);
+    println!("Process #{} got exit code {}", sys_get_pid(), ret);
+    assert_eq!(ret, EXIT_CODE);
+
+    for waiter in waiters {
+        assert_eq!(sys_wait_pid(waiter), EXIT_CODE);
+    }
+
+    println!("All waiters observed the same exit code.");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const SEM_KEY: u32 = 0x3636;
+
+fn is_entry(entry: &AuditEntry, kind: usize, detail: usize) -> bool {
+    entry.kind == kind && entry.detail == detail
+}
+
+/// Whether the latest entries are the block on the semaphore and its wake-up
+fn woken_by_sem(log: &[AuditEntry]) -> bool {
+    match log {
+        [.., blocked, woken] => {
+            is_entry(blocked, AUDIT_BLOCKED, BlockReason::Semaphore.into())
+                && is_entry(woken, AUDIT_WOKEN, WakeSource::Semaphore.into())
+                && blocked.tick <= woken.tick
+        }
+        _ => false,
+    }
+}
+
+fn main() -> isize {
+    assert!(sys_new_sem(SEM_KEY, 0));
+
+    let child = sys_fork();
+    if child == 0 {
+        assert!(sys_sem_wait(SEM_KEY));
+        let mut log = [AuditEntry::default(); 16];
+        let count = sys_wake_log(0, &mut log).expect("Failed to get the log");
+        for entry in &log[..count] {
+            println!("{:>10}: {:?}", entry.tick, entry);
+        }
+        sys_exit(if woken_by_sem(&log[..count]) { 0 } else { 1 });
+    }
+
+    while sys_block_reason(child) != Some(BlockReason::Semaphore) {
+        sys_yield();
+    }
+
+    // the latest entry of the child is the block on the semaphore
+    let mut log = [AuditEntry::default(); 16];
+    let count = sys_wake_log(child, &mut log).expect("Failed to get the log");
+    assert!(count > 0);
+    assert!(is_entry(
+        &log[count - 1],
+        AUDIT_BLOCKED,
+        BlockReason::Semaphore.into()
+    ));
+
+    // the child checks the wake-up follows the block
+    assert!(sys_sem_signal(SEM_KEY));
+    assert_eq!(sys_wait_pid(child), 0, "The wake-up is not logged in order");
+
+    assert!(sys_remove_sem(SEM_KEY));
+    assert_eq!(sys_wake_log(u16::MAX, &mut log), None);
+
+    println!("Wake log test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const DIR_PATH: &str = "/APP";
+const FILE_NAME: &str = "WATCHED.TMP";
+const FILE_PATH: &str = "/APP/WATCHED.TMP";
+/// The events a watch fd queues, see `WATCH_QUEUE_SIZE` of the kernel
+const QUEUE_SIZE: usize = 16;
+
+fn main() -> isize {
+    // left by an earlier run
+    sys_unlink(FILE_PATH);
+
+    assert_eq!(sys_watch_add("/APP/MISSING.TMP"), None);
+    let fd = sys_watch_add(DIR_PATH).expect("Failed to watch the dir");
+    assert!(!sys_poll(&[fd])[0].readable);
+
+    // another process creates & writes the file
+    let child = sys_fork();
+    if child == 0 {
+        let file = sys_open(FILE_PATH, O_CREAT | O_RDWR).expect("Failed to create the file");
+        assert_eq!(sys_write(file, b"watched"), Some(7));
+        sys_close_file(file);
+        sys_exit(0);
+    }
+
+    let event = sys_watch_read(fd).unwrap();
+    println!("Event {:#x} on {}", event.kind, event.name());
+    assert_eq!(event.kind, WATCH_CREATE);
+    assert_eq!(event.name(), FILE_NAME);
+    let event = sys_watch_read(fd).unwrap();
+    assert_eq!((event.kind, event.name()), (WATCH_WRITE, FILE_NAME));
+    assert_eq!(sys_wait_pid(child), 0);
+
+    assert_eq!(sys_unlink(FILE_PATH), 0);
+    let event = sys_watch_read(fd).unwrap();
+    assert_eq!((event.kind, event.name()), (WATCH_UNLINK, FILE_NAME));
+
+    // the events over the queue are dropped for one overflow event
+    for _ in 0..QUEUE_SIZE {
+        let file = sys_open(FILE_PATH, O_CREAT | O_RDWR).expect("Failed to create the file");
+        sys_close_file(file);
+        assert_eq!(sys_unlink(FILE_PATH), 0);
+    }
+    for i in 0..QUEUE_SIZE {
+        let expected = if i % 2 == 0 { WATCH_CREATE } else { WATCH_UNLINK };
+        assert_eq!(sys_watch_read(fd).unwrap().kind, expected);
+    }
+    assert_eq!(sys_watch_read(fd).unwrap().kind, WATCH_OVERFLOW);
+    assert!(!sys_poll(&[fd])[0].readable);
+
+    // not a watch fd
+    assert_eq!(sys_watch_read(0), None);
+    sys_close_file(fd);
+
+    println!("Watch test passed!");
+
+    0
+}
+
+entry!(main);
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const TICK_BUDGET: usize = 20;
+// other ready processes (e.g. the kernel) share the timer with the child
+const MAX_ELAPSED: u64 = TICK_BUDGET as u64 * 4;
+
+fn main() -> isize {
+    let start = sys_uptime();
+    let child = sys_fork();
+
+    if child == 0 {
+        assert!(sys_set_tick_budget(0, TICK_BUDGET));
+        let mut i = 0u64;
+        loop {
+            i = core::hint::black_box(i.wrapping_add(1));
+        }
+    }
+
+    let ret = sys_wait_pid(child);
+    let elapsed = sys_uptime() - start;
+    println!("Process #{} exited with {} after {} ticks", child, ret, elapsed);
+
+    assert_eq!(ret, KILLED_EXIT_CODE);
+    assert!(elapsed > TICK_BUDGET as u64);
+    assert!(elapsed <= MAX_ELAPSED, "The watchdog fired too late");
+
+    println!("Watchdog test passed!");
+
+    0
+}
+
+entry!(main);
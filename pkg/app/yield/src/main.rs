@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+use lib::*;
+
+extern crate lib;
+
+const WORKERS: [u8; 3] = [b'A', b'B', b'C'];
+const ROUNDS: usize = 4;
+const START_KEY: u32 = 0x5949_454c;
+
+fn main() -> isize {
+    let (read_fd, write_fd) = sys_pipe().expect("Failed to create pipe");
+    assert!(sys_msg_get(START_KEY));
+
+    // the fd table is shared with the children
+    let mut children = [0u16; WORKERS.len()];
+    for (child, &name) in children.iter_mut().zip(WORKERS.iter()) {
+        *child = sys_fork();
+        if *child == 0 {
+            // wait until all the workers are ready
+            let mut buf = [0u8; 1];
+            assert_eq!(sys_msg_recv(START_KEY, &mut buf), 1);
+
+            for _ in 0..ROUNDS {
+                sys_write(write_fd, &[name]);
+                sys_yield();
+            }
+            sys_exit(0);
+        }
+    }
+
+    for _ in WORKERS {
+        assert_eq!(sys_msg_send(START_KEY, b"!", 0), 1);
+    }
+
+    for child in children {
+        assert_eq!(sys_wait_pid(child), 0);
+    }
+
+    let mut order = [0u8; WORKERS.len() * ROUNDS];
+    assert_eq!(sys_read(read_fd, &mut order), Some(order.len()));
+    println!("Run order: {}", core::str::from_utf8(&order).unwrap());
+
+    // whoever starts first, every worker runs once per round,
+    // and always in the same order
+    let first = &order[..WORKERS.len()];
+    for worker in WORKERS {
+        assert!(first.contains(&worker), "Worker {} was skipped", worker as char);
+    }
+    for round in order.chunks(WORKERS.len()) {
+        assert_eq!(round, first);
+    }
+
+    println!("Yield test passed!");
+
+    0
+}
+
+entry!(main);
@@ -19,6 +19,9 @@ pub struct Config<'a> {
     pub load_apps: bool,
 
     pub log_level: &'a str,
+
+    /// What the kernel does on panic, "hang" or "exit"
+    pub panic_policy: &'a str,
 }
 
 const DEFAULT_CONFIG: Config = Config {
@@ -30,6 +33,7 @@ const DEFAULT_CONFIG: Config = Config {
     cmdline: "",
     load_apps: false,
     log_level: "Info",
+    panic_policy: "hang",
 };
 
 impl<'a> Config<'a> {
@@ -69,6 +73,7 @@ impl<'a> Config<'a> {
             "cmdline" => self.cmdline = value,
             "load_apps" => self.load_apps = r10 != 0,
             "log_level" => self.log_level = value,
+            "panic_policy" => self.panic_policy = value,
             _ => warn!("undefined config key: {}", key),
         }
     }
@@ -41,6 +41,22 @@ pub struct App<'a> {
 pub type AppList = ArrayVec<App<'static>, MAX_APPLIST_LEN>;
 pub type AppListRef = Option<&'static ArrayVec<App<'static>, 16>>;
 pub type KernelPages = ArrayVec<PageRangeInclusive, 8>;
+
+/// The linear framebuffer of the current GOP mode
+#[derive(Clone, Copy, Debug)]
+pub struct FrameBuffer {
+    /// The physical address of the first pixel
+    pub base: u64,
+    /// The length in bytes
+    pub size: usize,
+    pub width: usize,
+    pub height: usize,
+    /// The pixels per scan line, which may be more than the width
+    pub stride: usize,
+    /// 0 for RGB, 1 for BGR, as `uefi::proto::console::gop::PixelFormat`
+    pub format: u32,
+}
+
 /// This structure represents the information that the bootloader passes to the kernel.
 pub struct BootInfo<'a> {
     /// The memory map
@@ -55,11 +71,20 @@ pub struct BootInfo<'a> {
     /// log level of kernel
     pub log_level: &'a str,
 
+    /// what the kernel does on panic, "hang" or "exit"
+    pub panic_policy: &'a str,
+
+    /// kernel command line, `key=value` pairs split by whitespace
+    pub cmdline: &'a str,
+
     // Loaded apps
     pub loaded_apps: Option<ArrayVec<App<'static>, 16>>,
 
     // Kernel pages
     pub kernel_pages: KernelPages,
+
+    // The framebuffer, None if there is no GOP
+    pub frame_buffer: Option<FrameBuffer>,
 }
 
 /// Get current page table from CR3
@@ -11,6 +11,8 @@ use alloc::vec;
 use arrayvec::ArrayVec;
 use elf::{load_elf, map_pages, map_physical_memory};
 use uefi::prelude::*;
+use uefi::proto::console::gop::PixelFormat;
+use uefi::table::boot::{OpenProtocolAttributes, OpenProtocolParams};
 use x86_64::registers::control::*;
 use ysos_boot::*;
 
@@ -54,6 +56,9 @@ fn efi_main(image: uefi::Handle, mut system_table: SystemTable<Boot>) -> Status
         set_entry(elf.header.pt2.entry_point() as usize);
     }
 
+    let frame_buffer = get_frame_buffer(bs);
+    info!("Frame buffer: {:#x?}", frame_buffer);
+
     // Load MemoryMap
     let max_mmap_size = system_table.boot_services().memory_map_size();
     let mmap_storage = Box::leak(
@@ -141,8 +146,11 @@ fn efi_main(image: uefi::Handle, mut system_table: SystemTable<Boot>) -> Status
         physical_memory_offset: config.physical_memory_offset,
         system_table: runtime,
         log_level: config.log_level,
+        panic_policy: config.panic_policy,
+        cmdline: config.cmdline,
         loaded_apps: apps,
         kernel_pages: kernelpages,
+        frame_buffer,
     };
 
     // Align stack to 8 bytes
@@ -153,3 +161,39 @@ fn efi_main(image: uefi::Handle, mut system_table: SystemTable<Boot>) -> Status
         jump_to_entry(&bootinfo, stacktop);
     }
 }
+
+/// Get the framebuffer of the current mode, shared with the console
+/// of the firmware until the boot services exit
+fn get_frame_buffer(bs: &BootServices) -> Option<FrameBuffer> {
+    let handle = bs.get_handle_for_protocol::<GraphicsOutput>().ok()?;
+    let mut gop = unsafe {
+        bs.open_protocol::<GraphicsOutput>(
+            OpenProtocolParams {
+                handle,
+                agent: bs.image_handle(),
+                controller: None,
+            },
+            OpenProtocolAttributes::GetProtocol,
+        )
+        .ok()?
+    };
+
+    let mode = gop.current_mode_info();
+    let format = match mode.pixel_format() {
+        PixelFormat::Rgb => 0,
+        PixelFormat::Bgr => 1,
+        // no linear framebuffer to draw in
+        _ => return None,
+    };
+    let (width, height) = mode.resolution();
+    let mut fb = gop.frame_buffer();
+
+    Some(FrameBuffer {
+        base: fb.as_mut_ptr() as u64,
+        size: fb.size(),
+        width,
+        height,
+        stride: mode.stride(),
+        format,
+    })
+}
@@ -131,11 +131,18 @@ impl AtaBus {
     /// Writes the given command
     ///
     /// reference: https://wiki.osdev.org/ATA_PIO_Mode#28_bit_PIO
-    fn write_command(&mut self, drive: u8, block: u32, cmd: AtaCommand) -> storage::Result<()> {
+    ///
+    /// `count` is the number of sectors to transfer, 0 stands for 256
+    fn write_command(
+        &mut self,
+        drive: u8,
+        block: u32,
+        count: u8,
+        cmd: AtaCommand,
+    ) -> storage::Result<()> {
         let bytes = block.to_le_bytes();
         unsafe {
-            // just 1 sector for current implementation
-            self.sector_count.write(1);
+            self.sector_count.write(count);
 
             // store the LBA28 address into four 8-bit registers
             // enable LBA28 mode by setting the drive register
@@ -176,7 +183,7 @@ impl AtaBus {
 
         // use `AtaCommand::IdentifyDevice` to identify the drive
         // call `write_command` with `drive` and `0` as the block number
-        self.write_command(drive, 0, AtaCommand::IdentifyDevice)
+        self.write_command(drive, 0, 1, AtaCommand::IdentifyDevice)
             .expect("");
 
         if self.status().is_empty() {
@@ -201,7 +208,8 @@ impl AtaBus {
         })
     }
 
-    /// Reads a block from the given drive and block number into the given buffer.
+    /// Reads the blocks from the given drive and block number into the given buffer,
+    /// one block for each `SECTOR_SIZE` bytes, up to `MAX_SECTORS` blocks in one command.
     ///
     /// reference: https://wiki.osdev.org/ATA_PIO_Mode#28_bit_PIO
     /// reference: https://wiki.osdev.org/IDE#Read.2FWrite_From_ATA_Drive
@@ -211,12 +219,21 @@ impl AtaBus {
         block: u32,
         buf: &mut [u8],
     ) -> storage::Result<()> {
-        self.write_command(drive, block, AtaCommand::ReadPio)?;
-
-        // read the data from the data port into the buffer
-        for chunk in buf.chunks_mut(2) {
-            let data = self.read_data().to_le_bytes();
-            chunk.copy_from_slice(&data)
+        let count = buf.len() / SECTOR_SIZE;
+        debug_assert!((1..=MAX_SECTORS).contains(&count) && buf.len() % SECTOR_SIZE == 0);
+        self.write_command(drive, block, count as u8, AtaCommand::ReadPio)?;
+
+        // read the data from the data port into the buffer,
+        // the drive gets each of the following sectors ready in turn
+        for (i, sector) in buf.chunks_mut(SECTOR_SIZE).enumerate() {
+            if i > 0 {
+                self.poll(AtaStatus::BUSY, false);
+                self.poll(AtaStatus::DATA_REQUEST_READY, true);
+            }
+            for chunk in sector.chunks_mut(2) {
+                let data = self.read_data().to_le_bytes();
+                chunk.copy_from_slice(&data)
+            }
         }
 
         if self.is_error() {
@@ -233,7 +250,7 @@ impl AtaBus {
     /// reference: https://wiki.osdev.org/ATA_PIO_Mode#28_bit_PIO
     /// reference: https://wiki.osdev.org/IDE#Read.2FWrite_From_ATA_Drive
     pub(super) fn write_pio(&mut self, drive: u8, block: u32, buf: &[u8]) -> storage::Result<()> {
-        self.write_command(drive, block, AtaCommand::WritePio)?;
+        self.write_command(drive, block, 1, AtaCommand::WritePio)?;
 
         // write the data from the buffer into the data port
         for chunk in buf.chunks(2) {
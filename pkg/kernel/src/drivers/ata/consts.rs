@@ -4,6 +4,11 @@
 
 use alloc::boxed::Box;
 
+/// The size of a sector in bytes
+pub(super) const SECTOR_SIZE: usize = 512;
+/// The maximum number of sectors transferred by a single command
+pub(super) const MAX_SECTORS: usize = 256;
+
 bitflags! {
     /// The possible error values found in an ATA drive's error port.
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -7,9 +7,10 @@
 mod bus;
 mod consts;
 
-use alloc::{boxed::Box, string::String};
+use alloc::{boxed::Box, string::String, vec};
 use bus::AtaBus;
-use consts::AtaDeviceType;
+use consts::{AtaDeviceType, MAX_SECTORS, SECTOR_SIZE};
+use core::sync::atomic::{AtomicUsize, Ordering};
 use spin::Mutex;
 
 pub const SERIAL: usize = 20;
@@ -32,6 +33,23 @@ lazy_static! {
     };
 }
 
+// the read commands issued to the drives, and the sectors they transferred
+static READ_COMMANDS: AtomicUsize = AtomicUsize::new(0);
+static READ_SECTORS: AtomicUsize = AtomicUsize::new(0);
+
+/// The reads issued to the drives since boot
+pub fn disk_stats() -> syscall_def::DiskStats {
+    syscall_def::DiskStats {
+        reads: READ_COMMANDS.load(Ordering::Relaxed),
+        sectors: READ_SECTORS.load(Ordering::Relaxed),
+    }
+}
+
+fn count_read(sectors: usize) {
+    READ_COMMANDS.fetch_add(1, Ordering::Relaxed);
+    READ_SECTORS.fetch_add(sectors, Ordering::Relaxed);
+}
+
 #[derive(Clone)]
 pub struct AtaDrive {
     pub bus: u8,
@@ -95,11 +113,29 @@ impl BlockDevice<Block512> for AtaDrive {
     }
 
     fn read_block(&self, offset: usize, block: &mut Block512) -> storage::Result<()> {
+        count_read(1);
         BUSES[self.bus as usize]
             .lock()
             .read_pio(self.drive, offset as u32, block.as_mut())
     }
 
+    fn read_blocks(&self, offset: usize, blocks: &mut [Block512]) -> storage::Result<()> {
+        let mut buf = vec![0u8; SECTOR_SIZE * MAX_SECTORS.min(blocks.len())];
+        for (i, chunk) in blocks.chunks_mut(MAX_SECTORS).enumerate() {
+            let buf = &mut buf[..SECTOR_SIZE * chunk.len()];
+            count_read(chunk.len());
+            BUSES[self.bus as usize].lock().read_pio(
+                self.drive,
+                (offset + i * MAX_SECTORS) as u32,
+                buf,
+            )?;
+            for (block, data) in chunk.iter_mut().zip(buf.chunks(SECTOR_SIZE)) {
+                block.as_mut().copy_from_slice(data);
+            }
+        }
+        Ok(())
+    }
+
     fn write_block(&self, offset: usize, block: &Block512) -> storage::Result<()> {
         BUSES[self.bus as usize]
             .lock()
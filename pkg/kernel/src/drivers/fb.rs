@@ -0,0 +1,18 @@
+use boot::{BootInfo, FrameBuffer};
+
+/// The framebuffer set up by the bootloader, mapped by `Syscall::MapFramebuffer`
+static FRAME_BUFFER: spin::Once<Option<FrameBuffer>> = spin::Once::new();
+
+pub fn init(boot_info: &'static BootInfo) {
+    match FRAME_BUFFER.call_once(|| boot_info.frame_buffer) {
+        Some(fb) => info!(
+            "Frame buffer: {}x{} at {:#x}, {} bytes",
+            fb.width, fb.height, fb.base, fb.size
+        ),
+        None => info!("Frame buffer: not provided"),
+    }
+}
+
+pub fn get_frame_buffer() -> Option<FrameBuffer> {
+    FRAME_BUFFER.get().copied().flatten()
+}
@@ -1,10 +1,15 @@
 use super::ata::*;
 use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::format;
-use alloc::string::ToString;
+use alloc::string::{String, ToString};
+use core::ops::{Deref, DerefMut};
+use spin::Mutex;
 use storage::fat16::Fat16;
 use storage::mbr::*;
 use storage::*;
+use syscall_def::{Dirent, FileKind, StatFsInfo, SysError, DIRENT_NAME_MAX};
+use syscall_def::{WATCH_CREATE, WATCH_UNLINK, WATCH_WRITE};
 
 pub static ROOTFS: spin::Once<Mount> = spin::Once::new();
 
@@ -12,6 +17,343 @@ pub fn get_rootfs() -> &'static Mount {
     ROOTFS.get().unwrap()
 }
 
+/// The file does not exist
+pub const ENOENT: isize = SysError::NotFound.code();
+/// The file is read-only, or the operation is not permitted
+pub const EACCES: isize = SysError::Perm.code();
+/// The file is opened by some process
+pub const EBUSY: isize = SysError::Busy.code();
+/// The file exists, but is created exclusively
+pub const EEXIST: isize = SysError::Exists.code();
+/// The process has opened as many fds as its limit
+pub const EMFILE: isize = SysError::NoFds.code();
+/// Too many symlinks are followed in a path, likely a loop
+pub const ELOOP: isize = SysError::Loop.code();
+
+/// The most symlinks followed in a path, more fail with `ELOOP`
+pub const SYMLOOP_MAX: usize = 8;
+
+bitflags! {
+    /// The flags of `Syscall::Open`, values are the same as Linux
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct OpenFlags: usize {
+        /// Open for writing only
+        const WRONLY = 0o1;
+        /// Open for reading & writing
+        const RDWR = 0o2;
+        /// Create the file if it does not exist
+        const CREAT = syscall_def::O_CREAT;
+        /// With `CREAT`, the file must not exist
+        const EXCL = syscall_def::O_EXCL;
+        /// Truncate the file to zero length if opened for writing
+        const TRUNC = 0o1000;
+        /// Every write goes to the end of the file
+        const APPEND = 0o2000;
+    }
+}
+
+/// The write bit of the owner, FAT16 keeps only this bit of the mode
+pub const MODE_WRITE: usize = 0o200;
+/// The mode of a file without the read-only attribute
+pub const MODE_DEFAULT: usize = 0o644;
+/// The mode of a file with the read-only attribute
+pub const MODE_READONLY: usize = 0o444;
+
+/// The number of open handles of each file, keyed by the normalized path
+static OPEN_FILES: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+/// Held from the check to the creation of a file, so only one of
+/// the racing exclusive creates succeeds
+static CREATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// The target of each symlink, keyed by the normalized path of the link,
+/// FAT16 cannot store symlinks, so they are kept by the kernel until reboot
+static SYMLINKS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// FAT16 names are case-insensitive, so are the paths
+pub fn normalize_path(path: &str) -> String {
+    path.split('/')
+        .filter(|s| !s.is_empty())
+        .fold(String::new(), |path, part| path + "/" + &part.to_uppercase())
+}
+
+fn is_opened(path: &str) -> bool {
+    OPEN_FILES.lock().contains_key(&normalize_path(path))
+}
+
+/// A file opened by a process
+///
+/// the file is counted as opened until all its handles are dropped,
+/// and cannot be removed or renamed in the meantime
+pub struct OpenFile {
+    path: String,
+    handle: FileHandle,
+    flags: OpenFlags,
+    mode: usize,
+}
+
+impl OpenFile {
+    /// Open the file, the write access is denied by a read-only mode
+    ///
+    /// with `TRUNC` the file is emptied, only when the write access is requested,
+    /// with `CREAT` a missing file is created, and with `EXCL` as well the file
+    /// must be created by this call, `AlreadyExists` otherwise
+    pub fn open(path: &str, flags: OpenFlags) -> storage::Result<Self> {
+        let mut handle = match flags.contains(OpenFlags::CREAT) {
+            true => Self::open_or_create(path, flags.contains(OpenFlags::EXCL))?,
+            false => get_rootfs().open_file(path)?,
+        };
+        let mode = match handle.meta.readonly {
+            true => MODE_READONLY,
+            false => MODE_DEFAULT,
+        };
+        let write_access = flags.intersects(OpenFlags::WRONLY | OpenFlags::RDWR);
+        if write_access && mode & MODE_WRITE == 0 {
+            return Err(FsError::ReadOnly);
+        }
+        if write_access && flags.contains(OpenFlags::TRUNC) {
+            handle.set_len(0)?;
+        }
+
+        let path = normalize_path(path);
+        *OPEN_FILES.lock().entry(path.clone()).or_default() += 1;
+        Ok(Self {
+            path,
+            handle,
+            flags,
+            mode,
+        })
+    }
+
+    fn open_or_create(path: &str, exclusive: bool) -> storage::Result<FileHandle> {
+        let _guard = CREATE_LOCK.lock();
+        match get_rootfs().create_file(path) {
+            Err(FsError::AlreadyExists) if !exclusive => get_rootfs().open_file(path),
+            Ok(handle) => {
+                crate::watch::notify(path, WATCH_CREATE);
+                Ok(handle)
+            }
+            ret => ret,
+        }
+    }
+
+    /// Write at the offset, or at the end regardless of it with `APPEND`
+    pub fn write(&mut self, buf: &[u8]) -> storage::Result<usize> {
+        if self.mode & MODE_WRITE == 0 {
+            return Err(FsError::ReadOnly);
+        }
+        if self.flags.contains(OpenFlags::APPEND) {
+            self.handle.seek(SeekFrom::End(0))?;
+        }
+        let written = self.handle.write(buf)?;
+        if written > 0 {
+            crate::watch::notify(&self.path, WATCH_WRITE);
+        }
+        Ok(written)
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.mode & MODE_WRITE != 0
+    }
+
+    /// Read at `offset` without moving the offset of the file,
+    /// returns 0 if `offset` is beyond the end
+    pub fn read_at(&mut self, offset: usize, buf: &mut [u8]) -> storage::Result<usize> {
+        let current = self.handle.seek(SeekFrom::Current(0))?;
+        if offset > self.handle.seek(SeekFrom::End(0))? {
+            self.handle.seek(SeekFrom::Start(current))?;
+            return Ok(0);
+        }
+        self.handle.seek(SeekFrom::Start(offset))?;
+        let ret = self.handle.read(buf);
+        self.handle.seek(SeekFrom::Start(current))?;
+        ret
+    }
+
+    /// Write at `offset` without moving the offset of the file,
+    /// regardless of `APPEND`
+    pub fn write_at(&mut self, offset: usize, buf: &[u8]) -> storage::Result<usize> {
+        if !self.is_writable() {
+            return Err(FsError::ReadOnly);
+        }
+        let current = self.handle.seek(SeekFrom::Current(0))?;
+        self.handle.seek(SeekFrom::Start(offset))?;
+        let ret = self.handle.write(buf);
+        self.handle.seek(SeekFrom::Start(current))?;
+        if ret.as_ref().is_ok_and(|written| *written > 0) {
+            crate::watch::notify(&self.path, WATCH_WRITE);
+        }
+        ret
+    }
+
+    /// Change the mode, the write bit is kept by the file system
+    pub fn chmod(&mut self, mode: usize) -> storage::Result<()> {
+        self.handle.set_readonly(mode & MODE_WRITE == 0)?;
+        self.mode = mode & 0o777;
+        Ok(())
+    }
+}
+
+impl Deref for OpenFile {
+    type Target = FileHandle;
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
+
+impl DerefMut for OpenFile {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.handle
+    }
+}
+
+impl Drop for OpenFile {
+    fn drop(&mut self) {
+        // the file may have been written through any writable handle
+        if self.is_writable() {
+            crate::proc::forget_app(&self.path);
+        }
+        let mut files = OPEN_FILES.lock();
+        if let Some(count) = files.get_mut(&self.path) {
+            *count -= 1;
+            if *count == 0 {
+                files.remove(&self.path);
+            }
+        }
+    }
+}
+
+impl core::fmt::Debug for OpenFile {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?}", self.handle)
+    }
+}
+
+/// Whether the file or dir at `path` exists, no fd is opened
+pub fn exists(path: &str) -> bool {
+    get_rootfs().exists(path).unwrap_or(false)
+}
+
+/// Whether `path` is a dir, the root dir included
+pub fn is_dir(path: &str) -> bool {
+    normalize_path(path).is_empty() || get_rootfs().metadata(path).is_ok_and(|m| m.is_dir())
+}
+
+/// Create a symlink at `link` to `target`, which may not exist and
+/// may be relative to the dir of the link
+///
+/// returns `EEXIST` if a file, a dir or a link is at `link`,
+/// or `ENOENT` if its dir does not exist or `target` is empty
+pub fn symlink(target: &str, link: &str) -> isize {
+    let key = normalize_path(link);
+    let dir = match key.rsplit_once('/') {
+        Some((dir, _)) => dir,
+        None => return EEXIST,
+    };
+    if target.is_empty() || !is_dir(dir) {
+        return ENOENT;
+    }
+    let mut links = SYMLINKS.lock();
+    if links.contains_key(&key) || exists(&key) {
+        return EEXIST;
+    }
+    links.insert(key, target.into());
+    0
+}
+
+/// The target of the symlink at `path` as created, `None` if it is not a link
+pub fn readlink(path: &str) -> Option<String> {
+    SYMLINKS.lock().get(&normalize_path(path)).cloned()
+}
+
+/// Fill `dirents` with the entries of the dir from the `cursor`-th one,
+/// returns the cursor of the next call, or 0 if all entries are read
+pub fn getdents(path: &str, cursor: usize, dirents: &mut [Dirent]) -> Option<usize> {
+    let mut iter = get_rootfs().read_dir(path).ok()?.skip(cursor).peekable();
+
+    let mut count = 0;
+    for (dirent, meta) in dirents.iter_mut().zip(iter.by_ref()) {
+        let len = meta.name.len().min(DIRENT_NAME_MAX);
+        dirent.name[..len].copy_from_slice(&meta.name.as_bytes()[..len]);
+        dirent.name_len = len as u8;
+        dirent.kind = match meta.is_dir() {
+            true => FileKind::Directory,
+            false => FileKind::Regular,
+        };
+        count += 1;
+    }
+
+    match iter.peek() {
+        Some(_) => Some(cursor + count),
+        None => Some(0),
+    }
+}
+
+/// Get the space of the root filesystem, `None` if it is not mounted
+pub fn statfs() -> Option<StatFsInfo> {
+    match ROOTFS.get()?.stat_fs() {
+        Ok(stat) => Some(StatFsInfo {
+            total_blocks: stat.total_blocks as u64,
+            free_blocks: stat.free_blocks as u64,
+            block_size: stat.block_size as u64,
+        }),
+        Err(err) => {
+            warn!("Failed to stat the filesystem: {:?}", err);
+            None
+        }
+    }
+}
+
+fn fs_error_code(err: FsError) -> isize {
+    warn!("Filesystem error: {:?}", err);
+    match err {
+        FsError::FileNotFound | FsError::NotADirectory | FsError::InvalidPath(_) => ENOENT,
+        _ => EACCES,
+    }
+}
+
+/// Remove the file at `path`, or the symlink but not its target
+///
+/// NOTE: a file with open fds is not removed, returns `EBUSY`
+pub fn unlink(path: &str) -> isize {
+    if SYMLINKS.lock().remove(&normalize_path(path)).is_some() {
+        return 0;
+    }
+    if is_opened(path) {
+        return EBUSY;
+    }
+
+    crate::proc::forget_app(path);
+    match get_rootfs().remove_file(path) {
+        Ok(()) => {
+            crate::watch::notify(path, WATCH_UNLINK);
+            0
+        }
+        Err(err) => fs_error_code(err),
+    }
+}
+
+/// Rename the file at `src` to `dst`, replacing `dst` if it exists
+///
+/// NOTE: returns `EBUSY` if either of them has open fds
+pub fn rename(src: &str, dst: &str) -> isize {
+    if is_opened(src) || is_opened(dst) {
+        return EBUSY;
+    }
+
+    crate::proc::forget_app(src);
+    crate::proc::forget_app(dst);
+    match get_rootfs().move_file(src, dst) {
+        Ok(()) => {
+            crate::watch::notify(src, WATCH_UNLINK);
+            crate::watch::notify(dst, WATCH_CREATE);
+            0
+        }
+        Err(err) => fs_error_code(err),
+    }
+}
+
 pub fn init() {
     info!("Opening disk device...");
 
@@ -1,12 +1,123 @@
 use alloc::string::String;
 use crossbeam_queue::ArrayQueue;
 use lazy_static::lazy_static;
+use spin::Mutex;
+use syscall_def::*;
 
 type Key = u8;
 lazy_static! {
     static ref INPUT_BUF: ArrayQueue<Key> = ArrayQueue::new(128);
 }
 
+/// The escape sequences being received from the serial console
+static ESCAPE: Mutex<EscapeDecoder> = Mutex::new(EscapeDecoder::new());
+
+/// A lone ESC is passed through if no sequence follows in the ticks
+const ESC_TIMEOUT_TICKS: u64 = 10;
+/// The longest sequence recognized, e.g. `ESC [ 3 ~`
+const ESC_SEQ_MAX: usize = 4;
+const ESC: u8 = 0x1B;
+
+/// Translate the ANSI escape sequences of the special keys into
+/// single key codes, the other bytes are passed through unchanged
+struct EscapeDecoder {
+    pending: [u8; ESC_SEQ_MAX],
+    len: usize,
+    since: u64,
+}
+
+impl EscapeDecoder {
+    const fn new() -> Self {
+        Self {
+            pending: [0; ESC_SEQ_MAX],
+            len: 0,
+            since: 0,
+        }
+    }
+
+    /// Feed a byte received at the tick `now`, the decoded keys go to `push`
+    fn feed(&mut self, byte: u8, now: u64, push: &mut impl FnMut(Key)) {
+        if self.len > 0 && now - self.since > ESC_TIMEOUT_TICKS {
+            self.flush(push);
+        }
+
+        match (&self.pending[..self.len], byte) {
+            ([], ESC) => {
+                self.since = now;
+                self.append(byte);
+            }
+            ([], _) => push(byte),
+            ([ESC], b'[' | b'O') => self.append(byte),
+            // ESC followed by a plain byte, or another ESC
+            ([ESC], _) => {
+                self.flush(push);
+                self.feed(byte, now, push);
+            }
+            ([ESC, b'[', ..], b'0'..=b'9') if self.len < ESC_SEQ_MAX => self.append(byte),
+            (seq, _) => match decode(seq, byte) {
+                Some(key) => {
+                    self.len = 0;
+                    push(key);
+                }
+                None => {
+                    // unknown sequences are passed through as they are
+                    self.flush(push);
+                    push(byte);
+                }
+            },
+        }
+    }
+
+    /// Pass through a lone ESC or a partial sequence timed out at `now`
+    fn flush_stale(&mut self, now: u64, push: &mut impl FnMut(Key)) {
+        if self.len > 0 && now - self.since > ESC_TIMEOUT_TICKS {
+            self.flush(push);
+        }
+    }
+
+    fn append(&mut self, byte: u8) {
+        self.pending[self.len] = byte;
+        self.len += 1;
+    }
+
+    fn flush(&mut self, push: &mut impl FnMut(Key)) {
+        for byte in &self.pending[..self.len] {
+            push(*byte);
+        }
+        self.len = 0;
+    }
+}
+
+/// The key of the sequence started by `seq` and ended by `last`
+fn decode(seq: &[u8], last: u8) -> Option<Key> {
+    match (seq, last) {
+        ([ESC, b'[' | b'O'], b'A') => Some(KEY_UP),
+        ([ESC, b'[' | b'O'], b'B') => Some(KEY_DOWN),
+        ([ESC, b'[' | b'O'], b'C') => Some(KEY_RIGHT),
+        ([ESC, b'[' | b'O'], b'D') => Some(KEY_LEFT),
+        ([ESC, b'[' | b'O'], b'H') => Some(KEY_HOME),
+        ([ESC, b'[' | b'O'], b'F') => Some(KEY_END),
+        ([ESC, b'[', b'1' | b'7'], b'~') => Some(KEY_HOME),
+        ([ESC, b'[', b'4' | b'8'], b'~') => Some(KEY_END),
+        ([ESC, b'[', b'3'], b'~') => Some(KEY_DELETE),
+        _ => None,
+    }
+}
+
+/// Push a byte received from the serial console, decoding escape sequences
+pub fn receive_byte(byte: u8) {
+    let now = crate::interrupt::read_counter();
+    ESCAPE.lock().feed(byte, now, &mut push_key);
+}
+
+/// Pass through a lone ESC once no sequence can follow it
+fn flush_stale_escape() {
+    let now = crate::interrupt::read_counter();
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        ESCAPE.lock().flush_stale(now, &mut push_key);
+    });
+}
+
 pub fn push_key(key: Key) {
     if INPUT_BUF.push(key).is_err() {
         warn!("Input buffer is full. Dropping key '{:?}'", key);
@@ -15,9 +126,20 @@ pub fn push_key(key: Key) {
 
 #[inline]
 pub fn try_pop_key() -> Option<Key> {
+    if INPUT_BUF.is_empty() {
+        flush_stale_escape();
+    }
     INPUT_BUF.pop()
 }
 
+#[inline]
+pub fn has_key() -> bool {
+    if INPUT_BUF.is_empty() {
+        flush_stale_escape();
+    }
+    !INPUT_BUF.is_empty()
+}
+
 pub fn pop_key() -> u8 {
     loop {
         if let Some(data) = try_pop_key() {
@@ -40,6 +162,8 @@ pub fn get_line() -> String {
                 print!("\x08\x20\x08");
                 line.pop();
             }
+            // no line editing with the special keys yet
+            KEY_UP..=KEY_DELETE => {}
             _ => {
                 if is_utf8(ch) {
                     let utf_char = char::from_u32(to_utf8(ch)).unwrap();
@@ -1,4 +1,5 @@
 pub mod ata;
+pub mod fb;
 pub mod filesystem;
 pub mod input;
 pub mod serial;
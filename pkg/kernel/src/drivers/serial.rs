@@ -1,9 +1,22 @@
-use super::uart16550::SerialPort;
+use super::uart16550::{LineConfig, Parity, SerialPort, FIFO_SIZE};
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+use syscall_def::{SerialConfig, PARITY_EVEN, PARITY_NONE, PARITY_ODD};
 
 const SERIAL_IO_PORT: u16 = 0x3F8; // COM1
+/// The number of bytes the output ring buffer holds
+const SERIAL_OUTPUT_SIZE: usize = 4096;
 
 once_mutex!(pub SERIAL: SerialPort);
 
+/// Bytes waiting to be sent by the transmit interrupt
+pub static OUTPUT_BUF: Mutex<OutputBuffer> = Mutex::new(OutputBuffer::new());
+
+/// Whether the output is drained by the transmit interrupt,
+/// otherwise it is sent synchronously
+static ASYNC_OUTPUT: AtomicBool = AtomicBool::new(false);
+
 pub fn init() {
     init_SERIAL(SerialPort::new(SERIAL_IO_PORT));
     get_serial_for_sure().init();
@@ -14,3 +27,197 @@ pub fn init() {
 }
 
 guard_access_fn!(pub get_serial(SERIAL: SerialPort));
+
+/// A fixed-size ring buffer, no heap is required
+pub struct OutputBuffer {
+    buf: [u8; SERIAL_OUTPUT_SIZE],
+    head: usize,
+    len: usize,
+}
+
+impl OutputBuffer {
+    const fn new() -> Self {
+        Self {
+            buf: [0; SERIAL_OUTPUT_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) -> bool {
+        if self.len == SERIAL_OUTPUT_SIZE {
+            return false;
+        }
+        self.buf[(self.head + self.len) % SERIAL_OUTPUT_SIZE] = byte;
+        self.len += 1;
+        true
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buf[self.head];
+        self.head = (self.head + 1) % SERIAL_OUTPUT_SIZE;
+        self.len -= 1;
+        Some(byte)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Drain the output by the transmit interrupt from now on,
+/// should be called once the serial irq is enabled
+pub fn enable_async_output() {
+    ASYNC_OUTPUT.store(true, Ordering::SeqCst);
+}
+
+/// Send all the queued bytes, and write synchronously from now on
+///
+/// NOTE: used on panic, when no more interrupt is handled
+pub fn disable_async_output(serial: &mut SerialPort) {
+    ASYNC_OUTPUT.store(false, Ordering::SeqCst);
+    let mut output = OUTPUT_BUF.lock();
+    serial.set_transmit_interrupt(false);
+    while let Some(byte) = output.pop() {
+        serial.send(byte);
+    }
+}
+
+/// Send all the queued bytes before returning, the output stays async
+pub fn flush(serial: &mut SerialPort) {
+    let mut output = OUTPUT_BUF.lock();
+    while let Some(byte) = output.pop() {
+        serial.send(byte);
+    }
+    serial.set_transmit_interrupt(false);
+}
+
+/// Reprogram the line once the queued bytes are sent at the old baud,
+/// returns false if the config is invalid or the port is busy
+pub fn configure(config: &SerialConfig) -> bool {
+    let parity = match config.parity {
+        PARITY_NONE => Parity::None,
+        PARITY_ODD => Parity::Odd,
+        PARITY_EVEN => Parity::Even,
+        _ => return false,
+    };
+    let config = LineConfig {
+        baud: config.baud,
+        data_bits: config.data_bits,
+        parity,
+        stop_bits: config.stop_bits,
+    };
+    if config.divisor().is_none() {
+        return false;
+    }
+
+    let mut serial = match get_serial() {
+        Some(serial) => serial,
+        None => return false,
+    };
+    flush(&mut serial);
+    while !serial.is_transmit_empty() {}
+    serial.configure(&config)
+}
+
+/// Program a few configs, and check the registers read back the same,
+/// the default is restored at the end
+#[cfg(feature = "serial_test")]
+pub fn test_config() {
+    const CONFIGS: [LineConfig; 3] = [
+        LineConfig {
+            baud: 9600,
+            data_bits: 7,
+            parity: Parity::Even,
+            stop_bits: 2,
+        },
+        LineConfig {
+            baud: 115200,
+            data_bits: 5,
+            parity: Parity::Odd,
+            stop_bits: 1,
+        },
+        LineConfig::DEFAULT,
+    ];
+
+    assert_eq!(CONFIGS[0].divisor(), Some(12));
+    assert_eq!(LineConfig::DEFAULT.divisor(), Some(3));
+    // not exact or out of the latch
+    for baud in [0, 7, 230400] {
+        let config = LineConfig {
+            baud,
+            ..LineConfig::DEFAULT
+        };
+        assert_eq!(config.divisor(), None, "Baud {} is accepted", baud);
+    }
+
+    let mut serial = get_serial_for_sure();
+    flush(&mut serial);
+    for config in CONFIGS {
+        while !serial.is_transmit_empty() {}
+        assert!(serial.configure(&config));
+        assert_eq!(serial.line_config(), config);
+    }
+    let bad = LineConfig {
+        data_bits: 9,
+        ..LineConfig::DEFAULT
+    };
+    assert!(!serial.configure(&bad));
+    assert_eq!(serial.line_config(), LineConfig::DEFAULT);
+    drop(serial);
+
+    info!("Serial config test passed.");
+}
+
+/// Queue the bytes to be sent by the transmit interrupt
+///
+/// if the ring is full, make room by sending the oldest bytes synchronously
+pub fn write_bytes(serial: &mut SerialPort, bytes: &[u8]) {
+    if !ASYNC_OUTPUT.load(Ordering::SeqCst) {
+        for &byte in bytes {
+            serial.send(byte);
+        }
+        return;
+    }
+
+    let mut output = OUTPUT_BUF.lock();
+    for &byte in bytes {
+        if !output.push(byte) {
+            serial.send(output.pop().unwrap());
+            output.push(byte);
+        }
+    }
+    transmit(serial, &mut output);
+}
+
+/// Move the queued bytes into the transmit FIFO,
+/// should be called on every serial interrupt
+pub fn drain(serial: &mut SerialPort) {
+    transmit(serial, &mut OUTPUT_BUF.lock());
+}
+
+fn transmit(serial: &mut SerialPort, output: &mut OutputBuffer) {
+    if serial.is_transmit_empty() {
+        for _ in 0..FIFO_SIZE {
+            match output.pop() {
+                Some(byte) => serial.send_nowait(byte),
+                None => break,
+            }
+        }
+    }
+    // get interrupted when the FIFO is empty again, only if there is more
+    serial.set_transmit_interrupt(!output.is_empty());
+}
+
+/// Format into the output ring of the locked serial port
+pub struct SerialWriter<'a>(pub &'a mut SerialPort);
+
+impl fmt::Write for SerialWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_bytes(self.0, s.as_bytes());
+        Ok(())
+    }
+}
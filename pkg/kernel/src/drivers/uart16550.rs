@@ -2,6 +2,11 @@ use core::fmt;
 use x86_64::instructions::port::Port;
 
 pub const PORT: u16 = 0x3F8;
+/// The number of bytes the transmit FIFO holds
+pub const FIFO_SIZE: usize = 16;
+/// The baud of divisor 1, a baud is valid if it divides this
+pub const BASE_BAUD: u32 = 115200;
+
 /// A port-mapped UART 16550 serial interface.
 pub struct SerialPort {
     data: Port<u8>,
@@ -13,11 +18,67 @@ pub struct SerialPort {
 }
 
 bitflags! {
+    #[derive(Clone, Copy)]
     struct LineControl:u8{
         const DLAB=0b10000000;
-        const OneStopBit=0b00000000;
-        const NoneParity=0b00000000;
-        const EightCharLength=0b00000011;
+        const EvenParity=0b00010000;
+        const ParityEnable=0b00001000;
+        const TwoStopBits=0b00000100;
+        // the data bits minus 5
+        const CharLength=0b00000011;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+}
+
+/// The baud & the frame of the serial line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineConfig {
+    pub baud: u32,
+    /// 5 ~ 8
+    pub data_bits: u8,
+    pub parity: Parity,
+    /// 1 or 2, 2 stands for 1.5 with 5 data bits
+    pub stop_bits: u8,
+}
+
+impl LineConfig {
+    /// 38400 baud, 8 data bits, no parity, one stop bit
+    pub const DEFAULT: Self = Self {
+        baud: 38400,
+        data_bits: 8,
+        parity: Parity::None,
+        stop_bits: 1,
+    };
+
+    /// The divisor latch of the baud, `None` if it is not exact
+    pub fn divisor(&self) -> Option<u16> {
+        if self.baud == 0 || BASE_BAUD % self.baud != 0 {
+            return None;
+        }
+        u16::try_from(BASE_BAUD / self.baud).ok()
+    }
+
+    /// The line control register without DLAB, `None` for a bad frame
+    fn line_control(&self) -> Option<LineControl> {
+        if !(5..=8).contains(&self.data_bits) || !(1..=2).contains(&self.stop_bits) {
+            return None;
+        }
+        let mut lcr = LineControl::from_bits_retain(self.data_bits - 5);
+        if self.stop_bits == 2 {
+            lcr |= LineControl::TwoStopBits;
+        }
+        match self.parity {
+            Parity::None => {}
+            Parity::Odd => lcr |= LineControl::ParityEnable,
+            Parity::Even => lcr |= LineControl::ParityEnable | LineControl::EvenParity,
+        }
+        Some(lcr)
     }
 }
 
@@ -37,19 +98,60 @@ impl SerialPort {
     pub fn init(&mut self) {
         unsafe {
             self.interrupt_enable.write(0x00); // Disable all interrupts
-            self.line_control.write(LineControl::DLAB.bits()); // Enable DLAB (set baud rate divisor)
-            self.data.write(0x03); // Set divisor to 3 (lo byte) 38400 baud
-            self.interrupt_enable.write(0x00); // Set divisor to 3 (hi byte) 38400 baud
-            self.line_control.write(
-                (LineControl::EightCharLength | LineControl::NoneParity | LineControl::OneStopBit)
-                    .bits(),
-            ); // 8 bits, no parity, one stop bit
+        }
+        self.configure(&LineConfig::DEFAULT); // 38400 baud, 8 bits, no parity, one stop bit
+        unsafe {
             self.interrupt_identification_fifo_control.write(0xC7); // Enable FIFO, clear them, with 14-byte threshold
             self.scratch.write(0xAE);
             self.interrupt_enable.write(0x01);
         }
     }
 
+    /// Program the divisor latch & the line control register,
+    /// returns false and keeps the line as it is if the config is invalid
+    ///
+    /// NOTE: the bytes still in the transmit FIFO are sent at the new baud
+    pub fn configure(&mut self, config: &LineConfig) -> bool {
+        let (divisor, lcr) = match (config.divisor(), config.line_control()) {
+            (Some(divisor), Some(lcr)) => (divisor, lcr),
+            _ => return false,
+        };
+        let [lo, hi] = divisor.to_le_bytes();
+        unsafe {
+            // the data & interrupt enable ports are the divisor latch with DLAB
+            self.line_control.write((lcr | LineControl::DLAB).bits());
+            self.data.write(lo);
+            self.interrupt_enable.write(hi);
+            self.line_control.write(lcr.bits());
+        }
+        true
+    }
+
+    /// Read back the config from the registers
+    pub fn line_config(&mut self) -> LineConfig {
+        unsafe {
+            let lcr = LineControl::from_bits_retain(self.line_control.read());
+            self.line_control.write((lcr | LineControl::DLAB).bits());
+            let divisor = u16::from_le_bytes([self.data.read(), self.interrupt_enable.read()]);
+            self.line_control.write(lcr.bits());
+
+            let parity = match (
+                lcr.contains(LineControl::ParityEnable),
+                lcr.contains(LineControl::EvenParity),
+            ) {
+                (false, _) => Parity::None,
+                (true, false) => Parity::Odd,
+                (true, true) => Parity::Even,
+            };
+            LineConfig {
+                baud: BASE_BAUD / divisor.max(1) as u32,
+                data_bits: (lcr & LineControl::CharLength).bits() + 5,
+                parity,
+                stop_bits: if lcr.contains(LineControl::TwoStopBits) { 2 } else { 1 },
+            }
+        }
+    }
+
     /// Sends a byte on the serial port.
     pub fn send(&mut self, data: u8) {
         unsafe {
@@ -58,6 +160,29 @@ impl SerialPort {
         }
     }
 
+    /// Sends a byte on the serial port no wait.
+    ///
+    /// NOTE: the caller should make sure the transmit FIFO has room
+    pub fn send_nowait(&mut self, data: u8) {
+        unsafe {
+            self.data.write(data);
+        }
+    }
+
+    /// Whether the transmit FIFO is empty,
+    /// i.e. `FIFO_SIZE` bytes can be sent without waiting
+    pub fn is_transmit_empty(&mut self) -> bool {
+        unsafe { self.line_status.read() & 0x20 != 0 }
+    }
+
+    /// Enables or disables the transmit-holding-register-empty interrupt,
+    /// the received-data-available interrupt is always enabled.
+    pub fn set_transmit_interrupt(&mut self, enable: bool) {
+        unsafe {
+            self.interrupt_enable.write(if enable { 0x03 } else { 0x01 });
+        }
+    }
+
     /// Receives a byte on the serial port no wait.
     pub fn receive(&mut self) -> Option<u8> {
         unsafe {
@@ -1,7 +1,10 @@
 use crate::{memory::gdt, proc::*};
 
 use super::consts::*;
+use alloc::{boxed::Box, collections::BinaryHeap};
+use core::cmp::{Ordering as CmpOrdering, Reverse};
 use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
 
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
 
@@ -13,7 +16,15 @@ pub unsafe fn register_idt(idt: &mut InterruptDescriptorTable) {
 
 pub extern "C" fn clock(mut context: ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
-        switch(&mut context);
+        inc_counter();
+        crate::memory::vdso::update(read_counter());
+        run_timers(read_counter());
+        // in case a transmit interrupt is missed
+        if let Some(mut serial) = crate::serial::get_serial() {
+            crate::serial::drain(&mut serial);
+        }
+        update_load();
+        preempt(&mut context);
         super::ack();
     });
 }
@@ -22,6 +33,8 @@ as_handler!(clock);
 
 static COUNTER: AtomicU64 = AtomicU64::new(0);
 
+/// The clock ticks since boot, only increased by the clock interrupt,
+/// so it is monotonic and cheap to read anywhere, even in interrupts
 #[inline]
 pub fn read_counter() -> u64 {
     // load counter value
@@ -33,3 +46,114 @@ pub fn inc_counter() -> u64 {
     // read counter value and increase it
     COUNTER.fetch_add(1, Ordering::SeqCst)
 }
+
+/// A callback run by the clock interrupt, it must be short
+pub type TimerCallback = Box<dyn FnOnce() + Send>;
+
+struct Timer {
+    deadline: u64,
+    // the registration order, timers of the same deadline fire in it
+    seq: u64,
+    callback: TimerCallback,
+}
+
+impl Timer {
+    fn key(&self) -> Reverse<(u64, u64)> {
+        Reverse((self.deadline, self.seq))
+    }
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        self.key().cmp(&other.key())
+    }
+}
+
+lazy_static! {
+    /// The pending timers, the earliest deadline on the top
+    static ref TIMERS: Mutex<BinaryHeap<Timer>> = Mutex::new(BinaryHeap::new());
+}
+
+static TIMER_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Run `callback` in the clock interrupt once the counter reaches `deadline`,
+/// a passed deadline fires on the next tick
+///
+/// returns the id of the timer for `cancel_timer`
+pub fn register_timer(deadline: u64, callback: TimerCallback) -> u64 {
+    let seq = TIMER_SEQ.fetch_add(1, Ordering::Relaxed);
+    let timer = Timer {
+        deadline,
+        seq,
+        callback,
+    };
+    x86_64::instructions::interrupts::without_interrupts(|| TIMERS.lock().push(timer));
+    seq
+}
+
+/// Drop the pending timer `id` without running it,
+/// returns false if it has fired or does not exist
+pub fn cancel_timer(id: u64) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let mut timers = TIMERS.lock();
+        let len = timers.len();
+        timers.retain(|timer| timer.seq != id);
+        timers.len() != len
+    })
+}
+
+/// Fire the timers whose deadline has been reached, in the order of the deadline,
+/// a callback can register new timers
+fn run_timers(now: u64) {
+    loop {
+        let timer = {
+            let mut timers = TIMERS.lock();
+            match timers.peek() {
+                Some(timer) if timer.deadline <= now => timers.pop().unwrap(),
+                _ => break,
+            }
+        };
+        (timer.callback)();
+    }
+}
+
+/// Register timers of different deadlines in reversed order,
+/// and check they fire in the order of the deadline
+#[cfg(feature = "timer_test")]
+pub fn test_timers() {
+    const DELAYS: [u64; 3] = [30, 10, 20];
+    const ORDER: [usize; 3] = [1, 2, 0];
+    static FIRED: AtomicU64 = AtomicU64::new(0);
+
+    let now = read_counter();
+    for (i, delay) in DELAYS.iter().enumerate() {
+        let deadline = now + delay;
+        register_timer(
+            deadline,
+            Box::new(move || {
+                let tick = read_counter();
+                let order = FIRED.fetch_add(1, Ordering::SeqCst) as usize;
+                info!("Timer #{} fired at tick {}, deadline {}", i, tick, deadline);
+                assert_eq!(ORDER[order], i, "Timer #{} fired out of order", i);
+                assert_eq!(tick, deadline, "Timer #{} fired at a wrong tick", i);
+                if order == DELAYS.len() - 1 {
+                    info!("Timer test passed.");
+                }
+            }),
+        );
+    }
+}
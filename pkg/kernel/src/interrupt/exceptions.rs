@@ -1,4 +1,6 @@
 use crate::memory::*;
+use alloc::string::String;
+use crate::proc::{self, ProcessContext, KILLED_EXIT_CODE};
 use x86_64::registers::control::Cr2;
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
 use x86_64::VirtAddr;
@@ -92,10 +94,54 @@ pub extern "x86-interrupt" fn bound_range_exceeded_handler(stack_frame: Interrup
     panic!("EXCEPTION: BOUND RANGE EXCEEDED\n\n{:#?}", stack_frame);
 }
 
-pub extern "x86-interrupt" fn invalid_opcode_handler(stack_frame: InterruptStackFrame) {
-    panic!("EXCEPTION: INVALID OPCODE\n\n{:#?}", stack_frame);
+/// Dump the registers of the faulting code to serial, then kill the
+/// faulting user process, or panic if the kernel itself faults
+fn handle_fatal(name: &str, context: &mut ProcessContext, error_code: Option<u64>) {
+    let frame = context.stack_frame();
+    let from_user = frame.code_segment.rpl() == x86_64::PrivilegeLevel::Ring3;
+    // the process table may be locked when the kernel faults
+    let proc_name = if from_user {
+        proc::current_name()
+    } else {
+        String::from("kernel")
+    };
+
+    error!(
+        "EXCEPTION: {} in process #{} ({})",
+        name,
+        proc::get_pid(),
+        proc_name
+    );
+    if let Some(error_code) = error_code {
+        error!("ERROR_CODE: 0x{:016x}", error_code);
+    }
+    error!(
+        "RIP: 0x{:016x}, RSP: 0x{:016x}, CR2: 0x{:016x}",
+        frame.instruction_pointer,
+        frame.stack_pointer,
+        Cr2::read_raw()
+    );
+    error!(
+        "RFLAGS: 0x{:016x} ({:?}), CS: 0x{:x}, SS: 0x{:x}",
+        frame.cpu_flags.bits(),
+        frame.cpu_flags,
+        frame.code_segment.0,
+        frame.stack_segment.0
+    );
+    error!("{:?}", context.regs);
+
+    if !from_user {
+        panic!("EXCEPTION: {} in kernel", name);
+    }
+    proc::exit(KILLED_EXIT_CODE, context);
+}
+
+pub extern "C" fn invalid_opcode(mut context: ProcessContext) {
+    handle_fatal("INVALID OPCODE", &mut context, None);
 }
 
+as_handler!(invalid_opcode);
+
 pub extern "x86-interrupt" fn device_not_available_handler(stack_frame: InterruptStackFrame) {
     panic!("EXCEPTION: DEVICE NOT AVAILABLE\n\n{:#?}", stack_frame);
 }
@@ -140,34 +186,27 @@ pub extern "x86-interrupt" fn stack_segment_fault_handler(
     );
 }
 
-pub extern "x86-interrupt" fn general_protection_fault_handler(
-    stack_frame: InterruptStackFrame,
-    error_code: u64,
-) {
+pub extern "C" fn general_protection_fault(mut context: ProcessContext, error_code: u64) {
     x86_64::instructions::interrupts::disable();
-    panic!(
-        "EXCEPTION: GENERAL PROTECTION FAULT, ERROR_CODE: 0x{:016x}\n\n{:#?}",
-        error_code, stack_frame
-    );
+    handle_fatal("GENERAL PROTECTION FAULT", &mut context, Some(error_code));
 }
 
-pub extern "x86-interrupt" fn page_fault_handler(
-    stack_frame: InterruptStackFrame,
-    err_code: PageFaultErrorCode,
-) {
+as_handler_with_err!(general_protection_fault, u64);
+
+pub extern "C" fn page_fault(mut context: ProcessContext, error_code: u64) {
+    let err_code = PageFaultErrorCode::from_bits_truncate(error_code);
     let cr2 = Cr2::read().unwrap_or(VirtAddr::new(0));
-    if !crate::proc::handle_page_fault(cr2, err_code) {
+    if !proc::handle_page_fault(cr2, err_code) {
         warn!(
-            "EXCEPTION: PAGE FAULT, ERROR_CODE: {:?}\n\nTrying to access: {:#x}\n{:#?}",
-            err_code, cr2, stack_frame
+            "EXCEPTION: PAGE FAULT, ERROR_CODE: {:?}, trying to access: {:#x}",
+            err_code, cr2
         );
-        // print info about which process causes page fault?
-        let pid = crate::proc::get_pid();
-
-        panic!("Cannot handle process {} 's page fault! ", pid);
+        handle_fatal("PAGE FAULT", &mut context, Some(error_code));
     }
 }
 
+as_handler_with_err!(page_fault, PageFaultErrorCode);
+
 pub extern "x86-interrupt" fn x87_floating_point_handler(stack_frame: InterruptStackFrame) {
     panic!("EXCEPTION: x87 FLOATING POINT\n\n{:#?}", stack_frame);
 }
@@ -5,7 +5,11 @@ mod exceptions;
 mod serial;
 mod syscall;
 
-pub use clock::read_counter;
+pub use clock::{cancel_timer, read_counter, register_timer, TimerCallback};
+pub use syscall::test_strace_format;
+
+#[cfg(feature = "timer_test")]
+pub use clock::test_timers;
 
 use crate::{interrupt::consts::Irq, memory::physical_to_virtual};
 use apic::*;
@@ -36,6 +40,8 @@ pub fn init() {
 
     // enable serial irq with IO APIC (use enable_irq)
     enable_irq(Irq::Serial0 as u8, 0);
+    // the output is drained by the serial irq from now on
+    crate::serial::enable_async_output();
     info!("Interrupts Initialized.");
 }
 
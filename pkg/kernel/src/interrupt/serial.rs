@@ -1,4 +1,4 @@
-use crate::{input, serial::get_serial_for_sure};
+use crate::{input, serial, serial::get_serial_for_sure};
 
 use super::consts::*;
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
@@ -9,9 +9,16 @@ pub unsafe fn register_idt(idt: &mut InterruptDescriptorTable) {
 
 pub extern "x86-interrupt" fn serial_handler(_st: InterruptStackFrame) {
     receive();
+    transmit();
     super::ack();
 }
 
+/// Send the queued output to uart 16550
+/// Should be called on every interrupt
+fn transmit() {
+    serial::drain(&mut get_serial_for_sure());
+}
+
 /// Receive character from uart 16550
 /// Should be called on every interrupt
 fn receive() {
@@ -21,6 +28,6 @@ fn receive() {
     drop(serial);
 
     if let Some(data) = data {
-        input::push_key(data);
+        input::receive_byte(data);
     }
 }
@@ -1,5 +1,6 @@
 use crate::{memory::gdt, proc::*};
-use alloc::format;
+use alloc::{format, string::String};
+use core::sync::atomic::{AtomicBool, Ordering};
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame};
 
 // NOTE: import `ysos_syscall` package as `syscall_def` in Cargo.toml
@@ -29,6 +30,35 @@ pub extern "C" fn syscall(mut context: ProcessContext) {
 
 as_handler!(syscall);
 
+/// Whether every syscall is logged with its arguments & return value
+static STRACE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_strace(on: bool) {
+    STRACE.store(on, Ordering::Relaxed);
+}
+
+fn strace_entry(pid: ProcessId, args: &SyscallArgs) -> String {
+    format!("#{} {}", pid, args)
+}
+
+fn strace_exit(pid: ProcessId, syscall: Syscall, ret: usize) -> String {
+    format!("#{} SYSRET : {:<10} -> 0x{:016x}", pid, format!("{:?}", syscall), ret)
+}
+
+/// Check the lines of the syscall tracing carry the name & arguments
+pub fn test_strace_format() {
+    let pid = ProcessId(7);
+    let args = SyscallArgs::new(Syscall::Write, 1, 0x1234, 5);
+    let entry = strace_entry(pid, &args);
+    assert!(entry.starts_with("#7 SYSCALL: Write"), "{}", entry);
+    assert!(entry.contains("0x0000000000001234"), "{}", entry);
+    assert!(entry.ends_with("0x0000000000000005)"), "{}", entry);
+
+    let exit = strace_exit(pid, Syscall::Write, 5);
+    assert!(exit.starts_with("#7 SYSRET : Write"), "{}", exit);
+    assert!(exit.ends_with("-> 0x0000000000000005"), "{}", exit);
+}
+
 #[derive(Clone, Debug)]
 pub struct SyscallArgs {
     pub syscall: Syscall,
@@ -44,36 +74,372 @@ pub fn dispatcher(context: &mut ProcessContext) {
         context.regs.rsi,
         context.regs.rdx,
     );
+    debug_assert!(
+        is_current_stack(context.stack_frame()),
+        "Syscall {:?} from a stack not owned by process #{}",
+        args.syscall,
+        get_pid()
+    );
+
+    // the return value is only logged if the caller is still running,
+    // for the syscalls returning nothing it is the syscall number
+    let traced = STRACE.load(Ordering::Relaxed).then(get_pid);
+    if let Some(pid) = traced {
+        info!("{}", strace_entry(pid, &args));
+    }
+
     match args.syscall {
-        // fd: arg0 as u8, buf: &[u8] (ptr: arg1 as *const u8, len: arg2)
-        // read from fd & return length
+        // fd: arg0 as u8 | flag, buf: &[u8] (ptr: arg1 as *const u8, len: arg2) -> len: isize
+        // read from fd & return length, or a negative `SysError` as len <= isize::MAX,
+        // without waiting for more input if the flag READ_NONBLOCK is set
         Syscall::Read => context.set_rax(sys_read(&args)),
-        // fd: arg0 as u8, buf: &[u8] (ptr: arg1 as *const u8, len: arg2)
-        // write to fd & return length
+        // fd: arg0 as u8, buf: &[u8] (ptr: arg1 as *const u8, len: arg2) -> len: isize
+        // write to fd & return length, or a negative `SysError` as len <= isize::MAX
         Syscall::Write => context.set_rax(sys_write(&args)),
+        // fd: arg0 as u8, iov: &[IoVec] (ptr: arg1 as *const IoVec, count: arg2) -> len: isize
+        // read into the segments in order, short once one is not filled
+        Syscall::Readv => context.set_rax(sys_readv(&args) as usize),
+        // fd: arg0 as u8, iov: &[IoVec] (ptr: arg1 as *const IoVec, count: arg2) -> len: isize
+        // write the segments in order as a whole, short once one is not taken entirely
+        Syscall::Writev => context.set_rax(sys_writev(&args) as usize),
         // None -> pid: u16
         // get current pid
         Syscall::GetPid => context.set_rax(sys_get_pid() as usize),
         // addr: arg0 as usize -> res: usize
         Syscall::Brk => context.set_rax(sys_brk(&args) as usize),
-        // path: &str (ptr: arg0 as *const u8, len: arg1) -> pid: u16
-        // spawn process from path
+        // fd: arg0 as u8, range: arg1 as *const [usize; 2] (offset, len), flags: arg2 -> addr: isize
+        // map the file or zeroed memory, pages are filled on the first access
+        Syscall::Mmap => context.set_rax(sys_mmap(&args) as usize),
+        // addr: arg0 as usize -> ret: isize
+        // remove the mapping starting at addr, shared pages are written back
+        Syscall::Munmap => context.set_rax(sys_munmap(&args) as usize),
+        // addr: arg0 as usize, len: arg1, advice: arg2 -> ret: isize
+        // free the frames of a heap range for DONTNEED (4), zeroed on the next access
+        Syscall::Madvise => context.set_rax(sys_madvise(&args) as usize),
+        // addr: arg0 as usize, len: arg1 -> ret: isize
+        // lock the pages of a heap or mapped range, a locked page is kept by DONTNEED
+        Syscall::Mlock => context.set_rax(sys_mlock(&args, true) as usize),
+        // addr: arg0 as usize, len: arg1 -> ret: isize
+        // unlock the pages of a heap or mapped range locked by `Mlock`
+        Syscall::Munlock => context.set_rax(sys_mlock(&args, false) as usize),
+        // path: &str (ptr: arg0 as *const u8, len: arg1), flags: arg2 -> pid: u16
+        // spawn process from path, a suspended one waits for `Cont` to run,
+        // with stdin & stdout redirected to the fds of the caller in the flags,
+        // the exit code of a collectable one is kept for `Collect`,
+        // the child starts in the working dir given with the path by flag 4,
+        // and a detached one by flag 8 is reparented to the kernel process
         Syscall::Spawn => context.set_rax(sys_spawn_process(&args)),
+        // path: &str (ptr: arg0, len: arg1), attr: arg2 as *const SpawnAttr -> pid: u16
+        // spawn with the arguments & the redirected stdio in the attr, 0 for the defaults
+        Syscall::PosixSpawn => context.set_rax(sys_posix_spawn(&args)),
+        // index: arg0, buf: &mut [u8] (ptr: arg1 as *mut u8, len: arg2) -> len: isize
+        // copy the argument given on spawn, -1 past the last one
+        Syscall::GetArg => context.set_rax(sys_get_arg(&args) as usize),
+        // pid: arg0 as u16, code: arg1 as *mut isize -> ret: isize
+        // take the exit code of a collectable child once, 1 if it is running
+        Syscall::Collect => context.set_rax(sys_collect(&args) as usize),
+        // path: &str (ptr: arg0 as *const u8, len: arg1) -> ret: isize
+        // replace the image of the caller with the app, returns -1 only if failed
+        Syscall::Exec => sys_exec(&args, context),
+        // fd: arg0 as u8, cloexec: arg1 -> ret: isize
+        // mark the fd to be closed by exec or not, new fds are kept
+        Syscall::SetCloexec => context.set_rax(sys_set_cloexec(&args) as usize),
+        // fd: arg0 as u8, cmd: arg1, arg: arg2 -> ret: isize
+        // get (F_GETFL) or set (F_SETFL) the status flags of the fd, e.g. O_NONBLOCK
+        Syscall::Fcntl => context.set_rax(sys_fcntl(&args) as usize),
         // ret: arg0 as isize
         // exit process with retcode
         Syscall::Exit => sys_exit_process(&args, context),
+        // ret: arg0 as isize
+        // exit all the threads of the process with retcode
+        Syscall::ExitGroup => sys_exit_group(&args, context),
         // pid: arg0 as u16 -> status: isize
-        // block itself and wait until the process exit and be woke up
+        // block itself and wait until the process exit and be woke up,
+        // `-NotFound` at once for itself or a process that never existed
         Syscall::WaitPid => sys_wait_pid(&args, context),
-        // path: &str (ptr: arg0 as *const u8, len: arg1) -> fd: u8
-        // open file and return fd
-        Syscall::Open => context.set_rax(sys_open_file(&args)),
+        // pid: arg0 as isize -> count: isize
+        // kill the process, or the process group -pid if negative
+        Syscall::Kill => sys_kill(&args, context),
+        // pid: arg0 as u16, sig: arg1 -> ret: isize
+        // send sig to the handler of the process, -Inval for SIGSTOP & SIGCHLD
+        Syscall::Tkill => sys_tkill(&args, context),
+        // ret: arg0 as isize -> count: isize
+        // kill all the descendants of the caller with the exit code
+        Syscall::KillTree => context.set_rax(sys_kill_tree(&args) as usize),
+        // code: arg0 as *mut isize -> pid: u16 or -1
+        // block until any child exits & return its pid, -1 if no child left
+        Syscall::WaitAny => sys_wait_any(&args, context),
+        // pid: arg0 as u16, rusage: arg1 as *mut RUsage -> ret: isize
+        // wait like `WaitPid`, and fill the ticks & peak memory of the child
+        Syscall::Wait4 => sys_wait4(&args, context),
+        // who: arg0 as isize, rusage: arg1 as *mut RUsage -> ret: isize
+        // fill the usage of self with the voluntary & involuntary switches, RUSAGE_SELF only
+        Syscall::Getrusage => context.set_rax(sys_getrusage(&args) as usize),
+        // pid: arg0 as u16, pgid: arg1 as u16 -> ret: isize
+        // set the process group of self (pid 0) or a child
+        Syscall::SetPgid => context.set_rax(sys_set_pgid(&args) as usize),
+        // pid: arg0 as u16 -> pgid: isize
+        // get the process group of self (pid 0) or another, -1 if not found
+        Syscall::GetPgid => context.set_rax(sys_get_pgid(&args) as usize),
+        // None -> sid: isize
+        // lead a new session & process group, -1 if self leads a group already
+        Syscall::SetSid => context.set_rax(sys_set_sid() as usize),
+        // pid: arg0 as u16 -> sid: isize
+        // get the session of self (pid 0) or another, -1 if not found
+        Syscall::GetSid => context.set_rax(sys_get_sid(&args) as usize),
+        // key: arg0 as u32 -> ret: isize
+        // get the message queue by key, create it if not exist
+        Syscall::MsgGet => context.set_rax(sys_msg_get(&args) as usize),
+        // key & flags: arg0, msg: &[u8] (ptr: arg1 as *const u8, len: arg2) -> len: isize
+        // send a message, block if the queue is full
+        Syscall::MsgSend => sys_msg_send(&args, context),
+        // key: arg0 as u32, buf: &mut [u8] (ptr: arg1 as *mut u8, len: arg2) -> len: isize
+        // receive exactly one message, block if the queue is empty
+        Syscall::MsgRecv => sys_msg_recv(&args, context),
+        // path: &str (ptr: arg0 as *const u8, len: arg1), flags: arg2 -> fd: isize
+        // open file and return fd, or a negative `SysError` like `-Perm`
+        Syscall::Open => context.set_rax(sys_open_file(&args) as usize),
         // fd: arg0 as u8 -> ret: isize
-        // close file by fd
+        // close file by fd, `-BadFd` if not open
         Syscall::Close => context.set_rax(sys_close_file(&args) as usize),
+        // path: &str (ptr: arg0 as *const u8, len: arg1) -> ret: isize
+        // check if the path exists without opening it
+        Syscall::Access => context.set_rax(sys_access(&args) as usize),
+        // path: &str (ptr: arg0 as *const u8, len: arg1) -> ret: isize
+        // remove the file, refuse if it is still opened
+        Syscall::Unlink => context.set_rax(sys_unlink(&args) as usize),
+        // path: &str (ptr: arg0 as *const u8, len: arg1) -> fd: isize
+        // watch the writes, creates & unlinks of a file or the files in a dir
+        Syscall::WatchAdd => context.set_rax(sys_watch_add(&args) as usize),
+        // fd: arg0 as u8, event: arg1 as *mut WatchEvent -> ret: isize
+        // take the earliest change seen by the watch fd, 0 if none, -1 if not a watch fd
+        Syscall::WatchRead => context.set_rax(sys_watch_read(&args) as usize),
+        // op: arg0 as usize, arg1, arg2 -> ret: isize
+        // get or set a per-process knob selected by a `PR_*` op, -1 if unknown or invalid
+        Syscall::Prctl => context.set_rax(sys_prctl(&args) as usize),
+        // None -> canary: u64
+        // the stack canary of the image, for the `__stack_chk_guard` of the runtime
+        Syscall::StackCanary => context.set_rax(sys_stack_canary() as usize),
+        // None -> !
+        // report the overwritten canary from `__stack_chk_fail`, all the threads exit
+        Syscall::StackChkFail => sys_stack_chk_fail(context),
+        // src: &str (ptr: arg0 as *const u8, len: arg1), dst: arg2 as *const [usize; 2] -> ret: isize
+        // rename the file, replacing the existing dst
+        Syscall::Rename => context.set_rax(sys_rename(&args) as usize),
+        // target: &str (ptr: arg0 as *const u8, len: arg1), link: arg2 as *const [usize; 2] -> ret
+        // create a symlink, followed by open, spawn in a dir, list_dir & chroot
+        Syscall::Symlink => context.set_rax(sys_symlink(&args) as usize),
+        // path: &str (ptr: arg0 as *const u8, len: arg1), buf: arg2 as *const [usize; 2] -> len
+        // read the target of a symlink, the length may exceed the buf
+        Syscall::Readlink => context.set_rax(sys_readlink(&args) as usize),
+        // fds: &[u8] (ptr: arg0 as *const u8, len: arg1), flags: arg2 as *mut u8 -> ready: isize
+        // get the readiness flags of the fds without blocking
+        Syscall::Poll => context.set_rax(sys_poll(&args) as usize),
+        // fd: arg0 as u8, stat: arg1 as *mut FileStat -> ret: isize
+        // get the size & type of the resource behind the fd
+        Syscall::Fstat => context.set_rax(sys_fstat(&args) as usize),
+        // fd: arg0 as u8, offset: arg1 as isize, whence: arg2 as u8 -> offset: isize
+        // move the file offset & return the new absolute offset, which fits in
+        // 32 bits on FAT16, -1 if before the start, past the end or overflowing
+        Syscall::Seek => context.set_rax(sys_seek(&args) as usize),
+        // fd: arg0 as u8, mode: arg1 -> ret: isize
+        // change the mode of the file, only the write bit is kept on the disk
+        Syscall::Fchmod => context.set_rax(sys_fchmod(&args) as usize),
+        // fd: arg0 as u8, len: arg1 -> ret: isize
+        // resize the file, the extended part is filled with zeros
+        Syscall::Ftruncate => context.set_rax(sys_ftruncate(&args) as usize),
+        // fd: arg0 as u8 -> ret: isize
+        // push the buffered data of the fd out, e.g. the serial output ring
+        Syscall::Fsync => context.set_rax(sys_fsync(&args) as usize),
+        // fd: arg0 as u8, advice: arg1 -> ret: isize
+        // hint how the fd is going to be read, sequential reads of a file are read ahead
+        Syscall::Fadvise => context.set_rax(sys_fadvise(&args) as usize),
+        // fd: arg0 as u8, dirents: arg1 as *const [usize; 2] (ptr, len), cursor: arg2 -> cursor: isize
+        // fill the dirents from the cursor & return the next one, 0 if exhausted
+        Syscall::GetDents => context.set_rax(sys_getdents(&args) as usize),
+        // fds: arg0 as *mut [u8; 2] -> ret: isize
+        // create a pipe, store its read fd & write fd
+        Syscall::Pipe => context.set_rax(sys_pipe(&args) as usize),
+        // readers: arg0, fds: arg1 as *mut u8 (`readers` of them) -> write_fd: isize
+        // create a tee pipe, every read end gets all the bytes written, store the read fds
+        Syscall::TeePipe => context.set_rax(sys_tee_pipe(&args) as usize),
+        // init: arg0 as u64, flags: arg1 -> fd: isize
+        // create an event counter, read as a u64 & reset, or decreased by one with flag 1
+        Syscall::EventFd => context.set_rax(sys_eventfd(&args) as usize),
+        // in_fd: arg0 as u8, out_fd: arg1 as u8, count: arg2 -> moved: isize
+        // move bytes from a pipe to another in the kernel, short once empty or full
+        Syscall::Splice => context.set_rax(sys_splice(&args) as usize),
+        // old_fd: arg0 as u8, new_fd: arg1 as u8 -> fd: isize
+        // make new_fd refer to the resource of old_fd
+        Syscall::Dup2 => context.set_rax(sys_dup2(&args) as usize),
 
         // None
         Syscall::Stat => sys_list_process(),
+        // pid: arg0 as u16 -> ret: isize
+        // let the suspended child run
+        Syscall::Cont => context.set_rax(sys_cont(&args) as usize),
+        // stats: arg0 as *mut FrameStats -> ret: isize
+        // get the usage & fragmentation of the physical frames
+        Syscall::FrameStats => context.set_rax(sys_frame_stats(&args) as usize),
+        // depths: arg0 as *mut [usize; 2] -> ret: isize
+        // get the spawn depth of self & the maximum allowed
+        Syscall::SpawnDepth => context.set_rax(sys_spawn_depth(&args) as usize),
+        // pid: arg0 as u16 -> reason: isize
+        // get why the process is blocked as `BlockReason`, 0 if not blocked, -1 if not found
+        Syscall::BlockReason => context.set_rax(sys_block_reason(&args) as usize),
+        // pid: arg0 as u16, buf: &mut [AuditEntry] (ptr: arg1, len: arg2) -> count: isize
+        // copy the latest blocks & wake-ups, oldest first, -1 if not found
+        Syscall::WakeLog => context.set_rax(sys_wake_log(&args) as usize),
+        // key: &str (ptr: arg0 as *const u8, len: arg1), buf: arg2 as *const [usize; 2] -> len: isize
+        // copy the value of the environment variable, -1 if not set
+        Syscall::GetEnv => context.set_rax(sys_get_env(&args) as usize),
+        // key: &str (ptr: arg0 as *const u8, len: arg1), val: arg2 as *const [usize; 2] -> ret: isize
+        // set the environment variable, not visible to the parent or siblings
+        Syscall::SetEnv => context.set_rax(sys_set_env(&args) as usize),
+        // level: arg0 (0 for off, 1 ~ 5 for error ~ trace) -> ret: isize
+        // set the kernel log level, only for privileged processes
+        Syscall::SetLogLevel => context.set_rax(sys_set_log_level(&args) as usize),
+        // on: arg0 as bool -> ret: isize
+        // log every syscall & its return value, only for privileged processes
+        Syscall::SetStrace => context.set_rax(sys_set_strace(&args) as usize),
+        // config: arg0 as *const SerialConfig -> ret: isize
+        // reprogram the baud & frame of the serial line, only for privileged processes
+        Syscall::SerialConfig => context.set_rax(sys_serial_config(&args) as usize),
+        // path: arg0 & arg1 -> ret: isize
+        // confine the paths of self to an existing dir, only for privileged processes
+        Syscall::Chroot => context.set_rax(sys_chroot(&args) as usize),
+        // code: arg0 -> None
+        // log the code with the pid & registers of the caller for inspection
+        Syscall::Debug => sys_debug(&args, context),
+        // flags: arg0 -> None
+        // set the flags added to every `Open` of the process & its children
+        Syscall::SetOpenDefaults => sys_set_open_defaults(&args),
+        // policy: arg0 as SchedPolicy -> ret: isize
+        // switch the scheduler policy, only for privileged processes
+        Syscall::SetScheduler => context.set_rax(sys_set_scheduler(&args) as usize),
+        // None -> policy: usize
+        // get the current scheduler policy
+        Syscall::GetScheduler => context.set_rax(sys_get_scheduler()),
+        // fd: arg0 as u8 -> ret: isize
+        // 1 if the fd is a console, 0 for files & pipes, -1 if not opened
+        Syscall::IsTty => context.set_rax(sys_isatty(&args) as usize),
+        // bytes: arg0 -> None
+        // limit the heap, stack & mappings of the caller, 0 for no limit
+        Syscall::SetMemLimit => sys_set_mem_limit(&args),
+        // info: arg0 as *mut StatFsInfo -> ret: isize
+        // get the total & free blocks of the mounted filesystem, -1 if not mounted
+        Syscall::StatFs => context.set_rax(sys_statfs(&args) as usize),
+        // out_fd: arg0 as u8, in_fd: arg1 as u8, count: arg2 -> copied: isize
+        // copy from the offset of the file to the fd in the kernel, short at EOF or a full pipe
+        Syscall::SendFile => context.set_rax(sys_sendfile(&args) as usize),
+        // info: arg0 as *mut MemInfo -> ret: isize
+        // get the total & used physical frames of the machine
+        Syscall::MemInfo => context.set_rax(sys_meminfo(&args) as usize),
+        // pid: arg0 as u16 -> priority: isize
+        // get the effective priority of a process including aging, -1 if not found
+        Syscall::GetPriority => context.set_rax(sys_get_priority(&args) as usize),
+        // pid: arg0 as u16, priority: arg1 -> ret: isize
+        // set the base priority of self to a larger value or of a descendant to any
+        Syscall::SetPriority => context.set_rax(sys_set_priority(&args) as usize),
+        // name: &str (ptr: arg0 as *const u8, len: arg1) -> len: isize
+        // rename self in the process list, returns the chars kept, -1 if empty
+        Syscall::SetProcName => context.set_rax(sys_set_proc_name(&args) as usize),
+        // pid: arg0 as u16, buf: &mut [u8] (ptr: arg1 as *mut u8, len: arg2) -> len: isize
+        // copy the name of a process in the list, e.g. the app it execs, -1 if not found
+        Syscall::GetProcName => context.set_rax(sys_get_proc_name(&args) as usize),
+        // fd: arg0 as u8, stats: arg1 as *mut [u64; 2] -> ret: isize
+        // get the bytes read & written through the fd since it is opened, -1 if not opened
+        Syscall::FdStats => context.set_rax(sys_fd_stats(&args) as usize),
+        // None -> pid: u16
+        // get the largest pid handed out, the pids of the reaped processes are reused
+        Syscall::MaxPid => context.set_rax(sys_max_pid() as usize),
+        // stats: arg0 as *mut DiskStats -> ret: isize
+        // get the read commands issued to the disk & the sectors transferred
+        Syscall::DiskStats => context.set_rax(sys_disk_stats(&args) as usize),
+        // rate: arg0 as u64 -> ret: isize
+        // throttle self to run at most rate ticks per second, 0 to remove
+        Syscall::SetQuota => context.set_rax(sys_set_quota(&args) as usize),
+        // buf: &mut [u8] (ptr: arg0 as *mut u8, len: arg1) -> len: usize
+        // dump the ready queue & the blocked sets into buf, or the console without it
+        Syscall::DumpSched => context.set_rax(sys_dump_sched(&args)),
+        // tag: arg0 as u32, value: arg1 as u64 -> None
+        // append a marker with the tick & the pid of self to the trace ring
+        Syscall::TraceMark => sys_trace_mark(&args),
+        // buf: &mut [TraceEntry] (ptr: arg0 as *mut TraceEntry, len: arg1) -> count: usize
+        // copy the latest markers of the trace ring oldest first, or print them without buf
+        Syscall::DumpTrace => context.set_rax(sys_dump_trace(&args)),
+        // info: arg0 as *mut FbInfo -> ret: isize
+        // map the framebuffer into self, shared with other mappers, -1 if there is none
+        Syscall::MapFramebuffer => context.set_rax(sys_map_framebuffer(&args) as usize),
+        // None -> bytes: u64
+        // the bytes the heap of self can still grow by, within the heap area & the memory limit
+        Syscall::HeapAvail => context.set_rax(sys_heap_avail()),
+        // buf: &mut [u8] (ptr: arg0 as *mut u8, len: arg1) -> count: usize
+        // copy the latest warnings & errors of the kernel log, oldest first
+        Syscall::Dmesg => context.set_rax(sys_dmesg(&args)),
+        // None -> None
+        // give up the privilege of self for good, the forked children are unprivileged too
+        Syscall::DropPrivilege => sys_drop_privilege(),
+        // pid: arg0 as u16, buf: &mut [u16] (ptr: arg1 as *mut u16, len: arg2) -> count: isize
+        // fill the pids of the live children of self (pid 0) or another, -1 if not found
+        Syscall::ListChildren => context.set_rax(sys_list_children(&args) as usize),
+        // name: &str (ptr: arg0 as *const u8, len: arg1), buf: arg2 as *const [usize; 2] -> count
+        // fill the pids of the live processes named exactly so, the count may exceed the buf
+        Syscall::FindByName => context.set_rax(sys_find_by_name(&args) as usize),
+        // None -> !
+        // kill every process, check the frames are back to the boot baseline & quit QEMU
+        Syscall::Shutdown => sys_shutdown(),
+        // None -> freq: u64
+        // get the calibrated tsc cycles per second, 0 if not calibrated
+        Syscall::TscFrequency => context.set_rax(sys_tsc_frequency() as usize),
+        // name: &str (ptr: arg0 as *const u8, len: arg1), info: arg2 as *mut ElfInfo -> ret: isize
+        // get the entry point & loadable segments of the embedded app
+        Syscall::ElfInfo => context.set_rax(sys_elf_info(&args) as usize),
+        // counts: arg0 as *mut [usize; 2] -> ret: isize
+        // get the number of page faults & stack growth faults of self
+        Syscall::PageFaults => context.set_rax(sys_page_faults(&args) as usize),
+        // stats: arg0 as *mut Stats -> ret: isize
+        // get the ticks, page faults & switches of self since the last reset, then zero them
+        Syscall::ResetStats => context.set_rax(sys_reset_stats(&args) as usize),
+        // stats: arg0 as *mut [u64; 2] -> ret: isize
+        // the idle switches & the wakeups of the idle kernel process
+        Syscall::IdleStats => context.set_rax(sys_idle_stats(&args) as usize),
+        // None -> runs: u64
+        // the number of times the maintenance hooks ran in the idle kernel process
+        Syscall::IdleHookRuns => context.set_rax(sys_idle_hook_runs() as usize),
+        // entry: arg0, data: arg1 -> ret: isize
+        // call entry(data, pid) on each child exit, until `SigReturn`; entry 0 to remove
+        Syscall::SigChld => context.set_rax(sys_sigchld(&args) as usize),
+        // None -> None
+        // return from the child exit handler to where it interrupted, -1 if not in it
+        Syscall::SigReturn => sys_sigreturn(context),
+        // how: arg0, set: arg1 as u64, old: arg2 as *mut u64 -> ret: isize
+        // block, unblock or set the masked signals, the pending ones run once unmasked
+        Syscall::SigProcMask => sys_sigprocmask(&args, context),
+        // sig: arg0, act: arg1 as *const SigAction, old: arg2 as *mut SigAction -> ret: isize
+        // set the handler of the signal if act is not null, returns the old one into old
+        Syscall::Sigaction => context.set_rax(sys_sigaction(&args) as usize),
+        // None -> exited: bool
+        // whether any child has exited since the last call
+        Syscall::ChildExited => context.set_rax(sys_child_exited()),
+        // pid: arg0 as u16 -> age: isize
+        // the clock ticks since self (pid 0) or another process is created, -1 if not found
+        Syscall::ProcAge => context.set_rax(sys_proc_age(&args) as usize),
+        // pid: arg0 as u16, latency: arg1 as *mut SchedLatency -> ret: isize
+        // get the ticks self (pid 0) or another process waits in the ready queue
+        Syscall::SchedLatency => context.set_rax(sys_sched_latency(&args) as usize),
+        // pid: arg0 as u16, ticks: arg1 -> ret: isize
+        // kill self (pid 0) or a child once it runs more ticks than the budget
+        Syscall::TickBudget => context.set_rax(sys_set_tick_budget(&args) as usize),
+        // None -> count: u64
+        // get the number of context switches since boot
+        Syscall::ContextSwitches => context.set_rax(sys_context_switches() as usize),
+        // None -> load: u32
+        // get the ready & running processes averaged over the recent ticks, in fixed point
+        Syscall::LoadAvg => context.set_rax(sys_loadavg() as usize),
+        // None -> count: u64
+        // get the number of lookups of the process table by pid since boot
+        Syscall::TableLookups => context.set_rax(sys_table_lookups() as usize),
         // None
         Syscall::ListApp => sys_list_app(),
         // path: &str (arg0 as *const u8, arg1 as len)
@@ -81,20 +447,105 @@ pub fn dispatcher(context: &mut ProcessContext) {
         Syscall::ListDir => sys_list_dir(&args),
         // layout: arg0 as *const Layout -> ptr: *mut u8
         Syscall::Allocate => context.set_rax(sys_allocate(&args)),
-        // ptr: arg0 as *mut u8
-        Syscall::Deallocate => sys_deallocate(&args),
+        // ptr: arg0 as *mut u8, layout: arg1 as *const Layout -> ret: isize
+        Syscall::Deallocate => context.set_rax(sys_deallocate(&args) as usize),
+        // stats: arg0 as *mut [u64; 2] -> ret: isize
+        // the live allocations of self by `Allocate` & the bytes they hold
+        Syscall::AllocStats => context.set_rax(sys_alloc_stats(&args) as usize),
         // None
         // print process info
         Syscall::PrintInfo => context.set_rax(sys_print_info(&args) as usize),
-        // get current time
+        // None -> seconds: u64
+        // the seconds of the day, the RTC is read at most once per tick
         Syscall::Time => context.set_rax(sys_time() as usize),
+        // pid: arg0 as u16, resource: arg1, (new, old): arg2 as *const [usize; 2] -> ret: isize
+        // get the limit of self or a child, and replace it unless new is null
+        Syscall::Prlimit => context.set_rax(sys_prlimit(&args) as usize),
+        // None -> cpu: u32
+        // get the id of the processor running the caller, always 0 on a single CPU
+        Syscall::GetCpu => context.set_rax(sys_getcpu()),
+        // None -> None
+        // issue a full memory barrier on every online CPU, a local `mfence` on a single CPU
+        Syscall::MemBarrier => sys_membarrier(),
+        // buf: &mut [u8] (ptr: arg0 as *mut u8, len: arg1) -> len: isize
+        // fill the buffer with random bytes
+        Syscall::GetRandom => context.set_rax(sys_getrandom(&args) as usize),
+        // None -> ticks: u64
+        // get the number of clock ticks since boot
+        Syscall::Uptime => context.set_rax(sys_uptime() as usize),
+        // entry: arg0, args: arg1 & arg2 -> tid: u16 or -1
+        // run entry(arg1, arg2) in a thread sharing the memory, on a fresh stack
+        Syscall::Thread => context.set_rax(sys_thread(&args, context) as usize),
+        // flags: arg0, entry: arg1, args: &[usize; 2] (ptr: arg2) -> pid: u16 or -1
+        // run entry(args) in a child sharing the parts of self in flags, copying the others
+        Syscall::Clone => context.set_rax(sys_clone(&args, context) as usize),
+        // cow: arg0 as bool -> pid: u16 or 0 or -1
+        // fork the current process, with a private copy-on-write memory if cow
+        Syscall::Fork => sys_fork(&args, context),
         // None -> pid: u16 or 0 or -1
-        Syscall::Fork => sys_fork(context),
+        // create a child on the memory of self, which is blocked until the child execs or exits
+        Syscall::Vfork => sys_vfork(context),
+        // None -> ret: isize
+        // block until a signal is delivered to its handler, at once if one is pending
+        Syscall::Pause => sys_pause(context),
+        // ns: arg0 as u64 -> ret: isize
+        // busy-wait on the tsc within a tick, or block for the ticks covering ns
+        Syscall::NanoSleep => sys_nanosleep(&args, context),
+        // tick: arg0 as u64 -> ret: isize
+        // block until the clock tick is reached, at once if it has passed, returns 0
+        Syscall::SleepUntil => sys_sleep_until(&args, context),
+        // ms: arg0 as u64 -> left: u64
+        // send SIGALRM to self in ms, 0 to disarm, returns the ms left of the one replaced
+        Syscall::Alarm => context.set_rax(sys_alarm(&args) as usize),
+        // None -> ret: isize
+        // give up the time slice, queued behind the other ready processes
+        Syscall::Yield => sys_yield(context),
+        // None -> yielded: isize
+        // pause in a spin-wait loop, yield once called too often in one time slice
+        Syscall::SpinHint => sys_spin_hint(context),
         // op: u8, key: u32, val: usize -> ret: any
+        // val is the timeout in ms of a wait, 0 for none, or the count after a signal-all
         Syscall::Sem => sys_sem(&args, context),
+        // op: u8, key: u32, count: usize -> ret: any
+        // 0 to create a barrier for count processes, 1 to remove, 2 to wait until all arrive
+        Syscall::Barrier => sys_barrier(&args, context),
+        // None -> ret: isize
+        // redirect fd 1 of self into a new buffer, nested up to 8 deep, -1 over it
+        Syscall::CaptureStart => context.set_rax(sys_capture_start() as usize),
+        // buf: &mut [u8] (ptr: arg0 as *mut u8, len: arg1) -> len: isize
+        // copy the innermost capture & restore the fd 1 before it, -1 if not capturing
+        Syscall::CaptureEnd => context.set_rax(sys_capture_end(&args) as usize),
+        // channel_fd: arg0 as u8, fd: arg1 as u8 -> ret: isize
+        // queue the resource of fd in the write end of a pipe, apart from its bytes
+        Syscall::SendFd => context.set_rax(sys_send_fd(&args) as usize),
+        // channel_fd: arg0 as u8 -> fd: isize
+        // take a resource sent to the read end of a pipe at a new fd, -1 if none is queued
+        Syscall::RecvFd => context.set_rax(sys_recv_fd(&args) as usize),
+        // op: u8, addr: arg1 as *const AtomicU32, val: arg2 as u32 -> ret: isize
+        // wait if the word still equals val (0) or wake up to val waiters (1)
+        Syscall::Futex => sys_futex(&args, context),
+        // pid: arg0 as u16, mask: arg1 as u64 -> ret: isize
+        // pin self or a descendant to the CPUs in the mask, one must be online
+        Syscall::SetAffinity => context.set_rax(sys_set_affinity(&args) as usize),
+        // pid: arg0 as u16, mask: arg1 as *mut u64 -> ret: isize
+        // get the CPUs the process may run on, -1 if not found
+        Syscall::GetAffinity => context.set_rax(sys_get_affinity(&args) as usize),
+        // pid: arg0 as u16, addr: arg1, mapping: arg2 as *mut PageMapping -> ret: isize
+        // translate the address by the page table of self or a descendant, -1 if unmapped
+        Syscall::VirtToPhys => context.set_rax(sys_virt_to_phys(&args) as usize),
+        // pid: arg0 as u16 -> ret: isize
+        // save the registers, memory & fds of self or a descendant not running, 1 once restored
+        Syscall::Checkpoint => sys_checkpoint(&args, context),
+        // pid: arg0 as u16 -> ret: isize
+        // reset self or a descendant not running to its checkpoint, no return for self if done
+        Syscall::Restore => sys_restore(&args, context),
         // Unknown
         Syscall::Unknown => warn!("Unhandled syscall: {:x?}", context.regs.rax),
     }
+
+    if let Some(pid) = traced.filter(|&pid| pid == get_pid()) {
+        info!("{}", strace_exit(pid, args.syscall, context.regs.rax));
+    }
 }
 
 impl SyscallArgs {
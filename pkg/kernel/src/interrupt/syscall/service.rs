@@ -2,40 +2,368 @@ use x86_64::VirtAddr;
 
 use super::SyscallArgs;
 use crate::proc::*;
-use crate::runtime::get_uefi_runtime_for_sure;
+use crate::runtime::wall_time;
 use crate::{filesystem, proc};
+use alloc::vec::Vec;
 use core::alloc::Layout;
+use storage::SeekFrom;
 
 pub fn sys_spawn_process(args: &SyscallArgs) -> usize {
-    // get app by path
-    // - core::str::from_utf8_unchecked
-    // - core::slice::from_raw_parts
-    let path = unsafe {
-        core::str::from_utf8_unchecked(core::slice::from_raw_parts(
-            args.arg0 as *const u8,
-            args.arg1,
-        ))
+    // with bit 2 of arg2, arg0 points to the path & the working dir
+    // as (ptr, len, ptr, len), since there are only three args
+    let (path, cwd) = if args.arg2 & 4 != 0 {
+        match unsafe { (args.arg0 as *const [usize; 4]).as_ref() } {
+            Some(&[ptr, len, cwd_ptr, cwd_len]) => (
+                copy_str_from_user(ptr, len),
+                copy_str_from_user(cwd_ptr, cwd_len).map(Some),
+            ),
+            None => return 0,
+        }
+    } else {
+        (copy_str_from_user(args.arg0, args.arg1), Some(None))
+    };
+    let (path, cwd) = match (path, cwd) {
+        (Some(path), Some(cwd)) => (path, cwd),
+        _ => return 0,
     };
-    // spawn the process by name
-    let ret = proc::spawn(path);
+    // bit 0 of arg2 for suspended, bit 1 for collectable, bit 3 for detached,
+    // and the redirected stdin & stdout as fd + 1 in bits 8..16 & 16..24,
+    // 0 for not redirected
+    let stdio = [8, 16].map(|shift| match (args.arg2 >> shift) & 0xff {
+        0 => None,
+        fd => Some(fd as u8 - 1),
+    });
+    let ret = proc::spawn_with_stdio(
+        &path,
+        args.arg2 & 1 != 0,
+        stdio,
+        cwd.as_deref(),
+        Vec::new(),
+        StackSize::default(),
+    );
     // handle spawn error, return 0 if failed
     if ret.is_none() {
         return 0;
     }
+    // the child cannot exit before it is watched, as interrupts are off,
+    // and a detached one by bit 3 has its exit code dropped instead
+    if args.arg2 & 8 != 0 {
+        proc::detach(ret.unwrap());
+    } else if args.arg2 & 2 != 0 {
+        proc::watch_exit(ret.unwrap());
+    }
     // return pid as usize
     ret.unwrap().0 as usize
 }
 
+pub fn sys_posix_spawn(args: &SyscallArgs) -> usize {
+    let path = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => path,
+        None => return 0,
+    };
+    // no attributes for the defaults, like a plain `Spawn`
+    let attr = match args.arg2 {
+        0 => syscall_def::SpawnAttr::default(),
+        ptr => match unsafe { (ptr as *const syscall_def::SpawnAttr).as_ref() } {
+            Some(attr) if attr.argc <= syscall_def::ARG_MAX => *attr,
+            _ => return 0,
+        },
+    };
+    let argv = attr.argv[..attr.argc]
+        .iter()
+        .map(|&[ptr, len]| copy_str_from_user(ptr, len))
+        .collect::<Option<Vec<_>>>();
+    let argv = match argv {
+        Some(argv) => argv,
+        None => return 0,
+    };
+    let stdio = attr.stdio.map(|fd| fd.checked_sub(1).map(|fd| fd as u8));
+    let stack_size = match StackSize::from_bytes(attr.stack[0] as u64, attr.stack[1] as u64) {
+        Some(size) => size,
+        None => return 0,
+    };
+    match proc::spawn_with_stdio(&path, false, stdio, None, argv, stack_size) {
+        Some(pid) => pid.0 as usize,
+        None => 0,
+    }
+}
+
+pub fn sys_get_arg(args: &SyscallArgs) -> isize {
+    let buf = unsafe { core::slice::from_raw_parts_mut(args.arg1 as *mut u8, args.arg2) };
+    match arg(args.arg0) {
+        Some(val) => {
+            let len = val.len().min(buf.len());
+            buf[..len].copy_from_slice(&val.as_bytes()[..len]);
+            val.len() as isize
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_collect(args: &SyscallArgs) -> isize {
+    let out = match unsafe { (args.arg1 as *mut isize).as_mut() } {
+        Some(out) => out,
+        None => return -1,
+    };
+    match proc::collect(ProcessId(args.arg0 as u16)) {
+        Some(Some(code)) => {
+            *out = code;
+            0
+        }
+        Some(None) => 1,
+        None => -1,
+    }
+}
+
+pub fn sys_exec(args: &SyscallArgs, context: &mut ProcessContext) {
+    // the context is replaced if succeeded
+    let replaced = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => proc::exec(&path, context),
+        None => false,
+    };
+    if !replaced {
+        context.set_rax(-1isize as usize);
+    }
+}
+
+pub fn sys_sendfile(args: &SyscallArgs) -> isize {
+    proc::sendfile(args.arg0 as u8, args.arg1 as u8, args.arg2)
+}
+
+pub fn sys_splice(args: &SyscallArgs) -> isize {
+    proc::splice(args.arg0 as u8, args.arg1 as u8, args.arg2)
+}
+
+pub fn sys_max_pid() -> u16 {
+    MAX_PID
+}
+
+pub fn sys_fd_stats(args: &SyscallArgs) -> isize {
+    let stats = match unsafe { (args.arg1 as *mut [u64; 2]).as_mut() } {
+        Some(stats) => stats,
+        None => return -1,
+    };
+    match proc::fd_stats(args.arg0 as u8) {
+        Some((read, written)) => {
+            *stats = [read, written];
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_set_mem_limit(args: &SyscallArgs) {
+    proc::set_mem_limit(args.arg0 as u64)
+}
+
+pub fn sys_set_cloexec(args: &SyscallArgs) -> isize {
+    proc::set_cloexec(args.arg0 as u8, args.arg1 != 0)
+}
+
+pub fn sys_fcntl(args: &SyscallArgs) -> isize {
+    proc::fcntl(args.arg0 as u8, args.arg1, args.arg2)
+}
+
 pub fn sys_write(args: &SyscallArgs) -> usize {
+    // a larger count would collide with the negative errors
+    if args.arg2 > isize::MAX as usize {
+        return syscall_def::SysError::Inval.code() as usize;
+    }
     // get buffer and fd by args
     let buf = unsafe { core::slice::from_raw_parts(args.arg1 as *const u8, args.arg2) };
     // call proc::write -> isize
     proc::write(args.arg0 as u8, buf) as usize
 }
 
+/// The segments of the iovec array at `ptr`, `None` if the array
+/// or any of the segments is not mapped for the caller
+fn iovecs_from_user(ptr: usize, count: usize) -> Option<&'static [syscall_def::IoVec]> {
+    if count == 0 {
+        return Some(&[]);
+    }
+    let size = core::mem::size_of::<syscall_def::IoVec>() * count;
+    if count > syscall_def::IOV_MAX || !is_user_range(ptr, size) {
+        return None;
+    }
+    let iovecs = unsafe { core::slice::from_raw_parts(ptr as *const syscall_def::IoVec, count) };
+
+    let mut total = 0usize;
+    for iov in iovecs {
+        total = total.checked_add(iov.len)?;
+        if !is_user_range(iov.base, iov.len) {
+            return None;
+        }
+    }
+    // a larger total would collide with the negative errors
+    (total <= isize::MAX as usize).then_some(iovecs)
+}
+
+pub fn sys_readv(args: &SyscallArgs) -> isize {
+    let iovecs = match iovecs_from_user(args.arg1, args.arg2) {
+        Some(iovecs) => iovecs,
+        None => return -1,
+    };
+    let mut bufs: Vec<&mut [u8]> = iovecs
+        .iter()
+        .map(|iov| match iov.len {
+            0 => &mut [],
+            len => unsafe { core::slice::from_raw_parts_mut(iov.base as *mut u8, len) },
+        })
+        .collect();
+    proc::readv(args.arg0 as u8, &mut bufs)
+}
+
+pub fn sys_writev(args: &SyscallArgs) -> isize {
+    let iovecs = match iovecs_from_user(args.arg1, args.arg2) {
+        Some(iovecs) => iovecs,
+        None => return -1,
+    };
+    let bufs: Vec<&[u8]> = iovecs
+        .iter()
+        .map(|iov| match iov.len {
+            0 => &[],
+            len => unsafe { core::slice::from_raw_parts(iov.base as *const u8, len) },
+        })
+        .collect();
+    proc::writev(args.arg0 as u8, &bufs)
+}
+
+pub fn sys_poll(args: &SyscallArgs) -> isize {
+    if args.arg2 == 0 {
+        return -1;
+    }
+    // one flag byte for each fd
+    let fds = unsafe { core::slice::from_raw_parts(args.arg0 as *const u8, args.arg1) };
+    let flags = unsafe { core::slice::from_raw_parts_mut(args.arg2 as *mut u8, args.arg1) };
+    proc::poll(fds, flags)
+}
+
+pub fn sys_seek(args: &SyscallArgs) -> isize {
+    let offset = args.arg1 as isize;
+    // whence: 0 for SET, 1 for CUR, 2 for END
+    let pos = match args.arg2 as u8 {
+        0 if offset >= 0 => SeekFrom::Start(offset as usize),
+        1 => SeekFrom::Current(offset),
+        2 => SeekFrom::End(offset),
+        _ => return -1,
+    };
+    proc::seek(args.arg0 as u8, pos)
+}
+
+pub fn sys_fchmod(args: &SyscallArgs) -> isize {
+    proc::fchmod(args.arg0 as u8, args.arg1)
+}
+
+pub fn sys_ftruncate(args: &SyscallArgs) -> isize {
+    proc::ftruncate(args.arg0 as u8, args.arg1)
+}
+
+pub fn sys_fsync(args: &SyscallArgs) -> isize {
+    proc::fsync(args.arg0 as u8)
+}
+
+pub fn sys_fadvise(args: &SyscallArgs) -> isize {
+    match storage::Advice::try_from(args.arg1) {
+        Ok(advice) => proc::fadvise(args.arg0 as u8, advice),
+        Err(_) => -1,
+    }
+}
+
+pub fn sys_isatty(args: &SyscallArgs) -> isize {
+    proc::isatty(args.arg0 as u8)
+}
+
+pub fn sys_statfs(args: &SyscallArgs) -> isize {
+    let info = match unsafe { (args.arg0 as *mut syscall_def::StatFsInfo).as_mut() } {
+        Some(info) => info,
+        None => return -1,
+    };
+    match filesystem::statfs() {
+        Some(value) => {
+            *info = value;
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_fstat(args: &SyscallArgs) -> isize {
+    let stat = match unsafe { (args.arg1 as *mut syscall_def::FileStat).as_mut() } {
+        Some(stat) => stat,
+        None => return -1,
+    };
+    match proc::fstat(args.arg0 as u8) {
+        Some(value) => {
+            *stat = value;
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_mmap(args: &SyscallArgs) -> isize {
+    let (offset, len) = match unsafe { (args.arg1 as *const [usize; 2]).as_ref() } {
+        Some(&[offset, len]) => (offset, len),
+        None => return -1,
+    };
+    let flags = match MmapFlags::from_bits(args.arg2) {
+        Some(flags) => flags,
+        None => return -1,
+    };
+    // exactly one of SHARED & PRIVATE
+    if flags.contains(MmapFlags::SHARED) == flags.contains(MmapFlags::PRIVATE) {
+        return -1;
+    }
+    proc::mmap(args.arg0 as u8, offset, len, flags)
+}
+
+pub fn sys_munmap(args: &SyscallArgs) -> isize {
+    match proc::munmap(VirtAddr::try_new(args.arg0 as u64).ok()) {
+        true => 0,
+        false => -1,
+    }
+}
+
+pub fn sys_madvise(args: &SyscallArgs) -> isize {
+    // only `MADV_DONTNEED` of Linux
+    if args.arg2 != 4 {
+        return -1;
+    }
+    match proc::madvise_dontneed(VirtAddr::try_new(args.arg0 as u64).ok(), args.arg1) {
+        true => 0,
+        false => -1,
+    }
+}
+
+pub fn sys_mlock(args: &SyscallArgs, locked: bool) -> isize {
+    match proc::mlock(VirtAddr::try_new(args.arg0 as u64).ok(), args.arg1, locked) {
+        true => 0,
+        false => -1,
+    }
+}
+
+pub fn sys_getdents(args: &SyscallArgs) -> isize {
+    let (ptr, len) = match unsafe { (args.arg1 as *const [usize; 2]).as_ref() } {
+        Some(&[ptr, len]) => (ptr as *mut syscall_def::Dirent, len),
+        None => return -1,
+    };
+    if ptr.is_null() || len == 0 {
+        return -1;
+    }
+    let dirents = unsafe { core::slice::from_raw_parts_mut(ptr, len) };
+    proc::getdents(args.arg0 as u8, args.arg2, dirents)
+}
+
 pub fn sys_read(args: &SyscallArgs) -> usize {
+    // a larger count would collide with the negative errors
+    if args.arg2 > isize::MAX as usize {
+        return syscall_def::SysError::Inval.code() as usize;
+    }
     let buf = unsafe { core::slice::from_raw_parts_mut(args.arg1 as *mut u8, args.arg2) };
-    proc::read(args.arg0 as u8, buf) as usize
+    match args.arg0 & syscall_def::READ_NONBLOCK {
+        0 => proc::read(args.arg0 as u8, buf) as usize,
+        _ => proc::try_read(args.arg0 as u8, buf) as usize,
+    }
 }
 
 pub fn sys_exit_process(args: &SyscallArgs, context: &mut ProcessContext) {
@@ -43,6 +371,19 @@ pub fn sys_exit_process(args: &SyscallArgs, context: &mut ProcessContext) {
     proc::exit(args.arg0 as isize, context);
 }
 
+pub fn sys_exit_group(args: &SyscallArgs, context: &mut ProcessContext) {
+    proc::exit_group(args.arg0 as isize, context);
+}
+
+pub fn sys_stack_canary() -> u64 {
+    canary()
+}
+
+pub fn sys_stack_chk_fail(context: &mut ProcessContext) {
+    error!("Stack smashing detected in #{}", get_pid());
+    proc::exit_group(STACK_CHK_EXIT_CODE, context);
+}
+
 pub fn sys_list_app() {
     // list all processes
     proc::list_app();
@@ -53,55 +394,564 @@ pub fn sys_list_process() {
     proc::print_process_list();
 }
 
+pub fn sys_context_switches() -> u64 {
+    context_switches()
+}
+
+pub fn sys_loadavg() -> u32 {
+    load_avg()
+}
+
+pub fn sys_table_lookups() -> u64 {
+    table_lookups()
+}
+
 pub fn sys_list_dir(args: &SyscallArgs) {
-    // get path by args
-    let path = unsafe {
+    let path = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => path,
+        None => return,
+    };
+    match lookup_path(&path) {
+        Ok(path) => filesystem::ls(&path),
+        Err(_) => warn!("Cannot list {}: too many symlinks", path),
+    }
+}
+
+pub fn sys_wait_pid(args: &SyscallArgs, context: &mut ProcessContext) {
+    let pid = ProcessId(args.arg0 as u16);
+    wait_pid(pid, context);
+}
+
+pub fn sys_wait_any(args: &SyscallArgs, context: &mut ProcessContext) {
+    let code = unsafe { (args.arg0 as *mut isize).as_mut() };
+    wait_any(code, context);
+}
+
+pub fn sys_getrusage(args: &SyscallArgs) -> isize {
+    let out = match unsafe { (args.arg1 as *mut syscall_def::RUsage).as_mut() } {
+        Some(out) => out,
+        None => return syscall_def::SysError::Inval.code(),
+    };
+    match getrusage(args.arg0 as isize) {
+        Some(usage) => {
+            *out = usage;
+            0
+        }
+        None => syscall_def::SysError::Inval.code(),
+    }
+}
+
+pub fn sys_wait4(args: &SyscallArgs, context: &mut ProcessContext) {
+    let pid = ProcessId(args.arg0 as u16);
+    let rusage = VirtAddr::try_new(args.arg1 as u64).ok().filter(|addr| !addr.is_null());
+    wait4(pid, rusage, context);
+}
+
+pub fn sys_kill(args: &SyscallArgs, context: &mut ProcessContext) {
+    kill(args.arg0 as isize, context);
+}
+
+pub fn sys_kill_tree(args: &SyscallArgs) -> isize {
+    kill_tree(args.arg0 as isize) as isize
+}
+
+pub fn sys_set_pgid(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller, pgid 0 means the same as pid
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    let pgid = match args.arg1 as u16 {
+        0 => pid,
+        pgid => ProcessId(pgid),
+    };
+    if set_pgid(pid, pgid) {
+        0
+    } else {
+        -1
+    }
+}
+
+pub fn sys_get_pgid(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    get_pgid(pid).map_or(-1, |pgid| pgid.0 as isize)
+}
+
+pub fn sys_set_sid() -> isize {
+    set_sid().map_or(-1, |sid| sid.0 as isize)
+}
+
+pub fn sys_get_sid(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    get_sid(pid).map_or(-1, |sid| sid.0 as isize)
+}
+
+pub fn sys_elf_info(args: &SyscallArgs) -> isize {
+    let name = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(name) => name,
+        None => return -1,
+    };
+    let info = match unsafe { (args.arg2 as *mut syscall_def::ElfInfo).as_mut() } {
+        Some(info) => info,
+        None => return -1,
+    };
+    match elf_info(&name) {
+        Some(elf) => {
+            *info = elf;
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_page_faults(args: &SyscallArgs) -> isize {
+    let counts = match unsafe { (args.arg0 as *mut [usize; 2]).as_mut() } {
+        Some(counts) => counts,
+        None => return -1,
+    };
+    let (total, stack) = page_faults();
+    *counts = [total, stack];
+    0
+}
+
+pub fn sys_reset_stats(args: &SyscallArgs) -> isize {
+    let stats = match unsafe { (args.arg0 as *mut syscall_def::Stats).as_mut() } {
+        Some(stats) => stats,
+        None => return -1,
+    };
+    *stats = reset_stats();
+    0
+}
+
+pub fn sys_idle_stats(args: &SyscallArgs) -> isize {
+    let stats = match unsafe { (args.arg0 as *mut [u64; 2]).as_mut() } {
+        Some(stats) => stats,
+        None => return -1,
+    };
+    let (idle, wakeups) = idle_stats();
+    *stats = [idle, wakeups];
+    0
+}
+
+pub fn sys_idle_hook_runs() -> u64 {
+    idle_hook_runs()
+}
+
+pub fn sys_frame_stats(args: &SyscallArgs) -> isize {
+    let stats = match unsafe { (args.arg0 as *mut syscall_def::FrameStats).as_mut() } {
+        Some(stats) => stats,
+        None => return -1,
+    };
+    *stats = crate::memory::get_frame_alloc_for_sure().stats();
+    0
+}
+
+pub fn sys_meminfo(args: &SyscallArgs) -> isize {
+    let info = match unsafe { (args.arg0 as *mut syscall_def::MemInfo).as_mut() } {
+        Some(info) => info,
+        None => return -1,
+    };
+    *info = crate::memory::get_frame_alloc_for_sure().mem_info();
+    0
+}
+
+pub fn sys_disk_stats(args: &SyscallArgs) -> isize {
+    let stats = match unsafe { (args.arg0 as *mut syscall_def::DiskStats).as_mut() } {
+        Some(stats) => stats,
+        None => return -1,
+    };
+    *stats = crate::drivers::ata::disk_stats();
+    0
+}
+
+pub fn sys_cont(args: &SyscallArgs) -> isize {
+    if cont(ProcessId(args.arg0 as u16)) {
+        0
+    } else {
+        -1
+    }
+}
+
+pub fn sys_spawn_depth(args: &SyscallArgs) -> isize {
+    let depths = match unsafe { (args.arg0 as *mut [usize; 2]).as_mut() } {
+        Some(depths) => depths,
+        None => return -1,
+    };
+    let (depth, max) = spawn_depth();
+    *depths = [depth, max];
+    0
+}
+
+pub fn sys_set_tick_budget(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    if set_tick_budget(pid, args.arg1) {
+        0
+    } else {
+        -1
+    }
+}
+
+pub fn sys_sigchld(args: &SyscallArgs) -> isize {
+    // entry 0 removes the handler
+    let handler = match args.arg0 {
+        0 => None,
+        entry => match VirtAddr::try_new(entry as u64) {
+            Ok(entry) => Some((entry, args.arg1)),
+            Err(_) => return -1,
+        },
+    };
+    set_child_handler(handler);
+    0
+}
+
+pub fn sys_sigaction(args: &SyscallArgs) -> isize {
+    let act = unsafe { (args.arg1 as *const syscall_def::SigAction).as_ref() };
+    let old = unsafe { (args.arg2 as *mut syscall_def::SigAction).as_mut() };
+    if act.is_some_and(|act| VirtAddr::try_new(act.entry as u64).is_err()) {
+        return syscall_def::SysError::Inval.code();
+    }
+    // a null act only reads the handler
+    match sigaction(args.arg0, act.copied()) {
+        Some(replaced) => {
+            if let Some(old) = old {
+                *old = replaced;
+            }
+            0
+        }
+        None => syscall_def::SysError::Inval.code(),
+    }
+}
+
+pub fn sys_tkill(args: &SyscallArgs, context: &mut ProcessContext) {
+    tkill(ProcessId(args.arg0 as u16), args.arg1, context);
+}
+
+pub fn sys_sigprocmask(args: &SyscallArgs, context: &mut ProcessContext) {
+    let old = unsafe { (args.arg2 as *mut u64).as_mut() };
+    // the handler returns to the syscall with the result set
+    context.set_rax(0);
+    match sigprocmask(args.arg0, args.arg1 as u64, context) {
+        Some(mask) => {
+            if let Some(old) = old {
+                *old = mask;
+            }
+        }
+        None => context.set_rax(-1isize as usize),
+    }
+}
+
+pub fn sys_child_exited() -> usize {
+    take_child_exited() as usize
+}
+
+pub fn sys_sigreturn(context: &mut ProcessContext) {
+    // the context is replaced by the interrupted one
+    if !sig_return(context) {
+        context.set_rax(-1isize as usize);
+    }
+}
+
+pub fn sys_checkpoint(args: &SyscallArgs, context: &mut ProcessContext) {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    // the saved frame of the caller returns 1 instead
+    let ret = if checkpoint(pid, context) { 0 } else { -1 };
+    context.set_rax(ret as usize);
+}
+
+pub fn sys_restore(args: &SyscallArgs, context: &mut ProcessContext) {
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    // the caller resumes from its checkpoint if restored
+    if !restore_checkpoint(pid, context) {
+        context.set_rax(-1isize as usize);
+    } else if pid != get_pid() {
+        context.set_rax(0);
+    }
+}
+
+pub fn sys_set_quota(args: &SyscallArgs) -> isize {
+    if set_quota(args.arg0 as u64) {
+        0
+    } else {
+        -1
+    }
+}
+
+pub fn sys_trace_mark(args: &SyscallArgs) {
+    crate::trace::mark(get_pid(), args.arg0 as u32, args.arg1 as u64);
+}
+
+pub fn sys_dump_trace(args: &SyscallArgs) -> usize {
+    // print to the console without a buffer
+    if args.arg0 == 0 {
+        return crate::trace::dump();
+    }
+    let buf = unsafe {
+        core::slice::from_raw_parts_mut(args.arg0 as *mut syscall_def::TraceEntry, args.arg1)
+    };
+    crate::trace::copy_to(buf)
+}
+
+pub fn sys_dmesg(args: &SyscallArgs) -> usize {
+    let buf = unsafe { core::slice::from_raw_parts_mut(args.arg0 as *mut u8, args.arg1) };
+    crate::dmesg::copy_to(buf)
+}
+
+pub fn sys_heap_avail() -> usize {
+    heap_avail() as usize
+}
+
+pub fn sys_map_framebuffer(args: &SyscallArgs) -> isize {
+    let out = match unsafe { (args.arg0 as *mut syscall_def::FbInfo).as_mut() } {
+        Some(out) => out,
+        None => return -1,
+    };
+    match map_framebuffer() {
+        Some(info) => {
+            *out = info;
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_dump_sched(args: &SyscallArgs) -> usize {
+    let dump = dump_sched();
+    // print to the console without a buffer
+    if args.arg0 == 0 {
+        print!("{}", dump);
+        return dump.len();
+    }
+    let buf = unsafe { core::slice::from_raw_parts_mut(args.arg0 as *mut u8, args.arg1) };
+    let len = core::cmp::min(buf.len(), dump.len());
+    buf[..len].copy_from_slice(&dump.as_bytes()[..len]);
+    dump.len()
+}
+
+pub fn sys_list_children(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    let children = match list_children(pid) {
+        Some(children) => children,
+        None => return -1,
+    };
+    // the count is returned even if the buffer is too small
+    let buf = unsafe { core::slice::from_raw_parts_mut(args.arg1 as *mut u16, args.arg2) };
+    for (dst, child) in buf.iter_mut().zip(children.iter()) {
+        *dst = child.0;
+    }
+    children.len() as isize
+}
+
+pub fn sys_find_by_name(args: &SyscallArgs) -> isize {
+    let name = unsafe {
         core::str::from_utf8_unchecked(core::slice::from_raw_parts(
             args.arg0 as *const u8,
             args.arg1,
         ))
     };
-    filesystem::ls(path);
+    let buf = match unsafe { (args.arg2 as *const [usize; 2]).as_ref() } {
+        Some(&[ptr, len]) => unsafe { core::slice::from_raw_parts_mut(ptr as *mut u16, len) },
+        None => return -1,
+    };
+    let pids = find_by_name(name);
+    // the count is returned even if the buffer is too small
+    for (dst, pid) in buf.iter_mut().zip(pids.iter()) {
+        *dst = pid.0;
+    }
+    pids.len() as isize
 }
 
-pub fn sys_wait_pid(args: &SyscallArgs, context: &mut ProcessContext) {
-    let pid = ProcessId(args.arg0 as u16);
-    wait_pid(pid, context);
+pub fn sys_proc_age(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    proc_age(pid).map_or(-1, |age| age as isize)
+}
+
+pub fn sys_sched_latency(args: &SyscallArgs) -> isize {
+    let latency = match unsafe { (args.arg1 as *mut syscall_def::SchedLatency).as_mut() } {
+        Some(latency) => latency,
+        None => return -1,
+    };
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    match sched_latency(pid) {
+        Some(value) => {
+            *latency = value;
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_get_priority(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    get_priority(pid).map_or(-1, |priority| priority as isize)
+}
+
+pub fn sys_set_priority(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    if set_priority(pid, args.arg1) {
+        0
+    } else {
+        -1
+    }
+}
+
+pub fn sys_prlimit(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    // the new & old limits are passed as (new, old), since there are only three args,
+    // a null new limit only gets the old one
+    let (new, old) = match unsafe { (args.arg2 as *const [usize; 2]).as_ref() } {
+        Some(&[new, old]) => (new as *const syscall_def::Limit, old as *mut syscall_def::Limit),
+        None => return -1,
+    };
+    let new = unsafe { new.as_ref() }.copied();
+    match prlimit(pid, args.arg1, new) {
+        Some(limit) => {
+            if let Some(old) = unsafe { old.as_mut() } {
+                *old = limit;
+            }
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_virt_to_phys(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    let addr = match VirtAddr::try_new(args.arg1 as u64) {
+        Ok(addr) => addr,
+        Err(_) => return -1,
+    };
+    let out = match unsafe { (args.arg2 as *mut syscall_def::PageMapping).as_mut() } {
+        Some(out) => out,
+        None => return -1,
+    };
+    match virt_to_phys(pid, addr) {
+        Some(mapping) => {
+            *out = mapping;
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_get_affinity(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    let out = match unsafe { (args.arg1 as *mut u64).as_mut() } {
+        Some(out) => out,
+        None => return -1,
+    };
+    match get_affinity(pid) {
+        Some(mask) => {
+            *out = mask;
+            0
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_set_affinity(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    if set_affinity(pid, args.arg1 as u64) {
+        0
+    } else {
+        -1
+    }
 }
 
 pub fn sys_allocate(args: &SyscallArgs) -> usize {
-    let layout = unsafe { (args.arg0 as *const Layout).as_ref().unwrap() };
+    let layout = match unsafe { (args.arg0 as *const Layout).as_ref() } {
+        Some(layout) => layout,
+        None => return 0,
+    };
 
     if layout.size() == 0 {
         return 0;
     }
 
-    let ret = crate::memory::user::USER_ALLOCATOR
-        .lock()
-        .allocate_first_fit(*layout);
-
-    match ret {
-        Ok(ptr) => ptr.as_ptr() as usize,
-        Err(_) => 0,
-    }
+    crate::memory::user::user_alloc(*layout, get_pid())
 }
 
-pub fn sys_deallocate(args: &SyscallArgs) {
-    let layout = unsafe { (args.arg1 as *const Layout).as_ref().unwrap() };
+pub fn sys_deallocate(args: &SyscallArgs) -> isize {
+    let layout = match unsafe { (args.arg1 as *const Layout).as_ref() } {
+        Some(layout) => layout,
+        None => return -1,
+    };
 
     if args.arg0 == 0 || layout.size() == 0 {
-        return;
+        return -1;
     }
 
-    let ptr = args.arg0 as *mut u8;
-
-    unsafe {
-        crate::memory::user::USER_ALLOCATOR
-            .lock()
-            .deallocate(core::ptr::NonNull::new_unchecked(ptr), *layout);
+    if crate::memory::user::user_dealloc(args.arg0, *layout) {
+        0
+    } else {
+        -1
     }
 }
 
+pub fn sys_alloc_stats(args: &SyscallArgs) -> isize {
+    let stats = match unsafe { (args.arg0 as *mut [u64; 2]).as_mut() } {
+        Some(stats) => stats,
+        None => return -1,
+    };
+    let (count, bytes) = crate::memory::user::user_alloc_stats(get_pid());
+    *stats = [count, bytes];
+    0
+}
+
 pub fn sys_print_info(args: &SyscallArgs) -> isize {
     let pid = ProcessId(args.arg0 as u16);
     if still_alive(pid) && get_process_manager().print_process_info(&pid) {
@@ -112,26 +962,252 @@ pub fn sys_print_info(args: &SyscallArgs) -> isize {
 }
 
 pub fn sys_time() -> u64 {
-    let uefi_runtime = get_uefi_runtime_for_sure();
-    let time = uefi_runtime.get_time();
-    time.hour() as u64 * 3600 + time.minute() as u64 * 60 + time.second() as u64
+    wall_time()
+}
+
+pub fn sys_uptime() -> u64 {
+    crate::interrupt::read_counter()
+}
+
+pub fn sys_thread(args: &SyscallArgs, context: &ProcessContext) -> isize {
+    let entry = match VirtAddr::try_new(args.arg0 as u64) {
+        Ok(entry) if !entry.is_null() => entry,
+        _ => return -1,
+    };
+    match thread(context, entry, [args.arg1, args.arg2]) {
+        Some(pid) => pid.0 as isize,
+        None => -1,
+    }
+}
+
+pub fn sys_clone(args: &SyscallArgs, context: &ProcessContext) -> isize {
+    let flags = match CloneFlags::from_bits(args.arg0) {
+        Some(flags) => flags,
+        None => return -1,
+    };
+    let entry = match VirtAddr::try_new(args.arg1 as u64) {
+        Ok(entry) if !entry.is_null() => entry,
+        _ => return -1,
+    };
+    let entry_args = match unsafe { (args.arg2 as *const [usize; 2]).as_ref() } {
+        Some(entry_args) => *entry_args,
+        None => return -1,
+    };
+    match clone_process(context, flags, entry, entry_args) {
+        Some(pid) => pid.0 as isize,
+        None => -1,
+    }
 }
 
-pub fn sys_fork(context: &mut ProcessContext) {
+pub fn sys_fork(args: &SyscallArgs, context: &mut ProcessContext) {
     trace!("Process {} is forking", get_pid());
-    fork(context);
+    fork(context, args.arg0 != 0);
+}
+
+pub fn sys_vfork(context: &mut ProcessContext) {
+    trace!("Process {} is vforking", get_pid());
+    vfork(context);
+}
+
+pub fn sys_yield(context: &mut ProcessContext) {
+    context.set_rax(0);
+    // the caller is pushed to the tail of the ready queue
+    switch(context);
+}
+
+pub fn sys_spin_hint(context: &mut ProcessContext) {
+    if proc::spin_hint() {
+        context.set_rax(1);
+        switch(context);
+    } else {
+        core::hint::spin_loop();
+        context.set_rax(0);
+    }
+}
+
+pub fn sys_getcpu() -> usize {
+    cpu_id()
+}
+
+pub fn sys_membarrier() {
+    membarrier();
+}
+
+pub fn sys_getrandom(args: &SyscallArgs) -> isize {
+    if !crate::memory::user::is_user_buffer(args.arg0 as u64, args.arg1) {
+        return -1;
+    }
+    let buf = unsafe { core::slice::from_raw_parts_mut(args.arg0 as *mut u8, args.arg1) };
+    crate::random::fill(buf);
+    buf.len() as isize
+}
+
+/// Log a user-space diagnostic with the full register context
+///
+/// NOTE: the process is not stopped as there is no stop / continue yet,
+/// it just returns to the caller
+pub fn sys_debug(args: &SyscallArgs, context: &ProcessContext) {
+    let frame = context.stack_frame();
+    warn!(
+        "DEBUG: code {:#x} from process #{} ({})",
+        args.arg0,
+        get_pid(),
+        current_name()
+    );
+    warn!(
+        "RIP: 0x{:016x}, RSP: 0x{:016x}, RFLAGS: 0x{:016x}",
+        frame.instruction_pointer,
+        frame.stack_pointer,
+        frame.cpu_flags.bits()
+    );
+    warn!("{:?}", context.regs);
+}
+
+pub fn sys_set_scheduler(args: &SyscallArgs) -> isize {
+    let policy = match syscall_def::SchedPolicy::try_from(args.arg0) {
+        Ok(policy) => policy,
+        Err(_) => return -1,
+    };
+    if !is_privileged() {
+        return -1;
+    }
+    set_sched_policy(policy);
+    0
+}
+
+pub fn sys_get_scheduler() -> usize {
+    sched_policy().into()
+}
+
+pub fn sys_set_log_level(args: &SyscallArgs) -> isize {
+    use log::LevelFilter;
+
+    let level = match args.arg0 {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Error,
+        2 => LevelFilter::Warn,
+        3 => LevelFilter::Info,
+        4 => LevelFilter::Debug,
+        5 => LevelFilter::Trace,
+        _ => return -1,
+    };
+    if !is_privileged() {
+        return -1;
+    }
+    crate::logger::set_level(level);
+    0
+}
+
+pub fn sys_serial_config(args: &SyscallArgs) -> isize {
+    let config = match unsafe { (args.arg0 as *const syscall_def::SerialConfig).as_ref() } {
+        Some(config) => config,
+        None => return -1,
+    };
+    if !is_privileged() || !crate::serial::configure(config) {
+        return -1;
+    }
+    0
+}
+
+pub fn sys_set_strace(args: &SyscallArgs) -> isize {
+    if !is_privileged() {
+        return -1;
+    }
+    super::set_strace(args.arg0 != 0);
+    0
+}
+
+pub fn sys_chroot(args: &SyscallArgs) -> isize {
+    let path = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => path,
+        None => return -1,
+    };
+    if !is_privileged() || !chroot(&path) {
+        return -1;
+    }
+    0
+}
+
+pub fn sys_drop_privilege() {
+    drop_privilege();
+}
+
+pub fn sys_shutdown() -> ! {
+    proc::shutdown()
+}
+
+pub fn sys_alarm(args: &SyscallArgs) -> u64 {
+    alarm(args.arg0 as u64)
+}
+
+pub fn sys_nanosleep(args: &SyscallArgs, context: &mut ProcessContext) {
+    nanosleep(args.arg0 as u64, context);
+}
+
+pub fn sys_sleep_until(args: &SyscallArgs, context: &mut ProcessContext) {
+    sleep_until(args.arg0 as u64, context);
+}
+
+pub fn sys_pause(context: &mut ProcessContext) {
+    pause(context);
+}
+
+pub fn sys_tsc_frequency() -> u64 {
+    crate::tsc::tsc_per_sec()
 }
 
 pub fn sys_sem(args: &SyscallArgs, context: &mut ProcessContext) {
     match args.arg0 {
-        0 => context.set_rax(new_sem(args.arg1 as u32, args.arg2)),
+        0 => context.set_rax(new_sem(args.arg1 as u32, args.arg2, false)),
         1 => context.set_rax(remove_sem(args.arg1 as u32)),
         2 => sem_signal(args.arg1 as u32, context),
-        3 => sem_wait(args.arg1 as u32, context),
+        3 => sem_wait(args.arg1 as u32, args.arg2 as u64, context),
+        4 => context.set_rax(new_sem(args.arg1 as u32, args.arg2, true)),
+        5 => sem_signal_all(args.arg1 as u32, args.arg2, context),
+        _ => context.set_rax(usize::MAX),
+    }
+}
+
+pub fn sys_barrier(args: &SyscallArgs, context: &mut ProcessContext) {
+    match args.arg0 {
+        0 => context.set_rax(new_barrier(args.arg1 as u32, args.arg2)),
+        1 => context.set_rax(remove_barrier(args.arg1 as u32)),
+        2 => barrier_wait(args.arg1 as u32, context),
         _ => context.set_rax(usize::MAX),
     }
 }
 
+pub fn sys_futex(args: &SyscallArgs, context: &mut ProcessContext) {
+    let addr = match VirtAddr::try_new(args.arg1 as u64) {
+        Ok(addr) => addr,
+        Err(_) => return context.set_rax(-1isize as usize),
+    };
+    match args.arg0 {
+        0 => futex_wait(addr, args.arg2 as u32, context),
+        1 => context.set_rax(futex_wake(addr, args.arg2) as usize),
+        _ => context.set_rax(-1isize as usize),
+    }
+}
+
+/// Set in the high bits of the key to fail instead of blocking
+const MSG_NOWAIT: usize = 1 << 32;
+
+pub fn sys_msg_get(args: &SyscallArgs) -> isize {
+    msg_get(args.arg0 as u32);
+    0
+}
+
+pub fn sys_msg_send(args: &SyscallArgs, context: &mut ProcessContext) {
+    let msg = unsafe { core::slice::from_raw_parts(args.arg1 as *const u8, args.arg2) };
+    let nowait = args.arg0 & MSG_NOWAIT != 0;
+    msg_send(args.arg0 as u32, msg, nowait, context);
+}
+
+pub fn sys_msg_recv(args: &SyscallArgs, context: &mut ProcessContext) {
+    let buf = unsafe { core::slice::from_raw_parts_mut(args.arg1 as *mut u8, args.arg2) };
+    msg_recv(args.arg0 as u32, buf, context);
+}
+
 pub fn sys_brk(args: &SyscallArgs) -> isize {
     match args.arg0 as usize {
         0 => brk(None),
@@ -143,17 +1219,278 @@ pub fn sys_get_pid() -> u16 {
     get_pid().0
 }
 
-pub fn sys_open_file(args: &SyscallArgs) -> usize {
-    let path = unsafe {
+pub fn sys_open_file(args: &SyscallArgs) -> isize {
+    let path = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => path,
+        None => return syscall_def::SysError::Inval.code(),
+    };
+    let flags = filesystem::OpenFlags::from_bits_truncate(args.arg2);
+    open_file(&path, flags)
+}
+
+pub fn sys_set_open_defaults(args: &SyscallArgs) {
+    set_open_defaults(filesystem::OpenFlags::from_bits_truncate(args.arg0));
+}
+
+pub fn sys_close_file(args: &SyscallArgs) -> isize {
+    let fd = args.arg0 as u8;
+    match close_file(fd) {
+        true => 0,
+        false => syscall_def::SysError::BadFd.code(),
+    }
+}
+
+pub fn sys_access(args: &SyscallArgs) -> isize {
+    let path = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => path,
+        None => return -1,
+    };
+    if filesystem::exists(&path) {
+        0
+    } else {
+        -1
+    }
+}
+
+pub fn sys_watch_add(args: &SyscallArgs) -> isize {
+    let path = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => path,
+        None => return -1,
+    };
+    watch_add(&path)
+}
+
+pub fn sys_watch_read(args: &SyscallArgs) -> isize {
+    let event = match unsafe { (args.arg1 as *mut syscall_def::WatchEvent).as_mut() } {
+        Some(event) => event,
+        None => return -1,
+    };
+    watch_read(args.arg0 as u8, event)
+}
+
+pub fn sys_unlink(args: &SyscallArgs) -> isize {
+    let path = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => path,
+        None => return -1,
+    };
+    filesystem::unlink(&path)
+}
+
+pub fn sys_symlink(args: &SyscallArgs) -> isize {
+    let target = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(target) => target,
+        None => return -1,
+    };
+    // the link path is passed as (ptr, len), since there are only three args
+    let link = match unsafe { (args.arg2 as *const [usize; 2]).as_ref() } {
+        Some(&[ptr, len]) => match copy_str_from_user(ptr, len) {
+            Some(link) => link,
+            None => return -1,
+        },
+        None => return filesystem::ENOENT,
+    };
+    symlink(&target, &link)
+}
+
+pub fn sys_readlink(args: &SyscallArgs) -> isize {
+    let path = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(path) => path,
+        None => return -1,
+    };
+    let buf = match unsafe { (args.arg2 as *const [usize; 2]).as_ref() } {
+        Some(&[ptr, len]) if is_user_range(ptr, len) => unsafe {
+            core::slice::from_raw_parts_mut(ptr as *mut u8, len)
+        },
+        _ => return syscall_def::SysError::Inval.code(),
+    };
+    match readlink(&path) {
+        Ok(target) => {
+            // the length is returned even if the buffer is too small
+            let len = target.len().min(buf.len());
+            buf[..len].copy_from_slice(&target.as_bytes()[..len]);
+            target.len() as isize
+        }
+        Err(err) => err,
+    }
+}
+
+pub fn sys_rename(args: &SyscallArgs) -> isize {
+    let src = match copy_str_from_user(args.arg0, args.arg1) {
+        Some(src) => src,
+        None => return -1,
+    };
+    // the dst path is passed as (ptr, len), since there are only three args
+    let dst = match unsafe { (args.arg2 as *const [usize; 2]).as_ref() } {
+        Some(&[ptr, len]) => match copy_str_from_user(ptr, len) {
+            Some(dst) => dst,
+            None => return -1,
+        },
+        None => return filesystem::ENOENT,
+    };
+    filesystem::rename(&src, &dst)
+}
+
+pub fn sys_block_reason(args: &SyscallArgs) -> isize {
+    match block_reason(ProcessId(args.arg0 as u16)) {
+        Some(Some(reason)) => usize::from(reason) as isize,
+        Some(None) => 0,
+        None => -1,
+    }
+}
+
+pub fn sys_wake_log(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    let buf = unsafe {
+        core::slice::from_raw_parts_mut(args.arg1 as *mut syscall_def::AuditEntry, args.arg2)
+    };
+    match wake_log(pid, buf) {
+        Some(count) => count as isize,
+        None => -1,
+    }
+}
+
+pub fn sys_get_env(args: &SyscallArgs) -> isize {
+    let key = unsafe {
         core::str::from_utf8_unchecked(core::slice::from_raw_parts(
             args.arg0 as *const u8,
             args.arg1,
         ))
     };
-    open_file(path) as usize
+    // the buffer is passed as (ptr, len), since there are only three args
+    let buf = match unsafe { (args.arg2 as *const [usize; 2]).as_ref() } {
+        Some(&[ptr, len]) => unsafe { core::slice::from_raw_parts_mut(ptr as *mut u8, len) },
+        None => return -1,
+    };
+    match env(key) {
+        Some(val) => {
+            let len = val.len().min(buf.len());
+            buf[..len].copy_from_slice(&val.as_bytes()[..len]);
+            val.len() as isize
+        }
+        None => -1,
+    }
 }
 
-pub fn sys_close_file(args: &SyscallArgs) -> bool {
-    let fd = args.arg0 as u8;
-    close_file(fd)
+pub fn sys_set_proc_name(args: &SyscallArgs) -> isize {
+    set_proc_name_from_user(args.arg0, args.arg1)
+}
+
+fn set_proc_name_from_user(ptr: usize, len: usize) -> isize {
+    let name = unsafe {
+        core::str::from_utf8_unchecked(core::slice::from_raw_parts(ptr as *const u8, len))
+    };
+    if name.is_empty() {
+        return -1;
+    }
+    set_proc_name(name) as isize
+}
+
+pub fn sys_get_proc_name(args: &SyscallArgs) -> isize {
+    // pid 0 means the caller
+    let pid = match args.arg0 as u16 {
+        0 => get_pid(),
+        pid => ProcessId(pid),
+    };
+    copy_proc_name_to_user(pid, args.arg1, args.arg2)
+}
+
+fn copy_proc_name_to_user(pid: ProcessId, ptr: usize, len: usize) -> isize {
+    let buf = unsafe { core::slice::from_raw_parts_mut(ptr as *mut u8, len) };
+    match proc_name(pid) {
+        Some(name) => {
+            let len = name.len().min(buf.len());
+            buf[..len].copy_from_slice(&name.as_bytes()[..len]);
+            name.len() as isize
+        }
+        None => -1,
+    }
+}
+
+pub fn sys_prctl(args: &SyscallArgs) -> isize {
+    match args.arg0 {
+        syscall_def::PR_SET_NAME => set_proc_name_from_user(args.arg1, args.arg2),
+        syscall_def::PR_GET_NAME => copy_proc_name_to_user(get_pid(), args.arg1, args.arg2),
+        syscall_def::PR_SET_NO_NEW_PRIVS if args.arg1 == 1 => {
+            set_no_new_privs();
+            0
+        }
+        syscall_def::PR_GET_NO_NEW_PRIVS => no_new_privs() as isize,
+        syscall_def::PR_SET_OPEN_DEFAULTS => {
+            set_open_defaults(filesystem::OpenFlags::from_bits_truncate(args.arg1));
+            0
+        }
+        syscall_def::PR_GET_OPEN_DEFAULTS => open_defaults().bits() as isize,
+        _ => -1,
+    }
+}
+
+pub fn sys_set_env(args: &SyscallArgs) -> isize {
+    let key = unsafe {
+        core::str::from_utf8_unchecked(core::slice::from_raw_parts(
+            args.arg0 as *const u8,
+            args.arg1,
+        ))
+    };
+    let val = match unsafe { (args.arg2 as *const [usize; 2]).as_ref() } {
+        Some(&[ptr, len]) => unsafe {
+            core::str::from_utf8_unchecked(core::slice::from_raw_parts(ptr as *const u8, len))
+        },
+        None => return -1,
+    };
+    set_env(key, val);
+    0
+}
+
+pub fn sys_pipe(args: &SyscallArgs) -> isize {
+    let fds = match unsafe { (args.arg0 as *mut [u8; 2]).as_mut() } {
+        Some(fds) => fds,
+        None => return -1,
+    };
+    let (read_fd, write_fd) = pipe();
+    *fds = [read_fd, write_fd];
+    0
+}
+
+pub fn sys_tee_pipe(args: &SyscallArgs) -> isize {
+    let readers = args.arg0;
+    if readers == 0 || readers > crate::tee::TEE_MAX_READERS || args.arg1 == 0 {
+        return -1;
+    }
+    let fds = unsafe { core::slice::from_raw_parts_mut(args.arg1 as *mut u8, readers) };
+    let (write_fd, read_fds) = proc::tee_pipe(readers);
+    fds.copy_from_slice(&read_fds);
+    write_fd as isize
+}
+
+pub fn sys_send_fd(args: &SyscallArgs) -> isize {
+    proc::send_fd(args.arg0 as u8, args.arg1 as u8)
+}
+
+pub fn sys_recv_fd(args: &SyscallArgs) -> isize {
+    proc::recv_fd(args.arg0 as u8)
+}
+
+pub fn sys_eventfd(args: &SyscallArgs) -> isize {
+    // bit 0 of the flags for the semaphore mode, the others are reserved
+    if args.arg1 & !1 != 0 {
+        return -1;
+    }
+    eventfd(args.arg0 as u64, args.arg1 & 1 != 0) as isize
+}
+
+pub fn sys_dup2(args: &SyscallArgs) -> isize {
+    dup2(args.arg0 as u8, args.arg1 as u8)
+}
+
+pub fn sys_capture_start() -> isize {
+    capture_start()
+}
+
+pub fn sys_capture_end(args: &SyscallArgs) -> isize {
+    let buf = unsafe { core::slice::from_raw_parts_mut(args.arg0 as *mut u8, args.arg1) };
+    capture_end(buf)
 }
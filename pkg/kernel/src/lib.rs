@@ -38,8 +38,10 @@ use boot::BootInfo;
 pub fn init(boot_info: &'static BootInfo) {
     serial::init(); // init serial output
     logger::init(boot_info.log_level); // init logger syste
+    init_panic_policy(boot_info.panic_policy);
     runtime::init(boot_info); // init runtime system
     memory::address::init(boot_info);
+    fb::init(boot_info); // record the framebuffer
     memory::gdt::init(); // init gdt
     memory::allocator::init(); // init kernel heap allocator
     proc::init(boot_info);
@@ -47,14 +49,51 @@ pub fn init(boot_info: &'static BootInfo) {
     interrupt::init(); // init interrupts
 
     x86_64::instructions::interrupts::enable();
+    tsc::init(); // calibrate tsc with the clock running
+    random::init(); // init random source
     filesystem::init(); // init filesystem
 
+    info!("Test log level gate.");
+    logger::test_level_gate();
+    info!("Log level gate test done.");
+
+    info!("Test strace format.");
+    interrupt::test_strace_format();
+    info!("Strace format test done.");
+
     info!("Test stack grow.");
     grow_stack();
     info!("Stack grow test done.");
 
+    memory::record_boot_frames();
+    proc::register_idle_hook("compact frames", 1, memory::compact_recycled_frames);
+
     info!("Interrupts Enabled.");
     info!("YatSenOS initialized.");
+
+    #[cfg(feature = "timer_test")]
+    interrupt::test_timers();
+
+    #[cfg(feature = "serial_test")]
+    serial::test_config();
+
+    #[cfg(feature = "sched_test")]
+    proc::test_idle_fallback();
+
+    #[cfg(feature = "switch_test")]
+    proc::test_switch_hook();
+
+    #[cfg(feature = "steal_test")]
+    proc::test_work_stealing();
+
+    #[cfg(feature = "bootarg_test")]
+    proc::test_boot_args();
+
+    #[cfg(feature = "sched_check")]
+    proc::test_invariant_check();
+
+    #[cfg(feature = "panic_test")]
+    panic!("Intentional panic to test the panic policy.");
 }
 
 pub fn shutdown(boot_info: &'static BootInfo) -> ! {
@@ -71,7 +110,11 @@ pub fn shutdown(boot_info: &'static BootInfo) -> ! {
 pub fn wait(init: proc::ProcessId) {
     loop {
         if proc::still_alive(init) {
+            // the scheduler only switches back here when no other process
+            // is ready, do the housekeeping and sleep until the next interrupt
+            proc::run_idle_hooks();
             x86_64::instructions::hlt(); // Why? Check reflection question 5
+            proc::count_idle_wakeup();
         } else {
             break;
         }
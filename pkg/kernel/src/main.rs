@@ -16,5 +16,6 @@ pub fn spawn_init() -> proc::ProcessId {
     // NOTE: you may want to clear the screen before starting the shell
     print!("\x1b[1;1H\x1b[2J");
     // proc::list_app();
-    proc::spawn("app/sh").unwrap()
+    let init = proc::boot_arg("init").unwrap_or_else(|| "app/sh".into());
+    proc::spawn(&init, false).unwrap()
 }
@@ -1,5 +1,6 @@
-use alloc::vec::Vec;
+use alloc::{collections::BTreeMap, vec::Vec};
 use boot::{MemoryMap, MemoryType};
+use syscall_def::{FrameStats, MemInfo};
 use x86_64::structures::paging::{FrameAllocator, FrameDeallocator, PhysFrame, Size4KiB};
 use x86_64::PhysAddr;
 
@@ -17,6 +18,9 @@ pub struct BootInfoFrameAllocator {
     used: usize,
     frames: BootInfoFrameIter,
     recycled: Vec<PhysFrame>,
+    /// The number of owners of the frames shared by cow forks,
+    /// the frames owned by one are not recorded
+    shared: BTreeMap<PhysFrame, usize>,
 }
 
 impl BootInfoFrameAllocator {
@@ -31,6 +35,7 @@ impl BootInfoFrameAllocator {
             frames: create_frame_iter(memory_map),
             used: 0,
             recycled: Vec::new(),
+            shared: BTreeMap::new(),
         }
     }
 
@@ -45,6 +50,68 @@ impl BootInfoFrameAllocator {
     pub fn frames_recycled(&self) -> usize {
         self.recycled.len()
     }
+
+    /// The frames allocated and not recycled yet
+    pub fn frames_in_use(&self) -> usize {
+        self.used - self.recycled.len()
+    }
+
+    /// The longest run of physically contiguous frames in the recycle list,
+    /// the frames never allocated are not counted
+    pub fn largest_recycled_run(&self) -> usize {
+        let mut frames = self.recycled.clone();
+        frames.sort_unstable();
+
+        let mut largest = 0;
+        let mut run = 0;
+        for (index, frame) in frames.iter().enumerate() {
+            if index > 0 && frames[index - 1] + 1 == *frame {
+                run += 1;
+            } else {
+                run = 1;
+            }
+            largest = largest.max(run);
+        }
+        largest
+    }
+
+    /// Sort the recycle list so the frames are reused from the lowest,
+    /// then consecutive allocations tend to be physically contiguous
+    pub fn compact_recycled(&mut self) {
+        // the frames are popped from the end
+        if !self.recycled.windows(2).all(|pair| pair[0] >= pair[1]) {
+            self.recycled.sort_unstable_by(|a, b| b.cmp(a));
+        }
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        FrameStats {
+            total: self.size,
+            used: self.used,
+            recycled: self.recycled.len(),
+            largest_run: self.largest_recycled_run(),
+        }
+    }
+
+    pub fn mem_info(&self) -> MemInfo {
+        MemInfo {
+            total: self.size,
+            used: self.used,
+            recycled: self.recycled.len(),
+            page_size: super::PAGE_SIZE as usize,
+        }
+    }
+
+    /// Add an owner to the frame, it is recycled after all the owners
+    /// deallocate it
+    pub fn share_frame(&mut self, frame: PhysFrame) {
+        *self.shared.entry(frame).or_insert(1) += 1;
+    }
+
+    /// The number of owners of the frame
+    pub fn ref_count(&self, frame: PhysFrame) -> usize {
+        self.shared.get(&frame).copied().unwrap_or(1)
+    }
 }
 
 unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
@@ -60,6 +127,14 @@ unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
 
 impl FrameDeallocator<Size4KiB> for BootInfoFrameAllocator {
     unsafe fn deallocate_frame(&mut self, frame: PhysFrame) {
+        // a shared frame loses an owner only
+        if let Some(count) = self.shared.get_mut(&frame) {
+            *count -= 1;
+            if *count == 1 {
+                self.shared.remove(&frame);
+            }
+            return;
+        }
         // deallocate frame
         self.recycled.push(frame);
     }
@@ -4,10 +4,35 @@ mod frames;
 
 pub mod gdt;
 pub mod user;
+pub mod vdso;
 
 pub use address::*;
 pub use frames::*;
 
+/// The frames in use once the kernel is initialized, before any app runs
+static BOOT_FRAMES: spin::Once<usize> = spin::Once::new();
+
+/// Keep the frames in use as the baseline of the leak check at shutdown
+pub fn record_boot_frames() {
+    let frames = get_frame_alloc_for_sure().frames_in_use();
+    BOOT_FRAMES.call_once(|| frames);
+}
+
+/// The frames in use over the baseline at boot
+pub fn residual_frames() -> usize {
+    let frames = get_frame_alloc_for_sure().frames_in_use();
+    frames.saturating_sub(BOOT_FRAMES.get().copied().unwrap_or(0))
+}
+
+/// Compact the recycled frames, an idle hook,
+/// skipped if the allocator is in use
+pub fn compact_recycled_frames() -> bool {
+    if let Some(mut alloc) = get_frame_alloc() {
+        alloc.compact_recycled();
+    }
+    false
+}
+
 pub fn init(boot_info: &'static boot::BootInfo) {
     let memory_map = &boot_info.memory_map;
 
@@ -35,6 +60,7 @@ pub fn init(boot_info: &'static boot::BootInfo) {
     }
 
     user::init();
+    vdso::init().expect("Clock Page Initialization Failed.");
 
     info!("Frame Allocator initialized.");
 }
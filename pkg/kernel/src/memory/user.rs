@@ -1,5 +1,9 @@
-use crate::proc::PageTableContext;
+use crate::proc::{PageTableContext, ProcessId};
+use alloc::collections::BTreeMap;
+use core::alloc::Layout;
+use core::ptr::NonNull;
 use linked_list_allocator::LockedHeap;
+use spin::Mutex;
 use x86_64::{
     structures::paging::{
         mapper::MapToError, FrameAllocator, Mapper, Page, PageTableFlags, Size4KiB,
@@ -11,8 +15,101 @@ pub const USER_HEAP_START: usize = 0x4000_0000_0000;
 pub const USER_HEAP_SIZE: usize = 1024 * 1024; // 1 MiB
 const USER_HEAP_PAGE: usize = USER_HEAP_SIZE / crate::memory::PAGE_SIZE as usize;
 
+/// The end of the lower half, all the user memory lives below it
+pub const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
 pub static USER_ALLOCATOR: LockedHeap = LockedHeap::empty();
 
+/// Live allocations of the user heap, from address to layout & the process
+/// that allocated it, `None` once that process has exited
+static USER_ALLOCATIONS: Mutex<BTreeMap<usize, (Layout, Option<ProcessId>)>> =
+    Mutex::new(BTreeMap::new());
+
+/// Allocate memory from the user heap, returns 0 if failed
+/// Whether the buffer lies entirely in the user space
+pub fn is_user_buffer(addr: u64, len: usize) -> bool {
+    addr != 0
+        && addr
+            .checked_add(len as u64)
+            .is_some_and(|end| end <= USER_SPACE_END)
+}
+
+pub fn user_alloc(layout: Layout, owner: ProcessId) -> usize {
+    match USER_ALLOCATOR.lock().allocate_first_fit(layout) {
+        Ok(ptr) => {
+            let addr = ptr.as_ptr() as usize;
+            USER_ALLOCATIONS.lock().insert(addr, (layout, Some(owner)));
+            addr
+        }
+        Err(_) => 0,
+    }
+}
+
+/// Free memory of the user heap
+///
+/// the pointer must be returned by `user_alloc` with the same layout,
+/// otherwise the free is ignored to keep the allocator consistent
+pub fn user_dealloc(addr: usize, layout: Layout) -> bool {
+    if !(USER_HEAP_START..USER_HEAP_START + USER_HEAP_SIZE).contains(&addr) {
+        warn!("Ignore freeing {:#x}: out of the user heap.", addr);
+        return false;
+    }
+
+    let mut allocations = USER_ALLOCATIONS.lock();
+    match allocations.get(&addr) {
+        Some((origin, _)) if *origin == layout => {
+            allocations.remove(&addr);
+        }
+        Some((origin, _)) => {
+            warn!(
+                "Ignore freeing {:#x}: layout {:?} mismatches {:?}.",
+                addr, layout, origin
+            );
+            return false;
+        }
+        None => {
+            warn!("Ignore freeing {:#x}: unknown pointer.", addr);
+            return false;
+        }
+    }
+
+    unsafe {
+        USER_ALLOCATOR
+            .lock()
+            .deallocate(NonNull::new_unchecked(addr as *mut u8), layout);
+    }
+
+    true
+}
+
+/// The live allocations made by `owner` & the bytes they hold,
+/// whoever frees them, e.g. to find the leaks of a process
+pub fn user_alloc_stats(owner: ProcessId) -> (u64, u64) {
+    USER_ALLOCATIONS
+        .lock()
+        .values()
+        .filter(|(_, pid)| *pid == Some(owner))
+        .fold((0, 0), |(count, bytes), (layout, _)| {
+            (count + 1, bytes + layout.size() as u64)
+        })
+}
+
+/// Disown the allocations left by `owner` once it exits, so a process
+/// reusing the pid starts from none, returns them like `user_alloc_stats`
+///
+/// they are not freed, as another process may still use them
+pub fn user_alloc_disown(owner: ProcessId) -> (u64, u64) {
+    let stats = user_alloc_stats(owner);
+    if stats.0 > 0 {
+        for (_, pid) in USER_ALLOCATIONS.lock().values_mut() {
+            if *pid == Some(owner) {
+                *pid = None;
+            }
+        }
+    }
+    stats
+}
+
 // NOTE: export mod user / call in the kernel init / after frame allocator
 pub fn init() {
     init_user_heap().expect("User Heap Initialization Failed.");
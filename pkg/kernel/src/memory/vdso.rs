@@ -0,0 +1,50 @@
+//! The clock page
+//!
+//! a single frame mapped read-only at `VDSO_ADDR` in the kernel page table,
+//! which every process inherits, so the time is read with a plain load
+
+use super::*;
+use crate::proc::PageTableContext;
+use core::ptr::addr_of_mut;
+use syscall_def::{VdsoData, VDSO_ADDR};
+use x86_64::{
+    structures::paging::{
+        mapper::MapToError, FrameAllocator, Mapper, Page, PageTableFlags, Size4KiB,
+    },
+    VirtAddr,
+};
+
+/// The kernel address of the clock page
+static DATA: spin::Once<u64> = spin::Once::new();
+
+// NOTE: call after the frame allocator, before any process is created
+pub fn init() -> Result<(), MapToError<Size4KiB>> {
+    let mapper = &mut PageTableContext::new().mapper();
+    let frame_allocator = &mut *get_frame_alloc_for_sure();
+
+    let frame = frame_allocator
+        .allocate_frame()
+        .ok_or(MapToError::FrameAllocationFailed)?;
+    let data = physical_to_virtual(frame.start_address().as_u64());
+    unsafe { core::ptr::write_bytes(data as *mut u8, 0, PAGE_SIZE as usize) };
+
+    // not writable, a copy-on-write fork shares it as it is, and the kernel
+    // keeps an owner, so the frame is never recycled
+    let page = Page::containing_address(VirtAddr::new(VDSO_ADDR));
+    let flags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+    unsafe { mapper.map_to(page, frame, flags, frame_allocator)?.flush() };
+
+    DATA.call_once(|| data);
+    Ok(())
+}
+
+/// Publish the clock ticks, called by the clock interrupt on every tick
+pub fn update(ticks: u64) {
+    if let Some(&addr) = DATA.get() {
+        let data = addr as *mut VdsoData;
+        unsafe {
+            addr_of_mut!((*data).ticks_per_sec).write_volatile(crate::tsc::ticks_per_sec());
+            addr_of_mut!((*data).ticks).write_volatile(ticks);
+        }
+    }
+}
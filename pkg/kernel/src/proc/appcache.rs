@@ -0,0 +1,78 @@
+use alloc::{collections::VecDeque, string::String, sync::Arc, vec::Vec};
+use spin::Mutex;
+
+use crate::filesystem::normalize_path;
+
+/// The maximum number of apps kept, the least recently used is dropped first
+pub const APP_CACHE_SIZE: usize = 4;
+/// The maximum bytes of all the apps kept, a larger app is never kept
+pub const APP_CACHE_BYTES: usize = 1024 * 1024;
+
+/// The images of the apps spawned lately, so the spawns of the same app
+/// skip reading it from the disk
+static APP_CACHE: Mutex<AppCache> = Mutex::new(AppCache::new());
+
+struct AppCache {
+    // keyed by the normalized path, the most recently used last
+    apps: VecDeque<(String, Arc<Vec<u8>>)>,
+    bytes: usize,
+}
+
+impl AppCache {
+    const fn new() -> Self {
+        Self {
+            apps: VecDeque::new(),
+            bytes: 0,
+        }
+    }
+
+    fn get(&mut self, path: &str) -> Option<Arc<Vec<u8>>> {
+        let index = self.apps.iter().position(|(p, _)| p == path)?;
+        let app = self.apps.remove(index)?;
+        let image = app.1.clone();
+        self.apps.push_back(app);
+        Some(image)
+    }
+
+    fn insert(&mut self, path: String, image: Arc<Vec<u8>>) {
+        if image.len() > APP_CACHE_BYTES {
+            return;
+        }
+        self.remove(&path);
+        while self.apps.len() == APP_CACHE_SIZE || self.bytes + image.len() > APP_CACHE_BYTES {
+            if let Some((_, old)) = self.apps.pop_front() {
+                self.bytes -= old.len();
+            }
+        }
+        self.bytes += image.len();
+        self.apps.push_back((path, image));
+    }
+
+    fn remove(&mut self, path: &str) {
+        if let Some(index) = self.apps.iter().position(|(p, _)| p == path) {
+            let (_, old) = self.apps.remove(index).unwrap();
+            self.bytes -= old.len();
+        }
+    }
+}
+
+/// The image of the app at `path`, from the cache or by `read` on a miss,
+/// only ELF files are kept
+pub fn get_or_read(path: &str, read: impl FnOnce() -> Option<Vec<u8>>) -> Option<Arc<Vec<u8>>> {
+    let path = normalize_path(path);
+    if let Some(image) = APP_CACHE.lock().get(&path) {
+        trace!("App cache hit: {}", path);
+        return Some(image);
+    }
+
+    let image = Arc::new(read()?);
+    if image.starts_with(b"\x7fELF") {
+        APP_CACHE.lock().insert(path, image.clone());
+    }
+    Some(image)
+}
+
+/// Drop the cached image of the file at `path`, once it may be modified
+pub fn forget_app(path: &str) {
+    APP_CACHE.lock().remove(&normalize_path(path));
+}
@@ -0,0 +1,95 @@
+use alloc::{format, string::String};
+use syscall_def::{AuditEntry, BlockReason, WakeSource, AUDIT_BLOCKED, AUDIT_STOPPED, AUDIT_WOKEN};
+
+/// The number of transitions kept, the oldest ones are dropped first
+pub const AUDIT_LOG_SIZE: usize = 16;
+
+/// The last state transitions of a process, to see why it woke
+#[derive(Debug, Clone, Copy)]
+pub struct AuditLog {
+    entries: [AuditEntry; AUDIT_LOG_SIZE],
+    // where the next entry goes
+    next: usize,
+    len: usize,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self {
+            entries: [AuditEntry::default(); AUDIT_LOG_SIZE],
+            next: 0,
+            len: 0,
+        }
+    }
+}
+
+impl AuditLog {
+    fn push(&mut self, kind: usize, detail: usize) {
+        self.entries[self.next] = AuditEntry {
+            tick: crate::interrupt::read_counter(),
+            kind,
+            detail,
+        };
+        self.next = (self.next + 1) % AUDIT_LOG_SIZE;
+        self.len = (self.len + 1).min(AUDIT_LOG_SIZE);
+    }
+
+    pub fn blocked(&mut self, reason: BlockReason) {
+        self.push(AUDIT_BLOCKED, reason.into());
+    }
+
+    pub fn woken(&mut self, source: WakeSource) {
+        self.push(AUDIT_WOKEN, source.into());
+    }
+
+    pub fn stopped(&mut self) {
+        self.push(AUDIT_STOPPED, 0);
+    }
+
+    /// The entries from the oldest to the latest
+    pub fn iter(&self) -> impl Iterator<Item = &AuditEntry> {
+        let start = (self.next + AUDIT_LOG_SIZE - self.len) % AUDIT_LOG_SIZE;
+        (0..self.len).map(move |i| &self.entries[(start + i) % AUDIT_LOG_SIZE])
+    }
+
+    /// Copy the latest entries into `buf`, oldest first,
+    /// returns the number copied
+    pub fn copy_to(&self, buf: &mut [AuditEntry]) -> usize {
+        let skip = self.len.saturating_sub(buf.len());
+        let mut count = 0;
+        for (dst, src) in buf.iter_mut().zip(self.iter().skip(skip)) {
+            *dst = *src;
+            count += 1;
+        }
+        count
+    }
+}
+
+/// The source that wakes a process blocked for `reason`
+pub fn wake_source(reason: BlockReason) -> WakeSource {
+    match reason {
+        BlockReason::Sleeping => WakeSource::Timer,
+        BlockReason::WaitingChild => WakeSource::ChildExit,
+        BlockReason::Semaphore => WakeSource::Semaphore,
+        BlockReason::Message => WakeSource::Message,
+        BlockReason::Futex => WakeSource::Futex,
+        BlockReason::Vfork => WakeSource::VforkDone,
+        BlockReason::Paused => WakeSource::Signal,
+    }
+}
+
+/// Describe the entry, e.g. `Blocked(Semaphore)` or `Woken(Timer)`
+pub fn describe(entry: &AuditEntry) -> String {
+    match entry.kind {
+        AUDIT_BLOCKED => match BlockReason::try_from(entry.detail) {
+            Ok(reason) => format!("Blocked({:?})", reason),
+            Err(_) => format!("Blocked({})", entry.detail),
+        },
+        AUDIT_WOKEN => match WakeSource::try_from(entry.detail) {
+            Ok(source) => format!("Woken({:?})", source),
+            Err(_) => format!("Woken({})", entry.detail),
+        },
+        AUDIT_STOPPED => String::from("Stopped"),
+        kind => format!("Unknown({})", kind),
+    }
+}
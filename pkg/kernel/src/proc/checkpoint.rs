@@ -0,0 +1,19 @@
+use super::ProcessContext;
+use crate::resource::Resource;
+use alloc::{collections::BTreeMap, sync::Weak, vec::Vec};
+use spin::Mutex;
+use x86_64::{structures::paging::Page, VirtAddr};
+
+/// The maximum number of pages saved by a checkpoint, which is held in the kernel heap
+pub const CHECKPOINT_MAX_PAGES: usize = 256;
+
+/// The state of a process saved by `Syscall::Checkpoint`
+///
+/// the pages are the mapped ones of the writable segments, the heap & the stack,
+/// the fds refer to their resources without keeping them open
+pub struct Checkpoint {
+    pub(super) context: ProcessContext,
+    pub(super) pages: Vec<(Page, Vec<u8>)>,
+    pub(super) heap_end: VirtAddr,
+    pub(super) fds: BTreeMap<u8, Weak<Mutex<Resource>>>,
+}
@@ -46,6 +46,13 @@ impl ProcessContext {
         context.as_mut().as_mut_ptr().write(self.value);
     }
 
+    /// Rewind the instruction pointer to the `int 0x80` instruction,
+    /// so the syscall is issued again when the process is resumed
+    #[inline]
+    pub fn restart_syscall(&mut self) {
+        self.value.stack_frame.instruction_pointer -= 2u64;
+    }
+
     pub fn stack_frame(&self) -> &InterruptStackFrameValue {
         &self.value.stack_frame
     }
@@ -63,6 +70,17 @@ impl ProcessContext {
         trace!("Init stack frame: {:#?}", &self.stack_frame);
     }
 
+    /// Call `entry` with the arguments on the current stack,
+    /// the callee never returns, the red zone is kept
+    pub fn enter_handler(&mut self, entry: VirtAddr, args: [usize; 2]) {
+        let stack_top = (self.stack_top() - 128) & !0xf;
+        // aligned as if the return address is pushed by a `call`
+        self.value.stack_frame.stack_pointer = VirtAddr::new(stack_top - 8);
+        self.value.stack_frame.instruction_pointer = entry;
+        self.value.regs.rdi = args[0];
+        self.value.regs.rsi = args[1];
+    }
+
     pub fn update_stack_frame(&mut self, stack_top: VirtAddr) {
         self.value.stack_frame.stack_pointer = stack_top;
     }
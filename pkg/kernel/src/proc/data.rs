@@ -1,18 +1,26 @@
-use alloc::{collections::BTreeMap, string::String, sync::Arc};
-use spin::RwLock;
-use storage::FileSystem;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::{format, string::String, vec::Vec};
+use alloc::sync::{Arc, Weak};
+use spin::{Mutex, RwLock};
+use storage::{Advice, FsError, SeekFrom};
+use syscall_def::{Dirent, FileStat, SigAction, WatchEvent};
 
-use crate::{filesystem::get_rootfs, resource::*};
+use crate::{filesystem::*, resource::*};
 
+use super::limits::Limits;
 use super::*;
 use sync::SemaphoreSet;
+use syscall_def::RLIMIT_NOFILE;
 
 #[derive(Debug, Clone)]
 pub struct ProcessData {
-    // shared data
-    pub(super) env: Arc<RwLock<BTreeMap<String, String>>>,
+    // environment variables, shared with the forked children until
+    // either side modifies them, then the map is cloned (copy on write),
+    // the outer lock is shared by the children cloned with `CLONE_ENV`
+    pub(super) env: Arc<RwLock<Arc<BTreeMap<String, String>>>>,
 
-    // file descriptors table
+    // file descriptors table, copied on fork,
+    // but the resources behind the fds are shared
     pub(super) resources: Arc<RwLock<ResourceSet>>,
 
     // the number of page that code segment is mapped
@@ -20,15 +28,97 @@ pub struct ProcessData {
 
     // semaphores
     pub(super) semaphores: Arc<RwLock<SemaphoreSet>>,
+
+    // the number of children that are not reaped yet
+    pub(super) child_count: usize,
+
+    // process group id, inherited on fork
+    pub(super) pgid: ProcessId,
+
+    // session id, inherited on fork & spawn, the pid of the session leader
+    pub(super) sid: ProcessId,
+
+    // whether the restricted syscalls are allowed, set for the init process
+    // & inherited on fork unless `no_new_privs`, dropped for good by `DropPrivilege`
+    pub(super) privileged: bool,
+
+    // whether no child may be privileged, set for good by `Prctl`,
+    // inherited by both forked and spawned children
+    pub(super) no_new_privs: bool,
+
+    // the number of spawns from the kernel to this process,
+    // kept on fork and increased by one on spawn
+    pub(super) spawn_depth: usize,
+
+    // the scheduling priority under `SchedPolicy::Priority`, lower runs first,
+    // inherited by both forked and spawned children
+    pub(super) priority: usize,
+
+    // the flags added to every `Open`, like a umask,
+    // inherited by both forked and spawned children
+    pub(super) open_defaults: OpenFlags,
+
+    // the ticks, children, fds & memory allowed,
+    // inherited by both forked and spawned children
+    pub(super) limits: Limits,
+
+    // the CPUs the process may run on, bit n for CPU n,
+    // inherited by both forked and spawned children
+    pub(super) affinity: u64,
+
+    // the signals held pending, bit `sig - 1` for each,
+    // inherited by forked children only
+    pub(super) sigmask: u64,
+
+    // the handlers set by `Sigaction`, the others take the default action,
+    // inherited by forked children only, reset on exec with the image
+    pub(super) sigactions: BTreeMap<usize, SigAction>,
+
+    // the working dir, relative paths of `Open` start from it,
+    // inherited by both forked and spawned children unless given on spawn
+    pub(super) cwd: String,
+
+    // the root dir set by `Chroot`, absolute paths start from it & `..`
+    // never goes above it, inherited by both forked and spawned children
+    pub(super) root: String,
+
+    // the arguments given on spawn, kept on fork & exec
+    pub(super) args: Vec<String>,
+
+    // the initial & the maximum size of the stack, kept on fork & exec,
+    // the default for a spawned child unless given
+    pub(super) stack_size: StackSize,
+
+    // the stack canary of the image, drawn again on every load of an ELF,
+    // kept on fork as the copy of the memory holds it
+    pub(super) canary: u64,
 }
 
 impl Default for ProcessData {
     fn default() -> Self {
         Self {
-            env: Arc::new(RwLock::new(BTreeMap::new())),
+            env: Arc::default(),
             resources: Arc::new(RwLock::new(ResourceSet::default())),
             code_segment_pages: 0,
             semaphores: Arc::new(RwLock::new(SemaphoreSet::new())),
+            child_count: 0,
+            // set to the pid of the new process unless given
+            pgid: ProcessId(0),
+            sid: ProcessId(0),
+            privileged: false,
+            no_new_privs: false,
+            spawn_depth: 0,
+            priority: DEFAULT_PRIORITY,
+            open_defaults: OpenFlags::empty(),
+            limits: Limits::default(),
+            affinity: ALL_CPUS,
+            sigmask: 0,
+            sigactions: BTreeMap::new(),
+            cwd: String::from("/"),
+            root: String::from("/"),
+            args: Vec::new(),
+            stack_size: StackSize::default(),
+            canary: 0,
         }
     }
 }
@@ -38,44 +128,426 @@ impl ProcessData {
         Self::default()
     }
 
+    /// Clone the data for a forked child, with its own copy of the fd table
+    /// and the environment
+    pub fn fork(&self) -> Self {
+        let mut data = self.clone();
+        data.resources = Arc::new(RwLock::new(self.resources.read().clone()));
+        data.env = Arc::new(RwLock::new(self.env.read().clone()));
+        data.privileged = self.privileged && !self.no_new_privs;
+        data
+    }
+
+    /// Start with a copy of the environment of `other`,
+    /// e.g. init with the boot arguments kept in the kernel
+    pub fn copy_env(&mut self, other: &ProcessData) {
+        self.env = Arc::new(RwLock::new(other.env.read().clone()));
+    }
+
+    /// Close all the fds, a resource is released once its last fd is closed,
+    /// e.g. the read end of a pipe sees EOF after the last write end is gone
+    pub fn close_all(&self) {
+        self.resources.write().handles.clear();
+    }
+
+    pub fn fd_stats(&self, fd: u8) -> Option<(u64, u64)> {
+        self.resources.read().fd_stats(fd)
+    }
+
+    pub fn sendfile(&self, out_fd: u8, in_fd: u8, count: usize) -> isize {
+        self.resources.read().sendfile(out_fd, in_fd, count)
+    }
+
+    pub fn splice(&self, in_fd: u8, out_fd: u8, count: usize) -> isize {
+        self.resources.read().splice(in_fd, out_fd, count)
+    }
+
+    pub fn set_cloexec(&self, fd: u8, cloexec: bool) -> isize {
+        self.resources.write().set_cloexec(fd, cloexec)
+    }
+
+    pub fn fcntl(&self, fd: u8, cmd: usize, arg: usize) -> isize {
+        self.resources.write().fcntl(fd, cmd, arg)
+    }
+
+    pub fn close_on_exec(&self) {
+        self.resources.write().close_on_exec()
+    }
+
+    pub fn cwd(&self) -> &str {
+        &self.cwd
+    }
+
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// The absolute path of `path`, a relative one starts from the working dir
+    /// and an absolute one from the root, the `.` & `..` are dropped
+    pub fn resolve(&self, path: &str) -> String {
+        let base = if path.starts_with('/') {
+            &self.root
+        } else {
+            &self.cwd
+        };
+        let floor = components(&self.root).count();
+        let mut parts: Vec<&str> = components(base).collect();
+        for part in components(path) {
+            match part {
+                "." => {}
+                ".." => {
+                    // the root is its own parent
+                    if parts.len() > floor {
+                        parts.pop();
+                    }
+                }
+                part => parts.push(part),
+            }
+        }
+        format!("/{}", parts.join("/"))
+    }
+
+    /// Resolve `path` like `resolve`, then follow the symlinks in it,
+    /// see `follow`
+    pub fn lookup(&self, path: &str) -> Result<String, isize> {
+        self.follow(&self.resolve(path))
+    }
+
+    /// Resolve `path` following the symlinks in its dirs but not in the
+    /// last name, e.g. to create or read the link itself
+    pub fn lookup_link(&self, path: &str) -> Result<String, isize> {
+        let path = self.resolve(path);
+        match path.rsplit_once('/') {
+            Some((dir, name)) if !name.is_empty() => {
+                let dir = self.follow(if dir.is_empty() { "/" } else { dir })?;
+                Ok(format!("{}/{}", dir.trim_end_matches('/'), name))
+            }
+            _ => Ok(path),
+        }
+    }
+
+    /// Follow the symlinks in the resolved `path`, a relative target is
+    /// from the dir of its link, an absolute one from the root
+    ///
+    /// returns `ELOOP` once more than `SYMLOOP_MAX` links are followed
+    fn follow(&self, path: &str) -> Result<String, isize> {
+        let floor = components(&self.root).count();
+        let mut pending: VecDeque<String> = components(path).map(String::from).collect();
+        let mut parts: Vec<String> = Vec::new();
+        let mut followed = 0;
+        while let Some(part) = pending.pop_front() {
+            match part.as_str() {
+                "." => continue,
+                ".." => {
+                    if parts.len() > floor {
+                        parts.pop();
+                    }
+                    continue;
+                }
+                _ => parts.push(part),
+            }
+            let target = match crate::filesystem::readlink(&parts.join("/")) {
+                Some(target) => target,
+                None => continue,
+            };
+            followed += 1;
+            if followed > SYMLOOP_MAX {
+                return Err(ELOOP);
+            }
+            parts.pop();
+            if target.starts_with('/') {
+                parts.truncate(floor);
+            }
+            for part in components(&target).rev() {
+                pending.push_front(part.into());
+            }
+        }
+        Ok(format!("/{}", parts.join("/")))
+    }
+
+    /// Create a symlink at `link` to `target`, see `filesystem::symlink`
+    pub fn symlink(&self, target: &str, link: &str) -> isize {
+        match self.lookup_link(link) {
+            Ok(link) => crate::filesystem::symlink(target, &link),
+            Err(err) => err,
+        }
+    }
+
+    /// The target of the symlink at `path`,
+    /// `Inval` if it is not a link, `ENOENT` if nothing is there
+    pub fn readlink(&self, path: &str) -> Result<String, isize> {
+        let path = self.lookup_link(path)?;
+        match crate::filesystem::readlink(&path) {
+            Some(target) => Ok(target),
+            None if is_dir(&path) || exists(&path) => Err(SysError::Inval.code()),
+            None => Err(ENOENT),
+        }
+    }
+
+    /// Make the dir at `path` the root & the working dir,
+    /// returns false if it is not a dir
+    pub fn chroot(&mut self, path: &str) -> bool {
+        let root = match self.lookup(path) {
+            Ok(root) => root,
+            Err(_) => return false,
+        };
+        if !is_dir(&root) {
+            return false;
+        }
+        self.cwd.clone_from(&root);
+        self.root = root;
+        true
+    }
+
+    /// The argument at `index`, `None` past the last one
+    pub fn arg(&self, index: usize) -> Option<&str> {
+        self.args.get(index).map(String::as_str)
+    }
+
     pub fn env(&self, key: &str) -> Option<String> {
         self.env.read().get(key).cloned()
     }
 
     pub fn set_env(&mut self, key: &str, val: &str) {
-        self.env.write().insert(key.into(), val.into());
+        // clone the map first if it is still shared with other processes
+        Arc::make_mut(&mut *self.env.write()).insert(key.into(), val.into());
     }
 
     pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
         self.resources.read().read(fd, buf)
     }
 
+    pub fn try_read(&self, fd: u8, buf: &mut [u8]) -> isize {
+        self.resources.read().try_read(fd, buf)
+    }
+
     pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
         self.resources.read().write(fd, buf)
     }
 
+    pub fn readv(&self, fd: u8, bufs: &mut [&mut [u8]]) -> isize {
+        self.resources.read().readv(fd, bufs)
+    }
+
+    pub fn writev(&self, fd: u8, bufs: &[&[u8]]) -> isize {
+        self.resources.read().writev(fd, bufs)
+    }
+
+    pub fn poll(&self, fds: &[u8], flags: &mut [u8]) -> isize {
+        self.resources.read().poll(fds, flags)
+    }
+
+    pub fn fstat(&self, fd: u8) -> Option<FileStat> {
+        self.resources.read().fstat(fd)
+    }
+
+    pub fn isatty(&self, fd: u8) -> isize {
+        self.resources.read().isatty(fd)
+    }
+
+    pub fn getdents(&self, fd: u8, cursor: usize, dirents: &mut [Dirent]) -> isize {
+        self.resources.read().getdents(fd, cursor, dirents)
+    }
+
+    pub fn seek(&self, fd: u8, pos: SeekFrom) -> isize {
+        self.resources.read().seek(fd, pos)
+    }
+
+    pub fn fchmod(&self, fd: u8, mode: usize) -> isize {
+        self.resources.read().fchmod(fd, mode)
+    }
+
+    pub fn resource(&self, fd: u8) -> Option<Arc<Mutex<Resource>>> {
+        self.resources.read().get(fd)
+    }
+
+    pub fn install(&self, fd: u8, res: Arc<Mutex<Resource>>) {
+        self.resources.write().install(fd, res)
+    }
+
+    pub fn ftruncate(&self, fd: u8, len: usize) -> isize {
+        self.resources.read().ftruncate(fd, len)
+    }
+
+    pub fn fsync(&self, fd: u8) -> isize {
+        self.resources.read().fsync(fd)
+    }
+
+    pub fn fadvise(&self, fd: u8, advice: Advice) -> isize {
+        self.resources.read().fadvise(fd, advice)
+    }
+
     pub fn sem_wait(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
         self.semaphores.write().wait(key, pid)
     }
 
-    pub fn sem_signal(&self, key: u32) -> SemaphoreResult {
-        self.semaphores.write().signal(key)
+    pub fn sem_signal(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        self.semaphores.write().signal(key, pid)
     }
 
-    pub fn new_sem(&self, key: u32, value: usize) -> bool {
-        self.semaphores.write().insert(key, value)
+    pub fn sem_signal_all(&self, key: u32, pid: ProcessId, value: usize) -> SemaphoreResult {
+        self.semaphores.write().signal_all(key, pid, value)
+    }
+
+    pub fn sem_set_timeout(&self, key: u32, pid: ProcessId, timer: u64) -> SemaphoreResult {
+        self.semaphores.read().set_timeout(key, pid, timer)
+    }
+
+    pub fn sem_timeout(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        self.semaphores.read().timeout(key, pid)
+    }
+
+    pub fn new_sem(&self, key: u32, value: usize, robust: bool) -> bool {
+        self.semaphores.write().insert(key, value, robust)
+    }
+
+    /// Release the semaphores for the exiting `pid`, see `SemaphoreSet::release`
+    pub fn release_semaphores(&self, pid: ProcessId) -> Vec<ProcessId> {
+        self.semaphores.read().release(pid)
     }
 
     pub fn remove_sem(&self, key: u32) -> bool {
         self.semaphores.write().remove(key)
     }
 
-    pub fn open_file(&self, path: &str) -> u8 {
-        let handle = get_rootfs().open_file(path).unwrap();
-        self.resources.write().open(Resource::File(handle))
+    pub fn new_barrier(&self, key: u32, count: usize) -> bool {
+        self.semaphores.write().insert_barrier(key, count)
+    }
+
+    pub fn remove_barrier(&self, key: u32) -> bool {
+        self.semaphores.write().remove_barrier(key)
+    }
+
+    pub fn barrier_wait(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        self.semaphores.read().barrier_wait(key, pid)
+    }
+
+    pub fn open_defaults(&self) -> OpenFlags {
+        self.open_defaults
+    }
+
+    pub fn set_open_defaults(&mut self, flags: OpenFlags) {
+        self.open_defaults = flags;
+    }
+
+    pub fn canary(&self) -> u64 {
+        self.canary
+    }
+
+    pub fn no_new_privs(&self) -> bool {
+        self.no_new_privs
+    }
+
+    /// Keep every later child unprivileged, there is no way to clear it
+    pub fn set_no_new_privs(&mut self) {
+        self.no_new_privs = true;
+    }
+
+    /// Open the file, or the dir to read its entries by `getdents`,
+    /// the open defaults are added to the `flags`
+    ///
+    /// returns the fd, `EACCES` if writing a read-only file is requested,
+    /// `EMFILE` if the fd limit is reached, `EEXIST` if the file to create
+    /// exclusively exists, `ELOOP` if the symlinks in the path loop,
+    /// or `ENOENT` if the file cannot be opened
+    pub fn open_file(&self, path: &str, flags: OpenFlags) -> isize {
+        if self.is_fd_limit_reached() {
+            return EMFILE;
+        }
+        let flags = flags | self.open_defaults;
+        let path = &match self.lookup(path) {
+            Ok(path) => path,
+            Err(err) => return err,
+        };
+        if is_dir(path) {
+            return self.resources.write().open(Resource::Dir(path.into())) as isize;
+        }
+        match OpenFile::open(path, flags) {
+            Ok(file) => self.resources.write().open(Resource::File(file)) as isize,
+            Err(FsError::ReadOnly) => EACCES,
+            Err(FsError::AlreadyExists) => EEXIST,
+            Err(_) => ENOENT,
+        }
+    }
+
+    /// Open a resource other than a file, e.g. the memory of a process,
+    /// returns the fd or `EMFILE` if the fd limit is reached
+    pub fn open_resource(&self, res: Resource) -> isize {
+        if self.is_fd_limit_reached() {
+            return EMFILE;
+        }
+        self.resources.write().open(res) as isize
+    }
+
+    fn is_fd_limit_reached(&self) -> bool {
+        self.resources.read().handles.len() as u64 >= self.limits.soft(RLIMIT_NOFILE)
     }
 
     pub fn close_file(&self, fd: u8) -> bool {
         self.resources.write().close(fd)
     }
+
+    pub fn dup2(&self, old_fd: u8, new_fd: u8) -> isize {
+        self.resources.write().dup2(old_fd, new_fd)
+    }
+
+    pub fn pipe(&self) -> (u8, u8) {
+        self.resources.write().pipe()
+    }
+
+    pub fn snapshot_fds(&self) -> BTreeMap<u8, Weak<Mutex<Resource>>> {
+        self.resources.read().snapshot_fds()
+    }
+
+    pub fn restore_fds(&self, fds: &BTreeMap<u8, Weak<Mutex<Resource>>>) {
+        self.resources.write().restore_fds(fds)
+    }
+
+    pub fn tee_pipe(&self, readers: usize) -> (u8, Vec<u8>) {
+        self.resources.write().tee_pipe(readers)
+    }
+
+    pub fn send_fd(&self, channel_fd: u8, fd: u8) -> isize {
+        self.resources.read().send_fd(channel_fd, fd)
+    }
+
+    pub fn recv_fd(&self, channel_fd: u8) -> isize {
+        if self.is_fd_limit_reached() {
+            return EMFILE;
+        }
+        self.resources.write().recv_fd(channel_fd)
+    }
+
+    /// Open a watch fd on the existing file or dir at `path`
+    pub fn watch_add(&self, path: &str) -> isize {
+        let path = &match self.lookup(path) {
+            Ok(path) => path,
+            Err(err) => return err,
+        };
+        if !is_dir(path) && !exists(path) {
+            return ENOENT;
+        }
+        self.open_resource(Resource::Watch(crate::watch::add(path)))
+    }
+
+    pub fn watch_read(&self, fd: u8, event: &mut WatchEvent) -> isize {
+        self.resources.read().watch_read(fd, event)
+    }
+
+    pub fn eventfd(&self, count: u64, semaphore: bool) -> u8 {
+        self.resources.write().eventfd(count, semaphore)
+    }
+
+    pub fn capture_start(&self) -> isize {
+        self.resources.write().capture_start()
+    }
+
+    pub fn capture_end(&self, buf: &mut [u8]) -> isize {
+        self.resources.write().capture_end(buf)
+    }
+}
+
+/// The names in `path`, without the empty ones of the repeated `/`
+fn components(path: &str) -> impl DoubleEndedIterator<Item = &str> {
+    path.split('/').filter(|part| !part.is_empty())
 }
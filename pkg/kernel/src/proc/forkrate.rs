@@ -0,0 +1,25 @@
+use syscall_def::{FORK_RATE_LIMIT, FORK_RATE_WINDOW};
+
+/// The ticks of the last forks of a process, a fork is rejected
+/// once there are `FORK_RATE_LIMIT` in the last `FORK_RATE_WINDOW` ticks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ForkRate {
+    ticks: [u64; FORK_RATE_LIMIT],
+    // the slot of the oldest fork, taken by the next one
+    next: usize,
+    count: usize,
+}
+
+impl ForkRate {
+    /// Whether a fork at `now` is within the rate
+    pub fn allows(&self, now: u64) -> bool {
+        self.count < FORK_RATE_LIMIT || now - self.ticks[self.next] >= FORK_RATE_WINDOW
+    }
+
+    /// Record a fork at `now`, which drops the oldest one
+    pub fn record(&mut self, now: u64) {
+        self.ticks[self.next] = now;
+        self.next = (self.next + 1) % FORK_RATE_LIMIT;
+        self.count = core::cmp::min(self.count + 1, FORK_RATE_LIMIT);
+    }
+}
@@ -0,0 +1,40 @@
+use super::ProcessId;
+use alloc::collections::*;
+use spin::Mutex;
+
+/// The waiters of all the futex words, shared by every process
+static FUTEXES: Mutex<FutexSet> = Mutex::new(FutexSet::new());
+
+pub fn get_futexes() -> spin::MutexGuard<'static, FutexSet> {
+    FUTEXES.lock()
+}
+
+/// The processes blocked on futex words, keyed by the physical address
+/// of the word so that processes sharing the memory meet at the same key
+#[derive(Debug)]
+pub struct FutexSet {
+    waiters: BTreeMap<u64, VecDeque<ProcessId>>,
+}
+
+impl FutexSet {
+    pub const fn new() -> Self {
+        Self {
+            waiters: BTreeMap::new(),
+        }
+    }
+
+    /// Queue `pid` on the word at `key`, woken up in order
+    pub fn wait(&mut self, key: u64, pid: ProcessId) {
+        self.waiters.entry(key).or_default().push_back(pid);
+    }
+
+    /// Take the first waiter on the word at `key`
+    pub fn pop(&mut self, key: u64) -> Option<ProcessId> {
+        let queue = self.waiters.get_mut(&key)?;
+        let pid = queue.pop_front();
+        if queue.is_empty() {
+            self.waiters.remove(&key);
+        }
+        pid
+    }
+}
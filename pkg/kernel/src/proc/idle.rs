@@ -0,0 +1,55 @@
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
+
+use super::get_process_manager;
+
+/// A maintenance task of the idle kernel process,
+/// returns whether there is more work to do
+pub type IdleTask = fn() -> bool;
+
+struct IdleHook {
+    name: &'static str,
+    task: IdleTask,
+    // the most runs in an idle period, i.e. until the next interrupt
+    budget: usize,
+}
+
+/// The hooks run in the order registered, only by the idle kernel process
+static IDLE_HOOKS: Mutex<Vec<IdleHook>> = Mutex::new(Vec::new());
+/// The number of times any of the idle hooks ran
+static IDLE_HOOK_RUNS: AtomicU64 = AtomicU64::new(0);
+
+/// Run `task` up to `budget` times in each idle period instead of halting
+/// at once, a single run must be short as interrupts are off during it
+pub fn register_idle_hook(name: &'static str, budget: usize, task: IdleTask) {
+    debug!("Idle hook registered: {} ({} runs)", name, budget);
+    IDLE_HOOKS.lock().push(IdleHook { name, task, budget });
+}
+
+/// Run the idle hooks before the kernel process halts,
+/// stops as soon as a process is ready
+pub fn run_idle_hooks() {
+    for hook in IDLE_HOOKS.lock().iter() {
+        for _ in 0..hook.budget {
+            let more = x86_64::instructions::interrupts::without_interrupts(|| {
+                if get_process_manager().has_ready() {
+                    return None;
+                }
+                trace!("Idle hook: {}", hook.name);
+                IDLE_HOOK_RUNS.fetch_add(1, Ordering::Relaxed);
+                Some((hook.task)())
+            });
+            match more {
+                Some(true) => continue,
+                Some(false) => break,
+                None => return,
+            }
+        }
+    }
+}
+
+#[inline]
+pub fn idle_hook_runs() -> u64 {
+    IDLE_HOOK_RUNS.load(Ordering::Relaxed)
+}
@@ -0,0 +1,95 @@
+use syscall_def::{Limit, RLIMIT_AS, RLIMIT_CPU, RLIMIT_NOFILE, RLIMIT_NPROC, RLIM_INFINITY};
+
+use super::MAX_CHILD_COUNT;
+
+/// The most fds a process may hold, every value of a `u8`
+pub const MAX_FD_COUNT: u64 = 256;
+
+/// The limits of a process, the soft values are enforced,
+/// and can be raised up to the hard ones, which can only be lowered
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    // the ticks the process may run
+    cpu: Limit,
+    // the children not reaped yet
+    nproc: Limit,
+    // the fds opened
+    nofile: Limit,
+    // the bytes of heap, stack & mappings
+    memory: Limit,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        let unlimited = Limit {
+            soft: RLIM_INFINITY,
+            hard: RLIM_INFINITY,
+        };
+        let children = MAX_CHILD_COUNT as u64;
+        Self {
+            cpu: unlimited,
+            nproc: Limit {
+                soft: children,
+                hard: children,
+            },
+            nofile: Limit {
+                soft: MAX_FD_COUNT,
+                hard: MAX_FD_COUNT,
+            },
+            memory: unlimited,
+        }
+    }
+}
+
+impl Limits {
+    fn slot(&mut self, resource: usize) -> Option<&mut Limit> {
+        match resource {
+            RLIMIT_CPU => Some(&mut self.cpu),
+            RLIMIT_NPROC => Some(&mut self.nproc),
+            RLIMIT_NOFILE => Some(&mut self.nofile),
+            RLIMIT_AS => Some(&mut self.memory),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, resource: usize) -> Option<Limit> {
+        let mut limits = *self;
+        limits.slot(resource).copied()
+    }
+
+    /// The enforced value of `resource`, `RLIM_INFINITY` for no limit
+    pub fn soft(&self, resource: usize) -> u64 {
+        self.get(resource).map_or(RLIM_INFINITY, |limit| limit.soft)
+    }
+
+    /// Replace the limit of `resource`, returns the old one, or `None` if
+    /// the resource is unknown, the soft value is over the hard one
+    /// or the hard one is raised
+    pub fn set(&mut self, resource: usize, new: Limit) -> Option<Limit> {
+        let new = Limit {
+            soft: normalize(resource, new.soft),
+            hard: normalize(resource, new.hard),
+        };
+        let slot = self.slot(resource)?;
+        if new.soft > new.hard || new.hard > slot.hard {
+            return None;
+        }
+        Some(core::mem::replace(slot, new))
+    }
+
+    /// Set the soft value only, up to the hard one
+    pub fn set_soft(&mut self, resource: usize, value: u64) {
+        let value = normalize(resource, value);
+        if let Some(slot) = self.slot(resource) {
+            slot.soft = value.min(slot.hard);
+        }
+    }
+}
+
+/// 0 of the ticks & bytes is no limit, like `TickBudget` & `SetMemLimit`
+fn normalize(resource: usize, value: u64) -> u64 {
+    match (resource, value) {
+        (RLIMIT_CPU | RLIMIT_AS, 0) => RLIM_INFINITY,
+        _ => value,
+    }
+}
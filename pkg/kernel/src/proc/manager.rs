@@ -1,22 +1,88 @@
 use crate::humanized_size;
+use crate::interrupt::{cancel_timer, register_timer};
 use crate::memory::{get_frame_alloc_for_sure, PAGE_SIZE};
+use crate::{filesystem::ENOENT, procmem::ProcMem, resource::Resource};
 
+use super::processor::MAX_CPU_COUNT;
+use super::table::ProcessTable;
 use super::*;
 
 use alloc::collections::{BTreeMap, BTreeSet};
-use alloc::sync::Weak;
+use alloc::{boxed::Box, sync::Weak};
 use alloc::{collections::VecDeque, format, sync::Arc};
+use core::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use spin::mutex::Mutex;
-use spin::RwLock;
-use x86_64::VirtAddr;
+use storage::{Advice, SeekFrom};
+use syscall_def::LOAD_SHIFT;
+use x86_64::{PhysAddr, VirtAddr};
 
 pub static PROCESS_MANAGER: spin::Once<ProcessManager> = spin::Once::new();
 
+/// The number of times the running process has been changed
+static CONTEXT_SWITCHES: AtomicU64 = AtomicU64::new(0);
+
+#[inline]
+pub fn context_switches() -> u64 {
+    CONTEXT_SWITCHES.load(Ordering::Relaxed)
+}
+
+/// The number of times nothing was ready and the kernel process idled
+static IDLE_SWITCHES: AtomicU64 = AtomicU64::new(0);
+/// The number of times the idle kernel process was woken by an interrupt
+static IDLE_WAKEUPS: AtomicU64 = AtomicU64::new(0);
+
+/// The idle switches and the wakeups of the idle kernel process
+#[inline]
+pub fn idle_stats() -> (u64, u64) {
+    (
+        IDLE_SWITCHES.load(Ordering::Relaxed),
+        IDLE_WAKEUPS.load(Ordering::Relaxed),
+    )
+}
+
+/// The number of times an idle CPU took processes from the queue of another
+static STEALS: AtomicU64 = AtomicU64::new(0);
+/// The number of processes taken by those steals
+static STOLEN: AtomicU64 = AtomicU64::new(0);
+
+/// The steals and the processes moved by them, see `ProcessManager::steal`
+#[inline]
+pub fn steal_stats() -> (u64, u64) {
+    (STEALS.load(Ordering::Relaxed), STOLEN.load(Ordering::Relaxed))
+}
+
+/// The weight of the latest tick in the load average is `1 / LOAD_DECAY`
+const LOAD_DECAY: u32 = 16;
+/// The ready and running processes averaged over the recent ticks,
+/// in fixed point with `LOAD_SHIFT` fraction bits
+static LOAD_AVG: AtomicU32 = AtomicU32::new(0);
+
+#[inline]
+pub fn load_avg() -> u32 {
+    LOAD_AVG.load(Ordering::Relaxed)
+}
+
+#[inline]
+pub fn count_idle_wakeup() {
+    IDLE_WAKEUPS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The result of waiting for any child
+#[derive(Debug)]
+pub enum WaitAnyResult {
+    /// The child exited first among the ones not reaped, with its exit code
+    Exited(ProcessId, isize),
+    /// All the children not reaped are still running
+    Running,
+    /// There is no child to wait for
+    NoChild,
+}
+
 pub fn init(init: Arc<Process>, app_list: boot::AppListRef) {
     // set init process as Running
     init.write().resume();
-    // set processor's current pid to init's pid
-    processor::set_pid(init.pid());
+    // set processor's current process to init
+    processor::set_proc(init.clone());
 
     PROCESS_MANAGER.call_once(|| ProcessManager::new(init, app_list));
 }
@@ -27,17 +93,52 @@ pub fn get_process_manager() -> &'static ProcessManager {
         .expect("Process Manager has not been initialized")
 }
 
+/// The tries of `pop_ready` to take its pick before falling back to the front
+const POP_READY_RETRIES: usize = 4;
+
+/// Called by `switch_next` with the pids it switches from & to, once the running
+/// process is changed, the one switched from may have exited,
+/// in the interrupt context so it must be short
+pub type SwitchHook = fn(ProcessId, ProcessId);
+
+/// The most hooks registered by `on_switch`
+const SWITCH_HOOKS_MAX: usize = 4;
+
+/// The processes and the queues deciding which one runs next
+///
+/// Lock order: the queues, `ready_queues`, `ready_since`, `waiting_processes`,
+/// `waiting_any`, `policy` & `switch_hooks`, are leaves, each is locked alone
+/// and released before any other lock is taken, the pids are copied out to
+/// look up the processes, and a steal moves them between two ready queues
+/// holding one at a time.
+/// A process is locked only after the lookup returns, as `ProcessTable`
+/// releases its shard before that, and it may be held while queueing it.
+/// So no lock is ever waited on while holding a queue, e.g. `kill` against
+/// `fork` or `switch_next`, whatever processes they lock.
 pub struct ProcessManager {
-    processes: RwLock<BTreeMap<ProcessId, Arc<Process>>>,
-    ready_queue: Mutex<VecDeque<ProcessId>>,
-    waiting_processes: Mutex<BTreeMap<ProcessId, BTreeSet<ProcessId>>>,
+    processes: ProcessTable,
+    /// The ready queue of each CPU, a process is queued on the CPU it is
+    /// spawned or woken up on, until an idle one steals it
+    ready_queues: [Mutex<VecDeque<ProcessId>>; MAX_CPU_COUNT],
+    /// The tick each queued process became ready at, kept over the
+    /// requeues until it is picked, see `ProcessInner::record_latency`
+    ready_since: Mutex<BTreeMap<ProcessId, u64>>,
+    /// Processes blocked until the key exits, in the order they started
+    /// to wait, so the earliest waiter is woken up first
+    waiting_processes: Mutex<BTreeMap<ProcessId, VecDeque<ProcessId>>>,
+    /// Parents blocked until any of their children exits
+    waiting_any: Mutex<BTreeSet<ProcessId>>,
+    /// How the next process is picked, all the policies share
+    /// the ready queues, so switching needs no migration
+    policy: Mutex<SchedPolicy>,
+    /// Run on every change of the running process, in the order registered
+    switch_hooks: Mutex<[Option<SwitchHook>; SWITCH_HOOKS_MAX]>,
     app_list: boot::AppListRef,
 }
 
 impl ProcessManager {
     pub fn new(init: Arc<Process>, app_list: boot::AppListRef) -> Self {
-        let mut processes = BTreeMap::new();
-        let ready_queue = VecDeque::new();
+        let processes = ProcessTable::new();
         let waiting_processes = BTreeMap::new();
         let pid = init.pid();
 
@@ -45,68 +146,527 @@ impl ProcessManager {
 
         processes.insert(pid, init);
         Self {
-            processes: RwLock::new(processes),
-            ready_queue: Mutex::new(ready_queue),
+            processes,
+            ready_queues: core::array::from_fn(|_| Mutex::new(VecDeque::new())),
+            ready_since: Mutex::new(BTreeMap::new()),
             waiting_processes: Mutex::new(waiting_processes),
+            waiting_any: Mutex::new(BTreeSet::new()),
+            policy: Mutex::new(SchedPolicy::default()),
+            switch_hooks: Mutex::new([None; SWITCH_HOOKS_MAX]),
             app_list,
         }
     }
 
+    /// Queue the process to run on the current CPU, the kernel process is
+    /// never queued as it is the idle one, picked only when nothing else is ready
     #[inline]
     pub fn push_ready(&self, pid: ProcessId) {
-        self.ready_queue.lock().push_back(pid);
+        self.push_ready_on(processor::cpu_id(), pid);
+    }
+
+    fn push_ready_on(&self, cpu: usize, pid: ProcessId) {
+        if pid != KERNEL_PID {
+            self.mark_ready(pid);
+            self.ready_queues[cpu].lock().push_back(pid);
+        }
+    }
+
+    /// The ready queue of the current CPU
+    #[inline]
+    fn ready_queue(&self) -> &Mutex<VecDeque<ProcessId>> {
+        &self.ready_queues[processor::cpu_id()]
+    }
+
+    /// The processes queued on all the CPUs, in the order of the CPUs
+    fn all_ready(&self) -> Vec<ProcessId> {
+        self.ready_queues
+            .iter()
+            .flat_map(|queue| queue.lock().iter().copied().collect::<Vec<_>>())
+            .collect()
+    }
+
+    /// Start the wait of `pid` in the ready queue, not by a requeue
+    fn mark_ready(&self, pid: ProcessId) {
+        let now = crate::interrupt::read_counter();
+        self.ready_since.lock().entry(pid).or_insert(now);
+    }
+
+    pub fn policy(&self) -> SchedPolicy {
+        *self.policy.lock()
     }
 
+    /// Run `hook` on every change of the running process,
+    /// returns false if `SWITCH_HOOKS_MAX` hooks are registered already
+    pub fn on_switch(&self, hook: SwitchHook) -> bool {
+        let mut hooks = self.switch_hooks.lock();
+        match hooks.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(hook);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_policy(&self, policy: SchedPolicy) {
+        info!("Scheduler policy: {:?}", policy);
+        *self.policy.lock() = policy;
+    }
+
+    /// Queue the process preempted by the clock,
+    /// FIFO keeps a user process at the front to run it again
+    pub fn push_preempted(&self, pid: ProcessId) {
+        if self.policy() == SchedPolicy::Fifo && pid != KERNEL_PID {
+            self.mark_ready(pid);
+            self.ready_queue().lock().push_front(pid);
+        } else {
+            self.push_ready(pid);
+        }
+    }
+
+    /// Pop the next process to check from the ready queue of `cpu`,
+    /// the ready one with the lowest effective priority value first by `Priority`
+    ///
+    /// The processes are compared on a copy of the queue, which is not held
+    /// meanwhile, so the pick is taken only if it is still queued, with up to
+    /// `POP_READY_RETRIES` tries before the front is taken instead
+    fn pop_ready(&self, cpu: usize) -> ProcessId {
+        if self.policy() == SchedPolicy::Priority {
+            for _ in 0..POP_READY_RETRIES {
+                let queued: Vec<ProcessId> =
+                    self.ready_queues[cpu].lock().iter().copied().collect();
+                let now = crate::interrupt::read_counter();
+                let best = queued
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, pid)| {
+                        let proc = self.get_proc(pid)?;
+                        let inner = proc.read();
+                        inner.is_runnable(now, cpu).then(|| (inner.effective_priority(), i))
+                    })
+                    .min();
+                let pid = match best {
+                    Some((_, i)) => queued[i],
+                    None => break,
+                };
+                let mut queue = self.ready_queues[cpu].lock();
+                if let Some(i) = queue.iter().position(|queued| *queued == pid) {
+                    return queue.remove(i).unwrap();
+                }
+            }
+        }
+        self.ready_queues[cpu].lock().pop_front().unwrap()
+    }
+
+    /// Move up to half of the processes queued on the most loaded other CPU
+    /// to the back of the queue of `cpu`, only the ones allowed to run on it,
+    /// returns how many are moved
+    ///
+    /// They are taken from the back of that queue, the ones to wait there the
+    /// longest, and the ones queued there meanwhile are left in place
+    fn steal(&self, cpu: usize) -> usize {
+        let victim = (0..MAX_CPU_COUNT)
+            .filter(|other| *other != cpu)
+            .max_by_key(|other| self.ready_queues[*other].lock().len());
+        let Some(victim) = victim else {
+            return 0;
+        };
+        let queued: Vec<ProcessId> = self.ready_queues[victim].lock().iter().copied().collect();
+        let batch: Vec<ProcessId> = queued
+            .iter()
+            .rev()
+            .filter(|pid| {
+                self.get_proc(pid)
+                    .is_some_and(|proc| proc.read().affinity() & (1 << cpu) != 0)
+            })
+            .take(queued.len().div_ceil(2))
+            .copied()
+            .collect();
+        if batch.is_empty() {
+            return 0;
+        }
+
+        let mut stolen = Vec::with_capacity(batch.len());
+        self.ready_queues[victim].lock().retain(|pid| {
+            let take = batch.contains(pid);
+            if take {
+                stolen.push(*pid);
+            }
+            !take
+        });
+        let count = stolen.len();
+        self.ready_queues[cpu].lock().extend(stolen);
+        if count > 0 {
+            trace!("CPU {} stole {} processes from CPU {}", cpu, count, victim);
+            STEALS.fetch_add(1, Ordering::Relaxed);
+            STOLEN.fetch_add(count as u64, Ordering::Relaxed);
+        }
+        count
+    }
+
+    /// Queue the caller to be woken up once `pid` exits,
+    /// a process already queued keeps its place
     #[inline]
     pub fn add_waiting(&self, pid: ProcessId) {
-        self.waiting_processes
-            .lock()
-            .entry(pid)
-            .or_default()
-            .insert(get_pid());
+        let waiter = get_pid();
+        let mut waiting = self.waiting_processes.lock();
+        let waiters = waiting.entry(pid).or_default();
+        if !waiters.contains(&waiter) {
+            waiters.push_back(waiter);
+        }
+    }
+
+    #[inline]
+    pub fn add_waiting_any(&self, pid: ProcessId) {
+        self.waiting_any.lock().insert(pid);
+    }
+
+    /// Wake up `pid` by a timer at `wake_tick`, processes of the same
+    /// tick are woken up in the order they fell asleep
+    pub fn add_sleeping(&self, pid: ProcessId, wake_tick: u64) {
+        let timer = register_timer(
+            wake_tick,
+            Box::new(move || get_process_manager().wake_sleeping(pid)),
+        );
+        if let Some(proc) = self.get_proc(&pid) {
+            proc.write().set_sleep_timer(Some(timer));
+        }
+    }
+
+    /// Give up the wait of `pid` on the semaphore `key` at `wake_tick`,
+    /// returns the timer to be cancelled if it is signaled first
+    pub fn add_sem_timeout(&self, pid: ProcessId, key: u32, wake_tick: u64) -> u64 {
+        register_timer(
+            wake_tick,
+            Box::new(move || get_process_manager().sem_timeout(pid, key)),
+        )
+    }
+
+    /// Send `SIGALRM` to the current process in `ticks`, 0 to disarm, which kills
+    /// it with `ALARM_EXIT_CODE` unless it has a handler,
+    /// the alarm before is replaced, returns its ticks left
+    pub fn alarm(&self, ticks: u64) -> u64 {
+        let now = crate::interrupt::read_counter();
+        let proc = self.current();
+        let pid = proc.pid();
+        let mut inner = proc.write();
+        let left = match inner.take_alarm() {
+            Some((timer, deadline)) => {
+                cancel_timer(timer);
+                deadline.saturating_sub(now)
+            }
+            None => 0,
+        };
+        if ticks != 0 {
+            let deadline = now + ticks;
+            let timer = register_timer(
+                deadline,
+                Box::new(move || get_process_manager().fire_alarm(pid)),
+            );
+            inner.set_alarm(Some((timer, deadline)));
+        }
+        left
+    }
+
+    /// The alarm is cancelled once replaced or the process is killed,
+    /// so it fires at most once
+    fn fire_alarm(&self, pid: ProcessId) {
+        let armed = self
+            .get_proc(&pid)
+            .is_some_and(|proc| proc.write().take_alarm().is_some());
+        if armed {
+            info!("Process #{} is signaled by its alarm.", pid);
+            self.send_signal(pid, SIGALRM);
+        }
+    }
+
+    fn sem_timeout(&self, pid: ProcessId, key: u32) {
+        let ret = match self.get_proc(&pid) {
+            Some(proc) => proc.read().sem_timeout(key, pid),
+            None => return,
+        };
+        if let SemaphoreResult::TimedOut(pid) = ret {
+            self.wake_up_with(pid, SEM_TIMED_OUT);
+        }
+    }
+
+    /// Wake up `pid` if it is still sleeping,
+    /// it may have been killed in the meantime
+    fn wake_sleeping(&self, pid: ProcessId) {
+        let sleeping = self.get_proc(&pid).is_some_and(|proc| {
+            let mut inner = proc.write();
+            inner.set_sleep_timer(None);
+            inner.block_reason() == Some(BlockReason::Sleeping)
+        });
+        if sleeping {
+            self.wake_up(pid);
+        }
     }
 
     #[inline]
     fn add_proc(&self, pid: ProcessId, proc: Arc<Process>) {
-        self.processes.write().insert(pid, proc);
+        self.processes.insert(pid, proc);
     }
 
     #[inline]
     fn get_proc(&self, pid: &ProcessId) -> Option<Arc<Process>> {
-        self.processes.read().get(pid).cloned()
+        self.processes.get(pid)
     }
 
     #[inline]
-    pub fn block_proc(&self, pid: &ProcessId) {
-        self.get_proc(pid).unwrap().write().block();
+    pub fn block_proc(&self, pid: &ProcessId, reason: BlockReason) {
+        self.get_proc(pid).unwrap().write().block(reason);
+        // the wait starts over once woken up
+        self.ready_since.lock().remove(pid);
+    }
+
+    /// Why the process is blocked, `None` if it is not found
+    pub fn block_reason(&self, pid: &ProcessId) -> Option<Option<BlockReason>> {
+        self.get_proc(pid).map(|proc| proc.read().block_reason())
     }
+
+    /// Copy the latest state transitions of `pid` into `buf`, oldest first,
+    /// `None` if it is not found
+    pub fn wake_log(&self, pid: &ProcessId, buf: &mut [AuditEntry]) -> Option<usize> {
+        self.get_proc(pid).map(|proc| proc.read().audit_log().copy_to(buf))
+    }
+
+    /// Whether `pid` is still blocked for `reason`, not killed meanwhile
+    pub fn is_blocked_by(&self, pid: &ProcessId, reason: BlockReason) -> bool {
+        self.get_proc(pid).is_some_and(|proc| {
+            let inner = proc.read();
+            inner.status() == ProgramStatus::Blocked && inner.block_reason() == Some(reason)
+        })
+    }
+
     pub fn current(&self) -> Arc<Process> {
-        self.get_proc(&processor::get_pid())
-            .expect("No current process")
+        self.current_on(processor::cpu_id())
+    }
+
+    /// Get the process running on the given processor, from the processor
+    /// itself, the process table is not looked up
+    pub fn current_on(&self, cpu: usize) -> Arc<Process> {
+        processor::get_proc_on(cpu).expect("No current process")
     }
 
     pub fn wake_up(&self, pid: ProcessId) {
-        self.get_proc(&pid).unwrap().write().pause();
+        if let Some(proc) = self.get_proc(&pid) {
+            let mut inner = proc.write();
+            // the process may have been killed while blocked
+            if inner.status() != ProgramStatus::Blocked {
+                return;
+            }
+            inner.pause();
+        } else {
+            return;
+        }
+        self.push_ready(pid);
+    }
+
+    /// Wake up `pid` blocked in a syscall, which returns `ret`
+    pub fn wake_up_with(&self, pid: ProcessId, ret: isize) {
+        if let Some(proc) = self.get_proc(&pid) {
+            let mut inner = proc.write();
+            if inner.status() != ProgramStatus::Blocked {
+                return;
+            }
+            inner.context().set_rax(ret as usize);
+            inner.pause();
+        } else {
+            return;
+        }
         self.push_ready(pid);
     }
 
-    pub fn wake_waiting(&self, ret: isize) {
-        let now_pid = get_pid();
-        let mut wait_proc = self.waiting_processes.lock();
-        if let Some(wait_set) = wait_proc.remove(&now_pid) {
-            for pid in wait_set {
-                self.get_proc(&pid)
-                    .unwrap()
-                    .write()
-                    .context()
-                    .set_rax(ret as usize);
-                self.wake_up(pid);
+    /// Wake up all processes waiting for `pid` with its exit code.
+    ///
+    /// The whole waiting queue is taken out at once, so every waiter
+    /// receives the exit code and is woken up exactly once, in the order
+    /// they started to wait, which is also the order they are queued to run.
+    pub fn wake_waiting(&self, pid: ProcessId, ret: isize) {
+        // taken out before any waiter is locked, see the lock order
+        let wait_set = self.waiting_processes.lock().remove(&pid);
+        let usage = self.rusage(pid).unwrap_or_default();
+        for waiter in wait_set.into_iter().flatten() {
+            self.reap(pid, waiter);
+            let proc = match self.get_proc(&waiter) {
+                Some(proc) => proc,
+                None => continue,
+            };
+            let mut inner = proc.write();
+            // the waiter may have been killed while blocked
+            if inner.status() != ProgramStatus::Blocked {
+                continue;
+            }
+            inner.context().set_rax(ret as usize);
+            inner.fill_rusage(usage);
+            inner.pause();
+            drop(inner);
+            self.push_ready(waiter);
+        }
+    }
+
+    /// Wake up the parent of `pid` if it waits for any child,
+    /// it checks its children again once scheduled
+    fn wake_waiting_any(&self, pid: ProcessId) {
+        let parent = match self.get_proc(&pid).and_then(|proc| proc.read().parent()) {
+            Some(parent) => parent,
+            None => return,
+        };
+        if !self.waiting_any.lock().remove(&parent.pid()) {
+            return;
+        }
+        let mut inner = parent.write();
+        if inner.status() != ProgramStatus::Blocked {
+            return;
+        }
+        inner.pause();
+        drop(inner);
+        self.push_ready(parent.pid());
+    }
+
+    /// Find the child of `parent` to be collected by waiting for any child
+    pub fn exited_child(&self, parent: ProcessId) -> WaitAnyResult {
+        let children = match self.get_proc(&parent) {
+            Some(proc) => proc.read().children().to_vec(),
+            None => return WaitAnyResult::NoChild,
+        };
+
+        let mut result = WaitAnyResult::NoChild;
+        let mut first_order = usize::MAX;
+        for child in children.iter() {
+            let inner = child.read();
+            if inner.is_reaped() {
+                continue;
+            }
+            match inner.exit_code() {
+                Some(code) if inner.exit_order() < first_order => {
+                    first_order = inner.exit_order();
+                    result = WaitAnyResult::Exited(child.pid(), code);
+                }
+                Some(_) => {}
+                None if first_order == usize::MAX => result = WaitAnyResult::Running,
+                None => {}
             }
         }
+        result
     }
 
     pub fn get_exit_code(&self, pid: ProcessId) -> Option<isize> {
-        self.get_proc(&pid).unwrap().read().exit_code()
+        self.get_proc(&pid)?.read().exit_code()
+    }
+
+    pub fn rusage(&self, pid: ProcessId) -> Option<RUsage> {
+        Some(self.get_proc(&pid)?.read().rusage())
+    }
+
+    /// Whether `pid` is a child of `parent`
+    pub fn is_child(&self, pid: ProcessId, parent: ProcessId) -> bool {
+        self.get_proc(&pid)
+            .and_then(|proc| proc.read().parent())
+            .is_some_and(|p| p.pid() == parent)
+    }
+
+    /// Continue the suspended process `pid`, only by its parent
+    pub fn cont(&self, pid: ProcessId, parent: ProcessId) -> bool {
+        if !self.is_child(pid, parent) {
+            return false;
+        }
+        let proc = self.get_proc(&pid).unwrap();
+        let mut inner = proc.write();
+        if inner.status() != ProgramStatus::Stopped {
+            return false;
+        }
+        inner.pause();
+        drop(inner);
+        self.push_ready(pid);
+        true
+    }
+
+    /// Mark a dead process as reaped once its parent `waiter`
+    /// has collected the exit code
+    pub fn reap(&self, pid: ProcessId, waiter: ProcessId) {
+        let proc = match self.get_proc(&pid) {
+            Some(proc) => proc,
+            None => return,
+        };
+        let mut inner = proc.write();
+        if inner.is_reaped() {
+            return;
+        }
+        if let Some(parent) = inner.parent().filter(|p| p.pid() == waiter) {
+            inner.set_reaped();
+            drop(inner);
+            parent.write().dec_child_count();
+            trace!("Process #{} reaped by #{}", pid, waiter);
+            self.release(pid);
+        }
+    }
+
+    /// Drop a dead process nobody is left to wait for from the table,
+    /// and free its pid unless it still names the group or the session
+    /// of a live process, which a new process must not join by chance
+    fn release(&self, pid: ProcessId) {
+        if self.processes.remove(&pid).is_none() {
+            return;
+        }
+        let named = self.processes.values().iter().any(|proc| {
+            let inner = proc.read();
+            inner.pgid() == Some(pid) || inner.sid() == Some(pid)
+        });
+        if named {
+            trace!("Pid #{} still names a group, not reused", pid);
+        } else {
+            pid.free();
+        }
+    }
+
+    /// Release the dead children of `pid` not reaped, and `pid` itself if
+    /// its parent is gone, as nobody can reap them once `pid` is dead
+    fn release_orphans(&self, pid: ProcessId, parent: Option<&Arc<Process>>) {
+        let orphans: Vec<ProcessId> = self
+            .processes
+            .values()
+            .iter()
+            .filter(|proc| {
+                let inner = proc.read();
+                inner.status() == ProgramStatus::Dead
+                    && !inner.is_reaped()
+                    && inner.parent().is_some_and(|parent| parent.pid() == pid)
+            })
+            .map(|proc| proc.pid())
+            .collect();
+        for orphan in orphans {
+            self.release(orphan);
+        }
+        let parent_alive = parent.is_some_and(|p| p.read().status() != ProgramStatus::Dead);
+        if !parent_alive {
+            self.release(pid);
+        }
+    }
+
+    fn alive_count(&self) -> usize {
+        self.processes
+            .values()
+            .iter()
+            .filter(|p| p.read().status() != ProgramStatus::Dead)
+            .count()
+    }
+
+    /// Check whether `parent` is allowed to create one more child
+    fn can_create_child(&self, parent: &Process) -> bool {
+        if self.alive_count() >= MAX_PROCESS_COUNT {
+            warn!("Process limit ({}) reached.", MAX_PROCESS_COUNT);
+            false
+        } else {
+            let inner = parent.read();
+            let limit = inner.limit(RLIMIT_NPROC);
+            if inner.child_count() as u64 >= limit {
+                warn!("Process #{} reached the child limit ({}).", parent.pid(), limit);
+                return false;
+            }
+            true
+        }
     }
 
     pub fn app_list(&self) -> boot::AppListRef {
@@ -119,11 +679,45 @@ impl ProcessManager {
         name: String,
         parent: Option<Weak<Process>>,
         proc_data: Option<ProcessData>,
-    ) -> ProcessId {
+        suspended: bool,
+    ) -> Option<ProcessId> {
+        let parent_proc = parent.as_ref().and_then(|p| p.upgrade());
+        if let Some(parent) = parent_proc.as_ref() {
+            if !self.can_create_child(parent) {
+                return None;
+            }
+            let depth = parent.read().spawn_depth() + 1;
+            if depth > max_spawn_depth() {
+                warn!(
+                    "Process #{} reached the spawn depth limit ({}).",
+                    parent.pid(),
+                    max_spawn_depth()
+                );
+                return None;
+            }
+        }
+
         let kproc = self.get_proc(&KERNEL_PID).unwrap();
         let page_table = kproc.read().clone_page_table();
         let proc_vm = Some(ProcessVm::new(page_table));
-        let proc = Process::new(name, parent, proc_vm, proc_data);
+        let mut proc_data = proc_data.unwrap_or_default();
+        if let Some(parent) = parent_proc.as_ref() {
+            // only the init process is privileged on spawn
+            proc_data.privileged = parent.pid() == KERNEL_PID;
+            let parent = parent.read();
+            proc_data.spawn_depth = parent.spawn_depth() + 1;
+            proc_data.priority = parent.priority();
+            proc_data.open_defaults = parent.open_defaults();
+            proc_data.no_new_privs = parent.no_new_privs();
+            proc_data.limits = parent.limits();
+            proc_data.affinity = parent.affinity();
+            proc_data.root = parent.root().into();
+            // init gets the boot arguments
+            if proc_data.privileged {
+                proc_data.copy_env(&parent);
+            }
+        }
+        let proc = Process::new(name, parent, proc_vm, Some(proc_data));
         let pid = proc.pid();
         let mut inner = proc.write();
 
@@ -135,170 +729,1460 @@ impl ProcessManager {
         trace!("entry: {:x}", entry);
         proc.write().init_stack_frame(entry, stack_top);
 
-        // mark process as ready
-        proc.write().pause();
+        // mark process as ready, or leave it out of the ready queue
+        // until continued if suspended
+        if suspended {
+            proc.write().stop();
+        } else {
+            proc.write().pause();
+        }
         trace!("New {:#?}", &proc);
         // something like kernel thread
         self.add_proc(pid, proc);
-        self.push_ready(pid);
+        if !suspended {
+            self.push_ready(pid);
+        }
+
+        if let Some(parent) = parent_proc {
+            parent.write().inc_child_count();
+        }
+
+        Some(pid)
+    }
+
+    /// Replace the image of the current process, the pid, the family
+    /// and the fds not marked close-on-exec are kept
+    pub fn exec(&self, elf: &ElfFile, name: String, context: &mut ProcessContext) {
+        let kproc = self.get_proc(&KERNEL_PID).unwrap();
+        let page_table = kproc.read().clone_page_table();
+
+        let current = self.current();
+        // before the memory lent by the vfork parent is replaced
+        self.end_vfork(&current);
+        current.write().exec(elf, name, page_table, current.pid());
+        current.write().restore(context);
+        debug!("Exec process: {}#{}", current.read().name(), current.pid());
+    }
 
-        pid
+    pub fn save_current(&self, cpu: usize, context: &ProcessContext) -> ProcessId {
+        self.save_on(cpu, context, false)
     }
 
-    pub fn save_current(&self, context: &ProcessContext) -> ProcessId {
+    /// Save the current process preempted by the clock, so the switch away
+    /// from it counts as involuntary, see `ProcessInner::count_leave`
+    pub fn save_preempted(&self, cpu: usize, context: &ProcessContext) -> ProcessId {
+        self.save_on(cpu, context, true)
+    }
+
+    fn save_on(&self, cpu: usize, context: &ProcessContext, preempted: bool) -> ProcessId {
         // save now current into process context
-        let temp = self.current();
+        let temp = self.current_on(cpu);
         let mut nowproc = temp.write();
         // update current process's tick count
         nowproc.tick();
+        nowproc.charge_quota(crate::interrupt::read_counter());
         // update current process's context
         nowproc.save(context);
+        nowproc.set_preempted(preempted);
+        let over_budget = nowproc.is_over_budget();
+        drop(nowproc);
+
+        // the watchdog: kill the process running out of its tick budget
+        if over_budget {
+            warn!(
+                "Process #{} exceeded its tick budget ({}).",
+                temp.pid(),
+                temp.read().tick_budget()
+            );
+            self.kill(temp.pid(), KILLED_EXIT_CODE);
+        }
+
         // push current process to ready queue if still alive
         temp.pid()
     }
 
-    pub fn switch_next(&self, context: &mut ProcessContext) -> ProcessId {
-        // fetch the next process from ready queue
-        let mut nextpid = self.ready_queue.lock().pop_front().unwrap();
-        let mut nextproc = self.get_proc(&nextpid).unwrap();
-        // check if the next process is ready, continue to fetch if not ready
-        while !nextproc.read().is_ready() {
-            self.push_ready(nextpid);
-            nextpid = self.ready_queue.lock().pop_front().unwrap();
-            nextproc = self.get_proc(&nextpid).unwrap();
+    /// Fetch the next ready process from the queue of `cpu`, the ones throttled
+    /// by the quota are queued again, `None` if there is nothing to do
+    ///
+    /// the queue is scanned at most once, the blocked, stopped and dead
+    /// ones are dropped, they are queued again once woken up or continued,
+    /// an empty queue steals from another CPU first
+    fn pop_next_ready(&self, cpu: usize) -> Option<ProcessId> {
+        if self.ready_queues[cpu].lock().is_empty() {
+            self.steal(cpu);
         }
-        // restore next process's context
-        nextproc.write().restore(context);
-        // update processor's current pid
-        processor::set_pid(nextpid);
-
-        nextpid
+        let now = crate::interrupt::read_counter();
+        let count = self.ready_queues[cpu].lock().len();
+        for _ in 0..count {
+            let pid = self.pop_ready(cpu);
+            let (runnable, ready) = match self.get_proc(&pid) {
+                Some(proc) => {
+                    let inner = proc.read();
+                    (inner.is_runnable(now, cpu), inner.is_ready())
+                }
+                None => continue,
+            };
+            if runnable {
+                self.age_ready(cpu);
+                return Some(pid);
+            }
+            if ready {
+                self.push_ready_on(cpu, pid);
+            } else {
+                self.ready_since.lock().remove(&pid);
+            }
+        }
+        None
     }
 
-    pub fn kill_current(&self, ret: isize) {
-        self.kill(processor::get_pid(), ret);
-    }
+    /// Queue blocked processes only, and check the scan of the queue
+    /// finds nothing to run, so `switch_next` falls back to the idle process
+    #[cfg(feature = "sched_test")]
+    pub fn test_idle_fallback(&self) {
+        const COUNT: usize = 3;
 
-    pub fn handle_page_fault(&self, addr: VirtAddr, err_code: PageFaultErrorCode) -> bool {
-        // handle page fault
-        let nowproc = self.current();
-        if !err_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION) {
-            let mut inner = nowproc.write();
-            inner.handle_page_fault(addr);
-            true
-        } else {
-            false
+        let kernel = self.get_proc(&KERNEL_PID).unwrap();
+        let pids: Vec<ProcessId> = (0..COUNT)
+            .map(|_| {
+                // share the kernel page table, so it is kept on the exit
+                let vm = ProcessVm::new(kernel.read().vm().page_table.fork());
+                let proc = Process::new(String::from("blocked"), None, Some(vm), None);
+                let pid = proc.pid();
+                self.add_proc(pid, proc);
+                self.block_proc(&pid, BlockReason::Semaphore);
+                self.push_ready(pid);
+                pid
+            })
+            .collect();
+
+        let cpu = processor::cpu_id();
+        assert_eq!(self.pop_next_ready(cpu), None, "A blocked process is picked");
+        assert!(
+            self.ready_queues[cpu].lock().is_empty(),
+            "The blocked processes are queued again"
+        );
+
+        for pid in pids {
+            self.kill(pid, 0);
         }
+        info!("Idle fallback test passed.");
     }
 
-    pub fn kill_self(&self, ret: isize) {
-        self.kill(processor::get_pid(), ret);
-    }
+    /// Queue a burst of processes on another CPU, one of them pinned there,
+    /// and check an empty queue steals half of the others, the earliest
+    /// queued left, as only the boot processor is brought up to run them
+    #[cfg(feature = "steal_test")]
+    pub fn test_work_stealing(&self) {
+        const COUNT: usize = 6;
 
-    pub fn kill(&self, pid: ProcessId, ret: isize) {
-        let proc = self.get_proc(&pid);
+        let cpu = processor::cpu_id();
+        let other = (cpu + 1) % MAX_CPU_COUNT;
+        let kernel = self.get_proc(&KERNEL_PID).unwrap();
+        let pids: Vec<ProcessId> = (0..COUNT)
+            .map(|_| {
+                // share the kernel page table, so it is kept on the exit
+                let vm = ProcessVm::new(kernel.read().vm().page_table.fork());
+                let proc = Process::new(String::from("stolen"), None, Some(vm), None);
+                let pid = proc.pid();
+                self.add_proc(pid, proc);
+                self.push_ready_on(other, pid);
+                pid
+            })
+            .collect();
+        let pinned = pids[COUNT - 1];
+        self.get_proc(&pinned).unwrap().write().set_affinity(1 << other);
 
-        if proc.is_none() {
-            warn!("Process #{} not found.", pid);
-            return;
-        }
+        assert!(self.ready_queues[cpu].lock().is_empty(), "The local queue is not empty");
+        let (steals, stolen) = steal_stats();
+        let picked = self.pop_next_ready(cpu).expect("Nothing is stolen");
+        let (steals_after, stolen_after) = steal_stats();
+        assert_eq!(steals_after, steals + 1, "The steal is not counted");
+        assert_eq!(stolen_after - stolen, (COUNT / 2) as u64);
 
-        let proc = proc.unwrap();
+        // the latest queued ones, but the pinned one, in their order
+        let moved = &pids[COUNT - 1 - COUNT / 2..COUNT - 1];
+        assert_eq!(picked, moved[0], "The stolen ones are reordered");
+        let local: Vec<ProcessId> = self.ready_queues[cpu].lock().iter().copied().collect();
+        assert_eq!(local, moved[1..], "Other processes are stolen");
+        let remote: Vec<ProcessId> = self.ready_queues[other].lock().iter().copied().collect();
+        let mut left = pids[..COUNT - 1 - COUNT / 2].to_vec();
+        left.push(pinned);
+        assert_eq!(remote, left, "The pinned process is stolen");
 
-        if proc.read().status() == ProgramStatus::Dead {
-            warn!("Process #{} is already dead.", pid);
-            return;
+        // nothing left to take once only the pinned one is there
+        self.ready_queues[other].lock().retain(|pid| *pid == pinned);
+        assert_eq!(self.steal(cpu), 0, "The pinned process is stolen");
+
+        for pid in pids {
+            self.kill(pid, 0);
         }
+        assert!(self.ready_queues.iter().all(|queue| queue.lock().is_empty()));
+        info!("Work stealing test passed.");
+    }
 
-        trace!("Kill Porcess {:?}", pid);
+    /// Keep the pairs of the kernel command line as the env of the kernel,
+    /// see `cmdline::parse`, the later ones override the earlier
+    pub fn set_boot_args(&self, cmdline: &str) {
+        let kproc = self.get_proc(&KERNEL_PID).unwrap();
+        let mut kproc = kproc.write();
+        for (key, val) in crate::cmdline::parse(cmdline) {
+            kproc.set_env(key, val);
+        }
+    }
 
-        proc.kill(ret);
+    pub fn boot_arg(&self, key: &str) -> Option<String> {
+        self.get_proc(&KERNEL_PID)?.read().env(key)
     }
 
-    pub fn print_process_list(&self) {
-        let mut output =
-            String::from("  PID | PPID | Process Name |  Ticks  |   Memory  | Status\n");
+    /// Switch between two processes requeued in turn, like preempted ones,
+    /// and check a hook sees the switches in the order they are made
+    #[cfg(feature = "switch_test")]
+    pub fn test_switch_hook(&self) {
+        use core::sync::atomic::AtomicBool;
 
-        for (_, p) in self.processes.read().iter() {
-            if p.read().status() != ProgramStatus::Dead {
-                output += format!("{}\n", p).as_str();
+        const ROUNDS: usize = 3;
+        static RECORDING: AtomicBool = AtomicBool::new(false);
+        static SEEN: Mutex<Vec<(ProcessId, ProcessId)>> = Mutex::new(Vec::new());
+
+        fn record(from: ProcessId, to: ProcessId) {
+            if RECORDING.load(Ordering::Relaxed) {
+                SEEN.lock().push((from, to));
             }
         }
+        assert!(self.on_switch(record), "No room for the switch hook");
 
-        // TODO: print memory usage of kernel heap
-        let alloc = get_frame_alloc_for_sure();
-        let frames_used = alloc.frames_used();
-        let frames_recycled = alloc.frames_recycled();
-        let frames_total = alloc.frames_total();
-
-        let used = (frames_used - frames_recycled) * PAGE_SIZE as usize;
-        let total = frames_total * PAGE_SIZE as usize;
-
-        output += &format_usage("Memory", used, total);
-        drop(alloc);
-
-        output += format!("Queue  : {:?}\n", self.ready_queue.lock()).as_str();
+        let kernel = self.get_proc(&KERNEL_PID).unwrap();
+        let pids: Vec<ProcessId> = (0..2)
+            .map(|_| {
+                // share the kernel page table, so it is kept on the exit
+                let vm = ProcessVm::new(kernel.read().vm().page_table.fork());
+                let proc = Process::new(String::from("switched"), None, Some(vm), None);
+                let pid = proc.pid();
+                self.add_proc(pid, proc);
+                self.push_ready(pid);
+                pid
+            })
+            .collect();
 
-        output += &processor::print_processors();
+        let cpu = processor::cpu_id();
+        let mut context = ProcessContext::default();
+        let mut made = Vec::new();
+        let mut prev = KERNEL_PID;
+        RECORDING.store(true, Ordering::Relaxed);
+        for _ in 0..ROUNDS * 2 {
+            let next = self.switch_next(cpu, &mut context);
+            made.push((prev, next));
+            self.save_current(cpu, &context);
+            self.push_ready(next);
+            prev = next;
+        }
+        // back to the idle kernel process once neither can run
+        for pid in pids.iter() {
+            self.block_proc(pid, BlockReason::Semaphore);
+        }
+        made.push((prev, self.switch_next(cpu, &mut context)));
+        RECORDING.store(false, Ordering::Relaxed);
 
-        print!("{}", output);
-    }
+        let seen = core::mem::take(&mut *SEEN.lock());
+        assert_eq!(seen, made, "The hook sees other switches");
+        for (i, (_, to)) in made.iter().take(ROUNDS * 2).enumerate() {
+            assert_eq!(*to, pids[i % 2], "The processes do not take turns");
+        }
+        assert_eq!(made.last().unwrap().1, KERNEL_PID);
 
-    pub fn print_process_info(&self, pid: &ProcessId) -> bool {
-        if let Some(proc) = self.get_proc(pid) {
-            proc.read().print_info();
-            true
-        } else {
-            warn!("Process #{} not found.", pid);
-            false
+        for pid in pids {
+            self.kill(pid, 0);
         }
+        info!("Switch hook test passed.");
     }
 
-    pub fn is_proc_alive(&self, pid: &ProcessId) -> bool {
-        if let Some(proc) = self.get_proc(pid) {
-            proc.read().status() != ProgramStatus::Dead
-        } else {
-            false
+    /// Age the ready processes left in the queue of `cpu` after one is chosen
+    fn age_ready(&self, cpu: usize) {
+        let queued: Vec<ProcessId> = self.ready_queues[cpu].lock().iter().copied().collect();
+        for pid in queued.iter() {
+            if let Some(proc) = self.get_proc(pid) {
+                let mut inner = proc.write();
+                if inner.is_ready() {
+                    inner.grow_age();
+                }
+            }
         }
     }
 
-    pub fn fork(&self) -> Arc<Process> {
-        // get current process
-        let proc = self.current();
-        // fork to get child
-        let child = proc.fork();
-        // add child to process list
-        self.add_proc(child.pid(), child.clone());
-        // maybe print the process ready queue?
-        debug!("Ready Queue: {:?}", self.ready_queue.lock());
-
-        child
+    /// Whether any process is waiting in a ready queue, an idle CPU
+    /// would steal the ones queued on another
+    pub fn has_ready(&self) -> bool {
+        self.ready_queues.iter().any(|queue| !queue.lock().is_empty())
     }
 
-    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
-        self.current().read().read(fd, buf)
+    /// Fold the ready processes and the running one on the clock tick
+    /// into the load average, the idle kernel process is not counted
+    pub fn update_load(&self, cpu: usize) {
+        let ready = self
+            .all_ready()
+            .iter()
+            .filter(|pid| self.get_proc(pid).is_some_and(|proc| proc.read().is_ready()))
+            .count() as u32;
+        let current = self.current_on(cpu);
+        let running = (current.pid() != KERNEL_PID
+            && current.read().status() == ProgramStatus::Running) as u32;
+
+        let sample = (ready + running) << LOAD_SHIFT;
+        let load = LOAD_AVG.load(Ordering::Relaxed);
+        LOAD_AVG.store((load * (LOAD_DECAY - 1) + sample) / LOAD_DECAY, Ordering::Relaxed);
     }
 
-    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
+    pub fn switch_next(&self, cpu: usize, context: &mut ProcessContext) -> ProcessId {
+        // idle in the kernel process until the next interrupt if nothing is ready
+        let nextpid = self.pop_next_ready(cpu).unwrap_or_else(|| {
+            IDLE_SWITCHES.fetch_add(1, Ordering::Relaxed);
+            KERNEL_PID
+        });
+        let nextproc = self.get_proc(&nextpid).unwrap();
+        let since = self.ready_since.lock().remove(&nextpid);
+        let mut inner = nextproc.write();
+        if let Some(since) = since {
+            inner.record_latency(crate::interrupt::read_counter() - since);
+        }
+        // restore next process's context
+        inner.restore(context);
+        // count the switch only if the running process is changed
+        let prevpid = processor::get_pid_on(cpu);
+        let changed = prevpid != Some(nextpid);
+        if changed {
+            CONTEXT_SWITCHES.fetch_add(1, Ordering::Relaxed);
+            inner.count_switch();
+        }
+        drop(inner);
+        if let Some(prev) = processor::get_proc_on(cpu).filter(|_| changed) {
+            prev.write().count_leave();
+        }
+        // update processor's current process, found by `current` until the next switch
+        processor::set_proc_on(cpu, nextproc);
+
+        if let Some(prevpid) = prevpid.filter(|_| changed) {
+            // copied out, so a hook is free to take any lock
+            let hooks = *self.switch_hooks.lock();
+            for hook in hooks.iter().flatten() {
+                hook(prevpid, nextpid);
+            }
+        }
+
+        #[cfg(feature = "sched_check")]
+        self.validate_invariants();
+
+        nextpid
+    }
+
+    /// Check the ready queues against the process table: every queued pid
+    /// is found and not dead, none is queued twice, and the current
+    /// process of this processor is set
+    #[cfg(feature = "sched_check")]
+    fn check_invariants(&self) -> Result<(), String> {
+        let queue = self.all_ready();
+        let mut queued = BTreeSet::new();
+        for pid in queue.iter() {
+            if !queued.insert(*pid) {
+                return Err(format!("Process #{} is queued twice: {:?}", pid, queue));
+            }
+            match self.get_proc(pid) {
+                Some(proc) if proc.read().status() == ProgramStatus::Dead => {
+                    return Err(format!("Process #{} is queued but dead", pid));
+                }
+                Some(_) => {}
+                None => return Err(format!("Process #{} is queued but not found", pid)),
+            }
+        }
+
+        let cpu = processor::cpu_id();
+        if processor::get_proc_on(cpu).is_none() {
+            return Err(format!("No current process on CPU {}", cpu));
+        }
+        Ok(())
+    }
+
+    /// Panic on a broken invariant of the scheduler, see `check_invariants`
+    #[cfg(feature = "sched_check")]
+    pub fn validate_invariants(&self) {
+        if let Err(err) = self.check_invariants() {
+            panic!("Scheduler invariant violated: {}", err);
+        }
+    }
+
+    /// Corrupt the ready queue on purpose, and check the validator
+    /// reports it, the queue is restored after each case
+    #[cfg(feature = "sched_check")]
+    pub fn test_invariant_check(&self) {
+        let queue = self.ready_queue();
+        let saved = queue.lock().clone();
+        let cases = [
+            ("a missing process", [ProcessId(u16::MAX)].to_vec()),
+            ("a process queued twice", [KERNEL_PID, KERNEL_PID].to_vec()),
+        ];
+        for (case, pids) in cases {
+            queue.lock().extend(pids);
+            let result = self.check_invariants();
+            queue.lock().clone_from(&saved);
+            match result {
+                Err(err) => info!("Invariant check caught {}: {}", case, err),
+                Ok(()) => panic!("Invariant check missed {}", case),
+            }
+        }
+        self.validate_invariants();
+        info!("Invariant check test passed.");
+    }
+
+    pub fn kill_current(&self, ret: isize) {
+        self.kill(processor::get_pid(), ret);
+    }
+
+    pub fn handle_page_fault(&self, addr: VirtAddr, err_code: PageFaultErrorCode) -> bool {
+        // handle page fault
+        let nowproc = self.current();
+        let mut inner = nowproc.write();
+        inner.handle_page_fault(addr, err_code)
+    }
+
+    /// The physical address behind `addr` of the current process
+    pub fn translate(&self, addr: VirtAddr) -> Option<PhysAddr> {
+        self.current().read().vm().translate(addr)
+    }
+
+    pub fn is_user_range(&self, addr: VirtAddr, len: usize) -> bool {
+        self.current().read().vm().is_user_range(addr, len)
+    }
+
+    pub fn is_privileged(&self) -> bool {
+        self.current().read().privileged()
+    }
+
+    pub fn drop_privilege(&self) {
+        self.current().write().drop_privilege();
+    }
+
+    pub fn spawn_depth(&self) -> usize {
+        self.current().read().spawn_depth()
+    }
+
+    pub fn canary(&self) -> u64 {
+        self.current().read().canary()
+    }
+
+    pub fn page_faults(&self) -> (usize, usize) {
+        self.current().read().page_faults()
+    }
+
+    pub fn reset_stats(&self) -> Stats {
+        self.current().write().reset_stats()
+    }
+
+    pub fn kill_self(&self, ret: isize) {
+        self.kill(processor::get_pid(), ret);
+    }
+
+    pub fn kill(&self, pid: ProcessId, ret: isize) {
+        let proc = self.get_proc(&pid);
+
+        if proc.is_none() {
+            warn!("Process #{} not found.", pid);
+            return;
+        }
+
+        let proc = proc.unwrap();
+
+        if proc.read().status() == ProgramStatus::Dead {
+            warn!("Process #{} is already dead.", pid);
+            return;
+        }
+
+        trace!("Kill Porcess {:?}", pid);
+
+        // before the semaphores are dropped with the process data,
+        // the waiters of those it still holds would never be signaled
+        let abandoned = proc.read().release_semaphores(pid);
+        for waiter in abandoned {
+            self.wake_up_with(waiter, SEM_OWNER_DEAD);
+        }
+
+        // before the memory lent by the vfork parent is released
+        self.end_vfork(&proc);
+        // checked again under the lock, only one of the racing kills goes on
+        if !proc.kill(ret) {
+            return;
+        }
+        // notify the parent like `SIGCHLD`, but for a detached one, nobody
+        // waits for its exit code, and it is released with no parent below
+        let parent = {
+            let inner = proc.read();
+            inner.parent().filter(|_| !inner.is_detached())
+        };
+        if let Some(parent) = parent.as_ref() {
+            let mut inner = parent.write();
+            inner.child_signal().notify(pid);
+            inner.post_exit(pid, ret);
+            let paused = inner.status() == ProgramStatus::Blocked
+                && inner.block_reason() == Some(BlockReason::Paused)
+                && inner.signal_pending();
+            drop(inner);
+            // the handler is entered once it is switched to
+            if paused {
+                self.wake_up(parent.pid());
+            }
+        }
+        self.waiting_any.lock().remove(&pid);
+        // a ready process killed by another is not picked later
+        for queue in self.ready_queues.iter() {
+            queue.lock().retain(|queued| *queued != pid);
+        }
+        self.ready_since.lock().remove(&pid);
+        // before the waiters, which may reap it off the table
+        self.wake_waiting_any(pid);
+        self.wake_waiting(pid, ret);
+        self.release_orphans(pid, parent.as_ref());
+
+        #[cfg(feature = "sched_check")]
+        self.validate_invariants();
+    }
+
+    /// Kill every process but the kernel, the newest first, and release
+    /// their memory on the page table of the kernel, as the tables of
+    /// the caller are freed too
+    ///
+    /// returns the number of processes that cannot be cleaned up
+    pub fn reap_all(&self) -> usize {
+        self.get_proc(&KERNEL_PID).unwrap().read().vm().page_table.load();
+
+        let mut procs: Vec<Arc<Process>> = self
+            .processes
+            .values()
+            .into_iter()
+            .filter(|proc| proc.pid() != KERNEL_PID)
+            .filter(|proc| proc.read().status() != ProgramStatus::Dead)
+            .collect();
+        // the pids are reused, so they tell nothing of the age
+        procs.sort_unstable_by_key(|proc| {
+            core::cmp::Reverse((proc.read().created_at(), proc.pid()))
+        });
+
+        let mut unclean = 0;
+        for proc in procs {
+            self.kill(proc.pid(), KILLED_EXIT_CODE);
+            let inner = proc.read();
+            if inner.status() != ProgramStatus::Dead || inner.has_vm() {
+                warn!("Process {}#{} is not cleaned up.", inner.name(), proc.pid());
+                unclean += 1;
+            }
+        }
+        unclean
+    }
+
+    /// Kill the current thread and all the others sharing the memory,
+    /// the leader goes last, so it releases the memory with the code
+    pub fn exit_group(&self, ret: isize) {
+        let current = self.current();
+        let leader = current.read().thread_group().unwrap_or(current.pid());
+        let threads: Vec<ProcessId> = self
+            .processes
+            .values()
+            .into_iter()
+            .filter(|p| {
+                let inner = p.read();
+                inner.status() != ProgramStatus::Dead && inner.thread_group() == Some(leader)
+            })
+            .map(|p| p.pid())
+            .collect();
+
+        for pid in threads {
+            self.kill(pid, ret);
+        }
+        let alive = self
+            .get_proc(&leader)
+            .is_some_and(|p| p.read().status() != ProgramStatus::Dead);
+        if alive {
+            self.kill(leader, ret);
+        }
+    }
+
+    /// Kill all the live processes in the process group `pgid`,
+    /// returns the number of processes killed
+    pub fn kill_group(&self, pgid: ProcessId, ret: isize) -> usize {
+        let members: Vec<ProcessId> = self
+            .processes
+            .values()
+            .into_iter()
+            .filter(|p| p.pid() != KERNEL_PID)
+            .filter(|p| {
+                let inner = p.read();
+                inner.status() != ProgramStatus::Dead && inner.pgid() == Some(pgid)
+            })
+            .map(|p| p.pid())
+            .collect();
+
+        for pid in members.iter() {
+            self.kill(*pid, ret);
+        }
+
+        members.len()
+    }
+
+    /// Kill all the live descendants of `pid`,
+    /// returns the number of processes killed
+    pub fn kill_tree(&self, pid: ProcessId, ret: isize) -> usize {
+        // dead processes are kept in the table until the manager drops them,
+        // so the parent chain of every live process is still reachable
+        let descendants: Vec<ProcessId> = self
+            .processes
+            .values()
+            .into_iter()
+            .filter(|p| p.pid() != KERNEL_PID && p.pid() != pid)
+            .filter(|p| p.read().status() != ProgramStatus::Dead)
+            .filter(|p| is_descendant(p, pid))
+            .map(|p| p.pid())
+            .collect();
+
+        for pid in descendants.iter() {
+            self.kill(*pid, ret);
+        }
+
+        descendants.len()
+    }
+
+    /// Get the live process `pid`, which must be
+    /// the current process or one of its children
+    fn get_self_or_child(&self, pid: ProcessId) -> Option<Arc<Process>> {
+        let current = self.current();
+        let proc = self.get_proc(&pid)?;
+
+        if proc.pid() != current.pid()
+            && proc.read().parent().map(|p| p.pid()) != Some(current.pid())
+        {
+            return None;
+        }
+
+        if proc.read().status() == ProgramStatus::Dead {
+            return None;
+        }
+        Some(proc)
+    }
+
+    /// Get the limit of the resource of `pid`, and replace it if `new`,
+    /// `pid` must be the current process or one of its children
+    ///
+    /// returns the old limit, `None` if not allowed, see `Limits::set`
+    pub fn prlimit(&self, pid: ProcessId, resource: usize, new: Option<Limit>) -> Option<Limit> {
+        let proc = self.get_self_or_child(pid)?;
+        let mut inner = proc.write();
+        match new {
+            Some(limit) => inner.set_limit(resource, limit),
+            None => inner.limits().get(resource),
+        }
+    }
+
+    /// Set the process group of `pid`, which must be
+    /// the current process or one of its children
+    pub fn set_pgid(&self, pid: ProcessId, pgid: ProcessId) -> bool {
+        match self.get_self_or_child(pid) {
+            Some(proc) => {
+                proc.write().set_pgid(pgid);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The process group of the live process `pid`
+    pub fn get_pgid(&self, pid: ProcessId) -> Option<ProcessId> {
+        self.get_proc(&pid)?.read().pgid()
+    }
+
+    /// The session of the live process `pid`
+    pub fn get_sid(&self, pid: ProcessId) -> Option<ProcessId> {
+        self.get_proc(&pid)?.read().sid()
+    }
+
+    /// Make the current process lead a new session, returns the new sid,
+    /// or `None` if it leads a process group already
+    pub fn set_sid(&self) -> Option<ProcessId> {
+        let current = self.current();
+        let pid = current.pid();
+        let mut inner = current.write();
+        if inner.pgid() == Some(pid) {
+            return None;
+        }
+        inner.set_sid(pid);
+        Some(pid)
+    }
+
+    /// Set the tick budget of `pid`, which must be
+    /// the current process or one of its children
+    pub fn set_tick_budget(&self, pid: ProcessId, ticks: usize) -> bool {
+        match self.get_self_or_child(pid) {
+            Some(proc) => {
+                proc.write().set_tick_budget(ticks);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The live processes whose parent is `pid`, in the order of pids
+    pub fn list_children(&self, pid: ProcessId) -> Option<Vec<ProcessId>> {
+        self.get_proc(&pid)?;
+        let mut children: Vec<ProcessId> = self
+            .processes
+            .values()
+            .into_iter()
+            .filter(|p| {
+                let inner = p.read();
+                inner.status() != ProgramStatus::Dead
+                    && inner.parent().is_some_and(|parent| parent.pid() == pid)
+            })
+            .map(|p| p.pid())
+            .collect();
+        children.sort();
+        Some(children)
+    }
+
+    /// The live processes named exactly `name`, in the order of pids
+    pub fn find_by_name(&self, name: &str) -> Vec<ProcessId> {
+        let mut pids: Vec<ProcessId> = self
+            .processes
+            .values()
+            .into_iter()
+            .filter(|p| {
+                let inner = p.read();
+                inner.status() != ProgramStatus::Dead && inner.name() == name
+            })
+            .map(|p| p.pid())
+            .collect();
+        pids.sort();
+        pids
+    }
+
+    pub fn proc_name(&self, pid: ProcessId) -> Option<String> {
+        Some(self.get_proc(&pid)?.read().name().into())
+    }
+
+    pub fn proc_age(&self, pid: ProcessId) -> Option<u64> {
+        Some(self.get_proc(&pid)?.read().proc_age())
+    }
+
+    pub fn sched_latency(&self, pid: ProcessId) -> Option<SchedLatency> {
+        Some(self.get_proc(&pid)?.read().sched_latency())
+    }
+
+    /// The effective priority of the live process `pid`
+    pub fn get_priority(&self, pid: ProcessId) -> Option<usize> {
+        let proc = self.get_proc(&pid)?;
+        let inner = proc.read();
+        if inner.status() == ProgramStatus::Dead {
+            return None;
+        }
+        Some(inner.effective_priority())
+    }
+
+    /// Set the base priority of `pid`, the current process can only
+    /// lower its own priority, i.e. raise the value, and set any value
+    /// for its live descendants
+    pub fn set_priority(&self, pid: ProcessId, priority: usize) -> bool {
+        let current = self.current();
+        let proc = match self.get_proc(&pid) {
+            Some(proc) => proc,
+            None => return false,
+        };
+        if proc.read().status() == ProgramStatus::Dead {
+            return false;
+        }
+
+        let allowed = if pid == current.pid() {
+            priority >= proc.read().priority()
+        } else {
+            is_descendant(&proc, current.pid())
+        };
+        if allowed {
+            proc.write().set_priority(priority);
+        }
+        allowed
+    }
+
+    pub fn get_affinity(&self, pid: ProcessId) -> Option<u64> {
+        let proc = self.get_proc(&pid)?;
+        let inner = proc.read();
+        if inner.status() == ProgramStatus::Dead {
+            return None;
+        }
+        Some(inner.affinity())
+    }
+
+    /// Set the CPUs `pid` may run on, for the current process itself
+    /// or its live descendants, the mask must include an online CPU
+    pub fn set_affinity(&self, pid: ProcessId, mask: u64) -> bool {
+        let current = self.current();
+        let proc = match self.get_proc(&pid) {
+            Some(proc) => proc,
+            None => return false,
+        };
+        if proc.read().status() == ProgramStatus::Dead || mask & processor::online_mask() == 0 {
+            return false;
+        }
+
+        let allowed = pid == current.pid() || is_descendant(&proc, current.pid());
+        if allowed {
+            proc.write().set_affinity(mask);
+        }
+        allowed
+    }
+
+    /// Translate `addr` by the page table of `pid`, for the current process
+    /// itself or its live descendants, `None` if not allowed or unmapped
+    pub fn virt_to_phys(&self, pid: ProcessId, addr: VirtAddr) -> Option<PageMapping> {
+        let current = self.current();
+        let proc = self.get_proc(&pid)?;
+        if pid != current.pid() && !is_descendant(&proc, current.pid()) {
+            return None;
+        }
+
+        let inner = proc.read();
+        if inner.status() == ProgramStatus::Dead || !inner.has_vm() {
+            return None;
+        }
+        let (phys, flags) = inner.vm().mapping(addr)?;
+        Some(PageMapping {
+            phys: phys.as_u64(),
+            flags: flags.bits(),
+        })
+    }
+
+    /// Save the registers, the memory & the fds of the current process
+    /// itself, or a live descendant not running
+    ///
+    /// `context` is the syscall frame of the current process
+    pub fn checkpoint(&self, pid: ProcessId, context: &ProcessContext) -> bool {
+        let current = self.current();
+        if pid == current.pid() {
+            return current.write().checkpoint(Some(context));
+        }
+        match self.live_descendant(pid) {
+            Some(proc) if proc.read().status() != ProgramStatus::Running => {
+                proc.write().checkpoint(None)
+            }
+            _ => false,
+        }
+    }
+
+    /// Reset the current process itself, or a live descendant not running,
+    /// to its checkpoint, the current process resumes from it by `context`
+    pub fn restore_checkpoint(&self, pid: ProcessId, context: &mut ProcessContext) -> bool {
+        let current = self.current();
+        if pid == current.pid() {
+            return current.write().restore_checkpoint(Some(context));
+        }
+        match self.live_descendant(pid) {
+            Some(proc) if proc.read().status() != ProgramStatus::Running => {
+                proc.write().restore_checkpoint(None)
+            }
+            _ => false,
+        }
+    }
+
+    /// The live descendant `pid` of the current process, `None` if not allowed
+    fn live_descendant(&self, pid: ProcessId) -> Option<Arc<Process>> {
+        let proc = self.get_proc(&pid)?;
+        let live = proc.read().status() != ProgramStatus::Dead && proc.read().has_vm();
+        (live && is_descendant(&proc, self.current().pid())).then_some(proc)
+    }
+
+    /// Open the memory of a live descendant as a file
+    pub fn open_proc_mem(&self, pid: ProcessId) -> isize {
+        if self.live_descendant(pid).is_none() {
+            return ENOENT;
+        }
+        self.current().read().open_resource(Resource::ProcMem(ProcMem::new(pid)))
+    }
+
+    /// Read the memory of the descendant `pid`, 0 once it has exited
+    pub fn peek_mem(&self, pid: ProcessId, addr: usize, buf: &mut [u8]) -> usize {
+        match self.live_descendant(pid) {
+            Some(proc) => proc.read().vm().peek(addr, buf),
+            None => 0,
+        }
+    }
+
+    /// Write the memory of the descendant `pid`, 0 once it has exited
+    pub fn poke_mem(&self, pid: ProcessId, addr: usize, buf: &[u8]) -> usize {
+        match self.live_descendant(pid) {
+            Some(proc) => proc.read().vm().poke(addr, buf),
+            None => 0,
+        }
+    }
+
+    pub fn set_child_handler(&self, handler: Option<(VirtAddr, usize)>) {
+        self.current().write().child_signal().set_handler(handler);
+    }
+
+    pub fn take_child_exited(&self) -> bool {
+        self.current().write().child_signal().take_exited()
+    }
+
+    /// Set the handler of `sig` for the current process unless `action` is `None`,
+    /// returns the one before
+    pub fn sigaction(&self, sig: usize, action: Option<SigAction>) -> SigAction {
+        let current = self.current();
+        let mut inner = current.write();
+        match action {
+            Some(action) => inner.set_sigaction(sig, action),
+            None => inner.sigaction(sig).unwrap_or_default(),
+        }
+    }
+
+    /// Send `sig` to `pid`, which runs its handler once switched to, a paused
+    /// one is woken up for it, and a sleep is cut short with `SysError::Intr`
+    /// unless the handler has `SA_RESTART`, the masked ones wait meanwhile
+    ///
+    /// without a handler `pid` is killed with `-sig`, even if masked,
+    /// returns false if it is not alive
+    pub fn send_signal(&self, pid: ProcessId, sig: usize) -> bool {
+        let proc = match self.get_proc(&pid) {
+            Some(proc) if pid != KERNEL_PID => proc,
+            _ => return false,
+        };
+        let mut inner = proc.write();
+        if inner.status() == ProgramStatus::Dead {
+            return false;
+        }
+        let action = match inner.sigaction(sig) {
+            Some(action) => action,
+            None => {
+                drop(inner);
+                self.kill(pid, -(sig as isize));
+                return true;
+            }
+        };
+        inner.send_signal(sig);
+        let blocked = inner.status() == ProgramStatus::Blocked && inner.signal_pending();
+        let intr = match inner.block_reason().filter(|_| blocked) {
+            Some(BlockReason::Paused) => Some(0),
+            Some(BlockReason::Sleeping) if action.flags & SA_RESTART == 0 => {
+                if let Some(timer) = inner.take_sleep_timer() {
+                    cancel_timer(timer);
+                }
+                Some(SysError::Intr.code())
+            }
+            _ => None,
+        };
+        drop(inner);
+        // the handler is entered once it is switched to
+        if let Some(ret) = intr {
+            self.wake_up_with(pid, ret);
+        }
+        true
+    }
+
+    /// Return from the signal handler of the current process,
+    /// and enter the next one for the pending signals if any
+    pub fn sig_return(&self, context: &mut ProcessContext) -> bool {
+        let current = self.current();
+        let mut inner = current.write();
+        if !inner.sig_return(context) {
+            return false;
+        }
+        inner.deliver_signals(context);
+        true
+    }
+
+    /// Change the signal mask of the current process, see `set_sigmask`,
+    /// the pending signals no longer masked are delivered from `context`
+    pub fn sigprocmask(&self, how: usize, set: u64, context: &mut ProcessContext) -> Option<u64> {
+        let current = self.current();
+        let mut inner = current.write();
+        let old = inner.set_sigmask(how, set)?;
+        inner.deliver_signals(context);
+        Some(old)
+    }
+
+    pub fn set_quota(&self, quota: Option<(u64, u64)>) {
+        let now = crate::interrupt::read_counter();
+        self.current().write().set_quota(quota, now);
+    }
+
+    pub fn set_open_defaults(&self, flags: OpenFlags) {
+        self.current().write().set_open_defaults(flags);
+    }
+
+    pub fn open_defaults(&self) -> OpenFlags {
+        self.current().read().open_defaults()
+    }
+
+    pub fn no_new_privs(&self) -> bool {
+        self.current().read().no_new_privs()
+    }
+
+    pub fn set_no_new_privs(&self) {
+        self.current().write().set_no_new_privs();
+    }
+
+    pub fn chroot(&self, path: &str) -> bool {
+        self.current().write().chroot(path)
+    }
+
+    /// Describe every structure holding the processes to run or wake up
+    pub fn dump_sched(&self) -> String {
+        let name = |pid: &ProcessId| match self.get_proc(pid) {
+            Some(proc) => format!("#{} ({})", pid, proc.read().name()),
+            None => format!("#{} (?)", pid),
+        };
+        let names = |pids: &mut dyn Iterator<Item = &ProcessId>| {
+            pids.map(name).collect::<Vec<_>>().join(", ")
+        };
+
+        // copy the queues out before the processes are named
+        let ready = self.all_ready();
+        let waiting = self.waiting_processes.lock().clone();
+        let waiting_any = self.waiting_any.lock().clone();
+
+        let mut output = format!("Policy  : {:?}\n", self.policy());
+        output += format!("Ready   : {}\n", names(&mut ready.iter())).as_str();
+        for (pid, waiters) in waiting.iter() {
+            let waiters = names(&mut waiters.iter());
+            output += format!("Waiting : {} <- {}\n", name(pid), waiters).as_str();
+        }
+        output += format!("WaitAny : {}\n", names(&mut waiting_any.iter())).as_str();
+
+        // sleeping processes are held by the timers, find them by the reason
+        let procs = self.processes.values();
+        let sleeping: Vec<ProcessId> = procs
+            .iter()
+            .filter(|p| p.read().block_reason() == Some(BlockReason::Sleeping))
+            .map(|p| p.pid())
+            .collect();
+        output += format!("Sleeping: {}\n", names(&mut sleeping.iter())).as_str();
+
+        // the semaphores are shared by the forked processes, list each set once
+        let mut sets = Vec::new();
+        for proc in procs.iter().filter(|p| p.pid() != KERNEL_PID) {
+            let inner = proc.read();
+            if inner.status() == ProgramStatus::Dead {
+                continue;
+            }
+            let set = inner.semaphores.clone();
+            if !sets.iter().any(|s| Arc::ptr_eq(s, &set)) {
+                sets.push(set);
+            }
+        }
+        for set in sets {
+            for (key, count, waiters) in set.read().snapshot() {
+                output += format!(
+                    "Sem     : <{:#x}> count {} <- {}\n",
+                    key,
+                    count,
+                    names(&mut waiters.iter())
+                )
+                .as_str();
+            }
+        }
+
+        output
+    }
+
+    pub fn print_process_list(&self) {
+        let mut output =
+            String::from("  PID | PPID | Process Name |  Ticks  |   Age   |   Memory  | Status\n");
+
+        for p in self.processes.values() {
+            if p.read().status() != ProgramStatus::Dead {
+                output += format!("{}\n", p).as_str();
+            }
+        }
+
+        // TODO: print memory usage of kernel heap
+        let alloc = get_frame_alloc_for_sure();
+        let frames_used = alloc.frames_used();
+        let frames_recycled = alloc.frames_recycled();
+        let frames_total = alloc.frames_total();
+
+        let used = (frames_used - frames_recycled) * PAGE_SIZE as usize;
+        let total = frames_total * PAGE_SIZE as usize;
+
+        output += &format_usage("Memory", used, total);
+        output += format!(
+            "Recycle: {} frames (largest run: {})\n",
+            frames_recycled,
+            alloc.largest_recycled_run()
+        )
+        .as_str();
+        drop(alloc);
+
+        for (cpu, queue) in self.ready_queues.iter().enumerate() {
+            let queue = queue.lock();
+            if !queue.is_empty() {
+                output += format!("Queue {}: {:?}\n", cpu, queue).as_str();
+            }
+        }
+
+        let switches = context_switches();
+        let ticks = crate::interrupt::read_counter().max(1);
+        output += format!(
+            "Switch : {} times ({:.2} per tick)\n",
+            switches,
+            switches as f32 / ticks as f32
+        )
+        .as_str();
+
+        let (idle, wakeups) = idle_stats();
+        output += format!("Idle   : {} switches, {} wakeups\n", idle, wakeups).as_str();
+
+        let (steals, stolen) = steal_stats();
+        output += format!("Steal  : {} times, {} processes\n", steals, stolen).as_str();
+
+        output += &processor::print_processors();
+
+        print!("{}", output);
+    }
+
+    pub fn print_process_info(&self, pid: &ProcessId) -> bool {
+        if let Some(proc) = self.get_proc(pid) {
+            proc.read().print_info();
+            true
+        } else {
+            warn!("Process #{} not found.", pid);
+            false
+        }
+    }
+
+    pub fn is_proc_alive(&self, pid: &ProcessId) -> bool {
+        if let Some(proc) = self.get_proc(pid) {
+            proc.read().status() != ProgramStatus::Dead
+        } else {
+            false
+        }
+    }
+
+    pub fn fork(&self, cow: bool) -> Option<Arc<Process>> {
+        // get current process
+        let proc = self.current();
+        if !self.can_create_child(&proc) {
+            return None;
+        }
+        // reject the forks over the rate, e.g. of a `loop { fork() }`,
+        // before they fill the process table
+        let now = crate::interrupt::read_counter();
+        if !proc.read().fork_rate().allows(now) {
+            warn!("Process #{} forks too fast.", proc.pid());
+            return None;
+        }
+        // fork to get child
+        let child = proc.fork(cow);
+        proc.write().fork_rate_mut().record(now);
+        // add child to process list
+        self.add_proc(child.pid(), child.clone());
+        // maybe print the process ready queue?
+        debug!("Ready Queue: {:?}", self.ready_queue().lock());
+
+        #[cfg(feature = "sched_check")]
+        self.validate_invariants();
+
+        Some(child)
+    }
+
+    /// Create a vfork child of the current process, see `Process::vfork`,
+    /// it counts as a fork in the rate
+    pub fn vfork(&self) -> Option<Arc<Process>> {
+        let proc = self.current();
+        if !self.can_create_child(&proc) {
+            return None;
+        }
+        let now = crate::interrupt::read_counter();
+        if !proc.read().fork_rate().allows(now) {
+            warn!("Process #{} forks too fast.", proc.pid());
+            return None;
+        }
+        let child = proc.vfork();
+        proc.write().fork_rate_mut().record(now);
+        self.add_proc(child.pid(), child.clone());
+
+        Some(child)
+    }
+
+    /// Give the stack back to the parent blocked by the vfork of `proc`
+    /// and wake it up, nothing to do if `proc` is not a vfork child
+    fn end_vfork(&self, proc: &Process) {
+        let (parent, stack) = match proc.write().end_vfork() {
+            Some(lent) => lent,
+            None => return,
+        };
+        if let Some(parent_proc) = self.get_proc(&parent) {
+            parent_proc.write().return_stack(stack);
+        }
+        self.wake_up(parent);
+    }
+
+    /// Create a thread of the current process, see `Process::thread`
+    pub fn thread(
+        &self,
+        context: &ProcessContext,
+        entry: VirtAddr,
+        args: [usize; 2],
+    ) -> Option<Arc<Process>> {
+        let proc = self.current();
+        if !self.can_create_child(&proc) {
+            return None;
+        }
+        let child = proc.thread(context, entry, args);
+        self.add_proc(child.pid(), child.clone());
+
+        Some(child)
+    }
+
+    /// Create a child of the current process, see `Process::clone_child`,
+    /// the children with a copy of the memory count as forks in the rate
+    pub fn clone_child(
+        &self,
+        context: &ProcessContext,
+        flags: CloneFlags,
+        entry: VirtAddr,
+        args: [usize; 2],
+    ) -> Option<Arc<Process>> {
+        let proc = self.current();
+        if !self.can_create_child(&proc) {
+            return None;
+        }
+        let fork = !flags.contains(CloneFlags::VM);
+        let now = crate::interrupt::read_counter();
+        if fork && !proc.read().fork_rate().allows(now) {
+            warn!("Process #{} forks too fast.", proc.pid());
+            return None;
+        }
+        let child = proc.clone_child(context, flags, entry, args);
+        if fork {
+            proc.write().fork_rate_mut().record(now);
+        }
+        self.add_proc(child.pid(), child.clone());
+
+        Some(child)
+    }
+
+    pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
+        self.current().read().read(fd, buf)
+    }
+
+    pub fn try_read(&self, fd: u8, buf: &mut [u8]) -> isize {
+        self.current().read().try_read(fd, buf)
+    }
+
+    pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
         self.current().write().write(fd, buf)
     }
 
-    pub fn open_file(&self, path: &str) -> u8 {
-        self.current().write().open_file(path)
+    pub fn readv(&self, fd: u8, bufs: &mut [&mut [u8]]) -> isize {
+        self.current().read().readv(fd, bufs)
+    }
+
+    pub fn writev(&self, fd: u8, bufs: &[&[u8]]) -> isize {
+        self.current().read().writev(fd, bufs)
+    }
+
+    pub fn poll(&self, fds: &[u8], flags: &mut [u8]) -> isize {
+        self.current().read().poll(fds, flags)
+    }
+
+    pub fn fstat(&self, fd: u8) -> Option<FileStat> {
+        self.current().read().fstat(fd)
+    }
+
+    pub fn isatty(&self, fd: u8) -> isize {
+        self.current().read().isatty(fd)
+    }
+
+    pub fn getdents(&self, fd: u8, cursor: usize, dirents: &mut [Dirent]) -> isize {
+        self.current().read().getdents(fd, cursor, dirents)
+    }
+
+    pub fn seek(&self, fd: u8, pos: SeekFrom) -> isize {
+        self.current().read().seek(fd, pos)
+    }
+
+    pub fn ftruncate(&self, fd: u8, len: usize) -> isize {
+        self.current().read().ftruncate(fd, len)
+    }
+
+    pub fn fsync(&self, fd: u8) -> isize {
+        self.current().read().fsync(fd)
+    }
+
+    pub fn fadvise(&self, fd: u8, advice: Advice) -> isize {
+        self.current().read().fadvise(fd, advice)
+    }
+
+    /// Map the file behind the fd, a writable shared mapping
+    /// needs a writable file, the fd is ignored for anonymous ones
+    pub fn mmap(&self, fd: u8, offset: usize, len: usize, flags: MmapFlags) -> isize {
+        let current = self.current();
+        let mut proc = current.write();
+
+        let file = match flags.contains(MmapFlags::ANONYMOUS) {
+            true => None,
+            false => {
+                let write = flags.contains(MmapFlags::SHARED | MmapFlags::WRITE);
+                match proc.resource(fd) {
+                    Some(file) if file.lock().is_file(write) => Some(file),
+                    _ => return -1,
+                }
+            }
+        };
+
+        let limit = proc.mem_limit();
+        match proc.vm_mut().mmap(file, offset, len, flags, limit) {
+            Some(addr) => addr.as_u64() as isize,
+            None => -1,
+        }
+    }
+
+    /// Keep the exit code of the child `pid` of the caller for `collect`
+    pub fn watch_exit(&self, pid: ProcessId) {
+        self.current().write().watch_exit(pid);
+    }
+
+    /// Hand the new child `pid` of the caller over to the kernel process,
+    /// so it is no longer counted, waited for or killed with the caller
+    pub fn detach(&self, pid: ProcessId) {
+        let current = self.current();
+        if let Some(proc) = self.get_proc(&pid).filter(|_| self.is_child(pid, current.pid())) {
+            let kproc = self.get_proc(&KERNEL_PID).unwrap();
+            proc.write().detach(Arc::downgrade(&kproc), pid);
+            current.write().dec_child_count();
+            debug!("Process #{} detached from #{}", pid, current.pid());
+        }
+    }
+
+    /// Take the exit code of a watched child, which is reaped once taken
+    pub fn collect(&self, pid: ProcessId) -> Option<Option<isize>> {
+        let code = self.current().write().collect(pid);
+        if let Some(Some(_)) = code {
+            self.reap(pid, processor::get_pid());
+        }
+        code
+    }
+
+    pub fn munmap(&self, addr: VirtAddr) -> bool {
+        self.current().write().vm_mut().munmap(addr)
+    }
+
+    /// Map the device memory into the current process, see `MmapSet::map_device`
+    pub fn map_device(&self, phys: PhysAddr, len: usize) -> Option<VirtAddr> {
+        self.current().write().vm_mut().map_device(phys, len)
+    }
+
+    pub fn madvise_dontneed(&self, addr: VirtAddr, len: usize) -> bool {
+        self.current().read().vm().madvise_dontneed(addr, len)
+    }
+
+    pub fn mlock(&self, addr: VirtAddr, len: usize, locked: bool) -> bool {
+        self.current().read().vm().mlock(addr, len, locked)
+    }
+
+    pub fn fd_stats(&self, fd: u8) -> Option<(u64, u64)> {
+        self.current().read().fd_stats(fd)
+    }
+
+    pub fn sendfile(&self, out_fd: u8, in_fd: u8, count: usize) -> isize {
+        self.current().read().sendfile(out_fd, in_fd, count)
+    }
+
+    pub fn splice(&self, in_fd: u8, out_fd: u8, count: usize) -> isize {
+        self.current().read().splice(in_fd, out_fd, count)
+    }
+
+    pub fn set_mem_limit(&self, bytes: u64) {
+        self.current().write().set_mem_limit(bytes)
+    }
+
+    pub fn set_cloexec(&self, fd: u8, cloexec: bool) -> isize {
+        self.current().read().set_cloexec(fd, cloexec)
+    }
+
+    pub fn fcntl(&self, fd: u8, cmd: usize, arg: usize) -> isize {
+        self.current().read().fcntl(fd, cmd, arg)
+    }
+
+    pub fn fchmod(&self, fd: u8, mode: usize) -> isize {
+        self.current().read().fchmod(fd, mode)
+    }
+
+    pub fn open_file(&self, path: &str, flags: OpenFlags) -> isize {
+        if let Some(pid) = ProcMem::parse_path(path) {
+            return self.open_proc_mem(pid);
+        }
+        self.current().write().open_file(path, flags)
     }
 
     pub fn close_file(&self, fd: u8) -> bool {
         self.current().write().close_file(fd)
     }
 
+    pub fn dup2(&self, old_fd: u8, new_fd: u8) -> isize {
+        self.current().read().dup2(old_fd, new_fd)
+    }
+
+    pub fn pipe(&self) -> (u8, u8) {
+        self.current().read().pipe()
+    }
+
+    pub fn tee_pipe(&self, readers: usize) -> (u8, Vec<u8>) {
+        self.current().read().tee_pipe(readers)
+    }
+
+    pub fn send_fd(&self, channel_fd: u8, fd: u8) -> isize {
+        self.current().read().send_fd(channel_fd, fd)
+    }
+
+    pub fn recv_fd(&self, channel_fd: u8) -> isize {
+        self.current().read().recv_fd(channel_fd)
+    }
+
+    pub fn watch_add(&self, path: &str) -> isize {
+        self.current().read().watch_add(path)
+    }
+
+    pub fn watch_read(&self, fd: u8, event: &mut WatchEvent) -> isize {
+        self.current().read().watch_read(fd, event)
+    }
+
+    pub fn eventfd(&self, init: u64, semaphore: bool) -> u8 {
+        self.current().read().eventfd(init, semaphore)
+    }
+
+    pub fn capture_start(&self) -> isize {
+        self.current().read().capture_start()
+    }
+
+    pub fn capture_end(&self, buf: &mut [u8]) -> isize {
+        self.current().read().capture_end(buf)
+    }
+
     pub fn brk(&self, addr: Option<VirtAddr>) -> Option<VirtAddr> {
         let pid = get_pid();
         if let Some(proc) = self.get_proc(&pid) {
-            proc.read().brk(addr)
+            proc.write().brk(addr)
         } else {
             None
         }
     }
+
+    pub fn heap_avail(&self) -> u64 {
+        self.current().read().heap_avail()
+    }
+}
+
+/// Whether `proc` is a descendant of `ancestor`
+fn is_descendant(proc: &Process, ancestor: ProcessId) -> bool {
+    let mut parent = proc.read().parent();
+    while let Some(proc) = parent {
+        if proc.pid() == ancestor {
+            return true;
+        }
+        parent = proc.read().parent();
+    }
+    false
 }
 
 // A helper function to format memory usage
@@ -1,42 +1,110 @@
+mod appcache;
+mod audit;
+mod checkpoint;
 mod context;
 mod data;
+mod forkrate;
+mod futex;
+mod idle;
+mod limits;
 mod manager;
+mod msg;
 mod paging;
 mod pid;
 mod process;
 mod processor;
+mod quota;
+mod signal;
 mod sync;
+mod table;
 mod vm;
 
-use crate::filesystem::get_rootfs;
+use crate::filesystem::{get_rootfs, OpenFlags};
 use crate::proc::vm::ProcessVm;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 pub use manager::*;
 use process::*;
-use storage::FileSystem;
-use xmas_elf::ElfFile;
+use storage::{Advice, FileSystem, SeekFrom};
+use syscall_def::{AuditEntry, BlockReason, Dirent, ElfInfo, FileStat, Limit, PageMapping, RUsage};
+use syscall_def::{sig_bit, FbInfo, SchedPolicy, Stats, WakeSource, WatchEvent, SPIN_HINT_LIMIT};
+use syscall_def::{SIGALRM, SIGCHLD, SIGKILL, SIGSTOP, SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK};
+use syscall_def::{SchedLatency, SysError, RLIMIT_AS, RLIMIT_CPU, RLIMIT_NPROC};
+use syscall_def::{SigAction, NSIG, RUSAGE_SELF, SA_RESETHAND, SA_RESTART};
+use xmas_elf::{program, ElfFile};
 
 use alloc::string::{String, ToString};
+pub use appcache::forget_app;
 pub use context::ProcessContext;
 pub use data::ProcessData;
+pub use idle::{idle_hook_runs, register_idle_hook, run_idle_hooks, IdleTask};
 pub use paging::PageTableContext;
-pub use pid::ProcessId;
+pub use pid::{ProcessId, MAX_PID};
+pub use process::CloneFlags;
+pub use table::table_lookups;
 
-use x86_64::structures::idt::PageFaultErrorCode;
-use x86_64::VirtAddr;
+use x86_64::structures::idt::{InterruptStackFrameValue, PageFaultErrorCode};
+use x86_64::{PhysAddr, VirtAddr};
 
-use sync::SemaphoreResult;
+use futex::get_futexes;
+use msg::{get_message_queues, MsgResult};
+use sync::{SemaphoreResult, SEM_OWNER_DEAD, SEM_TIMED_OUT};
 
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+pub use vm::stack::StackSize;
 use vm::stack::*;
 
+pub use vm::mmap::MmapFlags;
+
 pub const KERNEL_PID: ProcessId = ProcessId(1);
 
+/// The exit code of a process killed by another one
+pub const KILLED_EXIT_CODE: isize = -9;
+/// The exit code of a process killed by its alarm, without a `SIGALRM` handler
+pub const ALARM_EXIT_CODE: isize = -(SIGALRM as isize);
+/// The exit code of a process whose stack canary is overwritten
+pub const STACK_CHK_EXIT_CODE: isize = -6;
+
+/// The maximum number of live processes in the system
+pub const MAX_PROCESS_COUNT: usize = 64;
+/// The maximum number of unreaped children of a single process
+pub const MAX_CHILD_COUNT: usize = 16;
+
+/// Whether `wait_pid` only accepts the children of the caller (POSIX),
+/// otherwise any process can be waited, e.g. by its siblings
+pub const WAIT_CHILDREN_ONLY: bool = false;
+
+/// The priority of the processes unless inherited
+pub const DEFAULT_PRIORITY: usize = 0;
+
+/// The affinity of the processes unless inherited, i.e. all the CPUs
+pub const ALL_CPUS: u64 = u64::MAX;
+
+/// The maximum length of the name set by `set_proc_name` in chars,
+/// the width of the name column in the process list
+pub const PROC_NAME_MAX: usize = 12;
+
+/// The default of the maximum spawn depth, see `set_max_spawn_depth`
+pub const DEFAULT_MAX_SPAWN_DEPTH: usize = 32;
+
+static MAX_SPAWN_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_SPAWN_DEPTH);
+
+/// Limit how deep a chain of spawns can go, counted from the kernel
+pub fn set_max_spawn_depth(depth: usize) {
+    MAX_SPAWN_DEPTH.store(depth, Ordering::Relaxed);
+}
+
+pub fn max_spawn_depth() -> usize {
+    MAX_SPAWN_DEPTH.load(Ordering::Relaxed)
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ProgramStatus {
     Running,
     Ready,
     Blocked,
+    /// Spawned suspended, not scheduled until continued
+    Stopped,
     Dead,
 }
 
@@ -52,26 +120,135 @@ pub fn init(boot_info: &'static boot::BootInfo) {
     kproc.write().resume();
     let app_list = boot_info.loaded_apps.as_ref();
     manager::init(kproc, app_list);
+    set_boot_args(boot_info.cmdline);
 
     info!("Process Manager Initialized.");
 }
 
+/// Give up the CPU, e.g. by a yield, a voluntary switch
 pub fn switch(context: &mut ProcessContext) {
+    reschedule(context, false);
+}
+
+/// Switch away from the process preempted by the clock, an involuntary switch
+pub fn preempt(context: &mut ProcessContext) {
+    reschedule(context, true);
+}
+
+fn reschedule(context: &mut ProcessContext, preempted: bool) {
     x86_64::instructions::interrupts::without_interrupts(|| {
         // switch to the next process
+        let cpu = processor::cpu_id();
         let manager = get_process_manager();
-        let pid = manager.save_current(context);
-        manager.push_ready(pid);
-        manager.switch_next(context);
+        let pid = if preempted {
+            manager.save_preempted(cpu, context)
+        } else {
+            manager.save_current(cpu, context)
+        };
+        manager.push_preempted(pid);
+        manager.switch_next(cpu, context);
     });
 }
 
+/// Sample the load on the clock tick, before switching away
+pub fn update_load() {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().update_load(processor::cpu_id())
+    })
+}
+
+#[cfg(feature = "sched_test")]
+pub fn test_idle_fallback() {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().test_idle_fallback()
+    })
+}
+
+#[cfg(feature = "steal_test")]
+pub fn test_work_stealing() {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().test_work_stealing()
+    })
+}
+
+/// Keep the kernel command line as the env of the kernel,
+/// which is copied for init once it is spawned
+pub fn set_boot_args(cmdline: &str) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_boot_args(cmdline)
+    })
+}
+
+/// The value of `key` on the kernel command line, e.g. `init`
+pub fn boot_arg(key: &str) -> Option<String> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().boot_arg(key))
+}
+
+/// Boot with a simulated command line, launching `/APP/BOOTARG` as init
+#[cfg(feature = "bootarg_test")]
+pub fn test_boot_args() {
+    set_boot_args("init=/APP/BOOTARG bootarg=first  bootarg=ok quiet");
+}
+
+#[cfg(feature = "switch_test")]
+pub fn test_switch_hook() {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().test_switch_hook()
+    })
+}
+
+/// Run `hook` on every change of the running process, see `SwitchHook`
+pub fn on_switch(hook: SwitchHook) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().on_switch(hook))
+}
+
+#[cfg(feature = "sched_check")]
+pub fn test_invariant_check() {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().test_invariant_check()
+    })
+}
+
+/// Whether the current process may call the restricted syscalls,
+/// i.e. the init process or its forked children
+pub fn is_privileged() -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().is_privileged())
+}
+
+/// Give up the privilege of the current process for good
+pub fn drop_privilege() {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().drop_privilege())
+}
+
+pub fn sched_policy() -> SchedPolicy {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().policy())
+}
+
+pub fn set_sched_policy(policy: SchedPolicy) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_policy(policy)
+    })
+}
+
+pub fn current_name() -> String {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().name().into()
+    })
+}
+
 pub fn print_process_list() {
     x86_64::instructions::interrupts::without_interrupts(|| {
         get_process_manager().print_process_list();
     })
 }
 
+/// The argument of the current process at `index`, given on spawn
+pub fn arg(index: usize) -> Option<String> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().arg(index).map(String::from)
+    })
+}
+
 pub fn env(key: &str) -> Option<String> {
     x86_64::instructions::interrupts::without_interrupts(|| {
         // get current process's environment variable
@@ -79,6 +256,24 @@ pub fn env(key: &str) -> Option<String> {
     })
 }
 
+/// Rename the current process, truncated to `PROC_NAME_MAX` chars,
+/// returns the number of chars kept
+pub fn set_proc_name(name: &str) -> usize {
+    let name: String = name.chars().take(PROC_NAME_MAX).collect();
+    let len = name.chars().count();
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().write().set_name(name)
+    });
+    len
+}
+
+pub fn set_env(key: &str, val: &str) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        // set current process's environment variable
+        get_process_manager().current().write().set_env(key, val)
+    })
+}
+
 pub fn process_exit(ret: isize) -> ! {
     x86_64::instructions::interrupts::without_interrupts(|| {
         get_process_manager().kill_current(ret);
@@ -90,6 +285,12 @@ pub fn process_exit(ret: isize) -> ! {
 }
 
 pub fn handle_page_fault(addr: VirtAddr, err_code: PageFaultErrorCode) -> bool {
+    // the kernel may write to the user memory with the process locked
+    let cow_write = PageFaultErrorCode::PROTECTION_VIOLATION | PageFaultErrorCode::CAUSED_BY_WRITE;
+    if !err_code.contains(PageFaultErrorCode::USER_MODE) && err_code.contains(cow_write) {
+        return paging::handle_cow_fault(addr);
+    }
+
     x86_64::instructions::interrupts::without_interrupts(|| {
         get_process_manager().handle_page_fault(addr, err_code)
     })
@@ -129,161 +330,1292 @@ pub fn list_app() {
 //     elf_spawn(name.to_string(), &app.elf)
 // }
 
-pub fn spawn(path: &str) -> Option<ProcessId> {
+/// Get the ELF metadata of the embedded app `name`
+pub fn elf_info(name: &str) -> Option<ElfInfo> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let app_list = get_process_manager().app_list()?;
+        let app = app_list.iter().find(|app| app.name.eq(name))?;
+        let load_segments = app
+            .elf
+            .program_iter()
+            .filter(|segment| segment.get_type() == Ok(program::Type::Load))
+            .count();
+
+        Some(ElfInfo {
+            entry: app.elf.header.pt2.entry_point(),
+            load_segments: load_segments as u64,
+        })
+    })
+}
+
+/// Read the whole app at `path`, the recently spawned ones are cached
+fn read_app(path: &str) -> Option<Arc<Vec<u8>>> {
+    appcache::get_or_read(path, || {
+        let mut handle = match get_rootfs().open_file(path) {
+            Ok(handle) => handle,
+            Err(err) => {
+                warn!("Cannot open {}: {:?}", path, err);
+                return None;
+            }
+        };
+        let mut buf = Vec::new();
+        if let Err(err) = handle.read_all(&mut buf) {
+            warn!("Cannot read {}: {:?}", path, err);
+            return None;
+        }
+        Some(buf)
+    })
+}
+
+/// Spawn the app at `path`, a `suspended` one runs only after `cont`
+pub fn spawn(path: &str, suspended: bool) -> Option<ProcessId> {
+    spawn_with_stdio(path, suspended, [None, None], None, Vec::new(), StackSize::default())
+}
+
+/// The longest path taken from the user space
+pub const PATH_MAX: usize = 256;
+
+/// Whether the `len` bytes at `ptr` are all mapped for the current process
+pub fn is_user_range(ptr: usize, len: usize) -> bool {
+    let addr = match VirtAddr::try_new(ptr as u64) {
+        Ok(addr) => addr,
+        Err(_) => return false,
+    };
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().is_user_range(addr, len)
+    })
+}
+
+/// Copy `len` bytes of a string at `ptr` from the current process,
+/// `None` if it is longer than `PATH_MAX`, not mapped or not UTF-8
+pub fn copy_str_from_user(ptr: usize, len: usize) -> Option<String> {
+    if len == 0 {
+        return Some(String::new());
+    }
+    if len > PATH_MAX || !is_user_range(ptr, len) {
+        return None;
+    }
+    let bytes = unsafe { core::slice::from_raw_parts(ptr as *const u8, len) };
+    core::str::from_utf8(bytes).ok().map(String::from)
+}
+
+/// Spawn the app with its stdin and stdout replaced by the given fds
+/// of the caller, `None` keeps the console
+///
+/// the child starts in `cwd` if given, which must be a dir,
+/// or in the working dir of the caller, and gets `args` for `GetArg`
+/// and a stack of `stack_size`
+pub fn spawn_with_stdio(
+    path: &str,
+    suspended: bool,
+    stdio: [Option<u8>; 2],
+    cwd: Option<&str>,
+    args: Vec<String>,
+    stack_size: StackSize,
+) -> Option<ProcessId> {
+    let cwd = match cwd {
+        Some(dir) => match lookup_path(dir) {
+            Ok(dir) if crate::filesystem::is_dir(&dir) => Some(dir),
+            _ => {
+                warn!("Cannot spawn {} in {}: not a dir", path, dir);
+                return None;
+            }
+        },
+        None => None,
+    };
+    let name: Vec<&str> = path.rsplit('/').collect();
+    let buf = read_app(path)?;
+    let elf = match ElfFile::new(buf.as_slice()) {
+        Ok(elf) => elf,
+        Err(err) => {
+            warn!("Cannot parse {}: {}", path, err);
+            return None;
+        }
+    };
+    elf_spawn(name[0].to_string(), &elf, suspended, stdio, cwd, args, stack_size)
+}
+
+/// The absolute path of `path` from the working dir of the current process,
+/// with the symlinks followed, `ELOOP` if they loop
+pub fn lookup_path(path: &str) -> Result<String, isize> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().lookup(path)
+    })
+}
+
+/// Replace the image of the current process with the app at `path`,
+/// returns false if the app cannot be loaded, the caller keeps running
+pub fn exec(path: &str, context: &mut ProcessContext) -> bool {
     let name: Vec<&str> = path.rsplit('/').collect();
-    let mut handle = get_rootfs().open_file(path).expect("Cannot open file");
-    let mut buf = Vec::new();
-    let elf = {
-        handle.read_all(&mut buf).expect("");
-        ElfFile::new(buf.as_slice()).unwrap()
+    let buf = match read_app(path) {
+        Some(buf) => buf,
+        None => return false,
     };
-    elf_spawn(name[0].to_string(), &elf)
+    let elf = match ElfFile::new(buf.as_slice()) {
+        Ok(elf) => elf,
+        Err(err) => {
+            warn!("Cannot parse {}: {}", path, err);
+            return false;
+        }
+    };
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().exec(&elf, name[0].to_string(), context)
+    });
+    true
+}
+
+/// Keep the exit code of the child `pid` until the caller collects it
+pub fn watch_exit(pid: ProcessId) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().watch_exit(pid)
+    })
 }
 
-pub fn elf_spawn(name: String, elf: &ElfFile) -> Option<ProcessId> {
-    let pid = x86_64::instructions::interrupts::without_interrupts(|| {
+/// Reparent the new child `pid` to the kernel process, see `ProcessManager::detach`
+pub fn detach(pid: ProcessId) {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().detach(pid))
+}
+
+/// Take the exit code of a collectable child, see `ProcessInner::collect`
+pub fn collect(pid: ProcessId) -> Option<Option<isize>> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().collect(pid))
+}
+
+pub fn elf_spawn(
+    name: String,
+    elf: &ElfFile,
+    suspended: bool,
+    stdio: [Option<u8>; 2],
+    cwd: Option<String>,
+    args: Vec<String>,
+    stack_size: StackSize,
+) -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
         let process_name = name.to_lowercase();
-        let parent = Arc::downgrade(&manager.current());
-        let pid = manager.spawn(elf, name, Some(parent), None);
+        let current = manager.current();
 
-        debug!("Spawned process: {}#{}", process_name, pid);
-        pid
-    });
+        // the child shares the resources with the caller, like `dup2`
+        let mut proc_data = ProcessData::new();
+        proc_data.cwd = cwd.unwrap_or_else(|| current.read().cwd().into());
+        // a new process group in the session of the caller
+        proc_data.sid = current.read().sid().unwrap_or(ProcessId(0));
+        proc_data.args = args;
+        proc_data.stack_size = stack_size;
+        for (fd, src) in stdio.iter().enumerate() {
+            if let Some(src) = src {
+                proc_data.install(fd as u8, current.read().resource(*src)?);
+            }
+        }
+
+        let parent = Arc::downgrade(&current);
+        let pid = manager.spawn(elf, name, Some(parent), Some(proc_data), suspended)?;
 
-    Some(pid)
+        debug!("Spawned process: {}#{}", process_name, pid);
+        Some(pid)
+    })
 }
 
 pub fn read(fd: u8, buf: &mut [u8]) -> isize {
     x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().read(fd, buf))
 }
 
+pub fn try_read(fd: u8, buf: &mut [u8]) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().try_read(fd, buf)
+    })
+}
+
 pub fn write(fd: u8, buf: &[u8]) -> isize {
     x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().write(fd, buf))
 }
 
-pub fn fork(context: &mut ProcessContext) {
+pub fn readv(fd: u8, bufs: &mut [&mut [u8]]) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().readv(fd, bufs))
+}
+
+pub fn writev(fd: u8, bufs: &[&[u8]]) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().writev(fd, bufs))
+}
+
+pub fn poll(fds: &[u8], flags: &mut [u8]) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().poll(fds, flags))
+}
+
+pub fn fstat(fd: u8) -> Option<FileStat> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().fstat(fd))
+}
+
+pub fn isatty(fd: u8) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().isatty(fd))
+}
+
+pub fn getdents(fd: u8, cursor: usize, dirents: &mut [Dirent]) -> isize {
     x86_64::instructions::interrupts::without_interrupts(|| {
-        let manager = get_process_manager();
-        // save_current as parent
-        let pid = manager.save_current(context);
-        // fork to get child
-        let child = manager.fork();
-        // push to child & parent to ready queue
-        trace!("Process {} forked Process {}", get_pid().0, child.pid());
-        manager.push_ready(child.pid());
-        manager.push_ready(pid);
-        // switch to next process
-        manager.switch_next(context);
+        get_process_manager().getdents(fd, cursor, dirents)
     })
 }
 
-pub fn exit(ret: isize, context: &mut ProcessContext) {
+pub fn seek(fd: u8, pos: SeekFrom) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().seek(fd, pos))
+}
+
+/// The bytes read & written through the fd of the current process
+pub fn fd_stats(fd: u8) -> Option<(u64, u64)> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().fd_stats(fd))
+}
+
+pub fn sendfile(out_fd: u8, in_fd: u8, count: usize) -> isize {
     x86_64::instructions::interrupts::without_interrupts(|| {
-        let manager = get_process_manager();
-        manager.wake_waiting(ret);
-        manager.kill_self(ret);
-        manager.switch_next(context);
+        get_process_manager().sendfile(out_fd, in_fd, count)
     })
 }
 
-pub fn brk(addr: Option<VirtAddr>) -> isize {
+pub fn splice(in_fd: u8, out_fd: u8, count: usize) -> isize {
     x86_64::instructions::interrupts::without_interrupts(|| {
-        let manager = get_process_manager();
-        match manager.brk(addr) {
-            Some(new_end) => new_end.as_u64() as isize,
-            None => -1,
-        }
+        get_process_manager().splice(in_fd, out_fd, count)
     })
 }
 
-pub fn get_pid() -> ProcessId {
-    processor::get_pid()
+pub fn set_mem_limit(bytes: u64) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_mem_limit(bytes)
+    })
 }
 
-pub fn wait_pid(pid: ProcessId, context: &mut ProcessContext) {
+pub fn set_cloexec(fd: u8, cloexec: bool) -> isize {
     x86_64::instructions::interrupts::without_interrupts(|| {
-        if still_alive(pid) {
-            let manager = get_process_manager();
-            let now_pid = get_pid();
-            manager.save_current(context);
-            manager.block_proc(&now_pid);
-            manager.add_waiting(pid);
-            manager.switch_next(context);
-        } else {
-            let exit_code = get_process_manager().get_exit_code(pid).unwrap();
-            context.set_rax(exit_code as usize);
-        }
-    });
+        get_process_manager().set_cloexec(fd, cloexec)
+    })
 }
 
-#[inline]
-pub fn still_alive(pid: ProcessId) -> bool {
+pub fn fcntl(fd: u8, cmd: usize, arg: usize) -> isize {
     x86_64::instructions::interrupts::without_interrupts(|| {
-        get_process_manager().is_proc_alive(&pid)
+        get_process_manager().fcntl(fd, cmd, arg)
+    })
+}
+
+pub fn ftruncate(fd: u8, len: usize) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().ftruncate(fd, len)
+    })
+}
+
+pub fn fsync(fd: u8) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().fsync(fd))
+}
+
+pub fn fadvise(fd: u8, advice: Advice) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().fadvise(fd, advice)
+    })
+}
+
+pub fn mmap(fd: u8, offset: usize, len: usize, flags: MmapFlags) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().mmap(fd, offset, len, flags)
+    })
+}
+
+/// Free the frames of a heap range, mapped again with zeros on access
+pub fn madvise_dontneed(addr: Option<VirtAddr>, len: usize) -> bool {
+    match addr {
+        Some(addr) => x86_64::instructions::interrupts::without_interrupts(|| {
+            get_process_manager().madvise_dontneed(addr, len)
+        }),
+        None => false,
+    }
+}
+
+/// Lock or unlock a page range of the heap or a mapping,
+/// the locked pages are never freed by `madvise_dontneed`
+pub fn mlock(addr: Option<VirtAddr>, len: usize, locked: bool) -> bool {
+    match addr {
+        Some(addr) => x86_64::instructions::interrupts::without_interrupts(|| {
+            get_process_manager().mlock(addr, len, locked)
+        }),
+        None => false,
+    }
+}
+
+/// Map the framebuffer into the current process, shared with the
+/// other mappers and the kernel, None if there is no framebuffer
+pub fn map_framebuffer() -> Option<FbInfo> {
+    let fb = crate::fb::get_frame_buffer()?;
+    // the base may be in the middle of a page
+    let offset = fb.base % crate::memory::PAGE_SIZE;
+    let phys = PhysAddr::new(fb.base - offset);
+    let len = fb.size + offset as usize;
+    let addr = x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().map_device(phys, len)
+    })?;
+
+    Some(FbInfo {
+        base: (addr + offset).as_u64() as usize,
+        size: fb.size,
+        width: fb.width,
+        height: fb.height,
+        stride: fb.stride,
+        format: fb.format,
     })
 }
 
-pub fn sem_wait(key: u32, context: &mut ProcessContext) {
+pub fn munmap(addr: Option<VirtAddr>) -> bool {
+    match addr {
+        Some(addr) => {
+            x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().munmap(addr))
+        }
+        None => false,
+    }
+}
+
+pub fn fork(context: &mut ProcessContext, cow: bool) {
     x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
         let manager = get_process_manager();
-        let pid = processor::get_pid();
-        let ret = manager.current().write().sem_wait(key, pid);
-        match ret {
-            SemaphoreResult::Ok => context.set_rax(0),
-            SemaphoreResult::NotExist => context.set_rax(1),
-            SemaphoreResult::Block(_pid) => {
-                // save, block it, then switch to next
-                manager.save_current(context);
-                manager.block_proc(&pid);
-                manager.switch_next(context);
+        // save_current as parent
+        let pid = manager.save_current(cpu, context);
+        // fork to get child
+        let child = match manager.fork(cow) {
+            Some(child) => child,
+            None => {
+                // fork failed, keep running the parent and return -1
+                manager.current().write().resume();
+                context.set_rax(-1isize as usize);
+                return;
             }
-            _ => unreachable!(),
         };
+        // push to child & parent to ready queue
+        trace!("Process {} forked Process {}", get_pid().0, child.pid());
+        manager.push_ready(child.pid());
+        manager.push_ready(pid);
+        // switch to next process
+        manager.switch_next(cpu, context);
     })
 }
 
-pub fn sem_signal(key: u32, context: &mut ProcessContext) {
+/// Block the caller until a signal is delivered to its handler, returns 0
+/// once the handler returns, or at once if a signal is pending already
+pub fn pause(context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
         let manager = get_process_manager();
-        let ret = manager.current().write().sem_signal(key);
-        match ret {
-            SemaphoreResult::Ok => context.set_rax(0),
-            SemaphoreResult::NotExist => context.set_rax(1),
-            SemaphoreResult::WakeUp(pid) => manager.wake_up(pid),
-            _ => unreachable!(),
-        };
+        context.set_rax(0);
+        {
+            let current = manager.current();
+            let mut inner = current.write();
+            if inner.signal_pending() {
+                inner.deliver_signals(context);
+                return;
+            }
+        }
+        let pid = manager.save_current(cpu, context);
+        manager.block_proc(&pid, BlockReason::Paused);
+        manager.switch_next(cpu, context);
     })
 }
 
-pub fn new_sem(key: u32, value: usize) -> usize {
+/// Create a child running on the memory and the stack of the caller,
+/// which is blocked until the child execs or exits
+pub fn vfork(context: &mut ProcessContext) {
     x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
         let manager = get_process_manager();
-        let ret = manager.current().write().new_sem(key, value);
-        if ret {
-            0
-        } else {
-            1
-        }
+        let pid = manager.save_current(cpu, context);
+        let child = match manager.vfork() {
+            Some(child) => child,
+            None => {
+                manager.current().write().resume();
+                context.set_rax(-1isize as usize);
+                return;
+            }
+        };
+        trace!("Process {} vforked Process {}", pid, child.pid());
+        manager.block_proc(&pid, BlockReason::Vfork);
+        manager.push_ready(child.pid());
+        manager.switch_next(cpu, context);
     })
 }
 
-pub fn remove_sem(key: u32) -> usize {
+/// Create a thread calling `entry` with `args`, the caller keeps running
+pub fn thread(context: &ProcessContext, entry: VirtAddr, args: [usize; 2]) -> Option<ProcessId> {
     x86_64::instructions::interrupts::without_interrupts(|| {
         let manager = get_process_manager();
-        let ret = manager.current().write().remove_sem(key);
-        if ret {
-            0
-        } else {
-            1
-        }
+        let child = manager.thread(context, entry, args)?;
+        manager.push_ready(child.pid());
+        Some(child.pid())
     })
 }
 
-pub fn open_file(path: &str) -> u8 {
-    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().open_file(path))
+/// Create a child calling `entry` with `args`, sharing the parts of the
+/// caller in `flags`, the caller keeps running
+pub fn clone_process(
+    context: &ProcessContext,
+    flags: CloneFlags,
+    entry: VirtAddr,
+    args: [usize; 2],
+) -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let child = manager.clone_child(context, flags, entry, args)?;
+        manager.push_ready(child.pid());
+        Some(child.pid())
+    })
 }
 
-pub fn close_file(fd: u8) -> bool {
-    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().close_file(fd))
+pub fn exit(ret: isize, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
+        let manager = get_process_manager();
+        manager.kill_self(ret);
+        manager.switch_next(cpu, context);
+    })
+}
+
+/// Exit the current thread and all the other threads of the process
+pub fn exit_group(ret: isize, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
+        let manager = get_process_manager();
+        manager.exit_group(ret);
+        manager.switch_next(cpu, context);
+    })
+}
+
+/// The frames allowed to stay in use over the baseline at shutdown
+pub const LEAK_THRESHOLD: usize = 16;
+
+/// Kill every process, check no frame is leaked since the boot,
+/// then quit QEMU with the result of the check
+pub fn shutdown() -> ! {
+    x86_64::instructions::interrupts::disable();
+    info!("Shutdown: reaping all processes.");
+    let unclean = get_process_manager().reap_all();
+    let residual = crate::memory::residual_frames();
+    if unclean == 0 && residual <= LEAK_THRESHOLD {
+        info!("Shutdown: {} residual frames, no leak found.", residual);
+        crate::exit_qemu(crate::SHUTDOWN_EXIT_CODE)
+    } else {
+        error!(
+            "Shutdown: {} residual frames, {} processes not cleaned up.",
+            residual, unclean
+        );
+        crate::exit_qemu(crate::LEAK_EXIT_CODE)
+    }
+}
+
+/// Kill the process `pid`, or every process in the group `-pid`
+/// if `pid` is negative
+pub fn kill(pid: isize, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
+        let manager = get_process_manager();
+        let ret = if pid < 0 {
+            let pgid = ProcessId(pid.unsigned_abs() as u16);
+            manager.kill_group(pgid, KILLED_EXIT_CODE) as isize
+        } else {
+            let pid = ProcessId(pid as u16);
+            if pid != KERNEL_PID && manager.is_proc_alive(&pid) {
+                manager.kill(pid, KILLED_EXIT_CODE);
+                1
+            } else {
+                -1
+            }
+        };
+
+        if manager.is_proc_alive(&get_pid()) {
+            context.set_rax(ret as usize);
+        } else {
+            // the caller killed itself
+            manager.switch_next(cpu, context);
+        }
+    })
+}
+
+pub fn kill_tree(ret: isize) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().kill_tree(get_pid(), ret)
+    })
+}
+
+pub fn set_pgid(pid: ProcessId, pgid: ProcessId) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_pgid(pid, pgid)
+    })
+}
+
+pub fn get_pgid(pid: ProcessId) -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().get_pgid(pid))
+}
+
+pub fn get_sid(pid: ProcessId) -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().get_sid(pid))
+}
+
+pub fn set_sid() -> Option<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().set_sid())
+}
+
+/// Get the spawn depth of self & the maximum allowed
+/// Let the suspended child `pid` of the current process run
+pub fn cont(pid: ProcessId) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().cont(pid, processor::get_pid())
+    })
+}
+
+pub fn set_open_defaults(flags: OpenFlags) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_open_defaults(flags)
+    })
+}
+
+pub fn open_defaults() -> OpenFlags {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().open_defaults())
+}
+
+/// Whether the children of the current process are never privileged
+pub fn no_new_privs() -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().no_new_privs())
+}
+
+pub fn set_no_new_privs() {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_no_new_privs()
+    })
+}
+
+/// Confine the paths of the current process to the dir at `path`,
+/// see `ProcessData::chroot`
+pub fn chroot(path: &str) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().chroot(path))
+}
+
+pub fn spawn_depth() -> (usize, usize) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        (get_process_manager().spawn_depth(), max_spawn_depth())
+    })
+}
+
+/// The stack canary of the current process, see `ProcessData::canary`
+pub fn canary() -> u64 {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().canary())
+}
+
+pub fn page_faults() -> (usize, usize) {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().page_faults())
+}
+
+pub fn reset_stats() -> Stats {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().reset_stats())
+}
+
+pub fn set_tick_budget(pid: ProcessId, ticks: usize) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_tick_budget(pid, ticks)
+    })
+}
+
+pub fn set_child_handler(handler: Option<(VirtAddr, usize)>) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_child_handler(handler)
+    })
+}
+
+pub fn take_child_exited() -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().take_child_exited()
+    })
+}
+
+pub fn sig_return(context: &mut ProcessContext) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().sig_return(context)
+    })
+}
+
+/// Set the handler of `sig` unless `action` is `None`, returns the one before,
+/// `None` if `sig` cannot be caught, see `signal::is_catchable`
+pub fn sigaction(sig: usize, action: Option<SigAction>) -> Option<SigAction> {
+    if !signal::is_catchable(sig) {
+        return None;
+    }
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        Some(get_process_manager().sigaction(sig, action))
+    })
+}
+
+/// Send `sig` to `pid`, see `ProcessManager::send_signal`, a signal sent to the
+/// caller itself runs the handler before it returns, unless masked
+pub fn tkill(pid: ProcessId, sig: usize, context: &mut ProcessContext) {
+    if !(1..=NSIG).contains(&sig) || sig == SIGSTOP || sig == SIGCHLD {
+        context.set_rax(SysError::Inval.code() as usize);
+        return;
+    }
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
+        let manager = get_process_manager();
+        if !manager.send_signal(pid, sig) {
+            context.set_rax(SysError::NotFound.code() as usize);
+            return;
+        }
+        context.set_rax(0);
+        if !manager.is_proc_alive(&get_pid()) {
+            // the caller killed itself
+            manager.switch_next(cpu, context);
+        } else if pid == get_pid() {
+            manager.current().write().deliver_signals(context);
+        }
+    })
+}
+
+/// Change the signal mask, returns the old one, `None` if `how` is unknown
+///
+/// the return value must be set in `context` first, as it is saved
+/// to return to once a pending signal is delivered
+pub fn sigprocmask(how: usize, set: u64, context: &mut ProcessContext) -> Option<u64> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().sigprocmask(how, set, context)
+    })
+}
+
+/// Throttle the current process to `rate` ticks per second,
+/// 0 to remove the quota, false if the tsc is not calibrated
+pub fn set_quota(rate: u64) -> bool {
+    let hz = crate::tsc::ticks_per_sec();
+    if hz == 0 {
+        return false;
+    }
+    let quota = (rate != 0).then_some((rate, hz));
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_quota(quota)
+    });
+    true
+}
+
+/// Arm the alarm of the current process to kill it in `ms`, 0 to disarm,
+/// returns the ms left of the alarm replaced, 0 if there was none
+/// Count a spin hint of the current process, returns true if it
+/// should yield, i.e. it has spun over the limit in this time slice
+pub fn spin_hint() -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().write().spin_hint()
+    })
+}
+
+pub fn alarm(ms: u64) -> u64 {
+    let hz = crate::tsc::ticks_per_sec().max(1);
+    let ticks = (ms * hz).div_ceil(1000);
+    let left = x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().alarm(ticks)
+    });
+    (left * 1000).div_ceil(hz)
+}
+
+pub fn dump_sched() -> String {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().dump_sched())
+}
+
+pub fn list_children(pid: ProcessId) -> Option<Vec<ProcessId>> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().list_children(pid)
+    })
+}
+
+/// The live processes named exactly `name`
+pub fn find_by_name(name: &str) -> Vec<ProcessId> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().find_by_name(name)
+    })
+}
+
+/// The name of `pid` shown in the process list, dead ones not reaped included
+pub fn proc_name(pid: ProcessId) -> Option<String> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().proc_name(pid))
+}
+
+pub fn proc_age(pid: ProcessId) -> Option<u64> {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().proc_age(pid))
+}
+
+pub fn sched_latency(pid: ProcessId) -> Option<SchedLatency> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().sched_latency(pid)
+    })
+}
+
+pub fn get_priority(pid: ProcessId) -> Option<usize> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().get_priority(pid)
+    })
+}
+
+pub fn set_priority(pid: ProcessId, priority: usize) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_priority(pid, priority)
+    })
+}
+
+pub fn prlimit(pid: ProcessId, resource: usize, new: Option<Limit>) -> Option<Limit> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().prlimit(pid, resource, new)
+    })
+}
+
+/// Save the state of self or a descendant, see `ProcessManager::checkpoint`
+pub fn checkpoint(pid: ProcessId, context: &ProcessContext) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().checkpoint(pid, context)
+    })
+}
+
+/// Reset self or a descendant to its checkpoint, the registers of
+/// the current process are restored to `context`
+pub fn restore_checkpoint(pid: ProcessId, context: &mut ProcessContext) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().restore_checkpoint(pid, context)
+    })
+}
+
+pub fn virt_to_phys(pid: ProcessId, addr: VirtAddr) -> Option<PageMapping> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().virt_to_phys(pid, addr)
+    })
+}
+
+pub fn get_affinity(pid: ProcessId) -> Option<u64> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().get_affinity(pid)
+    })
+}
+
+pub fn set_affinity(pid: ProcessId, mask: u64) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().set_affinity(pid, mask)
+    })
+}
+
+pub fn brk(addr: Option<VirtAddr>) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        match manager.brk(addr) {
+            Some(new_end) => new_end.as_u64() as isize,
+            None => -1,
+        }
+    })
+}
+
+/// The bytes the heap of the caller can still grow by, see `ProcessVm::heap_avail`
+pub fn heap_avail() -> u64 {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().heap_avail())
+}
+
+pub fn get_pid() -> ProcessId {
+    processor::get_pid()
+}
+
+/// The id of the processor running the caller
+pub fn cpu_id() -> usize {
+    processor::cpu_id()
+}
+
+/// Order the memory accesses of every online CPU, see `processor::membarrier`
+pub fn membarrier() {
+    processor::membarrier()
+}
+
+/// Block until the child `pid` exits and return its exit code,
+/// `-NotFound` at once for self or a process that is not a child
+pub fn wait_pid(pid: ProcessId, context: &mut ProcessContext) {
+    wait4(pid, None, context)
+}
+
+/// The resource usage of the caller so far, only `RUSAGE_SELF` is supported
+pub fn getrusage(who: isize) -> Option<RUsage> {
+    if who != RUSAGE_SELF {
+        return None;
+    }
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        Some(get_process_manager().current().read().rusage())
+    })
+}
+
+/// Wait for the child like `wait_pid`, and put its resource usage
+/// at `rusage` once it exits, maybe after the caller is woken up
+pub fn wait4(pid: ProcessId, rusage: Option<VirtAddr>, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let now_pid = get_pid();
+        // waiting on itself would never be woken up
+        let not_child = WAIT_CHILDREN_ONLY && !get_process_manager().is_child(pid, now_pid);
+        if pid == now_pid || not_child {
+            context.set_rax(SysError::NotFound.code() as usize);
+            return;
+        }
+
+        if still_alive(pid) {
+            let cpu = processor::cpu_id();
+            let manager = get_process_manager();
+            manager.save_current(cpu, context);
+            manager.block_proc(&now_pid, BlockReason::WaitingChild);
+            if let Some(addr) = rusage {
+                manager.current().write().set_rusage_out(addr);
+            }
+            manager.add_waiting(pid);
+            manager.switch_next(cpu, context);
+        } else {
+            let manager = get_process_manager();
+            let exit_code = manager.get_exit_code(pid).unwrap_or(SysError::NotFound.code());
+            if let (Some(addr), Some(usage)) = (rusage, manager.rusage(pid)) {
+                unsafe { *addr.as_mut_ptr::<RUsage>() = usage };
+            }
+            manager.reap(pid, now_pid);
+            context.set_rax(exit_code as usize);
+        }
+    });
+}
+
+/// Collect the child exited first, or block until any child exits,
+/// -1 at once if there is no child to wait for
+pub fn wait_any(code: Option<&mut isize>, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let now_pid = get_pid();
+        match manager.exited_child(now_pid) {
+            WaitAnyResult::Exited(pid, exit_code) => {
+                manager.reap(pid, now_pid);
+                if let Some(code) = code {
+                    *code = exit_code;
+                }
+                context.set_rax(pid.0 as usize);
+            }
+            WaitAnyResult::Running => {
+                // issue the syscall again once woken up
+                let cpu = processor::cpu_id();
+                context.restart_syscall();
+                manager.save_current(cpu, context);
+                manager.block_proc(&now_pid, BlockReason::WaitingChild);
+                manager.add_waiting_any(now_pid);
+                manager.switch_next(cpu, context);
+            }
+            WaitAnyResult::NoChild => context.set_rax(-1isize as usize),
+        }
+    });
+}
+
+#[inline]
+pub fn still_alive(pid: ProcessId) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().is_proc_alive(&pid)
+    })
+}
+
+/// Whether the current process owns the stack of the interrupted user code
+///
+/// the pid recorded by the processor is the only source of truth for the
+/// current process, this is a sanity check of it at the syscall entry
+pub fn is_current_stack(stack_frame: &InterruptStackFrameValue) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager()
+            .current()
+            .read()
+            .is_on_stack(stack_frame.stack_pointer)
+    })
+}
+
+/// Why the process is blocked, `None` if it is not found
+pub fn block_reason(pid: ProcessId) -> Option<Option<BlockReason>> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().block_reason(&pid)
+    })
+}
+
+/// The latest state transitions of the process, `None` if it is not found
+pub fn wake_log(pid: ProcessId, buf: &mut [AuditEntry]) -> Option<usize> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().wake_log(&pid, buf)
+    })
+}
+
+/// Sleep for at least `ns` nanoseconds
+///
+/// durations within a tick are busy-waited on the tsc,
+/// longer ones block the caller for the ticks covering them
+pub fn nanosleep(ns: u64, context: &mut ProcessContext) {
+    let ns_per_tick = crate::tsc::tsc_to_ns(crate::tsc::tsc_per_tick());
+    if ns_per_tick == 0 {
+        context.set_rax(-1isize as usize);
+        return;
+    }
+    context.set_rax(0);
+
+    if ns < ns_per_tick {
+        crate::tsc::busy_wait(crate::tsc::ns_to_tsc(ns));
+        return;
+    }
+
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
+        let manager = get_process_manager();
+        let pid = get_pid();
+        // the current tick is partly elapsed, wait for one more
+        let wake_tick = crate::interrupt::read_counter() + ns.div_ceil(ns_per_tick) + 1;
+        manager.save_current(cpu, context);
+        manager.block_proc(&pid, BlockReason::Sleeping);
+        manager.add_sleeping(pid, wake_tick);
+        manager.switch_next(cpu, context);
+    });
+}
+
+/// Block the caller until the clock tick `deadline`, returns at once if it
+/// has passed, so a periodic loop advancing the deadline does not drift
+pub fn sleep_until(deadline: u64, context: &mut ProcessContext) {
+    context.set_rax(0);
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        if crate::interrupt::read_counter() >= deadline {
+            return;
+        }
+        let cpu = processor::cpu_id();
+        let manager = get_process_manager();
+        let pid = get_pid();
+        manager.save_current(cpu, context);
+        manager.block_proc(&pid, BlockReason::Sleeping);
+        manager.add_sleeping(pid, deadline);
+        manager.switch_next(cpu, context);
+    });
+}
+
+/// Wait the semaphore, giving up after `timeout_ms` if it is not 0
+pub fn sem_wait(key: u32, timeout_ms: u64, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
+        let manager = get_process_manager();
+        let pid = processor::get_pid();
+        let ret = manager.current().write().sem_wait(key, pid);
+        match ret {
+            SemaphoreResult::Ok => context.set_rax(0),
+            SemaphoreResult::NotExist => context.set_rax(1),
+            SemaphoreResult::Block(_pid) => {
+                // returns 0 once signaled, or `SEM_OWNER_DEAD` set by the exit
+                // of the holder of a robust semaphore, or `SEM_TIMED_OUT`
+                context.set_rax(0);
+                if timeout_ms != 0 {
+                    // the current tick is partly elapsed, wait for one more
+                    let ticks = (timeout_ms * crate::tsc::ticks_per_sec()).div_ceil(1000);
+                    let wake_tick = crate::interrupt::read_counter() + ticks + 1;
+                    let timer = manager.add_sem_timeout(pid, key, wake_tick);
+                    manager.current().read().sem_set_timeout(key, pid, timer);
+                }
+                // save, block it, then switch to next
+                manager.save_current(cpu, context);
+                manager.block_proc(&pid, BlockReason::Semaphore);
+                manager.switch_next(cpu, context);
+            }
+            _ => unreachable!(),
+        };
+    })
+}
+
+pub fn sem_signal(key: u32, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let pid = processor::get_pid();
+        let ret = manager.current().write().sem_signal(key, pid);
+        match ret {
+            SemaphoreResult::Ok => context.set_rax(0),
+            SemaphoreResult::NotExist => context.set_rax(1),
+            SemaphoreResult::WakeUp(pid) => {
+                manager.wake_up(pid);
+                context.set_rax(0);
+            }
+            _ => unreachable!(),
+        };
+    })
+}
+
+/// Wake up all the waiters of the semaphore, then set its count to `value`
+pub fn sem_signal_all(key: u32, value: usize, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let pid = processor::get_pid();
+        let ret = manager.current().write().sem_signal_all(key, pid, value);
+        match ret {
+            SemaphoreResult::NotExist => context.set_rax(1),
+            SemaphoreResult::WakeUpAll(waiters) => {
+                for waiter in waiters {
+                    manager.wake_up(waiter);
+                }
+                context.set_rax(0);
+            }
+            _ => unreachable!(),
+        };
+    })
+}
+
+pub fn new_sem(key: u32, value: usize, robust: bool) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let ret = manager.current().write().new_sem(key, value, robust);
+        if ret {
+            0
+        } else {
+            1
+        }
+    })
+}
+
+pub fn remove_sem(key: u32) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let ret = manager.current().write().remove_sem(key);
+        if ret {
+            0
+        } else {
+            1
+        }
+    })
+}
+
+pub fn new_barrier(key: u32, count: usize) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let ret = manager.current().write().new_barrier(key, count);
+        if ret {
+            0
+        } else {
+            1
+        }
+    })
+}
+
+pub fn remove_barrier(key: u32) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let manager = get_process_manager();
+        let ret = manager.current().write().remove_barrier(key);
+        if ret {
+            0
+        } else {
+            1
+        }
+    })
+}
+
+/// Arrive at the barrier, blocked until the last process arrives,
+/// which wakes up all the others
+pub fn barrier_wait(key: u32, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let cpu = processor::cpu_id();
+        let manager = get_process_manager();
+        let pid = processor::get_pid();
+        let ret = manager.current().write().barrier_wait(key, pid);
+        match ret {
+            SemaphoreResult::NotExist => context.set_rax(1),
+            SemaphoreResult::WakeUpAll(waiters) => {
+                for waiter in waiters {
+                    manager.wake_up(waiter);
+                }
+                context.set_rax(0);
+            }
+            SemaphoreResult::Block(_pid) => {
+                context.set_rax(0);
+                manager.save_current(cpu, context);
+                manager.block_proc(&pid, BlockReason::Semaphore);
+                manager.switch_next(cpu, context);
+            }
+            _ => unreachable!(),
+        };
+    })
+}
+
+pub fn msg_get(key: u32) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_message_queues().get_or_create(key);
+    })
+}
+
+pub fn msg_send(key: u32, msg: &[u8], nowait: bool, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let pid = processor::get_pid();
+        let ret = get_message_queues().send(key, msg, pid, nowait);
+        handle_msg_result(ret, context);
+    })
+}
+
+pub fn msg_recv(key: u32, buf: &mut [u8], context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let pid = processor::get_pid();
+        let ret = get_message_queues().recv(key, buf, pid);
+        handle_msg_result(ret, context);
+    })
+}
+
+fn handle_msg_result(ret: MsgResult, context: &mut ProcessContext) {
+    let manager = get_process_manager();
+    match ret {
+        MsgResult::Ok(len, wake) => {
+            if let Some(pid) = wake {
+                manager.wake_up(pid);
+            }
+            context.set_rax(len);
+        }
+        MsgResult::NotExist | MsgResult::TooLarge => context.set_rax(-1isize as usize),
+        MsgResult::Full => context.set_rax(-2isize as usize),
+        MsgResult::Block => {
+            // issue the syscall again once woken up
+            let cpu = processor::cpu_id();
+            let pid = processor::get_pid();
+            context.restart_syscall();
+            manager.save_current(cpu, context);
+            manager.block_proc(&pid, BlockReason::Message);
+            manager.switch_next(cpu, context);
+        }
+    }
+}
+
+/// Key a futex word by its physical address, it must be aligned and mapped
+fn futex_key(addr: VirtAddr) -> Option<u64> {
+    if !addr.is_aligned(core::mem::align_of::<AtomicU32>() as u64) {
+        return None;
+    }
+    get_process_manager().translate(addr).map(|addr| addr.as_u64())
+}
+
+/// Block the caller on the futex word at `addr` if it still holds `expected`,
+/// returns 0 once woken up, -1 for a bad address, -2 if the value differs
+pub fn futex_wait(addr: VirtAddr, expected: u32, context: &mut ProcessContext) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let key = match futex_key(addr) {
+            Some(key) => key,
+            None => return context.set_rax(-1isize as usize),
+        };
+        // nothing else runs to change the word with interrupts disabled
+        let word = unsafe { &*addr.as_ptr::<AtomicU32>() };
+        if word.load(Ordering::SeqCst) != expected {
+            return context.set_rax(-2isize as usize);
+        }
+
+        let cpu = processor::cpu_id();
+        let pid = processor::get_pid();
+        let manager = get_process_manager();
+        context.set_rax(0);
+        manager.save_current(cpu, context);
+        manager.block_proc(&pid, BlockReason::Futex);
+        get_futexes().wait(key, pid);
+        manager.switch_next(cpu, context);
+    })
+}
+
+/// Wake up at most `count` processes waiting on the futex word at `addr`,
+/// returns the number of them, or -1 for a bad address
+pub fn futex_wake(addr: VirtAddr, count: usize) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let key = match futex_key(addr) {
+            Some(key) => key,
+            None => return -1,
+        };
+        let manager = get_process_manager();
+        let mut futexes = get_futexes();
+        let mut woken = 0;
+        while woken < count {
+            let pid = match futexes.pop(key) {
+                Some(pid) => pid,
+                None => break,
+            };
+            // skip the waiters killed while blocked
+            if manager.is_blocked_by(&pid, BlockReason::Futex) {
+                manager.wake_up(pid);
+                woken += 1;
+            }
+        }
+        woken as isize
+    })
+}
+
+pub fn fchmod(fd: u8, mode: usize) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().fchmod(fd, mode))
+}
+
+pub fn open_file(path: &str, flags: OpenFlags) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().open_file(path, flags)
+    })
+}
+
+pub fn close_file(fd: u8) -> bool {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().close_file(fd))
+}
+
+pub fn dup2(old_fd: u8, new_fd: u8) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().dup2(old_fd, new_fd)
+    })
+}
+
+pub fn pipe() -> (u8, u8) {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().pipe())
+}
+
+pub fn tee_pipe(readers: usize) -> (u8, Vec<u8>) {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().tee_pipe(readers)
+    })
+}
+
+pub fn send_fd(channel_fd: u8, fd: u8) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().send_fd(channel_fd, fd)
+    })
+}
+
+pub fn recv_fd(channel_fd: u8) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().recv_fd(channel_fd))
+}
+
+pub fn symlink(target: &str, link: &str) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().symlink(target, link)
+    })
+}
+
+pub fn readlink(path: &str) -> Result<String, isize> {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().current().read().readlink(path)
+    })
+}
+
+pub fn watch_add(path: &str) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().watch_add(path))
+}
+
+pub fn watch_read(fd: u8, event: &mut WatchEvent) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().watch_read(fd, event)
+    })
+}
+
+pub fn eventfd(init: u64, semaphore: bool) -> u8 {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().eventfd(init, semaphore)
+    })
+}
+
+/// Read the memory of the descendant `pid` at `addr`, see `ProcessVm::peek`
+pub fn peek_mem(pid: ProcessId, addr: usize, buf: &mut [u8]) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().peek_mem(pid, addr, buf)
+    })
+}
+
+/// Write the memory of the descendant `pid` at `addr`, see `ProcessVm::poke`
+pub fn poke_mem(pid: ProcessId, addr: usize, buf: &[u8]) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        get_process_manager().poke_mem(pid, addr, buf)
+    })
+}
+
+pub fn capture_start() -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().capture_start())
+}
+
+pub fn capture_end(buf: &mut [u8]) -> isize {
+    x86_64::instructions::interrupts::without_interrupts(|| get_process_manager().capture_end(buf))
 }
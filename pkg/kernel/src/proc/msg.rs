@@ -0,0 +1,117 @@
+use super::ProcessId;
+use alloc::{collections::*, vec::Vec};
+use spin::Mutex;
+
+/// The maximum number of messages in a queue
+pub const MSG_QUEUE_CAPACITY: usize = 16;
+/// The maximum size of a single message in bytes
+pub const MSG_MAX_SIZE: usize = 256;
+
+/// All the message queues, shared by every process
+static MESSAGE_QUEUES: Mutex<MessageQueueSet> = Mutex::new(MessageQueueSet::new());
+
+pub fn get_message_queues() -> spin::MutexGuard<'static, MessageQueueSet> {
+    MESSAGE_QUEUES.lock()
+}
+
+/// Message queue result
+#[derive(Debug)]
+pub enum MsgResult {
+    /// Done with the message length, and maybe a process to wake up
+    Ok(usize, Option<ProcessId>),
+    /// The queue does not exist
+    NotExist,
+    /// The message is larger than the limit or the receive buffer
+    TooLarge,
+    /// The queue is full and the sender does not wait
+    Full,
+    /// The caller should be blocked until woken up
+    Block,
+}
+
+/// A bounded queue of messages, boundaries are preserved
+#[derive(Debug, Default)]
+pub struct MessageQueue {
+    messages: VecDeque<Vec<u8>>,
+    senders: VecDeque<ProcessId>,
+    receivers: VecDeque<ProcessId>,
+}
+
+impl MessageQueue {
+    /// Push a message to the queue
+    ///
+    /// if the queue is full, block the sender unless `nowait`,
+    /// else wake up a waiting receiver if any
+    pub fn send(&mut self, msg: &[u8], pid: ProcessId, nowait: bool) -> MsgResult {
+        if msg.len() > MSG_MAX_SIZE {
+            return MsgResult::TooLarge;
+        }
+
+        if self.messages.len() == MSG_QUEUE_CAPACITY {
+            if nowait {
+                return MsgResult::Full;
+            }
+            self.senders.push_back(pid);
+            return MsgResult::Block;
+        }
+
+        self.messages.push_back(msg.to_vec());
+        MsgResult::Ok(msg.len(), self.receivers.pop_front())
+    }
+
+    /// Pop exactly one message from the queue into `buf`
+    ///
+    /// if the queue is empty, block the receiver,
+    /// else wake up a waiting sender if any
+    pub fn recv(&mut self, buf: &mut [u8], pid: ProcessId) -> MsgResult {
+        let len = match self.messages.front() {
+            Some(msg) => msg.len(),
+            None => {
+                self.receivers.push_back(pid);
+                return MsgResult::Block;
+            }
+        };
+
+        // keep the message in the queue if it does not fit
+        if len > buf.len() {
+            return MsgResult::TooLarge;
+        }
+
+        let msg = self.messages.pop_front().unwrap();
+        buf[..len].copy_from_slice(&msg);
+        MsgResult::Ok(len, self.senders.pop_front())
+    }
+}
+
+#[derive(Debug)]
+pub struct MessageQueueSet {
+    queues: BTreeMap<u32, MessageQueue>,
+}
+
+impl MessageQueueSet {
+    pub const fn new() -> Self {
+        Self {
+            queues: BTreeMap::new(),
+        }
+    }
+
+    /// Get the queue by key, create it if not exist
+    pub fn get_or_create(&mut self, key: u32) {
+        trace!("MsgQueue Get: <{:#x}>", key);
+        self.queues.entry(key).or_default();
+    }
+
+    pub fn send(&mut self, key: u32, msg: &[u8], pid: ProcessId, nowait: bool) -> MsgResult {
+        match self.queues.get_mut(&key) {
+            Some(queue) => queue.send(msg, pid, nowait),
+            None => MsgResult::NotExist,
+        }
+    }
+
+    pub fn recv(&mut self, key: u32, buf: &mut [u8], pid: ProcessId) -> MsgResult {
+        match self.queues.get_mut(&key) {
+            Some(queue) => queue.recv(buf, pid),
+            None => MsgResult::NotExist,
+        }
+    }
+}
@@ -3,11 +3,19 @@ use core::ptr::copy_nonoverlapping;
 
 use alloc::sync::Arc;
 use x86_64::{
+    instructions::tlb,
     registers::control::{Cr3, Cr3Flags},
-    structures::paging::*,
+    structures::paging::{page_table::PageTableEntry, *},
     VirtAddr,
 };
 
+/// Marks a page made read-only by a copy-on-write fork,
+/// it becomes writable again on the first write
+pub const COW_FLAG: PageTableFlags = PageTableFlags::BIT_9;
+
+/// The number of P4 entries for the user space
+const USER_P4_ENTRIES: usize = 256;
+
 pub struct Cr3RegValue {
     pub addr: PhysFrame,
     pub flags: Cr3Flags,
@@ -81,6 +89,195 @@ impl PageTableContext {
             reg: self.reg.clone(),
         }
     }
+
+    /// Create a new page table for a copy-on-write fork.
+    ///
+    /// The user part is copied down to P1, the pages accepted by `keep` are
+    /// shared by both tables and the writable ones become read-only with
+    /// `COW_FLAG`, the other pages are not mapped in the new table.
+    pub fn fork_cow(&self, keep: &dyn Fn(VirtAddr) -> bool) -> Self {
+        let child = self.clone_l4();
+        let alloc = &mut *crate::memory::get_frame_alloc_for_sure();
+
+        let parent_p4 = table_of(self.reg.addr);
+        let child_p4 = table_of(child.reg.addr);
+        for index in 0..USER_P4_ENTRIES {
+            fork_entry(
+                &mut parent_p4[index],
+                &mut child_p4[index],
+                4,
+                (index as u64) << 39,
+                keep,
+                alloc,
+            );
+        }
+
+        // the parent is running, drop its writable mappings
+        tlb::flush_all();
+
+        child
+    }
+
+    /// Free all the user pages and tables created by `fork_cow`, and the P4.
+    ///
+    /// # Safety
+    ///
+    /// The table must not be used by any process after this.
+    pub unsafe fn free_cow(&self, dealloc: &mut BootInfoFrameAllocator) {
+        let p4 = table_of(self.reg.addr);
+        for index in 0..USER_P4_ENTRIES {
+            free_entry(&mut p4[index], 4, dealloc);
+        }
+        dealloc.deallocate_frame(self.reg.addr);
+    }
+}
+
+fn table_of(frame: PhysFrame) -> &'static mut PageTable {
+    let addr = physical_to_virtual(frame.start_address().as_u64());
+    unsafe { &mut *(addr as *mut PageTable) }
+}
+
+fn is_user_table(flags: PageTableFlags) -> bool {
+    flags.contains(PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE)
+        && !flags.contains(PageTableFlags::HUGE_PAGE)
+}
+
+/// Copy a user entry of `level` mapping `addr` into the child,
+/// the kernel entries are left shared
+fn fork_entry(
+    parent: &mut PageTableEntry,
+    child: &mut PageTableEntry,
+    level: u8,
+    addr: u64,
+    keep: &dyn Fn(VirtAddr) -> bool,
+    alloc: &mut BootInfoFrameAllocator,
+) {
+    let flags = parent.flags();
+    if level == 1 {
+        if !flags.contains(PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE) {
+            return;
+        }
+        if !keep(VirtAddr::new_truncate(addr)) {
+            child.set_unused();
+            return;
+        }
+        alloc.share_frame(PhysFrame::containing_address(parent.addr()));
+        if flags.contains(PageTableFlags::WRITABLE) {
+            let flags = (flags - PageTableFlags::WRITABLE) | COW_FLAG;
+            parent.set_flags(flags);
+            child.set_flags(flags);
+        }
+        return;
+    }
+
+    if !is_user_table(flags) {
+        return;
+    }
+
+    let frame = alloc
+        .allocate_frame()
+        .expect("Cannot alloc page table for cow fork.");
+    let parent_table = table_of(PhysFrame::containing_address(parent.addr()));
+    let child_table = table_of(frame);
+    unsafe {
+        copy_nonoverlapping::<PageTable>(parent_table, child_table, 1);
+    }
+    child.set_addr(frame.start_address(), flags);
+
+    let shift = 12 + 9 * (level as u64 - 2);
+    for index in 0..512 {
+        fork_entry(
+            &mut parent_table[index],
+            &mut child_table[index],
+            level - 1,
+            addr | (index as u64) << shift,
+            keep,
+            alloc,
+        );
+    }
+}
+
+/// Free a user entry of `level` together with the tables below it
+unsafe fn free_entry(entry: &mut PageTableEntry, level: u8, dealloc: &mut BootInfoFrameAllocator) {
+    let flags = entry.flags();
+    if level == 1 {
+        if flags.contains(PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE) {
+            dealloc.deallocate_frame(PhysFrame::containing_address(entry.addr()));
+        }
+        return;
+    }
+
+    if !is_user_table(flags) {
+        return;
+    }
+
+    let frame = PhysFrame::containing_address(entry.addr());
+    let table = table_of(frame);
+    for index in 0..512 {
+        free_entry(&mut table[index], level - 1, dealloc);
+    }
+    dealloc.deallocate_frame(frame);
+    entry.set_unused();
+}
+
+/// Resolve a write to a copy-on-write page of the current page table,
+/// the page is copied unless the faulting process is its last owner
+///
+/// returns false if the page is not a copy-on-write page
+pub fn handle_cow_fault(addr: VirtAddr) -> bool {
+    let mut mapper = PageTableContext::new().mapper();
+    let page = Page::<Size4KiB>::containing_address(addr);
+
+    let (frame, flags) = match mapper.translate(page.start_address()) {
+        mapper::TranslateResult::Mapped {
+            frame: mapper::MappedFrame::Size4KiB(frame),
+            flags,
+            ..
+        } => (frame, flags),
+        _ => return false,
+    };
+    if !flags.contains(COW_FLAG) {
+        return false;
+    }
+
+    let flags = (flags - COW_FLAG) | PageTableFlags::WRITABLE;
+    let alloc = &mut *crate::memory::get_frame_alloc_for_sure();
+
+    if alloc.ref_count(frame) == 1 {
+        return match unsafe { mapper.update_flags(page, flags) } {
+            Ok(flush) => {
+                flush.flush();
+                true
+            }
+            Err(_) => false,
+        };
+    }
+
+    let new_frame = match alloc.allocate_frame() {
+        Some(frame) => frame,
+        None => return false,
+    };
+    unsafe {
+        copy_nonoverlapping::<u8>(
+            physical_to_virtual(frame.start_address().as_u64()) as *const u8,
+            physical_to_virtual(new_frame.start_address().as_u64()) as *mut u8,
+            PAGE_SIZE as usize,
+        );
+    }
+
+    match mapper.unmap(page) {
+        Ok((_, flush)) => flush.flush(),
+        Err(_) => return false,
+    }
+    match unsafe { mapper.map_to(page, new_frame, flags, alloc) } {
+        Ok(flush) => flush.flush(),
+        Err(_) => return false,
+    }
+
+    // the parent or the siblings still own the old frame
+    unsafe { alloc.deallocate_frame(frame) };
+
+    true
 }
 
 impl core::fmt::Debug for PageTableContext {
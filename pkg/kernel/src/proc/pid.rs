@@ -1,15 +1,58 @@
-use core::sync::atomic::{AtomicU16, Ordering};
+use alloc::collections::VecDeque;
+use spin::Mutex;
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ProcessId(pub u16);
 
-static COUNTER: AtomicU16 = AtomicU16::new(0);
+/// The largest pid handed out, `u16::MAX` is left out as the user lib
+/// returns it for a failed fork, and 0 is never handed out
+pub const MAX_PID: u16 = u16::MAX - 1;
+
+static ALLOCATOR: Mutex<PidAllocator> = Mutex::new(PidAllocator::new());
+
+/// Hands out the pids never used first, then the freed ones, the earliest
+/// freed first, so a pid is reused as late as possible, and a `wait_pid`
+/// that races with the reaping hardly finds a new process under it
+struct PidAllocator {
+    // the next pid never used, over `MAX_PID` once they run out
+    next: u32,
+    free: VecDeque<u16>,
+}
+
+impl PidAllocator {
+    const fn new() -> Self {
+        Self {
+            next: 1,
+            free: VecDeque::new(),
+        }
+    }
+
+    fn alloc(&mut self) -> Option<u16> {
+        if self.next <= MAX_PID as u32 {
+            self.next += 1;
+            return Some((self.next - 1) as u16);
+        }
+        self.free.pop_front()
+    }
+
+    fn free(&mut self, pid: u16) {
+        self.free.push_back(pid);
+    }
+}
 
 impl ProcessId {
     pub fn new() -> Self {
-        COUNTER.fetch_add(1, Ordering::SeqCst);
-        trace!("New ProcessId: {}", COUNTER.load(Ordering::SeqCst));
-        ProcessId(COUNTER.load(Ordering::SeqCst))
+        let pid = ALLOCATOR.lock().alloc().expect("No free pid left");
+        trace!("New ProcessId: {}", pid);
+        ProcessId(pid)
+    }
+
+    /// Give the pid of a process gone from the table back for reuse,
+    /// the kernel process is never freed
+    pub fn free(self) {
+        if self != super::KERNEL_PID {
+            ALLOCATOR.lock().free(self.0);
+        }
     }
 }
 
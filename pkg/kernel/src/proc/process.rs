@@ -1,16 +1,47 @@
+use super::audit::{self, AuditLog};
+use super::checkpoint::{Checkpoint, CHECKPOINT_MAX_PAGES};
+use super::forkrate::ForkRate;
+use super::limits::Limits;
+use super::quota::Quota;
+use super::signal::{ChildSignal, HandlerCall, PendingSignals};
 use super::ProcessId;
 use super::*;
 use crate::humanized_size;
 use crate::memory::*;
 use crate::proc::paging::PageTableContext;
+use alloc::collections::BTreeMap;
+use alloc::format;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::sync::Weak;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use spin::*;
 use vm::*;
+use x86_64::structures::idt::PageFaultErrorCode;
 use x86_64::VirtAddr;
 
+/// The number of processes exited, gives the order of the exits
+static EXIT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+bitflags! {
+    /// The parts of the caller shared with the child of `Syscall::Clone`,
+    /// the others are copied, values are the same as `syscall_def::CLONE_*`
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct CloneFlags: usize {
+        /// The memory, else a private copy-on-write copy
+        const VM = syscall_def::CLONE_VM;
+        /// The fd table, else a copy of it
+        const FILES = syscall_def::CLONE_FILES;
+        /// The `SigChld` handler, else the child has none
+        const SIGHAND = syscall_def::CLONE_SIGHAND;
+        /// The semaphores, else a copy of their counts
+        const SYSVSEM = syscall_def::CLONE_SYSVSEM;
+        /// The environment, else a copy of it
+        const ENV = syscall_def::CLONE_ENV;
+    }
+}
+
 #[derive(Clone)]
 pub struct Process {
     pid: ProcessId,
@@ -22,9 +53,61 @@ pub struct ProcessInner {
     parent: Option<Weak<Process>>,
     children: Vec<Arc<Process>>,
     ticks_passed: usize,
+    // the ticks before the last reset of the stats, still counted by the budget
+    ticks_reset: usize,
+    // the times switched to from another process
+    switches: usize,
+    // the times switched away from, as it gave up the CPU or was preempted,
+    // by the way it was last saved, see `ProcessManager::save_preempted`
+    voluntary_switches: usize,
+    involuntary_switches: usize,
+    preempted: bool,
+    // the ticks waited in the ready queue before being picked
+    latency: SchedLatency,
+    // the clock counter when the process is created
+    created_at: u64,
+    // the times passed over by the scheduler while ready since the last run,
+    // the effective priority is raised by one level for each
+    age: usize,
+    page_faults: usize,
+    stack_faults: usize,
+    // the peak memory usage over the lifetime, kept after the exit
+    peak_memory: u64,
+    // where the resource usage of the child waited by `wait4` goes
+    rusage_out: Option<(VirtAddr, RUsage)>,
     status: ProgramStatus,
+    block_reason: Option<BlockReason>,
+    // the last blocks & wake-ups, not inherited by forked children
+    audit: AuditLog,
     exit_code: Option<isize>,
+    exit_order: usize,
+    reaped: bool,
+    // handed over to the kernel by `Spawn`, exits with no one to wait for it
+    detached: bool,
     context: ProcessContext,
+    child_signal: ChildSignal,
+    // the signals for the handlers of `Sigaction`, not inherited by forked children
+    pending_signals: PendingSignals,
+    // the context interrupted by the running handler, of any signal
+    handler_saved: Option<ProcessContext>,
+    // the exit codes of the collectable children, `None` until they exit
+    exit_mailbox: BTreeMap<ProcessId, Option<isize>>,
+    // the soft CPU quota, not inherited by forked children
+    quota: Option<Quota>,
+    // the ticks of the last forks, not inherited by forked children
+    fork_rate: ForkRate,
+    // the timer & the deadline of the alarm, not inherited by forked children
+    alarm: Option<(u64, u64)>,
+    // the timer ending the sleep, cancelled once a signal cuts it short
+    sleep_timer: Option<u64>,
+    // the spin hints since the process is scheduled in
+    spin_hints: usize,
+    // the leader of the threads sharing the memory, `None` for the leader
+    thread_group: Option<ProcessId>,
+    // the state saved by `Syscall::Checkpoint`, not inherited
+    checkpoint: Option<Checkpoint>,
+    // the parent blocked by `Syscall::Vfork` until self execs or exits
+    vfork_parent: Option<ProcessId>,
     proc_data: Option<ProcessData>,
     proc_vm: Option<ProcessVm>,
 }
@@ -56,17 +139,53 @@ impl Process {
         // create context
         let pid = ProcessId::new();
         let proc_vm = proc_vm.unwrap_or_else(|| ProcessVm::new(PageTableContext::new()));
+        let mut proc_data = proc_data.unwrap_or_default();
+        if proc_data.pgid.0 == 0 {
+            proc_data.pgid = pid;
+        }
+        if proc_data.sid.0 == 0 {
+            proc_data.sid = pid;
+        }
 
         let inner = ProcessInner {
             name,
             parent,
             status: ProgramStatus::Ready,
+            block_reason: None,
+            audit: AuditLog::default(),
             context: ProcessContext::default(),
+            child_signal: ChildSignal::default(),
+            pending_signals: PendingSignals::default(),
+            handler_saved: None,
+            exit_mailbox: BTreeMap::new(),
+            quota: None,
+            fork_rate: ForkRate::default(),
+            alarm: None,
+            sleep_timer: None,
+            spin_hints: 0,
+            checkpoint: None,
+            vfork_parent: None,
+            thread_group: None,
             ticks_passed: 0,
+            ticks_reset: 0,
+            switches: 0,
+            voluntary_switches: 0,
+            involuntary_switches: 0,
+            preempted: false,
+            latency: SchedLatency::default(),
+            created_at: crate::interrupt::read_counter(),
+            age: 0,
+            page_faults: 0,
+            stack_faults: 0,
+            peak_memory: 0,
+            rusage_out: None,
             exit_code: None,
+            exit_order: 0,
+            reaped: false,
+            detached: false,
             children: Vec::new(),
             proc_vm: Some(proc_vm),
-            proc_data: Some(proc_data.unwrap_or_default()),
+            proc_data: Some(proc_data),
         };
 
         trace!("New process {}#{} created.", &inner.name, pid);
@@ -78,8 +197,12 @@ impl Process {
         })
     }
 
-    pub fn kill(&self, ret: isize) {
+    /// Kill the process, returns false if it is already dead
+    pub fn kill(&self, ret: isize) -> bool {
         let mut inner = self.inner.write();
+        if inner.status() == ProgramStatus::Dead {
+            return false;
+        }
 
         debug!(
             "Killing process {}#{} with ret code: {}",
@@ -89,6 +212,14 @@ impl Process {
         );
 
         inner.kill(ret);
+        let (count, bytes) = crate::memory::user::user_alloc_disown(self.pid);
+        if count > 0 {
+            info!(
+                "Process #{} leaks {} allocations of the user heap, {} bytes.",
+                self.pid, count, bytes
+            );
+        }
+        true
     }
 
     pub fn alloc_init_stack(&self) -> VirtAddr {
@@ -96,12 +227,12 @@ impl Process {
         self.write().alloc_init_stack(self.pid.0)
     }
 
-    pub fn fork(self: &Arc<Self>) -> Arc<Self> {
+    pub fn fork(self: &Arc<Self>, cow: bool) -> Arc<Self> {
         // lock inner as write
         let mut inner = self.write();
         // inner fork with parent weak ref
         let child_pid = ProcessId::new();
-        let child_inner = inner.fork(Arc::downgrade(self));
+        let child_inner = inner.fork(Arc::downgrade(self), cow);
         // print the child process info
         trace!(
             "Parent {} forked: {}#{}",
@@ -116,6 +247,7 @@ impl Process {
         });
         // add child to current process's children list
         inner.children.push(child_proc.clone());
+        inner.child_count += 1;
         // set fork ret value for parent with `context.set_rax`
         inner.context.set_rax(child_pid.0 as usize);
         // mark the child as ready & return it
@@ -123,6 +255,74 @@ impl Process {
 
         child_proc
     }
+
+    /// Create a vfork child, see `ProcessInner::vfork`
+    pub fn vfork(self: &Arc<Self>) -> Arc<Self> {
+        let mut inner = self.write();
+        let child_pid = ProcessId::new();
+        let child_inner = inner.vfork(Arc::downgrade(self), self.pid);
+        trace!("Parent {} vforked: {}#{}", inner.name, child_pid, child_inner.name);
+        let child_proc = Arc::new(Self {
+            pid: child_pid,
+            inner: Arc::new(RwLock::new(child_inner)),
+        });
+        inner.children.push(child_proc.clone());
+        inner.child_count += 1;
+        // the parent returns the pid of the child once resumed
+        inner.context.set_rax(child_pid.0 as usize);
+        child_proc.write().pause();
+
+        child_proc
+    }
+
+    /// Create a thread as a child, see `ProcessInner::thread`
+    pub fn thread(
+        self: &Arc<Self>,
+        context: &ProcessContext,
+        entry: VirtAddr,
+        args: [usize; 2],
+    ) -> Arc<Self> {
+        let mut inner = self.write();
+        let mut child_inner = inner.thread(Arc::downgrade(self), context, entry, args);
+        // the threads of a thread join the same group
+        child_inner.thread_group = Some(inner.thread_group.unwrap_or(self.pid));
+        let child_proc = Arc::new(Self {
+            pid: ProcessId::new(),
+            inner: Arc::new(RwLock::new(child_inner)),
+        });
+        trace!("Thread {}#{} created.", inner.name, child_proc.pid);
+        inner.children.push(child_proc.clone());
+        inner.child_count += 1;
+        child_proc.write().pause();
+
+        child_proc
+    }
+
+    /// Create a child by `Syscall::Clone`, see `ProcessInner::clone_child`
+    pub fn clone_child(
+        self: &Arc<Self>,
+        context: &ProcessContext,
+        flags: CloneFlags,
+        entry: VirtAddr,
+        args: [usize; 2],
+    ) -> Arc<Self> {
+        let mut inner = self.write();
+        let mut child_inner = inner.clone_child(Arc::downgrade(self), context, flags, entry, args);
+        // the children sharing the memory join the group as threads
+        if flags.contains(CloneFlags::VM) {
+            child_inner.thread_group = Some(inner.thread_group.unwrap_or(self.pid));
+        }
+        let child_proc = Arc::new(Self {
+            pid: ProcessId::new(),
+            inner: Arc::new(RwLock::new(child_inner)),
+        });
+        trace!("Clone {}#{} created: {:?}", inner.name, child_proc.pid, flags);
+        inner.children.push(child_proc.clone());
+        inner.child_count += 1;
+        child_proc.write().pause();
+
+        child_proc
+    }
 }
 
 impl ProcessInner {
@@ -130,40 +330,217 @@ impl ProcessInner {
         &self.name
     }
 
+    pub fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    /// The leader of the threads sharing the memory, `None` for a leader
+    pub fn thread_group(&self) -> Option<ProcessId> {
+        self.thread_group
+    }
+
+    /// The clock counter when the process is created
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// The clock ticks since the process is created
+    pub fn proc_age(&self) -> u64 {
+        crate::interrupt::read_counter() - self.created_at
+    }
+
     pub fn tick(&mut self) {
         self.ticks_passed += 1;
     }
 
+    pub fn count_switch(&mut self) {
+        self.switches += 1;
+    }
+
+    pub fn set_preempted(&mut self, preempted: bool) {
+        self.preempted = preempted;
+    }
+
+    /// Count a switch away from the process, see `voluntary_switches`
+    pub fn count_leave(&mut self) {
+        if self.preempted {
+            self.involuntary_switches += 1;
+        } else {
+            self.voluntary_switches += 1;
+        }
+    }
+
+    /// Count a pick from the ready queue after waiting `ticks` in it
+    pub fn record_latency(&mut self, ticks: u64) {
+        let ticks = ticks as usize;
+        self.latency.picks += 1;
+        self.latency.total += ticks;
+        self.latency.max = self.latency.max.max(ticks);
+    }
+
+    pub fn sched_latency(&self) -> SchedLatency {
+        self.latency
+    }
+
+    /// Zero the ticks, page faults & switches, returns the values before,
+    /// the tick budget & the resource usage still count the lifetime
+    pub fn reset_stats(&mut self) -> Stats {
+        let stats = Stats {
+            ticks: self.ticks_passed,
+            page_faults: self.page_faults,
+            stack_faults: self.stack_faults,
+            switches: self.switches,
+        };
+        self.ticks_reset += self.ticks_passed;
+        self.ticks_passed = 0;
+        self.page_faults = 0;
+        self.stack_faults = 0;
+        self.switches = 0;
+        stats
+    }
+
     pub fn status(&self) -> ProgramStatus {
         self.status
     }
 
+    /// Why the process is blocked, `None` if it is not blocked
+    pub fn block_reason(&self) -> Option<BlockReason> {
+        self.block_reason
+    }
+
+    /// Describe the status with the block reason, e.g. `Blocked(Sleeping)`
+    pub fn status_desc(&self) -> String {
+        match self.block_reason {
+            Some(reason) => format!("{:?}({:?})", self.status, reason),
+            None => format!("{:?}", self.status),
+        }
+    }
+
+    /// The last blocks & wake-ups of the process
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    /// Mark the process as ready, a blocked or stopped one is logged as woken
     pub fn pause(&mut self) {
+        match (self.status, self.block_reason) {
+            (ProgramStatus::Blocked, Some(reason)) => self.audit.woken(audit::wake_source(reason)),
+            (ProgramStatus::Stopped, _) => self.audit.woken(WakeSource::Signal),
+            _ => {}
+        }
         self.status = ProgramStatus::Ready;
+        self.block_reason = None;
     }
 
     pub fn resume(&mut self) {
         self.status = ProgramStatus::Running;
+        self.block_reason = None;
     }
 
-    pub fn block(&mut self) {
+    pub fn stop(&mut self) {
+        self.status = ProgramStatus::Stopped;
+        self.block_reason = None;
+        self.audit.stopped();
+    }
+
+    pub fn block(&mut self, reason: BlockReason) {
         self.status = ProgramStatus::Blocked;
+        self.block_reason = Some(reason);
+        self.audit.blocked(reason);
     }
 
     pub fn exit_code(&self) -> Option<isize> {
         self.exit_code
     }
 
+    /// Processes exited earlier have smaller orders
+    pub fn exit_order(&self) -> usize {
+        self.exit_order
+    }
+
+    pub fn is_reaped(&self) -> bool {
+        self.reaped
+    }
+
+    pub fn set_reaped(&mut self) {
+        self.reaped = true;
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached
+    }
+
+    /// Move under `parent` for good, leading a session of `pid` of its own,
+    /// so the exit code is dropped and the caller can no longer reach it
+    pub fn detach(&mut self, parent: Weak<Process>, pid: ProcessId) {
+        self.parent = Some(parent);
+        self.detached = true;
+        self.set_sid(pid);
+    }
+
     pub fn vm(&self) -> &ProcessVm {
         self.proc_vm.as_ref().unwrap()
     }
 
+    pub fn is_on_stack(&self, addr: VirtAddr) -> bool {
+        self.proc_vm
+            .as_ref()
+            .is_some_and(|vm| vm.stack.is_on_stack(addr))
+    }
+
     pub fn vm_mut(&mut self) -> &mut ProcessVm {
         self.proc_vm.as_mut().unwrap()
     }
 
-    pub fn handle_page_fault(&mut self, addr: VirtAddr) -> bool {
-        self.vm_mut().handle_page_fault(addr)
+    pub fn handle_page_fault(&mut self, addr: VirtAddr, err_code: PageFaultErrorCode) -> bool {
+        self.page_faults += 1;
+        if err_code.contains(PageFaultErrorCode::PROTECTION_VIOLATION) {
+            return err_code.contains(PageFaultErrorCode::CAUSED_BY_WRITE)
+                && paging::handle_cow_fault(addr);
+        }
+
+        let limit = self.mem_limit();
+        if self.vm_mut().handle_page_fault(addr, limit) {
+            self.stack_faults += 1;
+            self.update_peak_memory();
+            return true;
+        }
+        let mapped = self.vm_mut().handle_heap_fault(addr)
+            || self.vm_mut().handle_mmap_fault(addr, limit);
+        self.update_peak_memory();
+        mapped
+    }
+
+    fn update_peak_memory(&mut self) {
+        if let Some(vm) = self.proc_vm.as_ref() {
+            self.peak_memory = self.peak_memory.max(vm.memory_usage());
+        }
+    }
+
+    pub fn rusage(&self) -> RUsage {
+        RUsage {
+            ticks: self.ticks_passed + self.ticks_reset,
+            peak_memory: self.peak_memory as usize,
+            voluntary_switches: self.voluntary_switches,
+            involuntary_switches: self.involuntary_switches,
+        }
+    }
+
+    /// Put the resource usage of the waited child at `addr`
+    /// when the process is restored
+    pub fn set_rusage_out(&mut self, addr: VirtAddr) {
+        self.rusage_out = Some((addr, RUsage::default()));
+    }
+
+    pub fn fill_rusage(&mut self, usage: RUsage) {
+        if let Some((_, out)) = self.rusage_out.as_mut() {
+            *out = usage;
+        }
+    }
+
+    /// The number of page faults and the stack growth faults among them
+    pub fn page_faults(&self) -> (usize, usize) {
+        (self.page_faults, self.stack_faults)
     }
 
     pub fn clone_page_table(&self) -> PageTableContext {
@@ -178,6 +555,10 @@ impl ProcessInner {
         self.proc_data.as_ref().unwrap().env(key)
     }
 
+    pub fn set_env(&mut self, key: &str, val: &str) {
+        self.proc_data.as_mut().unwrap().set_env(key, val)
+    }
+
     pub fn context(&mut self) -> &mut ProcessContext {
         &mut self.context
     }
@@ -197,9 +578,131 @@ impl ProcessInner {
     pub(super) fn restore(&mut self, context: &mut ProcessContext) {
         // restore the process's context
         self.resume();
+        self.age = 0;
+        self.spin_hints = 0;
         self.context.restore(context);
+        self.deliver_signals(context);
         // restore the process's page table
         self.vm().page_table.load();
+        // the waiter of `wait4` sees the usage in its own address space
+        if let Some((addr, usage)) = self.rusage_out.take() {
+            unsafe { *addr.as_mut_ptr::<RUsage>() = usage };
+        }
+    }
+
+    pub fn child_signal(&mut self) -> &mut ChildSignal {
+        &mut self.child_signal
+    }
+
+    /// Enter from `context` the handler of the next signal not masked, the child
+    /// exits first, then the lowest pending signal, unless a handler is running
+    pub fn deliver_signals(&mut self, context: &mut ProcessContext) {
+        if self.handler_saved.is_some() {
+            return;
+        }
+        let mask = self.sigmask();
+        let call = match mask & sig_bit(SIGCHLD) {
+            0 => self.child_signal.next(),
+            _ => None,
+        };
+        if let Some((entry, args)) = call.or_else(|| self.next_sigaction(mask)) {
+            self.handler_saved = Some(*context);
+            context.enter_handler(entry, args);
+        }
+    }
+
+    /// Take the lowest pending signal not in `mask` with its handler,
+    /// which goes back to the default on `SA_RESETHAND`
+    fn next_sigaction(&mut self, mask: u64) -> Option<HandlerCall> {
+        let sig = self.pending_signals.take(mask)?;
+        let actions = &mut self.proc_data.as_mut()?.sigactions;
+        let action = match actions.get(&sig) {
+            Some(action) if action.flags & SA_RESETHAND != 0 => actions.remove(&sig)?,
+            Some(action) => *action,
+            None => return None,
+        };
+        Some((VirtAddr::new(action.entry as u64), [action.data, sig]))
+    }
+
+    /// Return from the running handler to the context it interrupted,
+    /// returns false if no handler is running
+    pub fn sig_return(&mut self, context: &mut ProcessContext) -> bool {
+        match self.handler_saved.take() {
+            Some(saved) => {
+                saved.restore(context);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a signal not masked waits for its handler
+    pub fn signal_pending(&self) -> bool {
+        let mask = self.sigmask();
+        (mask & sig_bit(SIGCHLD) == 0 && self.child_signal.has_pending())
+            || self.pending_signals.any_unmasked(mask)
+    }
+
+    /// The handler of `sig`, `None` for the default action
+    pub fn sigaction(&self, sig: usize) -> Option<SigAction> {
+        self.proc_data.as_ref()?.sigactions.get(&sig).copied()
+    }
+
+    /// Set the handler of `sig`, the default action once the entry is 0,
+    /// returns the one replaced
+    pub fn set_sigaction(&mut self, sig: usize, action: SigAction) -> SigAction {
+        let actions = &mut self.proc_data.as_mut().unwrap().sigactions;
+        let old = match action.entry {
+            0 => actions.remove(&sig),
+            _ => actions.insert(sig, action),
+        };
+        old.unwrap_or_default()
+    }
+
+    /// Hold `sig` for its handler, delivered once not masked
+    pub fn send_signal(&mut self, sig: usize) {
+        self.pending_signals.add(sig);
+    }
+
+    pub fn sigmask(&self) -> u64 {
+        self.proc_data.as_ref().map_or(0, |data| data.sigmask)
+    }
+
+    /// Change the mask by `how`, e.g. `SIG_BLOCK`, `SIGKILL` & `SIGSTOP`
+    /// are never masked, returns the old mask, `None` if `how` is unknown
+    pub fn set_sigmask(&mut self, how: usize, set: u64) -> Option<u64> {
+        let data = self.proc_data.as_mut()?;
+        let old = data.sigmask;
+        let mask = match how {
+            SIG_BLOCK => old | set,
+            SIG_UNBLOCK => old & !set,
+            SIG_SETMASK => set,
+            _ => return None,
+        };
+        data.sigmask = mask & !(sig_bit(SIGKILL) | sig_bit(SIGSTOP));
+        Some(old)
+    }
+
+    /// Keep the exit code of the child `pid` until it is collected
+    pub fn watch_exit(&mut self, pid: ProcessId) {
+        self.exit_mailbox.insert(pid, None);
+    }
+
+    /// Put the exit code of the child in the mailbox if it is watched
+    pub fn post_exit(&mut self, pid: ProcessId, ret: isize) {
+        if let Some(slot) = self.exit_mailbox.get_mut(&pid) {
+            *slot = Some(ret);
+        }
+    }
+
+    /// Take the exit code of a watched child, `Some(None)` if it is running,
+    /// `None` if it is not watched or already collected
+    pub fn collect(&mut self, pid: ProcessId) -> Option<Option<isize>> {
+        let slot = *self.exit_mailbox.get(&pid)?;
+        if slot.is_some() {
+            self.exit_mailbox.remove(&pid);
+        }
+        Some(slot)
     }
 
     pub fn init_stack_frame(&mut self, entry: VirtAddr, stack_top: VirtAddr) {
@@ -210,36 +713,267 @@ impl ProcessInner {
         self.parent.as_ref().and_then(|p| p.upgrade())
     }
 
+    pub fn children(&self) -> &[Arc<Process>] {
+        &self.children
+    }
+
     pub fn add_child(&mut self, child: Arc<Process>) {
         self.children.push(child);
     }
 
+    pub fn pgid(&self) -> Option<ProcessId> {
+        self.proc_data.as_ref().map(|data| data.pgid)
+    }
+
+    pub fn set_pgid(&mut self, pgid: ProcessId) {
+        if let Some(data) = self.proc_data.as_mut() {
+            data.pgid = pgid;
+        }
+    }
+
+    pub fn sid(&self) -> Option<ProcessId> {
+        self.proc_data.as_ref().map(|data| data.sid)
+    }
+
+    /// Lead a new session and a new process group, both of the id `pid`
+    pub fn set_sid(&mut self, pid: ProcessId) {
+        if let Some(data) = self.proc_data.as_mut() {
+            data.sid = pid;
+            data.pgid = pid;
+        }
+    }
+
+    pub fn tick_budget(&self) -> usize {
+        self.limit(RLIMIT_CPU) as usize
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.proc_data.as_ref().map(|data| data.limits).unwrap_or_default()
+    }
+
+    /// The soft limit of the resource, `RLIM_INFINITY` for no limit
+    pub fn limit(&self, resource: usize) -> u64 {
+        self.limits().soft(resource)
+    }
+
+    /// Replace the limit of the resource, see `Limits::set`
+    pub fn set_limit(&mut self, resource: usize, limit: Limit) -> Option<Limit> {
+        self.proc_data.as_mut()?.limits.set(resource, limit)
+    }
+
+    pub fn priority(&self) -> usize {
+        self.proc_data
+            .as_ref()
+            .map_or(DEFAULT_PRIORITY, |data| data.priority)
+    }
+
+    /// The priority used by the scheduler, raised from the base one
+    /// by aging, i.e. a starved process runs eventually
+    pub fn effective_priority(&self) -> usize {
+        self.priority().saturating_sub(self.age)
+    }
+
+    pub fn set_priority(&mut self, priority: usize) {
+        self.age = 0;
+        if let Some(data) = self.proc_data.as_mut() {
+            data.priority = priority;
+        }
+    }
+
+    pub fn affinity(&self) -> u64 {
+        self.proc_data.as_ref().map_or(ALL_CPUS, |data| data.affinity)
+    }
+
+    pub fn set_affinity(&mut self, mask: u64) {
+        if let Some(data) = self.proc_data.as_mut() {
+            data.affinity = mask;
+        }
+    }
+
+    /// Passed over by the scheduler while ready
+    pub(super) fn grow_age(&mut self) {
+        self.age += 1;
+    }
+
+    pub fn privileged(&self) -> bool {
+        self.proc_data.as_ref().is_some_and(|data| data.privileged)
+    }
+
+    /// Give up the privilege, there is no way to get it back
+    pub fn drop_privilege(&mut self) {
+        if let Some(data) = self.proc_data.as_mut() {
+            data.privileged = false;
+        }
+    }
+
+    pub fn spawn_depth(&self) -> usize {
+        self.proc_data.as_ref().map_or(0, |data| data.spawn_depth)
+    }
+
+    pub fn mem_limit(&self) -> u64 {
+        self.limit(RLIMIT_AS)
+    }
+
+    pub fn set_mem_limit(&mut self, bytes: u64) {
+        if let Some(data) = self.proc_data.as_mut() {
+            data.limits.set_soft(RLIMIT_AS, bytes);
+        }
+    }
+
+    pub fn set_tick_budget(&mut self, ticks: usize) {
+        if let Some(data) = self.proc_data.as_mut() {
+            data.limits.set_soft(RLIMIT_CPU, ticks as u64);
+        }
+    }
+
+    /// Set the quota of `rate` ticks per second, `None` to remove it
+    pub fn set_quota(&mut self, quota: Option<(u64, u64)>, now: u64) {
+        self.quota = quota.map(|(rate, hz)| Quota::new(rate, hz, now));
+    }
+
+    pub fn take_alarm(&mut self) -> Option<(u64, u64)> {
+        self.alarm.take()
+    }
+
+    pub fn set_alarm(&mut self, alarm: Option<(u64, u64)>) {
+        self.alarm = alarm;
+    }
+
+    pub fn take_sleep_timer(&mut self) -> Option<u64> {
+        self.sleep_timer.take()
+    }
+
+    pub fn set_sleep_timer(&mut self, timer: Option<u64>) {
+        self.sleep_timer = timer;
+    }
+
+    /// Count a spin hint, true once there are more than `SPIN_HINT_LIMIT`
+    /// in this time slice
+    pub fn spin_hint(&mut self) -> bool {
+        self.spin_hints += 1;
+        self.spin_hints > SPIN_HINT_LIMIT
+    }
+
+    pub fn fork_rate(&self) -> &ForkRate {
+        &self.fork_rate
+    }
+
+    pub fn fork_rate_mut(&mut self) -> &mut ForkRate {
+        &mut self.fork_rate
+    }
+
+    /// Charge a tick to the quota if any
+    pub(super) fn charge_quota(&mut self, now: u64) {
+        if let Some(quota) = self.quota.as_mut() {
+            quota.charge(now);
+        }
+    }
+
+    /// Ready and not throttled by the quota
+    /// Whether the process is ready, not throttled and allowed on the `cpu`
+    pub fn is_runnable(&self, now: u64, cpu: usize) -> bool {
+        self.is_ready()
+            && !self.quota.is_some_and(|quota| quota.is_throttled(now))
+            && self.affinity() & (1 << cpu) != 0
+    }
+
+    /// Whether the process has run for more ticks than its budget
+    pub fn is_over_budget(&self) -> bool {
+        let budget = self.tick_budget();
+        budget != 0 && self.ticks_passed + self.ticks_reset > budget
+    }
+
+    pub fn child_count(&self) -> usize {
+        self.proc_data.as_ref().map_or(0, |data| data.child_count)
+    }
+
+    pub fn inc_child_count(&mut self) {
+        if let Some(data) = self.proc_data.as_mut() {
+            data.child_count += 1;
+        }
+    }
+
+    /// Called when a child's exit code has been collected
+    pub fn dec_child_count(&mut self) {
+        if let Some(data) = self.proc_data.as_mut() {
+            data.child_count = data.child_count.saturating_sub(1);
+        }
+    }
+
     pub fn sem_wait(&mut self, key: u32, pid: ProcessId) -> SemaphoreResult {
         self.proc_data.as_mut().unwrap().sem_wait(key, pid)
     }
 
-    pub fn sem_signal(&mut self, key: u32) -> SemaphoreResult {
-        self.proc_data.as_mut().unwrap().sem_signal(key)
+    pub fn sem_signal(&mut self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        self.proc_data.as_mut().unwrap().sem_signal(key, pid)
+    }
+
+    pub fn sem_signal_all(&mut self, key: u32, pid: ProcessId, value: usize) -> SemaphoreResult {
+        self.proc_data.as_mut().unwrap().sem_signal_all(key, pid, value)
+    }
+
+    pub fn sem_set_timeout(&self, key: u32, pid: ProcessId, timer: u64) -> SemaphoreResult {
+        self.proc_data.as_ref().unwrap().sem_set_timeout(key, pid, timer)
     }
 
-    pub fn new_sem(&mut self, key: u32, value: usize) -> bool {
-        self.proc_data.as_mut().unwrap().new_sem(key, value)
+    /// The process may be dead, then there is nothing to time out
+    pub fn sem_timeout(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        match self.proc_data.as_ref() {
+            Some(data) => data.sem_timeout(key, pid),
+            None => SemaphoreResult::NotExist,
+        }
+    }
+
+    pub fn new_sem(&mut self, key: u32, value: usize, robust: bool) -> bool {
+        self.proc_data.as_mut().unwrap().new_sem(key, value, robust)
+    }
+
+    pub fn release_semaphores(&self, pid: ProcessId) -> Vec<ProcessId> {
+        self.proc_data
+            .as_ref()
+            .map(|data| data.release_semaphores(pid))
+            .unwrap_or_default()
     }
 
     pub fn remove_sem(&mut self, key: u32) -> bool {
         self.proc_data.as_mut().unwrap().remove_sem(key)
     }
 
+    pub fn new_barrier(&mut self, key: u32, count: usize) -> bool {
+        self.proc_data.as_mut().unwrap().new_barrier(key, count)
+    }
+
+    pub fn remove_barrier(&mut self, key: u32) -> bool {
+        self.proc_data.as_mut().unwrap().remove_barrier(key)
+    }
+
+    pub fn barrier_wait(&mut self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        self.proc_data.as_mut().unwrap().barrier_wait(key, pid)
+    }
+
     pub fn kill(&mut self, ret: isize) {
         // set exit code
         self.exit_code = Some(ret);
+        self.exit_order = EXIT_COUNT.fetch_add(1, Ordering::Relaxed);
         // set status to dead
         self.status = ProgramStatus::Dead;
 
+        self.update_peak_memory();
+        if let Some((timer, _)) = self.alarm.take() {
+            crate::interrupt::cancel_timer(timer);
+        }
         // take and drop unused resources
         // recycle process stack
         self.proc_vm.take();
-        self.proc_data.take();
+        // close all the fds, so the other ends of the pipes see EOF
+        if let Some(data) = self.proc_data.take() {
+            data.close_all();
+        }
+    }
+
+    /// Whether the memory is not released yet, i.e. not killed
+    pub fn has_vm(&self) -> bool {
+        self.proc_vm.is_some()
     }
 
     pub fn alloc_init_stack(&mut self, pid: u16) -> VirtAddr {
@@ -249,33 +983,136 @@ impl ProcessInner {
         trace!("stack_bottom: {:x}", stack_bottom);
         self.vm_mut()
             .stack
-            .init(&mut page_table, frame_allocator, ProcessId(pid));
+            .init(&mut page_table, frame_allocator, ProcessId(pid), StackSize::default());
         VirtAddr::new(stack_bottom + STACK_DEF_SIZE - 8)
     }
 
     pub fn load_elf(&mut self, elf: &ElfFile, pid: ProcessId) -> VirtAddr {
-        self.vm_mut().load_elf(elf, pid)
+        let data = self.proc_data.as_mut().unwrap();
+        data.canary = crate::random::random_u64();
+        let stack_size = data.stack_size;
+        self.vm_mut().load_elf(elf, pid, stack_size)
+    }
+
+    /// Load `elf` into a new page table and start over from its entry,
+    /// the old memory is freed, the fds marked close-on-exec are closed
+    pub fn exec(
+        &mut self,
+        elf: &ElfFile,
+        name: String,
+        page_table: PageTableContext,
+        pid: ProcessId,
+    ) {
+        let mut proc_vm = ProcessVm::new(page_table);
+        let data = self.proc_data.as_mut().unwrap();
+        data.canary = crate::random::random_u64();
+        let stack_size = data.stack_size;
+        let stack_top = proc_vm.load_elf(elf, pid, stack_size);
+
+        // leave the old page table before it is freed
+        proc_vm.page_table.load();
+        self.proc_vm = Some(proc_vm);
+
+        self.name = name.to_ascii_lowercase();
+        self.proc_data.as_ref().unwrap().close_on_exec();
+
+        let entry = VirtAddr::new(elf.header.pt2.entry_point());
+        self.context = ProcessContext::default();
+        self.context.init_stack_frame(entry, stack_top);
+        // the handlers are gone with the old image
+        self.child_signal = ChildSignal::default();
+        self.pending_signals.clear();
+        self.handler_saved = None;
+        self.proc_data.as_mut().unwrap().sigactions.clear();
+        self.checkpoint = None;
+    }
+
+    /// Save the registers, the memory & the fds, replacing the previous
+    /// checkpoint, fails if the memory is over `CHECKPOINT_MAX_PAGES`
+    ///
+    /// `context` is the syscall frame of the current process, which
+    /// returns 1 for `Syscall::Checkpoint` once restored
+    pub fn checkpoint(&mut self, context: Option<&ProcessContext>) -> bool {
+        let context = match context {
+            Some(context) => {
+                let mut context = *context;
+                context.set_rax(1);
+                context
+            }
+            None => self.context,
+        };
+        let pages = match self.vm().save_pages(CHECKPOINT_MAX_PAGES) {
+            Some(pages) => pages,
+            None => return false,
+        };
+
+        self.checkpoint = Some(Checkpoint {
+            context,
+            pages,
+            heap_end: self.vm().heap_end(),
+            fds: self.proc_data.as_ref().unwrap().snapshot_fds(),
+        });
+        true
+    }
+
+    /// Reset the process to its checkpoint, which is kept for later restores,
+    /// fails without a change if the heap end has moved since or a saved page
+    /// is not writable, the registers go to `context` for the current process
+    pub fn restore_checkpoint(&mut self, context: Option<&mut ProcessContext>) -> bool {
+        let checkpoint = match &self.checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => return false,
+        };
+        let vm = self.proc_vm.as_ref().unwrap();
+        if vm.heap_end() != checkpoint.heap_end || !vm.restore_pages(&checkpoint.pages) {
+            return false;
+        }
+
+        self.proc_data.as_ref().unwrap().restore_fds(&checkpoint.fds);
+        match context {
+            Some(context) => checkpoint.context.restore(context),
+            None => self.context = checkpoint.context,
+        }
+        true
     }
 
     pub fn print_info(&self) {
         println!("Process: {}", self.name);
         println!("Ticks: {}", self.ticks_passed);
+        println!("Status: {}", self.status_desc());
         let (size, unit) =
             crate::humanized_size(self.proc_data.as_ref().unwrap().code_segment_pages * PAGE_SIZE);
         println!("Code Segment Memory Usage: {:>7.*} {}", 3, size, unit);
         let (size, unit) = crate::humanized_size(self.vm().stack.usage() * PAGE_SIZE);
         println!("Prcoess Memory Usage: {:>7.*} {}", 3, size, unit);
+        println!(
+            "Page Faults: {} (stack growth: {})",
+            self.page_faults, self.stack_faults
+        );
+        println!(
+            "Sched Latency: {} ticks on average, {} at most ({} picks)",
+            self.latency.average(),
+            self.latency.max,
+            self.latency.picks
+        );
+        println!("Wake Log:");
+        for entry in self.audit.iter() {
+            println!("  {:>10}: {}", entry.tick, audit::describe(entry));
+        }
     }
 
-    pub fn fork(&mut self, parent: Weak<Process>) -> ProcessInner {
+    pub fn fork(&mut self, parent: Weak<Process>, cow: bool) -> ProcessInner {
         // calculate the real stack offset
         let child_stack_offset = (self.children.len() as u64 + 1) * STACK_MAX_PAGES;
 
         // FIXME: fork the process virtual memory struct
-        let proc_vm = self.proc_vm.as_ref().unwrap().fork(child_stack_offset);
-
-        // clone the process data struct
-        let child_proc_data = self.proc_data.as_ref().unwrap().clone();
+        // a copy-on-write child keeps the stack at the same address
+        let vm = self.proc_vm.as_ref().unwrap();
+        let proc_vm = if cow {
+            vm.fork_cow()
+        } else {
+            vm.fork(child_stack_offset)
+        };
 
         // update child's stack frame
         let mut child_context = self.context;
@@ -286,30 +1123,170 @@ impl ProcessInner {
         // set the return value 0 for child with `context.set_rax`
         child_context.set_rax(0);
 
+        self.child_inner(parent, proc_vm, child_context)
+    }
+
+    /// Create a child sharing the memory and running on the stack of self,
+    /// from the saved registers, self is blocked until the child execs or
+    /// exits, see `end_vfork`
+    pub fn vfork(&mut self, parent: Weak<Process>, parent_pid: ProcessId) -> ProcessInner {
+        let proc_vm = self.vm().vfork();
+        let mut child_context = self.context;
+        child_context.set_rax(0);
+
+        let mut child = self.child_inner(parent, proc_vm, child_context);
+        child.vfork_parent = Some(parent_pid);
+        child
+    }
+
+    /// Stop lending the stack to the vfork parent, returns the parent
+    /// and its stack, with the pages grown by self, `None` if not vforked
+    pub fn end_vfork(&mut self) -> Option<(ProcessId, Stack)> {
+        let parent = self.vfork_parent.take()?;
+        Some((parent, self.vm_mut().take_stack()))
+    }
+
+    /// Take back the stack lent to a vfork child, which is freed already
+    /// if self has been killed meanwhile
+    pub fn return_stack(&mut self, stack: Stack) {
+        if let Some(vm) = self.proc_vm.as_mut() {
+            vm.stack = stack;
+        }
+    }
+
+    /// Create a thread sharing the memory, it calls `entry` with `args`
+    /// on a fresh stack, from the registers in `context`
+    pub fn thread(
+        &mut self,
+        parent: Weak<Process>,
+        context: &ProcessContext,
+        entry: VirtAddr,
+        args: [usize; 2],
+    ) -> ProcessInner {
+        let child_stack_offset = (self.children.len() as u64 + 1) * STACK_MAX_PAGES;
+        let proc_vm = self.vm().thread(child_stack_offset);
+
+        let mut child_context = *context;
+        let stack_top = proc_vm.stack.stack_min_addr() + proc_vm.stack.memory_usage();
+        child_context.update_stack_frame(stack_top);
+        child_context.enter_handler(entry, args);
+
+        self.child_inner(parent, proc_vm, child_context)
+    }
+
+    /// Create a child calling `entry` with `args`, from the registers in
+    /// `context`, the parts in `flags` are shared and the others copied
+    ///
+    /// the child runs on a fresh stack if the memory is shared,
+    /// else on its copy of the stack of the caller
+    pub fn clone_child(
+        &mut self,
+        parent: Weak<Process>,
+        context: &ProcessContext,
+        flags: CloneFlags,
+        entry: VirtAddr,
+        args: [usize; 2],
+    ) -> ProcessInner {
+        let mut child_context = *context;
+        let proc_vm = if flags.contains(CloneFlags::VM) {
+            let child_stack_offset = (self.children.len() as u64 + 1) * STACK_MAX_PAGES;
+            let proc_vm = self.vm().thread(child_stack_offset);
+            let stack_top = proc_vm.stack.stack_min_addr() + proc_vm.stack.memory_usage();
+            child_context.update_stack_frame(stack_top);
+            proc_vm
+        } else {
+            self.vm().fork_cow()
+        };
+        child_context.enter_handler(entry, args);
+
+        let mut child = self.child_inner(parent, proc_vm, child_context);
+        let data = self.proc_data.as_ref().unwrap();
+        let child_data = child.proc_data.as_mut().unwrap();
+        if flags.contains(CloneFlags::FILES) {
+            child_data.resources = data.resources.clone();
+        }
+        if flags.contains(CloneFlags::ENV) {
+            child_data.env = data.env.clone();
+        }
+        if !flags.contains(CloneFlags::SYSVSEM) {
+            child_data.semaphores = Arc::new(RwLock::new(data.semaphores.read().copy()));
+        }
+        if flags.contains(CloneFlags::SIGHAND) {
+            child.child_signal.set_handler(self.child_signal.handler());
+        }
+        child
+    }
+
+    fn child_inner(
+        &self,
+        parent: Weak<Process>,
+        proc_vm: ProcessVm,
+        context: ProcessContext,
+    ) -> ProcessInner {
+        // fork the process data struct with a copy of the fd table
+        let mut child_proc_data = self.proc_data.as_ref().unwrap().fork();
+        child_proc_data.child_count = 0;
+
         // construct the child process inner
         ProcessInner {
             name: self.name.clone(),
             parent: Some(parent),
             children: Vec::new(),
             ticks_passed: 0,
+            ticks_reset: 0,
+            switches: 0,
+            voluntary_switches: 0,
+            involuntary_switches: 0,
+            preempted: false,
+            latency: SchedLatency::default(),
+            created_at: crate::interrupt::read_counter(),
+            age: 0,
+            page_faults: 0,
+            stack_faults: 0,
+            peak_memory: 0,
+            rusage_out: None,
             status: ProgramStatus::Ready,
+            block_reason: None,
+            audit: AuditLog::default(),
             exit_code: None,
-            context: child_context,
+            exit_order: 0,
+            reaped: false,
+            detached: false,
+            context,
+            // the handler of the parent is not inherited
+            child_signal: ChildSignal::default(),
+            pending_signals: PendingSignals::default(),
+            handler_saved: None,
+            exit_mailbox: BTreeMap::new(),
+            quota: None,
+            fork_rate: ForkRate::default(),
+            alarm: None,
+            sleep_timer: None,
+            spin_hints: 0,
+            checkpoint: None,
+            vfork_parent: None,
+            thread_group: None,
             proc_vm: Some(proc_vm),
             proc_data: Some(child_proc_data),
         }
     }
 
-    pub fn open_file(&mut self, path: &str) -> u8 {
-        self.proc_data.as_mut().unwrap().open_file(path)
+    pub fn open_file(&mut self, path: &str, flags: OpenFlags) -> isize {
+        self.proc_data.as_mut().unwrap().open_file(path, flags)
     }
 
     pub fn close_file(&mut self, fd: u8) -> bool {
         self.proc_data.as_mut().unwrap().close_file(fd)
     }
 
-    pub fn brk(&self, addr: Option<VirtAddr>) -> Option<VirtAddr> {
-        self.proc_vm.as_ref().unwrap().brk(addr)
+    pub fn brk(&mut self, addr: Option<VirtAddr>) -> Option<VirtAddr> {
+        let ret = self.proc_vm.as_ref().unwrap().brk(addr, self.mem_limit());
+        self.update_peak_memory();
+        ret
+    }
+
+    pub fn heap_avail(&self) -> u64 {
+        self.vm().heap_avail(self.mem_limit())
     }
 }
 
@@ -349,7 +1326,7 @@ impl core::fmt::Debug for Process {
             .field("status", &inner.status)
             .field("ticks_passed", &inner.ticks_passed)
             .field("children", &inner.children.iter().map(|c| c.pid.0))
-            .field("status", &inner.status)
+            .field("block_reason", &inner.block_reason)
             .field("context", &inner.context)
             .field("vm", &inner.proc_vm)
             .finish()
@@ -362,14 +1339,15 @@ impl core::fmt::Display for Process {
         let (size, unit) = humanized_size(inner.proc_vm.as_ref().map_or(0, |vm| vm.memory_usage()));
         write!(
             f,
-            " #{:-3} | #{:-3} | {:12} | {:7} | {:>5.1} {} | {:?}",
+            " #{:-3} | #{:-3} | {:12} | {:7} | {:7} | {:>5.1} {} | {:?}",
             self.pid.0,
             inner.parent().map(|p| p.pid.0).unwrap_or(0),
             inner.name,
             inner.ticks_passed,
+            inner.proc_age(),
             size,
             unit,
-            inner.status
+            inner.status_desc()
         )?;
         Ok(())
     }
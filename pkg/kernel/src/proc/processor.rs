@@ -1,24 +1,41 @@
 use core::sync::atomic::{AtomicU16, Ordering};
 
-use crate::proc::ProcessId;
-use alloc::{string::String, vec::Vec};
+use crate::proc::{Process, ProcessId};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use spin::Mutex;
 use x86::cpuid::CpuId;
 
-const MAX_CPU_COUNT: usize = 4;
+pub const MAX_CPU_COUNT: usize = 4;
 
 #[allow(clippy::declare_interior_mutable_const)]
 const EMPTY: Processor = Processor::new(); // means no process
 
 static PROCESSORS: [Processor; MAX_CPU_COUNT] = [EMPTY; MAX_CPU_COUNT];
 
-/// Returns the current processor based on the current APIC ID
-fn current() -> &'static Processor {
-    let cpuid = CpuId::new()
+/// Returns the id of the current processor, i.e. its initial APIC ID
+#[inline]
+pub fn cpu_id() -> usize {
+    CpuId::new()
         .get_feature_info()
         .unwrap()
-        .initial_local_apic_id() as usize;
+        .initial_local_apic_id() as usize
+}
+
+/// The CPUs running the scheduler, bit n for CPU n,
+/// only the boot processor is brought up
+pub fn online_mask() -> u64 {
+    1 << cpu_id()
+}
 
-    &PROCESSORS[cpuid]
+/// Issue a full memory barrier on every online CPU, the others would run
+/// `mfence` on an IPI, but only the boot processor is brought up
+pub fn membarrier() {
+    unsafe { core::arch::asm!("mfence", options(nostack, preserves_flags)) };
+}
+
+/// Returns the current processor based on the current APIC ID
+fn current() -> &'static Processor {
+    &PROCESSORS[cpu_id()]
 }
 
 pub fn print_processors() -> String {
@@ -34,18 +51,25 @@ pub fn print_processors() -> String {
     )
 }
 
-/// Processor holds the current process id
-pub struct Processor(AtomicU16);
+/// Processor holds the current process id, and the process itself,
+/// so the syscalls find it without a lookup of the process table
+pub struct Processor {
+    pid: AtomicU16,
+    proc: Mutex<Option<Arc<Process>>>,
+}
 
 impl Processor {
     pub const fn new() -> Self {
-        Self(AtomicU16::new(0))
+        Self {
+            pid: AtomicU16::new(0),
+            proc: Mutex::new(None),
+        }
     }
 }
 
 #[inline]
-pub fn set_pid(pid: ProcessId) {
-    current().set_pid(pid)
+pub fn set_proc(proc: Arc<Process>) {
+    current().set_proc(proc)
 }
 
 #[inline]
@@ -53,20 +77,40 @@ pub fn get_pid() -> ProcessId {
     current().get_pid().expect("No current process")
 }
 
+/// Set the running process of the given processor, on every switch
+#[inline]
+pub fn set_proc_on(cpu: usize, proc: Arc<Process>) {
+    PROCESSORS[cpu].set_proc(proc)
+}
+
+/// Get the running process of the given processor, kept since the last switch
+#[inline]
+pub fn get_proc_on(cpu: usize) -> Option<Arc<Process>> {
+    PROCESSORS[cpu].proc.lock().clone()
+}
+
+/// Get the running process of the given processor
+#[inline]
+pub fn get_pid_on(cpu: usize) -> Option<ProcessId> {
+    PROCESSORS[cpu].get_pid()
+}
+
 impl Processor {
     #[inline]
     pub fn is_free(&self) -> bool {
-        self.0.load(Ordering::Relaxed) == 0
+        self.pid.load(Ordering::Relaxed) == 0
     }
 
     #[inline]
-    pub fn set_pid(&self, pid: ProcessId) {
-        self.0.store(pid.0, Ordering::Relaxed);
+    pub fn set_proc(&self, proc: Arc<Process>) {
+        self.pid.store(proc.pid().0, Ordering::Relaxed);
+        // the last process is dropped after the lock is released
+        let _last = self.proc.lock().replace(proc);
     }
 
     #[inline]
     pub fn get_pid(&self) -> Option<ProcessId> {
-        let pid = self.0.load(Ordering::Relaxed);
+        let pid = self.pid.load(Ordering::Relaxed);
         if pid == 0 {
             None
         } else {
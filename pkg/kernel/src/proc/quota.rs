@@ -0,0 +1,44 @@
+/// A soft CPU quota, a bucket of ticks refilled over the clock
+///
+/// the credit is counted in 1/hz of a tick, so a refill of `rate`
+/// ticks per second adds `rate` for each clock tick passed
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    // the ticks allowed to run per second
+    rate: u64,
+    // the clock ticks per second
+    hz: u64,
+    credit: u64,
+    last_refill: u64,
+}
+
+impl Quota {
+    /// Start with the credit of a single tick, no burst at first
+    pub fn new(rate: u64, hz: u64, now: u64) -> Self {
+        Self {
+            rate,
+            hz,
+            credit: hz,
+            last_refill: now,
+        }
+    }
+
+    /// The credit refilled until `now`, up to a second of running
+    fn credit_at(&self, now: u64) -> u64 {
+        let refill = now.saturating_sub(self.last_refill).saturating_mul(self.rate);
+        self.credit
+            .saturating_add(refill)
+            .min(self.rate.saturating_mul(self.hz))
+    }
+
+    /// Charge a tick of running
+    pub fn charge(&mut self, now: u64) {
+        self.credit = self.credit_at(now).saturating_sub(self.hz);
+        self.last_refill = now;
+    }
+
+    /// Whether the credit cannot pay for another tick yet
+    pub fn is_throttled(&self, now: u64) -> bool {
+        self.credit_at(now) < self.hz
+    }
+}
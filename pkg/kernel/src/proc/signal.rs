@@ -0,0 +1,99 @@
+use alloc::collections::VecDeque;
+use syscall_def::{sig_bit, NSIG, SIGCHLD, SIGKILL, SIGSTOP};
+use x86_64::VirtAddr;
+
+use super::ProcessId;
+
+/// The entry of a handler in the user space & the arguments passed to it
+pub type HandlerCall = (VirtAddr, [usize; 2]);
+
+/// The notification of child exits, like `SIGCHLD`
+///
+/// a registered handler runs in the user space with the pid of each
+/// exited child, one at a time, and returns by `SigReturn`
+#[derive(Debug, Default)]
+pub struct ChildSignal {
+    // the entry in the user space & the data passed to it
+    handler: Option<(VirtAddr, usize)>,
+    // the exited children not delivered to the handler yet
+    pending: VecDeque<ProcessId>,
+    // set on any child exit until taken, with or without a handler
+    exited: bool,
+}
+
+impl ChildSignal {
+    pub fn handler(&self) -> Option<(VirtAddr, usize)> {
+        self.handler
+    }
+
+    /// Set the handler or remove it with `None`,
+    /// the exits not delivered yet are dropped on removal
+    pub fn set_handler(&mut self, handler: Option<(VirtAddr, usize)>) {
+        if handler.is_none() {
+            self.pending.clear();
+        }
+        self.handler = handler;
+    }
+
+    pub fn notify(&mut self, pid: ProcessId) {
+        self.exited = true;
+        if self.handler.is_some() {
+            self.pending.push_back(pid);
+        }
+    }
+
+    /// Whether any exit waits to be delivered to the handler
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Whether any child has exited since the last call
+    pub fn take_exited(&mut self) -> bool {
+        core::mem::take(&mut self.exited)
+    }
+
+    /// Take the next pending exit, with the handler to call for it
+    pub fn next(&mut self) -> Option<HandlerCall> {
+        let (entry, data) = self.handler?;
+        let pid = self.pending.pop_front()?;
+        Some((entry, [data, pid.0 as usize]))
+    }
+}
+
+/// Whether `Sigaction` may set a handler for `sig`, `SIGKILL` & `SIGSTOP`
+/// always take effect, and `SIGCHLD` has `SigChld` passing the pids
+pub fn is_catchable(sig: usize) -> bool {
+    (1..=NSIG).contains(&sig) && ![SIGKILL, SIGSTOP, SIGCHLD].contains(&sig)
+}
+
+/// The signals sent to the handlers of `Sigaction` and not delivered yet,
+/// one bit each, so a signal sent again meanwhile is delivered once
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PendingSignals(u64);
+
+impl PendingSignals {
+    pub fn add(&mut self, sig: usize) {
+        self.0 |= sig_bit(sig);
+    }
+
+    /// Whether any signal not in `mask` is pending
+    pub fn any_unmasked(&self, mask: u64) -> bool {
+        self.0 & !mask != 0
+    }
+
+    /// Take the lowest pending signal not in `mask`
+    pub fn take(&mut self, mask: u64) -> Option<usize> {
+        let unmasked = self.0 & !mask;
+        if unmasked == 0 {
+            return None;
+        }
+        let sig = unmasked.trailing_zeros() as usize + 1;
+        self.0 &= !sig_bit(sig);
+        Some(sig)
+    }
+
+    pub fn clear(&mut self) {
+        self.0 = 0;
+    }
+}
+
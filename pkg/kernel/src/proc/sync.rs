@@ -1,7 +1,12 @@
 use super::ProcessId;
-use alloc::collections::*;
+use alloc::{collections::*, vec::Vec};
 use spin::Mutex;
 
+/// Returned by a wait woken up as the holder of the robust semaphore exited
+pub const SEM_OWNER_DEAD: isize = 2;
+/// Returned by a wait not signaled before its timeout
+pub const SEM_TIMED_OUT: isize = 3;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct SemaphoreId(u32);
 
@@ -16,6 +21,13 @@ impl SemaphoreId {
 pub struct Semaphore {
     count: usize,
     wait_queue: VecDeque<ProcessId>,
+    /// Used as a lock, the units taken by each process are tracked
+    /// and given back if it exits without signaling
+    robust: bool,
+    holders: BTreeMap<ProcessId, usize>,
+    /// The timers of the waiters with a timeout, cancelled once they are
+    /// out of the wait queue
+    timers: BTreeMap<ProcessId, u64>,
 }
 
 /// Semaphore result
@@ -25,14 +37,40 @@ pub enum SemaphoreResult {
     NotExist,
     Block(ProcessId),
     WakeUp(ProcessId),
+    WakeUpAll(Vec<ProcessId>),
+    TimedOut(ProcessId),
 }
 
 impl Semaphore {
     /// Create a new semaphore
-    pub fn new(value: usize) -> Self {
+    pub fn new(value: usize, robust: bool) -> Self {
         Self {
             count: value,
             wait_queue: VecDeque::new(),
+            robust,
+            holders: BTreeMap::new(),
+            timers: BTreeMap::new(),
+        }
+    }
+
+    fn cancel_timeout(&mut self, pid: ProcessId) {
+        if let Some(timer) = self.timers.remove(&pid) {
+            crate::interrupt::cancel_timer(timer);
+        }
+    }
+
+    fn hold(&mut self, pid: ProcessId) {
+        if self.robust {
+            *self.holders.entry(pid).or_default() += 1;
+        }
+    }
+
+    fn unhold(&mut self, pid: ProcessId) {
+        if let Some(units) = self.holders.get_mut(&pid) {
+            *units -= 1;
+            if *units == 0 {
+                self.holders.remove(&pid);
+            }
         }
     }
 
@@ -49,6 +87,7 @@ impl Semaphore {
             SemaphoreResult::Block(pid)
         } else {
             self.count -= 1;
+            self.hold(pid);
             SemaphoreResult::Ok
         }
     }
@@ -57,43 +96,154 @@ impl Semaphore {
     ///
     /// if the wait queue is not empty, then pop a process from the wait queue
     /// else increase the count
-    pub fn signal(&mut self) -> SemaphoreResult {
+    pub fn signal(&mut self, pid: ProcessId) -> SemaphoreResult {
         // if the wait queue is not empty pop a process
         //      from the wait queue return WakeUp(pid)
         // else increase the count and return Ok
+        self.unhold(pid);
 
-        if let Some(pid) = self.wait_queue.pop_front() {
-            SemaphoreResult::WakeUp(pid)
+        if let Some(waiter) = self.wait_queue.pop_front() {
+            // the unit is handed over to the waiter
+            self.hold(waiter);
+            self.cancel_timeout(waiter);
+            SemaphoreResult::WakeUp(waiter)
         } else {
             self.count += 1;
             SemaphoreResult::Ok
         }
     }
+
+    /// Signal every waiting process at once, e.g. to release a barrier,
+    /// then set the count to `value`
+    pub fn signal_all(&mut self, pid: ProcessId, value: usize) -> SemaphoreResult {
+        self.unhold(pid);
+
+        let waiters: Vec<ProcessId> = self.wait_queue.drain(..).collect();
+        for waiter in waiters.iter() {
+            self.hold(*waiter);
+            self.cancel_timeout(*waiter);
+        }
+        self.count = value;
+        SemaphoreResult::WakeUpAll(waiters)
+    }
+
+    /// Forget the exited `pid`, it is taken out of the wait queue
+    ///
+    /// if it still holds units of a robust semaphore, they are given back
+    /// and the waiters are returned, to be woken up with an error
+    pub fn release(&mut self, pid: ProcessId) -> Vec<ProcessId> {
+        self.wait_queue.retain(|waiter| *waiter != pid);
+        self.cancel_timeout(pid);
+        match self.holders.remove(&pid) {
+            Some(units) => {
+                self.count += units;
+                let waiters: Vec<ProcessId> = self.wait_queue.drain(..).collect();
+                for waiter in waiters.iter() {
+                    self.cancel_timeout(*waiter);
+                }
+                waiters
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Record `timer` as the timeout of the waiting `pid`
+    pub fn set_timeout(&mut self, pid: ProcessId, timer: u64) {
+        self.timers.insert(pid, timer);
+    }
+
+    /// The timeout of `pid` fires, it is taken out of the wait queue
+    /// unless signaled in the meantime
+    pub fn timeout(&mut self, pid: ProcessId) -> SemaphoreResult {
+        if self.timers.remove(&pid).is_none() {
+            return SemaphoreResult::Ok;
+        }
+        self.wait_queue.retain(|waiter| *waiter != pid);
+        SemaphoreResult::TimedOut(pid)
+    }
+}
+
+/// A reusable barrier, the waiters are released together once
+/// the expected number of processes have arrived
+#[derive(Debug, Clone)]
+pub struct Barrier {
+    count: usize,
+    waiters: Vec<ProcessId>,
+}
+
+impl Barrier {
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            waiters: Vec::new(),
+        }
+    }
+
+    /// Arrive at the barrier
+    ///
+    /// the last arrival wakes up all the others and the barrier is reset,
+    /// else the process is blocked
+    pub fn wait(&mut self, pid: ProcessId) -> SemaphoreResult {
+        if self.waiters.len() + 1 < self.count {
+            self.waiters.push(pid);
+            SemaphoreResult::Block(pid)
+        } else {
+            SemaphoreResult::WakeUpAll(core::mem::take(&mut self.waiters))
+        }
+    }
+
+    /// Forget the exited `pid`, if it was waiting, one less process
+    /// is expected from now on, or the barrier would never be released
+    pub fn release(&mut self, pid: ProcessId) {
+        let len = self.waiters.len();
+        self.waiters.retain(|waiter| *waiter != pid);
+        if self.waiters.len() < len {
+            self.count -= 1;
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct SemaphoreSet {
     sems: BTreeMap<SemaphoreId, Mutex<Semaphore>>,
+    barriers: BTreeMap<u32, Mutex<Barrier>>,
 }
 
 impl SemaphoreSet {
     pub fn new() -> Self {
         Self {
             sems: BTreeMap::new(),
+            barriers: BTreeMap::new(),
         }
     }
 
-    pub fn insert(&mut self, key: u32, value: usize) -> bool {
-        trace!("Sem Insert: <{:#x}>{}", key, value);
+    pub fn insert(&mut self, key: u32, value: usize, robust: bool) -> bool {
+        trace!("Sem Insert: <{:#x}>{} robust: {}", key, value, robust);
 
         // FIXME: insert a new semaphore into the sems
         //          use `insert(/* ... */).is_none()`
         let sid = SemaphoreId::new(key);
         self.sems
-            .insert(sid, Mutex::new(Semaphore::new(value)))
+            .insert(sid, Mutex::new(Semaphore::new(value, robust)))
             .is_none()
     }
 
+    /// A private set with the same semaphores & barriers, the counts are kept
+    /// while the waiters & holders of the original are not
+    pub fn copy(&self) -> Self {
+        let sems = self.sems.iter().map(|(sid, sem)| {
+            let sem = sem.lock();
+            (*sid, Mutex::new(Semaphore::new(sem.count, sem.robust)))
+        });
+        let barriers = self.barriers.iter().map(|(key, barrier)| {
+            (*key, Mutex::new(Barrier::new(barrier.lock().count)))
+        });
+        Self {
+            sems: sems.collect(),
+            barriers: barriers.collect(),
+        }
+    }
+
     pub fn remove(&mut self, key: u32) -> bool {
         trace!("Sem Remove: <{:#x}>", key);
 
@@ -118,7 +268,7 @@ impl SemaphoreSet {
     }
 
     /// Signal the semaphore (release/up/verhogen)
-    pub fn signal(&self, key: u32) -> SemaphoreResult {
+    pub fn signal(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
         let sid = SemaphoreId::new(key);
 
         // FIXME: try get the semaphore from the sems
@@ -126,11 +276,86 @@ impl SemaphoreSet {
         // FIXME: return NotExist if the semaphore is not exist
         if let Some(sem) = self.sems.get(&sid) {
             let mut sem = sem.lock();
-            sem.signal()
+            sem.signal(pid)
+        } else {
+            SemaphoreResult::NotExist
+        }
+    }
+
+    pub fn signal_all(&self, key: u32, pid: ProcessId, value: usize) -> SemaphoreResult {
+        let sid = SemaphoreId::new(key);
+        if let Some(sem) = self.sems.get(&sid) {
+            sem.lock().signal_all(pid, value)
         } else {
             SemaphoreResult::NotExist
         }
     }
+
+    pub fn set_timeout(&self, key: u32, pid: ProcessId, timer: u64) -> SemaphoreResult {
+        let sid = SemaphoreId::new(key);
+        if let Some(sem) = self.sems.get(&sid) {
+            sem.lock().set_timeout(pid, timer);
+            SemaphoreResult::Ok
+        } else {
+            SemaphoreResult::NotExist
+        }
+    }
+
+    pub fn timeout(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        let sid = SemaphoreId::new(key);
+        if let Some(sem) = self.sems.get(&sid) {
+            sem.lock().timeout(pid)
+        } else {
+            SemaphoreResult::NotExist
+        }
+    }
+
+    /// Create a barrier for `count` processes, at least one
+    pub fn insert_barrier(&mut self, key: u32, count: usize) -> bool {
+        trace!("Barrier Insert: <{:#x}>{}", key, count);
+        if count == 0 || self.barriers.contains_key(&key) {
+            return false;
+        }
+        self.barriers.insert(key, Mutex::new(Barrier::new(count)));
+        true
+    }
+
+    pub fn remove_barrier(&mut self, key: u32) -> bool {
+        trace!("Barrier Remove: <{:#x}>", key);
+        self.barriers.remove(&key).is_some()
+    }
+
+    pub fn barrier_wait(&self, key: u32, pid: ProcessId) -> SemaphoreResult {
+        match self.barriers.get(&key) {
+            Some(barrier) => barrier.lock().wait(pid),
+            None => SemaphoreResult::NotExist,
+        }
+    }
+
+    /// Release every semaphore & barrier for the exited `pid`,
+    /// returns the waiters whose semaphore was abandoned by it
+    pub fn release(&self, pid: ProcessId) -> Vec<ProcessId> {
+        for barrier in self.barriers.values() {
+            barrier.lock().release(pid);
+        }
+        self.sems
+            .values()
+            .flat_map(|sem| sem.lock().release(pid))
+            .collect()
+    }
+}
+
+impl SemaphoreSet {
+    /// The key, the count and the waiting processes of each semaphore
+    pub fn snapshot(&self) -> Vec<(u32, usize, Vec<ProcessId>)> {
+        self.sems
+            .iter()
+            .map(|(sid, sem)| {
+                let sem = sem.lock();
+                (sid.0, sem.count, sem.wait_queue.iter().copied().collect())
+            })
+            .collect()
+    }
 }
 
 impl core::fmt::Display for Semaphore {
@@ -0,0 +1,66 @@
+use super::*;
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::RwLock;
+
+/// The number of lookups by pid, to see the cost of the syscall paths
+static LOOKUPS: AtomicU64 = AtomicU64::new(0);
+
+#[inline]
+pub fn table_lookups() -> u64 {
+    LOOKUPS.load(Ordering::Relaxed)
+}
+
+/// The number of shards of the process table
+const TABLE_SHARDS: usize = 8;
+
+type Shard = RwLock<BTreeMap<ProcessId, Arc<Process>>>;
+
+/// The process table, sharded by `pid % TABLE_SHARDS`
+///
+/// `spin::RwLock` prefers readers, with a single lock the lookups of the
+/// scheduler on every switch could keep an insert spinning, while with
+/// the shards an insert only contends with the lookups of its own shard
+///
+/// NOTE: the manager runs with interrupts disabled on a single cpu,
+/// so the shards are not contended until more cpus are brought up
+pub struct ProcessTable {
+    shards: [Shard; TABLE_SHARDS],
+}
+
+impl ProcessTable {
+    pub fn new() -> Self {
+        Self {
+            shards: core::array::from_fn(|_| RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    #[inline]
+    fn shard(&self, pid: &ProcessId) -> &Shard {
+        &self.shards[pid.0 as usize % TABLE_SHARDS]
+    }
+
+    pub fn insert(&self, pid: ProcessId, proc: Arc<Process>) {
+        self.shard(&pid).write().insert(pid, proc);
+    }
+
+    pub fn remove(&self, pid: &ProcessId) -> Option<Arc<Process>> {
+        self.shard(pid).write().remove(pid)
+    }
+
+    pub fn get(&self, pid: &ProcessId) -> Option<Arc<Process>> {
+        LOOKUPS.fetch_add(1, Ordering::Relaxed);
+        self.shard(pid).read().get(pid).cloned()
+    }
+
+    /// All the processes ordered by pid, the shards are locked one by one
+    pub fn values(&self) -> Vec<Arc<Process>> {
+        let mut procs: Vec<Arc<Process>> = self
+            .shards
+            .iter()
+            .flat_map(|shard| shard.read().values().cloned().collect::<Vec<_>>())
+            .collect();
+        procs.sort_by_key(|proc| proc.pid());
+        procs
+    }
+}
@@ -1,8 +1,11 @@
 use core::sync::atomic::{AtomicU64, Ordering};
 
-use alloc::sync::Arc;
+use alloc::{collections::BTreeSet, sync::Arc};
+use spin::Mutex;
 use x86_64::{
-    structures::paging::{mapper::UnmapError, Page, PageSize, Size4KiB},
+    structures::paging::{
+        mapper::UnmapError, page::PageRange, FrameDeallocator, Mapper, Page, PageSize, Size4KiB,
+    },
     VirtAddr,
 };
 
@@ -32,6 +35,11 @@ pub struct Heap {
     ///
     /// use atomic to allow multiple threads to access the heap
     end: Arc<AtomicU64>,
+
+    /// the pages locked by `mlock`, which `dont_need` never frees
+    ///
+    /// shared like the end, dropped once the heap shrinks below them
+    locked: Arc<Mutex<BTreeSet<u64>>>,
 }
 
 impl Heap {
@@ -39,6 +47,7 @@ impl Heap {
         Self {
             base: VirtAddr::new(HEAP_START),
             end: Arc::new(AtomicU64::new(HEAP_START)),
+            locked: Arc::default(),
         }
     }
 
@@ -46,6 +55,16 @@ impl Heap {
         Self {
             base: self.base,
             end: self.end.clone(),
+            locked: self.locked.clone(),
+        }
+    }
+
+    /// Fork with a private end, for a copy-on-write page table
+    pub fn fork_cow(&self) -> Self {
+        Self {
+            base: self.base,
+            end: Arc::new(AtomicU64::new(self.end.load(Ordering::SeqCst))),
+            locked: Arc::new(Mutex::new(self.locked.lock().clone())),
         }
     }
 
@@ -76,10 +95,22 @@ impl Heap {
                     ret = Some(new_end);
                 } else {
                     let pages = (upper_bound - new_upper_bound) / PAGE_SIZE;
-                    elf::unmap_pages(new_upper_bound, pages, mapper, alloc, true).ok()?;
+                    unmap_present(new_upper_bound, pages, mapper, alloc).ok()?;
+                    self.locked.lock().retain(|page| *page < new_upper_bound);
                     self.end.swap(new_end.as_u64(), Ordering::SeqCst);
                     ret = Some(new_end);
                 }
+
+                // never expose stale data beyond the old end to the process
+                if new_end > now_end {
+                    unsafe {
+                        core::ptr::write_bytes(
+                            now_end.as_mut_ptr::<u8>(),
+                            0,
+                            (new_end - now_end) as usize,
+                        );
+                    }
+                }
             }
             // if the new_end is invalid (in range [base, base + HEAP_SIZE])
             Some(_) => {
@@ -107,6 +138,7 @@ impl Heap {
         }
         // load the current end address and **reset it to base** (use `swap`)
         let origin_end = self.end.swap(self.base.as_u64(), Ordering::SeqCst);
+        self.locked.lock().clear();
 
         let pages = Page::<Size4KiB>::containing_address(VirtAddr::new(origin_end))
             - Page::containing_address(self.base);
@@ -115,13 +147,127 @@ impl Heap {
         if origin_end == self.base.as_u64() {
             Ok(())
         } else {
-            elf::unmap_pages(self.base.as_u64(), pages, mapper, dealloc, true)
+            unmap_present(self.base.as_u64(), pages, mapper, dealloc)
         }
     }
 
+    /// Whether `len` bytes from the page aligned `addr` lie in [base, end)
+    fn contains(&self, addr: VirtAddr, len: usize) -> bool {
+        let end = self.end.load(Ordering::SeqCst);
+        addr.is_aligned(PAGE_SIZE)
+            && len > 0
+            && addr >= self.base
+            && addr.as_u64().checked_add(len as u64).is_some_and(|e| e <= end)
+    }
+
+    /// Free the frames of `len` bytes from the page aligned `addr`,
+    /// which is mapped again with zeros on the next access,
+    /// the pages locked by `set_locked` are kept
+    ///
+    /// the range must lie in [base, end), the end is kept as is
+    pub fn dont_need(
+        &self,
+        addr: VirtAddr,
+        len: usize,
+        mapper: MapperRef,
+        dealloc: FrameAllocatorRef,
+    ) -> bool {
+        if !self.contains(addr, len) {
+            return false;
+        }
+
+        let locked = self.locked.lock();
+        let pages = align_up(len as u64, PAGE_SIZE) / PAGE_SIZE;
+        (0..pages)
+            .map(|i| addr.as_u64() + i * PAGE_SIZE)
+            .filter(|page| !locked.contains(page))
+            .all(|page| unmap_present(page, 1, mapper, dealloc).is_ok())
+    }
+
+    /// Lock or unlock the pages of `len` bytes from the page aligned `addr`,
+    /// the range must lie in [base, end)
+    pub fn set_locked(&self, addr: VirtAddr, len: usize, locked: bool) -> bool {
+        if !self.contains(addr, len) {
+            return false;
+        }
+
+        let mut set = self.locked.lock();
+        let pages = align_up(len as u64, PAGE_SIZE) / PAGE_SIZE;
+        for page in (0..pages).map(|i| addr.as_u64() + i * PAGE_SIZE) {
+            if locked {
+                set.insert(page);
+            } else {
+                set.remove(&page);
+            }
+        }
+        true
+    }
+
+    /// Map a zeroed page for an access to a page freed by `dont_need`
+    pub fn handle_page_fault(
+        &self,
+        addr: VirtAddr,
+        mapper: MapperRef,
+        alloc: FrameAllocatorRef,
+    ) -> bool {
+        let upper_bound = align_up(self.end.load(Ordering::SeqCst), PAGE_SIZE);
+        if addr < self.base || addr.as_u64() >= upper_bound {
+            return false;
+        }
+
+        let page = Page::<Size4KiB>::containing_address(addr);
+        if elf::map_pages(page.start_address().as_u64(), 1, mapper, alloc, true).is_err() {
+            return false;
+        }
+        // the page table of the faulting process is the active one
+        unsafe {
+            core::ptr::write_bytes(page.start_address().as_mut_ptr::<u8>(), 0, PAGE_SIZE as usize);
+        }
+        true
+    }
+
+    pub fn end(&self) -> VirtAddr {
+        VirtAddr::new(self.end.load(Ordering::SeqCst))
+    }
+
+    /// The pages in [base, end), including the ones freed by `dont_need`
+    pub fn pages(&self) -> PageRange<Size4KiB> {
+        let upper_bound = align_up(self.end.load(Ordering::SeqCst), PAGE_SIZE);
+        Page::range(
+            Page::containing_address(self.base),
+            Page::containing_address(VirtAddr::new(upper_bound)),
+        )
+    }
+
     pub fn memory_usage(&self) -> u64 {
         self.end.load(Ordering::Relaxed) - self.base.as_u64()
     }
+
+    /// The bytes left between the end and `HEAP_END`
+    pub fn room(&self) -> u64 {
+        HEAP_END - self.end.load(Ordering::Relaxed)
+    }
+}
+
+/// Unmap the pages that are mapped, skipping the ones freed by `dont_need`
+fn unmap_present(
+    addr: u64,
+    pages: u64,
+    mapper: MapperRef,
+    dealloc: FrameAllocatorRef,
+) -> Result<(), UnmapError> {
+    let start = Page::<Size4KiB>::containing_address(VirtAddr::new(addr));
+    for page in Page::range(start, start + pages) {
+        match mapper.unmap(page) {
+            Ok((frame, flush)) => {
+                unsafe { dealloc.deallocate_frame(frame) };
+                flush.flush();
+            }
+            Err(UnmapError::PageNotMapped) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
 }
 
 impl core::fmt::Debug for Heap {
@@ -0,0 +1,322 @@
+use alloc::{collections::BTreeSet, sync::Arc, vec::Vec};
+use spin::Mutex;
+use x86_64::{
+    structures::paging::{mapper::*, page::PageRange, *},
+    PhysAddr, VirtAddr,
+};
+
+use crate::{memory::*, resource::Resource};
+
+use super::{FrameAllocatorRef, MapperRef};
+
+// memory mappings of files or anonymous memory
+// 0x10000000000 bytes -> 1TiB
+// from 0x0000_1000_0000_0000 to 0x0000_1100_0000_0000
+pub const MMAP_START: u64 = 0x1000_0000_0000;
+pub const MMAP_END: u64 = 0x1100_0000_0000;
+
+pub fn is_mmap_area(addr: VirtAddr) -> bool {
+    (MMAP_START..MMAP_END).contains(&addr.as_u64())
+}
+
+bitflags! {
+    /// The flags of `Syscall::Mmap`, values are the same as Linux
+    /// except `WRITE`, which stands for `PROT_WRITE`
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct MmapFlags: usize {
+        /// Writes are carried to the file
+        const SHARED = 0x01;
+        /// Writes stay in the process
+        const PRIVATE = 0x02;
+        /// Zero-filled memory without a file
+        const ANONYMOUS = 0x20;
+        /// The mapping is writable, read-only without it
+        const WRITE = 0x1000;
+    }
+}
+
+/// A mapped region, the pages are filled on the first access
+struct Mapping {
+    range: PageRange,
+    file: Option<Arc<Mutex<Resource>>>,
+    // the device memory behind the region, e.g. the framebuffer,
+    // its frames are shared and never freed
+    device: Option<PhysAddr>,
+    offset: usize,
+    len: usize,
+    flags: MmapFlags,
+    // the pages locked by `mlock`, kept for any reclaim of the mappings
+    locked: BTreeSet<Page>,
+}
+
+impl Mapping {
+    fn contains(&self, addr: VirtAddr) -> bool {
+        self.range.start.start_address() <= addr && addr < self.range.end.start_address()
+    }
+
+    fn page_flags(&self) -> PageTableFlags {
+        let mut flags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+        if self.flags.contains(MmapFlags::WRITE) {
+            flags |= PageTableFlags::WRITABLE;
+        }
+        flags
+    }
+
+    /// Fill a new frame with the bytes of the file behind `page`
+    fn fill(&self, page: Page, frame: PhysFrame) {
+        let base = physical_to_virtual(frame.start_address().as_u64()) as *mut u8;
+        let buf = unsafe { core::slice::from_raw_parts_mut(base, PAGE_SIZE as usize) };
+        buf.fill(0);
+
+        let page_offset = (page - self.range.start) as usize * PAGE_SIZE as usize;
+        if let Some(file) = &self.file {
+            let len = core::cmp::min(PAGE_SIZE as usize, self.len - page_offset);
+            file.lock().read_at(self.offset + page_offset, &mut buf[..len]);
+        }
+    }
+
+    /// Unmap the resident pages, the dirty ones of a shared file
+    /// mapping are written back first
+    ///
+    /// returns the number of pages freed
+    fn release(&self, mapper: MapperRef, dealloc: FrameAllocatorRef) -> u64 {
+        let mut count = 0;
+        for page in self.range {
+            let (frame, flags) = match mapper.translate(page.start_address()) {
+                TranslateResult::Mapped {
+                    frame: MappedFrame::Size4KiB(frame),
+                    flags,
+                    ..
+                } => (frame, flags),
+                _ => continue,
+            };
+
+            if let Some(file) = &self.file {
+                if self.flags.contains(MmapFlags::SHARED) && flags.contains(PageTableFlags::DIRTY)
+                {
+                    let page_offset = (page - self.range.start) as usize * PAGE_SIZE as usize;
+                    let len = core::cmp::min(PAGE_SIZE as usize, self.len - page_offset);
+                    let base = physical_to_virtual(frame.start_address().as_u64()) as *const u8;
+                    let buf = unsafe { core::slice::from_raw_parts(base, len) };
+                    if file.lock().write_at(self.offset + page_offset, buf).is_none() {
+                        warn!("Failed to write back mapped page: {:#x}", page.start_address());
+                    }
+                }
+            }
+
+            if let Ok((_, flush)) = mapper.unmap(page) {
+                flush.flush();
+                if self.device.is_none() {
+                    unsafe { dealloc.deallocate_frame(frame) };
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
+/// The memory mappings of a process, not inherited by forked children
+#[derive(Default)]
+pub struct MmapSet {
+    mappings: Vec<Mapping>,
+    usage: u64,
+}
+
+impl MmapSet {
+    /// Reserve a region for `len` bytes of the file from `offset`,
+    /// or zero-filled memory without a file
+    ///
+    /// the offset must be page aligned, returns the base address
+    pub fn map(
+        &mut self,
+        file: Option<Arc<Mutex<Resource>>>,
+        offset: usize,
+        len: usize,
+        flags: MmapFlags,
+    ) -> Option<VirtAddr> {
+        if len == 0 || offset as u64 % PAGE_SIZE != 0 {
+            return None;
+        }
+
+        let range = self.reserve(len)?;
+        trace!("Mmap: {:?} {:?}", range, flags);
+
+        self.mappings.push(Mapping {
+            range,
+            file,
+            device: None,
+            offset,
+            len,
+            flags,
+            locked: BTreeSet::new(),
+        });
+
+        Some(range.start.start_address())
+    }
+
+    /// Reserve a writable region shared with the device memory of
+    /// `len` bytes from the page aligned `phys`, which is not counted
+    /// in the usage
+    ///
+    /// returns the base address
+    pub fn map_device(&mut self, phys: PhysAddr, len: usize) -> Option<VirtAddr> {
+        if len == 0 || !phys.is_aligned(PAGE_SIZE) {
+            return None;
+        }
+
+        let range = self.reserve(len)?;
+        let flags = MmapFlags::SHARED | MmapFlags::WRITE;
+        trace!("Mmap device {:#x}: {:?}", phys, range);
+
+        self.mappings.push(Mapping {
+            range,
+            file: None,
+            device: Some(phys),
+            offset: 0,
+            len,
+            flags,
+            locked: BTreeSet::new(),
+        });
+
+        Some(range.start.start_address())
+    }
+
+    /// Find the pages for `len` bytes after the last mapping
+    fn reserve(&self, len: usize) -> Option<PageRange> {
+        let start = self
+            .mappings
+            .iter()
+            .map(|m| m.range.end.start_address().as_u64())
+            .max()
+            .unwrap_or(MMAP_START);
+        let end = start.checked_add(x86_64::align_up(len as u64, PAGE_SIZE))?;
+        if end > MMAP_END {
+            return None;
+        }
+
+        Some(Page::range(
+            Page::containing_address(VirtAddr::new(start)),
+            Page::containing_address(VirtAddr::new(end)),
+        ))
+    }
+
+    /// Remove the mapping starting at `addr`
+    pub fn unmap(&mut self, addr: VirtAddr, mapper: MapperRef, dealloc: FrameAllocatorRef) -> bool {
+        let index = match self
+            .mappings
+            .iter()
+            .position(|m| m.range.start.start_address() == addr)
+        {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let mapping = self.mappings.remove(index);
+        self.usage -= mapping.release(mapper, dealloc);
+        true
+    }
+
+    /// Lock or unlock the pages of `len` bytes from the page aligned `addr`,
+    /// the range must lie in a single mapping
+    pub fn set_locked(&mut self, addr: VirtAddr, len: usize, locked: bool) -> bool {
+        if len == 0 || !addr.is_aligned(PAGE_SIZE) {
+            return false;
+        }
+        let last = match addr.as_u64().checked_add(len as u64 - 1) {
+            Some(last) => VirtAddr::new_truncate(last),
+            None => return false,
+        };
+        let mapping = match self
+            .mappings
+            .iter_mut()
+            .find(|m| m.contains(addr) && m.contains(last))
+        {
+            Some(mapping) => mapping,
+            None => return false,
+        };
+
+        let first = Page::containing_address(addr);
+        for page in Page::range_inclusive(first, Page::containing_address(last)) {
+            if locked {
+                mapping.locked.insert(page);
+            } else {
+                mapping.locked.remove(&page);
+            }
+        }
+        true
+    }
+
+    pub fn handle_page_fault(
+        &mut self,
+        addr: VirtAddr,
+        mapper: MapperRef,
+        alloc: FrameAllocatorRef,
+    ) -> bool {
+        let mapping = match self.mappings.iter().find(|m| m.contains(addr)) {
+            Some(mapping) => mapping,
+            None => return false,
+        };
+
+        let page = Page::containing_address(addr);
+        if let Some(phys) = mapping.device {
+            let page_offset = (page - mapping.range.start) * PAGE_SIZE;
+            let frame = PhysFrame::containing_address(phys + page_offset);
+            let flags = mapping.page_flags() | PageTableFlags::NO_CACHE;
+            return match unsafe { mapper.map_to(page, frame, flags, alloc) } {
+                Ok(flush) => {
+                    flush.flush();
+                    true
+                }
+                Err(err) => {
+                    error!("Map device page failed: {:?}", err);
+                    false
+                }
+            };
+        }
+
+        let frame = match alloc.allocate_frame() {
+            Some(frame) => frame,
+            None => return false,
+        };
+        mapping.fill(page, frame);
+
+        match unsafe { mapper.map_to(page, frame, mapping.page_flags(), alloc) } {
+            Ok(flush) => flush.flush(),
+            Err(err) => {
+                error!("Map mmap page failed: {:?}", err);
+                unsafe { alloc.deallocate_frame(frame) };
+                return false;
+            }
+        }
+
+        self.usage += 1;
+        true
+    }
+
+    pub fn memory_usage(&self) -> u64 {
+        self.usage * PAGE_SIZE
+    }
+
+    /// Remove all the mappings, writing back the shared ones
+    pub fn clean_up(&mut self, mapper: MapperRef, dealloc: FrameAllocatorRef) {
+        for mapping in self.mappings.drain(..) {
+            mapping.release(mapper, dealloc);
+        }
+        self.usage = 0;
+    }
+}
+
+impl core::fmt::Debug for MmapSet {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.debug_list()
+            .entries(self.mappings.iter().map(|m| {
+                (
+                    m.range.start.start_address(),
+                    m.range.end.start_address(),
+                    m.flags,
+                )
+            }))
+            .finish()
+    }
+}
@@ -1,20 +1,26 @@
-use crate::{humanized_size, memory::*, ProcessId};
-use alloc::{format, vec::Vec};
+use crate::{humanized_size, memory::*, resource::Resource, ProcessId};
+use alloc::{format, sync::Arc, vec::Vec};
+use spin::Mutex;
 use boot::KernelPages;
 use x86_64::{
     structures::paging::{
-        mapper::{CleanUp, UnmapError},
+        mapper::{CleanUp, MappedFrame, TranslateResult, UnmapError},
         page::*,
         *,
     },
-    VirtAddr,
+    PhysAddr, VirtAddr,
 };
 use xmas_elf::ElfFile;
 
 pub mod heap;
+pub mod mmap;
 pub mod stack;
 
-use self::{heap::Heap, stack::Stack};
+use self::{
+    heap::Heap,
+    mmap::*,
+    stack::{Stack, StackSize},
+};
 
 use super::PageTableContext;
 
@@ -40,6 +46,14 @@ pub struct ProcessVm {
     // these fields will be empty for other processes
     pub(super) code: Vec<PageRangeInclusive>,
     pub(super) code_usage: u64,
+
+    // the page table is created by a copy-on-write fork,
+    // all its user pages are freed with the last user
+    pub(super) cow: bool,
+
+    // mappings are private to the process, not inherited by forks
+    // the mappings are shared with the threads
+    pub(super) mmaps: Arc<Mutex<MmapSet>>,
 }
 
 impl ProcessVm {
@@ -50,6 +64,8 @@ impl ProcessVm {
             heap: Heap::empty(),
             code: Vec::new(),
             code_usage: 0,
+            cow: false,
+            mmaps: Arc::default(),
         }
     }
 
@@ -71,21 +87,47 @@ impl ProcessVm {
         self
     }
 
-    pub fn brk(&self, addr: Option<VirtAddr>) -> Option<VirtAddr> {
-        self.heap.brk(
-            addr,
-            &mut self.page_table.mapper(),
-            &mut get_frame_alloc_for_sure(),
-        )
+    /// Move the end of the heap, fails if the growth would push
+    /// the process over `limit`
+    pub fn brk(&self, addr: Option<VirtAddr>, limit: u64) -> Option<VirtAddr> {
+        let mapper = &mut self.page_table.mapper();
+        let alloc = &mut *get_frame_alloc_for_sure();
+
+        if let Some(new_end) = addr {
+            let now_end = self.heap.brk(None, mapper, alloc)?;
+            if new_end > now_end && self.over_limit(limit, new_end - now_end) {
+                return None;
+            }
+        }
+
+        self.heap.brk(addr, mapper, alloc)
+    }
+
+    /// Free the frames of a heap range, see `Heap::dont_need`
+    pub fn madvise_dontneed(&self, addr: VirtAddr, len: usize) -> bool {
+        let mapper = &mut self.page_table.mapper();
+        let dealloc = &mut *get_frame_alloc_for_sure();
+
+        self.heap.dont_need(addr, len, mapper, dealloc)
+    }
+
+    /// Lock or unlock a page range of the heap or a mapping, the locked
+    /// heap pages are kept by `madvise_dontneed`
+    pub fn mlock(&self, addr: VirtAddr, len: usize, locked: bool) -> bool {
+        if mmap::is_mmap_area(addr) {
+            self.mmaps.lock().set_locked(addr, len, locked)
+        } else {
+            self.heap.set_locked(addr, len, locked)
+        }
     }
 
-    pub fn load_elf(&mut self, elf: &ElfFile, pid: ProcessId) -> VirtAddr {
+    pub fn load_elf(&mut self, elf: &ElfFile, pid: ProcessId, stack: StackSize) -> VirtAddr {
         let mapper = &mut self.page_table.mapper();
 
         let alloc = &mut *get_frame_alloc_for_sure();
 
         self.load_elf_code(elf, mapper, alloc);
-        self.stack.init(mapper, alloc, pid)
+        self.stack.init(mapper, alloc, pid, stack)
     }
 
     fn load_elf_code(&mut self, elf: &ElfFile, mapper: MapperRef, alloc: FrameAllocatorRef) {
@@ -110,18 +152,297 @@ impl ProcessVm {
             // do not share code info
             code: Vec::new(),
             code_usage: 0,
+            cow: self.cow,
+            mmaps: Arc::default(),
+        }
+    }
+
+    /// Share the memory with a thread, which runs on a fresh stack
+    pub fn thread(&self, stack_offset_count: u64) -> Self {
+        let page_table = self.page_table.fork();
+        let mapper = &mut page_table.mapper();
+        let alloc = &mut *get_frame_alloc_for_sure();
+
+        Self {
+            page_table,
+            stack: self.stack.thread(mapper, alloc, stack_offset_count),
+            heap: self.heap.fork(),
+            code: Vec::new(),
+            code_usage: 0,
+            cow: self.cow,
+            mmaps: self.mmaps.clone(),
         }
     }
 
-    pub fn handle_page_fault(&mut self, addr: VirtAddr) -> bool {
+    /// Share the memory with a vfork child, which runs on the stack itself,
+    /// the stack is lent until the child gives it back by `take_stack`
+    pub fn vfork(&self) -> Self {
+        Self {
+            page_table: self.page_table.fork(),
+            stack: self.stack.fork_cow(),
+            heap: self.heap.fork(),
+            code: Vec::new(),
+            code_usage: 0,
+            cow: self.cow,
+            mmaps: self.mmaps.clone(),
+        }
+    }
+
+    /// Take the stack out, the pages are left mapped for the new owner
+    pub fn take_stack(&mut self) -> Stack {
+        core::mem::replace(&mut self.stack, Stack::empty())
+    }
+
+    /// Fork with a private copy-on-write page table,
+    /// the child keeps the stack of the parent at the same address
+    pub fn fork_cow(&self) -> Self {
+        let stack = &self.stack;
+        let keep = |addr: VirtAddr| {
+            !mmap::is_mmap_area(addr) && !stack::is_stack_area(addr) || stack.is_on_stack(addr)
+        };
+
+        Self {
+            page_table: self.page_table.fork_cow(&keep),
+            stack: self.stack.fork_cow(),
+            heap: self.heap.fork_cow(),
+            code: Vec::new(),
+            code_usage: 0,
+            cow: true,
+            mmaps: Arc::default(),
+        }
+    }
+
+    pub fn handle_page_fault(&mut self, addr: VirtAddr, limit: u64) -> bool {
+        if self.over_limit(limit, self.stack.growth(addr)) {
+            warn!("Stack growth to {:#x} exceeds the memory limit.", addr);
+            return false;
+        }
+
         let mapper = &mut self.page_table.mapper();
         let alloc = &mut *get_frame_alloc_for_sure();
 
         self.stack.handle_page_fault(addr, mapper, alloc)
     }
 
+    /// The heap page is counted in the usage already, no limit is checked
+    pub fn handle_heap_fault(&mut self, addr: VirtAddr) -> bool {
+        let mapper = &mut self.page_table.mapper();
+        let alloc = &mut *get_frame_alloc_for_sure();
+
+        self.heap.handle_page_fault(addr, mapper, alloc)
+    }
+
+    pub fn mmap(
+        &mut self,
+        file: Option<Arc<Mutex<Resource>>>,
+        offset: usize,
+        len: usize,
+        flags: MmapFlags,
+        limit: u64,
+    ) -> Option<VirtAddr> {
+        if self.over_limit(limit, len as u64) {
+            return None;
+        }
+
+        self.mmaps.lock().map(file, offset, len, flags)
+    }
+
+    /// The device memory is shared, so it is not limited
+    pub fn map_device(&mut self, phys: PhysAddr, len: usize) -> Option<VirtAddr> {
+        self.mmaps.lock().map_device(phys, len)
+    }
+
+    pub fn munmap(&mut self, addr: VirtAddr) -> bool {
+        let mapper = &mut self.page_table.mapper();
+        let dealloc = &mut *get_frame_alloc_for_sure();
+
+        self.mmaps.lock().unmap(addr, mapper, dealloc)
+    }
+
+    pub fn handle_mmap_fault(&mut self, addr: VirtAddr, limit: u64) -> bool {
+        if self.over_limit(limit, PAGE_SIZE) {
+            warn!("Mapping {:#x} exceeds the memory limit.", addr);
+            return false;
+        }
+
+        let mapper = &mut self.page_table.mapper();
+        let alloc = &mut *get_frame_alloc_for_sure();
+
+        self.mmaps.lock().handle_page_fault(addr, mapper, alloc)
+    }
+
+    pub fn heap_end(&self) -> VirtAddr {
+        self.heap.end()
+    }
+
+    /// The pages kept by a checkpoint, the writable segments, the heap & the stack
+    fn checkpoint_pages(&self) -> Vec<Page> {
+        let writable = |page: &Page| {
+            self.mapping(page.start_address())
+                .is_some_and(|(_, flags)| flags.contains(PageTableFlags::WRITABLE))
+        };
+        let code = self.code.iter().flat_map(|range| *range).filter(writable);
+        code.chain(self.heap.pages()).chain(self.stack.pages()).collect()
+    }
+
+    /// Copy the pages kept by a checkpoint, the ones not mapped, e.g. freed
+    /// by `madvise_dontneed`, are skipped, `None` if there are over `max`
+    pub fn save_pages(&self, max: usize) -> Option<Vec<(Page, Vec<u8>)>> {
+        let mut pages = Vec::new();
+        for page in self.checkpoint_pages() {
+            let phys = match self.translate(page.start_address()) {
+                Some(phys) => phys,
+                None => continue,
+            };
+            if pages.len() == max {
+                return None;
+            }
+            let src = physical_to_virtual(phys.as_u64()) as *const u8;
+            let src = unsafe { core::slice::from_raw_parts(src, PAGE_SIZE as usize) };
+            pages.push((page, src.to_vec()));
+        }
+        Some(pages)
+    }
+
+    /// Write back the pages saved by `save_pages`, fails without a change if
+    /// any of them is no longer mapped writable, e.g. shared copy on write
+    pub fn restore_pages(&self, pages: &[(Page, Vec<u8>)]) -> bool {
+        let mut chunks = Vec::with_capacity(pages.len());
+        for (page, _) in pages {
+            match self.user_chunk(page.start_address().as_u64() as usize, true) {
+                Some((dst, _)) => chunks.push(dst),
+                None => return false,
+            }
+        }
+        for (dst, (_, data)) in chunks.into_iter().zip(pages) {
+            unsafe { core::ptr::copy_nonoverlapping(data.as_ptr(), dst, data.len()) };
+        }
+        true
+    }
+
+    /// The bytes the heap can still grow by, bounded by the end of
+    /// the heap area and `limit`, 0 for no limit
+    pub fn heap_avail(&self, limit: u64) -> u64 {
+        match limit {
+            0 => self.heap.room(),
+            limit => self.heap.room().min(limit.saturating_sub(self.limited_usage())),
+        }
+    }
+
+    /// Whether `extra` more bytes of heap, stack or mappings would
+    /// push the process over `limit`, 0 for no limit
+    fn over_limit(&self, limit: u64, extra: u64) -> bool {
+        limit != 0 && self.limited_usage() + extra > limit
+    }
+
+    /// The bytes of heap, stack & mappings, which are under the limit
+    fn limited_usage(&self) -> u64 {
+        self.stack.memory_usage() + self.heap.memory_usage() + self.mmaps.lock().memory_usage()
+    }
+
+    pub fn translate(&self, addr: VirtAddr) -> Option<PhysAddr> {
+        self.page_table.mapper().translate_addr(addr)
+    }
+
+    /// The physical address of `addr` and the flags of its page
+    pub fn mapping(&self, addr: VirtAddr) -> Option<(PhysAddr, PageTableFlags)> {
+        match self.page_table.mapper().translate(addr) {
+            TranslateResult::Mapped {
+                frame,
+                offset,
+                flags,
+            } => Some((frame.start_address() + offset, flags)),
+            _ => None,
+        }
+    }
+
+    /// The kernel pointer to the user byte at `addr` & the bytes left in its page,
+    /// `None` if the page is not mapped for the user, or not writable if `write`
+    fn user_chunk(&self, addr: usize, write: bool) -> Option<(*mut u8, usize)> {
+        let addr = VirtAddr::try_new(addr as u64).ok()?;
+        let (phys, flags) = self.mapping(addr)?;
+        let mut needed = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+        if write {
+            needed |= PageTableFlags::WRITABLE;
+        }
+        if !flags.contains(needed) {
+            return None;
+        }
+        let left = PAGE_SIZE - addr.as_u64() % PAGE_SIZE;
+        Some((physical_to_virtual(phys.as_u64()) as *mut u8, left as usize))
+    }
+
+    /// Read the memory at `addr` into `buf` through the physical memory,
+    /// short at the first page not mapped, returns the bytes read
+    pub fn peek(&self, addr: usize, buf: &mut [u8]) -> usize {
+        let mut count = 0;
+        while count < buf.len() {
+            let chunk = addr.checked_add(count).and_then(|a| self.user_chunk(a, false));
+            let (src, left) = match chunk {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            let len = core::cmp::min(left, buf.len() - count);
+            let src = unsafe { core::slice::from_raw_parts(src, len) };
+            buf[count..count + len].copy_from_slice(src);
+            count += len;
+        }
+        count
+    }
+
+    /// Write `buf` to the memory at `addr` through the physical memory,
+    /// short at the first page not writable, e.g. shared copy on write,
+    /// returns the bytes written
+    pub fn poke(&self, addr: usize, buf: &[u8]) -> usize {
+        let mut count = 0;
+        while count < buf.len() {
+            let chunk = addr.checked_add(count).and_then(|a| self.user_chunk(a, true));
+            let (dst, left) = match chunk {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            let len = core::cmp::min(left, buf.len() - count);
+            let dst = unsafe { core::slice::from_raw_parts_mut(dst, len) };
+            dst.copy_from_slice(&buf[count..count + len]);
+            count += len;
+        }
+        count
+    }
+
+    /// Whether all the `len` bytes from `addr` are in pages mapped for the user
+    pub fn is_user_range(&self, addr: VirtAddr, len: usize) -> bool {
+        if len == 0 {
+            return true;
+        }
+        let last = match addr.as_u64().checked_add(len as u64 - 1) {
+            Some(last) => match VirtAddr::try_new(last) {
+                Ok(last) => last,
+                Err(_) => return false,
+            },
+            None => return false,
+        };
+
+        let mapper = self.page_table.mapper();
+        let flags = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+        let mut pages = Page::<Size4KiB>::range_inclusive(
+            Page::containing_address(addr),
+            Page::containing_address(last),
+        );
+        pages.all(|page| match mapper.translate(page.start_address()) {
+            TranslateResult::Mapped {
+                frame: MappedFrame::Size4KiB(_),
+                flags: page_flags,
+                ..
+            } => page_flags.contains(flags),
+            _ => false,
+        })
+    }
+
     pub(super) fn memory_usage(&self) -> u64 {
-        self.stack.memory_usage() + self.heap.memory_usage() + self.code_usage
+        self.stack.memory_usage()
+            + self.heap.memory_usage()
+            + self.mmaps.lock().memory_usage()
+            + self.code_usage
     }
 
     pub(super) fn clean_up(&mut self) -> Result<(), UnmapError> {
@@ -130,9 +451,19 @@ impl ProcessVm {
 
         let origin_pages = dealloc.frames_recycled();
 
-        self.stack.clean_up(mapper, dealloc)?;
+        // the last of the threads writes back the shared mappings
+        if Arc::strong_count(&self.mmaps) == 1 {
+            self.mmaps.lock().clean_up(mapper, dealloc);
+        }
+        // the stack of a vfork child is given back to the parent
+        if self.stack.usage() != 0 {
+            self.stack.clean_up(mapper, dealloc)?;
+        }
 
-        if self.page_table.using_count() == 1 {
+        if self.page_table.using_count() == 1 && self.cow {
+            // free heap, code and the tables created by the fork
+            unsafe { self.page_table.free_cow(dealloc) };
+        } else if self.page_table.using_count() == 1 {
             // free heap
             self.heap.clean_up(mapper, dealloc)?;
 
@@ -168,6 +499,7 @@ impl core::fmt::Debug for ProcessVm {
         f.debug_struct("ProcessVm")
             .field("stack", &self.stack)
             .field("heap", &self.heap)
+            .field("mmaps", &*self.mmaps.lock())
             .field("memory_usage", &format!("{} {}", size, unit))
             .field("page_table", &self.page_table)
             .finish()
@@ -41,6 +41,52 @@ pub const KSTACK_DEF_SIZE: u64 = KSTACK_DEF_PAGE * PAGE_SIZE;
 pub const KSTACK_INIT_BOT: u64 = KSTACK_MAX - KSTACK_DEF_SIZE;
 pub const KSTACK_INIT_TOP: u64 = KSTACK_MAX - 8;
 
+/// The pages mapped for a user stack at start, and the most it can grow to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StackSize {
+    pub init_pages: u64,
+    pub max_pages: u64,
+}
+
+impl Default for StackSize {
+    fn default() -> Self {
+        Self {
+            init_pages: STACK_DEF_PAGE,
+            max_pages: STACK_MAX_PAGES,
+        }
+    }
+}
+
+impl StackSize {
+    /// The size from bytes rounded up to pages, 0 keeps the default
+    ///
+    /// `None` if the max is over the stack slot of a process,
+    /// or less than the initial size
+    pub fn from_bytes(init: u64, max: u64) -> Option<Self> {
+        let pages = |bytes: u64| bytes.checked_add(PAGE_SIZE - 1).map(|b| b / PAGE_SIZE);
+        let default = Self::default();
+        let size = Self {
+            init_pages: match init {
+                0 => default.init_pages,
+                init => pages(init)?,
+            },
+            max_pages: match max {
+                0 => default.max_pages,
+                max => pages(max)?,
+            },
+        };
+        match size.max_pages <= STACK_MAX_PAGES && size.init_pages <= size.max_pages {
+            true => Some(size),
+            false => None,
+        }
+    }
+}
+
+/// Whether the address is in the stack slots of any process
+pub fn is_stack_area(addr: VirtAddr) -> bool {
+    (super::heap::HEAP_START + super::heap::HEAP_SIZE..STACK_MAX).contains(&addr.as_u64())
+}
+
 const STACK_INIT_TOP_PAGE: Page<Size4KiB> = Page::containing_address(VirtAddr::new(STACK_INIT_TOP));
 
 const KSTACK_INIT_PAGE: Page<Size4KiB> = Page::containing_address(VirtAddr::new(KSTACK_INIT_BOT));
@@ -50,6 +96,8 @@ const KSTACK_INIT_TOP_PAGE: Page<Size4KiB> =
 pub struct Stack {
     range: PageRange<Size4KiB>,
     usage: u64,
+    // the stack cannot grow beyond this many pages below its top
+    max_pages: u64,
 }
 
 impl Stack {
@@ -57,6 +105,7 @@ impl Stack {
         Self {
             range: Page::range(top - size + 1, top + 1),
             usage: size,
+            max_pages: STACK_MAX_PAGES,
         }
     }
 
@@ -64,6 +113,7 @@ impl Stack {
         Self {
             range: Page::range(STACK_INIT_TOP_PAGE, STACK_INIT_TOP_PAGE),
             usage: 0,
+            max_pages: STACK_MAX_PAGES,
         }
     }
 
@@ -71,6 +121,7 @@ impl Stack {
         Self {
             range: Page::range(KSTACK_INIT_PAGE, KSTACK_INIT_TOP_PAGE),
             usage: KSTACK_DEF_PAGE,
+            max_pages: STACK_MAX_PAGES,
         }
     }
 
@@ -78,6 +129,11 @@ impl Stack {
         self.usage
     }
 
+    /// The pages mapped for the stack
+    pub fn pages(&self) -> PageRange<Size4KiB> {
+        self.range
+    }
+
     pub fn stack_min_addr(&self) -> VirtAddr {
         self.range.start.start_address()
     }
@@ -87,14 +143,17 @@ impl Stack {
         mapper: MapperRef,
         alloc: FrameAllocatorRef,
         pid: ProcessId,
+        size: StackSize,
     ) -> VirtAddr {
         debug_assert!(self.usage == 0, "Stack is not empty.");
-        let stack_bottom = STACK_MAX - (pid.0 - 1) as u64 * STACK_MAX_SIZE - STACK_DEF_SIZE;
+        let stack_top = STACK_MAX - (pid.0 - 1) as u64 * STACK_MAX_SIZE;
+        let stack_bottom = stack_top - size.init_pages * PAGE_SIZE;
         info!("Init stack for pid {}: {:#x}", pid.0, stack_bottom);
-        self.range = elf::map_pages(stack_bottom, STACK_DEF_PAGE, mapper, alloc, true).unwrap();
-        self.usage = STACK_DEF_PAGE;
+        self.range = elf::map_pages(stack_bottom, size.init_pages, mapper, alloc, true).unwrap();
+        self.usage = size.init_pages;
+        self.max_pages = size.max_pages;
 
-        self.range.start.start_address() + PAGE_SIZE - 8
+        VirtAddr::new(stack_top - 8)
     }
 
     pub fn set_stack(&mut self, bottom: u64, page_num: u64) {
@@ -120,6 +179,12 @@ impl Stack {
             return false;
         }
 
+        let page = Page::<Size4KiB>::containing_address(addr);
+        if page < self.range.end && self.range.end - page > self.max_pages {
+            warn!("Stack overflow at {:#x}, limited to {} pages.", addr, self.max_pages);
+            return false;
+        }
+
         if let Err(m) = self.grow_stack(addr, mapper, alloc) {
             error!("Grow stack failed: {:?}", m);
             return false;
@@ -128,7 +193,18 @@ impl Stack {
         true
     }
 
-    fn is_on_stack(&self, addr: VirtAddr) -> bool {
+    /// The bytes mapped to grow the stack down to `addr`
+    pub fn growth(&self, addr: VirtAddr) -> u64 {
+        let page = Page::<Size4KiB>::containing_address(addr);
+        match self.is_on_stack(addr) && page < self.range.start {
+            true => (self.range.start - page) * crate::memory::PAGE_SIZE,
+            false => 0,
+        }
+    }
+
+    /// Whether the address is in the stack region of the process,
+    /// including the pages not mapped yet
+    pub fn is_on_stack(&self, addr: VirtAddr) -> bool {
         let addr = addr.as_u64();
         let cur_stack_bot = self.range.start.start_address().as_u64();
         trace!("Current stack bot: {:#x}", cur_stack_bot);
@@ -236,6 +312,40 @@ impl Stack {
                 end: Page::containing_address(child_stack_min) + child_stack_count,
             },
             usage: self.usage(),
+            max_pages: self.max_pages,
+        }
+    }
+
+    /// A fresh stack of the default size for a thread, at the top of
+    /// the slot `stack_offset_count` pages below the one of this stack
+    pub fn thread(
+        &self,
+        mapper: MapperRef,
+        alloc: FrameAllocatorRef,
+        stack_offset_count: u64,
+    ) -> Self {
+        let slot_top = (self.stack_min_addr().as_u64() & STACK_START_MASK) + STACK_MAX_SIZE;
+        let mut stack_min = slot_top - STACK_DEF_SIZE - stack_offset_count * PAGE_SIZE;
+
+        while map_pages(stack_min, STACK_DEF_PAGE, mapper, alloc, true).is_err() {
+            trace!("Map thread stack to {:#x} failed.", stack_min);
+            stack_min -= STACK_MAX_SIZE; // stack grow down
+        }
+
+        let start = Page::containing_address(VirtAddr::new(stack_min));
+        Self {
+            range: Page::range(start, start + STACK_DEF_PAGE),
+            usage: STACK_DEF_PAGE,
+            max_pages: self.max_pages,
+        }
+    }
+
+    /// Keep the same stack range in a copy-on-write page table
+    pub fn fork_cow(&self) -> Self {
+        Self {
+            range: self.range,
+            usage: self.usage,
+            max_pages: self.max_pages,
         }
     }
 
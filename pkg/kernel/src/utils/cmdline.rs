@@ -0,0 +1,13 @@
+//! Kernel command line
+//!
+//! passed by the bootloader as `cmdline` of `boot.conf`, e.g.
+//! `init=/APP/SH quiet`, and kept as the env of the kernel for init
+
+/// Split the command line by whitespace into `key=value` pairs,
+/// a word without `=` is a flag with an empty value
+pub fn parse(cmdline: &str) -> impl Iterator<Item = (&str, &str)> {
+    cmdline
+        .split_whitespace()
+        .map(|arg| arg.split_once('=').unwrap_or((arg, "")))
+        .filter(|(key, _)| !key.is_empty())
+}
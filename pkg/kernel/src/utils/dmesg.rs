@@ -0,0 +1,73 @@
+use core::fmt::{Arguments, Write};
+use log::Level;
+use spin::Mutex;
+
+/// The bytes of the kernel messages kept, the oldest ones are overwritten first
+pub const DMESG_SIZE: usize = 4096;
+
+/// The warnings & errors of the kernel, in the order they are logged
+static DMESG: Mutex<LogRing> = Mutex::new(LogRing::new());
+
+struct LogRing {
+    buf: [u8; DMESG_SIZE],
+    // where the next byte goes
+    next: usize,
+    len: usize,
+}
+
+impl LogRing {
+    const fn new() -> Self {
+        Self {
+            buf: [0; DMESG_SIZE],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// The bytes from the oldest to the latest
+    fn iter(&self) -> impl Iterator<Item = &u8> {
+        let start = (self.next + DMESG_SIZE - self.len) % DMESG_SIZE;
+        (0..self.len).map(move |i| &self.buf[(start + i) % DMESG_SIZE])
+    }
+}
+
+impl Write for LogRing {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for byte in s.bytes() {
+            self.buf[self.next] = byte;
+            self.next = (self.next + 1) % DMESG_SIZE;
+        }
+        self.len = (self.len + s.len()).min(DMESG_SIZE);
+        Ok(())
+    }
+}
+
+/// Keep a message of the logger if it is a warning or an error
+///
+/// the message is dropped if the ring is in use, e.g. by a fault
+/// while another message is written
+pub fn record(level: Level, args: &Arguments) {
+    if level > Level::Warn {
+        return;
+    }
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        if let Some(mut ring) = DMESG.try_lock() {
+            writeln!(ring, "[{}]: {}", level, args).ok();
+        }
+    });
+}
+
+/// Copy the latest bytes of the messages into `buf`, oldest first,
+/// returns the number copied
+pub fn copy_to(buf: &mut [u8]) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let ring = DMESG.lock();
+        let skip = ring.len.saturating_sub(buf.len());
+        let mut count = 0;
+        for (dst, src) in buf.iter_mut().zip(ring.iter().skip(skip)) {
+            *dst = *src;
+            count += 1;
+        }
+        count
+    })
+}
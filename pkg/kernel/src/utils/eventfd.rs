@@ -0,0 +1,56 @@
+/// The largest value the counter may hold, `u64::MAX` is never reached
+pub const EVENTFD_MAX: u64 = u64::MAX - 1;
+
+/// A counter for notifications without a byte stream, like `eventfd`
+///
+/// every read and write moves a single `u64` in the native byte order
+#[derive(Debug)]
+pub struct EventFd {
+    count: u64,
+    // a read takes one from the counter, else the whole counter
+    semaphore: bool,
+}
+
+impl EventFd {
+    pub fn new(init: u64, semaphore: bool) -> Self {
+        Self {
+            count: init.min(EVENTFD_MAX),
+            semaphore,
+        }
+    }
+
+    /// Take the counter, or one of it in the semaphore mode,
+    /// returns 0 if the counter is zero, `None` if the buffer is too small
+    pub fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let buf = buf.get_mut(..8)?;
+        if self.count == 0 {
+            return Some(0);
+        }
+        let value = if self.semaphore { 1 } else { self.count };
+        self.count -= value;
+        buf.copy_from_slice(&value.to_ne_bytes());
+        Some(8)
+    }
+
+    /// Add to the counter, returns 0 if it would overflow,
+    /// `None` if the buffer is too small or the value is `u64::MAX`
+    pub fn write(&mut self, buf: &[u8]) -> Option<usize> {
+        let value = u64::from_ne_bytes(buf.get(..8)?.try_into().ok()?);
+        if value == u64::MAX {
+            return None;
+        }
+        if value > EVENTFD_MAX - self.count {
+            return Some(0);
+        }
+        self.count += value;
+        Some(8)
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.count > 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.count < EVENTFD_MAX
+    }
+}
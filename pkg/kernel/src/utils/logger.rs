@@ -1,31 +1,69 @@
-use log::{Metadata, Record};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use log::{LevelFilter, Metadata, Record};
 
 pub fn init(log_level: &str) {
     static LOGGER: Logger = Logger;
     log::set_logger(&LOGGER).unwrap();
 
     match log_level {
-        "Error" => log::set_max_level(log::LevelFilter::Error),
-        "Warn" => log::set_max_level(log::LevelFilter::Warn),
-        "Info" => log::set_max_level(log::LevelFilter::Info),
-        "Debug" => log::set_max_level(log::LevelFilter::Debug),
-        "Trace" => log::set_max_level(log::LevelFilter::Trace),
-        _ => log::set_max_level(log::LevelFilter::Info),
+        "Error" => log::set_max_level(LevelFilter::Error),
+        "Warn" => log::set_max_level(LevelFilter::Warn),
+        "Info" => log::set_max_level(LevelFilter::Info),
+        "Debug" => log::set_max_level(LevelFilter::Debug),
+        "Trace" => log::set_max_level(LevelFilter::Trace),
+        _ => log::set_max_level(LevelFilter::Info),
     }
     info!("Logger Initialized.");
 }
 
+/// Change the log level at runtime
+///
+/// the level is kept in an atomic, which is checked by the logging macros
+/// before formatting, so the suppressed messages cost nothing
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// Check that the messages below the log level are never formatted
+pub fn test_level_gate() {
+    static FORMATTED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counted;
+
+    impl core::fmt::Display for Counted {
+        fn fmt(&self, _f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            FORMATTED.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    let level = log::max_level();
+    set_level(LevelFilter::Warn);
+    debug!("{}", Counted);
+    trace!("{}", Counted);
+    info!("{}", Counted);
+    set_level(level);
+
+    assert_eq!(
+        FORMATTED.load(Ordering::SeqCst),
+        0,
+        "Suppressed log messages are formatted"
+    );
+}
+
 struct Logger;
 
 impl log::Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= metadata.level()
+        metadata.level() <= log::max_level()
     }
 
     fn log(&self, record: &Record) {
         // Implement the logger with serial output
         if self.enabled(record.metadata()) {
             println!("[{}]: {}", record.level(), record.args());
+            // kept for `Syscall::Dmesg` as well
+            super::dmesg::record(record.level(), record.args());
         }
     }
 
@@ -1,4 +1,4 @@
-use crate::{drivers::serial::get_serial, serial::SERIAL};
+use crate::drivers::serial::*;
 use core::fmt::*;
 use x86_64::instructions::interrupts;
 
@@ -58,16 +58,72 @@ macro_rules! println {
 pub fn print_internal(args: Arguments) {
     interrupts::without_interrupts(|| {
         if let Some(mut serial) = get_serial() {
-            serial.write_fmt(args).unwrap();
+            SerialWriter(&mut serial).write_fmt(args).unwrap();
         }
     });
 }
 
 #[allow(dead_code)]
+/// What the kernel does after reporting a panic
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Halt forever, so that the state can be inspected
+    Hang,
+    /// Quit QEMU with `PANIC_EXIT_CODE` through the isa-debug-exit device
+    Exit,
+}
+
+/// The I/O port of `-device isa-debug-exit,iobase=0xf4,iosize=0x04`
+const QEMU_EXIT_PORT: u16 = 0xf4;
+
+/// QEMU exits with `(PANIC_EXIT_CODE << 1) | 1`, i.e. 33
+pub const PANIC_EXIT_CODE: u32 = 0x10;
+
+/// QEMU exits with 1 after a shutdown without leaks
+pub const SHUTDOWN_EXIT_CODE: u32 = 0;
+
+/// QEMU exits with 35 if frames are leaked at shutdown
+pub const LEAK_EXIT_CODE: u32 = 0x11;
+
+/// Quit QEMU through the isa-debug-exit device, halt if it is not present
+pub fn exit_qemu(code: u32) -> ! {
+    x86_64::instructions::interrupts::disable();
+    unsafe { x86_64::instructions::port::Port::new(QEMU_EXIT_PORT).write(code) };
+    loop {
+        x86_64::instructions::hlt();
+    }
+}
+
+static PANIC_POLICY: spin::Once<PanicPolicy> = spin::Once::new();
+
+/// Set the panic policy from the boot config, hang before this
+pub fn init_panic_policy(policy: &str) {
+    let policy = match policy {
+        "exit" => PanicPolicy::Exit,
+        "hang" => PanicPolicy::Hang,
+        _ => {
+            warn!("Unknown panic policy: {}, hang on panic.", policy);
+            PanicPolicy::Hang
+        }
+    };
+    PANIC_POLICY.call_once(|| policy);
+}
+
 #[cfg_attr(not(test), panic_handler)]
 fn panic(info: &core::panic::PanicInfo) -> ! {
     // force unlock serial for panic output
-    unsafe { SERIAL.get().unwrap().force_unlock() };
+    unsafe {
+        SERIAL.get().unwrap().force_unlock();
+        OUTPUT_BUF.force_unlock();
+    }
+    // no more transmit interrupt will come
+    disable_async_output(&mut SERIAL.get().unwrap().lock());
     error!("ERROR: panic!\n\n{:#?}", info);
+
+    if PANIC_POLICY.get() == Some(&PanicPolicy::Exit) {
+        unsafe { x86_64::instructions::port::Port::new(QEMU_EXIT_PORT).write(PANIC_EXIT_CODE) };
+    }
+
+    // hang, or the exit device is not present
     loop {}
 }
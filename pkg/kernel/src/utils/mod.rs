@@ -3,10 +3,20 @@ mod macros;
 #[macro_use]
 mod regs;
 
+pub mod cmdline;
+pub mod dmesg;
+pub mod eventfd;
 pub mod func;
 pub mod logger;
+pub mod pipe;
+pub mod procmem;
+pub mod random;
 pub mod resource;
 pub mod runtime;
+pub mod tee;
+pub mod trace;
+pub mod tsc;
+pub mod watch;
 
 use crate::proc::*;
 pub use macros::*;
@@ -0,0 +1,134 @@
+use crate::resource::Resource;
+use alloc::{collections::VecDeque, sync::Arc};
+use core::cmp::min;
+use spin::Mutex;
+
+/// The maximum number of bytes buffered in a pipe
+pub const PIPE_CAPACITY: usize = 4096;
+/// The maximum number of fds passed through a pipe and not received yet
+pub const PIPE_MAX_FDS: usize = 16;
+
+#[derive(Debug, Default)]
+struct PipeBuffer {
+    buf: VecDeque<u8>,
+    // the resources sent by `send_fd`, apart from the bytes
+    fds: VecDeque<Arc<Mutex<Resource>>>,
+    readers: usize,
+    writers: usize,
+}
+
+/// The read end of a pipe
+#[derive(Debug)]
+pub struct PipeReader(Arc<Mutex<PipeBuffer>>);
+
+/// The write end of a pipe
+#[derive(Debug)]
+pub struct PipeWriter(Arc<Mutex<PipeBuffer>>);
+
+/// Create a new pipe, returning its read end and write end
+pub fn pipe() -> (PipeReader, PipeWriter) {
+    let buffer = Arc::new(Mutex::new(PipeBuffer {
+        buf: VecDeque::with_capacity(PIPE_CAPACITY),
+        fds: VecDeque::new(),
+        readers: 1,
+        writers: 1,
+    }));
+
+    (PipeReader(buffer.clone()), PipeWriter(buffer))
+}
+
+impl PipeReader {
+    /// Read the buffered bytes, returns 0 if the pipe is empty
+    pub fn read(&self, buf: &mut [u8]) -> usize {
+        let mut pipe = self.0.lock();
+        let count = min(buf.len(), pipe.buf.len());
+        for (dst, src) in buf.iter_mut().zip(pipe.buf.drain(..count)) {
+            *dst = src;
+        }
+        count
+    }
+
+    /// Whether a read would return immediately,
+    /// i.e. there is buffered data or all the write ends are closed
+    pub fn is_readable(&self) -> bool {
+        let pipe = self.0.lock();
+        !pipe.buf.is_empty() || pipe.writers == 0
+    }
+
+    /// Take the earliest resource sent through the pipe, if any
+    pub fn recv_fd(&self) -> Option<Arc<Mutex<Resource>>> {
+        self.0.lock().fds.pop_front()
+    }
+}
+
+impl PipeWriter {
+    /// Write as many bytes as the pipe can hold,
+    /// returns `None` if all the read ends have been closed
+    pub fn write(&self, buf: &[u8]) -> Option<usize> {
+        let mut pipe = self.0.lock();
+        if pipe.readers == 0 {
+            return None;
+        }
+        let count = min(buf.len(), PIPE_CAPACITY - pipe.buf.len());
+        pipe.buf.extend(&buf[..count]);
+        Some(count)
+    }
+
+    /// Whether a write would return immediately,
+    /// i.e. there is free space or all the read ends are closed
+    pub fn is_writable(&self) -> bool {
+        let pipe = self.0.lock();
+        pipe.buf.len() < PIPE_CAPACITY || pipe.readers == 0
+    }
+
+    /// Queue a resource for the read end to receive, fails if all the read
+    /// ends are closed, `PIPE_MAX_FDS` are queued, or it is an end of this
+    /// pipe, which would keep the pipe alive forever
+    ///
+    /// NOTE: `res` must not be locked by the caller, e.g. be this write end
+    pub fn send_fd(&self, res: Arc<Mutex<Resource>>) -> bool {
+        let is_own_end = match &*res.lock() {
+            Resource::PipeReader(reader) => Arc::ptr_eq(&reader.0, &self.0),
+            Resource::PipeWriter(writer) => Arc::ptr_eq(&writer.0, &self.0),
+            _ => false,
+        };
+        let mut pipe = self.0.lock();
+        if is_own_end || pipe.readers == 0 || pipe.fds.len() == PIPE_MAX_FDS {
+            return false;
+        }
+        pipe.fds.push_back(res);
+        true
+    }
+}
+
+/// Move up to `count` bytes from the buffer of `reader` to the one of `writer`,
+/// bounded by the buffered bytes and the free space
+///
+/// returns 0 if nothing is buffered, e.g. at EOF, or `None` if all the read ends
+/// of `writer` are closed or both ends belong to the same pipe
+pub fn splice(reader: &PipeReader, writer: &PipeWriter, count: usize) -> Option<usize> {
+    if Arc::ptr_eq(&reader.0, &writer.0) {
+        return None;
+    }
+
+    let mut src = reader.0.lock();
+    let mut dst = writer.0.lock();
+    if dst.readers == 0 {
+        return None;
+    }
+    let count = min(count, min(src.buf.len(), PIPE_CAPACITY - dst.buf.len()));
+    dst.buf.extend(src.buf.drain(..count));
+    Some(count)
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        self.0.lock().readers -= 1;
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        self.0.lock().writers -= 1;
+    }
+}
@@ -0,0 +1,51 @@
+use crate::proc::ProcessId;
+use storage::SeekFrom;
+
+/// The memory of another process opened as `/proc/<pid>/mem`, the offset
+/// is the virtual address in the target, like a debugger peeking & poking
+#[derive(Debug)]
+pub struct ProcMem {
+    pid: ProcessId,
+    offset: usize,
+}
+
+impl ProcMem {
+    pub fn new(pid: ProcessId) -> Self {
+        Self { pid, offset: 0 }
+    }
+
+    /// The pid in `/proc/<pid>/mem`, `None` for any other path
+    pub fn parse_path(path: &str) -> Option<ProcessId> {
+        let pid = path.strip_prefix("/proc/")?.strip_suffix("/mem")?;
+        pid.parse().ok().filter(|pid| *pid != 0).map(ProcessId)
+    }
+
+    /// Read from the offset, short at the first page not mapped for the user,
+    /// returns 0 past the mapped regions or once the target has exited
+    pub fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let count = crate::proc::peek_mem(self.pid, self.offset, buf);
+        self.offset += count;
+        Some(count)
+    }
+
+    /// Write at the offset, short at the first page not writable,
+    /// e.g. shared copy on write, `None` if nothing can be written
+    pub fn write(&mut self, buf: &[u8]) -> Option<usize> {
+        let count = crate::proc::poke_mem(self.pid, self.offset, buf);
+        if count == 0 && !buf.is_empty() {
+            return None;
+        }
+        self.offset += count;
+        Some(count)
+    }
+
+    /// Move to an address, there is no end to seek from
+    pub fn seek(&mut self, pos: SeekFrom) -> Option<usize> {
+        self.offset = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::Current(delta) => self.offset.checked_add_signed(delta)?,
+            SeekFrom::End(_) => return None,
+        };
+        Some(self.offset)
+    }
+}
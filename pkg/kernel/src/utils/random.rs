@@ -0,0 +1,56 @@
+//! Random number source
+//!
+//! use `RDRAND` when the cpu supports it, otherwise a xorshift PRNG
+//! seeded from the tsc at boot
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Once;
+use x86_64::instructions::random::RdRand;
+
+static RDRAND: Once<Option<RdRand>> = Once::new();
+static XORSHIFT_STATE: AtomicU64 = AtomicU64::new(0);
+
+pub fn init() {
+    let rdrand = *RDRAND.call_once(RdRand::new);
+    // xorshift must not start from zero
+    XORSHIFT_STATE.store(crate::tsc::rdtsc() | 1, Ordering::SeqCst);
+    info!(
+        "Random Initialized, using {}.",
+        if rdrand.is_some() { "RDRAND" } else { "xorshift" }
+    );
+}
+
+/// Get a random u64, from `RDRAND` if available
+pub fn random_u64() -> u64 {
+    if let Some(Some(rdrand)) = RDRAND.get() {
+        // RDRAND may fail transiently, retry a few times
+        for _ in 0..10 {
+            if let Some(val) = rdrand.get_u64() {
+                return val;
+            }
+        }
+    }
+    xorshift()
+}
+
+fn xorshift() -> u64 {
+    let mut old = XORSHIFT_STATE.load(Ordering::SeqCst);
+    loop {
+        let mut x = old;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        match XORSHIFT_STATE.compare_exchange(old, x, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => return x,
+            Err(cur) => old = cur,
+        }
+    }
+}
+
+/// Fill the buffer with random bytes
+pub fn fill(buf: &mut [u8]) {
+    for chunk in buf.chunks_mut(8) {
+        let bytes = random_u64().to_ne_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
@@ -88,3 +88,55 @@ macro_rules! as_handler {
         }
     };
 }
+
+/// Like `as_handler`, for the exceptions that push an error code
+///
+/// the error code is swapped with `rbp`, so the saved registers are followed
+/// by the stack frame as in `ProcessContext`, and then passed in `rdi`
+#[macro_export]
+macro_rules! as_handler_with_err {
+    ($fn: ident, $err: ty) => {
+        paste::item! {
+            #[naked]
+            pub extern "x86-interrupt" fn [<$fn _handler>](_sf: InterruptStackFrame, _err: $err) {
+                unsafe {
+                    core::arch::asm!("
+                    xchg rbp, [rsp]
+                    push rax
+                    push rbx
+                    push rcx
+                    push rdx
+                    push rsi
+                    push rdi
+                    push r8
+                    push r9
+                    push r10
+                    push r11
+                    push r12
+                    push r13
+                    push r14
+                    push r15
+                    mov rdi, rbp
+                    call {}
+                    pop r15
+                    pop r14
+                    pop r13
+                    pop r12
+                    pop r11
+                    pop r10
+                    pop r9
+                    pop r8
+                    pop rdi
+                    pop rsi
+                    pop rdx
+                    pop rcx
+                    pop rbx
+                    pop rax
+                    pop rbp
+                    iretq",
+                    sym $fn, options(noreturn));
+                }
+            }
+        }
+    };
+}
@@ -1,7 +1,35 @@
-use crate::drivers::input::*;
-use alloc::{collections::BTreeMap, string::String};
+use crate::drivers::{filesystem::OpenFile, input::*};
+use crate::eventfd::EventFd;
+use crate::pipe::*;
+use crate::procmem::ProcMem;
+use crate::tee::*;
+use crate::watch::Watch;
+use crate::filesystem;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
 use spin::Mutex;
-use storage::FileHandle;
+use storage::{Advice, SeekFrom};
+use syscall_def::{Dirent, FileKind, FileStat, SysError, WatchEvent, F_GETFL, F_SETFL, O_NONBLOCK};
+
+/// The resource can be read without blocking
+pub const POLL_READABLE: u8 = 1 << 0;
+/// The resource can be written without blocking
+pub const POLL_WRITABLE: u8 = 1 << 1;
+
+/// The bytes moved at a time by `sendfile`
+const SENDFILE_CHUNK: usize = 512;
+
+/// The maximum bytes kept by a capture of stdout, writes over it are short
+pub const CAPTURE_CAPACITY: usize = 4096;
+/// The maximum number of nested captures
+pub const MAX_CAPTURE_DEPTH: usize = 8;
+
+/// The fd redirected by a capture
+const STDOUT_FD: u8 = 1;
 
 #[derive(Debug, Clone)]
 pub enum StdIO {
@@ -10,15 +38,65 @@ pub enum StdIO {
     Stderr,
 }
 
-#[derive(Debug)]
+/// The fd table of a process, cloned for forked children,
+/// an fd and its copies refer to the same resource
+#[derive(Debug, Clone)]
 pub struct ResourceSet {
-    pub handles: BTreeMap<u8, Mutex<Resource>>,
+    pub handles: BTreeMap<u8, Arc<Mutex<Resource>>>,
+    // the fds closed by `exec`, new fds are kept by default
+    cloexec: BTreeSet<u8>,
+    // the status flags of each fd, e.g. `O_NONBLOCK`, none by default
+    status: BTreeMap<u8, usize>,
+    // the nested captures of stdout, the innermost last
+    captures: Vec<Capture>,
+    // the bytes read & written through each fd, reset once it is closed
+    counters: FdCounters,
+}
+
+/// The bytes read & written through each fd by `(read, written)`,
+/// counted behind a lock as the fds are read & written by `&self`
+#[derive(Debug, Default)]
+struct FdCounters(Mutex<BTreeMap<u8, (u64, u64)>>);
+
+impl Clone for FdCounters {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.0.lock().clone()))
+    }
+}
+
+impl FdCounters {
+    fn add(&self, fd: u8, read: usize, written: usize) {
+        let mut counters = self.0.lock();
+        let (total_read, total_written) = counters.entry(fd).or_default();
+        *total_read += read as u64;
+        *total_written += written as u64;
+    }
+
+    fn get(&self, fd: u8) -> (u64, u64) {
+        self.0.lock().get(&fd).copied().unwrap_or_default()
+    }
+
+    fn reset(&mut self, fd: u8) {
+        self.0.get_mut().remove(&fd);
+    }
+}
+
+/// A capture of stdout, the writes go to its buffer
+#[derive(Debug, Clone)]
+struct Capture {
+    // the stdout before the capture, restored once it ends
+    saved: Option<Arc<Mutex<Resource>>>,
+    buffer: Arc<Mutex<Resource>>,
 }
 
 impl Default for ResourceSet {
     fn default() -> Self {
         let mut res = Self {
             handles: BTreeMap::new(),
+            cloexec: BTreeSet::new(),
+            status: BTreeMap::new(),
+            captures: Vec::new(),
+            counters: FdCounters::default(),
         };
 
         res.open(Resource::Console(StdIO::Stdin));
@@ -31,35 +109,513 @@ impl Default for ResourceSet {
 
 impl ResourceSet {
     pub fn open(&mut self, res: Resource) -> u8 {
-        let fd = self.handles.len() as u8;
-        self.handles.insert(fd, Mutex::new(res));
+        self.open_shared(Arc::new(Mutex::new(res)))
+    }
+
+    /// Put the resource at the lowest unused fd, shared with its holders
+    fn open_shared(&mut self, res: Arc<Mutex<Resource>>) -> u8 {
+        let fd = (0..=u8::MAX)
+            .find(|fd| !self.handles.contains_key(fd))
+            .expect("No free file descriptor");
+        self.handles.insert(fd, res);
         fd
     }
 
+    /// Get the readiness flags of every fd in `fds`,
+    /// returns the number of fds that are ready
+    pub fn poll(&self, fds: &[u8], flags: &mut [u8]) -> isize {
+        let mut ready = 0;
+        for (fd, flag) in fds.iter().zip(flags.iter_mut()) {
+            *flag = self.handles.get(fd).map_or(0, |h| h.lock().poll());
+            if *flag != 0 {
+                ready += 1;
+            }
+        }
+        ready
+    }
+
+    /// Make `new_fd` refer to the same resource as `old_fd`,
+    /// the resource previously at `new_fd` is closed,
+    /// and `new_fd` is kept by `exec` like a new fd
+    pub fn dup2(&mut self, old_fd: u8, new_fd: u8) -> isize {
+        if let Some(res) = self.handles.get(&old_fd).cloned() {
+            self.handles.insert(new_fd, res);
+            self.cloexec.remove(&new_fd);
+            self.status.remove(&new_fd);
+            self.counters.reset(new_fd);
+            new_fd as isize
+        } else {
+            -1
+        }
+    }
+
+    /// Put the resource at `fd`, closing the one there if any,
+    /// used to set up the stdio of a spawned process
+    pub fn install(&mut self, fd: u8, res: Arc<Mutex<Resource>>) {
+        self.handles.insert(fd, res);
+        self.cloexec.remove(&fd);
+        self.status.remove(&fd);
+        self.counters.reset(fd);
+    }
+
+    /// Redirect stdout into a new buffer until `capture_end`,
+    /// the innermost of nested captures gets the writes
+    pub fn capture_start(&mut self) -> isize {
+        if self.captures.len() == MAX_CAPTURE_DEPTH {
+            return -1;
+        }
+        let buffer = Arc::new(Mutex::new(Resource::Capture(Vec::new())));
+        let saved = self.handles.insert(STDOUT_FD, buffer.clone());
+        self.captures.push(Capture { saved, buffer });
+        0
+    }
+
+    /// Copy the bytes of the innermost capture into `buf`, the rest is lost,
+    /// and restore the stdout before it, returns -1 if nothing is captured
+    pub fn capture_end(&mut self, buf: &mut [u8]) -> isize {
+        let capture = match self.captures.pop() {
+            Some(capture) => capture,
+            None => return -1,
+        };
+        match capture.saved {
+            Some(saved) => self.handles.insert(STDOUT_FD, saved),
+            None => self.handles.remove(&STDOUT_FD),
+        };
+
+        let buffer = capture.buffer.lock();
+        let bytes = match &*buffer {
+            Resource::Capture(bytes) => bytes,
+            _ => unreachable!(),
+        };
+        let len = core::cmp::min(buf.len(), bytes.len());
+        buf[..len].copy_from_slice(&bytes[..len]);
+        len as isize
+    }
+
+    /// Create a pipe, returns the fds of its read end and write end
+    pub fn pipe(&mut self) -> (u8, u8) {
+        let (reader, writer) = pipe();
+        let read_fd = self.open(Resource::PipeReader(reader));
+        let write_fd = self.open(Resource::PipeWriter(writer));
+        (read_fd, write_fd)
+    }
+
+    /// Create a tee pipe with `readers` read ends,
+    /// returns the fds of its write end and read ends
+    pub fn tee_pipe(&mut self, readers: usize) -> (u8, Vec<u8>) {
+        let (writer, readers) = tee_pipe(readers);
+        let write_fd = self.open(Resource::TeeWriter(writer));
+        let read_fds = readers
+            .into_iter()
+            .map(|reader| self.open(Resource::TeeReader(reader)))
+            .collect();
+        (write_fd, read_fds)
+    }
+
+    /// Send the resource of `fd` through the write end of the pipe `channel_fd`,
+    /// returns -1 if either is not opened or the pipe refuses it
+    pub fn send_fd(&self, channel_fd: u8, fd: u8) -> isize {
+        let (channel, res) = match (self.handles.get(&channel_fd), self.handles.get(&fd)) {
+            (Some(channel), Some(res)) if !Arc::ptr_eq(channel, res) => (channel, res),
+            _ => return -1,
+        };
+        match &*channel.lock() {
+            Resource::PipeWriter(writer) if writer.send_fd(res.clone()) => 0,
+            _ => -1,
+        }
+    }
+
+    /// Receive a resource from the read end of the pipe `channel_fd` at a new fd,
+    /// shared with the sender like `dup2`, returns -1 if nothing has been sent
+    pub fn recv_fd(&mut self, channel_fd: u8) -> isize {
+        let res = match self.handles.get(&channel_fd).map(|h| h.lock().recv_fd()) {
+            Some(Some(res)) => res,
+            _ => return -1,
+        };
+        self.open_shared(res) as isize
+    }
+
+    /// Take the earliest change seen by the watch fd into `event`,
+    /// returns 1 if there is one, 0 if not, or -1 if not a watch fd
+    pub fn watch_read(&self, fd: u8, event: &mut WatchEvent) -> isize {
+        let handle = match self.handles.get(&fd) {
+            Some(handle) => handle,
+            None => return -1,
+        };
+        match &*handle.lock() {
+            Resource::Watch(watch) => match watch.read() {
+                Some(read) => {
+                    *event = read;
+                    1
+                }
+                None => 0,
+            },
+            _ => -1,
+        }
+    }
+
+    /// Create an event counter, returns its fd
+    pub fn eventfd(&mut self, init: u64, semaphore: bool) -> u8 {
+        self.open(Resource::EventFd(EventFd::new(init, semaphore)))
+    }
+
+    /// The resource behind the fd, kept alive by the holder after closed
+    pub fn get(&self, fd: u8) -> Option<Arc<Mutex<Resource>>> {
+        self.handles.get(&fd).cloned()
+    }
+
     pub fn close(&mut self, fd: u8) -> bool {
+        self.cloexec.remove(&fd);
+        self.status.remove(&fd);
+        self.counters.reset(fd);
         self.handles.remove(&fd).is_some()
     }
 
+    /// The fds and their resources for a checkpoint, not keeping them open
+    pub fn snapshot_fds(&self) -> BTreeMap<u8, Weak<Mutex<Resource>>> {
+        self.handles
+            .iter()
+            .map(|(fd, res)| (*fd, Arc::downgrade(res)))
+            .collect()
+    }
+
+    /// Bring the fds back to a snapshot, the fds opened or replaced since
+    /// are closed, the resources closed by every holder since are gone
+    pub fn restore_fds(&mut self, fds: &BTreeMap<u8, Weak<Mutex<Resource>>>) {
+        let changed: Vec<u8> = self
+            .handles
+            .iter()
+            .filter(|(fd, res)| !fds.get(fd).is_some_and(|w| w.as_ptr() == Arc::as_ptr(res)))
+            .map(|(fd, _)| *fd)
+            .collect();
+        for fd in changed {
+            self.close(fd);
+        }
+        for (fd, res) in fds {
+            if let (false, Some(res)) = (self.handles.contains_key(fd), res.upgrade()) {
+                self.handles.insert(*fd, res);
+            }
+        }
+    }
+
+    /// The bytes read & written through the fd since it is opened,
+    /// `None` if it is not opened
+    pub fn fd_stats(&self, fd: u8) -> Option<(u64, u64)> {
+        self.handles.contains_key(&fd).then(|| self.counters.get(fd))
+    }
+
+    /// Mark the fd to be closed by `exec` or not, -1 if not opened
+    pub fn set_cloexec(&mut self, fd: u8, cloexec: bool) -> isize {
+        if !self.handles.contains_key(&fd) {
+            return -1;
+        }
+        match cloexec {
+            true => self.cloexec.insert(fd),
+            false => self.cloexec.remove(&fd),
+        };
+        0
+    }
+
+    /// Close the fds marked close-on-exec, the others are kept
+    pub fn close_on_exec(&mut self) {
+        for fd in core::mem::take(&mut self.cloexec) {
+            self.handles.remove(&fd);
+            self.status.remove(&fd);
+            self.counters.reset(fd);
+        }
+    }
+
+    /// Get (`F_GETFL`) or set (`F_SETFL`) the status flags of the fd,
+    /// only `O_NONBLOCK` is kept, the others are ignored like Linux
+    ///
+    /// returns the flags for `F_GETFL` & 0 for `F_SETFL`,
+    /// `BadFd` if the fd is not open, `Inval` for an unknown `cmd`
+    pub fn fcntl(&mut self, fd: u8, cmd: usize, arg: usize) -> isize {
+        if !self.handles.contains_key(&fd) {
+            return SysError::BadFd.code();
+        }
+        match cmd {
+            F_GETFL => self.status.get(&fd).copied().unwrap_or(0) as isize,
+            F_SETFL => {
+                match arg & O_NONBLOCK {
+                    0 => self.status.remove(&fd),
+                    flags => self.status.insert(fd, flags),
+                };
+                0
+            }
+            _ => SysError::Inval.code(),
+        }
+    }
+
+    fn is_nonblocking(&self, fd: u8) -> bool {
+        self.status.get(&fd).is_some_and(|flags| flags & O_NONBLOCK != 0)
+    }
+
+    fn is_readable(&self, fd: u8) -> bool {
+        self.handles.get(&fd).is_some_and(|res| res.lock().poll() & POLL_READABLE != 0)
+    }
+
+    /// Read from the fd, returns the bytes read, which may be less than
+    /// the buffer; a zero-length read returns 0 without touching the resource
+    ///
+    /// returns `BadFd` if the fd is not open or cannot be read,
+    /// a fd with `O_NONBLOCK` is read like `try_read`
     pub fn read(&self, fd: u8, buf: &mut [u8]) -> isize {
-        if let Some(count) = self.handles.get(&fd).and_then(|h| h.lock().read(buf)) {
+        if self.is_nonblocking(fd) {
+            return self.try_read(fd, buf);
+        }
+        let read = |res: &Arc<Mutex<Resource>>| match buf.is_empty() {
+            true => Some(0),
+            false => res.lock().read(buf),
+        };
+        if let Some(count) = self.handles.get(&fd).and_then(read) {
+            self.counters.add(fd, count, 0);
             count as isize
         } else {
-            -1
+            SysError::BadFd.code()
         }
     }
 
+    /// Read what is buffered in the fd, see `Resource::try_read`
+    ///
+    /// returns `Again` if nothing is buffered yet for a fd with `O_NONBLOCK`,
+    /// while the end of a pipe or a file still reads 0
+    pub fn try_read(&self, fd: u8, buf: &mut [u8]) -> isize {
+        let empty = buf.is_empty();
+        let read = |res: &Arc<Mutex<Resource>>| match empty {
+            true => Some(0),
+            false => res.lock().try_read(buf),
+        };
+        match self.handles.get(&fd).and_then(read) {
+            Some(0) if !empty && self.is_nonblocking(fd) && !self.is_readable(fd) => {
+                SysError::Again.code()
+            }
+            Some(count) => {
+                self.counters.add(fd, count, 0);
+                count as isize
+            }
+            None => SysError::BadFd.code(),
+        }
+    }
+
+    /// Write to the fd, files are written entirely or fail, while pipes
+    /// return a short count once full; a zero-length write returns 0
+    ///
+    /// returns `BadFd` if the fd is not open or cannot be written
     pub fn write(&self, fd: u8, buf: &[u8]) -> isize {
-        if let Some(count) = self.handles.get(&fd).and_then(|h| h.lock().write(buf)) {
+        let write = |res: &Arc<Mutex<Resource>>| match buf.is_empty() {
+            true => Some(0),
+            false => res.lock().write(buf),
+        };
+        if let Some(count) = self.handles.get(&fd).and_then(write) {
+            self.counters.add(fd, 0, count);
             count as isize
+        } else {
+            SysError::BadFd.code()
+        }
+    }
+
+    /// Read into the buffers in order, like a single read into them joined,
+    /// stops once a buffer is not filled, e.g. the pipe is drained
+    ///
+    /// returns the total bytes read, or -1 if nothing can be read
+    pub fn readv(&self, fd: u8, bufs: &mut [&mut [u8]]) -> isize {
+        let mut res = match self.handles.get(&fd) {
+            Some(res) => res.lock(),
+            None => return -1,
+        };
+
+        let mut total = 0;
+        for buf in bufs.iter_mut().filter(|buf| !buf.is_empty()) {
+            match res.read(buf) {
+                Some(read) => {
+                    total += read;
+                    if read < buf.len() {
+                        break;
+                    }
+                }
+                None if total == 0 => return -1,
+                None => break,
+            }
+        }
+        self.counters.add(fd, total, 0);
+        total as isize
+    }
+
+    /// Write the buffers in order, like a single write of them joined,
+    /// no other write to the resource comes in between
+    ///
+    /// stops once a buffer is not taken entirely, e.g. the pipe is full,
+    /// returns the total bytes written, or -1 if nothing can be written
+    pub fn writev(&self, fd: u8, bufs: &[&[u8]]) -> isize {
+        let mut res = match self.handles.get(&fd) {
+            Some(res) => res.lock(),
+            None => return -1,
+        };
+
+        let mut total = 0;
+        for buf in bufs.iter().filter(|buf| !buf.is_empty()) {
+            match res.write(buf) {
+                Some(written) => {
+                    total += written;
+                    if written < buf.len() {
+                        break;
+                    }
+                }
+                None if total == 0 => return -1,
+                None => break,
+            }
+        }
+        self.counters.add(fd, 0, total);
+        total as isize
+    }
+
+    /// Copy up to `count` bytes from the offset of the file `in_fd` to
+    /// `out_fd` in the kernel, the offset is advanced by the bytes copied
+    ///
+    /// stops early at EOF or once `out_fd` takes fewer bytes, e.g. a full pipe,
+    /// returns the bytes copied, or -1 if nothing can be copied
+    pub fn sendfile(&self, out_fd: u8, in_fd: u8, count: usize) -> isize {
+        let (input, output) = match (self.handles.get(&in_fd), self.handles.get(&out_fd)) {
+            (Some(input), Some(output)) if input.lock().is_file(false) => (input, output),
+            _ => return -1,
+        };
+
+        let mut buf = [0u8; SENDFILE_CHUNK];
+        let mut copied = 0;
+        while copied < count {
+            let len = core::cmp::min(SENDFILE_CHUNK, count - copied);
+            let read = match input.lock().read(&mut buf[..len]) {
+                Some(0) | None => break,
+                Some(read) => read,
+            };
+
+            let written = output.lock().write(&buf[..read]);
+            let taken = written.unwrap_or(0);
+            if taken < read {
+                // give back the bytes not taken
+                input.lock().seek(SeekFrom::Current(-((read - taken) as isize)));
+            }
+            // e.g. the read ends of the pipe are all closed
+            if written.is_none() && copied == 0 {
+                return -1;
+            }
+
+            copied += taken;
+            if taken < read {
+                break;
+            }
+        }
+        self.counters.add(in_fd, copied, 0);
+        self.counters.add(out_fd, 0, copied);
+        copied as isize
+    }
+
+    /// Move up to `count` bytes from the pipe `in_fd` to the pipe `out_fd`
+    /// in the kernel, short once the source is empty or the target is full
+    ///
+    /// returns the bytes moved, 0 at EOF, or -1 if the fds are not the two ends
+    /// of different pipes or the read ends of `out_fd` are all closed
+    pub fn splice(&self, in_fd: u8, out_fd: u8, count: usize) -> isize {
+        let (input, output) = match (self.handles.get(&in_fd), self.handles.get(&out_fd)) {
+            (Some(input), Some(output)) if !Arc::ptr_eq(input, output) => (input, output),
+            _ => return -1,
+        };
+
+        let moved = match (&*input.lock(), &*output.lock()) {
+            (Resource::PipeReader(reader), Resource::PipeWriter(writer)) => {
+                splice(reader, writer, count)
+            }
+            _ => None,
+        };
+        if let Some(moved) = moved {
+            self.counters.add(in_fd, moved, 0);
+            self.counters.add(out_fd, 0, moved);
+        }
+        moved.map_or(-1, |moved| moved as isize)
+    }
+
+    pub fn fstat(&self, fd: u8) -> Option<FileStat> {
+        self.handles.get(&fd).and_then(|h| h.lock().fstat())
+    }
+
+    /// 1 if the fd refers to a console, 0 for other resources,
+    /// or -1 if the fd is not opened
+    pub fn isatty(&self, fd: u8) -> isize {
+        match self.handles.get(&fd) {
+            Some(handle) => handle.lock().is_tty() as isize,
+            None => -1,
+        }
+    }
+
+    /// Read the entries of the dir behind the fd from `cursor`,
+    /// returns the next cursor, 0 if exhausted, or -1 if not a dir
+    pub fn getdents(&self, fd: u8, cursor: usize, dirents: &mut [Dirent]) -> isize {
+        let getdents = |h: &Arc<Mutex<Resource>>| h.lock().getdents(cursor, dirents);
+        if let Some(cursor) = self.handles.get(&fd).and_then(getdents) {
+            cursor as isize
         } else {
             -1
         }
     }
+
+    pub fn seek(&self, fd: u8, pos: SeekFrom) -> isize {
+        if let Some(offset) = self.handles.get(&fd).and_then(|h| h.lock().seek(pos)) {
+            offset as isize
+        } else {
+            -1
+        }
+    }
+
+    /// Change the mode of the file behind the fd, -1 for other resources
+    pub fn fchmod(&self, fd: u8, mode: usize) -> isize {
+        match self.handles.get(&fd).and_then(|h| h.lock().chmod(mode)) {
+            Some(()) => 0,
+            None => -1,
+        }
+    }
+
+    /// Resize the file behind the fd, -1 for other resources
+    pub fn ftruncate(&self, fd: u8, len: usize) -> isize {
+        match self.handles.get(&fd).and_then(|h| h.lock().set_len(len)) {
+            Some(()) => 0,
+            None => -1,
+        }
+    }
+
+    pub fn fsync(&self, fd: u8) -> isize {
+        match self.handles.get(&fd).and_then(|h| h.lock().sync()) {
+            Some(()) => 0,
+            None => -1,
+        }
+    }
+
+    /// Hint how the fd is going to be read, -1 for a bad fd
+    pub fn fadvise(&self, fd: u8, advice: Advice) -> isize {
+        match self.handles.get(&fd).and_then(|h| h.lock().advise(advice)) {
+            Some(()) => 0,
+            None => -1,
+        }
+    }
 }
 
 pub enum Resource {
-    File(FileHandle),
+    File(OpenFile),
+    /// A dir opened by its path, only for `getdents`
+    Dir(String),
     Console(StdIO),
+    PipeReader(PipeReader),
+    PipeWriter(PipeWriter),
+    /// A read end of a tee pipe, see `ResourceSet::tee_pipe`
+    TeeReader(TeeReader),
+    TeeWriter(TeeWriter),
+    EventFd(EventFd),
+    /// The bytes written to a captured stdout, see `ResourceSet::capture_start`
+    Capture(Vec<u8>),
+    /// The memory of a descendant, opened by `/proc/<pid>/mem`
+    ProcMem(ProcMem),
+    /// The changes of a file or a dir, see `ResourceSet::watch_read`
+    Watch(Watch),
     Null,
 }
 
@@ -85,13 +641,38 @@ impl Resource {
                 }
                 _ => None,
             },
+            Resource::PipeReader(pipe) => Some(pipe.read(buf)),
+            Resource::TeeReader(tee) => Some(tee.read(buf)),
+            Resource::EventFd(event) => event.read(buf),
+            Resource::ProcMem(mem) => mem.read(buf),
+            Resource::PipeWriter(_) | Resource::Dir(_) | Resource::Capture(_) => None,
+            Resource::TeeWriter(_) | Resource::Watch(_) => None,
             Resource::Null => Some(0),
         }
     }
 
+    /// Read without waiting for more input, the same as `read`
+    /// except that stdin is drained as far as `buf` holds
+    pub fn try_read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        match self {
+            Resource::Console(StdIO::Stdin) => {
+                let mut count = 0;
+                while count < buf.len() {
+                    match try_pop_key() {
+                        Some(ch) => buf[count] = ch,
+                        None => break,
+                    }
+                    count += 1;
+                }
+                Some(count)
+            }
+            _ => self.read(buf),
+        }
+    }
+
     pub fn write(&mut self, buf: &[u8]) -> Option<usize> {
         match self {
-            Resource::File(_file) => Some(0),
+            Resource::File(file) => file.write(buf).ok(),
             Resource::Console(stdio) => match *stdio {
                 StdIO::Stdin => None,
                 StdIO::Stdout => {
@@ -103,16 +684,207 @@ impl Resource {
                     Some(buf.len())
                 }
             },
+            Resource::PipeReader(_) | Resource::Dir(_) | Resource::Watch(_) => None,
+            Resource::PipeWriter(pipe) => pipe.write(buf),
+            Resource::TeeReader(_) => None,
+            Resource::TeeWriter(tee) => tee.write(buf),
+            Resource::EventFd(event) => event.write(buf),
+            Resource::ProcMem(mem) => mem.write(buf),
+            Resource::Capture(bytes) => {
+                let len = core::cmp::min(buf.len(), CAPTURE_CAPACITY - bytes.len());
+                bytes.extend_from_slice(&buf[..len]);
+                Some(len)
+            }
             Resource::Null => Some(buf.len()),
         }
     }
+
+    pub fn poll(&self) -> u8 {
+        match self {
+            Resource::Console(StdIO::Stdin) => {
+                if has_key() {
+                    POLL_READABLE
+                } else {
+                    0
+                }
+            }
+            Resource::Console(_) | Resource::Capture(_) => POLL_WRITABLE,
+            Resource::PipeReader(pipe) => {
+                if pipe.is_readable() {
+                    POLL_READABLE
+                } else {
+                    0
+                }
+            }
+            Resource::PipeWriter(pipe) => {
+                if pipe.is_writable() {
+                    POLL_WRITABLE
+                } else {
+                    0
+                }
+            }
+            Resource::TeeReader(tee) => {
+                if tee.is_readable() {
+                    POLL_READABLE
+                } else {
+                    0
+                }
+            }
+            Resource::TeeWriter(tee) => {
+                if tee.is_writable() {
+                    POLL_WRITABLE
+                } else {
+                    0
+                }
+            }
+            Resource::Watch(watch) => {
+                if watch.is_readable() {
+                    POLL_READABLE
+                } else {
+                    0
+                }
+            }
+            Resource::EventFd(event) => {
+                let readable = if event.is_readable() { POLL_READABLE } else { 0 };
+                let writable = if event.is_writable() { POLL_WRITABLE } else { 0 };
+                readable | writable
+            }
+            Resource::File(_) | Resource::Dir(_) | Resource::ProcMem(_) | Resource::Null => {
+                POLL_READABLE | POLL_WRITABLE
+            }
+        }
+    }
+
+    /// Take a resource sent through the pipe, `None` if it is not a read end
+    pub fn recv_fd(&self) -> Option<Arc<Mutex<Resource>>> {
+        match self {
+            Resource::PipeReader(reader) => reader.recv_fd(),
+            _ => None,
+        }
+    }
+
+    /// Whether the resource is the serial console or the keyboard
+    pub fn is_tty(&self) -> bool {
+        matches!(self, Resource::Console(_))
+    }
+
+    pub fn fstat(&mut self) -> Option<FileStat> {
+        match self {
+            Resource::File(file) => {
+                // the size may have changed since opened, ask the file itself
+                let offset = file.seek(SeekFrom::Current(0)).ok()?;
+                let size = file.seek(SeekFrom::End(0)).ok()?;
+                file.seek(SeekFrom::Start(offset)).ok()?;
+                Some(FileStat {
+                    size: size as u64,
+                    is_dir: false,
+                    kind: FileKind::Regular,
+                })
+            }
+            Resource::Dir(_) => Some(FileStat {
+                size: 0,
+                is_dir: true,
+                kind: FileKind::Directory,
+            }),
+            _ => Some(FileStat {
+                size: 0,
+                is_dir: false,
+                kind: FileKind::CharDevice,
+            }),
+        }
+    }
+
+    pub fn getdents(&self, cursor: usize, dirents: &mut [Dirent]) -> Option<usize> {
+        match self {
+            Resource::Dir(path) => filesystem::getdents(path, cursor, dirents),
+            _ => None,
+        }
+    }
+
+    pub fn seek(&mut self, pos: SeekFrom) -> Option<usize> {
+        match self {
+            Resource::File(file) => file.seek(pos).ok(),
+            Resource::ProcMem(mem) => mem.seek(pos),
+            // console, pipe and null devices are not seekable
+            _ => None,
+        }
+    }
+
+    /// Read a regular file at `offset`, the offset of the fd is kept
+    pub fn read_at(&mut self, offset: usize, buf: &mut [u8]) -> Option<usize> {
+        match self {
+            Resource::File(file) => file.read_at(offset, buf).ok(),
+            _ => None,
+        }
+    }
+
+    /// Write a regular file at `offset`, the offset of the fd is kept
+    pub fn write_at(&mut self, offset: usize, buf: &[u8]) -> Option<usize> {
+        match self {
+            Resource::File(file) => file.write_at(offset, buf).ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether the resource is a regular file, and writable if `write`
+    pub fn is_file(&self, write: bool) -> bool {
+        match self {
+            Resource::File(file) => !write || file.is_writable(),
+            _ => false,
+        }
+    }
+
+    pub fn chmod(&mut self, mode: usize) -> Option<()> {
+        match self {
+            Resource::File(file) => file.chmod(mode).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn set_len(&mut self, len: usize) -> Option<()> {
+        match self {
+            Resource::File(file) => file.set_len(len).ok(),
+            _ => None,
+        }
+    }
+
+    /// Push the buffered data out, nothing to do for the others
+    pub fn sync(&mut self) -> Option<()> {
+        match self {
+            Resource::File(file) => file.flush().ok(),
+            Resource::Console(StdIO::Stdout | StdIO::Stderr) => {
+                if let Some(mut serial) = crate::serial::get_serial() {
+                    crate::serial::flush(&mut serial);
+                }
+                Some(())
+            }
+            _ => Some(()),
+        }
+    }
+
+    /// Adjust the readahead of a file, nothing to do for the others
+    pub fn advise(&mut self, advice: Advice) -> Option<()> {
+        match self {
+            Resource::File(file) => file.advise(advice).ok(),
+            _ => Some(()),
+        }
+    }
 }
 
 impl core::fmt::Debug for Resource {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Resource::File(file) => write!(f, "File({:?})", file),
+            Resource::Dir(path) => write!(f, "Dir({})", path),
             Resource::Console(stdio) => write!(f, "Console({:?})", stdio),
+            Resource::PipeReader(_) => write!(f, "PipeReader"),
+            Resource::PipeWriter(_) => write!(f, "PipeWriter"),
+            Resource::TeeReader(_) => write!(f, "TeeReader"),
+            Resource::TeeWriter(_) => write!(f, "TeeWriter"),
+            Resource::EventFd(event) => write!(f, "{:?}", event),
+            Resource::Capture(bytes) => write!(f, "Capture({})", bytes.len()),
+            Resource::ProcMem(mem) => write!(f, "{:?}", mem),
+            Resource::Watch(_) => write!(f, "Watch"),
             Resource::Null => write!(f, "Null"),
         }
     }
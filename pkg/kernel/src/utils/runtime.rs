@@ -1,4 +1,12 @@
 use boot::{BootInfo, RuntimeServices, Time};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The bits of the seconds of the day in `WALL_TIME`
+const SECONDS_BITS: u32 = 17;
+
+/// The seconds of the day read from the RTC, packed with the clock tick
+/// it is read at above `SECONDS_BITS`, `u64::MAX` if never read
+static WALL_TIME: AtomicU64 = AtomicU64::new(u64::MAX);
 
 pub struct UefiRuntime {
     runtime_service: &'static RuntimeServices,
@@ -20,6 +28,21 @@ impl UefiRuntime {
 
 guard_access_fn!(pub get_uefi_runtime(UEFI_RUNTIME: UefiRuntime));
 
+/// The seconds of the day, the RTC is read at most once per clock tick
+/// and the cached value is returned within the same tick
+pub fn wall_time() -> u64 {
+    let tick = crate::interrupt::read_counter();
+    let cached = WALL_TIME.load(Ordering::SeqCst);
+    if cached != u64::MAX && cached >> SECONDS_BITS == tick {
+        return cached & ((1 << SECONDS_BITS) - 1);
+    }
+
+    let time = get_uefi_runtime_for_sure().get_time();
+    let seconds = time.hour() as u64 * 3600 + time.minute() as u64 * 60 + time.second() as u64;
+    WALL_TIME.store(tick << SECONDS_BITS | seconds, Ordering::SeqCst);
+    seconds
+}
+
 pub fn init(boot_info: &'static BootInfo) {
     unsafe {
         init_UEFI_RUNTIME(UefiRuntime::new(boot_info));
@@ -0,0 +1,124 @@
+use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+use core::cmp::min;
+use spin::Mutex;
+
+/// The maximum number of bytes the slowest reader of a tee pipe
+/// can fall behind the writer, writes over it are short
+pub const TEE_CAPACITY: usize = 4096;
+/// The maximum number of read ends of a tee pipe
+pub const TEE_MAX_READERS: usize = 8;
+
+#[derive(Debug, Default)]
+struct TeeBuffer {
+    // the bytes not consumed by every reader yet
+    buf: VecDeque<u8>,
+    // the stream offset of the first byte in `buf`
+    start: u64,
+    // the stream offset each reader has read up to, `None` once closed
+    cursors: Vec<Option<u64>>,
+    writers: usize,
+}
+
+impl TeeBuffer {
+    fn readers(&self) -> usize {
+        self.cursors.iter().flatten().count()
+    }
+
+    /// Drop the bytes all the open read ends are past
+    fn reclaim(&mut self) {
+        let end = self.start + self.buf.len() as u64;
+        let min_cursor = self.cursors.iter().flatten().copied().min().unwrap_or(end);
+        let count = (min_cursor - self.start) as usize;
+        self.buf.drain(..count);
+        self.start = min_cursor;
+    }
+}
+
+/// A read end of a tee pipe, with a cursor of its own
+#[derive(Debug)]
+pub struct TeeReader {
+    buffer: Arc<Mutex<TeeBuffer>>,
+    index: usize,
+}
+
+/// The write end of a tee pipe
+#[derive(Debug)]
+pub struct TeeWriter(Arc<Mutex<TeeBuffer>>);
+
+/// Create a tee pipe with `readers` read ends, each of which
+/// receives every byte written to the write end
+pub fn tee_pipe(readers: usize) -> (TeeWriter, Vec<TeeReader>) {
+    let buffer = Arc::new(Mutex::new(TeeBuffer {
+        buf: VecDeque::with_capacity(TEE_CAPACITY),
+        start: 0,
+        cursors: alloc::vec![Some(0); readers],
+        writers: 1,
+    }));
+
+    let readers = (0..readers)
+        .map(|index| TeeReader {
+            buffer: buffer.clone(),
+            index,
+        })
+        .collect();
+    (TeeWriter(buffer), readers)
+}
+
+impl TeeReader {
+    /// Read the bytes after the cursor, returns 0 if it has caught up
+    pub fn read(&self, buf: &mut [u8]) -> usize {
+        let mut tee = self.buffer.lock();
+        let cursor = tee.cursors[self.index].unwrap();
+        let offset = (cursor - tee.start) as usize;
+        let count = min(buf.len(), tee.buf.len() - offset);
+        for (dst, src) in buf.iter_mut().zip(tee.buf.range(offset..offset + count)) {
+            *dst = *src;
+        }
+        tee.cursors[self.index] = Some(cursor + count as u64);
+        tee.reclaim();
+        count
+    }
+
+    /// Whether a read would return immediately,
+    /// i.e. there are unread bytes or the write end is closed
+    pub fn is_readable(&self) -> bool {
+        let tee = self.buffer.lock();
+        let end = tee.start + tee.buf.len() as u64;
+        tee.cursors[self.index] != Some(end) || tee.writers == 0
+    }
+}
+
+impl TeeWriter {
+    /// Write as many bytes as the slowest reader leaves room for,
+    /// returns `None` if all the read ends have been closed
+    pub fn write(&self, buf: &[u8]) -> Option<usize> {
+        let mut tee = self.0.lock();
+        if tee.readers() == 0 {
+            return None;
+        }
+        let count = min(buf.len(), TEE_CAPACITY - tee.buf.len());
+        tee.buf.extend(&buf[..count]);
+        Some(count)
+    }
+
+    /// Whether a write would return immediately,
+    /// i.e. there is free space or all the read ends are closed
+    pub fn is_writable(&self) -> bool {
+        let tee = self.0.lock();
+        tee.buf.len() < TEE_CAPACITY || tee.readers() == 0
+    }
+}
+
+impl Drop for TeeReader {
+    fn drop(&mut self) {
+        let mut tee = self.buffer.lock();
+        tee.cursors[self.index] = None;
+        tee.reclaim();
+    }
+}
+
+impl Drop for TeeWriter {
+    fn drop(&mut self) {
+        self.0.lock().writers -= 1;
+    }
+}
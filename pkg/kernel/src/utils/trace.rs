@@ -0,0 +1,82 @@
+use spin::Mutex;
+use syscall_def::TraceEntry;
+
+use crate::proc::ProcessId;
+
+/// The number of markers kept, the oldest ones are overwritten first
+pub const TRACE_RING_SIZE: usize = 64;
+
+/// The markers left by all the processes, in the order they are made
+static TRACE_RING: Mutex<TraceRing> = Mutex::new(TraceRing::new());
+
+struct TraceRing {
+    entries: [TraceEntry; TRACE_RING_SIZE],
+    // where the next entry goes
+    next: usize,
+    len: usize,
+}
+
+impl TraceRing {
+    const fn new() -> Self {
+        Self {
+            entries: [TraceEntry {
+                tick: 0,
+                pid: 0,
+                tag: 0,
+                value: 0,
+            }; TRACE_RING_SIZE],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, entry: TraceEntry) {
+        self.entries[self.next] = entry;
+        self.next = (self.next + 1) % TRACE_RING_SIZE;
+        self.len = (self.len + 1).min(TRACE_RING_SIZE);
+    }
+
+    /// The entries from the oldest to the latest
+    fn iter(&self) -> impl Iterator<Item = &TraceEntry> {
+        let start = (self.next + TRACE_RING_SIZE - self.len) % TRACE_RING_SIZE;
+        (0..self.len).map(move |i| &self.entries[(start + i) % TRACE_RING_SIZE])
+    }
+}
+
+/// Append a marker of `pid`, the lock is held only for the copy
+pub fn mark(pid: ProcessId, tag: u32, value: u64) {
+    let entry = TraceEntry {
+        tick: crate::interrupt::read_counter(),
+        pid: pid.0,
+        tag,
+        value,
+    };
+    x86_64::instructions::interrupts::without_interrupts(|| TRACE_RING.lock().push(entry));
+}
+
+/// Copy the latest markers into `buf`, oldest first,
+/// returns the number copied
+pub fn copy_to(buf: &mut [TraceEntry]) -> usize {
+    x86_64::instructions::interrupts::without_interrupts(|| {
+        let ring = TRACE_RING.lock();
+        let skip = ring.len.saturating_sub(buf.len());
+        let mut count = 0;
+        for (dst, src) in buf.iter_mut().zip(ring.iter().skip(skip)) {
+            *dst = *src;
+            count += 1;
+        }
+        count
+    })
+}
+
+/// Print the markers to the console, oldest first,
+/// returns the number printed
+pub fn dump() -> usize {
+    let mut buf = [TraceEntry::default(); TRACE_RING_SIZE];
+    let count = copy_to(&mut buf);
+    println!("{:>10} {:>5} {:>10} {:>18}", "Tick", "Pid", "Tag", "Value");
+    for entry in &buf[..count] {
+        println!("{:>10} {:>5} {:#10x} {:#18x}", entry.tick, entry.pid, entry.tag, entry.value);
+    }
+    count
+}
@@ -0,0 +1,115 @@
+//! Time Stamp Counter
+//!
+//! calibrated at boot against the PIT, and then against the clock ticks
+//!
+//! reference: <https://wiki.osdev.org/Programmable_Interval_Timer>
+
+use crate::interrupt::read_counter;
+use core::sync::atomic::{AtomicU64, Ordering};
+use x86_64::instructions::port::Port;
+
+/// The input frequency of the PIT in Hz
+const PIT_FREQUENCY: u64 = 1_193_182;
+/// Calibrate against the PIT for 1 / `PIT_CALIBRATE_DIV` seconds
+const PIT_CALIBRATE_DIV: u64 = 100;
+/// Calibrate against the clock for this number of ticks
+const TICK_CALIBRATE_COUNT: u64 = 4;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+static TSC_PER_SEC: AtomicU64 = AtomicU64::new(0);
+static TSC_PER_TICK: AtomicU64 = AtomicU64::new(0);
+
+#[inline]
+pub fn rdtsc() -> u64 {
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+/// Calibrate the TSC, should be called with the clock interrupt enabled
+pub fn init() {
+    let per_sec = calibrate_with_pit();
+    TSC_PER_SEC.store(per_sec, Ordering::SeqCst);
+
+    // wait for a fresh tick to start with
+    let start_tick = read_counter();
+    while read_counter() == start_tick {
+        core::hint::spin_loop();
+    }
+    let start_tick = read_counter();
+    let start = rdtsc();
+    while read_counter() < start_tick + TICK_CALIBRATE_COUNT {
+        core::hint::spin_loop();
+    }
+    let per_tick = (rdtsc() - start) / TICK_CALIBRATE_COUNT;
+    TSC_PER_TICK.store(per_tick, Ordering::SeqCst);
+
+    info!(
+        "TSC Calibrated: {} MHz, {} us per tick.",
+        per_sec / 1_000_000,
+        tsc_to_ns(per_tick) / 1000
+    );
+}
+
+/// Count the TSC cycles until the PIT channel 2 finishes a one-shot countdown
+fn calibrate_with_pit() -> u64 {
+    let mut gate: Port<u8> = Port::new(0x61);
+    let mut command: Port<u8> = Port::new(0x43);
+    let mut channel2: Port<u8> = Port::new(0x42);
+    let latch = PIT_FREQUENCY / PIT_CALIBRATE_DIV;
+
+    unsafe {
+        // enable the gate of channel 2, disable the speaker
+        let value = gate.read();
+        gate.write((value & !0x02) | 0x01);
+        // channel 2, lobyte/hibyte, mode 0 (interrupt on terminal count)
+        command.write(0b1011_0000);
+        channel2.write((latch & 0xff) as u8);
+        channel2.write((latch >> 8) as u8);
+
+        let start = rdtsc();
+        // the output of channel 2 goes high once the count reaches zero
+        while gate.read() & 0x20 == 0 {
+            core::hint::spin_loop();
+        }
+        (rdtsc() - start) * PIT_CALIBRATE_DIV
+    }
+}
+
+/// TSC cycles per second, 0 if not calibrated
+#[inline]
+pub fn tsc_per_sec() -> u64 {
+    TSC_PER_SEC.load(Ordering::SeqCst)
+}
+
+/// TSC cycles per clock tick, 0 if not calibrated
+#[inline]
+pub fn tsc_per_tick() -> u64 {
+    TSC_PER_TICK.load(Ordering::SeqCst)
+}
+
+/// Clock ticks per second, 0 if not calibrated
+pub fn ticks_per_sec() -> u64 {
+    match tsc_per_tick() {
+        0 => 0,
+        per_tick => tsc_per_sec() / per_tick,
+    }
+}
+
+pub fn ns_to_tsc(ns: u64) -> u64 {
+    (ns as u128 * tsc_per_sec() as u128 / NANOS_PER_SEC as u128) as u64
+}
+
+pub fn tsc_to_ns(tsc: u64) -> u64 {
+    match tsc_per_sec() {
+        0 => 0,
+        per_sec => (tsc as u128 * NANOS_PER_SEC as u128 / per_sec as u128) as u64,
+    }
+}
+
+/// Spin for at least `cycles` TSC cycles
+pub fn busy_wait(cycles: u64) {
+    let start = rdtsc();
+    while rdtsc() - start < cycles {
+        core::hint::spin_loop();
+    }
+}
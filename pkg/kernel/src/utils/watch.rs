@@ -0,0 +1,97 @@
+use crate::filesystem::normalize_path;
+use alloc::{
+    collections::VecDeque,
+    string::String,
+    sync::{Arc, Weak},
+    vec::Vec,
+};
+use spin::Mutex;
+use syscall_def::{WatchEvent, DIRENT_NAME_MAX, WATCH_OVERFLOW};
+
+/// The maximum number of events queued in a watch fd
+pub const WATCH_QUEUE_SIZE: usize = 16;
+
+/// Every watch alive with its normalized path, the closed ones are
+/// pruned on the next `add`
+static WATCHES: Mutex<Vec<(String, Weak<Mutex<WatchQueue>>)>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Default)]
+struct WatchQueue {
+    events: VecDeque<WatchEvent>,
+    // the events are dropped until the reader takes the overflow event
+    overflowed: bool,
+}
+
+impl WatchQueue {
+    fn push(&mut self, event: WatchEvent) {
+        if self.overflowed || self.events.back() == Some(&event) {
+            return;
+        }
+        if self.events.len() == WATCH_QUEUE_SIZE {
+            self.overflowed = true;
+            return;
+        }
+        self.events.push_back(event);
+    }
+}
+
+/// The read end of a watch on a file or a dir, like a tiny `inotify`
+#[derive(Debug)]
+pub struct Watch(Arc<Mutex<WatchQueue>>);
+
+impl Watch {
+    /// Take the earliest event, the dropped ones are reported by a
+    /// `WATCH_OVERFLOW` after the queued ones, `None` if nothing happened
+    pub fn read(&self) -> Option<WatchEvent> {
+        let mut queue = self.0.lock();
+        if let Some(event) = queue.events.pop_front() {
+            return Some(event);
+        }
+        if !queue.overflowed {
+            return None;
+        }
+        queue.overflowed = false;
+        Some(WatchEvent {
+            kind: WATCH_OVERFLOW,
+            ..WatchEvent::default()
+        })
+    }
+
+    pub fn is_readable(&self) -> bool {
+        let queue = self.0.lock();
+        !queue.events.is_empty() || queue.overflowed
+    }
+}
+
+/// Watch the file or the dir at `path`, the caller checks it exists
+pub fn add(path: &str) -> Watch {
+    let queue = Arc::new(Mutex::new(WatchQueue::default()));
+    let mut watches = WATCHES.lock();
+    watches.retain(|(_, watch)| watch.strong_count() > 0);
+    watches.push((normalize_path(path), Arc::downgrade(&queue)));
+    Watch(queue)
+}
+
+/// Tell the watches of the file at `path` and of its parent dir
+/// that the file is changed, `kind` is one of the `WATCH_*` events
+pub fn notify(path: &str, kind: u8) {
+    let path = normalize_path(path);
+    let (parent, name) = path.rsplit_once('/').unwrap_or(("", &path));
+
+    let mut event = WatchEvent {
+        kind,
+        ..WatchEvent::default()
+    };
+    let len = core::cmp::min(name.len(), DIRENT_NAME_MAX);
+    event.name[..len].copy_from_slice(&name.as_bytes()[..len]);
+    event.name_len = len as u8;
+
+    for (watched, watch) in WATCHES.lock().iter() {
+        if *watched != path && watched != parent {
+            continue;
+        }
+        if let Some(queue) = watch.upgrade() {
+            queue.lock().push(event);
+        }
+    }
+}
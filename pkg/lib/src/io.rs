@@ -35,6 +35,8 @@ impl Stdin {
                             line.pop();
                             sys_write(1, "\x08\x20\x08".as_bytes());
                         }
+                        // no line editing with the special keys yet
+                        KEY_UP..=KEY_DELETE => {}
                         _ => {
                             line.push(c as char);
                             sys_write(1, &mut [c]);
@@ -52,7 +54,7 @@ impl Stdout {
     }
 
     pub fn write(&self, s: &str) {
-        sys_write(1, s.as_bytes());
+        sys_write_all(1, s.as_bytes());
     }
 }
 
@@ -62,7 +64,7 @@ impl Stderr {
     }
 
     pub fn write(&self, s: &str) {
-        sys_write(2, s.as_bytes());
+        sys_write_all(2, s.as_bytes());
     }
 }
 
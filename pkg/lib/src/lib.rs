@@ -30,6 +30,18 @@ pub fn init() {
     crate::allocator::init();
 }
 
+/// The guard compared by the code built with `-Z stack-protector`,
+/// installed from `sys_stack_canary` by `entry!` before anything runs
+#[no_mangle]
+pub static mut __stack_chk_guard: usize = 0;
+
+/// Called by the code built with `-Z stack-protector` once a canary
+/// on the stack no longer matches `__stack_chk_guard`
+#[no_mangle]
+pub extern "C" fn __stack_chk_fail() -> ! {
+    sys_stack_chk_fail()
+}
+
 #[macro_export]
 macro_rules! print {
     ($($arg:tt)*) => ($crate::_print(format_args!($($arg)*)));
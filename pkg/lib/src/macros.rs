@@ -6,9 +6,26 @@ macro_rules! entry {
     ($fn:ident) => {
         #[export_name = "_start"]
         pub extern "C" fn __impl_start() {
+            // it never returns, so its own canary is not checked after the change
+            unsafe { lib::__stack_chk_guard = lib::sys_stack_canary() as usize };
             lib::init(); // THIS LINE IS NEW IN LAB 7
             let ret = $fn();
-            lib::sys_exit(ret);
+            // the threads left running end with the main function
+            lib::sys_exit_group(ret);
+        }
+    };
+}
+
+/// Report to the kernel via `sys_debug` if the condition fails,
+/// the code defaults to the line number, and the program keeps running
+#[macro_export]
+macro_rules! debug_assert_kernel {
+    ($cond:expr) => {
+        $crate::debug_assert_kernel!($cond, line!() as usize)
+    };
+    ($cond:expr, $code:expr) => {
+        if !$cond {
+            $crate::sys_debug($code);
         }
     };
 }
@@ -1,42 +1,252 @@
+use alloc::{string::String, vec, vec::Vec};
+use core::sync::atomic::AtomicU32;
 use core::time::Duration;
 
-use syscall_def::Syscall;
+use syscall_def::{IoVec, SpawnAttr, Syscall};
 
+pub use syscall_def::{
+    AuditEntry, BlockReason, Dirent, DiskStats, ElfInfo, FbInfo, FileKind, FileStat, FrameStats,
+    Limit, MemInfo, PageMapping, RUsage, SchedLatency, SchedPolicy, SerialConfig, StatFsInfo, Stats,
+    SysError, TraceEntry, VdsoData, WakeSource, WatchEvent, ARG_MAX, AUDIT_BLOCKED, AUDIT_STOPPED,
+    AUDIT_WOKEN, CLONE_ENV, CLONE_FILES, CLONE_SIGHAND, CLONE_SYSVSEM, CLONE_VM, DIRENT_NAME_MAX,
+    FB_FORMAT_BGR, FB_FORMAT_RGB, FORK_RATE_LIMIT, FORK_RATE_WINDOW, F_GETFL, F_SETFL, IOV_MAX,
+    KEY_DELETE, KEY_DOWN, KEY_END, KEY_HOME, KEY_LEFT, KEY_RIGHT, KEY_UP, LOAD_SHIFT, O_CREAT,
+    O_EXCL, O_NONBLOCK, PAGE_ACCESSED, PAGE_COW, PAGE_DIRTY, PAGE_NO_EXECUTE, PAGE_PRESENT,
+    PAGE_USER, PAGE_WRITABLE, PARITY_EVEN, PARITY_NONE, PARITY_ODD, READ_NONBLOCK, RLIMIT_AS,
+    RLIMIT_CPU, RLIMIT_NOFILE, RLIMIT_NPROC, RLIM_INFINITY, RUSAGE_SELF, SPIN_HINT_LIMIT,
+    VDSO_ADDR, WATCH_CREATE, WATCH_OVERFLOW, WATCH_UNLINK, WATCH_WRITE,
+};
+pub use syscall_def::{
+    sig_bit, SigAction, NSIG, SA_RESETHAND, SA_RESTART, SIGALRM, SIGCHLD, SIGKILL, SIGSTOP,
+    SIGUSR1, SIGUSR2, SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK,
+};
+
+/// Read into the buffers in order, like one read into them joined.
+///
+/// Returns the total bytes read, which is short once a buffer is not filled.
 #[inline(always)]
-pub fn sys_write(fd: u8, buf: &[u8]) -> Option<usize> {
+pub fn sys_readv(fd: u8, bufs: &mut [&mut [u8]]) -> Option<usize> {
+    let iovecs: Vec<IoVec> = bufs
+        .iter_mut()
+        .map(|buf| IoVec {
+            base: buf.as_mut_ptr() as usize,
+            len: buf.len(),
+        })
+        .collect();
+    let ret = syscall!(
+        Syscall::Readv,
+        fd as u64,
+        iovecs.as_ptr() as u64,
+        iovecs.len() as u64
+    ) as isize;
+    usize::try_from(ret).ok()
+}
+
+/// Write the buffers in order, like one write of them joined,
+/// e.g. a header and a body without copying them together.
+///
+/// Returns the total bytes written, which is short once a buffer is not taken entirely.
+#[inline(always)]
+pub fn sys_writev(fd: u8, bufs: &[&[u8]]) -> Option<usize> {
+    let iovecs: Vec<IoVec> = bufs
+        .iter()
+        .map(|buf| IoVec {
+            base: buf.as_ptr() as usize,
+            len: buf.len(),
+        })
+        .collect();
     let ret = syscall!(
+        Syscall::Writev,
+        fd as u64,
+        iovecs.as_ptr() as u64,
+        iovecs.len() as u64
+    ) as isize;
+    usize::try_from(ret).ok()
+}
+
+#[inline(always)]
+pub fn sys_write(fd: u8, buf: &[u8]) -> Option<usize> {
+    sys_write_result(fd, buf).ok()
+}
+
+/// Like `sys_write`, but tells why it fails, e.g. `SysError::BadFd`
+/// if the fd is not open or cannot be written.
+#[inline(always)]
+pub fn sys_write_result(fd: u8, buf: &[u8]) -> SyscallResult<usize> {
+    syscall_result(syscall!(
         Syscall::Write,
         fd as u64,
         buf.as_ptr() as u64,
         buf.len() as u64
-    ) as isize;
-    if ret.is_negative() {
-        None
-    } else {
-        Some(ret as usize)
+    ) as isize)
+}
+
+/// Write the whole buffer, retrying after the short writes, e.g. to a full
+/// pipe, returns `None` if the fd fails before everything is written.
+pub fn sys_write_all(fd: u8, buf: &[u8]) -> Option<usize> {
+    let mut written = 0;
+    while written < buf.len() {
+        match sys_write(fd, &buf[written..])? {
+            // let the reader drain the pipe
+            0 => sys_yield(),
+            len => written += len,
+        }
     }
+    Some(written)
 }
 
 #[inline(always)]
 pub fn sys_read(fd: u8, buf: &mut [u8]) -> Option<usize> {
-    let ret = syscall!(
+    sys_read_result(fd, buf).ok()
+}
+
+/// Like `sys_read`, but tells why it fails, e.g. `SysError::BadFd`
+/// if the fd is not open or cannot be read.
+#[inline(always)]
+pub fn sys_read_result(fd: u8, buf: &mut [u8]) -> SyscallResult<usize> {
+    syscall_result(syscall!(
         Syscall::Read,
         fd as u64,
         buf.as_ptr() as u64,
         buf.len() as u64
-    ) as isize;
-    if ret.is_negative() {
-        None
-    } else {
-        Some(ret as usize)
+    ) as isize)
+}
+
+/// Read whatever is buffered in the fd without waiting, e.g. all
+/// the pending keys of stdin, 0 if there is none, a negated `SysError` on errors.
+/// A fd with `O_NONBLOCK` fails with `SysError::Again` instead of reading 0.
+///
+/// Stdin is raw in the kernel, the line editing of `Stdin::read_line`
+/// is left to the caller.
+#[inline(always)]
+pub fn sys_try_read(fd: u8, buf: &mut [u8]) -> isize {
+    syscall!(
+        Syscall::Read,
+        fd as u64 | READ_NONBLOCK as u64,
+        buf.as_ptr() as u64,
+        buf.len() as u64
+    ) as isize
+}
+
+/// Readiness of a fd reported by `sys_poll`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PollFlags {
+    pub readable: bool,
+    pub writable: bool,
+}
+
+impl From<u8> for PollFlags {
+    fn from(flags: u8) -> Self {
+        Self {
+            readable: flags & 1 != 0,
+            writable: flags & 2 != 0,
+        }
     }
 }
 
+/// Check which fds can be read or written without blocking.
+#[inline(always)]
+pub fn sys_poll(fds: &[u8]) -> Vec<PollFlags> {
+    let mut flags = vec![0u8; fds.len()];
+    syscall!(
+        Syscall::Poll,
+        fds.as_ptr() as u64,
+        fds.len() as u64,
+        flags.as_mut_ptr() as u64
+    );
+    flags.into_iter().map(PollFlags::from).collect()
+}
+
+pub const SEEK_SET: u8 = 0;
+pub const SEEK_CUR: u8 = 1;
+pub const SEEK_END: u8 = 2;
+
+#[inline(always)]
+pub fn sys_seek(fd: u8, offset: isize, whence: u8) -> isize {
+    syscall!(Syscall::Seek, fd as u64, offset as u64, whence as u64) as isize
+}
+
+/// Truncate or extend the file to `len` bytes, the extended part reads
+/// as zeros, returns false if the fd is not a regular file.
+#[inline(always)]
+pub fn sys_ftruncate(fd: u8, len: usize) -> bool {
+    syscall!(Syscall::Ftruncate, fd as u64, len as u64) == 0
+}
+
+/// Push the buffered data of `fd` out before returning,
+/// a no-op for the fds with nothing buffered, false for a bad fd.
+#[inline(always)]
+pub fn sys_fsync(fd: u8) -> bool {
+    syscall!(Syscall::Fsync, fd as u64) == 0
+}
+
+/// No hint on how the fd is read, see `sys_fadvise`.
+pub const POSIX_FADV_NORMAL: usize = 0;
+/// The fd is read at random offsets, nothing is read ahead.
+pub const POSIX_FADV_RANDOM: usize = 1;
+/// The fd is read from start to end, the following blocks are read ahead.
+pub const POSIX_FADV_SEQUENTIAL: usize = 2;
+
+/// Hint how `fd` is going to be read with one of `POSIX_FADV_*`, which
+/// sets the readahead of a file, a no-op for the other fds.
+/// Returns false for a bad fd or hint.
+#[inline(always)]
+pub fn sys_fadvise(fd: u8, hint: usize) -> bool {
+    syscall!(Syscall::Fadvise, fd as u64, hint as u64) == 0
+}
+
 #[inline(always)]
 pub fn sys_wait_pid(pid: u16) -> isize {
     syscall!(Syscall::WaitPid, pid as u64) as isize
 }
 
+/// Like `sys_wait_pid`, `SysError::NotFound` for self or a process
+/// that is not a child.
+///
+/// NOTE: the exit code is not checked, so a child exiting with
+/// `SysError::NotFound.code()` looks like one not found
+#[inline(always)]
+pub fn sys_wait_pid_result(pid: u16) -> SyscallResult<isize> {
+    match sys_wait_pid(pid) {
+        code if code == SysError::NotFound.code() => Err(SysError::NotFound),
+        code => Ok(code),
+    }
+}
+
+/// Get the resource usage of the caller so far, e.g. the switches away
+/// as it blocked or yielded against the ones as it was preempted,
+/// `who` is `RUSAGE_SELF`, the only one supported.
+#[inline(always)]
+pub fn sys_getrusage(who: isize) -> SyscallResult<RUsage> {
+    let mut usage = RUsage::default();
+    syscall_result(syscall!(
+        Syscall::Getrusage,
+        who as u64,
+        &mut usage as *mut RUsage as u64
+    ) as isize)?;
+    Ok(usage)
+}
+
+/// Wait for the child like `sys_wait_pid`, returns its exit code
+/// with the ticks and the peak memory it has used.
+#[inline(always)]
+pub fn sys_wait4(pid: u16) -> (isize, RUsage) {
+    let mut usage = RUsage::default();
+    let code = syscall!(Syscall::Wait4, pid as u64, &mut usage as *mut RUsage as u64) as isize;
+    (code, usage)
+}
+
+/// Wait for whichever child exits first, returns its pid & exit code,
+/// or `None` if there is no child left to wait for.
+#[inline(always)]
+pub fn sys_waitany() -> Option<(u16, isize)> {
+    let mut code = 0isize;
+    match syscall!(Syscall::WaitAny, &mut code as *mut isize as u64) as isize {
+        -1 => None,
+        pid => Some((pid as u16, code)),
+    }
+}
+
 #[inline(always)]
 pub fn sys_list_app() {
     syscall!(Syscall::ListApp);
@@ -52,86 +262,1771 @@ pub fn sys_stat() {
     syscall!(Syscall::Stat);
 }
 
+/// Get the ELF metadata of the embedded app, `None` if the app is unknown.
 #[inline(always)]
-pub fn sys_allocate(layout: &core::alloc::Layout) -> *mut u8 {
-    syscall!(Syscall::Allocate, layout as *const _) as *mut u8
+pub fn sys_elf_info(name: &str) -> Option<ElfInfo> {
+    let mut info = ElfInfo::default();
+    let ret = syscall!(
+        Syscall::ElfInfo,
+        name.as_ptr() as u64,
+        name.len() as u64,
+        &mut info as *mut ElfInfo as u64
+    ) as isize;
+    (ret == 0).then_some(info)
 }
 
+/// Get the number of page faults of the caller,
+/// and how many of them grew the stack.
 #[inline(always)]
-pub fn sys_deallocate(ptr: *mut u8, layout: &core::alloc::Layout) -> usize {
-    syscall!(Syscall::Deallocate, ptr, layout as *const _)
+pub fn sys_page_faults() -> (usize, usize) {
+    let mut counts = [0usize; 2];
+    syscall!(Syscall::PageFaults, counts.as_mut_ptr() as u64);
+    (counts[0], counts[1])
 }
 
+/// Get the ticks, page faults & switches of the caller since the last
+/// reset, then zero them to measure the next phase. The tick budget
+/// and `sys_wait4` still see the whole lifetime.
 #[inline(always)]
-pub fn sys_spawn(path: &str) -> u16 {
-    syscall!(Syscall::Spawn, path.as_ptr() as u64, path.len() as u64) as u16
+pub fn sys_reset_stats() -> Stats {
+    let mut stats = Stats::default();
+    syscall!(Syscall::ResetStats, &mut stats as *mut Stats as u64);
+    stats
 }
 
+/// Get the physical memory of the machine, see `MemInfo::used_bytes`
+/// and `MemInfo::free_bytes`.
 #[inline(always)]
-pub fn sys_get_pid() -> u16 {
-    syscall!(Syscall::GetPid) as u16
+pub fn sys_meminfo() -> MemInfo {
+    let mut info = MemInfo::default();
+    syscall!(Syscall::MemInfo, &mut info as *mut MemInfo as u64);
+    info
 }
 
+/// Get how many times the scheduler found nothing ready and idled,
+/// and how many times the idle kernel was woken by an interrupt.
 #[inline(always)]
-pub fn sys_exit(code: isize) -> ! {
-    syscall!(Syscall::Exit, code as u64);
-    unreachable!("This process should be terminated by now.")
+pub fn sys_idle_stats() -> (u64, u64) {
+    let mut stats = [0u64; 2];
+    syscall!(Syscall::IdleStats, stats.as_mut_ptr() as u64);
+    (stats[0], stats[1])
 }
 
+/// Get how many times the kernel ran its maintenance hooks while idle.
 #[inline(always)]
-pub fn sys_print_info(pid: u16) -> u16 {
-    syscall!(Syscall::PrintInfo, pid as u64) as u16
+pub fn sys_idle_hook_runs() -> u64 {
+    syscall!(Syscall::IdleHookRuns) as u64
+}
+
+/// A handler of child exits, called with the pid of the exited child.
+pub type ChildHandler = fn(u16);
+
+/// The entry called by the kernel, it returns to where the process
+/// was interrupted by `SigReturn` after the handler.
+extern "C" fn child_handler_entry(handler: usize, pid: usize) -> ! {
+    let handler: ChildHandler = unsafe { core::mem::transmute(handler) };
+    handler(pid as u16);
+    syscall!(Syscall::SigReturn);
+    unreachable!("Returned from the child exit handler");
 }
 
+/// Run `handler` on each child exit like `SIGCHLD`, `None` to remove it.
+/// It interrupts the caller anywhere, even in `sys_wait_pid`,
+/// so it should only touch atomics. Forked children do not inherit it.
 #[inline(always)]
-pub fn sys_time() -> u64 {
-    syscall!(Syscall::Time) as u64
+pub fn sys_sigchld(handler: Option<ChildHandler>) -> bool {
+    let ret = match handler {
+        Some(handler) => syscall!(
+            Syscall::SigChld,
+            child_handler_entry as usize as u64,
+            handler as usize as u64
+        ),
+        None => syscall!(Syscall::SigChld, 0),
+    };
+    ret == 0
+}
+
+/// A handler of `sys_sigaction`, called with the signal number.
+pub type SigHandler = fn(usize);
+
+/// The entry called by the kernel, it returns to where the process
+/// was interrupted by `SigReturn` after the handler.
+extern "C" fn sig_handler_entry(handler: usize, sig: usize) -> ! {
+    let handler: SigHandler = unsafe { core::mem::transmute(handler) };
+    handler(sig);
+    syscall!(Syscall::SigReturn);
+    unreachable!("Returned from the signal handler");
+}
+
+/// The action running `handler` for a signal, with `flags` like `SA_RESTART`,
+/// `SigAction::default()` is the default action, which kills the process.
+pub fn sig_action(handler: SigHandler, flags: usize) -> SigAction {
+    SigAction {
+        entry: sig_handler_entry as usize,
+        data: handler as usize,
+        flags,
+    }
 }
 
+/// Set the action of `sig`, returns the action replaced. Like the one of
+/// `sys_sigchld`, the handler interrupts the caller anywhere, so it should only
+/// touch atomics. Forked children inherit it, it is reset on exec.
+/// `SIGKILL`, `SIGSTOP` & `SIGCHLD` fail with `SysError::Inval`.
 #[inline(always)]
-pub fn sys_fork() -> u16 {
-    syscall!(Syscall::Fork) as u16
+pub fn sys_sigaction(sig: usize, action: &SigAction) -> SyscallResult<SigAction> {
+    let mut old = SigAction::default();
+    syscall_result(syscall!(
+        Syscall::Sigaction,
+        sig as u64,
+        action as *const SigAction as u64,
+        &mut old as *mut SigAction as u64
+    ) as isize)?;
+    Ok(old)
 }
 
+/// Send `sig` to the process `pid`, which runs its handler, or is killed
+/// with the exit code `-sig` if it has none. A masked signal waits until
+/// it is unmasked, a signal sent to self is handled before this returns.
 #[inline(always)]
-pub fn sys_new_sem(key: u32, value: usize) -> bool {
-    syscall!(Syscall::Sem, 0, key as usize, value) == 0
+pub fn sys_tkill(pid: u16, sig: usize) -> SyscallResult<()> {
+    syscall_result(syscall!(Syscall::Tkill, pid as u64, sig as u64) as isize).map(|_| ())
 }
 
+/// Change the masked signals by `how`, e.g. `SIG_BLOCK` with `sig_bit(SIGCHLD)`,
+/// returns the old mask. A masked child exit stays pending, and the handler
+/// runs before this returns once it is unmasked. Forked children inherit it.
 #[inline(always)]
-pub fn sys_remove_sem(key: u32) -> bool {
-    syscall!(Syscall::Sem, 1, key as usize) == 0
+pub fn sys_sigprocmask(how: usize, set: u64) -> Option<u64> {
+    let mut old = 0u64;
+    match syscall!(Syscall::SigProcMask, how as u64, set, &mut old as *mut u64 as u64) {
+        0 => Some(old),
+        _ => None,
+    }
 }
 
+/// Get the masked signals.
 #[inline(always)]
-pub fn sys_sem_signal(key: u32) -> bool {
-    syscall!(Syscall::Sem, 2, key as usize) == 0
+pub fn sys_sigmask() -> u64 {
+    sys_sigprocmask(SIG_BLOCK, 0).unwrap_or(0)
 }
 
+/// Whether any child has exited since the last call, with or without a handler.
 #[inline(always)]
-pub fn sys_sem_wait(key: u32) -> bool {
-    syscall!(Syscall::Sem, 3, key as usize) == 0
+pub fn sys_child_exited() -> bool {
+    syscall!(Syscall::ChildExited) != 0
 }
 
+/// Get the clock ticks since `pid` (0 for the caller) is created,
+/// forked children count from the fork.
 #[inline(always)]
-pub fn sys_open_file(path: &str) -> u8 {
-    syscall!(Syscall::Open, path.as_ptr() as u64, path.len() as u64) as u8
+pub fn sys_proc_age(pid: u16) -> Option<u64> {
+    match syscall!(Syscall::ProcAge, pid as u64) as isize {
+        -1 => None,
+        age => Some(age as u64),
+    }
 }
 
+/// Get the usage of the physical frames,
+/// including how fragmented the recycled ones are.
 #[inline(always)]
-pub fn sys_close_file(fd: u8) -> bool {
-    syscall!(Syscall::Close, fd as u64) == 0
+pub fn sys_frame_stats() -> FrameStats {
+    let mut stats = FrameStats::default();
+    syscall!(Syscall::FrameStats, &mut stats as *mut FrameStats as u64);
+    stats
 }
 
+/// Get the read commands issued to the disk since boot,
+/// and the sectors transferred by them.
 #[inline(always)]
-pub fn sys_brk(addr: Option<usize>) -> Option<usize> {
-    const BRK_FAILED: usize = !0;
-    match syscall!(Syscall::Brk, addr.unwrap_or(0)) {
-        BRK_FAILED => None,
-        ret => Some(ret),
+pub fn sys_disk_stats() -> DiskStats {
+    let mut stats = DiskStats::default();
+    syscall!(Syscall::DiskStats, &mut stats as *mut DiskStats as u64);
+    stats
+}
+
+/// Get the largest pid handed out. The pid of a process is freed once it
+/// is reaped, and reused after all the free ones freed earlier.
+#[inline(always)]
+pub fn sys_max_pid() -> u16 {
+    syscall!(Syscall::MaxPid) as u16
+}
+
+/// Get the spawn depth of the caller and the maximum allowed,
+/// `sys_spawn` fails once the maximum is reached.
+#[inline(always)]
+pub fn sys_spawn_depth() -> (usize, usize) {
+    let mut depths = [0usize; 2];
+    syscall!(Syscall::SpawnDepth, depths.as_mut_ptr() as u64);
+    (depths[0], depths[1])
+}
+
+/// The exit code of a process killed by `sys_kill` or the watchdog.
+pub const KILLED_EXIT_CODE: isize = -9;
+/// The exit code of a process calling `sys_stack_chk_fail`.
+pub const STACK_CHK_EXIT_CODE: isize = -6;
+
+/// Kill `pid` (0 for the caller) once it runs more than `ticks` ticks,
+/// 0 for unlimited. Children inherit the budget.
+#[inline(always)]
+pub fn sys_set_tick_budget(pid: u16, ticks: usize) -> bool {
+    syscall!(Syscall::TickBudget, pid as u64, ticks as u64) == 0
+}
+
+/// Get the effective priority of `pid` (0 for the caller), lower runs
+/// first by `SchedPolicy::Priority`. It drops below the base priority
+/// while the process is ready but passed over by the scheduler.
+#[inline(always)]
+pub fn sys_get_priority(pid: u16) -> Option<usize> {
+    match syscall!(Syscall::GetPriority, pid as u64) as isize {
+        -1 => None,
+        priority => Some(priority as usize),
+    }
+}
+
+/// Set the base priority of `pid` (0 for the caller). The caller can
+/// only raise its own value, and set any value for its descendants.
+#[inline(always)]
+pub fn sys_set_priority(pid: u16, priority: usize) -> bool {
+    syscall!(Syscall::SetPriority, pid as u64, priority as u64) == 0
+}
+
+/// Get the limit of the resource of `pid` (0 for the caller) or a child,
+/// and replace it with `new` if given. Returns the old limit, `None` if
+/// not allowed, e.g. for a soft value over the hard one or a raised hard one.
+#[inline(always)]
+pub fn sys_prlimit(pid: u16, resource: usize, new: Option<Limit>) -> Option<Limit> {
+    let mut old = Limit::default();
+    let new_ptr = new.as_ref().map_or(0, |new| new as *const Limit as usize);
+    let limits = [new_ptr, &mut old as *mut Limit as usize];
+    match syscall!(Syscall::Prlimit, pid as u64, resource as u64, limits.as_ptr() as u64) {
+        0 => Some(old),
+        _ => None,
+    }
+}
+
+/// Get the CPUs `pid` (0 for the caller) may run on, bit n for CPU n.
+#[inline(always)]
+pub fn sys_get_affinity(pid: u16) -> Option<u64> {
+    let mut mask = 0u64;
+    match syscall!(Syscall::GetAffinity, pid as u64, &mut mask as *mut u64 as u64) {
+        0 => Some(mask),
+        _ => None,
+    }
+}
+
+/// Translate `addr` by the page table of the caller, returns the physical
+/// address and the flags of the page, `None` if it is not mapped yet.
+#[inline(always)]
+pub fn sys_virt_to_phys(addr: usize) -> Option<PageMapping> {
+    sys_virt_to_phys_of(0, addr)
+}
+
+/// Save the registers, the heap, the stack, the writable segments and the fd table
+/// of `pid` (0 for the caller) or a descendant not running, up to 256 pages.
+///
+/// Returns `Some(false)` once saved, and `Some(true)` when the caller
+/// resumes from its own checkpoint by `sys_restore`, like `setjmp`.
+#[inline(always)]
+pub fn sys_checkpoint(pid: u16) -> Option<bool> {
+    match syscall!(Syscall::Checkpoint, pid as u64) as isize {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+/// Reset `pid` (0 for the caller) or a descendant not running to its checkpoint,
+/// never returns for the caller on success.
+///
+/// Fails if there is no checkpoint, or the heap end has moved since.
+/// The fds opened since are closed, and the ones closed since are reopened
+/// if their resources are still open elsewhere.
+#[inline(always)]
+pub fn sys_restore(pid: u16) -> bool {
+    syscall!(Syscall::Restore, pid as u64) == 0
+}
+
+/// Translate `addr` by the page table of `pid` (0 for the caller) or a descendant.
+#[inline(always)]
+pub fn sys_virt_to_phys_of(pid: u16, addr: usize) -> Option<PageMapping> {
+    let mut mapping = PageMapping::default();
+    let ret = syscall!(
+        Syscall::VirtToPhys,
+        pid as u64,
+        addr as u64,
+        &mut mapping as *mut PageMapping as u64
+    );
+    match ret {
+        0 => Some(mapping),
+        _ => None,
+    }
+}
+
+/// Pin `pid` (0 for the caller) or a descendant to the CPUs in the mask,
+/// which must include an online one, e.g. CPU 0 on a single CPU.
+#[inline(always)]
+pub fn sys_set_affinity(pid: u16, mask: u64) -> bool {
+    syscall!(Syscall::SetAffinity, pid as u64, mask) == 0
+}
+
+/// Rename the caller as shown by `sys_stat`, e.g. a forked child of the
+/// shell named after its command. Names longer than 12 chars are
+/// truncated, returns the number of chars kept, `None` for an empty name.
+#[inline(always)]
+pub fn sys_set_proc_name(name: &str) -> Option<usize> {
+    match syscall!(Syscall::SetProcName, name.as_ptr() as u64, name.len() as u64) as isize {
+        -1 => None,
+        len => Some(len as usize),
+    }
+}
+
+/// Get the name of `pid` (0 for the caller) as shown by `sys_stat`,
+/// i.e. the app it runs since the last `sys_exec` unless renamed.
+#[inline(always)]
+pub fn sys_get_proc_name(pid: u16) -> Option<String> {
+    let mut buf = [0u8; 64];
+    let len = syscall!(
+        Syscall::GetProcName,
+        pid as u64,
+        buf.as_mut_ptr() as u64,
+        buf.len() as u64
+    ) as isize;
+    if len < 0 {
+        return None;
+    }
+    let len = (len as usize).min(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+/// Rename the caller like `sys_set_proc_name` through `Syscall::Prctl`,
+/// returns the number of chars kept, `None` for an empty name.
+#[inline(always)]
+pub fn sys_prctl_set_name(name: &str) -> Option<usize> {
+    let ret = syscall!(
+        Syscall::Prctl,
+        syscall_def::PR_SET_NAME as u64,
+        name.as_ptr() as u64,
+        name.len() as u64
+    ) as isize;
+    usize::try_from(ret).ok()
+}
+
+/// Get the name of the caller through `Syscall::Prctl`.
+#[inline(always)]
+pub fn sys_prctl_get_name() -> String {
+    let mut buf = [0u8; 64];
+    let len = syscall!(
+        Syscall::Prctl,
+        syscall_def::PR_GET_NAME as u64,
+        buf.as_mut_ptr() as u64,
+        buf.len() as u64
+    );
+    let len = len.min(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+/// Keep every later child of the caller unprivileged, even a forked one,
+/// there is no way to clear it. Inherited by the children.
+#[inline(always)]
+pub fn sys_prctl_set_no_new_privs() {
+    syscall!(Syscall::Prctl, syscall_def::PR_SET_NO_NEW_PRIVS as u64, 1);
+}
+
+/// Whether `sys_prctl_set_no_new_privs` is in effect for the caller.
+#[inline(always)]
+pub fn sys_prctl_get_no_new_privs() -> bool {
+    syscall!(Syscall::Prctl, syscall_def::PR_GET_NO_NEW_PRIVS as u64) == 1
+}
+
+/// Replace the flags added to every `sys_open`, like `sys_set_open_defaults`.
+#[inline(always)]
+pub fn sys_prctl_set_open_defaults(flags: usize) {
+    syscall!(Syscall::Prctl, syscall_def::PR_SET_OPEN_DEFAULTS as u64, flags as u64);
+}
+
+/// Get the flags added to every `sys_open` of the caller.
+#[inline(always)]
+pub fn sys_prctl_get_open_defaults() -> usize {
+    syscall!(Syscall::Prctl, syscall_def::PR_GET_OPEN_DEFAULTS as u64)
+}
+
+/// Throttle the caller to run at most `ticks_per_sec` ticks per second
+/// on average, 0 to remove the quota. Unlike the tick budget, it is never
+/// killed, it just waits for the quota to refill. Not inherited by children.
+/// Returns false if the tsc is not calibrated.
+#[inline(always)]
+pub fn sys_set_quota(ticks_per_sec: u64) -> bool {
+    syscall!(Syscall::SetQuota, ticks_per_sec) == 0
+}
+
+/// Print the ready queue, the waiters of each process, the sleeping
+/// processes and the wait queue of each semaphore.
+#[inline(always)]
+pub fn sys_dump_sched() {
+    syscall!(Syscall::DumpSched, 0, 0);
+}
+
+/// Get the pids of the live children of `pid` (0 for the caller),
+/// empty if the process is not found.
+pub fn sys_list_children(pid: u16) -> Vec<u16> {
+    let mut pids = vec![0u16; 8];
+    loop {
+        let count = syscall!(
+            Syscall::ListChildren,
+            pid as u64,
+            pids.as_mut_ptr() as u64,
+            pids.len() as u64
+        ) as isize;
+        if count < 0 {
+            return Vec::new();
+        }
+        // more children than the buffer holds, try again with the count
+        if count as usize > pids.len() {
+            pids.resize(count as usize, 0);
+            continue;
+        }
+        pids.truncate(count as usize);
+        return pids;
+    }
+}
+
+/// Get the pids of the live processes named exactly `name`, e.g. to kill
+/// them all with `sys_kill`. Names are compared as set by `sys_set_proc_name`.
+pub fn sys_find_by_name(name: &str) -> Vec<u16> {
+    let mut pids = vec![0u16; 8];
+    loop {
+        let desc = [pids.as_mut_ptr() as usize, pids.len()];
+        let count = syscall!(
+            Syscall::FindByName,
+            name.as_ptr() as u64,
+            name.len() as u64,
+            desc.as_ptr() as u64
+        );
+        // more processes than the buffer holds, try again with the count
+        if count > pids.len() {
+            pids.resize(count, 0);
+            continue;
+        }
+        pids.truncate(count);
+        return pids;
+    }
+}
+
+/// Write the dump of `sys_dump_sched` into `buf`,
+/// returns the length of the whole dump, which may not fit.
+#[inline(always)]
+pub fn sys_dump_sched_into(buf: &mut [u8]) -> usize {
+    syscall!(Syscall::DumpSched, buf.as_mut_ptr() as u64, buf.len() as u64)
+}
+
+/// Leave a marker with the tick & the pid of the caller in the trace ring
+/// of the kernel, to line up the events of processes, e.g. a critical section.
+#[inline(always)]
+pub fn sys_trace_mark(tag: u32, value: u64) {
+    syscall!(Syscall::TraceMark, tag as u64, value);
+}
+
+/// Print the markers in the trace ring to the console, oldest first.
+#[inline(always)]
+pub fn sys_dump_trace() -> usize {
+    syscall!(Syscall::DumpTrace, 0, 0)
+}
+
+/// Copy the latest markers in the trace ring into `buf`, oldest first,
+/// returns the number copied.
+#[inline(always)]
+pub fn sys_dump_trace_into(buf: &mut [TraceEntry]) -> usize {
+    syscall!(Syscall::DumpTrace, buf.as_mut_ptr() as u64, buf.len() as u64)
+}
+
+#[inline(always)]
+pub fn sys_context_switches() -> u64 {
+    syscall!(Syscall::ContextSwitches) as u64
+}
+
+/// Get the number of ready and running processes, averaged over the recent
+/// clock ticks, in fixed point with `LOAD_SHIFT` fraction bits.
+#[inline(always)]
+pub fn sys_loadavg() -> u32 {
+    syscall!(Syscall::LoadAvg) as u32
+}
+
+/// The lookups of the process table by pid since boot, a debug counter
+/// of the cost of the syscall paths.
+#[inline(always)]
+pub fn sys_table_lookups() -> u64 {
+    syscall!(Syscall::TableLookups) as u64
+}
+
+#[inline(always)]
+pub fn sys_allocate(layout: &core::alloc::Layout) -> *mut u8 {
+    syscall!(Syscall::Allocate, layout as *const _) as *mut u8
+}
+
+/// Free memory from `sys_allocate`, returns false if the free is rejected.
+#[inline(always)]
+pub fn sys_deallocate(ptr: *mut u8, layout: &core::alloc::Layout) -> bool {
+    syscall!(Syscall::Deallocate, ptr, layout as *const _) == 0
+}
+
+/// Get the live allocations of the caller by `sys_allocate`, and the
+/// bytes they hold, e.g. to find the leaks before exiting. Only the
+/// `kernel_alloc` allocator allocates by it, the kernel logs the leaks on exit.
+#[inline(always)]
+pub fn sys_alloc_stats() -> (u64, u64) {
+    let mut stats = [0u64; 2];
+    syscall!(Syscall::AllocStats, stats.as_mut_ptr() as u64);
+    (stats[0], stats[1])
+}
+
+/// Spawn the app at `path`, `None` if it does not exist or is not an ELF.
+#[inline(always)]
+pub fn sys_spawn(path: &str) -> Option<u16> {
+    spawn(path, 0)
+}
+
+/// Spawn the app suspended, it does not run until `sys_cont`.
+#[inline(always)]
+pub fn sys_spawn_suspended(path: &str) -> Option<u16> {
+    spawn(path, 1)
+}
+
+/// Spawn the app and keep its exit code for `sys_collect`,
+/// no `sys_wait_pid` is needed.
+#[inline(always)]
+pub fn sys_spawn_collectable(path: &str) -> Option<u16> {
+    spawn(path, 2)
+}
+
+/// Spawn the app as a daemon under the kernel process in a session of
+/// its own, it keeps running after the caller exits or kills its tree,
+/// and its exit code is dropped, no `sys_wait_pid` is needed.
+#[inline(always)]
+pub fn sys_spawn_detached(path: &str) -> Option<u16> {
+    spawn(path, 8)
+}
+
+/// Spawn the app in the working dir `cwd`, the caller keeps its own,
+/// `None` if the app or the dir does not exist.
+#[inline(always)]
+pub fn sys_spawn_in(path: &str, cwd: &str) -> Option<u16> {
+    // both strings are passed behind the path arg with flag 4
+    let paths = [
+        path.as_ptr() as usize,
+        path.len(),
+        cwd.as_ptr() as usize,
+        cwd.len(),
+    ];
+    match syscall!(Syscall::Spawn, paths.as_ptr() as u64, 0, 4) as u16 {
+        // pid 0 is never used, the spawn failed
+        0 => None,
+        pid => Some(pid),
+    }
+}
+
+/// Take the exit code of a child spawned by `sys_spawn_collectable`,
+/// `None` if it is still running or the code is already collected.
+#[inline(always)]
+pub fn sys_collect(pid: u16) -> Option<isize> {
+    let mut code = 0isize;
+    match syscall!(Syscall::Collect, pid as u64, &mut code as *mut isize as u64) {
+        0 => Some(code),
+        _ => None,
     }
 }
 
+/// Spawn the app with its stdin and stdout replaced by the given fds,
+/// e.g. the ends of a pipe, `None` keeps the console.
+/// The fds stay open in the caller.
+#[inline(always)]
+pub fn sys_spawn_redirect(path: &str, stdin: Option<u8>, stdout: Option<u8>) -> Option<u16> {
+    // the kernel takes the fds as fd + 1, 0 for not redirected
+    let stdin = stdin.map_or(0, |fd| fd as u64 + 1);
+    let stdout = stdout.map_or(0, |fd| fd as u64 + 1);
+    spawn(path, stdin << 8 | stdout << 16)
+}
+
+/// Spawn the app with `argv` for `sys_get_arg` and its stdin and stdout
+/// replaced by the given fds like `sys_spawn_redirect`, in one call.
+///
+/// Returns `None` if the app cannot be spawned or there are more than `ARG_MAX` args.
+#[inline(always)]
+pub fn sys_posix_spawn(
+    path: &str,
+    argv: &[&str],
+    stdin: Option<u8>,
+    stdout: Option<u8>,
+) -> Option<u16> {
+    if argv.len() > ARG_MAX {
+        return None;
+    }
+    let mut attr = SpawnAttr {
+        argc: argv.len(),
+        // the kernel takes the fds as fd + 1, 0 for not redirected
+        stdio: [stdin, stdout].map(|fd| fd.map_or(0, |fd| fd as usize + 1)),
+        ..Default::default()
+    };
+    for (desc, arg) in attr.argv.iter_mut().zip(argv) {
+        *desc = [arg.as_ptr() as usize, arg.len()];
+    }
+    posix_spawn(path, &attr)
+}
+
+/// Spawn the app with a stack growing up to `size` bytes, the child is
+/// killed once it goes beyond. `None` if the size is over the stack slot.
+#[inline(always)]
+pub fn sys_spawn_stack(path: &str, size: usize) -> Option<u16> {
+    let attr = SpawnAttr {
+        stack: [0, size],
+        ..Default::default()
+    };
+    posix_spawn(path, &attr)
+}
+
+#[inline(always)]
+fn posix_spawn(path: &str, attr: &SpawnAttr) -> Option<u16> {
+    let ret = syscall!(
+        Syscall::PosixSpawn,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        attr as *const SpawnAttr as u64
+    );
+    match ret as u16 {
+        // pid 0 is never used, the spawn failed
+        0 => None,
+        pid => Some(pid),
+    }
+}
+
+/// Get the argument at `index` given by `sys_posix_spawn`,
+/// `None` past the last one.
+///
+/// NOTE: the value is truncated to 256 bytes
+#[inline(always)]
+pub fn sys_get_arg(index: usize) -> Option<String> {
+    let mut buf = [0u8; 256];
+    let len = syscall!(
+        Syscall::GetArg,
+        index as u64,
+        buf.as_mut_ptr() as u64,
+        buf.len() as u64
+    ) as isize;
+    if len < 0 {
+        return None;
+    }
+    let len = (len as usize).min(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[inline(always)]
+fn spawn(path: &str, flags: u64) -> Option<u16> {
+    let ret = syscall!(Syscall::Spawn, path.as_ptr() as u64, path.len() as u64, flags);
+    match ret as u16 {
+        // pid 0 is never used, the spawn failed
+        0 => None,
+        pid => Some(pid),
+    }
+}
+
+/// Replace this process with the app at `path`, keeping the pid and
+/// the fds not marked by `sys_set_cloexec`. Returns only if failed.
+#[inline(always)]
+pub fn sys_exec(path: &str) -> isize {
+    syscall!(Syscall::Exec, path.as_ptr() as u64, path.len() as u64) as isize
+}
+
+/// Mark the fd to be closed by `sys_exec` or not, new fds are kept.
+///
+/// Returns false if the fd is not opened.
+#[inline(always)]
+pub fn sys_set_cloexec(fd: u8, cloexec: bool) -> bool {
+    syscall!(Syscall::SetCloexec, fd as u64, cloexec as u64) == 0
+}
+
+/// Get (`F_GETFL`) or set (`F_SETFL`) the status flags of the fd,
+/// e.g. `O_NONBLOCK`. Returns the flags for `F_GETFL` and 0 for `F_SETFL`.
+#[inline(always)]
+pub fn sys_fcntl(fd: u8, cmd: usize, arg: usize) -> SyscallResult<usize> {
+    syscall_result(syscall!(Syscall::Fcntl, fd as u64, cmd as u64, arg as u64) as isize)
+}
+
+/// Make the reads of the fd fail with `SysError::Again` instead of
+/// returning 0 while nothing is buffered, or not. Forked children inherit it.
+#[inline(always)]
+pub fn sys_set_nonblocking(fd: u8, nonblocking: bool) -> bool {
+    let flags = match sys_fcntl(fd, F_GETFL, 0) {
+        Ok(flags) => flags,
+        Err(_) => return false,
+    };
+    let flags = match nonblocking {
+        true => flags | O_NONBLOCK,
+        false => flags & !O_NONBLOCK,
+    };
+    sys_fcntl(fd, F_SETFL, flags).is_ok()
+}
+
+/// Let the suspended child `pid` run.
+#[inline(always)]
+pub fn sys_cont(pid: u16) -> bool {
+    syscall!(Syscall::Cont, pid as u64) == 0
+}
+
+/// Kill the process `pid`.
+#[inline(always)]
+pub fn sys_kill(pid: u16) -> bool {
+    syscall!(Syscall::Kill, pid as u64) as isize > 0
+}
+
+/// Kill all processes in the group `pgid`, returns how many were killed.
+#[inline(always)]
+pub fn sys_killpg(pgid: u16) -> isize {
+    syscall!(Syscall::Kill, -(pgid as isize) as u64) as isize
+}
+
+/// Kill all the descendants of the caller with the exit code `ret`,
+/// returns how many were killed.
+#[inline(always)]
+pub fn sys_kill_tree(ret: isize) -> usize {
+    syscall!(Syscall::KillTree, ret as u64)
+}
+
+/// Set the process group of `pid`, 0 for the caller itself.
+#[inline(always)]
+pub fn sys_setpgid(pid: u16, pgid: u16) -> bool {
+    syscall!(Syscall::SetPgid, pid as u64, pgid as u64) == 0
+}
+
+/// Get the process group of `pid`, 0 for the caller itself.
+#[inline(always)]
+pub fn sys_getpgid(pid: u16) -> Option<u16> {
+    match syscall!(Syscall::GetPgid, pid as u64) as isize {
+        -1 => None,
+        pgid => Some(pgid as u16),
+    }
+}
+
+/// Make the caller lead a new session and a new process group of its pid,
+/// fails if it leads a process group already, e.g. a spawned app.
+#[inline(always)]
+pub fn sys_setsid() -> Option<u16> {
+    match syscall!(Syscall::SetSid) as isize {
+        -1 => None,
+        sid => Some(sid as u16),
+    }
+}
+
+/// Get the session of `pid`, 0 for the caller itself. Forked and spawned
+/// children are in the session of the parent.
+#[inline(always)]
+pub fn sys_getsid(pid: u16) -> Option<u16> {
+    match syscall!(Syscall::GetSid, pid as u64) as isize {
+        -1 => None,
+        sid => Some(sid as u16),
+    }
+}
+
+#[inline(always)]
+pub fn sys_get_pid() -> u16 {
+    syscall!(Syscall::GetPid) as u16
+}
+
+#[inline(always)]
+pub fn sys_exit(code: isize) -> ! {
+    syscall!(Syscall::Exit, code as u64);
+    unreachable!("This process should be terminated by now.")
+}
+
+/// Exit all the threads of the process, `sys_exit` ends only the caller.
+#[inline(always)]
+pub fn sys_exit_group(code: isize) -> ! {
+    syscall!(Syscall::ExitGroup, code as u64);
+    unreachable!("This process should be terminated by now.")
+}
+
+/// The stack canary of this image, drawn again by the kernel on every
+/// spawn & exec and kept on fork, see `__stack_chk_guard`.
+#[inline(always)]
+pub fn sys_stack_canary() -> u64 {
+    syscall!(Syscall::StackCanary) as u64
+}
+
+/// Report an overwritten stack canary, the kernel logs it and all the
+/// threads exit with `STACK_CHK_EXIT_CODE`.
+#[inline(always)]
+pub fn sys_stack_chk_fail() -> ! {
+    syscall!(Syscall::StackChkFail);
+    unreachable!("This process should be terminated by now.")
+}
+
+/// Kill every process and power off. QEMU exits with 1 if no frame
+/// is leaked since the boot, or 35 if the leak check fails.
+#[inline(always)]
+pub fn sys_shutdown() -> ! {
+    syscall!(Syscall::Shutdown);
+    unreachable!("The machine should be powered off by now.")
+}
+
+#[inline(always)]
+pub fn sys_print_info(pid: u16) -> u16 {
+    syscall!(Syscall::PrintInfo, pid as u64) as u16
+}
+
+#[inline(always)]
+pub fn sys_time() -> u64 {
+    syscall!(Syscall::Time) as u64
+}
+
+/// Get the number of clock ticks since boot.
+///
+/// The counter is increased once per timer interrupt, so it is monotonic
+/// and unaffected by any change of the wall clock.
+#[inline(always)]
+pub fn sys_uptime() -> u64 {
+    syscall!(Syscall::Uptime) as u64
+}
+
+/// Get the number of clock ticks since boot from the clock page.
+///
+/// The same counter as `sys_uptime`, read with a plain load, no syscall.
+#[inline(always)]
+pub fn read_time_fast() -> u64 {
+    let data = VDSO_ADDR as *const VdsoData;
+    unsafe { core::ptr::addr_of!((*data).ticks).read_volatile() }
+}
+
+/// Get the number of clock ticks per second from the clock page.
+#[inline(always)]
+pub fn read_clock_freq() -> u64 {
+    let data = VDSO_ADDR as *const VdsoData;
+    unsafe { core::ptr::addr_of!((*data).ticks_per_sec).read_volatile() }
+}
+
+/// The function run by a thread, with the argument of `sys_thread_create`
+pub type ThreadEntry = fn(usize);
+
+/// The entry called by the kernel, the thread exits with 0
+/// once the function returns.
+extern "C" fn thread_entry(entry: usize, arg: usize) -> ! {
+    let entry: ThreadEntry = unsafe { core::mem::transmute(entry) };
+    entry(arg);
+    sys_exit(0);
+}
+
+/// Run `entry(arg)` in a thread sharing the memory of the caller,
+/// returns its tid, or `None` if the process limit is reached.
+/// The caller keeps running, and the thread is waited as a child.
+#[inline(always)]
+pub fn sys_thread_create(entry: ThreadEntry, arg: usize) -> Option<u16> {
+    let ret = syscall!(
+        Syscall::Thread,
+        thread_entry as usize as u64,
+        entry as usize as u64,
+        arg as u64
+    ) as isize;
+    u16::try_from(ret).ok()
+}
+
+/// Run `entry(arg)` in a child sharing the parts of the caller in `flags`,
+/// `CLONE_VM` and the others, while the rest are copied.
+///
+/// The child runs on a fresh stack with `CLONE_VM`, else on its copy of
+/// the stack, and exits with 0 once the function returns. Returns its pid,
+/// or `None` on unknown flags or if the process limit is reached.
+#[inline(always)]
+pub fn sys_clone(flags: usize, entry: ThreadEntry, arg: usize) -> Option<u16> {
+    let args = [entry as usize, arg];
+    let ret = syscall!(
+        Syscall::Clone,
+        flags as u64,
+        thread_entry as usize as u64,
+        args.as_ptr() as u64
+    ) as isize;
+    u16::try_from(ret).ok()
+}
+
+/// Wait for the thread to exit, returns its exit code.
+#[inline(always)]
+pub fn sys_thread_join(tid: u16) -> isize {
+    sys_wait_pid(tid)
+}
+
+/// Returned by `sys_fork` when the process limit is reached.
+pub const FORK_FAILED: u16 = u16::MAX;
+
+/// Fork the current process.
+///
+/// Returns 0 in the child, the child's pid in the parent,
+/// or `FORK_FAILED` if no more process can be created.
+#[inline(always)]
+pub fn sys_fork() -> u16 {
+    syscall!(Syscall::Fork, 0) as u16
+}
+
+/// Fork the current process with a private copy of its memory.
+///
+/// The pages are shared until either process writes to them,
+/// returns like `sys_fork`.
+#[inline(always)]
+pub fn sys_fork_cow() -> u16 {
+    syscall!(Syscall::Fork, 1) as u16
+}
+
+/// Create a child running on the memory and the stack of the caller,
+/// without any copy, the caller is blocked until the child execs or exits.
+///
+/// The child must call only `sys_exec` or `sys_exit`, never returning
+/// from the function calling this, returns like `sys_fork`.
+#[inline(always)]
+pub fn sys_vfork() -> u16 {
+    syscall!(Syscall::Vfork) as u16
+}
+
+/// Give up the rest of the time slice, every other ready process
+/// runs once before the caller is scheduled again.
+#[inline(always)]
+pub fn sys_yield() {
+    syscall!(Syscall::Yield);
+}
+
+/// Hint the kernel in a spin-wait loop, it only pauses for the first
+/// `SPIN_HINT_LIMIT` calls in a time slice, then yields like `sys_yield`.
+///
+/// returns true if the caller has yielded.
+#[inline(always)]
+pub fn sys_spin_hint() -> bool {
+    syscall!(Syscall::SpinHint) == 1
+}
+
+/// Get the id of the processor running the caller, always 0 on a single CPU.
+/// The caller may be moved to another one right after the call.
+#[inline(always)]
+pub fn sys_getcpu() -> u32 {
+    syscall!(Syscall::GetCpu) as u32
+}
+
+/// Order the memory accesses of every CPU like a `mfence` on each, so the
+/// fast path of the other side only needs a compiler fence, it is slow.
+#[inline(always)]
+pub fn sys_membarrier() {
+    syscall!(Syscall::MemBarrier);
+}
+
+/// Fill the buffer with random bytes, returns the number of bytes filled.
+#[inline(always)]
+pub fn sys_getrandom(buf: &mut [u8]) -> Option<usize> {
+    let ret = syscall!(Syscall::GetRandom, buf.as_mut_ptr() as u64, buf.len() as u64) as isize;
+    if ret.is_negative() {
+        None
+    } else {
+        Some(ret as usize)
+    }
+}
+
+/// Get a random u64 from the kernel.
+#[inline(always)]
+pub fn random_u64() -> u64 {
+    let mut buf = [0u8; 8];
+    sys_getrandom(&mut buf).expect("Failed to get random bytes");
+    u64::from_ne_bytes(buf)
+}
+
+/// Sleep for at least `ns` nanoseconds, returns false if the tsc
+/// has not been calibrated, or a signal without `SA_RESTART` cuts it short.
+#[inline(always)]
+pub fn sys_nanosleep(ns: u64) -> bool {
+    syscall!(Syscall::NanoSleep, ns as usize) == 0
+}
+
+/// Block until a signal is delivered to its handler, returns once the
+/// handler returns, at once if a signal is pending already.
+#[inline(always)]
+pub fn sys_pause() {
+    syscall!(Syscall::Pause);
+}
+
+/// Sleep until the clock tick `tick` of `sys_uptime` is reached,
+/// returns at once if it has passed, or early on a signal without `SA_RESTART`.
+#[inline(always)]
+pub fn sys_sleep_until(tick: u64) {
+    syscall!(Syscall::SleepUntil, tick);
+}
+
+/// Arm the alarm to send `SIGALRM` to self in `ms` milliseconds, which kills
+/// it with the exit code `-SIGALRM` unless it has a handler of `sys_sigaction`,
+/// re-arming replaces it and 0 disarms it. Returns the
+/// milliseconds left of the alarm replaced, 0 if there was none.
+#[inline(always)]
+pub fn sys_alarm(ms: u64) -> u64 {
+    syscall!(Syscall::Alarm, ms) as u64
+}
+
+/// Set the kernel log level, 0 for off, 1 ~ 5 for error ~ trace,
+/// only privileged processes are allowed to do so.
+#[inline(always)]
+pub fn sys_set_log_level(level: usize) -> bool {
+    syscall!(Syscall::SetLogLevel, level as u64) == 0
+}
+
+/// Set the baud, data bits, parity & stop bits of the serial line,
+/// only privileged processes are allowed to do so.
+///
+/// The baud must divide 115200, parity is one of `PARITY_NONE`,
+/// `PARITY_ODD` & `PARITY_EVEN`.
+#[inline(always)]
+pub fn sys_serial_config(baud: u32, data_bits: u8, parity: u8, stop_bits: u8) -> bool {
+    let config = SerialConfig {
+        baud,
+        data_bits,
+        parity,
+        stop_bits,
+    };
+    syscall!(Syscall::SerialConfig, &config as *const SerialConfig as u64) == 0
+}
+
+/// Log every syscall with its arguments & return value in the kernel,
+/// only privileged processes are allowed to do so.
+#[inline(always)]
+pub fn sys_set_strace(on: bool) -> bool {
+    syscall!(Syscall::SetStrace, on as u64) == 0
+}
+
+/// Get the ticks `pid` (0 for self) waits in the ready queue each time before
+/// it is picked, `None` if the process does not exist.
+#[inline(always)]
+pub fn sys_sched_latency(pid: u16) -> Option<SchedLatency> {
+    let mut latency = SchedLatency::default();
+    match syscall!(Syscall::SchedLatency, pid as u64, &mut latency as *mut SchedLatency as u64) {
+        0 => Some(latency),
+        _ => None,
+    }
+}
+
+/// Switch the scheduler policy, only privileged processes are allowed to do so.
+#[inline(always)]
+pub fn sys_set_scheduler(policy: SchedPolicy) -> bool {
+    syscall!(Syscall::SetScheduler, usize::from(policy)) == 0
+}
+
+/// Give up the privilege of the restricted syscalls for good,
+/// the children forked later are unprivileged as well.
+#[inline(always)]
+pub fn sys_drop_privilege() {
+    syscall!(Syscall::DropPrivilege);
+}
+
+/// Make the dir at `path` the root & the working dir of the caller, only
+/// privileged processes are allowed to do so. Absolute paths then start from
+/// it and `..` never goes above it. Inherited by the children.
+#[inline(always)]
+pub fn sys_chroot(path: &str) -> bool {
+    syscall!(Syscall::Chroot, path.as_ptr() as u64, path.len() as u64) == 0
+}
+
+/// Get the current scheduler policy.
+#[inline(always)]
+pub fn sys_get_scheduler() -> SchedPolicy {
+    SchedPolicy::try_from(syscall!(Syscall::GetScheduler)).unwrap_or_default()
+}
+
+/// Log `code` with the pid & registers of the caller in the kernel,
+/// see `debug_assert_kernel!`.
+#[inline(always)]
+pub fn sys_debug(code: usize) {
+    syscall!(Syscall::Debug, code as u64);
+}
+
+/// Get the calibrated tsc cycles per second, 0 if not calibrated.
+#[inline(always)]
+pub fn sys_tsc_frequency() -> u64 {
+    syscall!(Syscall::TscFrequency) as u64
+}
+
+/// Read the time stamp counter.
+#[inline(always)]
+pub fn rdtsc() -> u64 {
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+/// Get why the process is blocked, `None` if it is not blocked
+/// or does not exist.
+#[inline(always)]
+pub fn sys_block_reason(pid: u16) -> Option<BlockReason> {
+    let ret = syscall!(Syscall::BlockReason, pid as u64);
+    BlockReason::try_from(ret).ok()
+}
+
+/// Get the latest blocks & wake-ups of `pid` (0 for the caller), oldest first.
+///
+/// Returns the number of entries filled, `None` if the process does not exist.
+#[inline(always)]
+pub fn sys_wake_log(pid: u16, buf: &mut [AuditEntry]) -> Option<usize> {
+    let ret = syscall!(
+        Syscall::WakeLog,
+        pid as u64,
+        buf.as_mut_ptr() as u64,
+        buf.len() as u64
+    ) as isize;
+    usize::try_from(ret).ok()
+}
+
+/// Get the environment variable of the caller.
+///
+/// NOTE: the value is truncated to 256 bytes
+#[inline(always)]
+pub fn sys_get_env(key: &str) -> Option<String> {
+    let mut buf = [0u8; 256];
+    let desc = [buf.as_mut_ptr() as usize, buf.len()];
+    let len = syscall!(
+        Syscall::GetEnv,
+        key.as_ptr() as u64,
+        key.len() as u64,
+        desc.as_ptr() as u64
+    ) as isize;
+    if len < 0 {
+        return None;
+    }
+    let len = (len as usize).min(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+/// Set the environment variable of the caller,
+/// a forked child gets a private copy of its parent's variables.
+#[inline(always)]
+pub fn sys_set_env(key: &str, val: &str) {
+    let desc = [val.as_ptr() as usize, val.len()];
+    syscall!(
+        Syscall::SetEnv,
+        key.as_ptr() as u64,
+        key.len() as u64,
+        desc.as_ptr() as u64
+    );
+}
+
+#[inline(always)]
+pub fn sys_new_sem(key: u32, value: usize) -> bool {
+    syscall!(Syscall::Sem, 0, key as usize, value) == 0
+}
+
+/// Create a semaphore used as a lock, if a process exits holding it,
+/// the units come back and the waiters fail instead of blocking forever.
+#[inline(always)]
+pub fn sys_new_robust_sem(key: u32, value: usize) -> bool {
+    syscall!(Syscall::Sem, 4, key as usize, value) == 0
+}
+
+#[inline(always)]
+pub fn sys_remove_sem(key: u32) -> bool {
+    syscall!(Syscall::Sem, 1, key as usize) == 0
+}
+
+#[inline(always)]
+pub fn sys_sem_signal(key: u32) -> bool {
+    syscall!(Syscall::Sem, 2, key as usize) == 0
+}
+
+/// Wake up all the processes waiting on the semaphore at once, like a barrier
+/// released, and reset its count to 0.
+#[inline(always)]
+pub fn sys_sem_signal_all(key: u32) -> bool {
+    syscall!(Syscall::Sem, 5, key as usize, 0) == 0
+}
+
+/// Fails if the semaphore does not exist, or once the holder of a robust one
+/// exits while the caller is waiting.
+#[inline(always)]
+pub fn sys_sem_wait(key: u32) -> bool {
+    syscall!(Syscall::Sem, 3, key as usize, 0) == 0
+}
+
+/// Like `sys_sem_wait`, but fails once not signaled within `ms` (0 for no limit).
+#[inline(always)]
+pub fn sys_sem_timedwait(key: u32, ms: u64) -> bool {
+    syscall!(Syscall::Sem, 3, key as usize, ms) == 0
+}
+
+/// Create a reusable barrier for `count` processes, fails if the key is taken
+/// or `count` is 0.
+#[inline(always)]
+pub fn sys_new_barrier(key: u32, count: usize) -> bool {
+    syscall!(Syscall::Barrier, 0, key as usize, count) == 0
+}
+
+#[inline(always)]
+pub fn sys_remove_barrier(key: u32) -> bool {
+    syscall!(Syscall::Barrier, 1, key as usize) == 0
+}
+
+/// Block until `count` processes have arrived, then all of them go on
+/// and the barrier is reset for the next round. A waiter that exits is
+/// not expected anymore. Fails if the barrier does not exist.
+#[inline(always)]
+pub fn sys_barrier_wait(key: u32) -> bool {
+    syscall!(Syscall::Barrier, 2, key as usize) == 0
+}
+
+/// Block while `word` still holds `expected`, returns 0 once woken up,
+/// -2 at once if the value differs, or -1 for a bad address.
+#[inline(always)]
+pub fn sys_futex_wait(word: &AtomicU32, expected: u32) -> isize {
+    syscall!(Syscall::Futex, 0, word.as_ptr() as u64, expected as u64) as isize
+}
+
+/// Wake up at most `count` processes waiting on `word`,
+/// returns the number of them.
+#[inline(always)]
+pub fn sys_futex_wake(word: &AtomicU32, count: usize) -> isize {
+    syscall!(Syscall::Futex, 1, word.as_ptr() as u64, count as u64) as isize
+}
+
+/// Set to fail with -2 instead of blocking when the queue is full.
+pub const MSG_NOWAIT: u64 = 1 << 32;
+
+/// Get the message queue by key, create it if not exist.
+#[inline(always)]
+pub fn sys_msg_get(key: u32) -> bool {
+    syscall!(Syscall::MsgGet, key as u64) == 0
+}
+
+/// Send a message, returns its length or a negative value on failure.
+#[inline(always)]
+pub fn sys_msg_send(key: u32, msg: &[u8], flags: u64) -> isize {
+    syscall!(
+        Syscall::MsgSend,
+        key as u64 | flags,
+        msg.as_ptr() as u64,
+        msg.len() as u64
+    ) as isize
+}
+
+/// Receive exactly one message, returns its length or -1 on failure.
+#[inline(always)]
+pub fn sys_msg_recv(key: u32, buf: &mut [u8]) -> isize {
+    syscall!(
+        Syscall::MsgRecv,
+        key as u64,
+        buf.as_mut_ptr() as u64,
+        buf.len() as u64
+    ) as isize
+}
+
+/// Open the file with no flags, the fd is never opened if it fails.
+#[inline(always)]
+pub fn sys_open_file(path: &str) -> u8 {
+    sys_open(path, 0).unwrap_or(u8::MAX)
+}
+
+/// Request the write access, denied if the file is read-only.
+pub const O_WRONLY: usize = 0o1;
+/// Request the read & write access, denied if the file is read-only.
+pub const O_RDWR: usize = 0o2;
+/// Empty the file if opened with `O_WRONLY` or `O_RDWR`, ignored otherwise.
+pub const O_TRUNC: usize = 0o1000;
+/// Every write goes to the end of the file, see `sys_open`.
+pub const O_APPEND: usize = 0o2000;
+
+/// Open the file with the flags, e.g. `O_APPEND`, or `O_CREAT` to create
+/// a missing one.
+///
+/// Returns `None` if the file does not exist or the access is denied.
+#[inline(always)]
+pub fn sys_open(path: &str, flags: usize) -> Option<u8> {
+    sys_open_result(path, flags).ok()
+}
+
+/// Like `sys_open`, but tells why it fails, e.g. `SysError::Exists`
+/// with `O_CREAT | O_EXCL` if the file exists.
+#[inline(always)]
+pub fn sys_open_result(path: &str, flags: usize) -> SyscallResult<u8> {
+    syscall_result(sys_open_raw(path, flags)).map(|fd| fd as u8)
+}
+
+/// Like `sys_open`, but returns the fd or a negative error code,
+/// e.g. `EEXIST` with `O_CREAT | O_EXCL` if the file exists.
+#[inline(always)]
+pub fn sys_open_raw(path: &str, flags: usize) -> isize {
+    syscall!(
+        Syscall::Open,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        flags as u64
+    ) as isize
+}
+
+/// The mode of a writable file, FAT16 keeps only the write bit.
+pub const MODE_DEFAULT: usize = 0o644;
+/// The mode of a read-only file.
+pub const MODE_READONLY: usize = 0o444;
+
+/// Change the mode of the file behind the fd, false if not a regular file.
+#[inline(always)]
+pub fn sys_fchmod(fd: u8, mode: usize) -> bool {
+    syscall!(Syscall::Fchmod, fd as u64, mode as u64) == 0
+}
+
+/// Set the flags added to every `sys_open` of this process,
+/// inherited by the children, e.g. `O_APPEND`.
+#[inline(always)]
+pub fn sys_set_open_defaults(flags: usize) {
+    syscall!(Syscall::SetOpenDefaults, flags as u64);
+}
+
+/// The bytes read & written through `fd` as `(read, written)`, counted
+/// since it is opened, a copy by `sys_dup2` counts apart and the counts
+/// of a forked child start from the ones of the parent.
+/// Returns `None` if the fd is not opened.
+#[inline(always)]
+pub fn sys_fd_stats(fd: u8) -> Option<(u64, u64)> {
+    let mut stats = [0u64; 2];
+    match syscall!(Syscall::FdStats, fd as u64, stats.as_mut_ptr() as u64) as isize {
+        0 => Some((stats[0], stats[1])),
+        _ => None,
+    }
+}
+
+/// Copy up to `count` bytes from the offset of the file `in_fd` to
+/// `out_fd` without passing through this process, advancing the offset.
+///
+/// Copies fewer at EOF or once `out_fd` is full, e.g. a pipe.
+/// Returns `None` if `in_fd` is not a file or `out_fd` cannot be written.
+#[inline(always)]
+pub fn sys_sendfile(out_fd: u8, in_fd: u8, count: usize) -> Option<usize> {
+    let ret = syscall!(
+        Syscall::SendFile,
+        out_fd as u64,
+        in_fd as u64,
+        count as u64
+    ) as isize;
+    usize::try_from(ret).ok()
+}
+
+/// Limit the heap, stack & mappings of this process to `bytes`,
+/// inherited by the children, 0 for no limit.
+///
+/// The growth over the limit fails, e.g. `sys_brk` returns `None`.
+#[inline(always)]
+pub fn sys_set_mem_limit(bytes: usize) {
+    syscall!(Syscall::SetMemLimit, bytes as u64);
+}
+
+/// Whether the fd refers to the console rather than a file or a pipe,
+/// false for an fd not opened.
+#[inline(always)]
+pub fn sys_isatty(fd: u8) -> bool {
+    syscall!(Syscall::IsTty, fd as u64) as isize == 1
+}
+
+/// Get the total & free blocks of the mounted filesystem,
+/// `None` if no filesystem is mounted.
+#[inline(always)]
+pub fn sys_statfs() -> Option<StatFsInfo> {
+    let mut info = StatFsInfo::default();
+    match syscall!(Syscall::StatFs, &mut info as *mut StatFsInfo as u64) as isize {
+        0 => Some(info),
+        _ => None,
+    }
+}
+
+/// Get the size & type of the resource behind the fd.
+#[inline(always)]
+pub fn sys_fstat(fd: u8) -> Option<FileStat> {
+    let mut stat = FileStat::default();
+    match syscall!(Syscall::Fstat, fd as u64, &mut stat as *mut FileStat as u64) as isize {
+        0 => Some(stat),
+        _ => None,
+    }
+}
+
+/// Fill `dirents` with the entries of the dir fd from `cursor` (0 to start),
+/// returns the next cursor, `Some(0)` once the dir is exhausted.
+#[inline(always)]
+pub fn sys_getdents(fd: u8, cursor: usize, dirents: &mut [Dirent]) -> Option<usize> {
+    let buf = [dirents.as_mut_ptr() as usize, dirents.len()];
+    let ret = syscall!(
+        Syscall::GetDents,
+        fd as u64,
+        buf.as_ptr() as u64,
+        cursor as u64
+    ) as isize;
+    if ret.is_negative() {
+        None
+    } else {
+        Some(ret as usize)
+    }
+}
+
+/// The number of entries fetched by each `Syscall::GetDents` of `ReadDir`
+const READ_DIR_BATCH: usize = 8;
+
+/// An iterator over the entries of a dir fd, see `sys_read_dir`
+pub struct ReadDir {
+    fd: u8,
+    cursor: Option<usize>,
+    batch: [Dirent; READ_DIR_BATCH],
+    pos: usize,
+}
+
+impl Iterator for ReadDir {
+    type Item = Dirent;
+
+    fn next(&mut self) -> Option<Dirent> {
+        if self.pos == READ_DIR_BATCH || self.batch[self.pos].name_len == 0 {
+            // the last batch has been fetched
+            let cursor = self.cursor.take()?;
+            self.batch = [Dirent::default(); READ_DIR_BATCH];
+            self.pos = 0;
+            self.cursor = sys_getdents(self.fd, cursor, &mut self.batch).filter(|c| *c != 0);
+        }
+
+        let dirent = self.batch[self.pos];
+        if dirent.name_len == 0 {
+            return None;
+        }
+        self.pos += 1;
+        Some(dirent)
+    }
+}
+
+/// Iterate the entries of the dir opened as `fd`.
+pub fn sys_read_dir(fd: u8) -> ReadDir {
+    ReadDir {
+        fd,
+        cursor: Some(0),
+        batch: [Dirent::default(); READ_DIR_BATCH],
+        pos: 0,
+    }
+}
+
+/// Check if the file or dir exists, without opening it.
+#[inline(always)]
+pub fn sys_exists(path: &str) -> bool {
+    syscall!(Syscall::Access, path.as_ptr() as u64, path.len() as u64) == 0
+}
+
+/// The file does not exist
+pub const ENOENT: isize = SysError::NotFound.code();
+/// The file is read-only, or the operation is not permitted
+pub const EACCES: isize = SysError::Perm.code();
+/// The file is still opened by some process
+pub const EBUSY: isize = SysError::Busy.code();
+/// The file to create exclusively exists
+pub const EEXIST: isize = SysError::Exists.code();
+/// Too many symlinks are followed in the path, likely a loop
+pub const ELOOP: isize = SysError::Loop.code();
+
+/// The result of a syscall, with the error it fails with
+pub type SyscallResult<T> = Result<T, SysError>;
+
+/// Decode the return value of a syscall, a negative one is a negated
+/// `SysError`, taken as `SysError::Inval` if the code is unknown.
+pub fn syscall_result(ret: isize) -> SyscallResult<usize> {
+    match usize::try_from(ret) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(SysError::try_from(ret.unsigned_abs()).unwrap_or(SysError::Inval)),
+    }
+}
+
+/// Remove the file, returns 0 or a negative error code
+///
+/// NOTE: a file with open fds is not removed, `EBUSY` is returned
+#[inline(always)]
+pub fn sys_unlink(path: &str) -> isize {
+    syscall!(Syscall::Unlink, path.as_ptr() as u64, path.len() as u64) as isize
+}
+
+/// Watch the file, or the files in the dir, for writes, creates & unlinks,
+/// returns the watch fd, or `None` if nothing is at the path.
+#[inline(always)]
+pub fn sys_watch_add(path: &str) -> Option<u8> {
+    let ret = syscall!(Syscall::WatchAdd, path.as_ptr() as u64, path.len() as u64) as isize;
+    u8::try_from(ret).ok()
+}
+
+/// Wait for the next change seen by the watch fd, the dropped ones
+/// are reported as one `WATCH_OVERFLOW`. Returns `None` if not a watch fd.
+pub fn sys_watch_read(fd: u8) -> Option<WatchEvent> {
+    let mut event = WatchEvent::default();
+    loop {
+        match syscall!(Syscall::WatchRead, fd as u64, &mut event as *mut WatchEvent as u64) {
+            1 => return Some(event),
+            // let the writers run
+            0 => sys_yield(),
+            _ => return None,
+        }
+    }
+}
+
+/// Rename the file, replacing `dst` if it exists,
+/// returns 0 or a negative error code
+///
+/// NOTE: `EBUSY` is returned if either of them has open fds
+#[inline(always)]
+pub fn sys_rename(src: &str, dst: &str) -> isize {
+    let dst = [dst.as_ptr() as usize, dst.len()];
+    syscall!(
+        Syscall::Rename,
+        src.as_ptr() as u64,
+        src.len() as u64,
+        dst.as_ptr() as u64
+    ) as isize
+}
+
+/// Create a symlink at `link` to `target`, which is relative to the dir
+/// of the link unless absolute, returns 0 or a negative error code.
+///
+/// NOTE: the links are kept by the kernel until reboot, not on the disk,
+/// and not listed in their dirs
+#[inline(always)]
+pub fn sys_symlink(target: &str, link: &str) -> isize {
+    let link = [link.as_ptr() as usize, link.len()];
+    syscall!(
+        Syscall::Symlink,
+        target.as_ptr() as u64,
+        target.len() as u64,
+        link.as_ptr() as u64
+    ) as isize
+}
+
+/// Get the target of the symlink at `path` as it was created,
+/// `None` if it is not a link.
+///
+/// NOTE: the target is truncated to 256 bytes
+pub fn sys_readlink(path: &str) -> Option<String> {
+    let mut buf = [0u8; 256];
+    let desc = [buf.as_mut_ptr() as usize, buf.len()];
+    let len = syscall!(
+        Syscall::Readlink,
+        path.as_ptr() as u64,
+        path.len() as u64,
+        desc.as_ptr() as u64
+    ) as isize;
+    if len < 0 {
+        return None;
+    }
+    let len = (len as usize).min(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[inline(always)]
+pub fn sys_close_file(fd: u8) -> bool {
+    sys_close_result(fd).is_ok()
+}
+
+/// Like `sys_close_file`, `SysError::BadFd` if the fd is not open.
+#[inline(always)]
+pub fn sys_close_result(fd: u8) -> SyscallResult<()> {
+    syscall_result(syscall!(Syscall::Close, fd as u64) as isize).map(|_| ())
+}
+
+/// Move up to `count` bytes from the pipe `in_fd` to the pipe `out_fd` in the kernel.
+///
+/// Moves fewer once `in_fd` is empty or `out_fd` is full, 0 at EOF.
+/// Returns `None` if the fds are not the ends of two pipes or `out_fd` is broken.
+#[inline(always)]
+pub fn sys_splice(in_fd: u8, out_fd: u8, count: usize) -> Option<usize> {
+    let ret = syscall!(
+        Syscall::Splice,
+        in_fd as u64,
+        out_fd as u64,
+        count as u64
+    ) as isize;
+    usize::try_from(ret).ok()
+}
+
+/// Create a pipe, returns its read fd and write fd.
+#[inline(always)]
+pub fn sys_pipe() -> Option<(u8, u8)> {
+    let mut fds = [0u8; 2];
+    if syscall!(Syscall::Pipe, fds.as_mut_ptr() as u64) == 0 {
+        Some((fds[0], fds[1]))
+    } else {
+        None
+    }
+}
+
+/// Create a tee pipe with `readers` read ends, up to 8, each of which
+/// receives every byte written, returns its write fd and read fds.
+///
+/// The bytes are kept until every open read end has read them, so writes
+/// are short once the slowest reader falls 4096 bytes behind.
+#[inline(always)]
+pub fn sys_tee_pipe(readers: usize) -> Option<(u8, Vec<u8>)> {
+    let mut fds = vec![0u8; readers];
+    let ret = syscall!(Syscall::TeePipe, readers as u64, fds.as_mut_ptr() as u64) as isize;
+    u8::try_from(ret).ok().map(|write_fd| (write_fd, fds))
+}
+
+/// Send the fd through the write end of a pipe, the receiver shares the
+/// resource like `sys_dup2`. Fails if the read ends are all closed,
+/// too many are queued, or the fd is an end of the same pipe.
+#[inline(always)]
+pub fn sys_send_fd(channel: u8, fd: u8) -> bool {
+    syscall!(Syscall::SendFd, channel as u64, fd as u64) == 0
+}
+
+/// Receive an fd sent to the read end of a pipe at a new fd,
+/// `None` if nothing is queued, it never blocks.
+#[inline(always)]
+pub fn sys_recv_fd(channel: u8) -> Option<u8> {
+    let ret = syscall!(Syscall::RecvFd, channel as u64) as isize;
+    u8::try_from(ret).ok()
+}
+
+/// A read takes one from the counter of an eventfd, not the whole counter.
+pub const EFD_SEMAPHORE: usize = 1;
+
+/// Create an event counter starting at `init`, returns its fd,
+/// or `u8::MAX` if the flags are unknown.
+#[inline(always)]
+pub fn sys_eventfd(init: u64, flags: usize) -> u8 {
+    let ret = syscall!(Syscall::EventFd, init, flags as u64) as isize;
+    u8::try_from(ret).unwrap_or(u8::MAX)
+}
+
+/// Add `value` to the counter of the eventfd, false if it would overflow.
+#[inline(always)]
+pub fn sys_eventfd_write(fd: u8, value: u64) -> bool {
+    sys_write(fd, &value.to_ne_bytes()) == Some(8)
+}
+
+/// Wait until the counter of the eventfd is not zero, then take it,
+/// or one of it with `EFD_SEMAPHORE`. Returns `None` if not an eventfd.
+pub fn sys_eventfd_read(fd: u8) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    loop {
+        match sys_read(fd, &mut buf)? {
+            // let the writer run
+            0 => sys_yield(),
+            _ => return Some(u64::from_ne_bytes(buf)),
+        }
+    }
+}
+
+#[inline(always)]
+pub fn sys_dup2(old_fd: u8, new_fd: u8) -> isize {
+    syscall!(Syscall::Dup2, old_fd as u64, new_fd as u64) as isize
+}
+
+/// Redirect the stdout of self into a buffer of the kernel until
+/// `sys_capture_end`, captures can be nested up to 8 deep.
+#[inline(always)]
+pub fn sys_capture_start() -> bool {
+    syscall!(Syscall::CaptureStart) == 0
+}
+
+/// End the innermost capture, copy what it got into `buf` and restore the
+/// stdout before it. Returns `None` if nothing is being captured.
+#[inline(always)]
+pub fn sys_capture_end(buf: &mut [u8]) -> Option<usize> {
+    let ret = syscall!(Syscall::CaptureEnd, buf.as_mut_ptr() as u64, buf.len() as u64) as isize;
+    if ret < 0 {
+        None
+    } else {
+        Some(ret as usize)
+    }
+}
+
+#[inline(always)]
+pub fn sys_brk(addr: Option<usize>) -> Option<usize> {
+    const BRK_FAILED: usize = !0;
+    match syscall!(Syscall::Brk, addr.unwrap_or(0)) {
+        BRK_FAILED => None,
+        ret => Some(ret),
+    }
+}
+
+/// The bytes the heap can still grow by with `sys_brk`, bounded by the
+/// end of the heap area and the memory limit of `sys_set_mem_limit`.
+#[inline(always)]
+pub fn sys_heap_avail() -> u64 {
+    syscall!(Syscall::HeapAvail) as u64
+}
+
+/// Writes to the mapping are carried to the file, see `sys_mmap`.
+pub const MAP_SHARED: usize = 0x01;
+/// Writes to the mapping stay in the process.
+pub const MAP_PRIVATE: usize = 0x02;
+/// Zero-filled memory without a file, the fd is ignored.
+pub const MAP_ANONYMOUS: usize = 0x20;
+/// The mapping is writable, read-only without it.
+pub const MAP_WRITE: usize = 0x1000;
+
+/// Map `len` bytes of the file from the page aligned `offset`,
+/// with exactly one of `MAP_SHARED` & `MAP_PRIVATE`.
+///
+/// The pages are read on the first access, the mapping is not
+/// inherited by forked children. Returns the base address.
+#[inline(always)]
+pub fn sys_mmap(fd: u8, offset: usize, len: usize, flags: usize) -> Option<usize> {
+    let range = [offset, len];
+    let ret = syscall!(
+        Syscall::Mmap,
+        fd as u64,
+        range.as_ptr() as u64,
+        flags as u64
+    ) as isize;
+    usize::try_from(ret).ok()
+}
+
+/// Remove the mapping starting at `addr`, the dirty pages of
+/// a shared mapping are written back to the file.
+#[inline(always)]
+pub fn sys_munmap(addr: usize) -> bool {
+    syscall!(Syscall::Munmap, addr as u64) == 0
+}
+
+/// Copy the latest warnings & errors of the kernel log into `buf`,
+/// oldest first, a line for each. Returns the number of bytes copied.
+#[inline(always)]
+pub fn sys_dmesg(buf: &mut [u8]) -> usize {
+    syscall!(Syscall::Dmesg, buf.as_mut_ptr() as u64, buf.len() as u64)
+}
+
+/// Map the framebuffer left by the bootloader for reads & writes, shared
+/// with the other mappers, None if there is none.
+///
+/// The mapping is removed by `sys_munmap` of the base rounded down
+/// to a page, or on exit.
+#[inline(always)]
+pub fn sys_map_framebuffer() -> Option<FbInfo> {
+    let mut info = FbInfo::default();
+    match syscall!(Syscall::MapFramebuffer, &mut info as *mut FbInfo as u64) as isize {
+        0 => Some(info),
+        _ => None,
+    }
+}
+
+/// Free the frames behind `len` bytes of the heap from the page aligned
+/// `ptr`, the range reads as zeros afterwards and the heap end is kept.
+#[inline(always)]
+pub fn sys_madvise_dontneed(ptr: *mut u8, len: usize) -> bool {
+    const MADV_DONTNEED: u64 = 4;
+    syscall!(Syscall::Madvise, ptr as u64, len as u64, MADV_DONTNEED) == 0
+}
+
+/// Lock the pages behind `len` bytes of the heap or a mapping from the page
+/// aligned `ptr`, `sys_madvise_dontneed` keeps their frames and data.
+#[inline(always)]
+pub fn sys_mlock(ptr: *mut u8, len: usize) -> bool {
+    syscall!(Syscall::Mlock, ptr as u64, len as u64) == 0
+}
+
+/// Unlock the pages locked by `sys_mlock`.
+#[inline(always)]
+pub fn sys_munlock(ptr: *mut u8, len: usize) -> bool {
+    syscall!(Syscall::Munlock, ptr as u64, len as u64) == 0
+}
+
+/// Advance `deadline` by `period` ticks and sleep until it, so a loop
+/// started with `deadline = sys_uptime()` wakes every `period` ticks,
+/// however long each round runs. Returns false if the round overran
+/// the new deadline, which is kept rather than skipped.
+pub fn sleep_periodic(deadline: &mut u64, period: u64) -> bool {
+    *deadline += period;
+    let on_time = sys_uptime() < *deadline;
+    sys_sleep_until(*deadline);
+    on_time
+}
+
 pub fn sleep(secs: u64) {
     let start = Duration::from_secs(sys_time());
     let dur = Duration::from_secs(secs);
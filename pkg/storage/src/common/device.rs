@@ -11,6 +11,15 @@ where
     /// Reads a block from the device into the provided buffer
     fn read_block(&self, offset: usize, block: &mut B) -> Result<()>;
 
+    /// Reads the consecutive blocks from `offset` into the provided buffers,
+    /// one at a time unless the device can read them at once
+    fn read_blocks(&self, offset: usize, blocks: &mut [B]) -> Result<()> {
+        for (i, block) in blocks.iter_mut().enumerate() {
+            self.read_block(offset + i, block)?;
+        }
+        Ok(())
+    }
+
     /// Writes a block to the device from the provided buffer
     fn write_block(&self, offset: usize, block: &B) -> Result<()>;
 
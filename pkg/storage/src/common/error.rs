@@ -32,6 +32,8 @@ pub enum FsError {
     DeviceError(DeviceError),
     /// Invalid path.
     InvalidPath(String),
+    /// The file already exists.
+    AlreadyExists,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
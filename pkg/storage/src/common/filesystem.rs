@@ -32,7 +32,7 @@ pub trait FileSystem: Debug + Sync + Send {
     }
 
     /// Removes the file at this path
-    fn remove_file(&self, _path: &str) -> Result<FileHandle> {
+    fn remove_file(&self, _path: &str) -> Result<()> {
         Err(FsError::NotSupported)
     }
 
@@ -55,4 +55,9 @@ pub trait FileSystem: Debug + Sync + Send {
     fn move_dir(&self, _src: &str, _dst: &str) -> Result<()> {
         Err(FsError::NotSupported)
     }
+
+    /// Returns the total & free space of this filesystem
+    fn stat_fs(&self) -> Result<FsStat> {
+        Err(FsError::NotSupported)
+    }
 }
@@ -6,6 +6,11 @@ pub trait Read {
     /// how many bytes were read.
     fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
 
+    /// Hint how the source is going to be read, to adjust the readahead.
+    fn advise(&mut self, _advice: Advice) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
     /// Read all bytes until EOF in this source, placing them into `buf`.
     fn read_all(&mut self, buf: &mut Vec<u8>) -> Result<usize> {
         let mut read_bytes = 0;
@@ -35,6 +40,18 @@ pub trait Write {
     /// contents reach their destination.
     fn flush(&mut self) -> Result<()>;
 
+    /// Truncate or extend the underlying storage to `len` bytes,
+    /// the extended part reads as zeros.
+    fn set_len(&mut self, _len: usize) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
+    /// Forbid or allow the writes to the underlying storage,
+    /// kept by the file system if it supports attributes.
+    fn set_readonly(&mut self, _readonly: bool) -> Result<()> {
+        Err(FsError::NotSupported)
+    }
+
     /// Attempts to write an entire buffer into this writer.
     fn write_all(&mut self, mut _buf: &[u8]) -> Result<()> {
         // not required for lab
@@ -42,6 +59,31 @@ pub trait Write {
     }
 }
 
+/// The access pattern hinted by `Read::advise`, values are the same as `POSIX_FADV_*`
+#[derive(Copy, PartialEq, Eq, Clone, Debug, Default)]
+pub enum Advice {
+    /// No hint, reads the blocks one at a time like `Random`
+    #[default]
+    Normal = 0,
+    /// Reads jump around, nothing is read ahead
+    Random = 1,
+    /// Reads go forward, the next blocks are fetched along on each read
+    Sequential = 2,
+}
+
+impl TryFrom<usize> for Advice {
+    type Error = FsError;
+
+    fn try_from(value: usize) -> Result<Self> {
+        match value {
+            0 => Ok(Advice::Normal),
+            1 => Ok(Advice::Random),
+            2 => Ok(Advice::Sequential),
+            _ => Err(FsError::NotSupported),
+        }
+    }
+}
+
 /// Enumeration of possible methods to seek within an I/O object.
 #[derive(Copy, PartialEq, Eq, Clone, Debug)]
 pub enum SeekFrom {
@@ -55,6 +97,18 @@ pub enum SeekFrom {
     Current(isize),
 }
 
+impl SeekFrom {
+    /// The absolute offset from `current` in a stream of `length` bytes,
+    /// `None` if it is before the start or overflows
+    pub fn resolve(self, current: usize, length: usize) -> Option<usize> {
+        match self {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => length.checked_add_signed(offset),
+            SeekFrom::Current(offset) => current.checked_add_signed(offset),
+        }
+    }
+}
+
 /// The `Seek` trait provides a cursor within byte stream.
 pub trait Seek {
     /// Seek to an offset, in bytes, in a stream.
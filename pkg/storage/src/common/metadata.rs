@@ -21,6 +21,8 @@ pub struct Metadata {
     pub entry_type: FileType,
     /// Length of the file in bytes, 0 for directories
     pub len: usize,
+    /// Whether the entry cannot be written
+    pub readonly: bool,
     /// Creation time of the file
     pub created: Option<FsTime>,
     /// Modification time of the file
@@ -35,12 +37,14 @@ impl Metadata {
         name: String,
         entry_type: FileType,
         len: usize,
+        readonly: bool,
         created: Option<FsTime>,
         modified: Option<FsTime>,
         accessed: Option<FsTime>,
     ) -> Self {
         Self {
             len,
+            readonly,
             name,
             created,
             modified,
@@ -61,3 +65,14 @@ impl Metadata {
         self.entry_type == FileType::Directory
     }
 }
+
+/// The space of a file system, counted in its allocation units
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FsStat {
+    /// The number of blocks for the file data
+    pub total_blocks: usize,
+    /// The number of blocks not allocated to any file
+    pub free_blocks: usize,
+    /// The size of a block in bytes
+    pub block_size: usize,
+}
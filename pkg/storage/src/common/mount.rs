@@ -40,6 +40,27 @@ impl FileSystem for Mount {
     fn exists(&self, path: &str) -> Result<bool> {
         self.fs.exists(self.trim_mount_point(path))
     }
+
+    #[inline]
+    fn create_file(&self, path: &str) -> Result<FileHandle> {
+        self.fs.create_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn remove_file(&self, path: &str) -> Result<()> {
+        self.fs.remove_file(self.trim_mount_point(path))
+    }
+
+    #[inline]
+    fn move_file(&self, src: &str, dst: &str) -> Result<()> {
+        self.fs
+            .move_file(self.trim_mount_point(src), self.trim_mount_point(dst))
+    }
+
+    #[inline]
+    fn stat_fs(&self) -> Result<FsStat> {
+        self.fs.stat_fs()
+    }
 }
 
 impl core::fmt::Debug for Mount {
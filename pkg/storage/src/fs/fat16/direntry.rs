@@ -23,6 +23,15 @@ pub struct DirEntry {
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub struct Cluster(pub u32);
 
+/// Where a dir entry is stored on the disk
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EntryLocation {
+    /// The sector holding the entry
+    pub sector: usize,
+    /// The byte offset of the entry in the sector
+    pub offset: usize,
+}
+
 bitflags! {
     /// File Attributes
     #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -117,6 +126,9 @@ pub struct ShortFileName {
 }
 
 impl ShortFileName {
+    /// The first byte of a deleted entry
+    pub const UNUSED: u8 = 0xE5;
+
     pub fn new(buf: &[u8]) -> Self {
         Self {
             name: buf[..8].try_into().unwrap(),
@@ -137,7 +149,7 @@ impl ShortFileName {
     }
 
     pub fn is_unused(&self) -> bool {
-        self.name[0] == 0xE5
+        self.name[0] == Self::UNUSED
     }
 
     pub fn matches(&self, sfn: &ShortFileName) -> bool {
@@ -256,6 +268,7 @@ impl From<&DirEntry> for Metadata {
             },
             name: entry.filename(),
             len: entry.size as usize,
+            readonly: entry.attributes.contains(Attributes::READ_ONLY),
             created: Some(entry.created_time),
             accessed: Some(entry.accessed_time),
             modified: Some(entry.modified_time),
@@ -2,10 +2,39 @@
 //!
 //! reference: <https://wiki.osdev.org/FAT#Directories_on_FAT12.2F16.2F32>
 
-use core::cmp::min;
+use core::cmp::{min, Ordering};
 
 use super::*;
 
+/// The size of a file is kept in 32 bits by the directory entry
+pub const MAX_FILE_SIZE: usize = u32::MAX as usize;
+/// The sectors fetched at once by a sequential reader, within a cluster
+pub const READAHEAD_SECTORS: usize = 8;
+
+/// The sectors fetched ahead for `Advice::Sequential`,
+/// private to the open file and dropped on any write through it
+#[derive(Clone, Default)]
+struct ReadAhead {
+    first: usize,
+    blocks: Vec<Block512>,
+}
+
+impl ReadAhead {
+    fn get(&self, sector: usize) -> Option<&Block512> {
+        sector.checked_sub(self.first).and_then(|i| self.blocks.get(i))
+    }
+
+    fn clear(&mut self) {
+        self.blocks.clear();
+    }
+}
+
+impl core::fmt::Debug for ReadAhead {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "ReadAhead({}+{})", self.first, self.blocks.len())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct File {
     /// The current offset in the file
@@ -14,79 +43,247 @@ pub struct File {
     current_cluster: Cluster,
     /// DirEntry of this file
     entry: DirEntry,
+    /// Where the DirEntry is stored, updated on write
+    location: EntryLocation,
     /// The file system handle that contains this file
     handle: Fat16Handle,
+    /// The access pattern hinted by `advise`
+    advice: Advice,
+    ahead: ReadAhead,
 }
 
 impl File {
-    pub fn new(handle: Fat16Handle, entry: DirEntry) -> Self {
+    pub fn new(handle: Fat16Handle, entry: DirEntry, location: EntryLocation) -> Self {
         Self {
             offset: 0,
             current_cluster: entry.cluster,
             entry,
+            location,
             handle,
+            advice: Advice::Normal,
+            ahead: ReadAhead::default(),
         }
     }
 
     pub fn length(&self) -> usize {
         self.entry.size as usize
     }
+
+    fn cluster_size(&self) -> usize {
+        self.handle.bpb.bytes_per_sector() as usize * self.handle.bpb.sectors_per_cluster() as usize
+    }
+
+    // get the cluster holding the offset, extend the chain if needed
+    fn cluster_at(&mut self, offset: usize) -> Result<Cluster> {
+        // an empty file has no cluster yet
+        if self.entry.cluster == Cluster::EMPTY {
+            self.entry.cluster = self.handle.alloc_cluster()?;
+        }
+        let mut cluster = self.entry.cluster;
+        for _ in 0..offset / self.cluster_size() {
+            cluster = self.next_cluster_or_alloc(&cluster)?;
+        }
+        Ok(cluster)
+    }
+
+    // get the next cluster of the chain, append a new one at the end
+    fn next_cluster_or_alloc(&self, cluster: &Cluster) -> Result<Cluster> {
+        match self.handle.get_next_cluster(cluster)? {
+            Cluster::END_OF_FILE | Cluster::EMPTY => {
+                let next = self.handle.alloc_cluster()?;
+                self.handle.set_next_cluster(cluster, next.0 as u16)?;
+                Ok(next)
+            }
+            Cluster::INVALID => Err(FsError::BadCluster),
+            next => Ok(next),
+        }
+    }
 }
 
 impl Read for File {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
         // read file content from disk
         let bps = self.handle.bpb.bytes_per_sector() as usize;
-        let spc = self.handle.bpb.sectors_per_cluster() as usize;
+        let cluster_size = bps * self.handle.bpb.sectors_per_cluster() as usize;
+        let sectors_per_cluster = cluster_size / bps;
         let mut read_bytes = 0;
-        let mut sector_offset = self.offset / bps;
-        let mut byte_offset = self.offset % bps;
         let mut block = Block::default();
-        loop {
-            self.handle.inner.read_block(
-                self.handle.cluster_to_first_sector(&self.current_cluster) + sector_offset,
-                &mut block,
-            )?;
+        while read_bytes < buf.len() && self.offset < self.length() {
+            let cluster_offset = self.offset % cluster_size;
+            let sector_offset = cluster_offset / bps;
+            let byte_offset = cluster_offset % bps;
+            let sector = self.handle.cluster_to_first_sector(&self.current_cluster) + sector_offset;
+
+            let block = match self.advice {
+                Advice::Sequential => {
+                    // fetch the rest of the cluster along, up to the end of the file
+                    if self.ahead.get(sector).is_none() {
+                        let left = (self.length() - self.offset + byte_offset).div_ceil(bps);
+                        let count = READAHEAD_SECTORS
+                            .min(sectors_per_cluster - sector_offset)
+                            .min(left);
+                        self.ahead.first = sector;
+                        self.ahead.blocks.resize(count, Block::default());
+                        self.handle.inner.read_blocks(sector, &mut self.ahead.blocks)?;
+                    }
+                    self.ahead.get(sector).unwrap()
+                }
+                Advice::Normal | Advice::Random => {
+                    self.handle.inner.read_block(sector, &mut block)?;
+                    &block
+                }
+            };
 
             let bytes_to_read = min(
                 min(buf.len() - read_bytes, bps - byte_offset),
-                self.entry.size as usize - self.offset,
+                self.length() - self.offset,
             );
 
             buf[read_bytes..read_bytes + bytes_to_read]
                 .copy_from_slice(&block[byte_offset..byte_offset + bytes_to_read]);
-            
+
             read_bytes += bytes_to_read;
             self.offset += bytes_to_read;
-            if read_bytes == buf.len() || self.offset == self.entry.size as usize {
-                break;
-            } else {
-                byte_offset = 0;
-                sector_offset += 1;
-                if sector_offset == spc {
-                    sector_offset = 0;
-                    self.current_cluster = self.handle.get_next_cluster(&self.current_cluster)?;
-                }
+
+            // move to the next cluster once the current one is consumed
+            if self.offset % cluster_size == 0 && self.offset < self.length() {
+                self.current_cluster = self.handle.get_next_cluster(&self.current_cluster)?;
             }
         }
         Ok(read_bytes)
     }
+
+    fn advise(&mut self, advice: Advice) -> Result<()> {
+        self.advice = advice;
+        self.ahead.clear();
+        Ok(())
+    }
 }
 
-// NOTE: `Seek` trait is not required for this lab
 impl Seek for File {
-    fn seek(&mut self, _pos: SeekFrom) -> Result<usize> {
-        unimplemented!()
+    fn seek(&mut self, pos: SeekFrom) -> Result<usize> {
+        let offset = pos
+            .resolve(self.offset, self.length())
+            .filter(|offset| *offset <= self.length())
+            .ok_or(FsError::InvalidOffset)?;
+
+        // walk the cluster chain from the beginning to find the cluster
+        // that contains the new offset
+        let cluster_size = self.handle.bpb.bytes_per_sector() as usize
+            * self.handle.bpb.sectors_per_cluster() as usize;
+        let mut cluster = self.entry.cluster;
+        for _ in 0..offset / cluster_size {
+            match self.handle.get_next_cluster(&cluster)? {
+                Cluster::END_OF_FILE | Cluster::EMPTY | Cluster::INVALID => break,
+                next => cluster = next,
+            }
+        }
+
+        self.offset = offset;
+        self.current_cluster = cluster;
+        Ok(offset)
     }
 }
 
-// NOTE: `Write` trait is not required for this lab
 impl Write for File {
-    fn write(&mut self, _buf: &[u8]) -> Result<usize> {
-        unimplemented!()
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.entry.attributes.contains(Attributes::READ_ONLY) {
+            return Err(FsError::ReadOnly);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.ahead.clear();
+        // the size must fit in the entry, reject instead of wrapping it
+        if self.offset.saturating_add(buf.len()) > MAX_FILE_SIZE {
+            return Err(FsError::InvalidOffset);
+        }
+
+        let bps = self.handle.bpb.bytes_per_sector() as usize;
+        let cluster_size = self.cluster_size();
+        let mut cluster = self.cluster_at(self.offset)?;
+        let mut written_bytes = 0;
+        let mut block = Block::default();
+        while written_bytes < buf.len() {
+            let cluster_offset = self.offset % cluster_size;
+            let sector = self.handle.cluster_to_first_sector(&cluster) + cluster_offset / bps;
+            let byte_offset = cluster_offset % bps;
+            let bytes_to_write = min(buf.len() - written_bytes, bps - byte_offset);
+
+            // keep the rest of the sector when it is partly written
+            if bytes_to_write < bps {
+                self.handle.inner.read_block(sector, &mut block)?;
+            }
+            block.as_mut()[byte_offset..byte_offset + bytes_to_write]
+                .copy_from_slice(&buf[written_bytes..written_bytes + bytes_to_write]);
+            self.handle.inner.write_block(sector, &block)?;
+
+            written_bytes += bytes_to_write;
+            self.offset += bytes_to_write;
+
+            if self.offset % cluster_size == 0 && written_bytes < buf.len() {
+                cluster = self.next_cluster_or_alloc(&cluster)?;
+            }
+        }
+
+        if self.offset > self.length() {
+            self.entry.size = self.offset as u32;
+        }
+        self.handle.update_slot(&self.location, &self.entry)?;
+
+        // keep the current cluster in sync with the offset for reading
+        self.seek(SeekFrom::Start(self.offset))?;
+        Ok(written_bytes)
     }
 
     fn flush(&mut self) -> Result<()> {
-        unimplemented!()
+        // every write goes to the device directly
+        Ok(())
+    }
+
+    /// The offset is kept, or moved to the new end if it is beyond
+    fn set_len(&mut self, len: usize) -> Result<()> {
+        if self.entry.attributes.contains(Attributes::READ_ONLY) {
+            return Err(FsError::ReadOnly);
+        }
+        if len > MAX_FILE_SIZE {
+            return Err(FsError::InvalidOffset);
+        }
+        self.ahead.clear();
+
+        let offset = self.offset;
+        match len.cmp(&self.length()) {
+            Ordering::Greater => {
+                // the new clusters are not cleared, write the zeros out
+                let zeros = [0u8; BLOCK_SIZE];
+                self.seek(SeekFrom::End(0))?;
+                while self.length() < len {
+                    self.write(&zeros[..min(BLOCK_SIZE, len - self.length())])?;
+                }
+            }
+            Ordering::Less => {
+                if len == 0 {
+                    self.handle.free_cluster_chain(self.entry.cluster)?;
+                    self.entry.cluster = Cluster::EMPTY;
+                } else {
+                    // drop the clusters after the one holding the last byte
+                    let last = self.cluster_at(len - 1)?;
+                    let next = self.handle.get_next_cluster(&last)?;
+                    self.handle.free_cluster_chain(next)?;
+                    self.handle.set_next_cluster(&last, 0xFFFF)?;
+                }
+                self.entry.size = len as u32;
+                self.handle.update_slot(&self.location, &self.entry)?;
+            }
+            Ordering::Equal => {}
+        }
+
+        self.seek(SeekFrom::Start(min(offset, len)))?;
+        Ok(())
+    }
+
+    fn set_readonly(&mut self, readonly: bool) -> Result<()> {
+        self.entry.attributes.set(Attributes::READ_ONLY, readonly);
+        self.handle.update_slot(&self.location, &self.entry)
     }
 }
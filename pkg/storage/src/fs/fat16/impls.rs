@@ -44,21 +44,18 @@ impl Fat16Impl {
             Ok(Cluster::END_OF_FILE)
         } else {
             let mut block = Block::default();
-            self.inner.read_block(self.fat_start, &mut block).unwrap();
-            let tem = (cluster.0 * 2) as usize;
+            // each FAT16 entry takes 2 bytes, locate the sector holding it
+            let fat_offset = (cluster.0 * 2) as usize;
+            self.inner
+                .read_block(self.fat_start + fat_offset / BLOCK_SIZE, &mut block)?;
+            let tem = fat_offset % BLOCK_SIZE;
             let next = u16::from_le_bytes(block[tem..tem + 2].try_into().unwrap()) as u32;
-            match Cluster(next) {
-                Cluster::EMPTY => Ok(Cluster::EMPTY),
-                Cluster::BAD => Err(FsError::BadCluster),
-                Cluster(c) => {
-                    if (0x0000_0002..0x0000_FFF6).contains(&c) {
-                        Ok(Cluster(c))
-                    } else if c >= 0xFFFF_FFF8 {
-                        Ok(Cluster::END_OF_FILE)
-                    } else {
-                        Ok(Cluster::INVALID)
-                    }
-                }
+            match next {
+                0x0000 => Ok(Cluster::EMPTY),
+                0xFFF7 => Err(FsError::BadCluster),
+                0x0002..=0xFFF6 => Ok(Cluster(next)),
+                0xFFF8..=0xFFFF => Ok(Cluster::END_OF_FILE),
+                _ => Ok(Cluster::INVALID),
             }
         }
     }
@@ -67,6 +64,22 @@ impl Fat16Impl {
     pub fn traverse_dir_entries<F>(&self, dir: &Directory, mut process_entry: F) -> Result<()>
     where
         F: FnMut(DirEntry) -> Result<()>,
+    {
+        self.traverse_dir_slots(dir, |entry, _| {
+            if entry.is_valid() {
+                process_entry(entry)?;
+            }
+            Ok(false)
+        })
+    }
+
+    // traverse all slots in the dir, including the unused ones,
+    // until the end of dir, which is also passed to `process_slot`
+    //
+    // return `true` in `process_slot` to stop the traversal
+    fn traverse_dir_slots<F>(&self, dir: &Directory, mut process_slot: F) -> Result<()>
+    where
+        F: FnMut(DirEntry, EntryLocation) -> Result<bool>,
     {
         let mut block = Block::default();
         let mut cluster = dir.cluster;
@@ -82,13 +95,16 @@ impl Fat16Impl {
             };
 
             while entry_num > 0 {
-                self.inner.read_block(now_sector, &mut block).unwrap();
+                self.inner.read_block(now_sector, &mut block)?;
                 let mut offset = 0;
                 for _ in 0..min(entry_num, entry_per_block) {
-                    let entry = DirEntry::parse(&block[offset..offset + DirEntry::LEN]).unwrap();
-                    if entry.is_valid() {
-                        process_entry(entry)?;
-                    } else {
+                    let entry = DirEntry::parse(&block[offset..offset + DirEntry::LEN])?;
+                    let is_eod = entry.filename.is_eod();
+                    let location = EntryLocation {
+                        sector: now_sector,
+                        offset,
+                    };
+                    if process_slot(entry, location)? || is_eod {
                         return Ok(());
                     }
                     entry_num -= 1;
@@ -105,6 +121,171 @@ impl Fat16Impl {
         }
     }
 
+    // find the entry by name in the dir, and where it is stored
+    pub fn find_dir_entry(&self, dir: &Directory, name: &str) -> Result<(DirEntry, EntryLocation)> {
+        let parse_name = ShortFileName::parse(name)?;
+        let mut result = None;
+        self.traverse_dir_slots(dir, |entry, location| {
+            if entry.is_valid() && entry.filename.matches(&parse_name) {
+                result = Some((entry, location));
+                return Ok(true);
+            }
+            Ok(false)
+        })?;
+        result.ok_or(FsError::FileNotFound)
+    }
+
+    // find the first unused slot in the dir
+    //
+    // NOTE: the dir is not extended when it is full
+    pub fn find_free_slot(&self, dir: &Directory) -> Result<EntryLocation> {
+        let mut result = None;
+        self.traverse_dir_slots(dir, |entry, location| {
+            if !entry.is_valid() {
+                result = Some(location);
+                return Ok(true);
+            }
+            Ok(false)
+        })?;
+        result.ok_or(FsError::WriteZero)
+    }
+
+    // read the raw bytes of the slot
+    pub fn read_slot(&self, location: &EntryLocation) -> Result<[u8; DirEntry::LEN]> {
+        let mut block = Block::default();
+        self.inner.read_block(location.sector, &mut block)?;
+        let offset = location.offset;
+        Ok(block[offset..offset + DirEntry::LEN].try_into().unwrap())
+    }
+
+    // overwrite the raw bytes of the slot
+    pub fn write_slot(&self, location: &EntryLocation, data: &[u8; DirEntry::LEN]) -> Result<()> {
+        let mut block = Block::default();
+        self.inner.read_block(location.sector, &mut block)?;
+        let offset = location.offset;
+        block.as_mut()[offset..offset + DirEntry::LEN].copy_from_slice(data);
+        self.inner.write_block(location.sector, &block)
+    }
+
+    // write the FAT entry of the cluster in every copy of the FAT
+    pub fn set_next_cluster(&self, cluster: &Cluster, next: u16) -> Result<()> {
+        let mut block = Block::default();
+        let fat_offset = (cluster.0 * 2) as usize;
+        let tem = fat_offset % BLOCK_SIZE;
+        for i in 0..self.bpb.fat_count() as usize {
+            let sector = self.fat_start
+                + i * self.bpb.sectors_per_fat() as usize
+                + fat_offset / BLOCK_SIZE;
+            self.inner.read_block(sector, &mut block)?;
+            block.as_mut()[tem..tem + 2].copy_from_slice(&next.to_le_bytes());
+            self.inner.write_block(sector, &block)?;
+        }
+        Ok(())
+    }
+
+    // the number of data clusters on the volume
+    fn cluster_count(&self) -> usize {
+        (self.bpb.total_sectors() as usize - self.first_data_sector)
+            / self.bpb.sectors_per_cluster() as usize
+    }
+
+    // count the clusters with a zero FAT entry
+    pub fn free_cluster_count(&self) -> Result<usize> {
+        let mut block = Block::default();
+        let mut loaded_sector = None;
+        let mut count = 0;
+        for c in 2..self.cluster_count() + 2 {
+            let fat_offset = c * 2;
+            let sector = self.fat_start + fat_offset / BLOCK_SIZE;
+            if loaded_sector != Some(sector) {
+                self.inner.read_block(sector, &mut block)?;
+                loaded_sector = Some(sector);
+            }
+            let tem = fat_offset % BLOCK_SIZE;
+            if block[tem..tem + 2] == [0, 0] {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    // find a free cluster and mark it as the end of a chain
+    pub fn alloc_cluster(&self) -> Result<Cluster> {
+        let mut block = Block::default();
+        let mut loaded_sector = None;
+        for c in 2..self.cluster_count() + 2 {
+            let fat_offset = c * 2;
+            let sector = self.fat_start + fat_offset / BLOCK_SIZE;
+            if loaded_sector != Some(sector) {
+                self.inner.read_block(sector, &mut block)?;
+                loaded_sector = Some(sector);
+            }
+            let tem = fat_offset % BLOCK_SIZE;
+            if block[tem..tem + 2] == [0, 0] {
+                let cluster = Cluster(c as u32);
+                self.set_next_cluster(&cluster, 0xFFFF)?;
+                return Ok(cluster);
+            }
+        }
+        Err(FsError::WriteZero)
+    }
+
+    // update the attributes, the first cluster & the size of the entry stored in the slot
+    pub fn update_slot(&self, location: &EntryLocation, entry: &DirEntry) -> Result<()> {
+        let mut data = self.read_slot(location)?;
+        data[11] = entry.attributes.bits();
+        data[26..28].copy_from_slice(&(entry.cluster.0 as u16).to_le_bytes());
+        data[28..32].copy_from_slice(&entry.size.to_le_bytes());
+        self.write_slot(location, &data)
+    }
+
+    // mark all the clusters of the chain as free
+    pub fn free_cluster_chain(&self, start: Cluster) -> Result<()> {
+        let mut cluster = start;
+        // an empty file has no cluster
+        while (2..0xFFF7).contains(&cluster.0) {
+            let next = self.get_next_cluster(&cluster)?;
+            self.set_next_cluster(&cluster, 0)?;
+            cluster = next;
+        }
+        Ok(())
+    }
+
+    // open the dir holding the last part of the path
+    pub fn open_parent_dir<'a>(&self, path: &'a str) -> Result<(Directory, &'a str)> {
+        let mut parts = self.parse_path(path);
+        let name = parts
+            .pop()
+            .ok_or_else(|| FsError::InvalidPath(String::from(path)))?;
+
+        let mut dir = self.open_root_dir();
+        for part in parts {
+            let entry = self.get_dir_entry_by_name(&dir, part)?;
+            if entry.is_directory() {
+                dir = Directory::from_entry(entry);
+            } else {
+                return Err(FsError::NotADirectory);
+            }
+        }
+
+        Ok((dir, name))
+    }
+
+    // remove the file entry and free its clusters
+    fn remove_entry(&self, entry: &DirEntry, location: &EntryLocation) -> Result<()> {
+        if entry.is_directory() {
+            return Err(FsError::NotAFile);
+        }
+        if entry.attributes.contains(Attributes::READ_ONLY) {
+            return Err(FsError::ReadOnly);
+        }
+
+        let mut data = self.read_slot(location)?;
+        data[0] = ShortFileName::UNUSED;
+        self.write_slot(location, &data)?;
+        self.free_cluster_chain(entry.cluster)
+    }
+
     pub fn get_dir_entry_by_name(&self, dir: &Directory, name: &str) -> Result<DirEntry> {
         let parse_name = ShortFileName::parse(name)?;
         let mut result = None;
@@ -170,14 +351,14 @@ impl FileSystem for Fat16 {
 
         for i in 0..parts.len() {
             let part = parts[i];
-            let entry = self.handle.get_dir_entry_by_name(&dir, part)?;
+            let (entry, location) = self.handle.find_dir_entry(&dir, part)?;
             if i == parts.len() - 1 {
                 if entry.is_directory() {
                     return Err(FsError::NotAFile);
                 } else {
                     return Ok(FileHandle::new(
                         Metadata::from(&entry),
-                        Box::new(File::new(self.handle.clone(), entry)),
+                        Box::new(File::new(self.handle.clone(), entry, location)),
                     ));
                 }
             } else {
@@ -211,16 +392,453 @@ impl FileSystem for Fat16 {
         let parts = self.handle.parse_path(path);
         let mut dir = self.handle.open_root_dir();
 
+        // the root dir always exists
+        if parts.is_empty() {
+            return Ok(true);
+        }
+
         for i in 0..parts.len() {
             let part = parts[i];
             let entry = self.handle.get_dir_entry_by_name(&dir, part)?;
             if i == parts.len() - 1 {
                 return Ok(true);
-            } else {
+            } else if entry.is_directory() {
                 dir = Directory::from_entry(entry);
+            } else {
+                return Err(FsError::NotADirectory);
             }
         }
 
         Err(FsError::FileNotFound)
     }
+
+    /// Create an empty file in the first unused slot of the parent dir,
+    /// fails with `AlreadyExists` if the name is taken
+    fn create_file(&self, path: &str) -> Result<FileHandle> {
+        let (dir, name) = self.handle.open_parent_dir(path)?;
+        match self.handle.find_dir_entry(&dir, name) {
+            Ok(_) => return Err(FsError::AlreadyExists),
+            Err(FsError::FileNotFound) => {}
+            Err(err) => return Err(err),
+        }
+
+        let filename = ShortFileName::parse(name)?;
+        let mut data = [0u8; DirEntry::LEN];
+        data[..8].copy_from_slice(&filename.name);
+        data[8..11].copy_from_slice(&filename.ext);
+        data[11] = Attributes::ARCHIVE.bits();
+
+        let location = self.handle.find_free_slot(&dir)?;
+        self.handle.write_slot(&location, &data)?;
+
+        let entry = DirEntry::parse(&data)?;
+        Ok(FileHandle::new(
+            Metadata::from(&entry),
+            Box::new(File::new(self.handle.clone(), entry, location)),
+        ))
+    }
+
+    fn remove_file(&self, path: &str) -> Result<()> {
+        let (dir, name) = self.handle.open_parent_dir(path)?;
+        let (entry, location) = self.handle.find_dir_entry(&dir, name)?;
+        self.handle.remove_entry(&entry, &location)
+    }
+
+    fn move_file(&self, src: &str, dst: &str) -> Result<()> {
+        let (src_dir, src_name) = self.handle.open_parent_dir(src)?;
+        let (dst_dir, dst_name) = self.handle.open_parent_dir(dst)?;
+        let (entry, src_location) = self.handle.find_dir_entry(&src_dir, src_name)?;
+        if entry.attributes.contains(Attributes::READ_ONLY) {
+            return Err(FsError::ReadOnly);
+        }
+
+        let dst_filename = ShortFileName::parse(dst_name)?;
+        let same_dir = src_dir.cluster == dst_dir.cluster;
+        // moving a dir elsewhere requires updating its ".." entry
+        if entry.is_directory() && !same_dir {
+            return Err(FsError::NotSupported);
+        }
+
+        // replace the existing destination file
+        match self.handle.find_dir_entry(&dst_dir, dst_name) {
+            Ok((_, location)) if location == src_location => return Ok(()),
+            Ok((target, location)) => self.handle.remove_entry(&target, &location)?,
+            Err(FsError::FileNotFound) => {}
+            Err(err) => return Err(err),
+        }
+
+        let mut data = self.handle.read_slot(&src_location)?;
+        data[..8].copy_from_slice(&dst_filename.name);
+        data[8..11].copy_from_slice(&dst_filename.ext);
+
+        if same_dir {
+            return self.handle.write_slot(&src_location, &data);
+        }
+
+        let dst_location = self.handle.find_free_slot(&dst_dir)?;
+        self.handle.write_slot(&dst_location, &data)?;
+
+        let mut old = self.handle.read_slot(&src_location)?;
+        old[0] = ShortFileName::UNUSED;
+        self.handle.write_slot(&src_location, &old)
+    }
+
+    /// The blocks are the clusters, the root dir is not counted
+    fn stat_fs(&self) -> Result<FsStat> {
+        Ok(FsStat {
+            total_blocks: self.handle.cluster_count(),
+            free_blocks: self.handle.free_cluster_count()?,
+            block_size: self.handle.bpb.sectors_per_cluster() as usize * BLOCK_SIZE,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::file::MAX_FILE_SIZE;
+    use super::*;
+    use spin::Mutex;
+
+    /// A tiny in-memory FAT16 volume
+    ///
+    /// [ BPB ] [ FAT ] [ FAT ] [ Root Dir ] [ Data ... ]
+    struct MemDisk(Mutex<Vec<[u8; BLOCK_SIZE]>>);
+
+    impl BlockDevice<Block512> for MemDisk {
+        fn block_count(&self) -> Result<usize> {
+            Ok(self.0.lock().len())
+        }
+
+        fn read_block(&self, offset: usize, block: &mut Block512) -> Result<()> {
+            block.as_mut().copy_from_slice(&self.0.lock()[offset]);
+            Ok(())
+        }
+
+        fn write_block(&self, offset: usize, block: &Block512) -> Result<()> {
+            self.0.lock()[offset].copy_from_slice(block.as_ref());
+            Ok(())
+        }
+    }
+
+    fn dir_entry(name: &[u8; 11], cluster: u16, size: u32) -> [u8; DirEntry::LEN] {
+        let mut data = [0u8; DirEntry::LEN];
+        data[..11].copy_from_slice(name);
+        data[11] = Attributes::ARCHIVE.bits();
+        data[26..28].copy_from_slice(&cluster.to_le_bytes());
+        data[28..32].copy_from_slice(&size.to_le_bytes());
+        data
+    }
+
+    /// `A.TXT` takes cluster 2, `B.TXT` takes cluster 3 and 4
+    fn volume() -> Fat16 {
+        let mut sectors = vec![[0u8; BLOCK_SIZE]; 32];
+
+        let bpb = &mut sectors[0];
+        bpb[0x0B..0x0D].copy_from_slice(&512u16.to_le_bytes());
+        bpb[0x0D] = 1;
+        bpb[0x0E..0x10].copy_from_slice(&1u16.to_le_bytes());
+        bpb[0x10] = 2;
+        bpb[0x11..0x13].copy_from_slice(&16u16.to_le_bytes());
+        bpb[0x13..0x15].copy_from_slice(&32u16.to_le_bytes());
+        bpb[0x15] = 0xF8;
+        bpb[0x16..0x18].copy_from_slice(&1u16.to_le_bytes());
+        bpb[0x1FE..0x200].copy_from_slice(&0xAA55u16.to_le_bytes());
+
+        for fat in &mut sectors[1..3] {
+            for (i, next) in [0xFFF8u16, 0xFFFF, 0xFFFF, 4, 0xFFFF].iter().enumerate() {
+                fat[i * 2..i * 2 + 2].copy_from_slice(&next.to_le_bytes());
+            }
+        }
+
+        sectors[3][..32].copy_from_slice(&dir_entry(b"A       TXT", 2, 5));
+        sectors[3][32..64].copy_from_slice(&dir_entry(b"B       TXT", 3, 600));
+        sectors[4][..5].copy_from_slice(b"hello");
+
+        Fat16::new(MemDisk(Mutex::new(sectors)))
+    }
+
+    fn fat_entries(fs: &Fat16, cluster: u32) -> Vec<u16> {
+        let mut block = Block::default();
+        (1..3)
+            .map(|sector| {
+                fs.handle.inner.read_block(sector, &mut block).unwrap();
+                let offset = cluster as usize * 2;
+                u16::from_le_bytes([block[offset], block[offset + 1]])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_exists() {
+        let fs = volume();
+
+        assert_eq!(fs.exists("/"), Ok(true));
+        assert_eq!(fs.exists("/A.TXT"), Ok(true));
+        assert_eq!(fs.exists("/C.TXT"), Err(FsError::FileNotFound));
+        assert_eq!(fs.exists("/A.TXT/B.TXT"), Err(FsError::NotADirectory));
+    }
+
+    #[test]
+    fn test_remove_file() {
+        let fs = volume();
+
+        fs.remove_file("/A.TXT").unwrap();
+
+        assert_eq!(fs.exists("/A.TXT"), Err(FsError::FileNotFound));
+        assert_eq!(fat_entries(&fs, 2), [0, 0]);
+        // entries after the deleted one are still reachable
+        assert_eq!(fs.metadata("/B.TXT").unwrap().len, 600);
+        assert_eq!(fs.remove_file("/A.TXT"), Err(FsError::FileNotFound));
+    }
+
+    #[test]
+    fn test_create_file() {
+        let fs = volume();
+
+        let mut file = fs.create_file("/C.TXT").unwrap();
+        assert_eq!(file.meta.len, 0);
+        assert_eq!(file.write(b"created").unwrap(), 7);
+        assert_eq!(fs.metadata("/C.TXT").unwrap().len, 7);
+        // the name is taken, also by the existing files
+        assert_eq!(fs.create_file("/C.TXT").err(), Some(FsError::AlreadyExists));
+        assert_eq!(fs.create_file("/A.TXT").err(), Some(FsError::AlreadyExists));
+
+        // the slot of a removed file is reused
+        fs.remove_file("/A.TXT").unwrap();
+        fs.create_file("/D.TXT").unwrap();
+        assert_eq!(fs.metadata("/B.TXT").unwrap().len, 600);
+        assert_eq!(fs.metadata("/C.TXT").unwrap().len, 7);
+    }
+
+    #[test]
+    fn test_move_file_over_existing() {
+        let fs = volume();
+
+        fs.move_file("/A.TXT", "/B.TXT").unwrap();
+
+        assert_eq!(fs.exists("/A.TXT"), Err(FsError::FileNotFound));
+        // the replaced file is released
+        assert_eq!(fat_entries(&fs, 3), [0, 0]);
+        assert_eq!(fat_entries(&fs, 4), [0, 0]);
+
+        let mut file = fs.open_file("/B.TXT").unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(file.read(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"hello");
+    }
+
+    #[test]
+    fn test_write_overwrite() {
+        let fs = volume();
+
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        file.seek(SeekFrom::Start(1)).unwrap();
+        assert_eq!(file.write(b"ipp"), Ok(3));
+
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(file.read(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"hippo");
+        // no cluster is taken for writing inside the file
+        assert_eq!(fat_entries(&fs, 2), [0xFFFF, 0xFFFF]);
+        assert_eq!(fat_entries(&fs, 5), [0, 0]);
+    }
+
+    #[test]
+    fn test_write_extend() {
+        let fs = volume();
+
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        file.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(file.write(&data), Ok(1000));
+
+        assert_eq!(fs.metadata("/A.TXT").unwrap().len, 1005);
+        // the chain grows into the free clusters after `B.TXT`
+        assert_eq!(fat_entries(&fs, 2), [5, 5]);
+        assert_eq!(fat_entries(&fs, 5), [0xFFFF, 0xFFFF]);
+
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        let mut buf = vec![0u8; 1100];
+        let mut len = 0;
+        while let Ok(n @ 1..) = file.read(&mut buf[len..]) {
+            len += n;
+        }
+        assert_eq!(len, 1005);
+        assert_eq!(&buf[..5], b"hello");
+        assert_eq!(&buf[5..len], &data[..]);
+    }
+
+    #[test]
+    fn test_read_sequential() {
+        let fs = volume();
+
+        let data: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        file.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(file.write(&data), Ok(1000));
+
+        // the sectors read ahead give the same bytes across the clusters
+        file.advise(Advice::Sequential).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        let mut buf = vec![0u8; 1100];
+        let mut len = 0;
+        while let Ok(n @ 1..) = file.read(&mut buf[len..(len + 64).min(1100)]) {
+            len += n;
+        }
+        assert_eq!(len, 1005);
+        assert_eq!(&buf[..5], b"hello");
+        assert_eq!(&buf[5..len], &data[..]);
+
+        // a write through the file drops the sectors read ahead
+        file.seek(SeekFrom::Start(1)).unwrap();
+        assert_eq!(file.write(b"ipp"), Ok(3));
+        file.seek(SeekFrom::Start(0)).unwrap();
+        assert_eq!(file.read(&mut buf[..5]), Ok(5));
+        assert_eq!(&buf[..5], b"hippo");
+    }
+
+    #[test]
+    fn test_set_len_shrink() {
+        let fs = volume();
+
+        let mut file = fs.open_file("/B.TXT").unwrap();
+        file.seek(SeekFrom::End(0)).unwrap();
+        file.set_len(100).unwrap();
+
+        assert_eq!(fs.metadata("/B.TXT").unwrap().len, 100);
+        // the second cluster is released, the offset moves to the new end
+        assert_eq!(fat_entries(&fs, 3), [0xFFFF, 0xFFFF]);
+        assert_eq!(fat_entries(&fs, 4), [0, 0]);
+        assert_eq!(file.seek(SeekFrom::Current(0)), Ok(100));
+
+        file.set_len(0).unwrap();
+        assert_eq!(fs.metadata("/B.TXT").unwrap().len, 0);
+        assert_eq!(fat_entries(&fs, 3), [0, 0]);
+    }
+
+    #[test]
+    fn test_set_len_extend() {
+        let fs = volume();
+
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        file.seek(SeekFrom::Start(2)).unwrap();
+        file.set_len(1000).unwrap();
+
+        assert_eq!(fs.metadata("/A.TXT").unwrap().len, 1000);
+        assert_eq!(fat_entries(&fs, 2), [5, 5]);
+        // the offset is kept
+        assert_eq!(file.seek(SeekFrom::Current(0)), Ok(2));
+
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        let mut buf = vec![0xAAu8; 1100];
+        let mut len = 0;
+        while let Ok(n @ 1..) = file.read(&mut buf[len..]) {
+            len += n;
+        }
+        assert_eq!(len, 1000);
+        assert_eq!(&buf[..5], b"hello");
+        assert!(buf[5..len].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_set_readonly() {
+        let fs = volume();
+
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        file.set_readonly(true).unwrap();
+        assert!(fs.metadata("/A.TXT").unwrap().readonly);
+        assert_eq!(file.write(b"j"), Err(FsError::ReadOnly));
+
+        // the attribute is kept on the disk
+        let mut file = fs.open_file("/A.TXT").unwrap();
+        assert_eq!(file.write(b"j"), Err(FsError::ReadOnly));
+        file.set_readonly(false).unwrap();
+        assert!(!fs.metadata("/A.TXT").unwrap().readonly);
+        assert_eq!(file.write(b"j"), Ok(1));
+        // the other fields of the slot are untouched
+        assert_eq!(fs.metadata("/A.TXT").unwrap().len, 5);
+    }
+
+    #[test]
+    fn test_seek_resolve() {
+        const GIB: usize = 1 << 30;
+
+        let pos = SeekFrom::Current(GIB as isize);
+        assert_eq!(pos.resolve(2 * GIB, 4 * GIB), Some(3 * GIB));
+        assert_eq!(SeekFrom::End(-1).resolve(0, 3 * GIB), Some(3 * GIB - 1));
+        assert_eq!(SeekFrom::Current(-1).resolve(0, 3 * GIB), None);
+        assert_eq!(SeekFrom::Current(isize::MAX).resolve(usize::MAX, 0), None);
+    }
+
+    #[test]
+    fn test_large_file_offsets() {
+        const GIB: usize = 1 << 30;
+        let fs = volume();
+
+        // a file of the largest size in the directory entry, far beyond
+        // the volume, the offsets are checked without touching the data
+        let mut block = Block::default();
+        fs.handle.inner.read_block(3, &mut block).unwrap();
+        block.as_mut()[64..96].copy_from_slice(&dir_entry(b"BIG     BIN", 2, u32::MAX));
+        fs.handle.inner.write_block(3, &block).unwrap();
+
+        let mut file = fs.open_file("/BIG.BIN").unwrap();
+        assert_eq!(file.seek(SeekFrom::Start(2 * GIB + 1)), Ok(2 * GIB + 1));
+        assert_eq!(file.seek(SeekFrom::Current(GIB as isize - 1)), Ok(3 * GIB));
+        assert_eq!(file.seek(SeekFrom::End(-(GIB as isize))), Ok(MAX_FILE_SIZE - GIB));
+        assert_eq!(
+            file.seek(SeekFrom::Current(GIB as isize + 1)),
+            Err(FsError::InvalidOffset)
+        );
+        assert_eq!(
+            file.seek(SeekFrom::End(-(MAX_FILE_SIZE as isize) - 1)),
+            Err(FsError::InvalidOffset)
+        );
+        // the offset is kept by a failed seek
+        assert_eq!(file.seek(SeekFrom::Current(0)), Ok(MAX_FILE_SIZE - GIB));
+
+        // the size cannot grow past the entry
+        assert_eq!(
+            file.set_len(MAX_FILE_SIZE + 1),
+            Err(FsError::InvalidOffset)
+        );
+        assert_eq!(file.seek(SeekFrom::End(0)), Ok(MAX_FILE_SIZE));
+        assert_eq!(file.write(b"x"), Err(FsError::InvalidOffset));
+        assert_eq!(fs.metadata("/BIG.BIN").unwrap().len, MAX_FILE_SIZE);
+    }
+
+    #[test]
+    fn test_stat_fs() {
+        let fs = volume();
+
+        let stat = fs.stat_fs().unwrap();
+        assert_eq!(stat.total_blocks, 28);
+        assert_eq!(stat.free_blocks, 25);
+        assert_eq!(stat.block_size, 512);
+
+        // a new file takes no cluster until written
+        let mut file = fs.create_file("/C.TXT").unwrap();
+        assert_eq!(fs.stat_fs().unwrap().free_blocks, 25);
+        file.write(&[0x5A; 1500]).unwrap();
+        assert_eq!(fs.stat_fs().unwrap().free_blocks, 22);
+
+        fs.remove_file("/B.TXT").unwrap();
+        assert_eq!(fs.stat_fs().unwrap().free_blocks, 24);
+    }
+
+    #[test]
+    fn test_move_file_rename() {
+        let fs = volume();
+
+        fs.move_file("/B.TXT", "/c.txt").unwrap();
+
+        assert_eq!(fs.exists("/B.TXT"), Err(FsError::FileNotFound));
+        assert_eq!(fs.metadata("/C.TXT").unwrap().len, 600);
+        assert_eq!(fat_entries(&fs, 3), [4, 4]);
+        assert_eq!(
+            fs.move_file("/B.TXT", "/D.TXT"),
+            Err(FsError::FileNotFound)
+        );
+    }
 }
@@ -76,6 +76,14 @@ where
         }
     }
 
+    fn read_blocks(&self, offset: usize, blocks: &mut [B]) -> Result<()> {
+        if offset + blocks.len() > self.size {
+            Err(FsError::InvalidOffset)
+        } else {
+            self.inner.read_blocks(offset + self.offset, blocks)
+        }
+    }
+
     fn write_block(&self, offset: usize, block: &B) -> Result<()> {
         if offset >= self.size {
             Err(FsError::InvalidOffset)
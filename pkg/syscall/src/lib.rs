@@ -1,6 +1,6 @@
 #![no_std]
 
-use num_enum::FromPrimitive;
+use num_enum::{FromPrimitive, IntoPrimitive, TryFromPrimitive};
 
 pub mod macros;
 
@@ -12,16 +12,172 @@ pub enum Syscall {
     Open = 2,
     Close = 3,
 
+    Fstat = 5,
+
+    Poll = 7,
+    Seek = 8,
+    Mmap = 9,
+
+    Munmap = 11,
     Brk = 12,
+    Sigaction = 13,
+    SigProcMask = 14,
+
+    Readv = 19,
+    Writev = 20,
+
+    Access = 21,
+    Pipe = 22,
+
+    Yield = 24,
+
+    Madvise = 28,
+
+    Dup2 = 33,
+    Pause = 34,
+
+    NanoSleep = 36,
+    Alarm = 37,
+    SleepUntil = 38,
 
     GetPid = 39,
 
+    Thread = 56,
+    Clone = 57,
+
     Fork = 58,
     Spawn = 59,
     Exit = 60,
     WaitPid = 61,
+    Kill = 62,
     Sem = 64,
 
+    MsgGet = 72,
+    MsgSend = 73,
+    MsgRecv = 74,
+
+    Fchmod = 76,
+    Ftruncate = 77,
+    GetDents = 78,
+
+    SetPgid = 82,
+    Rename = 83,
+
+    Unlink = 87,
+    Symlink = 88,
+    Readlink = 89,
+
+    Getrusage = 98,
+
+    Uptime = 102,
+
+    SetSid = 112,
+
+    KillTree = 114,
+    WaitAny = 115,
+    Wait4 = 116,
+
+    GetPgid = 121,
+    GetSid = 124,
+
+    SetLogLevel = 130,
+    Debug = 131,
+    SetOpenDefaults = 132,
+    SetScheduler = 133,
+    GetScheduler = 134,
+    IsTty = 135,
+    SetMemLimit = 136,
+    StatFs = 137,
+
+    SendFile = 140,
+    MemInfo = 141,
+    GetPriority = 142,
+    SetPriority = 143,
+    SetProcName = 144,
+    Fsync = 145,
+    SetQuota = 146,
+    DumpSched = 147,
+    ListChildren = 148,
+    TraceMark = 149,
+    DumpTrace = 150,
+    MapFramebuffer = 151,
+    HeapAvail = 152,
+    Dmesg = 153,
+    DropPrivilege = 154,
+    Barrier = 155,
+    CaptureStart = 156,
+    CaptureEnd = 157,
+    SpinHint = 158,
+    SendFd = 159,
+    RecvFd = 160,
+    ResetStats = 161,
+    SerialConfig = 162,
+    WatchAdd = 163,
+    WatchRead = 164,
+    Prctl = 165,
+    StackCanary = 166,
+    StackChkFail = 167,
+    TeePipe = 168,
+    Shutdown = 169,
+    Checkpoint = 170,
+    Restore = 171,
+    LoadAvg = 172,
+    Vfork = 173,
+    SchedLatency = 174,
+    Chroot = 175,
+    FindByName = 176,
+    Fcntl = 177,
+
+    Tkill = 200,
+
+    Futex = 202,
+    SetAffinity = 203,
+    GetAffinity = 204,
+    Fadvise = 221,
+    Mlock = 228,
+    Munlock = 229,
+    ExitGroup = 231,
+
+    Splice = 275,
+
+    EventFd = 284,
+
+    Prlimit = 302,
+    GetCpu = 309,
+    GetRandom = 318,
+    MemBarrier = 324,
+
+    AllocStats = 65490,
+    DiskStats = 65491,
+    MaxPid = 65492,
+    FdStats = 65493,
+    GetProcName = 65494,
+    IdleHookRuns = 65495,
+    VirtToPhys = 65496,
+    GetArg = 65497,
+    PosixSpawn = 65498,
+    TableLookups = 65499,
+    WakeLog = 65500,
+    Collect = 65501,
+    SetStrace = 65502,
+    ProcAge = 65503,
+    ChildExited = 65504,
+    SigReturn = 65505,
+    SigChld = 65506,
+    IdleStats = 65507,
+    SetCloexec = 65508,
+    Exec = 65509,
+    FrameStats = 65510,
+    Cont = 65511,
+    SpawnDepth = 65512,
+    BlockReason = 65513,
+    SetEnv = 65514,
+    GetEnv = 65515,
+    TscFrequency = 65516,
+    ElfInfo = 65517,
+    PageFaults = 65518,
+    TickBudget = 65519,
+    ContextSwitches = 65520,
     ListDir = 65521,
     Time = 65529,
     PrintInfo = 65530,
@@ -33,3 +189,566 @@ pub enum Syscall {
     #[num_enum(default)]
     Unknown = 65535,
 }
+
+/// Why a process is blocked, reported by `Syscall::BlockReason`
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+pub enum BlockReason {
+    /// Sleeping until the wake tick
+    Sleeping = 1,
+    /// Waiting for another process to exit
+    WaitingChild = 2,
+    /// Waiting on a semaphore
+    Semaphore = 3,
+    /// Waiting to send or receive a message
+    Message = 4,
+    /// Waiting on a futex word
+    Futex = 5,
+    /// Lending the memory to a vfork child until it execs or exits
+    Vfork = 6,
+    /// Paused until a signal is delivered to its handler
+    Paused = 7,
+}
+
+/// What woke a blocked or stopped process, see `AuditEntry`
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+pub enum WakeSource {
+    /// The wake tick of a sleep is reached
+    Timer = 1,
+    /// A waited child exited
+    ChildExit = 2,
+    /// The semaphore is signaled
+    Semaphore = 3,
+    /// A message is sent or received
+    Message = 4,
+    /// The futex word is woken
+    Futex = 5,
+    /// A suspended process is continued by `Syscall::Cont`,
+    /// or a paused one receives a signal
+    Signal = 6,
+    /// The vfork child execs or exits
+    VforkDone = 7,
+}
+
+/// The process blocked, the detail is the `BlockReason`
+pub const AUDIT_BLOCKED: usize = 1;
+/// The process woke up, the detail is the `WakeSource`
+pub const AUDIT_WOKEN: usize = 2;
+/// The process is suspended, no detail
+pub const AUDIT_STOPPED: usize = 3;
+
+/// A state transition of a process, filled by `Syscall::WakeLog`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AuditEntry {
+    /// The clock counter of the transition
+    pub tick: u64,
+    /// `AUDIT_BLOCKED`, `AUDIT_WOKEN` or `AUDIT_STOPPED`
+    pub kind: usize,
+    pub detail: usize,
+}
+
+/// A marker left by `Syscall::TraceMark`, filled by `Syscall::DumpTrace`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The clock counter when it is marked
+    pub tick: u64,
+    pub pid: u16,
+    pub tag: u32,
+    pub value: u64,
+}
+
+/// The framebuffer mapped by `Syscall::MapFramebuffer`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FbInfo {
+    /// The address of the first pixel in the caller
+    pub base: usize,
+    /// The length in bytes
+    pub size: usize,
+    pub width: usize,
+    pub height: usize,
+    /// The pixels per scan line, which may be more than the width
+    pub stride: usize,
+    /// `FB_FORMAT_RGB` or `FB_FORMAT_BGR`, the pixels are 4 bytes each
+    pub format: u32,
+}
+
+/// The bytes of a pixel are red, green, blue and reserved
+pub const FB_FORMAT_RGB: u32 = 0;
+/// The bytes of a pixel are blue, green, red and reserved
+pub const FB_FORMAT_BGR: u32 = 1;
+
+/// Why a syscall fails, returned negated, e.g. -2 for `NotFound`,
+/// the values are the same as the errno of Linux
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+pub enum SysError {
+    /// The file, the process or the child does not exist (`ENOENT`)
+    NotFound = 2,
+    /// A sleep is cut short by a signal, see `SA_RESTART` (`EINTR`)
+    Intr = 4,
+    /// The fd is not open, or cannot be used that way (`EBADF`)
+    BadFd = 9,
+    /// Nothing can be done now, try again later (`EAGAIN`)
+    Again = 11,
+    /// Out of memory (`ENOMEM`)
+    NoMem = 12,
+    /// The file is read-only, or the operation is not permitted (`EACCES`)
+    Perm = 13,
+    /// The file is opened by some process (`EBUSY`)
+    Busy = 16,
+    /// The file exists, but is created exclusively (`EEXIST`)
+    Exists = 17,
+    /// An argument is invalid, e.g. a bad pointer (`EINVAL`)
+    Inval = 22,
+    /// The process has opened as many fds as its limit (`EMFILE`)
+    NoFds = 24,
+    /// Too many symlinks are followed in a path, likely a loop (`ELOOP`)
+    Loop = 40,
+}
+
+impl SysError {
+    /// The value returned by the syscall
+    pub const fn code(self) -> isize {
+        -(self as isize)
+    }
+}
+
+/// The scheduling policy, set by `Syscall::SetScheduler`
+#[repr(usize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
+pub enum SchedPolicy {
+    /// Preempt the running process on every tick
+    #[default]
+    RoundRobin = 0,
+    /// Run the process until it blocks, yields or exits
+    Fifo = 1,
+    /// Preempt on every tick, and pick the ready process
+    /// with the lowest priority value first
+    Priority = 2,
+}
+
+/// The type of the resource behind an fd, see `FileStat`
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileKind {
+    /// A file on the filesystem
+    #[default]
+    Regular = 1,
+    /// The console, a pipe or the null device, which have no size
+    CharDevice = 2,
+    /// A directory opened to iterate its entries
+    Directory = 3,
+}
+
+/// The status of an open fd, filled by `Syscall::Fstat`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileStat {
+    /// The length of the file in bytes, 0 for character devices
+    pub size: u64,
+    /// Whether the fd refers to a directory
+    pub is_dir: bool,
+    /// The type of the resource
+    pub kind: FileKind,
+}
+
+/// The space of the mounted filesystem, filled by `Syscall::StatFs`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatFsInfo {
+    /// The number of blocks for the file data
+    pub total_blocks: u64,
+    /// The number of blocks not allocated to any file
+    pub free_blocks: u64,
+    /// The size of a block in bytes, i.e. a cluster of FAT16
+    pub block_size: u64,
+}
+
+/// The usage of the physical frames, filled by `Syscall::FrameStats`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// The number of usable frames
+    pub total: usize,
+    /// The number of frames taken from the memory map
+    pub used: usize,
+    /// The number of freed frames waiting to be reused
+    pub recycled: usize,
+    /// The most physically contiguous frames among the recycled ones
+    pub largest_run: usize,
+}
+
+/// The frame behind a virtual address, filled by `Syscall::VirtToPhys`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageMapping {
+    /// The physical address the virtual one translates to
+    pub phys: u64,
+    /// The flags of the page table entry, see `PAGE_PRESENT` and the others
+    pub flags: u64,
+}
+
+/// The page is mapped
+pub const PAGE_PRESENT: u64 = 1 << 0;
+/// The page can be written
+pub const PAGE_WRITABLE: u64 = 1 << 1;
+/// The page can be accessed by the user space
+pub const PAGE_USER: u64 = 1 << 2;
+/// The page has been read or written
+pub const PAGE_ACCESSED: u64 = 1 << 5;
+/// The page has been written
+pub const PAGE_DIRTY: u64 = 1 << 6;
+/// The page is shared after a fork, copied on the next write
+pub const PAGE_COW: u64 = 1 << 9;
+/// The page cannot be executed
+pub const PAGE_NO_EXECUTE: u64 = 1 << 63;
+
+/// The statistics of the caller since the last `Syscall::ResetStats`,
+/// filled by it before they are zeroed
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of ticks the process has been scheduled for
+    pub ticks: usize,
+    pub page_faults: usize,
+    /// The page faults that grew the stack, counted in `page_faults`
+    pub stack_faults: usize,
+    /// The times the process is switched to from another one
+    pub switches: usize,
+}
+
+/// The ticks a process waits in the ready queue before it is picked,
+/// filled by `Syscall::SchedLatency`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SchedLatency {
+    /// The number of times the process is picked from the ready queue
+    pub picks: usize,
+    /// The ticks waited over all the picks
+    pub total: usize,
+    /// The longest wait of a single pick
+    pub max: usize,
+}
+
+impl SchedLatency {
+    /// The ticks waited on average, 0 if never picked
+    pub fn average(&self) -> usize {
+        self.total.checked_div(self.picks).unwrap_or(0)
+    }
+}
+
+/// The resource usage of an exited child, filled by `Syscall::Wait4`,
+/// or of the caller by `Syscall::Getrusage`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RUsage {
+    /// The number of ticks the child has been scheduled for
+    pub ticks: usize,
+    /// The peak of the heap, stack, mapped and code memory in bytes
+    pub peak_memory: usize,
+    /// The switches away as the process blocked or yielded (`ru_nvcsw`)
+    pub voluntary_switches: usize,
+    /// The switches away as the clock preempted the process (`ru_nivcsw`)
+    pub involuntary_switches: usize,
+}
+
+/// The caller itself for `Syscall::Getrusage`, the only one supported
+pub const RUSAGE_SELF: isize = 0;
+
+/// The signal of the child exits, its handler is set by `Syscall::SigChld`
+pub const SIGCHLD: usize = 17;
+/// Never masked, `Syscall::Kill` always kills
+pub const SIGKILL: usize = 9;
+/// Sent by the alarm of `Syscall::Alarm`, the process exits with `-SIGALRM`
+/// unless it has a handler
+pub const SIGALRM: usize = 14;
+/// Free for the programs, sent by `Syscall::Tkill`
+pub const SIGUSR1: usize = 10;
+pub const SIGUSR2: usize = 12;
+/// The highest signal number, the signals are numbered from 1
+pub const NSIG: usize = 64;
+/// Never masked, `Syscall::Stop` always suspends
+pub const SIGSTOP: usize = 19;
+
+/// Add the set to the mask of `Syscall::SigProcMask`
+pub const SIG_BLOCK: usize = 0;
+/// Remove the set from the mask, the pending signals are delivered
+pub const SIG_UNBLOCK: usize = 1;
+/// Replace the mask with the set
+pub const SIG_SETMASK: usize = 2;
+
+/// The bit of the signal in a mask, bit `sig - 1`
+pub const fn sig_bit(sig: usize) -> u64 {
+    1 << (sig - 1)
+}
+
+/// Go back to the default action once the handler is entered
+pub const SA_RESETHAND: usize = 0x8000_0000;
+/// Keep sleeping when the signal is sent, the handler runs once the sleep
+/// returns, else the sleep is cut short with `SysError::Intr`
+pub const SA_RESTART: usize = 0x1000_0000;
+
+/// The handler of a signal set by `Syscall::Sigaction`, called in the user space
+/// as `entry(data, sig)`, it returns by `Syscall::SigReturn`
+///
+/// an entry of 0 is the default action, which kills the process with `-sig`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SigAction {
+    pub entry: usize,
+    pub data: usize,
+    /// e.g. `SA_RESTART`
+    pub flags: usize,
+}
+
+/// A segment of the user memory for `Syscall::Readv` & `Syscall::Writev`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IoVec {
+    pub base: usize,
+    pub len: usize,
+}
+
+/// Or'ed into the fd of `Syscall::Read` to take whatever is buffered
+/// at once, e.g. all the pending keys of stdin, possibly none
+pub const READ_NONBLOCK: usize = 1 << 31;
+
+/// Create the file of `Syscall::Open` if it does not exist
+pub const O_CREAT: usize = 0o100;
+/// With `O_CREAT`, fail if the file exists instead of opening it
+pub const O_EXCL: usize = 0o200;
+/// The status flag of a fd read without waiting, an empty read fails
+/// with `SysError::Again` instead of returning 0, see `Syscall::Fcntl`
+pub const O_NONBLOCK: usize = 0o4000;
+
+/// Get the status flags of a fd by `Syscall::Fcntl`, e.g. `O_NONBLOCK`
+pub const F_GETFL: usize = 3;
+/// Set the status flags of a fd by `Syscall::Fcntl`
+pub const F_SETFL: usize = 4;
+
+/// The most segments taken by `Syscall::Readv` & `Syscall::Writev`
+pub const IOV_MAX: usize = 1024;
+
+/// The forks allowed to a process in any `FORK_RATE_WINDOW` clock ticks,
+/// the `Syscall::Fork` over the rate fails, the threads are not counted
+pub const FORK_RATE_LIMIT: usize = 32;
+/// The window of `FORK_RATE_LIMIT` in clock ticks
+pub const FORK_RATE_WINDOW: u64 = 2000;
+
+/// The `Syscall::SpinHint` calls that only pause since the caller is
+/// scheduled in, the next one gives up the time slice
+pub const SPIN_HINT_LIMIT: usize = 16;
+
+/// Share the memory with the child of `Syscall::Clone`, which runs on
+/// a fresh stack like a thread, else a private copy-on-write copy
+pub const CLONE_VM: usize = 0x100;
+/// Share the fd table, else the child gets a copy
+pub const CLONE_FILES: usize = 0x400;
+/// Keep the `Syscall::SigChld` handler in the child, else it has none
+pub const CLONE_SIGHAND: usize = 0x800;
+/// Share the semaphores, else the child gets a copy of their counts
+pub const CLONE_SYSVSEM: usize = 0x40000;
+/// Share the environment, else the child gets a copy, not in Linux
+pub const CLONE_ENV: usize = 0x1_0000_0000;
+
+/// The operations of `Syscall::Prctl`, values are the same as Linux
+/// except the open defaults
+///
+/// Rename the caller like `Syscall::SetProcName`, name (ptr, len) in
+/// `arg1` & `arg2`, returns the number of chars kept
+pub const PR_SET_NAME: usize = 15;
+/// Copy the name of the caller to the buffer (ptr, len) in `arg1` &
+/// `arg2`, returns the full length of the name
+pub const PR_GET_NAME: usize = 16;
+/// Keep every later child unprivileged, `arg1` must be 1 since
+/// it cannot be cleared, inherited by the children
+pub const PR_SET_NO_NEW_PRIVS: usize = 38;
+/// Returns 1 if `PR_SET_NO_NEW_PRIVS` is set, else 0
+pub const PR_GET_NO_NEW_PRIVS: usize = 39;
+/// Replace the flags added to every `Syscall::Open` with `arg1`,
+/// like `Syscall::SetOpenDefaults`
+pub const PR_SET_OPEN_DEFAULTS: usize = 0x1000;
+/// Returns the flags added to every `Syscall::Open`
+pub const PR_GET_OPEN_DEFAULTS: usize = 0x1001;
+
+/// The most arguments given to a child by `Syscall::PosixSpawn`
+pub const ARG_MAX: usize = 16;
+
+/// The options of `Syscall::PosixSpawn`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SpawnAttr {
+    /// The arguments as (ptr, len), only the first `argc` are used
+    pub argv: [[usize; 2]; ARG_MAX],
+    pub argc: usize,
+    /// The fds of the caller for stdin & stdout as fd + 1, 0 keeps the console
+    pub stdio: [usize; 2],
+    /// The initial & the maximum bytes of the stack, 0 keeps the default
+    pub stack: [usize; 2],
+}
+
+/// The settings of the serial line, taken by `Syscall::SerialConfig`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SerialConfig {
+    /// The baud dividing 115200, e.g. 9600 or 38400
+    pub baud: u32,
+    /// 5 ~ 8
+    pub data_bits: u8,
+    /// `PARITY_NONE`, `PARITY_ODD` or `PARITY_EVEN`
+    pub parity: u8,
+    /// 1 or 2
+    pub stop_bits: u8,
+}
+
+pub const PARITY_NONE: u8 = 0;
+pub const PARITY_ODD: u8 = 1;
+pub const PARITY_EVEN: u8 = 2;
+
+/// The limit of a resource, taken & filled by `Syscall::Prlimit`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limit {
+    /// The value enforced, which can be raised up to the hard one
+    pub soft: u64,
+    /// The ceiling of the soft value, which can only be lowered
+    pub hard: u64,
+}
+
+/// No limit on the resource
+pub const RLIM_INFINITY: u64 = u64::MAX;
+/// The ticks the process may run, 0 is no limit like `TickBudget`
+pub const RLIMIT_CPU: usize = 0;
+/// The children not reaped yet
+pub const RLIMIT_NPROC: usize = 6;
+/// The fds opened, `Open` fails with `EMFILE` beyond
+pub const RLIMIT_NOFILE: usize = 7;
+/// The bytes of heap, stack & mappings, 0 is no limit like `SetMemLimit`
+pub const RLIMIT_AS: usize = 9;
+
+/// The physical memory of the machine, filled by `Syscall::MemInfo`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemInfo {
+    /// The number of usable frames
+    pub total: usize,
+    /// The number of frames taken from the memory map
+    pub used: usize,
+    /// The number of freed frames waiting to be reused
+    pub recycled: usize,
+    /// The size of a frame in bytes
+    pub page_size: usize,
+}
+
+impl MemInfo {
+    /// The bytes in use, the recycled frames are free
+    pub fn used_bytes(&self) -> usize {
+        (self.used - self.recycled) * self.page_size
+    }
+
+    pub fn free_bytes(&self) -> usize {
+        self.total * self.page_size - self.used_bytes()
+    }
+}
+
+// The keys decoded from the escape sequences of the serial console,
+// read from stdin as single bytes never seen in UTF-8
+pub const KEY_UP: u8 = 0xF8;
+pub const KEY_DOWN: u8 = 0xF9;
+pub const KEY_RIGHT: u8 = 0xFA;
+pub const KEY_LEFT: u8 = 0xFB;
+pub const KEY_HOME: u8 = 0xFC;
+pub const KEY_END: u8 = 0xFD;
+pub const KEY_DELETE: u8 = 0xFE;
+
+/// The maximum length of a name in `Dirent`, i.e. a FAT16 short name
+pub const DIRENT_NAME_MAX: usize = 12;
+
+/// A directory entry, filled by `Syscall::GetDents`
+///
+/// the records left empty have `name_len == 0`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Dirent {
+    /// The length of the name in bytes
+    pub name_len: u8,
+    /// The name, only the first `name_len` bytes are valid
+    pub name: [u8; DIRENT_NAME_MAX],
+    /// The type of the entry, `Regular` or `Directory`
+    pub kind: FileKind,
+}
+
+impl Dirent {
+    pub fn name(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len as usize]).unwrap_or("")
+    }
+}
+
+/// The watched file, or a file in the watched dir, is written
+pub const WATCH_WRITE: u8 = 1 << 0;
+/// A file is created in the watched dir, or renamed into it
+pub const WATCH_CREATE: u8 = 1 << 1;
+/// The watched file, or a file in the watched dir, is unlinked or renamed away
+pub const WATCH_UNLINK: u8 = 1 << 2;
+/// Some events are dropped since the queue is full, the name is empty
+pub const WATCH_OVERFLOW: u8 = 1 << 3;
+
+/// A change seen by a watch fd, filled by `Syscall::WatchRead`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WatchEvent {
+    /// One of `WATCH_WRITE`, `WATCH_CREATE`, `WATCH_UNLINK` & `WATCH_OVERFLOW`
+    pub kind: u8,
+    /// The length of the name in bytes
+    pub name_len: u8,
+    /// The name of the file changed, only the first `name_len` bytes are valid
+    pub name: [u8; DIRENT_NAME_MAX],
+}
+
+impl WatchEvent {
+    pub fn name(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len as usize]).unwrap_or("")
+    }
+}
+
+/// The fraction bits of the load average returned by `Syscall::LoadAvg`,
+/// i.e. `1 << LOAD_SHIFT` is one process ready or running all the time
+pub const LOAD_SHIFT: u32 = 8;
+
+/// The address of the clock page, mapped read-only into every process
+/// and updated by the kernel on every clock tick
+pub const VDSO_ADDR: u64 = 0x7fff_ffff_f000;
+
+/// The contents of the clock page at `VDSO_ADDR`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VdsoData {
+    /// The clock ticks since boot, the same as `Syscall::Uptime`
+    pub ticks: u64,
+    /// The clock ticks per second, 0 until calibrated
+    pub ticks_per_sec: u64,
+}
+
+/// The reads issued to the disk since boot, filled by `Syscall::DiskStats`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiskStats {
+    /// The number of read commands
+    pub reads: usize,
+    /// The number of sectors transferred by them
+    pub sectors: usize,
+}
+
+/// The ELF metadata of an embedded app, filled by `Syscall::ElfInfo`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ElfInfo {
+    /// The virtual address of the entry point
+    pub entry: u64,
+    /// The number of loadable segments
+    pub load_segments: u64,
+}
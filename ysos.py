@@ -23,6 +23,8 @@ parser.add_argument('--dry-run', action='store_true', help='Enable dry run')
 parser.add_argument('--bios', type=str,
                     default=os.path.join('assets', 'OVMF.fd'), help='Set BIOS path')
 parser.add_argument('--boot', type=str, default='esp', help='Set boot path')
+parser.add_argument('--panic-test', action='store_true',
+                    help='Build a kernel panicking after init, expect QEMU to exit with the panic code')
 
 parser.add_argument('task', type=str, choices=[
                     'build', 'clean', 'launch', 'run'
@@ -56,7 +58,12 @@ def get_apps():
     return apps
 
 
-def execute_command(cmd: list, workdir: str = None, shell: bool = False) -> int:
+# QEMU exits with (code << 1) | 1 for the kernel's PANIC_EXIT_CODE 0x10
+PANIC_EXIT_STATUS = 33
+
+
+def execute_command(cmd: list, workdir: str = None, shell: bool = False,
+                    expected: int = 0) -> int:
     debug('Executing', " ".join(cmd) + (f' in {workdir}' if workdir else ''))
 
     if args.dry_run:
@@ -65,7 +72,7 @@ def execute_command(cmd: list, workdir: str = None, shell: bool = False) -> int:
     prog = subprocess.Popen(cmd, cwd=workdir, shell=shell)
     prog.wait()
 
-    if prog.returncode != 0:
+    if prog.returncode != expected:
         raise Exception(f"{cmd} failed with code {prog.returncode}")
 
     return prog.returncode
@@ -83,14 +90,19 @@ def qemu(output: str = '-nographic', memory: str = '96M', debug: bool = False, i
         raise Exception('qemu-system-x86_64 not found in PATH')
 
     qemu_args = [qemu_exe, '-bios', args.bios, '-net', 'none', *output.split(),
-                 '-m', memory, '-drive', 'format=raw,file=fat:rw:esp']
+                 '-m', memory, '-drive', 'format=raw,file=fat:rw:esp',
+                 '-device', 'isa-debug-exit,iobase=0xf4,iosize=0x04']
 
     if debug:
         qemu_args += ['-s', '-S']
     elif intdbg:
         qemu_args += ['-no-reboot', '-d', 'int,cpu_reset']
 
-    execute_command(qemu_args)
+    if args.panic_test:
+        execute_command(qemu_args, expected=PANIC_EXIT_STATUS)
+        info('Panic test', f'QEMU exited with {PANIC_EXIT_STATUS} as expected')
+    else:
+        execute_command(qemu_args)
 
 
 def copy_to_esp(src: str, dst: str):
@@ -133,11 +145,17 @@ def build():
     if os.path.exists(config_path):
         copy_to_esp(config_path, os.path.join('EFI', 'BOOT', 'boot.conf'))
 
+    # the later key overrides the policy in the config
+    if args.panic_test and not args.dry_run:
+        with open(os.path.join(args.boot, 'EFI', 'BOOT', 'boot.conf'), 'a') as f:
+            f.write('\npanic_policy=exit\n')
+
     # build kernel
     kernel = os.path.join(os.getcwd(), 'pkg', 'kernel')
     info('Building', 'kernel...')
     profile = '--release' if args.profile == 'release' else '--profile=release-with-debug'
-    execute_command([cargo_exe, 'build', profile], kernel)
+    features = ['--features', 'panic_test'] if args.panic_test else []
+    execute_command([cargo_exe, 'build', profile, *features], kernel)
     profile_dir = 'release' if args.profile == 'release' else 'release-with-debug'
     compile_output = os.path.join(
         os.getcwd(), 'target', 'x86_64-unknown-none', profile_dir, 'ysos_kernel')